@@ -0,0 +1,170 @@
+use std::fs;
+use std::path::Path;
+
+use chess::{Board, BoardStatus, ChessMove, Color};
+
+use crate::observer::{self, Observer};
+use crate::pgn::PgnGame;
+use crate::stats::ResultsDatabase;
+use crate::variant::Variant;
+
+//Evals are capped so that a missed mate costs the same as losing a lot of material, like most accuracy scores do
+const MAX_EVAL_CP: f32 = 1000.0;
+
+//A finished game to score
+#[derive(Debug, Clone)]
+pub struct ScoredGame {
+    pub white: String,
+    pub black: String,
+    pub result: String,
+    pub start: Board,
+    pub moves: Vec<ChessMove>,
+}
+
+//How one side played in a game
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SideAccuracy {
+    pub moves: usize,
+    pub cp_loss: f64,
+    pub accuracy: f64,
+}
+
+impl SideAccuracy {
+    pub fn acpl(&self) -> f64 {
+        self.cp_loss / self.moves.max(1) as f64
+    }
+
+    pub fn average_accuracy(&self) -> f64 {
+        self.accuracy / self.moves.max(1) as f64
+    }
+}
+
+//Chance of winning from an eval in centipawns, from the same curve lichess uses
+fn win_percent(cp: f32) -> f32 {
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * cp).exp()) - 1.0)
+}
+
+//100 for keeping the winning chances the reference engine saw, dropping quickly for moves that throw them away
+fn move_accuracy(win_before: f32, win_after: f32) -> f32 {
+    (103.1668 * (-0.04354 * (win_before - win_after)).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+//Centipawns from white's point of view. Finished positions are scored by the rules rather than the engine
+fn evaluate(reference: &mut dyn Observer, board: &Board, depth: u32) -> Result<f32, String> {
+    let cp = match board.status() {
+        BoardStatus::Checkmate if board.side_to_move() == Color::White => -MAX_EVAL_CP,
+        BoardStatus::Checkmate => MAX_EVAL_CP,
+        BoardStatus::Stalemate => 0.0,
+        BoardStatus::Ongoing => {
+            let analysis = reference.analyse(board, depth).ok_or(format!("The reference engine didn't analyse {}", board))?;
+            analysis.eval * 100.0
+        }
+    };
+
+    Ok(cp.clamp(-MAX_EVAL_CP, MAX_EVAL_CP))
+}
+
+//Compares every move with the reference engine's eval of the position before and after it. White's and black's
+pub fn score_game(reference: &mut dyn Observer, depth: u32, game: &ScoredGame) -> Result<[SideAccuracy; 2], String> {
+    let mut board = game.start;
+    let mut before = evaluate(reference, &board, depth)?;
+    let mut sides = [SideAccuracy::default(); 2];
+
+    for m in game.moves.iter() {
+        let mover = board.side_to_move();
+
+        if !board.legal(*m) {
+            return Err(format!("Illegal move {} in {} vs {}", m, game.white, game.black));
+        }
+
+        board = board.make_move_new(*m);
+        let after = evaluate(reference, &board, depth)?;
+
+        //Both from the mover's point of view
+        let (before_cp, after_cp) = match mover {
+            Color::White => (before, after),
+            Color::Black => (-before, -after),
+        };
+
+        let side = &mut sides[mover.to_index()];
+        side.moves += 1;
+        side.cp_loss += (before_cp - after_cp).max(0.0) as f64;
+        side.accuracy += move_accuracy(win_percent(before_cp), win_percent(after_cp)) as f64;
+
+        before = after;
+    }
+
+    Ok(sides)
+}
+
+//Scores the games and adds them to the results database, printing each game's figures and the dashboard
+pub fn score_games(reference_name: &str, depth: u32, games: &[ScoredGame]) -> Result<(), String> {
+    let mut reference = observer::by_name(reference_name)?;
+    let depth = depth.clamp(1, reference.max_depth());
+
+    let mut database = ResultsDatabase::load();
+
+    println!("Scoring {} games against {} at depth {}", games.len(), reference_name, depth);
+
+    for (i, game) in games.iter().enumerate() {
+        let [white, black] = match score_game(&mut *reference, depth, game) {
+            Ok(sides) => sides,
+            Err(e) => {
+                println!("Skipping game {}: {}", i + 1, e);
+                continue;
+            }
+        };
+
+        println!(
+            "Game {}/{}: {} {:.1}% (ACPL {:.0}) vs {} {:.1}% (ACPL {:.0}), {}",
+            i + 1,
+            games.len(),
+            game.white,
+            white.average_accuracy(),
+            white.acpl(),
+            game.black,
+            black.average_accuracy(),
+            black.acpl(),
+            game.result
+        );
+
+        let (white_points, black_points) = match game.result.as_str() {
+            "1-0" => (1.0, 0.0),
+            "0-1" => (0.0, 1.0),
+            _ => (0.5, 0.5),
+        };
+
+        database.add_game(&game.white, white_points, white.moves, white.cp_loss, white.accuracy);
+        database.add_game(&game.black, black_points, black.moves, black.cp_loss, black.accuracy);
+    }
+
+    database.save()?;
+
+    println!();
+    println!("{}", database.dashboard());
+
+    Ok(())
+}
+
+//Scores the standard chess games of a PGN file. Games still in progress are left out
+pub fn run(path: &Path, reference: &str, depth: u32) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+
+    let games: Vec<ScoredGame> = PgnGame::parse_all(&text)?
+        .into_iter()
+        .filter(|game| game.variant() == Variant::Standard && game.result != "*")
+        .map(|game| ScoredGame {
+            white: game.get_tag("White").unwrap_or("?").to_string(),
+            black: game.get_tag("Black").unwrap_or("?").to_string(),
+            result: game.result.clone(),
+            start: game.start,
+            moves: game.moves,
+        })
+        .collect();
+
+    if games.is_empty() {
+        return Err(format!("No finished standard chess games in {:?}", path));
+    }
+
+    score_games(reference, depth, &games)
+}
@@ -0,0 +1,77 @@
+use chess::{Board, Color};
+
+use crate::gui::chess_display::GameOutcome;
+use crate::handicap::Handicap;
+use crate::settings;
+use crate::util::pgn_date;
+
+pub struct Achievement {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+pub const ACHIEVEMENTS: [Achievement; 4] = [
+    Achievement { id: "first_checkmate", name: "First Checkmate", description: "Checkmate a bot" },
+    Achievement { id: "flawless", name: "Flawless", description: "Win a game without losing a single piece" },
+    Achievement { id: "swarm_odds", name: "Outnumbered", description: "Beat Swarm while giving it piece odds" },
+    Achievement { id: "classic_survivor", name: "Survivor", description: "Last 100 moves against Classic" },
+];
+
+//Plies in 100 moves of each side
+const SURVIVOR_PLIES: usize = 200;
+
+//A game between the human and a bot as it stands, from the human's side
+pub struct HumanGame<'a> {
+    pub human: Color,
+    pub opponent: &'a str,
+    pub handicap: Handicap,
+    pub start: &'a Board,
+    pub board: &'a Board,
+    pub plies: usize,
+    pub outcome: Option<&'a GameOutcome>,
+}
+
+impl HumanGame<'_> {
+    fn won(&self) -> bool {
+        self.outcome.is_some_and(|outcome| outcome.points(self.human) >= 1.0)
+    }
+
+    fn earned(&self, id: &str) -> bool {
+        match id {
+            "first_checkmate" => self.outcome == Some(&GameOutcome::Checkmate(self.human)),
+            //Promoting keeps the number of pieces the same, so only captures bring it down
+            "flawless" => self.won() && self.board.color_combined(self.human).popcnt() >= self.start.color_combined(self.human).popcnt(),
+            "swarm_odds" => self.won() && self.opponent == "Swarm" && matches!(self.handicap, Handicap::Odds(color, _) if color == self.human),
+            "classic_survivor" => self.opponent == "Classic" && self.plies >= SURVIVOR_PLIES,
+            _ => false,
+        }
+    }
+}
+
+//Saves the achievements the game just earned and returns them
+pub fn check(game: &HumanGame) -> Vec<&'static Achievement> {
+    let earned_before = settings::get().achievements;
+
+    let new: Vec<&'static Achievement> = ACHIEVEMENTS
+        .iter()
+        .filter(|achievement| !earned_before.contains_key(achievement.id) && game.earned(achievement.id))
+        .collect();
+
+    if !new.is_empty() {
+        settings::update(|settings| {
+            for achievement in new.iter() {
+                settings.achievements.insert(achievement.id.to_string(), pgn_date());
+            }
+        });
+    }
+
+    new
+}
+
+//Every achievement with the date it was earned, if it was
+pub fn progress() -> Vec<(&'static Achievement, Option<String>)> {
+    let earned = settings::get().achievements;
+
+    ACHIEVEMENTS.iter().map(|achievement| (achievement, earned.get(achievement.id).cloned())).collect()
+}
@@ -0,0 +1,155 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::alg::external::{find_engine, EngineOptions, Protocol};
+use crate::alg::{draw_claim, epsilon, relay, PlayerTypeSupplier};
+use crate::gui::chess_display::PlayerType;
+
+pub const PRESET_DIR: &str = "res/presets";
+
+//A bot saved with everything it was set up with, so it can be picked, played in tournaments and shared under its own
+//name. Unlike the difficulty presets, anyone can make these
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BotPreset {
+    pub name: String,
+    //Name of the bot in the player list
+    pub bot: String,
+    #[serde(default)]
+    pub description: String,
+    //Plies searched, for bots that search
+    #[serde(default)]
+    pub depth: Option<u32>,
+    //The same options as the game creator's options editor, the epsilon and draw claims included
+    #[serde(default)]
+    pub options: EngineOptions,
+}
+
+static PRESETS: OnceLock<Vec<BotPreset>> = OnceLock::new();
+
+//Every preset in the presets folder, loaded the first time this is called
+pub fn presets() -> &'static [BotPreset] {
+    PRESETS.get_or_init(|| {
+        let Ok(entries) = fs::read_dir(PRESET_DIR) else {
+            return vec![];
+        };
+
+        let mut presets: Vec<BotPreset> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| match BotPreset::load(&path) {
+                Ok(preset) => Some(preset),
+                Err(e) => {
+                    println!("Could not load preset {:?}: {}", path, e);
+                    None
+                }
+            })
+            .collect();
+
+        presets.sort_by(|a, b| a.name.cmp(&b.name));
+
+        presets
+    })
+}
+
+pub fn find(name: &str) -> Option<&'static BotPreset> {
+    presets().iter().find(|preset| preset.name.eq_ignore_ascii_case(name))
+}
+
+impl BotPreset {
+    pub fn new(name: &str, bot: &str, options: EngineOptions) -> BotPreset {
+        BotPreset { name: name.trim().to_string(), bot: bot.to_string(), description: String::new(), depth: None, options }
+    }
+
+    pub fn load(path: &Path) -> Result<BotPreset, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let preset: BotPreset = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+
+        if preset.name.trim().is_empty() {
+            return Err(String::from("The preset has no name"));
+        }
+
+        Ok(preset)
+    }
+
+    //Where the preset is kept, named after it with anything that can't be in a file name left out
+    pub fn path(&self) -> PathBuf {
+        let file_name: String = self.name.chars().filter(|c| c.is_alphanumeric() || " -_".contains(*c)).collect();
+
+        Path::new(PRESET_DIR).join(format!("{}.json", file_name.trim()))
+    }
+
+    pub fn save(&self) -> Result<PathBuf, String> {
+        if self.name.is_empty() {
+            return Err(String::from("Presets need a name"));
+        }
+
+        if super::find_player_type(&self.name).is_some_and(|(found, _)| find(found).is_none()) {
+            return Err(format!("There already is a bot called {}", self.name));
+        }
+
+        fs::create_dir_all(PRESET_DIR).map_err(|e| e.to_string())?;
+
+        let path = self.path();
+        fs::write(&path, serde_json::to_string_pretty(self).unwrap()).map_err(|e| e.to_string())?;
+
+        Ok(path)
+    }
+
+    pub fn player_type(&self, base: &PlayerTypeSupplier, color: chess::Color) -> PlayerType {
+        configure(&self.bot, self.depth, &self.options, base, color)
+    }
+}
+
+//Starts `bot` the way the game creator would with these options. `base` is how the bot is started without them
+pub fn configure(bot: &str, depth: Option<u32>, options: &EngineOptions, base: &PlayerTypeSupplier, color: chess::Color) -> PlayerType {
+    let player = match find_engine(bot).filter(|engine| engine.protocol == Protocol::Uci) {
+        Some(engine) => engine.player_type_with(options),
+        None if bot == relay::NAME => PlayerType::computer(relay::relay_bot(color, options)),
+        None => base(color),
+    };
+
+    if let (Some(depth), PlayerType::Computer(engine)) = (depth, &player) {
+        engine.lock().unwrap().set_depth(depth);
+    }
+
+    let player = epsilon::wrap(player, epsilon::epsilon(options));
+    draw_claim::wrap(player, draw_claim::policy(options), color)
+}
+
+//Saves a preset made on the command line
+pub fn save_new(name: &str, bot: &str, depth: Option<u32>, options: &[(String, String)], description: Option<&str>) -> Result<PathBuf, String> {
+    let (bot, _) = super::find_player_type(bot).ok_or(format!("Unknown bot '{}'", bot))?;
+
+    if bot == "Human" || bot == "Remote" || find(bot).is_some() {
+        return Err(format!("{} can't be made into a preset", bot));
+    }
+
+    let preset = BotPreset {
+        description: description.unwrap_or_default().to_string(),
+        depth,
+        ..BotPreset::new(name, bot, options.iter().cloned().collect())
+    };
+
+    preset.save()
+}
+
+//Copies a preset someone shared into the presets folder, checking it can be read first
+pub fn import(file: &Path) -> Result<PathBuf, String> {
+    let preset = BotPreset::load(file)?;
+
+    if preset.path().exists() {
+        return Err(format!("There already is a preset called {}", preset.name));
+    }
+
+    preset.save()
+}
+
+pub fn export(name: &str, file: &Path) -> Result<(), String> {
+    let preset = find(name).ok_or(format!("No preset called '{}' in {}", name, PRESET_DIR))?;
+
+    fs::write(file, serde_json::to_string_pretty(preset).unwrap()).map_err(|e| e.to_string())
+}
@@ -1,19 +1,290 @@
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
 use std::{thread, time::Duration};
 
-use chess::{Board, ChessMove, MoveGen};
-use rand::Rng;
+use chess::{Board, ChessMove, Color};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
+use crate::clock::TimeControl;
 use crate::util::move_to_SAN;
+use crate::variant::{self, Variant};
+
+use super::explain::Explanation;
+
+//How long an engine past its max move time gets to answer after being told to stop before it forfeits
+pub const STOP_GRACE: Duration = Duration::from_secs(2);
+
+thread_local! {
+    //Positions engines on this thread have looked at, for benchmarking
+    static NODES: Cell<u64> = const { Cell::new(0) };
+
+    //Randomness for bots on this thread, seeded for games that have to play out the same way again
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+
+    //Times the randomness was used, to tell whether a game would play out the same way again
+    static RNG_DRAWS: Cell<u64> = const { Cell::new(0) };
+
+    //Set when the move the engine on this thread is looking for isn't wanted anymore
+    static CANCEL: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+
+    //Set when the engine on this thread has used up its time for the move and should play the best one it has so far
+    static STOP: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+
+    //Where the node count of the search on this thread is copied to now and then, for showing it while it runs
+    static LIVE_NODES: RefCell<Option<Arc<AtomicU64>>> = const { RefCell::new(None) };
+}
+
+//Nodes between copies to the live count
+const LIVE_NODES_EVERY: u64 = 1024;
+
+pub fn seed_rng(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG_DRAWS.with(|n| n.set(n.get() + 1));
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+pub fn rng_draws() -> u64 {
+    RNG_DRAWS.with(|n| n.get())
+}
+
+pub fn set_cancel_flag(flag: Option<Arc<AtomicBool>>) {
+    CANCEL.with(|c| *c.borrow_mut() = flag);
+}
+
+//Engines that search for a while check this to stop early. Whatever they return afterwards is ignored
+pub fn cancelled() -> bool {
+    CANCEL.with(|c| c.borrow().as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed)))
+}
+
+pub fn set_stop_flag(flag: Option<Arc<AtomicBool>>) {
+    STOP.with(|s| *s.borrow_mut() = flag);
+}
+
+//Whether a stop can come at all, so searches know to keep a move ready
+pub fn stoppable() -> bool {
+    STOP.with(|s| s.borrow().is_some())
+}
+
+//Unlike a cancel, the engine is still expected to answer, just as soon as it can
+pub fn stop_requested() -> bool {
+    STOP.with(|s| s.borrow().as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed)))
+}
+
+//Runs `f` with the stop flag set once `limit` is up, for games without a controller to keep an eye on the time.
+//Also returns whether it had to be stopped
+pub fn with_move_limit<T>(limit: Option<Duration>, f: impl FnOnce() -> T) -> (T, bool) {
+    let Some(limit) = limit else {
+        return (f(), false);
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let (done, timer) = mpsc::channel::<()>();
+
+    let flag = stop.clone();
+    thread::spawn(move || {
+        if timer.recv_timeout(limit) == Err(RecvTimeoutError::Timeout) {
+            flag.store(true, Ordering::Relaxed);
+        }
+    });
+
+    set_stop_flag(Some(stop.clone()));
+    let res = f();
+    set_stop_flag(None);
+    drop(done);
+
+    (res, stop.load(Ordering::Relaxed))
+}
+
+pub fn set_live_nodes(counter: Option<Arc<AtomicU64>>) {
+    LIVE_NODES.with(|l| *l.borrow_mut() = counter);
+}
+
+pub fn count_node() {
+    let nodes = NODES.with(|n| {
+        n.set(n.get() + 1);
+        n.get()
+    });
+
+    if nodes.is_multiple_of(LIVE_NODES_EVERY) {
+        LIVE_NODES.with(|l| {
+            if let Some(counter) = l.borrow().as_ref() {
+                counter.store(nodes, Ordering::Relaxed);
+            }
+        });
+    }
+}
+
+pub fn nodes() -> u64 {
+    NODES.with(|n| n.get())
+}
 
 pub fn available_moves(board: &Board) -> Vec<ChessMove> {
-    MoveGen::new_legal(&board).collect::<Vec<ChessMove>>()
+    let (variant, state) = variant::current();
+
+    variant.rules().legal_moves(board, &state)
+}
+
+//Same moves as `available_moves` without collecting them, for evaluators and searches that go through a lot of positions
+pub fn for_each_move(board: &Board, mut f: impl FnMut(ChessMove)) {
+    let (variant, state) = variant::current();
+
+    variant.rules().for_each_legal_move(board, &state, &mut f);
+}
+
+pub fn count_moves(board: &Board) -> usize {
+    let (variant, state) = variant::current();
+
+    variant.rules().count_legal_moves(board, &state)
+}
+
+//Board after a move from `available_moves`, which may be a variant move like a crazyhouse drop
+pub fn make_move(board: &Board, m: ChessMove) -> Board {
+    let (variant, state) = variant::current();
+
+    count_node();
+
+    variant.rules().apply(board, &state, m).0
+}
+
+//Changes to how a bot plays, picked while a game is going on and used from its next move
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EngineSettings {
+    //Plies searched past each move, for bots that search
+    pub depth: Option<u32>,
+    //Longest a move may take, on top of any clock
+    pub move_time: Option<Duration>,
+    //Past this the engine is told to stop and play its best move so far, and the controller gives up on it
+    //shortly after. Unlike move_time the engine doesn't plan around it
+    pub max_move_time: Option<Duration>,
+    //Chance of playing a random move instead of the bot's own
+    pub randomness: f32,
+}
+
+//What a bot is told about a game before its first move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameConfig {
+    pub color: Color,
+    pub variant: Variant,
+    pub start: Board,
+    pub time_control: TimeControl,
+}
+
+//What the last search got through, for the performance HUD. Engines that don't know leave it empty
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchStats {
+    //Plies searched past the root, which can fall short of the depth setting on a clock
+    pub depth: Option<u32>,
+    //Share of transposition table lookups that found the position, from 0 to 1
+    pub table_hit_rate: Option<f32>,
+    //Only from engines that count their own, like UCI engines. Otherwise the nodes counted with count_node are used
+    pub nodes: Option<u64>,
 }
 
 pub trait ChessAlgorithm : std::fmt::Debug + Send {
     fn get_move(&mut self, board: Board) -> ChessMove;
+    //For bots whose search depth can be changed
+    fn depth(&self) -> Option<u32> {
+        None
+    }
+    fn set_depth(&mut self, _depth: u32) {
+
+    }
+    //Before the first move of every game, for bots that keep things between moves
+    fn new_game(&mut self) {
+
+    }
+    //After new_game and before the first move, on another thread in the GUI so that slow setup like loading weights
+    //or waiting for an engine to be ready doesn't freeze the window or count on the clock
+    fn prepare(&mut self, _config: &GameConfig) {
+
+    }
+    //How the bot's search has been going, like how often its caches are used
+    fn info(&self) -> Option<String> {
+        None
+    }
+    //Why the last move was picked, for bots that add up evaluation terms
+    fn explain(&self) -> Option<Explanation> {
+        None
+    }
+    fn search_stats(&self) -> SearchStats {
+        SearchStats::default()
+    }
+    //Whether the bot takes a draw by repetition or the 50 move rule it can claim in `board`
+    fn claim_draw(&mut self, _board: &Board) -> bool {
+        true
+    }
+    //The bot's own score of `board` for its side, for bots with an evaluation
+    fn evaluate(&self, _board: &Board) -> Option<f32> {
+        None
+    }
+    //Whether the bot always plays the same moves in the same game as long as it doesn't use `with_rng`. Not true
+    //of bots outside of this program
+    fn reproducible(&self) -> bool {
+        true
+    }
+    //Whether the bot can't play any more, like an engine whose process exited or was killed. Its moves are then only
+    //stand-ins
+    fn crashed(&self) -> bool {
+        false
+    }
     fn do_move(&mut self, board: Board, chess_move: ChessMove) {
         
     }
+    fn game_over(&mut self, _result: &str, _reason: &str) {
+
+    }
+}
+
+//Bots handed out by player types, so that other bots can be wrapped around them
+impl ChessAlgorithm for Arc<Mutex<dyn ChessAlgorithm>> {
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        self.lock().unwrap().get_move(board)
+    }
+    fn depth(&self) -> Option<u32> {
+        self.lock().unwrap().depth()
+    }
+    fn set_depth(&mut self, depth: u32) {
+        self.lock().unwrap().set_depth(depth);
+    }
+    fn new_game(&mut self) {
+        self.lock().unwrap().new_game();
+    }
+    fn prepare(&mut self, config: &GameConfig) {
+        self.lock().unwrap().prepare(config);
+    }
+    fn info(&self) -> Option<String> {
+        self.lock().unwrap().info()
+    }
+    fn explain(&self) -> Option<Explanation> {
+        self.lock().unwrap().explain()
+    }
+    fn search_stats(&self) -> SearchStats {
+        self.lock().unwrap().search_stats()
+    }
+    fn claim_draw(&mut self, board: &Board) -> bool {
+        self.lock().unwrap().claim_draw(board)
+    }
+    fn evaluate(&self, board: &Board) -> Option<f32> {
+        self.lock().unwrap().evaluate(board)
+    }
+    fn reproducible(&self) -> bool {
+        self.lock().unwrap().reproducible()
+    }
+    fn crashed(&self) -> bool {
+        self.lock().unwrap().crashed()
+    }
+    fn do_move(&mut self, board: Board, chess_move: ChessMove) {
+        self.lock().unwrap().do_move(board, chess_move);
+    }
+    fn game_over(&mut self, result: &str, reason: &str) {
+        self.lock().unwrap().game_over(result, reason);
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -21,13 +292,24 @@ pub struct RandomChessAlgorithm;
 
 unsafe impl Send for RandomChessAlgorithm {}
 
+//Asks `engine` for a move with `settings` applied
+pub fn get_move_with(engine: &mut dyn ChessAlgorithm, board: Board, settings: EngineSettings) -> ChessMove {
+    if let Some(depth) = settings.depth {
+        engine.set_depth(depth);
+    }
+
+    if settings.randomness > 0.0 && with_rng(|rng| rng.gen::<f32>()) < settings.randomness {
+        return RandomChessAlgorithm.get_move(board);
+    }
+
+    engine.get_move(board)
+}
+
 impl ChessAlgorithm for RandomChessAlgorithm {
     fn get_move(&mut self, board: Board) -> ChessMove {
         let moves = available_moves(&board);
 
-        let mut rng = rand::thread_rng();
-
-        moves[rng.gen_range(0..moves.len())]
+        moves[with_rng(|rng| rng.gen_range(0..moves.len()))]
     }
 }
 
@@ -0,0 +1,230 @@
+use std::fs;
+use std::path::Path;
+
+use chess::{Board, BoardStatus, Color, Piece, ALL_PIECES};
+use serde::{Deserialize, Serialize};
+
+use super::endgame::with_oracle;
+use super::evaluators::{EvalTerm, Evaluator};
+use super::tree_search::TreeSearchEngine;
+
+pub const EVAL_PARAMS_PATH: &str = "res/eval_params.json";
+
+//Plies the Classic bot looks ahead after its own move
+pub const CLASSIC_DEPTH: u32 = 2;
+
+const MATE_SCORE: f32 = 1000.0;
+
+const MATERIAL: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+//Piece square tables in centipawns for white, written the way the board looks with rank 8 at the top
+#[rustfmt::skip]
+const PST: [[i32; 64]; 6] = [
+    [
+         0,  0,  0,  0,  0,  0,  0,  0,
+        50, 50, 50, 50, 50, 50, 50, 50,
+        10, 10, 20, 30, 30, 20, 10, 10,
+         5,  5, 10, 25, 25, 10,  5,  5,
+         0,  0,  0, 20, 20,  0,  0,  0,
+         5, -5,-10,  0,  0,-10, -5,  5,
+         5, 10, 10,-20,-20, 10, 10,  5,
+         0,  0,  0,  0,  0,  0,  0,  0,
+    ],
+    [
+        -50,-40,-30,-30,-30,-30,-40,-50,
+        -40,-20,  0,  0,  0,  0,-20,-40,
+        -30,  0, 10, 15, 15, 10,  0,-30,
+        -30,  5, 15, 20, 20, 15,  5,-30,
+        -30,  0, 15, 20, 20, 15,  0,-30,
+        -30,  5, 10, 15, 15, 10,  5,-30,
+        -40,-20,  0,  5,  5,  0,-20,-40,
+        -50,-40,-30,-30,-30,-30,-40,-50,
+    ],
+    [
+        -20,-10,-10,-10,-10,-10,-10,-20,
+        -10,  0,  0,  0,  0,  0,  0,-10,
+        -10,  0,  5, 10, 10,  5,  0,-10,
+        -10,  5,  5, 10, 10,  5,  5,-10,
+        -10,  0, 10, 10, 10, 10,  0,-10,
+        -10, 10, 10, 10, 10, 10, 10,-10,
+        -10,  5,  0,  0,  0,  0,  5,-10,
+        -20,-10,-10,-10,-10,-10,-10,-20,
+    ],
+    [
+          0,  0,  0,  0,  0,  0,  0,  0,
+          5, 10, 10, 10, 10, 10, 10,  5,
+         -5,  0,  0,  0,  0,  0,  0, -5,
+         -5,  0,  0,  0,  0,  0,  0, -5,
+         -5,  0,  0,  0,  0,  0,  0, -5,
+         -5,  0,  0,  0,  0,  0,  0, -5,
+         -5,  0,  0,  0,  0,  0,  0, -5,
+          0,  0,  0,  5,  5,  0,  0,  0,
+    ],
+    [
+        -20,-10,-10, -5, -5,-10,-10,-20,
+        -10,  0,  0,  0,  0,  0,  0,-10,
+        -10,  0,  5,  5,  5,  5,  0,-10,
+         -5,  0,  5,  5,  5,  5,  0, -5,
+          0,  0,  5,  5,  5,  5,  0, -5,
+        -10,  5,  5,  5,  5,  5,  0,-10,
+        -10,  0,  5,  0,  0,  0,  0,-10,
+        -20,-10,-10, -5, -5,-10,-10,-20,
+    ],
+    [
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -20,-30,-30,-40,-40,-30,-30,-20,
+        -10,-20,-20,-20,-20,-20,-20,-10,
+         20, 20,  0,  0,  0,  0, 20, 20,
+         20, 30, 10,  0,  0, 10, 30, 20,
+    ],
+];
+
+//Weights of the Classic evaluator in pawns. Tables are indexed by square from a1 to h8 from white's point of view,
+//and mirrored for black
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassicParams {
+    pub material: Vec<f32>,
+    pub pst: Vec<Vec<f32>>,
+}
+
+impl Default for ClassicParams {
+    fn default() -> Self {
+        ClassicParams {
+            material: MATERIAL.iter().map(|v| *v as f32 / 100.0).collect(),
+            pst: PST
+                .iter()
+                .map(|table| (0..64).map(|square| table[square ^ 56] as f32 / 100.0).collect())
+                .collect(),
+        }
+    }
+}
+
+//Table square of a piece on `square`, seen from its own side
+fn relative_square(square: chess::Square, color: Color) -> usize {
+    match color {
+        Color::White => square.to_index(),
+        Color::Black => square.to_index() ^ 56,
+    }
+}
+
+impl ClassicParams {
+    pub const LEN: usize = 6 + 6 * 64;
+
+    //The tuned parameters if there are any, and the built in ones otherwise
+    pub fn load() -> ClassicParams {
+        let res = fs::read_to_string(EVAL_PARAMS_PATH)
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str::<ClassicParams>(&text).map_err(|e| e.to_string()));
+
+        match res {
+            Ok(params) if params.material.len() == 6 && params.pst.iter().all(|t| t.len() == 64) && params.pst.len() == 6 => params,
+            Ok(_) => {
+                println!("{} has the wrong number of weights, using the default ones", EVAL_PARAMS_PATH);
+                ClassicParams::default()
+            }
+            Err(_) if !Path::new(EVAL_PARAMS_PATH).exists() => ClassicParams::default(),
+            Err(e) => {
+                println!("Could not load {}: {}. Using the default weights", EVAL_PARAMS_PATH, e);
+                ClassicParams::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap()).map_err(|e| e.to_string())
+    }
+
+    //All weights in one list: material first, then every table
+    pub fn to_vec(&self) -> Vec<f32> {
+        self.material.iter().chain(self.pst.iter().flatten()).copied().collect()
+    }
+
+    pub fn from_vec(weights: &[f32]) -> ClassicParams {
+        ClassicParams {
+            material: weights[..6].to_vec(),
+            pst: weights[6..].chunks(64).map(|table| table.to_vec()).collect(),
+        }
+    }
+
+    //How many times each weight counts towards white's score, so that the evaluation is the dot product of these
+    //with `to_vec`
+    pub fn features(board: &Board) -> Vec<(usize, f32)> {
+        let mut features = vec![];
+
+        for piece in ALL_PIECES {
+            for color in [Color::White, Color::Black] {
+                let sign = if color == Color::White { 1.0 } else { -1.0 };
+
+                for square in *board.pieces(piece) & *board.color_combined(color) {
+                    features.push((piece.to_index(), sign));
+                    features.push((6 + piece.to_index() * 64 + relative_square(square, color), sign));
+                }
+            }
+        }
+
+        features
+    }
+
+    fn piece_score(&self, board: &Board, piece: Piece, color: Color) -> f32 {
+        (*board.pieces(piece) & *board.color_combined(color))
+            .map(|square| self.material[piece.to_index()] + self.pst[piece.to_index()][relative_square(square, color)])
+            .sum()
+    }
+
+    pub fn evaluate(&self, board: &Board, color: Color) -> f32 {
+        match board.status() {
+            BoardStatus::Checkmate if board.side_to_move() == color => return -MATE_SCORE,
+            BoardStatus::Checkmate => return MATE_SCORE,
+            BoardStatus::Stalemate => return 0.0,
+            BoardStatus::Ongoing => {}
+        }
+
+        ALL_PIECES
+            .iter()
+            .map(|piece| self.piece_score(board, *piece, color) - self.piece_score(board, *piece, !color))
+            .sum()
+    }
+}
+
+impl Evaluator for ClassicParams {
+    fn evaluate(&self, board: &Board, color: Color) -> f32 {
+        ClassicParams::evaluate(self, board, color)
+    }
+
+    //Material and piece square tables, or just the result once the game is over
+    fn terms(&self, board: &Board, color: Color) -> Vec<EvalTerm> {
+        if board.status() != BoardStatus::Ongoing {
+            return vec![EvalTerm::new("result", self.evaluate(board, color))];
+        }
+
+        let side = |color| {
+            ALL_PIECES.iter().fold((0.0, 0.0), |(material, placement), piece| {
+                let pieces = *board.pieces(*piece) & *board.color_combined(color);
+
+                (
+                    material + self.material[piece.to_index()] * pieces.popcnt() as f32,
+                    placement + pieces.map(|square| self.pst[piece.to_index()][relative_square(square, color)]).sum::<f32>(),
+                )
+            })
+        };
+
+        let (own, other) = (side(color), side(!color));
+
+        vec![EvalTerm::new("material", own.0 - other.0), EvalTerm::new("piece placement", own.1 - other.1)]
+    }
+}
+
+//A normal engine for once: material and piece square tables with a short alpha-beta search
+pub fn classic_engine(color: Color) -> TreeSearchEngine {
+    classic_engine_at_depth(color, CLASSIC_DEPTH)
+}
+
+pub fn classic_engine_at_depth(color: Color, depth: u32) -> TreeSearchEngine {
+    let params = ClassicParams::load();
+
+    //Looks the basic endings up rather than searching them
+    TreeSearchEngine::with_evaluator(color, with_oracle(params), depth)
+}
@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+use crate::util::timestamp;
+
+//Lines kept in memory for the console panel
+const MAX_LINES: usize = 1000;
+
+//Every external engine's conversation is appended to a file named after it in here
+const LOG_DIR: &str = "engine_logs";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    //Sent to the engine
+    Sent,
+    Stdout,
+    Stderr,
+    //From the arena about the engine, like it being killed
+    Arena,
+}
+
+impl Stream {
+    fn marker(&self) -> &'static str {
+        match self {
+            Stream::Sent => ">",
+            Stream::Stdout => "<",
+            Stream::Stderr => "!",
+            Stream::Arena => "#",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConsoleLine {
+    pub engine: String,
+    pub stream: Stream,
+    pub text: String,
+}
+
+impl ConsoleLine {
+    pub fn to_text(&self) -> String {
+        format!("{} {} {}", self.engine, self.stream.marker(), self.text)
+    }
+}
+
+//Recent lines, and how many were ever recorded so that a game can ask for only the ones since it started
+static LINES: Mutex<(VecDeque<ConsoleLine>, usize)> = Mutex::new((VecDeque::new(), 0));
+
+//Lines recorded so far
+pub fn position() -> usize {
+    LINES.lock().unwrap().1
+}
+
+//Up to the last `count` lines recorded after `since`, oldest first
+pub fn lines_since(since: usize, count: usize) -> Vec<ConsoleLine> {
+    let lines = LINES.lock().unwrap();
+    let (buffer, total) = &*lines;
+
+    let new = (total - since.min(*total)).min(buffer.len());
+
+    buffer.iter().skip(buffer.len() - new.min(count)).cloned().collect()
+}
+
+//One engine process's side of the console, shared with the threads reading its output
+#[derive(Debug)]
+pub struct EngineLog {
+    engine: String,
+    file: Option<Mutex<File>>,
+}
+
+impl EngineLog {
+    pub fn open(engine: &str, command: &[String]) -> EngineLog {
+        let file_name: String = engine.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect();
+        let path = format!("{}/{}.log", LOG_DIR, file_name);
+
+        let file = fs::create_dir_all(LOG_DIR)
+            .and_then(|_| OpenOptions::new().create(true).append(true).open(&path))
+            .map_err(|e| println!("Could not open engine log {}: {}", path, e))
+            .ok();
+
+        let log = EngineLog {
+            engine: engine.to_string(),
+            file: file.map(Mutex::new),
+        };
+
+        log.write(&format!("--- {} started at {} ---", command.join(" "), timestamp()));
+
+        log
+    }
+
+    fn write(&self, text: &str) {
+        if let Some(file) = &self.file {
+            let _ = writeln!(file.lock().unwrap(), "{}", text);
+        }
+    }
+
+    pub fn record(&self, stream: Stream, text: &str) {
+        let line = ConsoleLine {
+            engine: self.engine.clone(),
+            stream,
+            text: text.to_string(),
+        };
+
+        self.write(&format!("{} {}", stream.marker(), text));
+
+        let mut lines = LINES.lock().unwrap();
+        lines.0.push_back(line);
+        lines.1 += 1;
+
+        if lines.0.len() > MAX_LINES {
+            lines.0.pop_front();
+        }
+    }
+}
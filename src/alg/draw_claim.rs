@@ -0,0 +1,161 @@
+use chess::{Board, ChessMove, Color};
+
+use crate::alg::chess_alg::{ChessAlgorithm, GameConfig, SearchStats};
+use crate::alg::evaluators::eval_material;
+use crate::alg::explain::Explanation;
+use crate::alg::external::{EngineOptions, UciOption, UciOptionKind};
+use crate::gui::chess_display::{GameOutcome, PlayerType};
+use crate::settings;
+
+//Every bot has this in the options editor next to the epsilon. Like it, it is kept out of what engines are sent
+pub const OPTION: &str = "Draw claims";
+
+//Threefold repetition and the 50 move rule can be claimed by either player, while fivefold repetition and the 75 move
+//rule end the game whatever the players want. Positions are counted before the current one, moves in plies
+const CLAIM_REPETITIONS: usize = 2;
+const FORCED_REPETITIONS: usize = 4;
+const CLAIM_REVERSABLE_MOVES: u32 = 100;
+const FORCED_REVERSABLE_MOVES: u32 = 150;
+
+pub fn forced_draw(repetitions: usize, reversable_moves: u32) -> Option<GameOutcome> {
+    if repetitions >= FORCED_REPETITIONS {
+        Some(GameOutcome::DrawByRepetition)
+    } else if reversable_moves >= FORCED_REVERSABLE_MOVES {
+        Some(GameOutcome::DrawBy50MoveRule)
+    } else {
+        None
+    }
+}
+
+pub fn claimable_draw(repetitions: usize, reversable_moves: u32) -> Option<GameOutcome> {
+    if repetitions >= CLAIM_REPETITIONS {
+        Some(GameOutcome::DrawByRepetition)
+    } else if reversable_moves >= CLAIM_REVERSABLE_MOVES {
+        Some(GameOutcome::DrawBy50MoveRule)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClaimPolicy {
+    //As soon as it can, which is what people are taken to do too
+    #[default]
+    Always,
+    Never,
+    //Only when its own evaluation of the position is below even
+    WhenWorse,
+}
+
+const ALL_POLICIES: [ClaimPolicy; 3] = [ClaimPolicy::Always, ClaimPolicy::Never, ClaimPolicy::WhenWorse];
+
+impl ClaimPolicy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClaimPolicy::Always => "Always",
+            ClaimPolicy::Never => "Never",
+            ClaimPolicy::WhenWorse => "When worse",
+        }
+    }
+}
+
+pub fn option() -> UciOption {
+    UciOption {
+        name: String::from(OPTION),
+        kind: UciOptionKind::Combo {
+            default: ClaimPolicy::default().name().to_string(),
+            choices: ALL_POLICIES.iter().map(|policy| policy.name().to_string()).collect(),
+        },
+    }
+}
+
+pub fn policy(values: &EngineOptions) -> ClaimPolicy {
+    values
+        .get(OPTION)
+        .and_then(|value| ALL_POLICIES.into_iter().find(|policy| policy.name().eq_ignore_ascii_case(value)))
+        .unwrap_or_default()
+}
+
+//What was set for the bot in the game creator last
+pub fn saved(name: &str) -> ClaimPolicy {
+    settings::get().engine_options.get(name).map(policy).unwrap_or_default()
+}
+
+//Bots that claim every draw are left as they are, since that is what every bot does by default
+pub fn wrap(player: PlayerType, policy: ClaimPolicy, color: Color) -> PlayerType {
+    match player {
+        PlayerType::Computer(engine) if policy != ClaimPolicy::Always => PlayerType::computer(DrawClaims { inner: engine, policy, color }),
+        player => player,
+    }
+}
+
+//Plays A's moves and claims draws the way `policy` says
+#[derive(Debug)]
+pub struct DrawClaims<A> {
+    inner: A,
+    policy: ClaimPolicy,
+    color: Color,
+}
+
+impl<A: ChessAlgorithm> ChessAlgorithm for DrawClaims<A> {
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        self.inner.get_move(board)
+    }
+
+    fn depth(&self) -> Option<u32> {
+        self.inner.depth()
+    }
+
+    fn set_depth(&mut self, depth: u32) {
+        self.inner.set_depth(depth);
+    }
+
+    fn new_game(&mut self) {
+        self.inner.new_game();
+    }
+
+    fn prepare(&mut self, config: &GameConfig) {
+        self.inner.prepare(config);
+    }
+
+    fn info(&self) -> Option<String> {
+        self.inner.info()
+    }
+
+    fn explain(&self) -> Option<Explanation> {
+        self.inner.explain()
+    }
+
+    fn search_stats(&self) -> SearchStats {
+        self.inner.search_stats()
+    }
+
+    //Bots that don't evaluate positions go by the material
+    fn claim_draw(&mut self, board: &Board) -> bool {
+        match self.policy {
+            ClaimPolicy::Always => true,
+            ClaimPolicy::Never => false,
+            ClaimPolicy::WhenWorse => self.evaluate(board).unwrap_or_else(|| eval_material(board, self.color)) < 0.0,
+        }
+    }
+
+    fn evaluate(&self, board: &Board) -> Option<f32> {
+        self.inner.evaluate(board)
+    }
+
+    fn reproducible(&self) -> bool {
+        self.inner.reproducible()
+    }
+
+    fn crashed(&self) -> bool {
+        self.inner.crashed()
+    }
+
+    fn do_move(&mut self, board: Board, chess_move: ChessMove) {
+        self.inner.do_move(board, chess_move);
+    }
+
+    fn game_over(&mut self, result: &str, reason: &str) {
+        self.inner.game_over(result, reason);
+    }
+}
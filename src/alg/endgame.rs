@@ -0,0 +1,415 @@
+use std::sync::OnceLock;
+
+use chess::{get_bishop_moves, get_king_moves, get_pawn_attacks, get_pawn_quiets, get_rook_moves, BitBoard, Board, ChessMove, Color, MoveGen, Piece, Square, ALL_SQUARES, EMPTY};
+
+use crate::variant::{self, Variant};
+
+use super::chess_alg::{ChessAlgorithm, GameConfig, SearchStats};
+use super::evaluators::{EvalTerm, Evaluator};
+use super::explain::Explanation;
+
+//Known wins score this minus the plies to mate, which keeps them under the classic engine's mate score so that a mate
+//the search can see still comes first
+const ORACLE_WIN: f32 = 900.0;
+
+const POSITIONS: usize = 2 * 64 * 64 * 64;
+
+static KQK: OnceLock<Table> = OnceLock::new();
+static KRK: OnceLock<Table> = OnceLock::new();
+static KPK: OnceLock<Table> = OnceLock::new();
+
+//How an ending with king and queen, rook or pawn against a lone king goes for the side to move, with both sides playing
+//perfectly
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    //Plies until the side to move mates, or is mated
+    Win(u32),
+    Loss(u32),
+    Draw,
+}
+
+impl Verdict {
+    //The same position from the other side, one ply earlier
+    fn before(&self) -> Verdict {
+        match self {
+            Verdict::Win(plies) => Verdict::Loss(plies + 1),
+            Verdict::Loss(plies) => Verdict::Win(plies + 1),
+            Verdict::Draw => Verdict::Draw,
+        }
+    }
+
+    //Higher is better for the side to move: quicker wins and slower losses
+    fn rank(&self) -> (i32, i32) {
+        match self {
+            Verdict::Win(plies) => (2, -(*plies as i32)),
+            Verdict::Draw => (1, 0),
+            Verdict::Loss(plies) => (0, *plies as i32),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Verdict::Win(plies) => format!("mates in {}", plies / 2 + 1),
+            Verdict::Loss(plies) => format!("gets mated in {}", plies / 2),
+            Verdict::Draw => String::from("drawn"),
+        }
+    }
+
+    //For `color`, whichever side is to move
+    fn score(&self, board: &Board, color: Color) -> f32 {
+        let score = match self {
+            Verdict::Win(plies) => ORACLE_WIN - *plies as f32,
+            Verdict::Loss(plies) => -ORACLE_WIN + *plies as f32,
+            Verdict::Draw => 0.0,
+        };
+
+        if board.side_to_move() == color { score } else { -score }
+    }
+}
+
+//A position in one of the tables, with the side that has the extra piece playing up the board as white
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    strong_to_move: bool,
+    strong_king: Square,
+    weak_king: Square,
+    piece: Square,
+}
+
+impl Position {
+    fn index(&self) -> usize {
+        (((!self.strong_to_move as usize) * 64 + self.strong_king.to_index()) * 64 + self.weak_king.to_index()) * 64 + self.piece.to_index()
+    }
+
+    fn from_index(index: usize) -> Position {
+        Position {
+            strong_to_move: index / (64 * 64 * 64) == 0,
+            strong_king: ALL_SQUARES[index / (64 * 64) % 64],
+            weak_king: ALL_SQUARES[index / 64 % 64],
+            piece: ALL_SQUARES[index % 64],
+        }
+    }
+
+    fn with(&self, strong_king: Square, weak_king: Square, piece: Square) -> Position {
+        Position { strong_to_move: !self.strong_to_move, strong_king, weak_king, piece }
+    }
+
+    fn attacks(&self, piece: Piece, blockers: BitBoard) -> BitBoard {
+        match piece {
+            Piece::Queen => get_rook_moves(self.piece, blockers) | get_bishop_moves(self.piece, blockers),
+            Piece::Rook => get_rook_moves(self.piece, blockers),
+            _ => get_pawn_attacks(self.piece, Color::White, !EMPTY),
+        }
+    }
+
+    fn weak_in_check(&self, piece: Piece) -> bool {
+        let blockers = BitBoard::from_square(self.strong_king) | BitBoard::from_square(self.weak_king);
+
+        self.attacks(piece, blockers) & BitBoard::from_square(self.weak_king) != EMPTY
+    }
+
+    fn legal(&self, piece: Piece) -> bool {
+        let rank = self.piece.get_rank().to_index();
+
+        self.strong_king != self.weak_king
+            && self.piece != self.strong_king
+            && self.piece != self.weak_king
+            && get_king_moves(self.strong_king) & BitBoard::from_square(self.weak_king) == EMPTY
+            && (piece != Piece::Pawn || (1..7).contains(&rank))
+            && !(self.strong_to_move && self.weak_in_check(piece))
+    }
+
+    //Calls `f` with what every move leads to in `tables`, the first being the table the position is in and the rest
+    //what a pawn promotes to. Taking the piece is a draw, so it's given as 0 like an unknown position
+    fn successors(&self, piece: Piece, tables: &[&[u8]], mut f: impl FnMut(u8)) {
+        let strong_king = BitBoard::from_square(self.strong_king);
+        let weak_king = BitBoard::from_square(self.weak_king);
+        let own = tables[0];
+
+        if !self.strong_to_move {
+            //The weak king's own square doesn't block the lines it moves along
+            let guarded = get_king_moves(self.strong_king) | self.attacks(piece, strong_king);
+
+            for to in get_king_moves(self.weak_king) & !guarded & !strong_king {
+                if to == self.piece {
+                    f(0);
+                } else {
+                    f(own[self.with(self.strong_king, to, self.piece).index()]);
+                }
+            }
+
+            return;
+        }
+
+        for to in get_king_moves(self.strong_king) & !get_king_moves(self.weak_king) & !BitBoard::from_square(self.piece) & !weak_king {
+            f(own[self.with(to, self.weak_king, self.piece).index()]);
+        }
+
+        if piece != Piece::Pawn {
+            for to in self.attacks(piece, strong_king | weak_king) & !strong_king & !weak_king {
+                f(own[self.with(self.strong_king, self.weak_king, to).index()]);
+            }
+
+            return;
+        }
+
+        for to in get_pawn_quiets(self.piece, Color::White, strong_king | weak_king) {
+            let next = self.with(self.strong_king, self.weak_king, to).index();
+
+            //Promoting to a knight or bishop can only draw
+            if to.get_rank().to_index() == 7 {
+                for table in &tables[1..] {
+                    f(table[next]);
+                }
+            } else {
+                f(own[next]);
+            }
+        }
+    }
+}
+
+//Plies to mate plus one for every position with king and `piece` against a king, or 0 for a draw or an illegal
+//position. The side with the piece can't lose
+#[derive(Debug)]
+struct Table {
+    plies: Vec<u8>,
+}
+
+impl Table {
+    //Works backwards from every mate, a ply at a time. Pawns need the queen and rook tables for their promotions
+    fn solve(piece: Piece, promotions: &[&Table]) -> Table {
+        println!("Working out the K{}K endgame table", piece.to_string(Color::White));
+
+        let mut plies = vec![0; POSITIONS];
+        let legal: Vec<bool> = (0..POSITIONS).map(|index| Position::from_index(index).legal(piece)).collect();
+
+        let promotions: Vec<&[u8]> = promotions.iter().map(|table| table.plies.as_slice()).collect();
+        let horizon = promotions.iter().flat_map(|table| table.iter()).max().copied().unwrap_or(0);
+
+        for index in POSITIONS / 2..POSITIONS {
+            let position = Position::from_index(index);
+
+            if legal[index] && position.weak_in_check(piece) {
+                let mut moves = 0;
+                position.successors(piece, &[plies.as_slice()], |_| moves += 1);
+
+                if moves == 0 {
+                    plies[index] = 1;
+                }
+            }
+        }
+
+        //Positions mated in `depth` plies are found on that pass. The strong side's moves are only read on passes for
+        //the weak side and the other way around, so every pass can update the table as it goes
+        let mut depth: u8 = 1;
+        let mut idle = 0;
+
+        while idle < 2 || depth <= horizon {
+            let strong_to_move = depth % 2 == 1;
+            let range = if strong_to_move { 0..POSITIONS / 2 } else { POSITIONS / 2..POSITIONS };
+            let mut changed = false;
+
+            for index in range {
+                if !legal[index] || plies[index] != 0 {
+                    continue;
+                }
+
+                let position = Position::from_index(index);
+                let mut tables = vec![plies.as_slice()];
+                tables.extend(promotions.iter());
+
+                let (mut moves, mut unknown, mut mating) = (0, 0, false);
+
+                position.successors(piece, &tables, |next| {
+                    moves += 1;
+                    unknown += (next == 0) as usize;
+                    mating |= next != 0 && next <= depth;
+                });
+
+                let solved = if strong_to_move { mating } else { moves > 0 && unknown == 0 };
+
+                if solved {
+                    plies[index] = depth + 1;
+                    changed = true;
+                }
+            }
+
+            idle = if changed { 0 } else { idle + 1 };
+            depth += 1;
+        }
+
+        Table { plies }
+    }
+}
+
+fn table(piece: Piece) -> &'static Table {
+    match piece {
+        Piece::Queen => KQK.get_or_init(|| Table::solve(Piece::Queen, &[])),
+        Piece::Rook => KRK.get_or_init(|| Table::solve(Piece::Rook, &[])),
+        _ => KPK.get_or_init(|| Table::solve(Piece::Pawn, &[table(Piece::Queen), table(Piece::Rook)])),
+    }
+}
+
+//How the position goes for the side to move, if it's one of the endings the tables know. Other variants have their
+//own rules, so only standard chess is looked up
+pub fn probe(board: &Board) -> Option<Verdict> {
+    if board.combined().popcnt() > 3 || variant::current().0 != Variant::Standard {
+        return None;
+    }
+
+    let Some(square) = (*board.combined() & !*board.pieces(Piece::King)).next() else {
+        return Some(Verdict::Draw);
+    };
+
+    let piece = board.piece_on(square).unwrap();
+    let strong = board.color_on(square).unwrap();
+
+    if piece == Piece::Knight || piece == Piece::Bishop {
+        return Some(Verdict::Draw);
+    }
+
+    //Black's pawns move down the board, so its positions are mirrored to be white's
+    let relative = |square: Square| if strong == Color::White { square } else { ALL_SQUARES[square.to_index() ^ 56] };
+
+    let position = Position {
+        strong_to_move: board.side_to_move() == strong,
+        strong_king: relative(board.king_square(strong)),
+        weak_king: relative(board.king_square(!strong)),
+        piece: relative(square),
+    };
+
+    Some(match (table(piece).plies[position.index()], position.strong_to_move) {
+        (0, _) => Verdict::Draw,
+        (plies, true) => Verdict::Win(plies as u32 - 1),
+        (plies, false) => Verdict::Loss(plies as u32 - 1),
+    })
+}
+
+//The quickest win, a draw or the longest defence, whichever the position allows
+pub fn best_move(board: &Board) -> Option<(ChessMove, Verdict)> {
+    probe(board)?;
+
+    MoveGen::new_legal(board)
+        .filter_map(|m| Some((m, probe(&board.make_move_new(m))?.before())))
+        //The first of equally good moves, so that promotions go to a queen
+        .reduce(|best, next| if next.1.rank() > best.1.rank() { next } else { best })
+}
+
+//Plays the endings the tables know perfectly and leaves everything else to another bot
+#[derive(Debug)]
+pub struct EndgameBot {
+    fallback: Box<dyn ChessAlgorithm>,
+    //What the tables said about the last move, if they were used for it
+    verdict: Option<Verdict>,
+}
+
+impl EndgameBot {
+    pub fn new<T: ChessAlgorithm + 'static>(fallback: T) -> EndgameBot {
+        EndgameBot { fallback: Box::new(fallback), verdict: None }
+    }
+}
+
+impl ChessAlgorithm for EndgameBot {
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        if let Some((m, verdict)) = best_move(&board) {
+            self.verdict = Some(verdict);
+            return m;
+        }
+
+        self.verdict = None;
+        self.fallback.get_move(board)
+    }
+
+    fn depth(&self) -> Option<u32> {
+        self.fallback.depth()
+    }
+
+    fn set_depth(&mut self, depth: u32) {
+        self.fallback.set_depth(depth);
+    }
+
+    fn new_game(&mut self) {
+        self.verdict = None;
+        self.fallback.new_game();
+    }
+
+    fn prepare(&mut self, config: &GameConfig) {
+        self.fallback.prepare(config);
+    }
+
+    fn info(&self) -> Option<String> {
+        match self.verdict {
+            Some(verdict) => Some(format!("Endgame tables: {}", verdict.describe())),
+            None => self.fallback.info(),
+        }
+    }
+
+    fn explain(&self) -> Option<Explanation> {
+        self.verdict.is_none().then(|| self.fallback.explain()).flatten()
+    }
+
+    fn search_stats(&self) -> SearchStats {
+        if self.verdict.is_none() { self.fallback.search_stats() } else { SearchStats::default() }
+    }
+
+    fn claim_draw(&mut self, board: &Board) -> bool {
+        self.fallback.claim_draw(board)
+    }
+
+    fn evaluate(&self, board: &Board) -> Option<f32> {
+        self.fallback.evaluate(board)
+    }
+
+    fn reproducible(&self) -> bool {
+        self.fallback.reproducible()
+    }
+
+    fn crashed(&self) -> bool {
+        self.fallback.crashed()
+    }
+
+    fn do_move(&mut self, board: Board, chess_move: ChessMove) {
+        self.fallback.do_move(board, chess_move);
+    }
+
+    fn game_over(&mut self, result: &str, reason: &str) {
+        self.fallback.game_over(result, reason);
+    }
+}
+
+//Another evaluator that goes by the tables whenever the position is in one
+pub struct WithOracle<E> {
+    eval: E,
+}
+
+pub fn with_oracle<E: Evaluator>(eval: E) -> WithOracle<E> {
+    WithOracle { eval }
+}
+
+impl<E: Evaluator> Evaluator for WithOracle<E> {
+    fn evaluate(&self, board: &Board, color: Color) -> f32 {
+        match probe(board) {
+            Some(verdict) => verdict.score(board, color),
+            None => self.eval.evaluate(board, color),
+        }
+    }
+
+    fn evaluate_batch(&self, boards: &[Board], color: Color) -> Vec<f32> {
+        let mut scores = self.eval.evaluate_batch(boards, color);
+
+        for (score, board) in scores.iter_mut().zip(boards) {
+            if let Some(verdict) = probe(board) {
+                *score = verdict.score(board, color);
+            }
+        }
+
+        scores
+    }
+
+    fn terms(&self, board: &Board, color: Color) -> Vec<EvalTerm> {
+        match probe(board) {
+            Some(verdict) => vec![EvalTerm::new("endgame tables", verdict.score(board, color))],
+            None => self.eval.terms(board, color),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use chess::{Board, ChessMove};
+use rand::Rng;
+
+use crate::alg::chess_alg::{available_moves, with_rng, ChessAlgorithm, GameConfig, SearchStats};
+use crate::alg::draw_claim;
+use crate::alg::explain::Explanation;
+use crate::alg::external::{EngineOptions, UciOption, UciOptionKind};
+use crate::gui::chess_display::PlayerType;
+use crate::settings;
+
+//Every bot has this in the options editor, on top of a UCI engine's own options. It is kept out of what engines are
+//sent
+pub const OPTION: &str = "Epsilon %";
+
+pub fn option() -> UciOption {
+    UciOption { name: String::from(OPTION), kind: UciOptionKind::Spin { default: 0, min: 0, max: 100 } }
+}
+
+//Chance of a random move set in `values`, from 0 to 1
+pub fn epsilon(values: &EngineOptions) -> f32 {
+    values.get(OPTION).and_then(|value| option().validate(value)).and_then(|value| value.parse::<f32>().ok()).unwrap_or(0.0) / 100.0
+}
+
+//`values` as they are sent to an engine, without the options every bot has
+pub fn without(values: &EngineOptions) -> EngineOptions {
+    values
+        .iter()
+        .filter(|(name, _)| *name != OPTION && *name != draw_claim::OPTION)
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+//What was set for the bot in the game creator last
+pub fn saved(name: &str) -> f32 {
+    settings::get().engine_options.get(name).map(epsilon).unwrap_or(0.0)
+}
+
+//People and bots that never play random moves are left as they are
+pub fn wrap(player: PlayerType, epsilon: f32) -> PlayerType {
+    match player {
+        PlayerType::Computer(engine) if epsilon > 0.0 => PlayerType::computer(EpsilonGreedy::new(engine, epsilon)),
+        player => player,
+    }
+}
+
+//Plays A's move, except for a uniformly random legal move `epsilon` of the time. Unlike the randomness setting of the
+//engine panel it is part of the bot, so it goes wherever the bot goes
+#[derive(Debug)]
+pub struct EpsilonGreedy<A> {
+    inner: A,
+    epsilon: f32,
+
+    moves: usize,
+    random_moves: usize,
+    last_random: bool,
+}
+
+impl<A: ChessAlgorithm> EpsilonGreedy<A> {
+    pub fn new(inner: A, epsilon: f32) -> EpsilonGreedy<A> {
+        EpsilonGreedy { inner, epsilon: epsilon.clamp(0.0, 1.0), moves: 0, random_moves: 0, last_random: false }
+    }
+}
+
+impl<A: ChessAlgorithm> ChessAlgorithm for EpsilonGreedy<A> {
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        self.moves += 1;
+        self.last_random = self.epsilon > 0.0 && with_rng(|rng| rng.gen::<f32>()) < self.epsilon;
+
+        if !self.last_random {
+            return self.inner.get_move(board);
+        }
+
+        self.random_moves += 1;
+
+        let moves = available_moves(&board);
+        moves[with_rng(|rng| rng.gen_range(0..moves.len()))]
+    }
+
+    fn depth(&self) -> Option<u32> {
+        self.inner.depth()
+    }
+
+    fn set_depth(&mut self, depth: u32) {
+        self.inner.set_depth(depth);
+    }
+
+    fn new_game(&mut self) {
+        self.moves = 0;
+        self.random_moves = 0;
+        self.last_random = false;
+        self.inner.new_game();
+    }
+
+    fn prepare(&mut self, config: &GameConfig) {
+        self.inner.prepare(config);
+    }
+
+    fn info(&self) -> Option<String> {
+        let random = format!("Random moves: {} of {} at epsilon {:.2}", self.random_moves, self.moves, self.epsilon);
+
+        match self.inner.info() {
+            Some(info) => Some(format!("{}\n{}", random, info)),
+            None => Some(random),
+        }
+    }
+
+    //Random moves have nothing to explain
+    fn explain(&self) -> Option<Explanation> {
+        (!self.last_random).then(|| self.inner.explain()).flatten()
+    }
+
+    //Random moves aren't searched
+    fn search_stats(&self) -> SearchStats {
+        if self.last_random { SearchStats::default() } else { self.inner.search_stats() }
+    }
+
+    fn claim_draw(&mut self, board: &Board) -> bool {
+        self.inner.claim_draw(board)
+    }
+
+    fn evaluate(&self, board: &Board) -> Option<f32> {
+        self.inner.evaluate(board)
+    }
+
+    fn reproducible(&self) -> bool {
+        self.inner.reproducible()
+    }
+
+    fn crashed(&self) -> bool {
+        self.inner.crashed()
+    }
+
+    fn do_move(&mut self, board: Board, chess_move: ChessMove) {
+        self.inner.do_move(board, chess_move);
+    }
+
+    fn game_over(&mut self, result: &str, reason: &str) {
+        self.inner.game_over(result, reason);
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+use chess::Board;
+
+use crate::variant::{self, VariantState};
+
+//Positions remembered by each engine
+const EVAL_CACHE_SIZE: usize = 1 << 16;
+
+//Zobrist hashes are as random as hashing them again would make them, so they are used as they are
+#[derive(Debug, Default)]
+pub struct ZobristHasher(u64);
+
+impl Hasher for ZobristHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ *byte as u64;
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = n;
+    }
+}
+
+pub type ZobristMap<V> = HashMap<u64, V, BuildHasherDefault<ZobristHasher>>;
+
+//Evals of positions a lookahead engine has already looked at, keyed on the zobrist hash. Bots that shuffle their
+//pieces back and forth reach the same positions over and over
+//
+//Roughly least recently used: positions go in the newer half, which becomes the older half when it fills up, and
+//anything used from the older half moves back to the newer one. Whatever is still in the older half when the newer
+//one fills up again is forgotten
+#[derive(Debug, Default)]
+pub struct EvalCache {
+    newer: ZobristMap<(f32, VariantState)>,
+    older: ZobristMap<(f32, VariantState)>,
+    hits: u64,
+    misses: u64,
+}
+
+impl EvalCache {
+    pub fn new() -> EvalCache {
+        EvalCache::default()
+    }
+
+    //The eval of `board` from before, if it was seen with the same crazyhouse pockets
+    pub fn get(&mut self, board: &Board) -> Option<f32> {
+        let hash = board.get_hash();
+        let (_, state) = variant::current();
+
+        if let Some(&(score, _)) = self.newer.get(&hash).filter(|(_, s)| *s == state) {
+            self.hits += 1;
+            return Some(score);
+        }
+
+        match self.older.remove(&hash).filter(|(_, s)| *s == state) {
+            Some((score, _)) => {
+                self.hits += 1;
+                self.insert(board, score);
+                Some(score)
+            }
+
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, board: &Board, score: f32) {
+        let (_, state) = variant::current();
+
+        if self.newer.len() >= EVAL_CACHE_SIZE / 2 {
+            self.older = std::mem::take(&mut self.newer);
+        }
+
+        self.newer.insert(board.get_hash(), (score, state));
+    }
+
+    pub fn get_or_eval(&mut self, board: &Board, eval: impl FnOnce(&Board) -> f32) -> f32 {
+        self.get(board).unwrap_or_else(|| {
+            let score = eval(board);
+            self.insert(board, score);
+            score
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.newer.clear();
+        self.older.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    pub fn positions(&self) -> usize {
+        self.newer.len() + self.older.len()
+    }
+
+    pub fn hit_rate(&self) -> f32 {
+        self.hits as f32 / (self.hits + self.misses).max(1) as f32
+    }
+
+    pub fn stats(&self) -> String {
+        format!(
+            "Eval cache: {:.1}% of {} lookups hit, {} positions",
+            self.hit_rate() * 100.0,
+            self.hits + self.misses,
+            self.positions()
+        )
+    }
+}
@@ -1,6 +1,89 @@
-use chess::{Board, Color, ALL_SQUARES, Square, BoardStatus};
+use chess::{get_rank, BitBoard, Board, Color, Rank, ALL_SQUARES, Square, BoardStatus, EMPTY};
 
-use super::chess_alg::available_moves;
+use super::chess_alg::for_each_move;
+
+//Scores positions for `color`, higher being better. Plain functions and closures are evaluators, but ones that are
+//quicker at scoring many boards at once, like neural nets, can implement `evaluate_batch` themselves so that
+//lookahead engines can hand them every candidate move in one call
+pub trait Evaluator {
+    fn evaluate(&self, board: &Board, color: Color) -> f32;
+
+    fn evaluate_batch(&self, boards: &[Board], color: Color) -> Vec<f32> {
+        boards.iter().map(|board| self.evaluate(board, color)).collect()
+    }
+
+    //The parts that add up to `evaluate`, so that bots can say why they picked a move. Evaluators that are one number
+    //are a single term
+    fn terms(&self, board: &Board, color: Color) -> Vec<EvalTerm> {
+        vec![EvalTerm::new("score", self.evaluate(board, color))]
+    }
+}
+
+impl<F: Fn(&Board, Color) -> f32> Evaluator for F {
+    fn evaluate(&self, board: &Board, color: Color) -> f32 {
+        self(board, color)
+    }
+}
+
+//One part of an evaluation, like material or king distance
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalTerm {
+    pub name: String,
+    pub value: f32,
+    //The value is minus something the bot wants less of, like the distance to the enemy king
+    pub penalty: bool,
+}
+
+impl EvalTerm {
+    pub fn new(name: &str, value: f32) -> EvalTerm {
+        EvalTerm { name: name.to_string(), value, penalty: false }
+    }
+
+    //How much better a move that changed the term by `change` is for it, like "reduces king distance by 3"
+    pub fn describe(&self, change: f32) -> String {
+        match (self.penalty, change >= 0.0) {
+            (false, true) => format!("gains {} on {}", format_change(change), self.name),
+            (false, false) => format!("loses {} on {}", format_change(-change), self.name),
+            (true, true) => format!("reduces {} by {}", self.name, format_change(change)),
+            (true, false) => format!("increases {} by {}", self.name, format_change(-change)),
+        }
+    }
+}
+
+//Whole numbers for counts and distances, two decimals for everything else
+fn format_change(change: f32) -> String {
+    if change.fract().abs() < 0.001 {
+        format!("{:.0}", change)
+    } else {
+        format!("{:.2}", change)
+    }
+}
+
+//An evaluator function with a name for its term
+pub struct Named<F> {
+    name: &'static str,
+    penalty: bool,
+    eval: F,
+}
+
+pub fn named<F: Fn(&Board, Color) -> f32>(name: &'static str, eval: F) -> Named<F> {
+    Named { name, penalty: false, eval }
+}
+
+//For evaluators that score minus what they measure
+pub fn named_penalty<F: Fn(&Board, Color) -> f32>(name: &'static str, eval: F) -> Named<F> {
+    Named { name, penalty: true, eval }
+}
+
+impl<F: Fn(&Board, Color) -> f32> Evaluator for Named<F> {
+    fn evaluate(&self, board: &Board, color: Color) -> f32 {
+        (self.eval)(board, color)
+    }
+
+    fn terms(&self, board: &Board, color: Color) -> Vec<EvalTerm> {
+        vec![EvalTerm { penalty: self.penalty, ..EvalTerm::new(self.name, self.evaluate(board, color)) }]
+    }
+}
 
 fn square_color(square: chess::Square) -> Color {
     let rank = square.get_rank().to_index();
@@ -20,7 +103,7 @@ fn opposite(color: Color) -> Color {
     }
 }
 
-fn value_of_piece(piece: chess::Piece) -> f32 {
+pub(super) fn value_of_piece(piece: chess::Piece) -> f32 {
     match piece {
         chess::Piece::Pawn => 1.0,
         chess::Piece::Knight => 3.0,
@@ -98,6 +181,55 @@ pub fn eval_swarm(board: &Board, color: Color) -> f32 {
     -dist
 }
 
+//A piece on the right side of the midline is worth this many more than one on the wrong side
+const WRONG_HALF_PENALTY: f32 = 10.0;
+
+//Ranks 1 to 4 for white and 5 to 8 for black
+fn home_half(color: Color) -> BitBoard {
+    let ranks = match color {
+        Color::White => [Rank::First, Rank::Second, Rank::Third, Rank::Fourth],
+        Color::Black => [Rank::Fifth, Rank::Sixth, Rank::Seventh, Rank::Eighth],
+    };
+
+    ranks.into_iter().fold(EMPTY, |mask, rank| mask | get_rank(rank))
+}
+
+//Pieces in `wanted` count for far more than the others, which still count for one so that losing them never helps
+fn eval_half(board: &Board, color: Color, wanted: BitBoard) -> f32 {
+    let pieces = *board.color_combined(color);
+
+    (pieces & wanted).popcnt() as f32 * (WRONG_HALF_PENALTY + 1.0) + (pieces & !wanted).popcnt() as f32
+}
+
+pub fn eval_stay_home(board: &Board, color: Color) -> f32 {
+    eval_half(board, color, home_half(color))
+}
+
+pub fn eval_invade(board: &Board, color: Color) -> f32 {
+    eval_half(board, color, !home_half(color))
+}
+
+//Own material minus the opponent's, with mating the opponent best of all
+pub fn eval_material(board: &Board, color: Color) -> f32 {
+    if board.status() == BoardStatus::Checkmate {
+        return if board.side_to_move() == color { -10e20 } else { 10e20 };
+    }
+
+    let mut score = 0.0;
+
+    for square in ALL_SQUARES {
+        if let (Some(piece), Some(piece_color)) = (board.piece_on(square), board.color_on(square)) {
+            if piece_color == color {
+                score += value_of_piece(piece);
+            } else {
+                score -= value_of_piece(piece);
+            }
+        }
+    }
+
+    score
+}
+
 pub fn eval_pacifist(board: &Board, color: Color) -> f32 {
     if board.status() == BoardStatus::Checkmate {
         return -10e20;
@@ -123,11 +255,11 @@ pub fn eval_generous(board: &Board, color: Color) -> f32 {
 
     let mut score = 0.0;
 
-    for m in available_moves(board) {
+    for_each_move(board, |m| {
         if let Some(capture) = board.piece_on(m.get_dest()) {
             score += value_of_piece(capture);
         }
-    }
+    });
 
     score as f32
 }
@@ -147,13 +279,13 @@ pub fn eval_insist_2(board: &Board, color: Color) -> f32 {
 
     let mut score = 10000.0;
 
-    for m in available_moves(board) {
+    for_each_move(board, |m| {
         if let Some(capture) = board.piece_on(m.get_dest()) {
             score = f32::min(score, value_of_piece(capture));
         } else {
             score = f32::min(score, 0.0);
         }
-    }
+    });
 
 
     if score < 0.0001 {
@@ -178,14 +310,16 @@ pub fn eval_insist_3(board: &Board, color: Color) -> f32 {
     }
 
     let mut score = 0.0;
+    let mut move_count = 0;
 
-    let moves = available_moves(board);
+    for_each_move(board, |m| {
+        move_count += 1;
 
-    for m in moves.iter() {
         if let Some(capture) = board.piece_on(m.get_dest()) {
-            score += 1.0 / moves.len() as f32 * value_of_piece(capture);
+            score += value_of_piece(capture);
         }
-    }
+    });
 
-    score as f32
+    //Averaged over every move, counted on the way rather than generating them twice
+    score / move_count.max(1) as f32
 }
\ No newline at end of file
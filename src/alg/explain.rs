@@ -0,0 +1,131 @@
+use chess::{Board, ChessMove, Color};
+use serde::{Deserialize, Serialize};
+
+use crate::util::move_to_SAN;
+
+use super::chess_alg::make_move;
+use super::evaluators::{EvalTerm, Evaluator};
+
+//What an evaluator bot thought of the move it played, kept with finished games for looking at afterwards
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MoveTerms {
+    pub score: f32,
+    //In the order the evaluator gives them
+    pub terms: Vec<(String, f32)>,
+}
+
+impl MoveTerms {
+    //Like "Score 1.25: Material 3.00, King distance -4.00"
+    pub fn comment(&self) -> String {
+        let terms: Vec<String> = self.terms.iter().map(|(name, value)| format!("{} {:.2}", name, value)).collect();
+
+        format!("Score {:.2}: {}", self.score, terms.join(", "))
+    }
+}
+
+//Why an evaluator bot picked its last move over the next best one, term by term
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    pub board: Board,
+    pub chosen: ChessMove,
+    pub score: f32,
+    //The best of the other moves, missing when there was only one legal move
+    pub runner_up: Option<(ChessMove, f32)>,
+    //Plies looked at past each move. The terms are still those of the positions right after the moves
+    pub lookahead: u32,
+    //Each term for the chosen move's position and the runner up's
+    pub terms: Vec<(EvalTerm, Option<EvalTerm>)>,
+}
+
+impl Explanation {
+    //`scores` has every move the bot looked at with how it scored it
+    pub fn new(eval: &dyn Evaluator, color: Color, board: Board, chosen: ChessMove, scores: &[(ChessMove, f32)], lookahead: u32) -> Explanation {
+        let score = scores.iter().find(|(m, _)| *m == chosen).map_or(f32::NAN, |(_, score)| *score);
+
+        let runner_up = scores
+            .iter()
+            .filter(|(m, _)| *m != chosen)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .copied();
+
+        let chosen_terms = eval.terms(&make_move(&board, chosen), color);
+        let runner_up_terms = runner_up.map(|(m, _)| eval.terms(&make_move(&board, m), color));
+
+        let terms = chosen_terms
+            .into_iter()
+            .enumerate()
+            .map(|(i, term)| (term, runner_up_terms.as_ref().and_then(|terms| terms.get(i).cloned())))
+            .collect();
+
+        Explanation {
+            board,
+            chosen,
+            score,
+            runner_up,
+            lookahead,
+            terms,
+        }
+    }
+
+    //The term that did the most for the chosen move
+    fn deciding_term(&self) -> Option<(&EvalTerm, f32)> {
+        self.terms
+            .iter()
+            .filter_map(|(term, other)| Some((term, term.value - other.as_ref()?.value)))
+            .filter(|(_, change)| *change > 0.0001)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    //Like "Chose Nf3 over e4 because it reduces king distance by 3"
+    pub fn summary(&self) -> String {
+        let chosen = move_to_SAN(&self.board, self.chosen);
+
+        let Some((runner_up, runner_up_score)) = self.runner_up else {
+            return format!("Played {}, the only legal move", chosen);
+        };
+
+        let runner_up = move_to_SAN(&self.board, runner_up);
+
+        if (self.score - runner_up_score).abs() < 0.0001 {
+            return format!("Chose {} at random, {} scored the same", chosen, runner_up);
+        }
+
+        //Deeper searches can pick a move that looks worse straight away
+        if self.lookahead > 0 {
+            return match self.deciding_term() {
+                Some((term, change)) => format!(
+                    "Chose {} over {}, {:.2} better looking {} plies past it. Straight away it {}",
+                    chosen,
+                    runner_up,
+                    self.score - runner_up_score,
+                    self.lookahead,
+                    term.describe(change)
+                ),
+                None => format!("Chose {} over {}, {:.2} better looking {} plies past it", chosen, runner_up, self.score - runner_up_score, self.lookahead),
+            };
+        }
+
+        match self.deciding_term() {
+            Some((term, change)) => format!("Chose {} over {} because it {}", chosen, runner_up, term.describe(change)),
+            None => format!("Chose {} over {}", chosen, runner_up),
+        }
+    }
+
+    pub fn move_terms(&self) -> MoveTerms {
+        MoveTerms {
+            score: self.score,
+            terms: self.terms.iter().map(|(term, _)| (term.name.clone(), term.value)).collect(),
+        }
+    }
+
+    //Every term, with the runner up's next to it
+    pub fn lines(&self) -> Vec<String> {
+        self.terms
+            .iter()
+            .map(|(term, other)| match other {
+                Some(other) => format!("{}: {:.2} vs {:.2}", term.name, term.value, other.value),
+                None => format!("{}: {:.2}", term.name, term.value),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,1017 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::{Board, ChessMove};
+
+use crate::cli;
+use crate::clock;
+use crate::settings;
+use crate::observer::{legal_line, Analysis, Observer};
+use crate::gui::chess_display::PlayerType;
+use crate::pgn::{self, parse_san};
+use crate::util::move_to_SAN;
+
+use super::chess_alg::{stop_requested, ChessAlgorithm, GameConfig, RandomChessAlgorithm, SearchStats, STOP_GRACE};
+use super::console::{EngineLog, Stream};
+use super::epsilon;
+
+//Time engines get to think about every move in untimed games
+const MOVE_TIME: Duration = Duration::from_secs(1);
+
+//Observers are given a depth rather than a time, and searches that take longer than this are given up on
+const OBSERVER_MAX_DEPTH: u32 = 18;
+const OBSERVER_TIMEOUT: Duration = Duration::from_secs(30);
+
+//How often a search is checked for having been told to stop
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+//How often engines with a memory limit have their memory use checked
+const MEMORY_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+//Think time from the engine's time manager on a clock
+fn move_time() -> Duration {
+    clock::think_time().map_or(MOVE_TIME, |t| t.budget.max(Duration::from_millis(10)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Uci,
+    Cecp,
+}
+
+#[derive(Debug, Clone)]
+pub struct EngineSpec {
+    pub name: String,
+    pub protocol: Protocol,
+    pub command: Vec<String>,
+}
+
+//Parses NAME=PROTOCOL:COMMAND, e.g. "Fairy-Max=xboard:fairymax"
+impl FromStr for EngineSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, rest) = s.split_once('=').ok_or("Expected NAME=PROTOCOL:COMMAND")?;
+        let (protocol, command) = rest.split_once(':').ok_or("Expected NAME=PROTOCOL:COMMAND")?;
+
+        let protocol = match protocol.to_ascii_lowercase().as_str() {
+            "uci" => Protocol::Uci,
+            "xboard" | "cecp" => Protocol::Cecp,
+            other => return Err(format!("Unknown engine protocol '{}', expected uci or xboard", other)),
+        };
+
+        let command: Vec<String> = command.split_whitespace().map(|s| s.to_string()).collect();
+
+        if name.trim().is_empty() || command.is_empty() {
+            return Err(String::from("Engine name and command can't be empty"));
+        }
+
+        Ok(EngineSpec {
+            name: name.trim().to_string(),
+            protocol,
+            command,
+        })
+    }
+}
+
+//Limits on an engine's processes, parsed from NAME:LIMITS, e.g. "Stockfish:memory=512,cores=0-3,timeout=10,isolate"
+#[derive(Debug, Clone, Default)]
+pub struct EngineLimits {
+    pub name: String,
+    //Megabytes of resident memory the engine can use before it is killed
+    pub memory_mb: Option<u64>,
+    //Cores the engine runs on, as a list taskset understands like 0-3 or 0,2
+    pub cores: Option<String>,
+    //How long past its think time an engine can go without answering before it is killed rather than waited on
+    pub timeout: Option<Duration>,
+    //Runs in an empty temporary directory instead of the arena's, which is removed once the engine exits
+    pub isolate: bool,
+}
+
+impl FromStr for EngineLimits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, list) = s.split_once(':').ok_or("Expected NAME:LIMITS")?;
+
+        if name.trim().is_empty() {
+            return Err(String::from("Engine name can't be empty"));
+        }
+
+        let mut limits = EngineLimits {
+            name: name.trim().to_string(),
+            ..Default::default()
+        };
+
+        for limit in list.split(',').map(str::trim).filter(|limit| !limit.is_empty()) {
+            match limit.split_once('=') {
+                Some(("memory", mb)) => {
+                    limits.memory_mb = Some(mb.parse().map_err(|_| format!("Invalid memory limit '{}', expected megabytes", mb))?);
+                }
+                Some(("cores", cores)) => limits.cores = Some(cores.to_string()),
+                Some(("timeout", secs)) => {
+                    let secs = secs.parse::<f32>().ok().filter(|secs| secs.is_finite() && *secs >= 0.0);
+                    limits.timeout = Some(Duration::from_secs_f32(secs.ok_or("Invalid timeout, expected seconds")?));
+                }
+                None if limit == "isolate" => limits.isolate = true,
+                _ => return Err(format!("Unknown engine limit '{}', expected memory=MB, cores=LIST, timeout=SECONDS or isolate", limit)),
+            }
+        }
+
+        Ok(limits)
+    }
+}
+
+//Values for a UCI engine's options by name, as they are sent with setoption
+pub type EngineOptions = BTreeMap<String, String>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UciOptionKind {
+    Spin { default: i64, min: i64, max: i64 },
+    Check { default: bool },
+    Combo { default: String, choices: Vec<String> },
+    String { default: String },
+    Button,
+}
+
+//An option the engine advertised after `uci`
+#[derive(Debug, Clone, PartialEq)]
+pub struct UciOption {
+    pub name: String,
+    pub kind: UciOptionKind,
+}
+
+impl UciOption {
+    //Parses `option name Skill Level type spin default 20 min 0 max 20`. Names and values can contain spaces
+    fn parse(line: &str) -> Option<UciOption> {
+        let rest = line.strip_prefix("option name ")?;
+        let (name, rest) = rest.split_once(" type ")?;
+
+        let tokens: Vec<&str> = rest.split_whitespace().collect();
+        let keywords = ["default", "min", "max", "var"];
+
+        //Every value following `keyword`, each running up to the next keyword
+        let values = |keyword: &str| -> Vec<String> {
+            let mut values = vec![];
+            let mut i = 0;
+
+            while i < tokens.len() {
+                if tokens[i] == keyword {
+                    let end = tokens[i + 1..].iter().position(|t| keywords.contains(t)).map_or(tokens.len(), |end| i + 1 + end);
+                    values.push(tokens[i + 1..end].join(" "));
+                    i = end;
+                } else {
+                    i += 1;
+                }
+            }
+
+            values
+        };
+
+        let first = |keyword: &str| values(keyword).into_iter().next().unwrap_or_default();
+        let number = |keyword: &str| first(keyword).parse::<i64>().ok();
+
+        let kind = match *tokens.first()? {
+            "spin" => UciOptionKind::Spin {
+                default: number("default")?,
+                min: number("min").unwrap_or(i64::MIN),
+                max: number("max").unwrap_or(i64::MAX),
+            },
+            "check" => UciOptionKind::Check { default: first("default") == "true" },
+            "combo" => UciOptionKind::Combo { default: first("default"), choices: values("var") },
+            "string" => UciOptionKind::String {
+                //<empty> is how engines write an empty default
+                default: Some(first("default")).filter(|d| d != "<empty>").unwrap_or_default(),
+            },
+            "button" => UciOptionKind::Button,
+            _ => return None,
+        };
+
+        Some(UciOption { name: name.trim().to_string(), kind })
+    }
+
+    pub fn default_value(&self) -> String {
+        match &self.kind {
+            UciOptionKind::Spin { default, .. } => default.to_string(),
+            UciOptionKind::Check { default } => default.to_string(),
+            UciOptionKind::Combo { default, .. } | UciOptionKind::String { default } => default.clone(),
+            UciOptionKind::Button => String::new(),
+        }
+    }
+
+    //`value` made valid for this option, or None if it can't be
+    pub fn validate(&self, value: &str) -> Option<String> {
+        match &self.kind {
+            UciOptionKind::Spin { min, max, .. } => value.trim().parse::<i64>().ok().map(|v| v.clamp(*min, *max).to_string()),
+            UciOptionKind::Check { .. } => matches!(value, "true" | "false").then(|| value.to_string()),
+            UciOptionKind::Combo { choices, .. } => choices.iter().find(|c| c.eq_ignore_ascii_case(value)).cloned(),
+            UciOptionKind::String { .. } => Some(value.to_string()),
+            UciOptionKind::Button => None,
+        }
+    }
+}
+
+//Options of engines that were already asked for them, by name
+static UCI_OPTIONS: Mutex<Option<HashMap<String, Vec<UciOption>>>> = Mutex::new(None);
+
+impl EngineSpec {
+    //With the options saved for this engine
+    pub fn player_type(&self) -> PlayerType {
+        let options = settings::get().engine_options.get(&self.name).cloned().unwrap_or_default();
+
+        self.player_type_with(&options)
+    }
+
+    //The epsilon option isn't sent, since the engine is wrapped to play it rather than playing it itself
+    pub fn player_type_with(&self, options: &EngineOptions) -> PlayerType {
+        let options = epsilon::without(options);
+
+        let engine = EngineProcess::start(self).and_then(|process| match self.protocol {
+            Protocol::Uci => UciEngine::new(process, &options).map(PlayerType::computer),
+            Protocol::Cecp => CecpEngine::new(process).map(PlayerType::computer),
+        });
+
+        match engine {
+            Ok(player) => player,
+            Err(e) => {
+                println!("Could not start engine {}: {}. Playing random moves instead", self.name, e);
+                PlayerType::computer(RandomChessAlgorithm)
+            }
+        }
+    }
+
+    //The --engine-limits given for this engine, if any
+    pub fn limits(&self) -> EngineLimits {
+        cli::args().engine_limits.iter().find(|limits| limits.name == self.name).cloned().unwrap_or_default()
+    }
+
+    //Starts the engine once to find out which options it has, remembering them for the rest of the run
+    pub fn uci_options(&self) -> Result<Vec<UciOption>, String> {
+        if self.protocol != Protocol::Uci {
+            return Err(format!("{} is not a UCI engine", self.name));
+        }
+
+        if let Some(options) = UCI_OPTIONS.lock().unwrap().as_ref().and_then(|known| known.get(&self.name)) {
+            return Ok(options.clone());
+        }
+
+        let mut process = EngineProcess::start(self)?;
+        let options = uci_handshake(&mut process)?;
+
+        UCI_OPTIONS.lock().unwrap().get_or_insert_with(HashMap::new).insert(self.name.clone(), options.clone());
+
+        Ok(options)
+    }
+}
+
+//The engine given on the command line with this name
+pub fn find_engine(name: &str) -> Option<&'static EngineSpec> {
+    cli::args().engines.iter().find(|engine| engine.name == name)
+}
+
+//Numbers the working directories of isolated engines
+static WORK_DIRS: AtomicUsize = AtomicUsize::new(0);
+
+//Every engine process started, so that they can all be told to quit when the window closes with games still going
+//on other threads. Engines that were dropped are skipped
+#[derive(Debug)]
+struct RunningEngine {
+    child: Weak<Mutex<Child>>,
+    stdin: Weak<Mutex<ChildStdin>>,
+    log: Weak<EngineLog>,
+    dead: Arc<AtomicBool>,
+    work_dir: Option<PathBuf>,
+}
+
+static RUNNING: Mutex<Vec<RunningEngine>> = Mutex::new(Vec::new());
+
+//How long engines get to quit by themselves before they are killed, when one is dropped or the arena shuts down
+const QUIT_GRACE: Duration = Duration::from_millis(500);
+
+//Sends every engine still running `quit`, and kills the ones that are still there shortly after. Searches waiting on
+//them give up straight away
+pub fn shutdown_engines() {
+    let running: Vec<RunningEngine> = std::mem::take(&mut *RUNNING.lock().unwrap());
+    let mut children = vec![];
+
+    for engine in running {
+        let Some(child) = engine.child.upgrade() else {
+            continue;
+        };
+
+        engine.dead.store(true, Ordering::Relaxed);
+
+        if let Some(log) = engine.log.upgrade() {
+            log.record(Stream::Arena, "Shutting down");
+        }
+
+        if let Some(stdin) = engine.stdin.upgrade() {
+            let _ = writeln!(stdin.lock().unwrap(), "quit");
+        }
+
+        children.push((child, engine.work_dir));
+    }
+
+    if children.is_empty() {
+        return;
+    }
+
+    println!("Stopping {} engine(s)", children.len());
+
+    let deadline = Instant::now() + QUIT_GRACE;
+
+    while Instant::now() < deadline && !children.iter().all(|(child, _)| matches!(child.lock().unwrap().try_wait(), Ok(Some(_)))) {
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    for (child, work_dir) in children {
+        let mut child = child.lock().unwrap();
+        let _ = child.kill();
+        let _ = child.wait();
+
+        if let Some(dir) = work_dir {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+//Resident memory of a process in kilobytes, from /proc, so only on Linux
+fn resident_kb(pid: u32) -> Option<u64> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|kb| kb.parse().ok())
+}
+
+//Kills the engine once it uses more than `limit_mb`, checking until it exits
+fn watch_memory(child: Arc<Mutex<Child>>, dead: Arc<AtomicBool>, log: Arc<EngineLog>, limit_mb: u64) {
+    thread::spawn(move || loop {
+        thread::sleep(MEMORY_CHECK_INTERVAL);
+
+        let mut child = child.lock().unwrap();
+
+        if !matches!(child.try_wait(), Ok(None)) {
+            break;
+        }
+
+        let Some(kb) = resident_kb(child.id()) else {
+            log.record(Stream::Arena, "Memory use can't be read on this system, so the memory limit is ignored");
+            break;
+        };
+
+        if kb / 1024 > limit_mb {
+            log.record(Stream::Arena, &format!("Killed for using {} MB, over its limit of {} MB", kb / 1024, limit_mb));
+            dead.store(true, Ordering::Relaxed);
+            let _ = child.kill();
+            break;
+        }
+    });
+}
+
+#[derive(Debug)]
+struct EngineProcess {
+    name: String,
+    //Shared with the memory watchdog
+    child: Arc<Mutex<Child>>,
+    //Shared with shutdown_engines
+    stdin: Arc<Mutex<ChildStdin>>,
+    lines: Receiver<String>,
+    //Everything sent and printed, for the console panel and the engine's log file
+    log: Arc<EngineLog>,
+
+    limits: EngineLimits,
+    //Set once the engine was killed for going over a limit. It is never asked anything again
+    dead: Arc<AtomicBool>,
+    //Where an isolated engine runs
+    work_dir: Option<PathBuf>,
+}
+
+impl EngineProcess {
+    fn start(spec: &EngineSpec) -> Result<EngineProcess, String> {
+        let limits = spec.limits();
+        let mut program = PathBuf::from(&spec.command[0]);
+
+        //A relative path would be looked up from the new working directory
+        let work_dir = if limits.isolate {
+            if program.components().count() > 1 {
+                program = fs::canonicalize(&program).map_err(|e| format!("Could not find {:?}: {}", program, e))?;
+            }
+
+            let file_name: String = spec.name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+            let dir = std::env::temp_dir().join(format!(
+                "chessarena-{}-{}-{}",
+                file_name,
+                std::process::id(),
+                WORK_DIRS.fetch_add(1, Ordering::Relaxed)
+            ));
+
+            fs::create_dir_all(&dir).map_err(|e| format!("Could not create {:?}: {}", dir, e))?;
+            Some(dir)
+        } else {
+            None
+        };
+
+        //taskset starts the engine in its own place, so the engine keeps its pid
+        let mut command = match &limits.cores {
+            Some(cores) => {
+                let mut command = Command::new("taskset");
+                command.arg("-c").arg(cores).arg(&program);
+                command
+            }
+            None => Command::new(&program),
+        };
+
+        if let Some(dir) = &work_dir {
+            command.current_dir(dir);
+        }
+
+        let mut child = command
+            .args(&spec.command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match &limits.cores {
+                Some(_) => format!("{} (limiting cores needs taskset)", e),
+                None => e.to_string(),
+            })?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        let child = Arc::new(Mutex::new(child));
+        let dead = Arc::new(AtomicBool::new(false));
+
+        let log = Arc::new(EngineLog::open(&spec.name, &spec.command));
+
+        if let Some(limit) = limits.memory_mb {
+            watch_memory(child.clone(), dead.clone(), log.clone(), limit);
+        }
+        let (sender, lines) = channel();
+
+        let stdout_log = log.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                stdout_log.record(Stream::Stdout, &line);
+
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        //Only logged, engines print their warnings and crashes here
+        let stderr_log = log.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                stderr_log.record(Stream::Stderr, &line);
+            }
+        });
+
+        let stdin = Arc::new(Mutex::new(stdin));
+
+        let mut running = RUNNING.lock().unwrap();
+        running.retain(|engine| engine.child.strong_count() > 0);
+        running.push(RunningEngine {
+            child: Arc::downgrade(&child),
+            stdin: Arc::downgrade(&stdin),
+            log: Arc::downgrade(&log),
+            dead: dead.clone(),
+            work_dir: work_dir.clone(),
+        });
+
+        Ok(EngineProcess {
+            name: spec.name.clone(),
+            child,
+            stdin,
+            lines,
+            log,
+
+            limits,
+            dead,
+            work_dir,
+        })
+    }
+
+    fn dead(&self) -> bool {
+        self.dead.load(Ordering::Relaxed)
+    }
+
+    //Killed, or exited by itself
+    fn crashed(&self) -> bool {
+        self.dead() || matches!(self.child.lock().unwrap().try_wait(), Ok(Some(_)))
+    }
+
+    //Stand-in for a reply that isn't a move at all. It is never legal, so the engine forfeits as for an illegal move
+    fn no_move(&self, why: &str) -> ChessMove {
+        self.log.record(Stream::Arena, why);
+        ChessMove::default()
+    }
+
+    fn kill(&mut self, reason: &str) {
+        println!("Killing {}: {}", self.name, reason);
+        self.log.record(Stream::Arena, &format!("Killed: {}", reason));
+
+        self.dead.store(true, Ordering::Relaxed);
+        let _ = self.child.lock().unwrap().kill();
+    }
+
+    fn send(&mut self, command: &str) {
+        if self.dead() {
+            return;
+        }
+
+        self.log.record(Stream::Sent, command);
+
+        if let Err(e) = writeln!(self.stdin.lock().unwrap(), "{}", command) {
+            println!("Could not send '{}' to {}: {}", command, self.name, e);
+        }
+    }
+
+    //Returns the first line for which `handler` returns a value, or None if the engine took too long or exited
+    fn wait_for<T>(&mut self, timeout: Duration, mut handler: impl FnMut(&str) -> Option<T>) -> Option<T> {
+        if self.dead() {
+            return None;
+        }
+
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let line = self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())).ok()?;
+
+            if let Some(res) = handler(line.trim()) {
+                return Some(res);
+            }
+        }
+    }
+
+    //Waits for the answer to a search of `think` long. An engine with a timeout that doesn't answer in time is killed,
+    //otherwise it gets `default` before it is given up on. Once the search is told to stop the engine is sent `stop`,
+    //its command for moving straight away, and is killed if it still hasn't answered STOP_GRACE later
+    fn wait_for_reply<T>(&mut self, think: Duration, default: Duration, stop: &str, mut handler: impl FnMut(&str) -> Option<T>) -> Option<T> {
+        let started = Instant::now();
+        let mut deadline = started + self.limits.timeout.map_or(default, |grace| think + grace);
+        let mut stop_sent = false;
+
+        let reply = loop {
+            let wait = deadline.saturating_duration_since(Instant::now()).min(STOP_POLL_INTERVAL);
+
+            if let Some(reply) = self.wait_for(wait, &mut handler) {
+                break Some(reply);
+            }
+
+            if Instant::now() >= deadline || self.crashed() {
+                break None;
+            }
+
+            if !stop_sent && stop_requested() {
+                self.send(stop);
+                stop_sent = true;
+                deadline = deadline.min(Instant::now() + STOP_GRACE);
+            }
+        };
+
+        if reply.is_none() && (self.limits.timeout.is_some() || stop_sent) && !self.dead() {
+            self.kill(&format!("no answer in {:.1}s", started.elapsed().as_secs_f32()));
+        }
+
+        reply
+    }
+}
+
+impl Drop for EngineProcess {
+    fn drop(&mut self) {
+        self.send("quit");
+
+        let mut child = self.child.lock().unwrap();
+
+        //Give the engine a moment to exit by itself
+        let deadline = Instant::now() + QUIT_GRACE;
+
+        while Instant::now() < deadline && !matches!(child.try_wait(), Ok(Some(_))) {
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let _ = child.kill();
+        let _ = child.wait();
+
+        if let Some(dir) = &self.work_dir {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UciEngine {
+    process: EngineProcess,
+    //From the info lines of the last search
+    stats: SearchStats,
+}
+
+//Sends `uci` and collects the options the engine advertises until it answers
+fn uci_handshake(process: &mut EngineProcess) -> Result<Vec<UciOption>, String> {
+    let mut options = vec![];
+
+    process.send("uci");
+    process
+        .wait_for(Duration::from_secs(10), |line| {
+            if let Some(option) = UciOption::parse(line) {
+                options.push(option);
+            }
+
+            (line == "uciok").then_some(())
+        })
+        .ok_or("Engine did not answer 'uci'")?;
+
+    Ok(options)
+}
+
+impl UciEngine {
+    //Options the engine doesn't have, or values it wouldn't take, are left out
+    fn new(mut process: EngineProcess, values: &EngineOptions) -> Result<UciEngine, String> {
+        let options = uci_handshake(&mut process)?;
+
+        for (name, value) in values.iter() {
+            let Some(option) = options.iter().find(|option| option.name.eq_ignore_ascii_case(name)) else {
+                println!("{} has no option '{}'", process.name, name);
+                continue;
+            };
+
+            match option.validate(value) {
+                Some(value) => process.send(&format!("setoption name {} value {}", option.name, value)),
+                None => println!("Invalid value '{}' for {} option '{}'", value, process.name, option.name),
+            }
+        }
+
+        process.send("ucinewgame");
+        process.send("isready");
+        process
+            .wait_for(Duration::from_secs(10), |line| (line == "readyok").then_some(()))
+            .ok_or("Engine did not answer 'isready'")?;
+
+        Ok(UciEngine { process, stats: SearchStats::default() })
+    }
+}
+
+impl ChessAlgorithm for UciEngine {
+    fn reproducible(&self) -> bool {
+        false
+    }
+
+    fn crashed(&self) -> bool {
+        self.process.crashed()
+    }
+
+    //Engines clear their hash and load their networks before answering, which can take a while
+    fn prepare(&mut self, _config: &GameConfig) {
+        self.process.send("ucinewgame");
+        self.process.send("isready");
+
+        if self.process.wait_for(Duration::from_secs(30), |line| (line == "readyok").then_some(())).is_none() {
+            println!("{} did not answer 'isready'", self.process.name);
+        }
+    }
+
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        self.process.send(&format!("position fen {}", board));
+        let move_time = move_time();
+        self.process.send(&format!("go movetime {}", move_time.as_millis()));
+
+        let mut stats = SearchStats::default();
+
+        let m = self.process.wait_for_reply(move_time, move_time * 10, "stop", |line| {
+            if let Some(info) = line.strip_prefix("info ") {
+                parse_search_stats(info, &mut stats);
+            }
+
+            line.strip_prefix("bestmove ")
+                .and_then(|rest| rest.split_whitespace().next())
+                .map(|m| ChessMove::from_str(m).ok())
+        });
+
+        self.stats = stats;
+
+        match m {
+            Some(Some(m)) => m,
+            Some(None) => self.process.no_move("The bestmove is not a move"),
+            None => self.process.no_move("No bestmove in time"),
+        }
+    }
+
+    fn search_stats(&self) -> SearchStats {
+        self.stats
+    }
+}
+
+//Depth and nodes as the engine reports them. Engines send them on separate lines, so each is kept until a newer one
+//comes
+fn parse_search_stats(info: &str, stats: &mut SearchStats) {
+    let tokens: Vec<&str> = info.split_whitespace().collect();
+
+    for pair in tokens.windows(2) {
+        match pair[0] {
+            "depth" => stats.depth = pair[1].parse().ok().or(stats.depth),
+            "nodes" => stats.nodes = pair[1].parse().ok().or(stats.nodes),
+            _ => {}
+        }
+    }
+}
+
+//Score in pawns from the side to move's point of view and the best line from an info line
+fn parse_info(info: &str) -> Option<(f32, Vec<String>)> {
+    let tokens: Vec<&str> = info.split_whitespace().collect();
+
+    let score_idx = tokens.iter().position(|t| *t == "score")?;
+
+    let score = match (tokens.get(score_idx + 1), tokens.get(score_idx + 2)) {
+        (Some(&"cp"), Some(cp)) => cp.parse::<f32>().ok()? / 100.0,
+        //Stored the same way as in PGN imports
+        (Some(&"mate"), Some(mate)) => pgn::mate_eval(mate)?,
+        _ => return None,
+    };
+
+    let pv = match tokens.iter().position(|t| *t == "pv") {
+        Some(pv_idx) => tokens[pv_idx + 1..].iter().map(|m| m.to_string()).collect(),
+        None => vec![],
+    };
+
+    Some((score, pv))
+}
+
+//A UCI engine that analyses games for the observer instead of playing in them
+#[derive(Debug)]
+pub struct UciObserver {
+    process: EngineProcess,
+}
+
+impl EngineSpec {
+    pub fn observer(&self) -> Result<UciObserver, String> {
+        if self.protocol != Protocol::Uci {
+            return Err(String::from("Only UCI engines can observe games"));
+        }
+
+        let options = settings::get().engine_options.get(&self.name).cloned().unwrap_or_default();
+        let engine = UciEngine::new(EngineProcess::start(self)?, &options)?;
+
+        Ok(UciObserver { process: engine.process })
+    }
+}
+
+impl Observer for UciObserver {
+    fn analyse(&mut self, board: &Board, depth: u32) -> Option<Analysis> {
+        self.process.send(&format!("position fen {}", board));
+        self.process.send(&format!("go depth {}", depth));
+
+        let mut last = None;
+
+        //Only the main line counts when the engine is set up to show several
+        self.process.wait_for_reply(Duration::ZERO, OBSERVER_TIMEOUT, "stop", |line| {
+            if let Some(info) = line.strip_prefix("info ").filter(|info| !info.contains("multipv") || info.contains("multipv 1 ")) {
+                last = parse_info(info).or(last.take());
+                None
+            } else {
+                line.starts_with("bestmove").then_some(())
+            }
+        })?;
+
+        let (score, pv) = last?;
+
+        Some(Analysis {
+            board: *board,
+            eval: if board.side_to_move() == chess::Color::White { score } else { -score },
+            pv: legal_line(board, pv.iter().filter_map(|m| ChessMove::from_str(m).ok())),
+            depth,
+        })
+    }
+
+    fn max_depth(&self) -> u32 {
+        OBSERVER_MAX_DEPTH
+    }
+}
+
+//Features that change how moves and positions are sent
+#[derive(Debug, Default)]
+struct CecpFeatures {
+    usermove: bool,
+    setboard: bool,
+    san: bool,
+}
+
+#[derive(Debug)]
+pub struct CecpEngine {
+    process: EngineProcess,
+    features: CecpFeatures,
+
+    //Position the engine currently has, if it is known
+    position: Option<Board>,
+}
+
+//Splits a feature line like `feature usermove=1 myname="Some Engine"` into pairs
+fn parse_features(line: &str) -> Vec<(String, String)> {
+    let mut features = vec![];
+    let mut rest = line.trim();
+
+    while let Some((name, value)) = rest.split_once('=') {
+        let name = name.trim().to_string();
+        let value = value.trim_start();
+
+        let (value, remaining) = if let Some(quoted) = value.strip_prefix('"') {
+            quoted.split_once('"').unwrap_or((quoted, ""))
+        } else {
+            value.split_once(' ').unwrap_or((value, ""))
+        };
+
+        features.push((name, value.to_string()));
+        rest = remaining;
+    }
+
+    features
+}
+
+impl CecpEngine {
+    fn new(mut process: EngineProcess) -> Result<CecpEngine, String> {
+        let mut features = CecpFeatures::default();
+
+        process.send("xboard");
+        process.send("protover 2");
+
+        //Protocol 1 engines never send features, so only wait a little for them
+        let mut timeout = Duration::from_secs(2);
+
+        loop {
+            let done = process.wait_for(timeout, |line| {
+                let line = line.strip_prefix("feature ")?;
+
+                let mut replies = vec![];
+                let mut done = false;
+
+                for (name, value) in parse_features(line) {
+                    let enabled = value == "1";
+
+                    let accepted = match name.as_str() {
+                        "usermove" => {
+                            features.usermove = enabled;
+                            true
+                        }
+                        "setboard" => {
+                            features.setboard = enabled;
+                            true
+                        }
+                        "san" => {
+                            features.san = enabled;
+                            true
+                        }
+                        "done" => {
+                            //done=0 means the engine needs more time to start up
+                            done = enabled;
+
+                            if !enabled {
+                                timeout = Duration::from_secs(60 * 60);
+                            }
+                            true
+                        }
+                        "myname" | "ping" | "sigint" | "sigterm" | "reuse" | "analyze" | "colors" | "time" | "draw" | "variants" | "debug" | "memory" | "smp" | "name" | "playother" | "ics" | "pause" | "nps" | "exclude" | "setscore" | "highlight" | "option" | "egt" => true,
+                        _ => false,
+                    };
+
+                    replies.push(format!("{} {}", if accepted { "accepted" } else { "rejected" }, name));
+                }
+
+                Some((replies, done))
+            });
+
+            match done {
+                Some((replies, done)) => {
+                    for reply in replies {
+                        process.send(&reply);
+                    }
+
+                    if done {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        process.send("new");
+        process.send("force");
+        process.send("post");
+        process.send(&format!("st {}", MOVE_TIME.as_secs().max(1)));
+
+        Ok(CecpEngine {
+            process,
+            features,
+            position: Some(Board::default()),
+        })
+    }
+
+    fn send_move(&mut self, board: &Board, m: ChessMove) {
+        let text = if self.features.san {
+            move_to_SAN(board, m)
+        } else {
+            m.to_string()
+        };
+
+        if self.features.usermove {
+            self.process.send(&format!("usermove {}", text));
+        } else {
+            self.process.send(&text);
+        }
+    }
+
+    //Brings the engine's position in line with `board`
+    fn sync(&mut self, board: Board) -> bool {
+        if self.position == Some(board) {
+            return true;
+        }
+
+        //Usually only the opponent's move is missing
+        if let Some(position) = self.position {
+            let reply = chess::MoveGen::new_legal(&position).find(|m| position.make_move_new(*m) == board);
+
+            if let Some(m) = reply {
+                self.send_move(&position, m);
+                self.position = Some(board);
+                return true;
+            }
+        }
+
+        if self.features.setboard {
+            self.process.send("new");
+            self.process.send("force");
+            self.process.send(&format!("setboard {}", board));
+            self.position = Some(board);
+            return true;
+        }
+
+        false
+    }
+}
+
+impl ChessAlgorithm for CecpEngine {
+    fn reproducible(&self) -> bool {
+        false
+    }
+
+    fn crashed(&self) -> bool {
+        self.process.crashed()
+    }
+
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        if !self.sync(board) {
+            self.position = None;
+            return self.process.no_move("No setboard support, so the position can't be given");
+        }
+
+        let move_time = move_time();
+        self.process.send(&format!("st {}", move_time.as_secs().max(1)));
+        self.process.send("go");
+
+        let reply = self.process.wait_for_reply(move_time, move_time * 10, "?", |line| {
+            if let Some(m) = line.strip_prefix("move ") {
+                Some(Ok(m.trim().to_string()))
+            } else if line == "resign" || line.starts_with("Illegal move") || line.starts_with("Error") {
+                Some(Err(line.to_string()))
+            } else {
+                None
+            }
+        });
+
+        //Stay in force mode so the engine doesn't play on by itself
+        self.process.send("force");
+
+        let m = match reply {
+            Some(Ok(m)) => ChessMove::from_str(&m).ok().or_else(|| parse_san(&board, &m)).ok_or(format!("'{}' is not a move", m)),
+            Some(Err(message)) => Err(format!("No move: {}", message)),
+            None => Err(String::from("No move in time")),
+        };
+
+        //The engine's position is unknown after a bad reply
+        self.position = m.as_ref().ok().filter(|m| board.legal(**m)).map(|m| board.make_move_new(*m));
+
+        m.unwrap_or_else(|why| self.process.no_move(&why))
+    }
+
+    fn game_over(&mut self, result: &str, reason: &str) {
+        self.process.send(&format!("result {} {{{}}}", result, reason));
+        self.position = None;
+    }
+}
@@ -1,25 +1,174 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::cli;
 use crate::gui::chess_display::PlayerType;
 
-use self::{chess_alg::{RandomChessAlgorithm, FirstMoveAlgorithm, AlphabeticalChessAlgorithm}, one_lookahead::SingleLookaheadEngine, evaluators::{eval_matching_colors, eval_opposite_colors, eval_pacifist}};
+use self::{endgame::EndgameBot, chess_alg::{RandomChessAlgorithm, FirstMoveAlgorithm, AlphabeticalChessAlgorithm}, one_lookahead::SingleLookaheadEngine, evaluators::{eval_matching_colors, eval_opposite_colors, eval_pacifist, named, named_penalty}};
 
 pub mod chess_alg;
 pub mod one_lookahead;
 pub mod evaluators;
+pub mod explain;
 pub mod tree_search;
+pub mod classic;
+pub mod external;
+pub mod script;
+pub mod plugin;
+pub mod weighted;
+pub mod policy;
+pub mod console;
+pub mod eval_cache;
+pub mod endgame;
+pub mod epsilon;
+pub mod draw_claim;
+pub mod relay;
+pub mod bot_preset;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+
+pub type PlayerTypeSupplier = Arc<dyn Fn(chess::Color) -> PlayerType + Send + Sync>;
+pub type BuiltinPlayerType = fn(chess::Color) -> PlayerType;
+
+//Plies the Hard preset looks past its own moves
+const HARD_DEPTH: u32 = 4;
 
-pub type PlayerTypeSupplier = fn(chess::Color) -> PlayerType;
+//Difficulty presets come first, so that people who don't know the bots can just pick one
+const PRESET_DESCRIPTIONS: [(&str, &str); 3] = [
+    ("Easy", "Plays random moves"),
+    ("Medium", "Takes the most material it can, looking one move ahead"),
+    ("Hard", "The Classic engine, searching 4 plies past its own moves"),
+];
 
-pub const ALL_PLAYER_TYPES: [(&str, PlayerTypeSupplier); 12] = [
+pub const ALL_PLAYER_TYPES: [(&str, BuiltinPlayerType); 22] = [
     ("Human", |_| {PlayerType::Human}),
+    ("Remote", |_| PlayerType::Remote),
+    ("Easy", |_| PlayerType::computer(RandomChessAlgorithm)),
+    ("Medium", |color| PlayerType::computer(SingleLookaheadEngine::with_evaluator(color, named("material", evaluators::eval_material)))),
+    ("Hard", |color| PlayerType::computer(classic::classic_engine_at_depth(color, HARD_DEPTH))),
     ("Random", |_| {PlayerType::computer(RandomChessAlgorithm)}),
-    ("Matching", |color| {PlayerType::computer(SingleLookaheadEngine::new(color, eval_matching_colors))}),
-    ("Opposite", |color| {PlayerType::computer(SingleLookaheadEngine::new(color, eval_opposite_colors))}),
-    ("Pacifist", |color| {PlayerType::computer(SingleLookaheadEngine::new(color, eval_pacifist))}),
+    ("Matching", |color| {PlayerType::computer(SingleLookaheadEngine::with_evaluator(color, named("pieces on their own color", eval_matching_colors)))}),
+    ("Opposite", |color| {PlayerType::computer(SingleLookaheadEngine::with_evaluator(color, named("pieces on the other color", eval_opposite_colors)))}),
+    ("Pacifist", |color| {PlayerType::computer(SingleLookaheadEngine::with_evaluator(color, named("opponent's material", eval_pacifist)))}),
     ("First", |_| PlayerType::computer(FirstMoveAlgorithm)),
     ("Alphabetical", |_| PlayerType::computer(AlphabeticalChessAlgorithm)),
-    ("Huddle", |color| PlayerType::computer(SingleLookaheadEngine::new(color, evaluators::eval_huddle))),
-    ("Swarm", |color| PlayerType::computer(SingleLookaheadEngine::new(color, evaluators::eval_swarm))),
-    ("Generous", |color| PlayerType::computer(SingleLookaheadEngine::new(color, evaluators::eval_generous))),
-    ("I Insist 2", |color| PlayerType::computer(SingleLookaheadEngine::new(color, evaluators::eval_insist_2))),
-    ("I Insist 3", |color| PlayerType::computer(SingleLookaheadEngine::new(color, evaluators::eval_insist_3))),
-];
\ No newline at end of file
+    ("Huddle", |color| PlayerType::computer(SingleLookaheadEngine::with_evaluator(color, named_penalty("distance to their own king", evaluators::eval_huddle)))),
+    ("Swarm", |color| PlayerType::computer(SingleLookaheadEngine::with_evaluator(color, named_penalty("king distance", evaluators::eval_swarm)))),
+    ("Stay home", |color| PlayerType::computer(SingleLookaheadEngine::with_evaluator(color, named("pieces in their own half", evaluators::eval_stay_home)))),
+    ("Invade", |color| PlayerType::computer(SingleLookaheadEngine::with_evaluator(color, named("pieces in the enemy half", evaluators::eval_invade)))),
+    ("Generous", |color| PlayerType::computer(SingleLookaheadEngine::with_evaluator(color, named("material offered", evaluators::eval_generous)))),
+    ("I Insist 2", |color| PlayerType::computer(SingleLookaheadEngine::with_evaluator(color, named("captures offered", evaluators::eval_insist_2)))),
+    ("I Insist 3", |color| PlayerType::computer(SingleLookaheadEngine::with_evaluator(color, named("captures offered", evaluators::eval_insist_3)))),
+    ("Classic", |color| PlayerType::computer(classic::classic_engine(color))),
+    ("Endgame Random", |_| PlayerType::computer(EndgameBot::new(RandomChessAlgorithm))),
+    ("Endgame Classic", |color| PlayerType::computer(EndgameBot::new(classic::classic_engine(color)))),
+    (relay::NAME, relay::player_type),
+];
+
+static PLAYER_TYPES: OnceLock<Vec<(String, PlayerTypeSupplier)>> = OnceLock::new();
+
+//Built in player types followed by the external engines given on the command line, plugins, scripted bots,
+//evolved weights, models trained on PGN databases and ONNX models
+pub fn player_types() -> &'static [(String, PlayerTypeSupplier)] {
+    PLAYER_TYPES.get_or_init(|| {
+        let mut types: Vec<(String, PlayerTypeSupplier)> = ALL_PLAYER_TYPES
+            .iter()
+            .map(|(name, supplier)| (name.to_string(), Arc::new(*supplier) as PlayerTypeSupplier))
+            .collect();
+
+        for engine in cli::args().engines.iter() {
+            let engine = engine.clone();
+            types.push((engine.name.clone(), Arc::new(move |_| engine.player_type())));
+        }
+
+        for bot in plugin::plugins() {
+            types.push((bot.name.clone(), Arc::new(move |color| PlayerType::computer(plugin::PluginBot::new(bot, color)))));
+        }
+
+        for (name, path) in script::discover() {
+            let bot_name = name.clone();
+            types.push((name, Arc::new(move |color| PlayerType::computer(script::ScriptBot::new(&bot_name, &path, color)))));
+        }
+
+        for bot in weighted::discover() {
+            types.push((bot.name.clone(), Arc::new(move |color| PlayerType::computer(bot.engine(color)))));
+        }
+
+        for model in policy::discover() {
+            let model = Arc::new(model);
+            types.push((model.name.clone(), Arc::new(move |_| PlayerType::computer(policy::PolicyBot::new(model.clone())))));
+        }
+
+        //Human-like models are played the same way, sampling moves as often as people would play them
+        #[cfg(feature = "onnx")]
+        for (name, path) in onnx::human_like().into_iter().chain(onnx::discover(&cli::args().onnx_models)) {
+            let model = Arc::new(onnx::OnnxModel::new(&name, &path));
+            types.push((name, Arc::new(move |color| PlayerType::computer(onnx::OnnxBot::new(model.clone(), color)))));
+        }
+
+        //Presets bring their own options, so they are started from the bot as it is without any
+        let mut presets: Vec<(String, PlayerTypeSupplier)> = vec![];
+
+        for preset in bot_preset::presets() {
+            if types.iter().chain(presets.iter()).any(|(name, _)| name.eq_ignore_ascii_case(&preset.name)) {
+                println!("Preset {} has the name of another bot, leaving it out", preset.name);
+                continue;
+            }
+
+            let Some((_, base)) = types.iter().find(|(name, _)| *name == preset.bot) else {
+                println!("Preset {} is of {}, which isn't a bot here", preset.name, preset.bot);
+                continue;
+            };
+
+            let base = base.clone();
+            presets.push((preset.name.clone(), Arc::new(move |color| preset.player_type(&base, color))));
+        }
+
+        //Every bot plays some random moves if it was given an epsilon in the game creator, and claims draws the way it
+        //was told to there
+        types
+            .into_iter()
+            .map(|(name, supplier)| {
+                let bot_name = name.clone();
+                let supplier = move |color| {
+                    let player = epsilon::wrap(supplier(color), epsilon::saved(&bot_name));
+                    draw_claim::wrap(player, draw_claim::saved(&bot_name), color)
+                };
+
+                (name, Arc::new(supplier) as PlayerTypeSupplier)
+            })
+            .chain(presets)
+            .collect()
+    })
+}
+
+//Presets, human-like models, plugin bots and bot presets that were given one come with a description
+pub fn player_description(name: &str) -> Option<&'static str> {
+    if let Some((_, description)) = PRESET_DESCRIPTIONS.iter().find(|(preset, _)| *preset == name) {
+        return Some(description);
+    }
+
+    #[cfg(feature = "onnx")]
+    if onnx::is_human_like(name) {
+        return Some(onnx::HUMAN_LIKE_DESCRIPTION);
+    }
+
+    if let Some(preset) = bot_preset::find(name).filter(|preset| !preset.description.is_empty()) {
+        return Some(&preset.description);
+    }
+
+    plugin::plugins()
+        .iter()
+        .find(|bot| bot.name == name && !bot.description.is_empty())
+        .map(|bot| bot.description.as_str())
+}
+
+pub fn is_preset(name: &str) -> bool {
+    PRESET_DESCRIPTIONS.iter().any(|(preset, _)| *preset == name)
+}
+
+pub fn find_player_type(name: &str) -> Option<(&'static str, PlayerTypeSupplier)> {
+    player_types()
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(n, supplier)| (n.as_str(), supplier.clone()))
+}
@@ -3,11 +3,23 @@ use std::fmt::Formatter;
 use chess::{Color, Board, ChessMove};
 use rand::Rng;
 
-use super::chess_alg::{ChessAlgorithm, available_moves};
+use super::chess_alg::{ChessAlgorithm, for_each_move, make_move, with_rng};
+use super::eval_cache::EvalCache;
+use super::evaluators::Evaluator;
+use super::explain::Explanation;
 
 pub struct SingleLookaheadEngine {
     color: Color,
-    eval: Box<dyn Fn(&Board, Color) -> f32>
+    eval: Box<dyn Evaluator>,
+    cache: EvalCache,
+
+    //Kept between moves so that they don't have to be allocated every time
+    moves: Vec<ChessMove>,
+    boards: Vec<Board>,
+    scores: Vec<Option<f32>>,
+    uncached: Vec<Board>,
+
+    explanation: Option<Explanation>,
 }
 
 impl std::fmt::Debug for SingleLookaheadEngine {
@@ -18,9 +30,52 @@ impl std::fmt::Debug for SingleLookaheadEngine {
 
 impl SingleLookaheadEngine {
     pub fn new<T: Fn(&Board, Color) -> f32 + 'static>(color: Color, eval: T) -> SingleLookaheadEngine {
+        SingleLookaheadEngine::with_evaluator(color, eval)
+    }
+
+    pub fn with_evaluator<T: Evaluator + 'static>(color: Color, eval: T) -> SingleLookaheadEngine {
         SingleLookaheadEngine {
             color,
-            eval: Box::new(eval)
+            eval: Box::new(eval),
+            cache: EvalCache::new(),
+
+            moves: Vec::new(),
+            boards: Vec::new(),
+            scores: Vec::new(),
+            uncached: Vec::new(),
+
+            explanation: None,
+        }
+    }
+
+    //Scores every board in `self.boards`, asking the evaluator about all of the ones that aren't cached at once
+    fn score_boards(&mut self) {
+        self.scores.clear();
+        self.uncached.clear();
+
+        for board in self.boards.iter() {
+            let score = self.cache.get(board);
+
+            if score.is_none() {
+                self.uncached.push(*board);
+            }
+
+            self.scores.push(score);
+        }
+
+        if self.uncached.is_empty() {
+            return;
+        }
+
+        let mut evaluated = self.eval.evaluate_batch(&self.uncached, self.color).into_iter();
+
+        for (board, score) in self.boards.iter().zip(self.scores.iter_mut()) {
+            if score.is_none() {
+                let eval = evaluated.next().unwrap();
+
+                self.cache.insert(board, eval);
+                *score = Some(eval);
+            }
         }
     }
 }
@@ -29,25 +84,53 @@ unsafe impl Send for SingleLookaheadEngine {}
 
 impl ChessAlgorithm for SingleLookaheadEngine {
     fn get_move(&mut self, board: Board) -> ChessMove {
+        self.moves.clear();
+        self.boards.clear();
+
+        for_each_move(&board, |m| {
+            self.moves.push(m);
+            self.boards.push(make_move(&board, m));
+        });
+
+        self.score_boards();
+
         let mut best_score = f32::NEG_INFINITY;
         let mut best_moves = Vec::new();
 
-        for m in available_moves(&board) {
-            let res = board.make_move_new(m);
-
-            let score = (self.eval)(&res, self.color);
+        for (m, score) in self.moves.iter().zip(self.scores.iter()) {
+            let score = score.unwrap();
 
             if (score - best_score).abs() < 0.0001 {
-                best_moves.push(m);
+                best_moves.push(*m);
             } else if score > best_score {
                 best_score = score;
                 best_moves.clear();
-                best_moves.push(m);
+                best_moves.push(*m);
             }
         }
 
-        let mut rng = rand::thread_rng();
+        let chosen = best_moves[with_rng(|rng| rng.gen_range(0..best_moves.len()))];
+
+        let scores: Vec<(ChessMove, f32)> = self.moves.iter().copied().zip(self.scores.iter().map(|score| score.unwrap())).collect();
+        self.explanation = Some(Explanation::new(self.eval.as_ref(), self.color, board, chosen, &scores, 0));
+
+        chosen
+    }
+
+    fn new_game(&mut self) {
+        self.cache.clear();
+        self.explanation = None;
+    }
+
+    fn info(&self) -> Option<String> {
+        Some(self.cache.stats())
+    }
+
+    fn explain(&self) -> Option<Explanation> {
+        self.explanation.clone()
+    }
 
-        best_moves[rng.gen_range(0..best_moves.len())]
+    fn evaluate(&self, board: &Board) -> Option<f32> {
+        Some(self.eval.evaluate(board, self.color))
     }
 }
\ No newline at end of file
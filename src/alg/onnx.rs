@@ -0,0 +1,307 @@
+use std::fmt::Formatter;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use chess::{Board, ChessMove, Color, Piece, ALL_PIECES};
+use ort::session::Session;
+use ort::value::Tensor;
+use rand::Rng;
+
+use super::chess_alg::{available_moves, with_rng, ChessAlgorithm, GameConfig, RandomChessAlgorithm};
+use super::evaluators::Evaluator;
+use super::one_lookahead::SingleLookaheadEngine;
+
+pub const ONNX_DIR: &str = "res/onnx";
+
+//Models trained on the games of people around a rating, like Maia, converted to the same input and policy as other
+//models. Each one is named after its rating, like 1500.onnx
+pub const HUMAN_LIKE_DIR: &str = "res/onnx/human-like";
+pub const HUMAN_LIKE_RATINGS: [u32; 3] = [1100, 1500, 1900];
+pub const HUMAN_LIKE_DESCRIPTION: &str = "Plays the moves people around this rating play, mistakes included, from a model trained on their games";
+
+//Planes of 8x8 the board is encoded into: the side to move's pieces, the other side's pieces, both sides' castling
+//rights, the en passant square and one plane of ones
+pub const INPUT_PLANES: usize = 18;
+
+//One policy logit for every pair of source and destination squares
+pub const POLICY_SIZE: usize = 64 * 64;
+
+//Underpromotions share their logit with the queen promotion and are only played when nothing else is likely
+const UNDERPROMOTION_PENALTY: f32 = 5.0;
+
+//Name and path of every model in the onnx folder and given with --onnx
+pub fn discover(extra: &[PathBuf]) -> Vec<(String, PathBuf)> {
+    let mut models: Vec<PathBuf> = fs::read_dir(ONNX_DIR)
+        .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+
+    models.retain(|path| path.extension().is_some_and(|ext| ext == "onnx"));
+    models.sort();
+    models.extend(extra.iter().cloned());
+
+    models
+        .into_iter()
+        .map(|path| (path.file_stem().unwrap_or_default().to_string_lossy().to_string(), path))
+        .collect()
+}
+
+//Name and path of the human-like models that are there
+pub fn human_like() -> Vec<(String, PathBuf)> {
+    HUMAN_LIKE_RATINGS
+        .iter()
+        .map(|rating| (format!("Human-like {}", rating), Path::new(HUMAN_LIKE_DIR).join(format!("{}.onnx", rating))))
+        .filter(|(_, path)| path.is_file())
+        .collect()
+}
+
+pub fn is_human_like(name: &str) -> bool {
+    HUMAN_LIKE_RATINGS.iter().any(|rating| name == format!("Human-like {}", rating))
+}
+
+//Squares are mirrored for black so that every position is seen from the side to move, with its pieces at the bottom
+fn relative_index(square: chess::Square, color: Color) -> usize {
+    match color {
+        Color::White => square.to_index(),
+        Color::Black => square.to_index() ^ 56,
+    }
+}
+
+//Fills `input` with `INPUT_PLANES` planes of 64 squares for `board`
+pub fn encode(board: &Board, input: &mut Vec<f32>) {
+    let start = input.len();
+    input.resize(start + INPUT_PLANES * 64, 0.0);
+
+    let planes = &mut input[start..];
+    let us = board.side_to_move();
+    let mut set = |plane: usize, square: usize| planes[plane * 64 + square] = 1.0;
+
+    for (side, color) in [us, !us].into_iter().enumerate() {
+        for piece in ALL_PIECES {
+            for square in *board.pieces(piece) & *board.color_combined(color) {
+                set(side * 6 + piece.to_index(), relative_index(square, us));
+            }
+        }
+
+        let rights = board.castle_rights(color);
+
+        for (i, has) in [rights.has_kingside(), rights.has_queenside()].into_iter().enumerate() {
+            if has {
+                (0..64).for_each(|square| set(12 + side * 2 + i, square));
+            }
+        }
+    }
+
+    if let Some(square) = board.en_passant() {
+        set(16, relative_index(square, us));
+    }
+
+    (0..64).for_each(|square| set(17, square));
+}
+
+//Where a move's logit is in the policy head
+pub fn policy_index(board: &Board, m: ChessMove) -> usize {
+    let us = board.side_to_move();
+
+    relative_index(m.get_source(), us) * 64 + relative_index(m.get_dest(), us)
+}
+
+//What a model made of a batch of positions. Either head can be missing
+struct ModelOutput {
+    policy: Option<Vec<f32>>,
+    value: Option<Vec<f32>>,
+}
+
+//A model is loaded the first time a bot using it moves, and then shared by every bot using it
+pub struct OnnxModel {
+    pub name: String,
+    path: PathBuf,
+    session: OnceLock<Result<Mutex<Session>, String>>,
+}
+
+impl std::fmt::Debug for OnnxModel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OnnxModel {{ name: {:?}, path: {:?} }}", self.name, self.path)
+    }
+}
+
+impl OnnxModel {
+    pub fn new(name: &str, path: &Path) -> OnnxModel {
+        OnnxModel {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            session: OnceLock::new(),
+        }
+    }
+
+    fn session(&self) -> Result<&Mutex<Session>, String> {
+        self.session
+            .get_or_init(|| {
+                Session::builder()
+                    .and_then(|mut builder| builder.commit_from_file(&self.path))
+                    .map(Mutex::new)
+                    .map_err(|e| format!("Could not load ONNX model {:?}: {}", self.path, e))
+            })
+            .as_ref()
+            .map_err(|e| e.clone())
+    }
+
+    //Runs the model on every board at once. Outputs named policy and value are used, and otherwise whichever
+    //output has `POLICY_SIZE` numbers per board is the policy and one with a single number is the value
+    fn run(&self, boards: &[Board]) -> Result<ModelOutput, String> {
+        let mut input = Vec::with_capacity(boards.len() * INPUT_PLANES * 64);
+
+        for board in boards.iter() {
+            encode(board, &mut input);
+        }
+
+        let tensor = Tensor::from_array(([boards.len(), INPUT_PLANES, 8, 8], input)).map_err(|e| e.to_string())?;
+
+        let mut session = self.session()?.lock().unwrap();
+        let outputs = session.run(ort::inputs![tensor]).map_err(|e| format!("{} failed: {}", self.name, e))?;
+
+        let mut result = ModelOutput { policy: None, value: None };
+
+        for (name, value) in outputs.iter() {
+            let Ok((_, data)) = value.try_extract_tensor::<f32>() else {
+                continue;
+            };
+
+            let per_board = data.len() / boards.len().max(1);
+
+            if name == "policy" || (per_board == POLICY_SIZE && result.policy.is_none()) {
+                result.policy = Some(data.to_vec());
+            } else if name == "value" || (per_board == 1 && result.value.is_none()) {
+                result.value = Some(data.to_vec());
+            }
+        }
+
+        if result.policy.is_none() && result.value.is_none() {
+            return Err(format!("{} has neither a policy output of {} numbers nor a value output", self.name, POLICY_SIZE));
+        }
+
+        Ok(result)
+    }
+}
+
+//Scores positions with the value head, which is from the side to move's point of view
+pub struct OnnxEvaluator {
+    model: Arc<OnnxModel>,
+}
+
+impl Evaluator for OnnxEvaluator {
+    fn evaluate(&self, board: &Board, color: Color) -> f32 {
+        self.evaluate_batch(std::slice::from_ref(board), color)[0]
+    }
+
+    fn evaluate_batch(&self, boards: &[Board], color: Color) -> Vec<f32> {
+        let values = match self.model.run(boards) {
+            Ok(ModelOutput { value: Some(values), .. }) => values,
+            Ok(_) => vec![0.0; boards.len()],
+            Err(e) => {
+                println!("{}", e);
+                vec![0.0; boards.len()]
+            }
+        };
+
+        boards
+            .iter()
+            .zip(values)
+            .map(|(board, value)| if board.side_to_move() == color { value } else { -value })
+            .collect()
+    }
+}
+
+//Plays from the policy head, picking moves as often as the model thinks they'd be played. Models with only a value
+//head look one move ahead instead, scoring every move in one batch
+pub struct OnnxBot {
+    model: Arc<OnnxModel>,
+    lookahead: SingleLookaheadEngine,
+}
+
+impl std::fmt::Debug for OnnxBot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OnnxBot {{ model: {:?} }}", self.model.name)
+    }
+}
+
+unsafe impl Send for OnnxBot {}
+
+impl OnnxBot {
+    pub fn new(model: Arc<OnnxModel>, color: Color) -> OnnxBot {
+        OnnxBot {
+            lookahead: SingleLookaheadEngine::with_evaluator(color, OnnxEvaluator { model: model.clone() }),
+            model,
+        }
+    }
+
+    fn policy_move(&self, board: &Board, policy: &[f32]) -> Option<ChessMove> {
+        let moves = available_moves(board);
+
+        //Softmax over the legal moves only. Drops and other moves the policy has no logit for are left out
+        let logits: Vec<(ChessMove, f32)> = moves
+            .iter()
+            .filter(|m| m.get_source() != m.get_dest())
+            .map(|m| {
+                let logit = policy.get(policy_index(board, *m)).copied().unwrap_or(f32::NEG_INFINITY);
+
+                match m.get_promotion() {
+                    Some(Piece::Queen) | None => (*m, logit),
+                    Some(_) => (*m, logit - UNDERPROMOTION_PENALTY),
+                }
+            })
+            .collect();
+
+        let max = logits.iter().map(|(_, logit)| *logit).fold(f32::NEG_INFINITY, f32::max);
+
+        if !max.is_finite() {
+            return None;
+        }
+
+        let weights: Vec<f32> = logits.iter().map(|(_, logit)| (logit - max).exp()).collect();
+        let mut pick = with_rng(|rng| rng.gen::<f32>()) * weights.iter().sum::<f32>();
+
+        for ((m, _), weight) in logits.iter().zip(weights) {
+            pick -= weight;
+
+            if pick <= 0.0 {
+                return Some(*m);
+            }
+        }
+
+        logits.last().map(|(m, _)| *m)
+    }
+}
+
+impl ChessAlgorithm for OnnxBot {
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        match self.model.run(&[board]) {
+            Ok(ModelOutput { policy: Some(policy), .. }) => {
+                if let Some(m) = self.policy_move(&board, &policy) {
+                    return m;
+                }
+            }
+
+            Ok(_) => return self.lookahead.get_move(board),
+
+            Err(e) => println!("{}", e),
+        }
+
+        RandomChessAlgorithm.get_move(board)
+    }
+
+    fn new_game(&mut self) {
+        self.lookahead.new_game();
+    }
+
+    //Loads the model before the first move rather than on it
+    fn prepare(&mut self, _config: &GameConfig) {
+        if let Err(e) = self.model.session() {
+            println!("{}", e);
+        }
+    }
+
+    fn info(&self) -> Option<String> {
+        self.lookahead.info()
+    }
+}
@@ -0,0 +1,192 @@
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use chess::{Board, ChessMove, Color};
+use libloading::Library;
+
+use crate::cli;
+use crate::variant;
+
+use super::chess_alg::ChessAlgorithm;
+use super::console::{EngineLog, Stream};
+
+//Plugins are dynamic libraries exporting
+//  extern "C" fn chessarena_plugin() -> *const PluginInfo
+//Only C types cross the boundary so plugins can be built with any Rust version, or another language entirely.
+//Positions are passed as FEN and moves are returned in UCI notation
+pub const ENTRY_POINT: &[u8] = b"chessarena_plugin";
+pub const ABI_VERSION: u32 = 1;
+
+#[repr(C)]
+pub struct PluginInfo {
+    //Must be ABI_VERSION
+    pub abi_version: u32,
+    pub bot_count: usize,
+    pub bots: *const PluginBotInfo,
+}
+
+#[repr(C)]
+pub struct PluginBotInfo {
+    pub name: *const c_char,
+    pub description: *const c_char,
+    //Creates a bot playing `color`, 0 for white and 1 for black
+    pub create: unsafe extern "C" fn(color: u8) -> *mut c_void,
+    //Writes the chosen move as a NUL terminated string of at most `out_len` bytes. Returns false if it couldn't pick one
+    pub get_move: unsafe extern "C" fn(bot: *mut c_void, fen: *const c_char, out: *mut c_char, out_len: usize) -> bool,
+    pub destroy: unsafe extern "C" fn(bot: *mut c_void),
+}
+
+#[derive(Debug, Clone)]
+pub struct PluginBotEntry {
+    pub name: String,
+    pub description: String,
+
+    create: unsafe extern "C" fn(color: u8) -> *mut c_void,
+    get_move: unsafe extern "C" fn(bot: *mut c_void, fen: *const c_char, out: *mut c_char, out_len: usize) -> bool,
+    destroy: unsafe extern "C" fn(bot: *mut c_void),
+}
+
+static PLUGINS: OnceLock<Vec<PluginBotEntry>> = OnceLock::new();
+
+unsafe fn read_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().to_string()
+    }
+}
+
+fn load_library(path: &Path) -> Result<Vec<PluginBotEntry>, String> {
+    unsafe {
+        let library = Library::new(path).map_err(|e| e.to_string())?;
+
+        let entry = library
+            .get::<unsafe extern "C" fn() -> *const PluginInfo>(ENTRY_POINT)
+            .map_err(|e| e.to_string())?;
+
+        let info = entry();
+
+        if info.is_null() {
+            return Err(String::from("Plugin returned no info"));
+        }
+
+        let info = &*info;
+
+        if info.abi_version != ABI_VERSION {
+            return Err(format!("Plugin uses ABI version {} but {} is required", info.abi_version, ABI_VERSION));
+        }
+
+        let bots = if info.bot_count == 0 {
+            vec![]
+        } else {
+            std::slice::from_raw_parts(info.bots, info.bot_count)
+                .iter()
+                .map(|bot| PluginBotEntry {
+                    name: read_string(bot.name),
+                    description: read_string(bot.description),
+                    create: bot.create,
+                    get_move: bot.get_move,
+                    destroy: bot.destroy,
+                })
+                .collect()
+        };
+
+        //The bots' functions live in the library, so it stays loaded for the rest of the program
+        std::mem::forget(library);
+
+        Ok(bots)
+    }
+}
+
+//Every bot from the libraries in the plugin folder, loaded the first time this is called
+pub fn plugins() -> &'static [PluginBotEntry] {
+    PLUGINS.get_or_init(|| {
+        let dir = &cli::args().plugin_dir;
+
+        let Ok(entries) = fs::read_dir(dir) else {
+            return vec![];
+        };
+
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION))
+            .collect();
+
+        paths.sort();
+
+        let mut bots = vec![];
+
+        for path in paths {
+            match load_library(&path) {
+                Ok(loaded) => {
+                    for bot in loaded.into_iter().filter(|bot| !bot.name.is_empty()) {
+                        println!("Loaded plugin bot {} from {:?}", bot.name, path);
+                        bots.push(bot);
+                    }
+                }
+                Err(e) => println!("Could not load plugin {:?}: {}", path, e),
+            }
+        }
+
+        bots
+    })
+}
+
+#[derive(Debug)]
+pub struct PluginBot {
+    entry: PluginBotEntry,
+    instance: *mut c_void,
+    log: EngineLog,
+}
+
+//Plugins are only ever called from one thread at a time
+unsafe impl Send for PluginBot {}
+
+impl PluginBot {
+    pub fn new(entry: &PluginBotEntry, color: Color) -> PluginBot {
+        let instance = unsafe { (entry.create)(color.to_index() as u8) };
+
+        PluginBot {
+            entry: entry.clone(),
+            instance,
+            log: EngineLog::open(&entry.name, &[String::from("plugin")]),
+        }
+    }
+}
+
+impl ChessAlgorithm for PluginBot {
+    fn reproducible(&self) -> bool {
+        false
+    }
+
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        const MOVE_LENGTH: usize = 16;
+
+        let fen = CString::new(board.to_string()).unwrap();
+        let mut out = [0 as c_char; MOVE_LENGTH];
+
+        let chosen = unsafe { (self.entry.get_move)(self.instance, fen.as_ptr(), out.as_mut_ptr(), MOVE_LENGTH) };
+
+        //Don't trust the plugin to have terminated the string
+        out[MOVE_LENGTH - 1] = 0;
+        let uci = unsafe { read_string(out.as_ptr()) };
+
+        //A move that isn't legal is still played, so that the plugin forfeits
+        match variant::parse_uci(&uci) {
+            Some(m) if chosen => m,
+            _ => {
+                self.log.record(Stream::Arena, &format!("No move chosen ('{}')", uci));
+                ChessMove::default()
+            }
+        }
+    }
+}
+
+impl Drop for PluginBot {
+    fn drop(&mut self) {
+        unsafe { (self.entry.destroy)(self.instance) }
+    }
+}
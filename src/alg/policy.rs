@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+use std::fmt::Formatter;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chess::{Board, ChessMove, Color, MoveGen};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::pgn::PgnGame;
+use crate::variant::{self, Variant};
+
+use super::chess_alg::{available_moves, with_rng, ChessAlgorithm};
+
+pub const POLICY_DIR: &str = "res/policies";
+
+//Moves that were never seen in the database still get picked now and then
+const PRIOR: f32 = 20.0;
+
+//Book positions seen fewer times than this are dropped to keep the file small
+const MIN_BOOK_COUNT: u32 = 2;
+
+//Index of a move by piece, source and destination, mirrored for black so that both colors share what they learn
+fn move_index(board: &Board, m: ChessMove) -> Option<usize> {
+    let piece = board.piece_on(m.get_source())?;
+
+    let flip = match board.side_to_move() {
+        Color::White => 0,
+        Color::Black => 56,
+    };
+
+    Some(piece.to_index() * 4096 + (m.get_source().to_index() ^ flip) * 64 + (m.get_dest().to_index() ^ flip))
+}
+
+//Board, side to move, castling and en passant of a FEN, without the move counters
+fn position_key(board: &Board) -> String {
+    board.to_string().split(' ').take(4).collect::<Vec<_>>().join(" ")
+}
+
+//A move prediction model learned from a PGN database. Every kind of move (piece, from and to) is scored by how often
+//it was played when it was available, and the first moves of games are also remembered exactly as an opening book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyModel {
+    pub name: String,
+    pub games: usize,
+    chosen: Vec<u32>,
+    available: Vec<u32>,
+    //Position key to the moves played from it in UCI notation, with how often they were played
+    book: BTreeMap<String, BTreeMap<String, u32>>,
+}
+
+impl PolicyModel {
+    pub fn new(name: &str) -> PolicyModel {
+        PolicyModel {
+            name: name.to_string(),
+            games: 0,
+            chosen: vec![0; 6 * 64 * 64],
+            available: vec![0; 6 * 64 * 64],
+            book: BTreeMap::new(),
+        }
+    }
+
+    //Only standard games can be learned from
+    pub fn learn(&mut self, game: &PgnGame, book_plies: usize) -> bool {
+        if game.variant() != Variant::Standard {
+            return false;
+        }
+
+        let mut board = game.start;
+
+        for (ply, m) in game.moves.iter().enumerate() {
+            for legal in MoveGen::new_legal(&board) {
+                if let Some(idx) = move_index(&board, legal) {
+                    self.available[idx] += 1;
+                }
+            }
+
+            if let Some(idx) = move_index(&board, *m) {
+                self.chosen[idx] += 1;
+            }
+
+            if ply < book_plies {
+                *self.book.entry(position_key(&board)).or_default().entry(variant::move_to_uci(*m)).or_default() += 1;
+            }
+
+            board = board.make_move_new(*m);
+        }
+
+        self.games += 1;
+
+        true
+    }
+
+    pub fn prune_book(&mut self) {
+        self.book.retain(|_, moves| moves.values().sum::<u32>() >= MIN_BOOK_COUNT);
+    }
+
+    pub fn score(&self, board: &Board, m: ChessMove) -> f32 {
+        match move_index(board, m) {
+            Some(idx) => (self.chosen[idx] as f32 + 1.0) / (self.available[idx] as f32 + PRIOR),
+            None => 1.0 / PRIOR,
+        }
+    }
+
+    pub fn book_moves(&self, board: &Board) -> Vec<(ChessMove, u32)> {
+        self.book
+            .get(&position_key(board))
+            .map(|moves| moves.iter().filter_map(|(uci, count)| Some((variant::parse_uci(uci)?, *count))).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn load(path: &Path) -> Result<PolicyModel, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+        serde_json::from_str(&text).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, String> {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+
+        let path = dir.join(format!("{}.json", self.name));
+        fs::write(&path, serde_json::to_string(self).unwrap()).map_err(|e| e.to_string())?;
+
+        Ok(path)
+    }
+}
+
+//Every model in the policies folder, sorted by name
+pub fn discover() -> Vec<PolicyModel> {
+    let Ok(entries) = fs::read_dir(POLICY_DIR) else {
+        return vec![];
+    };
+
+    let mut models: Vec<PolicyModel> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| match PolicyModel::load(&path) {
+            Ok(model) => Some(model),
+            Err(e) => {
+                println!("Could not load policy {:?}: {}", path, e);
+                None
+            }
+        })
+        .collect();
+
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+
+    models
+}
+
+//Picks a random item, with chances proportional to the weights
+fn weighted_choice<T: Copy>(items: &[(T, f32)]) -> T {
+    let total: f32 = items.iter().map(|(_, w)| w).sum();
+    let mut pick = with_rng(|rng| rng.gen_range(0.0..total.max(f32::MIN_POSITIVE)));
+
+    for (item, weight) in items {
+        if pick < *weight {
+            return *item;
+        }
+
+        pick -= weight;
+    }
+
+    items[items.len() - 1].0
+}
+
+//Plays from the opening book while it can, then picks moves the database liked. Squaring the scores makes the
+//popular moves win more often than they would by sampling alone
+pub struct PolicyBot {
+    model: Arc<PolicyModel>,
+}
+
+impl std::fmt::Debug for PolicyBot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PolicyBot {{ model: {:?} }}", self.model.name)
+    }
+}
+
+impl PolicyBot {
+    pub fn new(model: Arc<PolicyModel>) -> PolicyBot {
+        PolicyBot { model }
+    }
+}
+
+impl ChessAlgorithm for PolicyBot {
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        let moves = available_moves(&board);
+
+        let book: Vec<(ChessMove, f32)> = self
+            .model
+            .book_moves(&board)
+            .into_iter()
+            .filter(|(m, _)| moves.contains(m))
+            .map(|(m, count)| (m, count as f32))
+            .collect();
+
+        if !book.is_empty() {
+            return weighted_choice(&book);
+        }
+
+        let scored: Vec<(ChessMove, f32)> = moves.iter().map(|m| (*m, self.model.score(&board, *m).powi(2))).collect();
+
+        weighted_choice(&scored)
+    }
+}
+
+//Splits a PGN database into games and learns from every one that parses
+pub fn train(pgn: &Path, name: &str, book_plies: usize) -> Result<PathBuf, String> {
+    let text = fs::read_to_string(pgn).map_err(|e| format!("Could not read {:?}: {}", pgn, e))?;
+
+    let mut model = PolicyModel::new(name);
+    let mut skipped = 0;
+
+    let mut chunks = vec![];
+    let mut current = String::new();
+
+    for line in text.lines() {
+        if line.starts_with("[Event ") && !current.trim().is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+
+        current.push_str(line);
+        current.push('\n');
+    }
+
+    chunks.push(current);
+
+    for chunk in chunks {
+        match PgnGame::parse_all(&chunk) {
+            Ok(games) => {
+                for game in games {
+                    if !model.learn(&game, book_plies) {
+                        skipped += 1;
+                    } else if model.games.is_multiple_of(1000) {
+                        println!("Learned from {} games", model.games);
+                    }
+                }
+            }
+            Err(e) => {
+                println!("Skipping game: {}", e);
+                skipped += 1;
+            }
+        }
+    }
+
+    if model.games == 0 {
+        return Err(format!("No standard games found in {:?}", pgn));
+    }
+
+    model.prune_book();
+
+    println!(
+        "Learned from {} games ({} skipped), {} book positions",
+        model.games,
+        skipped,
+        model.book.len()
+    );
+
+    model.save(Path::new(POLICY_DIR))
+}
@@ -0,0 +1,182 @@
+use std::sync::{Arc, Mutex};
+
+use chess::{Board, ChessMove};
+
+use crate::alg::chess_alg::{ChessAlgorithm, GameConfig, RandomChessAlgorithm, SearchStats};
+use crate::alg::explain::Explanation;
+use crate::alg::external::{EngineOptions, UciOption, UciOptionKind};
+use crate::alg::{find_player_type, player_types};
+use crate::gui::chess_display::PlayerType;
+use crate::settings;
+
+pub const NAME: &str = "Relay";
+
+//Members are picked in the same options editor UCI engines use, and saved the same way
+const MEMBERS: usize = 4;
+const SWITCH_EVERY: &str = "Switch every";
+const NONE: &str = "None";
+
+fn member_option(i: usize) -> String {
+    format!("Bot {}", i + 1)
+}
+
+//Bots that can join a relay. People and relays can't
+fn member_choices() -> Vec<String> {
+    player_types()
+        .iter()
+        .map(|(name, _)| name.clone())
+        .filter(|name| !["Human", "Remote", NAME].contains(&name.as_str()))
+        .collect()
+}
+
+pub fn options() -> Vec<UciOption> {
+    let choices = member_choices();
+
+    let mut options: Vec<UciOption> = (0..MEMBERS)
+        .map(|i| {
+            //Two bots by default, and the rest can be left out
+            let (default, choices) = match i {
+                0 => (String::from("Random"), choices.clone()),
+                1 => (String::from("Classic"), choices.clone()),
+                _ => (String::from(NONE), [vec![String::from(NONE)], choices.clone()].concat()),
+            };
+
+            UciOption { name: member_option(i), kind: UciOptionKind::Combo { default, choices } }
+        })
+        .collect();
+
+    options.push(UciOption { name: String::from(SWITCH_EVERY), kind: UciOptionKind::Spin { default: 1, min: 1, max: 50 } });
+    options
+}
+
+//Bots take turns at playing a side's moves, each playing `every` moves in a row
+#[derive(Debug)]
+pub struct RelayBot {
+    members: Vec<(String, Arc<Mutex<dyn ChessAlgorithm>>)>,
+    every: usize,
+    moves_played: usize,
+    //Member that played the last move
+    last: Option<usize>,
+}
+
+impl RelayBot {
+    fn current(&self) -> usize {
+        (self.moves_played / self.every) % self.members.len()
+    }
+
+    fn each(&self, f: impl Fn(&mut dyn ChessAlgorithm)) {
+        for (_, member) in &self.members {
+            f(&mut *member.lock().unwrap());
+        }
+    }
+}
+
+//Options that are missing or no longer valid fall back to their defaults
+pub fn relay_bot(color: chess::Color, values: &EngineOptions) -> RelayBot {
+    let options = options();
+    let value = |option: &UciOption| values.get(&option.name).and_then(|v| option.validate(v)).unwrap_or_else(|| option.default_value());
+
+    let mut members = vec![];
+
+    for option in &options[..MEMBERS] {
+        let name = value(option);
+
+        if let Some((name, supplier)) = find_player_type(&name) {
+            if let PlayerType::Computer(engine) = supplier(color) {
+                members.push((name.to_string(), engine));
+            }
+        }
+    }
+
+    if members.is_empty() {
+        members.push((String::from("Random"), Arc::new(Mutex::new(RandomChessAlgorithm)) as Arc<Mutex<dyn ChessAlgorithm>>));
+    }
+
+    let every = value(&options[MEMBERS]).parse().unwrap_or(1);
+
+    RelayBot { members, every, moves_played: 0, last: None }
+}
+
+//With the options saved from the game creator, so tournaments and the command line play the same relay
+pub fn player_type(color: chess::Color) -> PlayerType {
+    let values = settings::get().engine_options.get(NAME).cloned().unwrap_or_default();
+
+    PlayerType::computer(relay_bot(color, &values))
+}
+
+impl ChessAlgorithm for RelayBot {
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        let current = self.current();
+        let m = self.members[current].1.lock().unwrap().get_move(board);
+
+        self.moves_played += 1;
+        self.last = Some(current);
+
+        m
+    }
+
+    fn depth(&self) -> Option<u32> {
+        self.members[self.current()].1.lock().unwrap().depth()
+    }
+
+    fn set_depth(&mut self, depth: u32) {
+        self.each(|member| member.set_depth(depth));
+    }
+
+    fn new_game(&mut self) {
+        self.moves_played = 0;
+        self.last = None;
+        self.each(|member| member.new_game());
+    }
+
+    fn prepare(&mut self, config: &GameConfig) {
+        self.each(|member| member.prepare(config));
+    }
+
+    fn info(&self) -> Option<String> {
+        let last = self.last?;
+        let (name, member) = &self.members[last];
+
+        let relay = format!("Relay: {} played the last move, {} plays next", name, self.members[self.current()].0);
+
+        match member.lock().unwrap().info() {
+            Some(info) => Some(format!("{}\n{}", relay, info)),
+            None => Some(relay),
+        }
+    }
+
+    fn explain(&self) -> Option<Explanation> {
+        self.members[self.last?].1.lock().unwrap().explain()
+    }
+
+    fn search_stats(&self) -> SearchStats {
+        self.last.map_or_else(SearchStats::default, |last| self.members[last].1.lock().unwrap().search_stats())
+    }
+
+    //The member due to play next decides
+    fn claim_draw(&mut self, board: &Board) -> bool {
+        self.members[self.current()].1.lock().unwrap().claim_draw(board)
+    }
+
+    fn evaluate(&self, board: &Board) -> Option<f32> {
+        self.members[self.current()].1.lock().unwrap().evaluate(board)
+    }
+
+    fn reproducible(&self) -> bool {
+        self.members.iter().all(|(_, member)| member.lock().unwrap().reproducible())
+    }
+
+    //One member that can't play is enough to lose the game
+    fn crashed(&self) -> bool {
+        self.members.iter().any(|(_, member)| member.lock().unwrap().crashed())
+    }
+
+    //Every member hears about every move, not just the ones it played, so bots that keep track of the game still can
+    fn do_move(&mut self, board: Board, chess_move: ChessMove) {
+        self.each(|member| member.do_move(board, chess_move));
+    }
+
+    fn game_over(&mut self, result: &str, reason: &str) {
+        self.each(|member| member.game_over(result, reason));
+    }
+}
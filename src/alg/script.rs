@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chess::{Board, ChessMove, Color, Piece, Square, EMPTY};
+use rand::Rng;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope, AST};
+
+use crate::util::move_to_SAN;
+use crate::variant;
+
+use super::chess_alg::{available_moves, make_move, with_rng, ChessAlgorithm, RandomChessAlgorithm};
+
+pub const SCRIPT_DIR: &str = "res/bots";
+
+//Stops scripts with infinite loops from hanging the engine thread forever
+const MAX_OPERATIONS: u64 = 50_000_000;
+
+//Name and path of every script in the bots folder
+pub fn discover() -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(SCRIPT_DIR) else {
+        return vec![];
+    };
+
+    let mut scripts: Vec<(String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+        .map(|path| (path.file_stem().unwrap().to_string_lossy().to_string(), path))
+        .collect();
+
+    scripts.sort();
+
+    scripts
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Black => "black",
+    }
+}
+
+fn script_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    engine
+        .register_type_with_name::<Board>("Board")
+        .register_get("fen", |board: &mut Board| board.to_string())
+        .register_get("turn", |board: &mut Board| color_name(board.side_to_move()).to_string())
+        .register_get("in_check", |board: &mut Board| *board.checkers() != EMPTY)
+        .register_fn("moves", |board: &mut Board| -> Array {
+            available_moves(board).into_iter().map(|m| Dynamic::from(variant::move_to_uci(m))).collect()
+        })
+        .register_fn("play", |board: &mut Board, uci: &str| -> Result<Board, Box<EvalAltResult>> {
+            match variant::parse_uci(uci) {
+                Some(m) if available_moves(board).contains(&m) => Ok(make_move(board, m)),
+                _ => Err(format!("Illegal move '{}'", uci).into()),
+            }
+        })
+        .register_fn("san", |board: &mut Board, uci: &str| -> Result<String, Box<EvalAltResult>> {
+            match variant::parse_uci(uci) {
+                Some(m) if available_moves(board).contains(&m) => Ok(move_to_SAN(board, m)),
+                _ => Err(format!("Illegal move '{}'", uci).into()),
+            }
+        })
+        //Pieces are written like in a FEN: uppercase for white and lowercase for black
+        .register_fn("piece_at", |board: &mut Board, square: &str| -> String {
+            square
+                .parse::<Square>()
+                .ok()
+                .and_then(|square| Some(board.piece_on(square)?.to_string(board.color_on(square)?)))
+                .unwrap_or_default()
+        })
+        .register_fn("count", |board: &mut Board, piece: &str| -> i64 {
+            let color = if piece.chars().all(|c| c.is_ascii_uppercase()) { Color::White } else { Color::Black };
+
+            let piece = match piece.to_ascii_lowercase().as_str() {
+                "p" => Piece::Pawn,
+                "n" => Piece::Knight,
+                "b" => Piece::Bishop,
+                "r" => Piece::Rook,
+                "q" => Piece::Queen,
+                "k" => Piece::King,
+                _ => return 0,
+            };
+
+            (*board.pieces(piece) & *board.color_combined(color)).popcnt() as i64
+        });
+
+    engine
+}
+
+//A bot written in Rhai. Scripts either define `choose_move(board)` returning a move in UCI notation, or
+//`evaluate(board, color)` returning a score that the bot maximises over the positions after each move
+#[derive(Debug)]
+pub struct ScriptBot {
+    name: String,
+    path: PathBuf,
+    color: Color,
+
+    engine: Engine,
+    ast: Option<AST>,
+    //Modification time of the script when it was last loaded
+    modified: Option<SystemTime>,
+}
+
+impl ScriptBot {
+    pub fn new(name: &str, path: &Path, color: Color) -> ScriptBot {
+        let mut bot = ScriptBot {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            color,
+
+            engine: script_engine(),
+            ast: None,
+            modified: None,
+        };
+
+        bot.reload();
+
+        bot
+    }
+
+    //Recompiles the script if it changed since it was last loaded. A script that fails to compile keeps the old version
+    fn reload(&mut self) {
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+
+        if self.ast.is_some() && modified == self.modified {
+            return;
+        }
+
+        self.modified = modified;
+
+        let compiled = fs::read_to_string(&self.path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| self.engine.compile(source).map_err(|e| e.to_string()));
+
+        match compiled {
+            Ok(ast) => {
+                println!("Loaded script {:?}", self.path);
+                self.ast = Some(ast);
+            }
+            Err(e) => println!("Could not load script {:?}: {}", self.path, e),
+        }
+    }
+
+    fn has_fn(ast: &AST, name: &str) -> bool {
+        ast.iter_functions().any(|f| f.name == name)
+    }
+
+    fn choose_move(&self, ast: &AST, board: Board) -> Result<ChessMove, String> {
+        let uci: String = self
+            .engine
+            .call_fn(&mut Scope::new(), ast, "choose_move", (board,))
+            .map_err(|e| e.to_string())?;
+
+        match variant::parse_uci(&uci) {
+            Some(m) if available_moves(&board).contains(&m) => Ok(m),
+            _ => Err(format!("choose_move returned illegal move '{}'", uci)),
+        }
+    }
+
+    fn best_evaluated_move(&self, ast: &AST, board: Board) -> Result<ChessMove, String> {
+        let mut best_score = f64::NEG_INFINITY;
+        let mut best_moves = Vec::new();
+
+        for m in available_moves(&board) {
+            let res = make_move(&board, m);
+
+            let score: Dynamic = self
+                .engine
+                .call_fn(&mut Scope::new(), ast, "evaluate", (res, color_name(self.color).to_string()))
+                .map_err(|e| e.to_string())?;
+
+            let score = score
+                .as_float()
+                .or_else(|_| score.as_int().map(|i| i as f64))
+                .map_err(|t| format!("evaluate returned {} instead of a number", t))?;
+
+            if (score - best_score).abs() < 0.0001 {
+                best_moves.push(m);
+            } else if score > best_score {
+                best_score = score;
+                best_moves.clear();
+                best_moves.push(m);
+            }
+        }
+
+        Ok(best_moves[with_rng(|rng| rng.gen_range(0..best_moves.len()))])
+    }
+}
+
+impl ChessAlgorithm for ScriptBot {
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        self.reload();
+
+        let res = match &self.ast {
+            Some(ast) if ScriptBot::has_fn(ast, "choose_move") => self.choose_move(ast, board),
+            Some(ast) if ScriptBot::has_fn(ast, "evaluate") => self.best_evaluated_move(ast, board),
+            Some(_) => Err(String::from("Script defines neither choose_move nor evaluate")),
+            None => Err(String::from("Script is not loaded")),
+        };
+
+        match res {
+            Ok(m) => m,
+            Err(e) => {
+                println!("{}: {}. Playing a random move instead", self.name, e);
+                RandomChessAlgorithm.get_move(board)
+            }
+        }
+    }
+}
@@ -1,38 +1,147 @@
 use std::fmt::Formatter;
+use std::time::Instant;
 
 use chess::{Color, Board, ChessMove, MoveGen};
 use rand::Rng;
 
-use super::chess_alg::{ChessAlgorithm, available_moves};
+use crate::clock;
+
+use super::chess_alg::{ChessAlgorithm, SearchStats, available_moves, make_move, count_node, with_rng, cancelled, stop_requested, stoppable};
+use super::eval_cache::{EvalCache, ZobristMap};
+use super::evaluators::Evaluator;
+use super::explain::Explanation;
+
+//Positions remembered between moves. The table is emptied when it gets this big rather than picking entries to drop
+const MAX_TABLE_ENTRIES: usize = 1 << 20;
+
+//Positions just above the leaves are quicker to search again than to look up
+const MIN_TABLE_DEPTH: u32 = 2;
+
+fn interrupted() -> bool {
+    cancelled() || stop_requested()
+}
+
+//What a search from a position found. Cutoffs only tell which side of the window the score was on
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bound {
+    Exact,
+    //The score is at least this
+    Lower,
+    //The score is at most this
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TableEntry {
+    depth: u32,
+    score: f32,
+    bound: Bound,
+}
 
 pub struct TreeSearchEngine {
     color: Color,
-    eval: Box<dyn Fn(&Board, Color) -> f32 + Send>,
-    depth: u32
+    eval: Box<dyn Evaluator + Send>,
+    depth: u32,
+    //Kept for the whole game so that the search after the opponent's reply can use what the last one found
+    table: ZobristMap<TableEntry>,
+    cache: EvalCache,
+
+    explanation: Option<Explanation>,
+    //Of the last move
+    stats: SearchStats,
+    table_probes: u64,
+    table_hits: u64,
 }
 
 impl std::fmt::Debug for TreeSearchEngine {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "TreeSearchEngine {{ color: {:?}, depth: {} }}", self.color, self.depth)
+        write!(f, "TreeSearchEngine {{ color: {:?}, depth: {}, table: {} positions }}", self.color, self.depth, self.table.len())
     }
 }
 
 impl TreeSearchEngine {
     pub fn new<T: 'static + Fn(&Board, Color) -> f32 + Send>(color: Color, eval: T, depth: u32) -> Self {
+        Self::with_evaluator(color, eval, depth)
+    }
+
+    pub fn with_evaluator<T: 'static + Evaluator + Send>(color: Color, eval: T, depth: u32) -> Self {
         Self {
             color,
             eval: Box::new(eval),
-            depth
+            depth,
+            table: ZobristMap::default(),
+            cache: EvalCache::new(),
+
+            explanation: None,
+            stats: SearchStats::default(),
+            table_probes: 0,
+            table_hits: 0,
+        }
+    }
+
+    fn evaluate(&mut self, board: &Board) -> f32 {
+        self.cache.get_or_eval(board, |board| self.eval.evaluate(board, self.color))
+    }
+
+    //Only entries searched exactly as deep are used, as evaluators can care about whose turn it is at the leaves
+    fn probe(&mut self, board: &Board, alpha: f32, beta: f32, depth: u32) -> Option<f32> {
+        if depth < MIN_TABLE_DEPTH {
+            return None;
+        }
+
+        self.table_probes += 1;
+
+        let entry = self.table.get(&board.get_hash()).filter(|entry| entry.depth == depth)?;
+        self.table_hits += 1;
+
+        match entry.bound {
+            Bound::Exact => Some(entry.score),
+            Bound::Lower if entry.score >= beta => Some(beta),
+            Bound::Upper if entry.score <= alpha => Some(alpha),
+            _ => None,
+        }
+    }
+
+    fn store(&mut self, board: &Board, alpha: f32, beta: f32, depth: u32, score: f32) {
+        //A cancelled or stopped search ends with made up scores
+        if depth < MIN_TABLE_DEPTH || interrupted() {
+            return;
+        }
+
+        if self.table.len() >= MAX_TABLE_ENTRIES {
+            self.table.clear();
         }
+
+        let bound = if score <= alpha {
+            Bound::Upper
+        } else if score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+
+        self.table.insert(board.get_hash(), TableEntry { depth, score, bound });
     }
 
-    fn alpha_beta_max(&self, board: Board, mut alpha: f32, beta: f32, depth: u32) -> f32 {
-        if depth == 0 {
-            return (self.eval)(&board, self.color);
+    fn alpha_beta_max(&mut self, board: Board, alpha: f32, beta: f32, depth: u32) -> f32 {
+        if depth == 0 || interrupted() {
+            return self.evaluate(&board);
         }
 
+        if let Some(score) = self.probe(&board, alpha, beta, depth) {
+            return score;
+        }
+
+        let score = self.alpha_beta_max_moves(board, alpha, beta, depth);
+        self.store(&board, alpha, beta, depth, score);
+
+        score
+    }
+
+    fn alpha_beta_max_moves(&mut self, board: Board, mut alpha: f32, beta: f32, depth: u32) -> f32 {
         for m in MoveGen::new_legal(&board) {
             let res = board.make_move_new(m);
+            count_node();
 
             let score = self.alpha_beta_min(res, alpha, beta, depth - 1);
 
@@ -48,13 +157,25 @@ impl TreeSearchEngine {
         alpha
     }
 
-    fn alpha_beta_min(&self, board: Board, alpha: f32, mut beta: f32, depth: u32) -> f32 {
-        if depth == 0 {
-            return (self.eval)(&board, self.color);
+    fn alpha_beta_min(&mut self, board: Board, alpha: f32, beta: f32, depth: u32) -> f32 {
+        if depth == 0 || interrupted() {
+            return self.evaluate(&board);
         }
 
+        if let Some(score) = self.probe(&board, alpha, beta, depth) {
+            return score;
+        }
+
+        let score = self.alpha_beta_min_moves(board, alpha, beta, depth);
+        self.store(&board, alpha, beta, depth, score);
+
+        score
+    }
+
+    fn alpha_beta_min_moves(&mut self, board: Board, alpha: f32, mut beta: f32, depth: u32) -> f32 {
         for m in MoveGen::new_legal(&board) {
             let res = board.make_move_new(m);
+            count_node();
 
             let score = self.alpha_beta_max(res, alpha, beta, depth - 1);
 
@@ -71,17 +192,19 @@ impl TreeSearchEngine {
     }
 }
 
-impl ChessAlgorithm for TreeSearchEngine {
-    fn get_move(&mut self, board: Board) -> ChessMove {
-        let moves = available_moves(&board);
-
+impl TreeSearchEngine {
+    //Best score and every move that reaches it, looking `depth` plies past the moves themselves, along with what every
+    //move scored
+    fn search(&mut self, board: &Board, depth: u32) -> (f32, Vec<ChessMove>, Vec<(ChessMove, f32)>) {
         let mut best_score = f32::NEG_INFINITY;
         let mut best_moves = Vec::new();
+        let mut scores = Vec::new();
 
-        for m in moves {
-            let res = board.make_move_new(m);
+        for m in available_moves(board) {
+            let res = make_move(board, m);
 
-            let score = self.alpha_beta_min(res, f32::NEG_INFINITY, f32::INFINITY, self.depth);
+            let score = self.alpha_beta_min(res, f32::NEG_INFINITY, f32::INFINITY, depth);
+            scores.push((m, score));
 
             if (score - best_score).abs() < 0.0001 {
                 best_moves.push(m);
@@ -92,10 +215,96 @@ impl ChessAlgorithm for TreeSearchEngine {
             }
         }
 
+        (best_score, best_moves, scores)
+    }
+}
+
+impl ChessAlgorithm for TreeSearchEngine {
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        let start = Instant::now();
+        self.table_probes = 0;
+        self.table_hits = 0;
+
+        //On a clock the search deepens one ply at a time and stops when the next ply probably won't fit in the
+        //time it was given, which is roughly ten times what the last one took. It also deepens when it can be told
+        //to stop, so that there is a finished ply to fall back on
+        let budget = clock::think_time().map(|think_time| think_time.budget);
+
+        let (best_score, best_moves, scores, depth) = if budget.is_some() || stoppable() {
+            let (best_score, best_moves, scores) = self.search(&board, 0);
+            let mut res = (best_score, best_moves, scores, 0);
+
+            for depth in 1..=self.depth {
+                if cancelled() || stop_requested() {
+                    break;
+                }
+
+                if budget.is_some_and(|budget| start.elapsed() * 10 > budget) {
+                    println!("Out of time after looking {} plies past each move", depth - 1);
+                    break;
+                }
+
+                let (best_score, best_moves, scores) = self.search(&board, depth);
+                let deeper = (best_score, best_moves, scores, depth);
+
+                //Only some of the moves got looked at properly
+                if stop_requested() {
+                    println!("Stopped after looking {} plies past each move", depth - 1);
+                    break;
+                }
+
+                res = deeper;
+            }
+
+            res
+        } else {
+            let (best_score, best_moves, scores) = self.search(&board, self.depth);
+            (best_score, best_moves, scores, self.depth)
+        };
+
         println!("Eval: {}", best_score);
+        println!("{}", self.cache.stats());
+
+        self.stats = SearchStats {
+            depth: Some(depth),
+            table_hit_rate: (self.table_probes > 0).then(|| self.table_hits as f32 / self.table_probes as f32),
+            nodes: None,
+        };
+
+        let chosen = best_moves[with_rng(|rng| rng.gen_range(0..best_moves.len()))];
+        self.explanation = Some(Explanation::new(self.eval.as_ref(), self.color, board, chosen, &scores, depth));
+
+        chosen
+    }
+
+    fn depth(&self) -> Option<u32> {
+        Some(self.depth)
+    }
+
+    fn set_depth(&mut self, depth: u32) {
+        self.depth = depth;
+    }
 
-        let mut rng = rand::thread_rng();
+    fn new_game(&mut self) {
+        self.table.clear();
+        self.cache.clear();
+        self.explanation = None;
+        self.stats = SearchStats::default();
+    }
 
-        best_moves[rng.gen_range(0..best_moves.len())]
+    fn info(&self) -> Option<String> {
+        Some(format!("{}, {} positions in the transposition table", self.cache.stats(), self.table.len()))
     }
-}
\ No newline at end of file
+
+    fn explain(&self) -> Option<Explanation> {
+        self.explanation.clone()
+    }
+
+    fn search_stats(&self) -> SearchStats {
+        self.stats
+    }
+
+    fn evaluate(&self, board: &Board) -> Option<f32> {
+        Some(self.eval.evaluate(board, self.color))
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chess::{BitBoard, Board, BoardStatus, Color, Piece, Square, ALL_PIECES};
+use serde::{Deserialize, Serialize};
+
+use super::chess_alg::count_moves;
+use super::evaluators::{eval_huddle, eval_matching_colors, eval_swarm, value_of_piece, EvalTerm, Evaluator};
+use super::one_lookahead::SingleLookaheadEngine;
+
+pub const WEIGHTS_DIR: &str = "res/weights";
+
+//Things about a position a weighted evaluator can care about, all from the point of view of the evaluating side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    Material,
+    Mobility,
+    //Distance of our pieces to the enemy king, like Swarm
+    KingDistance,
+    //Distance of our pieces to our own king, like Huddle
+    Huddle,
+    MatchingColors,
+    Center,
+    PawnAdvancement,
+    Check,
+    Checkmate,
+}
+
+pub const ALL_FEATURES: [Feature; 9] = [
+    Feature::Material,
+    Feature::Mobility,
+    Feature::KingDistance,
+    Feature::Huddle,
+    Feature::MatchingColors,
+    Feature::Center,
+    Feature::PawnAdvancement,
+    Feature::Check,
+    Feature::Checkmate,
+];
+
+const CENTER: u64 = 0x0000_0018_1800_0000;
+
+fn material(board: &Board, color: Color) -> f32 {
+    ALL_PIECES
+        .iter()
+        .map(|piece| value_of_piece(*piece) * (*board.pieces(*piece) & *board.color_combined(color)).popcnt() as f32)
+        .sum()
+}
+
+//Moves `color` could make if it were its turn
+fn move_count(board: &Board, color: Color) -> f32 {
+    if board.side_to_move() == color {
+        count_moves(board) as f32
+    } else {
+        board.null_move().map_or(0.0, |b| count_moves(&b) as f32)
+    }
+}
+
+fn pawn_advancement(board: &Board, color: Color) -> f32 {
+    (*board.pieces(Piece::Pawn) & *board.color_combined(color))
+        .map(|square: Square| match color {
+            Color::White => square.get_rank().to_index() as f32 - 1.0,
+            Color::Black => 6.0 - square.get_rank().to_index() as f32,
+        })
+        .sum()
+}
+
+impl Feature {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::Material => "material",
+            Feature::Mobility => "mobility",
+            Feature::KingDistance => "king_distance",
+            Feature::Huddle => "huddle",
+            Feature::MatchingColors => "matching_colors",
+            Feature::Center => "center",
+            Feature::PawnAdvancement => "pawn_advancement",
+            Feature::Check => "check",
+            Feature::Checkmate => "checkmate",
+        }
+    }
+
+    //Rough size of the feature's values during a game, so that weights can be learned at a similar rate
+    pub fn scale(&self) -> f32 {
+        match self {
+            Feature::Material => 10.0,
+            Feature::Mobility => 20.0,
+            Feature::KingDistance | Feature::Huddle => 40.0,
+            Feature::MatchingColors => 8.0,
+            Feature::Center => 2.0,
+            Feature::PawnAdvancement => 10.0,
+            Feature::Check | Feature::Checkmate => 1.0,
+        }
+    }
+
+    pub fn value(&self, board: &Board, color: Color) -> f32 {
+        match self {
+            Feature::Material => material(board, color) - material(board, !color),
+            Feature::Mobility => move_count(board, color) - move_count(board, !color),
+            Feature::KingDistance => eval_swarm(board, color),
+            Feature::Huddle => eval_huddle(board, color),
+            Feature::MatchingColors => eval_matching_colors(board, color),
+            Feature::Center => {
+                let center = *board.combined() & BitBoard(CENTER);
+                (center & *board.color_combined(color)).popcnt() as f32 - (center & *board.color_combined(!color)).popcnt() as f32
+            }
+            Feature::PawnAdvancement => pawn_advancement(board, color) - pawn_advancement(board, !color),
+            Feature::Check => match (board.checkers().popcnt(), board.side_to_move() == color) {
+                (0, _) => 0.0,
+                (_, true) => -1.0,
+                (_, false) => 1.0,
+            },
+            Feature::Checkmate => match (board.status(), board.side_to_move() == color) {
+                (BoardStatus::Checkmate, true) => -1.0,
+                (BoardStatus::Checkmate, false) => 1.0,
+                _ => 0.0,
+            },
+        }
+    }
+}
+
+//An evaluator that scores positions as a weighted sum of features. Saved as JSON in the weights folder, where every
+//file becomes a player
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WeightedBot {
+    pub name: String,
+    //Feature name to weight, missing features have a weight of 0
+    pub weights: BTreeMap<String, f32>,
+    #[serde(default)]
+    pub generation: u32,
+    #[serde(default)]
+    pub fitness: f32,
+}
+
+impl WeightedBot {
+    pub fn weight(&self, feature: Feature) -> f32 {
+        self.weights.get(feature.name()).copied().unwrap_or(0.0)
+    }
+
+    pub fn set_weight(&mut self, feature: Feature, weight: f32) {
+        self.weights.insert(feature.name().to_string(), weight);
+    }
+
+    pub fn evaluate(&self, board: &Board, color: Color) -> f32 {
+        ALL_FEATURES
+            .iter()
+            .map(|feature| (feature, self.weight(*feature)))
+            .filter(|(_, weight)| *weight != 0.0)
+            .map(|(feature, weight)| weight * feature.value(board, color))
+            .sum()
+    }
+
+    pub fn engine(&self, color: Color) -> SingleLookaheadEngine {
+        SingleLookaheadEngine::with_evaluator(color, self.clone())
+    }
+
+    pub fn load(path: &Path) -> Result<WeightedBot, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+        serde_json::from_str(&text).map_err(|e| e.to_string())
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, String> {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+
+        let path = dir.join(format!("{}.json", self.name));
+        fs::write(&path, serde_json::to_string_pretty(self).unwrap()).map_err(|e| e.to_string())?;
+
+        Ok(path)
+    }
+}
+
+impl Evaluator for WeightedBot {
+    fn evaluate(&self, board: &Board, color: Color) -> f32 {
+        WeightedBot::evaluate(self, board, color)
+    }
+
+    //One term for every feature with a weight, already multiplied by it
+    fn terms(&self, board: &Board, color: Color) -> Vec<EvalTerm> {
+        ALL_FEATURES
+            .iter()
+            .map(|feature| (feature, self.weight(*feature)))
+            .filter(|(_, weight)| *weight != 0.0)
+            .map(|(feature, weight)| EvalTerm::new(&feature.name().replace('_', " "), weight * feature.value(board, color)))
+            .collect()
+    }
+}
+
+//Every weighted bot in the weights folder, sorted by name
+pub fn discover() -> Vec<WeightedBot> {
+    let Ok(entries) = fs::read_dir(WEIGHTS_DIR) else {
+        return vec![];
+    };
+
+    let mut bots: Vec<WeightedBot> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            match WeightedBot::load(&path) {
+                Ok(bot) => Some(bot),
+                Err(e) => {
+                    println!("Could not load weights {:?}: {}", path, e);
+                    None
+                }
+            }
+        })
+        .collect();
+
+    bots.sort_by(|a, b| a.name.cmp(&b.name));
+
+    bots
+}
@@ -0,0 +1,289 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use chess::Board;
+use serde::{Deserialize, Serialize};
+
+use crate::alg::player_types;
+use crate::events::{self, GameEvent};
+use crate::pgn::PgnGame;
+use crate::variant::{self, Variant};
+
+//Largest request body read. Positions and PGNs are well under this
+const MAX_BODY_LENGTH: usize = 64 * 1024;
+
+pub enum ApiCommand {
+    StartGame { white: String, black: String, variant: Variant },
+    SetPaused(bool),
+    Step,
+}
+
+pub struct ApiRequest {
+    pub command: ApiCommand,
+    pub reply: Sender<Result<(), String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct GameSnapshot {
+    white: String,
+    black: String,
+    start_fen: String,
+    fen: String,
+    variant: Variant,
+    moves: Vec<String>,
+    result: Option<String>,
+    reason: Option<String>,
+    paused: bool,
+    //Time left in timed games
+    white_ms: Option<u64>,
+    black_ms: Option<u64>,
+}
+
+impl GameSnapshot {
+    fn pgn(&self) -> String {
+        let mut game = PgnGame::new(Board::from_str(&self.start_fen).unwrap_or_default());
+
+        game.set_tag("White", &self.white);
+        game.set_tag("Black", &self.black);
+
+        if self.variant != Variant::Standard {
+            game.set_tag("Variant", self.variant.name());
+        }
+
+        game.moves = self.moves.iter().map(String::as_str).filter_map(variant::parse_uci).collect();
+
+        if let Some(result) = &self.result {
+            game.result = result.clone();
+        }
+
+        game.to_pgn(false)
+    }
+}
+
+#[derive(Deserialize)]
+struct StartGameBody {
+    white: String,
+    black: String,
+    #[serde(default)]
+    variant: Variant,
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn json<T: Serialize>(value: &T) -> Self {
+        Response {
+            status: "200 OK",
+            content_type: "application/json",
+            body: serde_json::to_string(value).unwrap(),
+        }
+    }
+
+    fn text(body: String) -> Self {
+        Response {
+            status: "200 OK",
+            content_type: "text/plain",
+            body,
+        }
+    }
+
+    fn error(status: &'static str, message: &str) -> Self {
+        Response {
+            status,
+            content_type: "application/json",
+            body: serde_json::json!({ "error": message }).to_string(),
+        }
+    }
+}
+
+pub fn start(port: u16) -> std::io::Result<Receiver<ApiRequest>> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+
+    let snapshot: Arc<Mutex<Option<GameSnapshot>>> = Arc::new(Mutex::new(None));
+    let (sender, receiver) = channel();
+
+    let events = events::subscribe();
+
+    {
+        let snapshot = snapshot.clone();
+
+        thread::spawn(move || {
+            for event in events {
+                let mut snapshot = snapshot.lock().unwrap();
+
+                match event {
+                    GameEvent::GameStarted { white, black, fen, variant } => {
+                        *snapshot = Some(GameSnapshot {
+                            white,
+                            black,
+                            start_fen: fen.clone(),
+                            fen,
+                            variant,
+                            ..Default::default()
+                        });
+                    }
+
+                    GameEvent::Move { uci, fen, .. } => {
+                        if let Some(snapshot) = snapshot.as_mut() {
+                            snapshot.moves.push(uci);
+                            snapshot.fen = fen;
+                        }
+                    }
+
+                    GameEvent::GameOver { result, reason } => {
+                        if let Some(snapshot) = snapshot.as_mut() {
+                            snapshot.result = Some(result);
+                            snapshot.reason = Some(reason);
+                        }
+                    }
+
+                    GameEvent::Takeback { ply, fen } => {
+                        if let Some(snapshot) = snapshot.as_mut() {
+                            snapshot.moves.truncate(ply);
+                            snapshot.fen = fen;
+                            snapshot.result = None;
+                            snapshot.reason = None;
+                        }
+                    }
+
+                    GameEvent::Paused { paused } => {
+                        if let Some(snapshot) = snapshot.as_mut() {
+                            snapshot.paused = paused;
+                        }
+                    }
+
+                    GameEvent::Clock { white_ms, black_ms } => {
+                        if let Some(snapshot) = snapshot.as_mut() {
+                            snapshot.white_ms = Some(white_ms);
+                            snapshot.black_ms = Some(black_ms);
+                        }
+                    }
+
+                    GameEvent::FlagFell { .. } | GameEvent::DrawOffered { .. } | GameEvent::Commentary { .. } | GameEvent::Eval { .. } => {}
+                }
+            }
+        });
+    }
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let snapshot = snapshot.clone();
+            let sender = sender.clone();
+
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, &snapshot, &sender) {
+                    println!("API connection error: {}", e);
+                }
+            });
+        }
+    });
+
+    println!("API listening on http://127.0.0.1:{}", port);
+
+    Ok(receiver)
+}
+
+fn handle_connection(mut stream: TcpStream, snapshot: &Mutex<Option<GameSnapshot>>, commands: &Sender<ApiRequest>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts.next().unwrap_or("").to_string();
+
+    let mut content_length = 0;
+
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let response = if content_length > MAX_BODY_LENGTH {
+        Response::error("413 Payload Too Large", &format!("Request bodies can be at most {} bytes", MAX_BODY_LENGTH))
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        route(&method, &path, &body, snapshot, commands)
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+
+    stream.flush()
+}
+
+fn send_command(commands: &Sender<ApiRequest>, command: ApiCommand) -> Response {
+    let (reply, result) = channel();
+
+    if commands.send(ApiRequest { command, reply }).is_err() {
+        return Response::error("503 Service Unavailable", "The arena is shutting down");
+    }
+
+    match result.recv() {
+        Ok(Ok(())) => Response::json(&serde_json::json!({ "ok": true })),
+        Ok(Err(e)) => Response::error("409 Conflict", &e),
+        Err(_) => Response::error("503 Service Unavailable", "The arena is shutting down"),
+    }
+}
+
+fn route(method: &str, path: &str, body: &[u8], snapshot: &Mutex<Option<GameSnapshot>>, commands: &Sender<ApiRequest>) -> Response {
+    let current = snapshot.lock().unwrap().clone();
+
+    match (method, path) {
+        ("GET", "/bots") => Response::json(&player_types().iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>()),
+
+        ("GET", "/game") | ("GET", "/game/fen") | ("GET", "/game/pgn") => {
+            let Some(current) = current else {
+                return Response::error("404 Not Found", "No game in progress");
+            };
+
+            match path {
+                "/game/fen" => Response::text(current.fen),
+                "/game/pgn" => Response::text(current.pgn()),
+                _ => Response::json(&current),
+            }
+        }
+
+        ("POST", "/game") => match serde_json::from_slice::<StartGameBody>(body) {
+            Ok(StartGameBody { white, black, variant }) => {
+                send_command(commands, ApiCommand::StartGame { white, black, variant })
+            }
+            Err(e) => Response::error("400 Bad Request", &e.to_string()),
+        },
+
+        ("POST", "/game/pause") => send_command(commands, ApiCommand::SetPaused(true)),
+        ("POST", "/game/resume") => send_command(commands, ApiCommand::SetPaused(false)),
+        ("POST", "/game/step") => send_command(commands, ApiCommand::Step),
+
+        _ => Response::error("404 Not Found", "Unknown endpoint"),
+    }
+}
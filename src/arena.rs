@@ -0,0 +1,190 @@
+use std::time::{Duration, Instant};
+
+use chess::{Board, BoardStatus, ChessMove, Color, MoveGen, Piece};
+use rand::Rng;
+
+use crate::alg::chess_alg::{rng_draws, with_move_limit, with_rng, ChessAlgorithm, GameConfig, STOP_GRACE};
+use crate::alg::draw_claim;
+use crate::alg::explain::MoveTerms;
+use crate::cli;
+use crate::clock::TimeControl;
+use crate::gui::chess_display::GameOutcome;
+use crate::variant::{self, Variant, VariantState};
+
+//A game played without the GUI, e.g. between bots that are being trained
+#[derive(Debug)]
+pub struct ArenaGame {
+    pub moves: Vec<ChessMove>,
+    pub outcome: GameOutcome,
+    //Nothing random happened, so the same bots would play the same game again
+    pub reproducible: bool,
+    //For every move, the terms behind it when it was an evaluator bot's own pick
+    pub terms: Vec<Option<MoveTerms>>,
+}
+
+impl ArenaGame {
+    //1 for a white win, 0.5 for a draw and 0 for a black win
+    pub fn white_score(&self) -> f32 {
+        match self.outcome.pgn_result() {
+            "1-0" => 1.0,
+            "0-1" => 0.0,
+            _ => 0.5,
+        }
+    }
+
+    pub fn score(&self, color: Color) -> f32 {
+        match color {
+            Color::White => self.white_score(),
+            Color::Black => 1.0 - self.white_score(),
+        }
+    }
+}
+
+//Plays a game on the current thread. Games that go on for more than `max_plies` are drawn
+pub fn play_game(
+    white: &mut dyn ChessAlgorithm,
+    black: &mut dyn ChessAlgorithm,
+    variant: Variant,
+    start: Board,
+    max_plies: usize,
+) -> ArenaGame {
+    play_game_watched(white, black, variant, start, max_plies, cli::args().max_move_time, &mut |_, _, _| {})
+}
+
+//Like play_game, calling `on_move` with the board before and after every move so that the game can be watched
+pub fn play_game_watched(
+    white: &mut dyn ChessAlgorithm,
+    black: &mut dyn ChessAlgorithm,
+    variant: Variant,
+    start: Board,
+    max_plies: usize,
+    max_move_time: Option<Duration>,
+    on_move: &mut dyn FnMut(&Board, ChessMove, &Board),
+) -> ArenaGame {
+    let rules = variant.rules();
+
+    white.new_game();
+    black.new_game();
+
+    variant::set_current(variant, VariantState::default());
+
+    let config = |color| GameConfig { color, variant, start, time_control: TimeControl::Untimed };
+    white.prepare(&config(Color::White));
+    black.prepare(&config(Color::Black));
+
+    let draws_before = rng_draws();
+    //A bot stopped partway through its search might have moved differently on a faster or less busy machine
+    let mut stopped = false;
+
+    let mut board = start;
+    let mut state = VariantState::default();
+
+    let mut history = vec![];
+    let mut moves = vec![];
+    let mut terms = vec![];
+    let mut reversable_moves = 0;
+
+    //Same draw rules as games in the GUI
+    let outcome = loop {
+        if let Some(outcome) = rules.outcome(&board, &state) {
+            break outcome;
+        }
+
+        let repetitions = history.iter().filter(|pos| **pos == board).count();
+
+        if let Some(outcome) = draw_claim::forced_draw(repetitions, reversable_moves) {
+            break outcome;
+        }
+
+        if let Some(outcome) = draw_claim::claimable_draw(repetitions, reversable_moves) {
+            if white.claim_draw(&board) || black.claim_draw(&board) {
+                break outcome;
+            }
+        }
+
+        if moves.len() >= max_plies {
+            break GameOutcome::DrawByMoveLimit;
+        }
+
+        let color = board.side_to_move();
+        let player: &mut dyn ChessAlgorithm = match color {
+            Color::White => &mut *white,
+            Color::Black => &mut *black,
+        };
+
+        variant::set_current(variant, state);
+        let view = rules.player_view(&board, color);
+        let started = Instant::now();
+        let (m, was_stopped) = with_move_limit(max_move_time, || player.get_move(view));
+        stopped |= was_stopped;
+
+        //The move is only a stand-in, so the game ends before it
+        if player.crashed() {
+            println!("{:?} can't play any more and forfeits the game", color);
+            break GameOutcome::Forfeit(!color);
+        }
+
+        //Same cap as in the GUI, for bots that don't stop when told to
+        if max_move_time.is_some_and(|limit| started.elapsed() > limit + STOP_GRACE) {
+            println!("{:?} took {:.1}s and didn't stop when told to, forfeiting", color, started.elapsed().as_secs_f32());
+            break GameOutcome::Forfeit(!color);
+        }
+
+        let legal = rules.legal_moves(&board, &state);
+
+        //A bot that can't see the whole board may try moves that are illegal on the real one, which isn't its fault
+        let m = if legal.contains(&m) {
+            m
+        } else if view != board {
+            println!("Bot played illegal move {}, playing a random one instead", variant::move_to_uci(m));
+            legal[with_rng(|rng| rng.gen_range(0..legal.len()))]
+        } else {
+            println!("Bot played illegal move {} as {:?} in {}, forfeiting", variant::move_to_uci(m), color, board);
+            break GameOutcome::IllegalMove(!color);
+        };
+
+        //Random moves of epsilon bots leave the last explanation in place, which is for another move
+        terms.push(player.explain().filter(|explanation| explanation.chosen == m).map(|explanation| explanation.move_terms()));
+
+        if board.piece_on(m.get_source()) == Some(Piece::Pawn) || variant::is_capture(&board, m) {
+            reversable_moves = 0;
+        } else {
+            reversable_moves += 1;
+        }
+
+        history.push(board);
+        moves.push(m);
+        (board, state) = rules.apply(&board, &state, m);
+
+        on_move(&history[history.len() - 1], m, &board);
+    };
+
+    white.game_over(outcome.pgn_result(), outcome.get_text());
+    black.game_over(outcome.pgn_result(), outcome.get_text());
+
+    let reproducible = !stopped && rng_draws() == draws_before && white.reproducible() && black.reproducible();
+
+    ArenaGame { moves, outcome, reproducible, terms }
+}
+
+//Up to `plies` uniformly random legal moves from the starting position. Moves that would end the game are left out,
+//so it stops early only if every move would
+pub fn random_opening(plies: usize) -> Vec<ChessMove> {
+    let mut rng = rand::thread_rng();
+    let mut board = Board::default();
+    let mut moves = vec![];
+
+    for _ in 0..plies {
+        let playable: Vec<ChessMove> = MoveGen::new_legal(&board).filter(|m| board.make_move_new(*m).status() == BoardStatus::Ongoing).collect();
+
+        if playable.is_empty() {
+            break;
+        }
+
+        let m = playable[rng.gen_range(0..playable.len())];
+        board = board.make_move_new(m);
+        moves.push(m);
+    }
+
+    moves
+}
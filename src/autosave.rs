@@ -0,0 +1,238 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chess::Board;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::clock::{TimeControl, TimeManagerKind};
+use crate::handicap::Handicap;
+use crate::pgn::GameTags;
+use crate::tournament::{GameResult, PairingConstraints, Tournament, TournamentConfig};
+use crate::util::{pgn_date, write_atomically};
+use crate::variant::{self, Variant};
+
+//The files only exist while something is in progress, and are removed on a clean exit. Finding one at startup means
+//the last run crashed or was killed
+const GAME_PATH: &str = "autosave.json";
+const TOURNAMENT_PATH: &str = "autosave_tournament.json";
+
+//How often the session is saved
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+//A game against or between local players, with enough to carry on from the same position and clocks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    //Names in the player list
+    pub white: String,
+    pub black: String,
+    //Edited PGN tags, including the names shown for the players
+    #[serde(default)]
+    pub tags: Option<GameTags>,
+    #[serde(default)]
+    pub variant: Variant,
+    pub handicap: String,
+    pub time_controls: [String; 2],
+    pub time_managers: [TimeManagerKind; 2],
+    pub start_fen: String,
+    pub moves: Vec<String>,
+    //Clock time used and given back by increments, in milliseconds
+    pub time_used: [u64; 2],
+    pub time_bonus: [u64; 2],
+    #[serde(default)]
+    pub auto_flip: bool,
+    pub date: String,
+}
+
+impl SavedGame {
+    pub fn handicap(&self) -> Handicap {
+        Handicap::from_name(&self.handicap).unwrap_or_default()
+    }
+
+    pub fn time_controls(&self) -> [TimeControl; 2] {
+        self.time_controls.each_ref().map(|t| TimeControl::from_name(t).unwrap_or_default())
+    }
+
+    pub fn start_board(&self) -> Result<Board, String> {
+        Board::from_str(&self.start_fen).map_err(|_| format!("Invalid start position '{}'", self.start_fen))
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} vs {} after {} moves ({})", self.white, self.black, self.moves.len().div_ceil(2), self.date)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedResult {
+    //Position in the schedule
+    pub index: usize,
+    pub result: String,
+    pub reason: String,
+    pub moves: Vec<String>,
+    #[serde(default)]
+    pub reproducible: bool,
+    #[serde(default)]
+    pub copy_of: Option<usize>,
+    #[serde(default)]
+    pub random_plies: usize,
+}
+
+//A watched tournament with the games that are over. Games that were being played start again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTournament {
+    pub bots: Vec<String>,
+    pub games: usize,
+    pub concurrency: usize,
+    pub max_plies: usize,
+    pub pgn: Option<PathBuf>,
+    #[serde(default)]
+    pub constraints: PairingConstraints,
+    #[serde(default)]
+    pub eval_terms: bool,
+    #[serde(default)]
+    pub eval_terms_json: Option<PathBuf>,
+    //The move limit a budget came to. The budget itself isn't kept, since the time it was counted from has passed
+    #[serde(default)]
+    pub move_time_ms: Option<u64>,
+    #[serde(default)]
+    pub random_plies: usize,
+    #[serde(default)]
+    pub stall_limit_ms: Option<u64>,
+    pub switch_every_ms: u64,
+    pub results: Vec<SavedResult>,
+    pub date: String,
+}
+
+impl SavedTournament {
+    pub fn new(tournament: &Tournament, switch_every: Duration) -> SavedTournament {
+        let config = &tournament.config;
+
+        SavedTournament {
+            bots: config.bots.clone(),
+            games: config.games,
+            concurrency: config.concurrency,
+            max_plies: config.max_plies,
+            pgn: config.pgn.clone(),
+            constraints: config.constraints.clone(),
+            eval_terms: config.eval_terms,
+            eval_terms_json: config.eval_terms_json.clone(),
+            move_time_ms: config.move_time.map(|time| time.as_millis() as u64),
+            random_plies: config.random_plies,
+            stall_limit_ms: config.stall_limit.map(|limit| limit.as_millis() as u64),
+            switch_every_ms: switch_every.as_millis() as u64,
+            results: tournament
+                .results
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|result| SavedResult {
+                    index: result.pairing.index,
+                    result: result.result.clone(),
+                    reason: result.reason.clone(),
+                    moves: result.moves.iter().copied().map(variant::move_to_uci).collect(),
+                    reproducible: result.reproducible,
+                    copy_of: result.copy_of,
+                    random_plies: result.random_plies,
+                })
+                .collect(),
+            date: pgn_date(),
+        }
+    }
+
+    //Starts the tournament again from where it was left
+    pub fn restore(&self) -> Result<(Arc<Tournament>, Duration), String> {
+        let config = TournamentConfig {
+            bots: self.bots.clone(),
+            games: self.games,
+            concurrency: self.concurrency,
+            max_plies: self.max_plies,
+            pgn: self.pgn.clone(),
+            constraints: self.constraints.clone(),
+            eval_terms: self.eval_terms,
+            eval_terms_json: self.eval_terms_json.clone(),
+            budget: None,
+            move_time: self.move_time_ms.map(Duration::from_millis),
+            random_plies: self.random_plies,
+            stall_limit: self.stall_limit_ms.map(Duration::from_millis),
+        };
+
+        let tournament = Tournament::new(config)?;
+
+        let results = self
+            .results
+            .iter()
+            .map(|saved| {
+                let pairing = tournament.pairings.get(saved.index).cloned().ok_or(format!("Game {} is not in the schedule", saved.index + 1))?;
+
+                Ok(GameResult {
+                    pairing,
+                    result: saved.result.clone(),
+                    reason: saved.reason.clone(),
+                    moves: saved.moves.iter().map(String::as_str).filter_map(variant::parse_uci).collect(),
+                    reproducible: saved.reproducible,
+                    copy_of: saved.copy_of,
+                    //Already written out when the game was first played
+                    terms: vec![],
+                    random_plies: saved.random_plies,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        tournament.restore_results(results);
+
+        Ok((tournament, Duration::from_millis(self.switch_every_ms)))
+    }
+
+    pub fn describe(&self) -> String {
+        format!("Tournament between {} bots with {} games played ({})", self.bots.len(), self.results.len(), self.date)
+    }
+}
+
+fn save<T: Serialize>(path: &str, value: &T) {
+    if let Err(e) = write_atomically(Path::new(path), &serde_json::to_string_pretty(value).unwrap()) {
+        println!("Could not autosave to {}: {}", path, e);
+    }
+}
+
+fn load<T: DeserializeOwned>(path: &str) -> Option<T> {
+    let text = fs::read_to_string(path).ok()?;
+
+    serde_json::from_str(&text)
+        .map_err(|e| println!("Could not read {}: {}", path, e))
+        .ok()
+}
+
+fn clear(path: &str) {
+    let _ = fs::remove_file(path);
+}
+
+pub fn save_game(game: &SavedGame) {
+    save(GAME_PATH, game);
+}
+
+pub fn load_game() -> Option<SavedGame> {
+    load(GAME_PATH)
+}
+
+pub fn clear_game() {
+    clear(GAME_PATH);
+}
+
+pub fn save_tournament(tournament: &SavedTournament) {
+    save(TOURNAMENT_PATH, tournament);
+}
+
+pub fn load_tournament() -> Option<SavedTournament> {
+    load(TOURNAMENT_PATH)
+}
+
+pub fn clear_tournament() {
+    clear(TOURNAMENT_PATH);
+}
+
+pub fn clear_all() {
+    clear_game();
+    clear_tournament();
+}
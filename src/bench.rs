@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use chess::Board;
+
+use crate::alg::chess_alg::nodes;
+use crate::alg::find_player_type;
+use crate::gui::chess_display::PlayerType;
+use crate::variant::{self, Variant, VariantState};
+
+//Openings, middlegames and endgames with plenty going on, including the usual move generator test positions
+const BENCH_POSITIONS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "8/8/4k3/3p4/3P4/4K3/8/8 w - - 0 1",
+];
+
+//FENs one per line. EPD lines without move counters work too, anything after the first four fields is ignored
+fn load_positions(path: &Path) -> Result<Vec<Board>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+
+    text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let fen = format!("{} 0 1", line.split_whitespace().take(4).collect::<Vec<_>>().join(" "));
+
+            Board::from_str(&fen).map_err(|_| format!("Invalid position '{}'", line))
+        })
+        .collect()
+}
+
+fn nodes_per_second(nodes: u64, time: Duration) -> u64 {
+    (nodes as f64 / time.as_secs_f64().max(0.000001)) as u64
+}
+
+//Times `repeat` moves from every position and counts the positions the bot looked at
+pub fn run(bot: &str, positions: Option<&Path>, repeat: u32) -> Result<(), String> {
+    let (name, supplier) = find_player_type(bot).ok_or(format!("Unknown bot '{}'", bot))?;
+
+    let positions = match positions {
+        Some(path) => load_positions(path)?,
+        None => BENCH_POSITIONS.iter().map(|fen| Board::from_str(fen).unwrap()).collect(),
+    };
+
+    if repeat > 1 {
+        println!("Benchmarking {} on {} positions, {} moves each", name, positions.len(), repeat);
+    } else {
+        println!("Benchmarking {} on {} positions", name, positions.len());
+    }
+
+    variant::set_current(Variant::Standard, VariantState::default());
+
+    let mut total_nodes = 0;
+    let mut total_time = Duration::ZERO;
+
+    for (i, board) in positions.iter().enumerate() {
+        let PlayerType::Computer(algorithm) = supplier(board.side_to_move()) else {
+            return Err(format!("{} is not a bot", name));
+        };
+
+        let nodes_before = nodes();
+        let start = Instant::now();
+
+        let mut algorithm = algorithm.lock().unwrap();
+        let mut m = algorithm.get_move(*board);
+
+        //Every move is timed from scratch, without anything the bot remembers from the last one
+        for _ in 1..repeat {
+            algorithm.new_game();
+            m = algorithm.get_move(*board);
+        }
+
+        let time = start.elapsed();
+        let nodes = nodes() - nodes_before;
+
+        println!(
+            "{:>3}: {:<6} {:>10} nodes {:>9.1} ms {:>10} nodes/s  {}",
+            i + 1,
+            variant::move_to_uci(m),
+            nodes,
+            time.as_secs_f64() * 1000.0,
+            nodes_per_second(nodes, time),
+            board
+        );
+
+        if let Some(info) = algorithm.info() {
+            println!("     {}", info);
+        }
+
+        total_nodes += nodes;
+        total_time += time;
+    }
+
+    println!();
+    println!("Nodes:         {}", total_nodes);
+    println!("Time:          {:.1} ms", total_time.as_secs_f64() * 1000.0);
+    println!("Nodes/second:  {}", nodes_per_second(total_nodes, total_time));
+    println!("Time per move: {:.3} ms", total_time.as_secs_f64() * 1000.0 / (positions.len() * repeat as usize).max(1) as f64);
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use tungstenite::{accept, Message, WebSocket};
+
+use crate::events::{self, GameEvent};
+
+struct BroadcastState {
+    //Each client has its own thread doing the sending, so a slow client can't hold up everyone else
+    clients: Vec<Sender<String>>,
+    //Events of the game in progress, replayed to clients that connect mid-game
+    current_game: Vec<String>,
+}
+
+pub fn start(port: u16) -> std::io::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+
+    let state = Arc::new(Mutex::new(BroadcastState {
+        clients: vec![],
+        current_game: vec![],
+    }));
+
+    let receiver = events::subscribe();
+
+    {
+        let state = state.clone();
+
+        thread::spawn(move || {
+            for event in receiver {
+                let json = serde_json::to_string(&event).unwrap();
+
+                let mut state = state.lock().unwrap();
+
+                if let GameEvent::GameStarted { .. } = event {
+                    state.current_game.clear();
+                }
+
+                state.current_game.push(json.clone());
+                state.clients.retain(|client| client.send(json.clone()).is_ok());
+            }
+        });
+    }
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let state = state.clone();
+
+            //Handshake on a separate thread so that a slow client can't hold up everyone else
+            thread::spawn(move || {
+                let mut client = match accept(stream) {
+                    Ok(client) => client,
+                    Err(e) => {
+                        println!("Broadcast handshake failed: {}", e);
+                        return;
+                    }
+                };
+
+                let (sender, receiver) = mpsc::channel();
+
+                //Events sent after this are queued for the client, so none are missed or sent twice
+                let current_game = {
+                    let mut state = state.lock().unwrap();
+                    state.clients.push(sender);
+                    state.current_game.clone()
+                };
+
+                send_all(&mut client, current_game.into_iter().chain(receiver));
+            });
+        }
+    });
+
+    println!("Broadcasting games on ws://0.0.0.0:{}", port);
+
+    Ok(())
+}
+
+//Stops at the first failed send, which drops the receiver so the client is removed on the next event
+fn send_all(client: &mut WebSocket<TcpStream>, events: impl Iterator<Item = String>) {
+    for json in events {
+        if client.send(Message::Text(json)).is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+//Move limits tried from the longest down, until the budget leaves room for enough games
+const MOVE_TIMES_MS: [u64; 8] = [5000, 2000, 1000, 500, 200, 100, 50, 20];
+
+//Games between every two bots wanted before the move limit is shortened, and the fewest played whatever the budget
+const TARGET_GAMES: usize = 10;
+const MIN_GAMES: usize = 2;
+
+//Games rarely go on to the ply limit, and bots rarely think for as long as they may
+const EXPECTED_PLIES: f64 = 0.5;
+
+//How a tournament fits into a wall clock budget
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BudgetPlan {
+    //Between every two bots, an even number so both get each color as often
+    pub games: usize,
+    pub move_time: Duration,
+    //If every move took the whole limit
+    pub estimate: Duration,
+}
+
+//Like 2h, 90m, 1h30m or 45s. A plain number is minutes
+pub fn parse_budget(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("'{}' is not a time budget like 2h, 90m or 1h30m", s);
+
+    if let Ok(minutes) = s.trim().parse::<f64>() {
+        return (minutes > 0.0 && minutes.is_finite()).then(|| Duration::from_secs_f64(minutes * 60.0)).ok_or_else(invalid);
+    }
+
+    let mut total = 0.0;
+    let mut number = String::new();
+
+    for c in s.trim().chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'h' | 'm' | 's' => {
+                let value: f64 = number.parse().map_err(|_| invalid())?;
+                number.clear();
+
+                total += value * match c {
+                    'h' => 3600.0,
+                    'm' => 60.0,
+                    _ => 1.0,
+                };
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    if !number.is_empty() || total <= 0.0 {
+        return Err(invalid());
+    }
+
+    Ok(Duration::from_secs_f64(total))
+}
+
+fn estimate(pairs: usize, games: usize, concurrency: usize, max_plies: usize, move_time: Duration) -> Duration {
+    let rounds = (pairs * games).div_ceil(concurrency.max(1));
+
+    move_time.mul_f64(max_plies as f64 * EXPECTED_PLIES * rounds as f64)
+}
+
+//The longest move limit that leaves room for `TARGET_GAMES` between every two of the `pairs` of bots, with as many
+//games as then fit. When even the shortest limit doesn't, it is used with as many games as fit
+pub fn plan(budget: Duration, pairs: usize, concurrency: usize, max_plies: usize) -> BudgetPlan {
+    //A round is a game on every slot, and as many rounds fit as games played one after the other would
+    let fit = |move_time: Duration| {
+        let per_game = move_time.mul_f64(max_plies as f64 * EXPECTED_PLIES).as_secs_f64();
+        let rounds = (budget.as_secs_f64() / per_game).floor() as usize;
+        let games = rounds * concurrency.max(1) / pairs.max(1);
+
+        (games - games % 2).max(MIN_GAMES)
+    };
+
+    let move_times = MOVE_TIMES_MS.map(Duration::from_millis);
+    let shortest = move_times[move_times.len() - 1];
+
+    let (games, move_time) = move_times
+        .into_iter()
+        .map(|move_time| (fit(move_time), move_time))
+        .find(|(games, _)| *games >= TARGET_GAMES)
+        .unwrap_or_else(|| (fit(shortest), shortest));
+
+    BudgetPlan {
+        games,
+        move_time,
+        estimate: estimate(pairs, games, concurrency, max_plies, move_time),
+    }
+}
+
+//Half the width of the 95% confidence interval of a rating from its score, in Elo. Half a point is added either way so
+//that bots that won or lost everything still get a finite one
+pub fn rating_margin(points: f32, games: usize) -> f32 {
+    let score = (points as f64 + 0.5) / (games as f64 + 1.0);
+    let variance = score * (1.0 - score);
+
+    let standard_error = (variance / games.max(1) as f64).sqrt();
+    //How much the rating changes for a change in the score, from the derivative of the Elo formula
+    let elo_per_score = 400.0 / (std::f64::consts::LN_10 * variance);
+
+    (1.96 * standard_error * elo_per_score) as f32
+}
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::alg::player_types;
+use crate::cli;
+use crate::tournament::{append_pgn, bot_name, play_pairing, Pairing};
+use crate::util::write_atomically;
+
+//Ratings found by the last calibration, shown in the game creator and ladders
+const RATINGS_PATH: &str = "ratings.json";
+
+//Random is always an anchor, so there is something to rate against without any engines
+const RANDOM_RATING: f32 = 400.0;
+
+//Ratings are searched for between these
+const MIN_RATING: f32 = -1000.0;
+const MAX_RATING: f32 = 4000.0;
+
+#[derive(Debug, Clone)]
+pub struct CalibrateConfig {
+    //Every bot when empty
+    pub bots: Vec<String>,
+    //Like Stockfish=1800
+    pub anchors: Vec<String>,
+    //Against every anchor, alternating colors
+    pub games: usize,
+    pub max_plies: usize,
+    pub pgn: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RatingFile {
+    //Anchors are included, at the ratings they were fixed at
+    pub ratings: BTreeMap<String, f32>,
+    pub anchors: BTreeMap<String, f32>,
+}
+
+impl RatingFile {
+    pub fn load() -> RatingFile {
+        match fs::read_to_string(RATINGS_PATH) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                println!("Could not read {}: {}", RATINGS_PATH, e);
+                RatingFile::default()
+            }),
+            Err(_) => RatingFile::default(),
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        write_atomically(Path::new(RATINGS_PATH), &serde_json::to_string_pretty(self).unwrap())
+            .map_err(|e| format!("Could not write {}: {}", RATINGS_PATH, e))
+    }
+}
+
+fn parse_anchor(anchor: &str) -> Result<(String, f32), String> {
+    let (name, rating) = anchor.rsplit_once('=').ok_or(format!("Anchors look like Stockfish=1800, not '{}'", anchor))?;
+    let rating = rating.trim().parse().map_err(|_| format!("Invalid rating '{}' for {}", rating, name))?;
+
+    Ok((bot_name(name.trim())?.to_string(), rating))
+}
+
+fn expected(rating: f32, opponent: f32) -> f32 {
+    1.0 / (1.0 + 10f32.powf((opponent - rating) / 400.0))
+}
+
+//Maximum likelihood rating from (anchor rating, points) of every game. The anchors' ratings are fixed, so every bot's
+//rating can be found on its own: the likelihood is highest where the points scored equal the points expected, which
+//only goes down as the rating goes up, so it is found by bisection. A drawn game against every anchor is added so that
+//winning or losing everything still gives a finite rating
+fn solve(games: &[(f32, f32)]) -> f32 {
+    let mut anchors: Vec<f32> = games.iter().map(|(anchor, _)| *anchor).collect();
+    anchors.sort_by(f32::total_cmp);
+    anchors.dedup();
+
+    let games: Vec<(f32, f32)> = games.iter().copied().chain(anchors.into_iter().map(|anchor| (anchor, 0.5))).collect();
+    let surplus = |rating: f32| games.iter().map(|(anchor, points)| points - expected(rating, *anchor)).sum::<f32>();
+
+    let (mut low, mut high) = (MIN_RATING, MAX_RATING);
+
+    for _ in 0..60 {
+        let mid = (low + high) / 2.0;
+
+        if surplus(mid) > 0.0 {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+//Plays a bot against every anchor and returns the bot's points in every game, by anchor
+fn play_anchors(config: &CalibrateConfig, bot: &str, anchors: &[(String, f32)], first_game: &mut usize) -> Result<Vec<Vec<f32>>, String> {
+    let mut results = vec![];
+
+    for (anchor, _) in anchors {
+        let mut points_by_game = vec![];
+
+        for game in 0..config.games.max(1) {
+            //The bot gets white first
+            let (white, black) = if game % 2 == 0 { (bot, anchor.as_str()) } else { (anchor.as_str(), bot) };
+
+            let pairing = Pairing { index: *first_game, white: white.to_string(), black: black.to_string() };
+            *first_game += 1;
+
+            let result = play_pairing(&pairing, config.max_plies, cli::args().max_move_time, 0, &mut |_, _, _| {})?;
+
+            println!("  {} vs {} {} ({})", white, black, result.result, result.reason);
+
+            let (white_points, black_points) = result.points();
+            let points = if white == bot { white_points } else { black_points };
+
+            points_by_game.push(points);
+
+            if let Some(path) = &config.pgn {
+                let mut pgn = result.pgn();
+                pgn.set_tag("Event", "Chess Arena calibration");
+
+                append_pgn(path, &pgn);
+            }
+        }
+
+        results.push(points_by_game);
+    }
+
+    Ok(results)
+}
+
+//Plays every bot against the anchors, rates them from the results and saves the ratings. Bots not calibrated this
+//time keep the ratings they had
+pub fn run(config: CalibrateConfig) -> Result<(), String> {
+    let mut anchors = vec![(String::from("Random"), RANDOM_RATING)];
+
+    for anchor in config.anchors.iter() {
+        let (name, rating) = parse_anchor(anchor)?;
+
+        match anchors.iter_mut().find(|(anchor, _)| *anchor == name) {
+            Some(existing) => existing.1 = rating,
+            None => anchors.push((name, rating)),
+        }
+    }
+
+    let bots: Vec<String> = if config.bots.is_empty() {
+        player_types()
+            .iter()
+            .map(|(name, _)| name.clone())
+            .filter(|name| name != "Human" && name != "Remote")
+            .collect()
+    } else {
+        config.bots.iter().map(|bot| bot_name(bot).map(str::to_string)).collect::<Result<_, String>>()?
+    };
+
+    let bots: Vec<String> = bots.into_iter().filter(|bot| anchors.iter().all(|(anchor, _)| anchor != bot)).collect();
+
+    if bots.is_empty() {
+        return Err(String::from("Every bot given is an anchor, so there is nothing to rate"));
+    }
+
+    let anchor_list = anchors.iter().map(|(name, rating)| format!("{} ({})", name, rating)).collect::<Vec<_>>().join(", ");
+    println!("Rating {} bots against {}, {} games each", bots.len(), anchor_list, config.games.max(1));
+
+    let mut rows = vec![];
+    let mut first_game = 0;
+
+    for (number, bot) in bots.iter().enumerate() {
+        println!("{}/{}: {}", number + 1, bots.len(), bot);
+
+        let results = play_anchors(&config, bot, &anchors, &mut first_game)?;
+
+        let games: Vec<(f32, f32)> = anchors
+            .iter()
+            .zip(results.iter())
+            .flat_map(|((_, rating), points)| points.iter().map(|points| (*rating, *points)))
+            .collect();
+        let totals: Vec<f32> = results.iter().map(|points| points.iter().sum()).collect();
+
+        let rating = solve(&games);
+
+        println!("  {} is rated {:.0}", bot, rating);
+        rows.push((bot.clone(), rating, totals));
+    }
+
+    rows.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut header = format!("{:<24} {:>7}", "Bot", "Rating");
+
+    for (anchor, _) in anchors.iter() {
+        header.push_str(&format!(" {:>12}", anchor));
+    }
+
+    let mut lines = vec![header];
+
+    for (bot, rating, totals) in rows.iter() {
+        let mut line = format!("{:<24} {:>7.0}", bot, rating);
+
+        for total in totals {
+            line.push_str(&format!(" {:>12}", format!("{}/{}", total, config.games.max(1))));
+        }
+
+        lines.push(line);
+    }
+
+    println!();
+    println!("{}", lines.join("\n"));
+
+    let mut file = RatingFile::load();
+
+    for (anchor, rating) in anchors {
+        file.ratings.insert(anchor.clone(), rating);
+        file.anchors.insert(anchor, rating);
+    }
+
+    for (bot, rating, _) in rows {
+        file.ratings.insert(bot.clone(), rating);
+        file.anchors.remove(&bot);
+    }
+
+    file.save()?;
+    println!("Ratings saved to {}", RATINGS_PATH);
+
+    Ok(())
+}
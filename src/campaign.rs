@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::alg::{is_preset, player_types};
+use crate::stats::ResultsDatabase;
+use crate::util::write_atomically;
+
+//The human's progress through the bots, kept between sessions
+const CAMPAIGN_PATH: &str = "campaign.json";
+
+//Where bots that haven't played any analysed games go in the order
+const UNRATED: f32 = 1500.0;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Opponent {
+    pub name: String,
+    //From the results database when the campaign started
+    pub rating: Option<f32>,
+
+    //From the human's side
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+
+    //Engines not given this time and bots whose files are gone can't be played, and don't hold up the campaign
+    #[serde(skip)]
+    pub available: bool,
+}
+
+impl Opponent {
+    fn new(name: &str, database: &ResultsDatabase) -> Opponent {
+        Opponent {
+            name: name.to_string(),
+            rating: database.bots.get(name).and_then(|stats| stats.rating()),
+            available: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn beaten(&self) -> bool {
+        self.wins > 0
+    }
+
+    //The human takes turns at playing white and black against every bot
+    pub fn human_color(&self) -> chess::Color {
+        if self.games().is_multiple_of(2) { chess::Color::White } else { chess::Color::Black }
+    }
+
+    pub fn record(&self) -> String {
+        format!("{}W {}D {}L", self.wins, self.draws, self.losses)
+    }
+}
+
+//Every bot from the weakest to the strongest. Beating one unlocks the next
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Campaign {
+    pub opponents: Vec<Opponent>,
+}
+
+//Bots that can be played, leaving out people, and the presets since they are other bots under another name
+fn bots() -> Vec<String> {
+    player_types()
+        .iter()
+        .map(|(name, _)| name.clone())
+        .filter(|name| name != "Human" && name != "Remote" && !is_preset(name))
+        .collect()
+}
+
+impl Campaign {
+    //Bots that are new since the campaign started join at the end, in the order of their ratings
+    pub fn load() -> Campaign {
+        let mut campaign: Campaign = match fs::read_to_string(CAMPAIGN_PATH) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                println!("Could not read {}: {}", CAMPAIGN_PATH, e);
+                Campaign::default()
+            }),
+            Err(_) => Campaign::default(),
+        };
+
+        let bots = bots();
+        let database = ResultsDatabase::load();
+
+        for opponent in campaign.opponents.iter_mut() {
+            opponent.available = bots.contains(&opponent.name);
+        }
+
+        let mut new: Vec<Opponent> = bots
+            .iter()
+            .filter(|bot| campaign.opponents.iter().all(|opponent| &opponent.name != *bot))
+            .map(|bot| Opponent::new(bot, &database))
+            .collect();
+
+        new.sort_by(|a, b| a.rating.unwrap_or(UNRATED).total_cmp(&b.rating.unwrap_or(UNRATED)));
+        campaign.opponents.extend(new);
+
+        campaign
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        write_atomically(Path::new(CAMPAIGN_PATH), &serde_json::to_string_pretty(self).unwrap())
+            .map_err(|e| format!("Could not write {}: {}", CAMPAIGN_PATH, e))
+    }
+
+    //The first bot that still has to be beaten, None once they all are
+    pub fn next(&self) -> Option<usize> {
+        self.opponents.iter().position(|opponent| opponent.available && !opponent.beaten())
+    }
+
+    pub fn unlocked(&self, idx: usize) -> bool {
+        self.opponents[idx].available && self.next().is_none_or(|next| idx <= next)
+    }
+
+    pub fn beaten(&self) -> usize {
+        self.opponents.iter().filter(|opponent| opponent.available && opponent.beaten()).count()
+    }
+
+    pub fn available(&self) -> usize {
+        self.opponents.iter().filter(|opponent| opponent.available).count()
+    }
+}
+
+//Saves a finished campaign game. `points` are the human's
+pub fn record(opponent: &str, points: f32) {
+    let mut campaign = Campaign::load();
+
+    let Some(entry) = campaign.opponents.iter_mut().find(|entry| entry.name == opponent) else {
+        return;
+    };
+
+    if points >= 1.0 {
+        entry.wins += 1;
+        println!("Campaign: beat {}", opponent);
+    } else if points > 0.0 {
+        entry.draws += 1;
+    } else {
+        entry.losses += 1;
+    }
+
+    if let Err(e) = campaign.save() {
+        println!("{}", e);
+    }
+}
@@ -0,0 +1,599 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+
+use crate::alg::external::{EngineLimits, EngineSpec};
+use crate::budget;
+use crate::clock::TimeControl;
+use crate::tournament::PairingConstraints;
+use crate::variant::Variant;
+
+#[derive(Parser, Debug)]
+#[command(name = "chessarena", about = "An arena for bad chess bots")]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Side length in pixels of board screenshots (F12)
+    #[arg(long, default_value_t = 1024)]
+    pub screenshot_size: u32,
+
+    /// Broadcast the events of the game on screen as JSON over WebSocket on this port. Tournaments need --spectate
+    #[arg(long)]
+    pub broadcast: Option<u16>,
+
+    /// Write the game on screen to this file for streaming overlays, as JSON if it ends in .json and as text otherwise
+    #[arg(long)]
+    pub overlay: Option<PathBuf>,
+
+    /// Serve the overlay on localhost on this port, as JSON at /overlay.json and as text at /overlay.txt
+    #[arg(long)]
+    pub overlay_port: Option<u16>,
+
+    /// Milliseconds between writes of the overlay file
+    #[arg(long, default_value_t = 500)]
+    pub overlay_interval: u64,
+
+    /// Serve a local HTTP API for querying and controlling the game on screen on this port
+    #[arg(long)]
+    pub api: Option<u16>,
+
+    /// Port to host network games on
+    #[arg(long, default_value_t = crate::net::DEFAULT_PORT)]
+    pub net_port: u16,
+
+    /// External engine to add as a player, as NAME=PROTOCOL:COMMAND where PROTOCOL is uci or xboard. Can be repeated
+    #[arg(long = "engine", value_name = "NAME=PROTOCOL:COMMAND")]
+    pub engines: Vec<EngineSpec>,
+
+    /// Limits for an --engine as NAME:LIMITS, where LIMITS is a comma separated list of memory=MB, cores=LIST (like 0-3, needs taskset), timeout=SECONDS to kill it once it goes that long past its think time without answering, and isolate to run it in an empty temporary directory. Can be repeated
+    #[arg(long = "engine-limits", value_name = "NAME:LIMITS")]
+    pub engine_limits: Vec<EngineLimits>,
+
+    /// Scale factor for text and buttons, overriding the saved one. Ctrl +/- changes it while running
+    #[arg(long)]
+    pub ui_scale: Option<f32>,
+
+    /// ONNX model to add as a bot named after the file, on top of the ones in res/onnx. Takes a [1, 18, 8, 8] board and gives a policy of 4096 source and destination square logits, a value, or both. Needs libonnxruntime, found through ORT_DYLIB_PATH. Can be repeated
+    #[cfg(feature = "onnx")]
+    #[arg(long = "onnx", value_name = "PATH")]
+    pub onnx_models: Vec<PathBuf>,
+
+    /// Folder to load bot plugins (dynamic libraries) from
+    #[arg(long, default_value = "plugins")]
+    pub plugin_dir: PathBuf,
+
+    /// Frame rate limit while the window is in use, 0 for no limit
+    #[arg(long, default_value_t = 60)]
+    pub max_fps: u32,
+
+    /// Frame rate limit after a few seconds without input or moves, 0 for no limit
+    #[arg(long, default_value_t = 10)]
+    pub idle_fps: u32,
+
+    /// Don't wait for the display's refresh when presenting frames
+    #[arg(long)]
+    pub no_vsync: bool,
+
+    /// Start in instant mode, where bot games run as fast as the engines allow with no frame rate limit, replay delay or speech. I toggles it during a game
+    #[arg(long)]
+    pub instant: bool,
+
+    /// Milliseconds between moves when replaying a finished game. - and = change it during the replay
+    #[arg(long, default_value_t = 800)]
+    pub replay_delay: u64,
+
+    /// Read out moves and results with text to speech
+    #[arg(long)]
+    pub speak: bool,
+
+    /// Command to read text out with instead of the system default (say, espeak or PowerShell). The text is passed as the last argument
+    #[arg(long, value_name = "COMMAND")]
+    pub speech_command: Option<String>,
+
+    /// Engine that analyses bot vs bot games for the eval bar, eval graph and arrows without playing: Classic, or the name of a UCI --engine
+    #[arg(long, value_name = "ENGINE")]
+    pub observer: Option<String>,
+
+    /// Longest any bot may think about a move in seconds, also in untimed games and tournaments. Engines that go past it are told to stop and play the best move they have so far
+    #[arg(long, value_name = "SECS", value_parser = parse_seconds)]
+    pub max_move_time: Option<Duration>,
+
+    /// White's time control in the game creator, like 5+3, 10s+0, "5+0 delay 3" or Untimed. Overrides the saved one
+    #[arg(long, value_parser = parse_time_control)]
+    pub white_time: Option<TimeControl>,
+
+    /// Black's time control in the game creator. Giving the sides different ones plays a time odds game
+    #[arg(long, value_parser = parse_time_control)]
+    pub black_time: Option<TimeControl>,
+}
+
+fn parse_variant(s: &str) -> Result<Variant, String> {
+    Variant::from_name(s).ok_or(format!("'{}' is not a variant", s))
+}
+
+fn parse_option(s: &str) -> Result<(String, String), String> {
+    s.split_once('=').map(|(name, value)| (name.to_string(), value.to_string())).ok_or(format!("Expected NAME=VALUE, got '{}'", s))
+}
+
+fn parse_seconds(s: &str) -> Result<Duration, String> {
+    s.parse::<f32>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs > 0.0)
+        .map(Duration::from_secs_f32)
+        .ok_or(format!("'{}' is not a positive number of seconds", s))
+}
+
+fn parse_time_control(s: &str) -> Result<TimeControl, String> {
+    TimeControl::from_name(s).ok_or(format!("'{}' is not a time control", s))
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Play on lichess.org through the Bot API
+    LichessBot {
+        /// Name of the bot to play with
+        bot: String,
+
+        /// Lichess API token with the bot:play scope
+        #[arg(long, env = "LICHESS_TOKEN")]
+        token: String,
+    },
+
+    /// Evolve weighted evaluators with round robins between bots and save the best ones as players
+    Evolve {
+        /// Bots in every generation
+        #[arg(long, default_value_t = 16)]
+        population: usize,
+
+        #[arg(long, default_value_t = 10)]
+        generations: u32,
+
+        /// Plies after which a game is called a draw
+        #[arg(long, default_value_t = 200)]
+        max_plies: usize,
+
+        /// Standard deviation of the noise added to weights of new bots
+        #[arg(long, default_value_t = 0.3)]
+        mutation: f32,
+
+        /// Name of the saved bot
+        #[arg(long, default_value = "Evolved")]
+        name: String,
+
+        /// Number of bots from the last generation to save, numbered after the name when more than one
+        #[arg(long, default_value_t = 1)]
+        keep: usize,
+    },
+
+    /// Train a weighted evaluator by playing games against itself and learning from the results
+    Train {
+        #[arg(long, default_value_t = 1000)]
+        games: usize,
+
+        /// Plies after which a game is called a draw
+        #[arg(long, default_value_t = 200)]
+        max_plies: usize,
+
+        #[arg(long, default_value_t = 0.05)]
+        learning_rate: f32,
+
+        /// Chance of playing a random move instead of the best one
+        #[arg(long, default_value_t = 0.1)]
+        epsilon: f64,
+
+        /// Games between checkpoints
+        #[arg(long, default_value_t = 100)]
+        checkpoint_every: usize,
+
+        #[arg(long, default_value = "checkpoints")]
+        checkpoint_dir: PathBuf,
+
+        /// Also save checkpoints to the weights folder so they show up as players
+        #[arg(long)]
+        register: bool,
+
+        /// Name of the trained bot. Checkpoints are named after it and the number of games played
+        #[arg(long, default_value = "Trained")]
+        name: String,
+
+        /// Weights file to continue training from
+        #[arg(long)]
+        resume: Option<PathBuf>,
+    },
+
+    /// Learn which moves are played from a PGN database and add a bot that plays like it
+    TrainPolicy {
+        /// PGN file with the games to learn from
+        pgn: PathBuf,
+
+        /// Name of the bot
+        #[arg(long, default_value = "Plays like the database")]
+        name: String,
+
+        /// Plies from the start of each game that are remembered exactly as an opening book
+        #[arg(long, default_value_t = 16)]
+        book_plies: usize,
+    },
+
+    /// Tune the Classic bot's material and piece square weights to predict the results of games in a PGN file
+    Tune {
+        /// PGN file with finished games
+        pgn: PathBuf,
+
+        #[arg(long, default_value_t = 200)]
+        epochs: usize,
+
+        #[arg(long, default_value_t = 50.0)]
+        learning_rate: f32,
+
+        /// Plies at the start of every game that aren't used
+        #[arg(long, default_value_t = 8)]
+        skip_plies: usize,
+
+        /// Where to write the weights. The Classic bot loads them from the default path
+        #[arg(long, default_value = crate::alg::classic::EVAL_PARAMS_PATH)]
+        output: PathBuf,
+    },
+
+    /// Play a round robin tournament between bots, printing the standings at the end
+    Tournament {
+        /// Names of the bots taking part
+        #[arg(required = true, num_args = 2..)]
+        bots: Vec<String>,
+
+        /// Games between every two bots, alternating colors
+        #[arg(long, default_value_t = 2)]
+        games: usize,
+
+        /// Games played at the same time, defaulting to one per CPU core
+        #[arg(long)]
+        concurrency: Option<usize>,
+
+        /// Plies after which a game is called a draw
+        #[arg(long, default_value_t = 300)]
+        max_plies: usize,
+
+        /// Plies at the start of every game that are random legal moves before the bots take over, so that bots that
+        /// always play the same moves still have different games. They are commented as random in the PGN
+        #[arg(long, default_value_t = 0)]
+        random_plies: usize,
+
+        /// Seconds a game can go without a move before the watchdog gives up on it, playing it again from the last move
+        /// or having the bot that stalled forfeit. Defaults to ten times the move limit, or 10 minutes without one
+        #[arg(long, value_name = "SECS", value_parser = parse_seconds)]
+        stall_limit: Option<Duration>,
+
+        /// File to append every finished game to
+        #[arg(long)]
+        pgn: Option<PathBuf>,
+
+        /// Watch the games in a window that switches between boards, jumping to ones where something just happened
+        #[arg(long)]
+        spectate: bool,
+
+        /// Seconds the spectator view stays on a board when nothing interesting happens elsewhere
+        #[arg(long, default_value_t = 10)]
+        switch_every: u64,
+
+        /// Engine to score every bot's moves against once the games are over, saving their accuracy to the results database: Classic, or the name of a UCI --engine
+        #[arg(long, value_name = "ENGINE")]
+        accuracy: Option<String>,
+
+        /// Depth the --accuracy engine analyses every position to, capped at what the engine can reach
+        #[arg(long, default_value_t = 12)]
+        accuracy_depth: u32,
+
+        /// Directory to write a Markdown and HTML report of the tournament to, with every game as PGN
+        #[arg(long, value_name = "DIR")]
+        report: Option<PathBuf>,
+
+        /// Write the terms behind every move of bots that explain their moves into the --pgn file, as comments
+        #[arg(long, requires = "pgn")]
+        eval_terms: bool,
+
+        /// File to append the terms behind every game's moves to, as a line of JSON per game
+        #[arg(long, value_name = "FILE")]
+        eval_terms_json: Option<PathBuf>,
+
+        /// Wall clock time the tournament has to finish in, like 2h, 90m or 1h30m. The games between every two bots and
+        /// the move limit are picked to fit instead of --games and --max-move-time, and games that would start after it are left out
+        #[arg(long, value_parser = budget::parse_budget, conflicts_with = "games")]
+        budget: Option<Duration>,
+
+        /// Only print the final standings
+        #[arg(long, conflicts_with = "json_lines")]
+        quiet: bool,
+
+        /// Print a JSON object per line for every finished game and the final standings instead of text
+        #[arg(long)]
+        json_lines: bool,
+
+        #[command(flatten)]
+        constraints: PairingConstraints,
+    },
+
+    /// Run a tournament whose games are played by workers connecting over TCP, on this or other machines
+    Coordinate {
+        /// Names of the bots taking part. Workers need to have every one of them
+        #[arg(required = true, num_args = 2..)]
+        bots: Vec<String>,
+
+        /// Games between every two bots, alternating colors
+        #[arg(long, default_value_t = 2)]
+        games: usize,
+
+        /// Games played on this machine at the same time, on top of the workers' games
+        #[arg(long, default_value_t = 0)]
+        local: usize,
+
+        /// Plies after which a game is called a draw
+        #[arg(long, default_value_t = 300)]
+        max_plies: usize,
+
+        /// Plies at the start of every game that are random legal moves before the bots take over, so that bots that
+        /// always play the same moves still have different games. They are commented as random in the PGN
+        #[arg(long, default_value_t = 0)]
+        random_plies: usize,
+
+        /// Seconds a game can go without a move before the watchdog gives up on it, playing it again from the last move
+        /// or having the bot that stalled forfeit. Defaults to ten times the move limit, or 10 minutes without one
+        #[arg(long, value_name = "SECS", value_parser = parse_seconds)]
+        stall_limit: Option<Duration>,
+
+        /// File to append every finished game to
+        #[arg(long)]
+        pgn: Option<PathBuf>,
+
+        /// Port workers connect to
+        #[arg(long, default_value_t = crate::distributed::DEFAULT_COORDINATOR_PORT)]
+        port: u16,
+
+        #[command(flatten)]
+        constraints: PairingConstraints,
+    },
+
+    /// Play tournament games handed out by a coordinator until it has none left
+    Worker {
+        /// Address of the coordinator, with or without a port
+        address: String,
+
+        /// Games played at the same time, defaulting to one per CPU core
+        #[arg(long)]
+        concurrency: Option<usize>,
+    },
+
+    /// Play a king of the hill ladder: the first bot defends its title against the others in turn, and whoever beats
+    /// the champion takes over
+    Ladder {
+        /// Names of the bots taking part, starting with the first champion
+        #[arg(required = true, num_args = 2..)]
+        bots: Vec<String>,
+
+        /// Games in every match, alternating colors. The champion keeps the title on a tied match
+        #[arg(long, default_value_t = 2)]
+        games: usize,
+
+        /// Times the queue of challengers is gone through
+        #[arg(long, default_value_t = 1)]
+        rounds: usize,
+
+        /// Plies after which a game is called a draw
+        #[arg(long, default_value_t = 300)]
+        max_plies: usize,
+
+        /// File to append every finished game to
+        #[arg(long)]
+        pgn: Option<PathBuf>,
+
+        /// File to write the history of the title and the reigns to
+        #[arg(long)]
+        log: Option<PathBuf>,
+    },
+
+    /// Play a bot against a UCI engine at increasing strength, Stockfish's Skill Level by default, and report the
+    /// first level it fails to beat
+    Gauntlet {
+        /// Name of the bot to measure
+        bot: String,
+
+        /// Name of the UCI --engine to play against. Defaults to the first one given
+        #[arg(long)]
+        engine: Option<String>,
+
+        /// Games at every level, alternating colors. The bot has to score more than half the points to go on
+        #[arg(long, default_value_t = 2)]
+        games: usize,
+
+        /// Limit the engine's strength with UCI_LimitStrength and UCI_Elo rather than Skill Level
+        #[arg(long)]
+        elo: bool,
+
+        /// Skill levels or Elo points between levels. Defaults to 1 skill level or 200 Elo
+        #[arg(long)]
+        step: Option<i64>,
+
+        /// Plies after which a game is called a draw
+        #[arg(long, default_value_t = 300)]
+        max_plies: usize,
+
+        /// File to append every finished game to
+        #[arg(long)]
+        pgn: Option<PathBuf>,
+    },
+
+    /// Play every bot against opponents with known ratings and save the ratings found, which the game creator and
+    /// ladders show
+    Calibrate {
+        /// Names of the bots to rate. Defaults to every bot
+        bots: Vec<String>,
+
+        /// Opponent with a fixed rating, like Stockfish=1800 for an --engine limited to that strength. Random is always
+        /// one, at 400 unless given another rating
+        #[arg(long)]
+        anchor: Vec<String>,
+
+        /// Games against every anchor, alternating colors
+        #[arg(long, default_value_t = 10)]
+        games: usize,
+
+        /// Plies after which a game is called a draw
+        #[arg(long, default_value_t = 300)]
+        max_plies: usize,
+
+        /// File to append every finished game to
+        #[arg(long)]
+        pgn: Option<PathBuf>,
+    },
+
+    /// Play a game between two bots with a fixed random seed and save it as a replay file
+    Record {
+        white: String,
+        black: String,
+
+        /// Replay file to write, usually ending in .bcr
+        #[arg(long)]
+        output: PathBuf,
+
+        /// Seed for the bots' random choices, picked at random if not given
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Plies white searches, for bots that search
+        #[arg(long)]
+        white_depth: Option<u32>,
+
+        /// Plies black searches, for bots that search
+        #[arg(long)]
+        black_depth: Option<u32>,
+
+        /// An option of white, as in the game creator's options editor, like "Epsilon %=10" or Hash=64. Can be repeated
+        #[arg(long = "white-option", value_name = "NAME=VALUE", value_parser = parse_option)]
+        white_options: Vec<(String, String)>,
+
+        /// An option of black, like --white-option. Can be repeated
+        #[arg(long = "black-option", value_name = "NAME=VALUE", value_parser = parse_option)]
+        black_options: Vec<(String, String)>,
+
+        #[arg(long, default_value = "Standard", value_parser = parse_variant)]
+        variant: Variant,
+
+        /// Plies after which the game is called a draw
+        #[arg(long, default_value_t = 300)]
+        max_plies: usize,
+    },
+
+    /// Watch a replay file, or check that the bots still play the same moves with its seed
+    Replay {
+        file: PathBuf,
+
+        /// Play the game again without the GUI and compare the moves instead of showing it
+        #[arg(long)]
+        verify: bool,
+    },
+
+    /// Play back every game added to the PGN files in a folder, for showing games written by tournaments elsewhere. --replay-delay sets the speed
+    Watch {
+        dir: PathBuf,
+
+        /// Seconds the end of a game stays up before the next one starts
+        #[arg(long, default_value_t = 5)]
+        hold: u64,
+
+        /// Also play the games already in the folder, not just new ones
+        #[arg(long)]
+        existing: bool,
+    },
+
+    /// Score the bots in a PGN file by how far their moves fall short of a reference engine's, saving the results to the results database
+    Accuracy {
+        /// PGN file with finished games
+        pgn: PathBuf,
+
+        /// Classic, or the name of a UCI --engine
+        #[arg(long, default_value = "Classic")]
+        reference: String,
+
+        /// Depth the reference engine analyses every position to, capped at what the engine can reach
+        #[arg(long, default_value_t = 12)]
+        depth: u32,
+    },
+
+    /// Show every bot's results, average centipawn loss and accuracy from the results database
+    Stats,
+
+    /// Time a bot's moves on a fixed set of positions. Only built in bots count the positions they search
+    Bench {
+        /// Name of the bot to benchmark
+        bot: String,
+
+        /// File with one FEN per line to use instead of the built in positions
+        #[arg(long)]
+        positions: Option<PathBuf>,
+
+        /// Moves to time from every position, for bots that move too quickly to time once
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        repeat: u32,
+    },
+
+    /// Report where a bot's games against itself go different ways, to check whether it is as deterministic as it seems
+    #[command(group = clap::ArgGroup::new("games_from").required(true).multiple(true).args(["pgn", "bot"]))]
+    Divergence {
+        /// PGN file whose games between a bot and itself are compared, by bot
+        #[arg(long)]
+        pgn: Option<PathBuf>,
+
+        /// Bot to play against itself, or with --pgn the only bot to report on
+        #[arg(long)]
+        bot: Option<String>,
+
+        /// Games the bot plays against itself when there is no --pgn
+        #[arg(long, default_value_t = 10)]
+        games: usize,
+
+        /// Plies after which a game is called a draw
+        #[arg(long, default_value_t = 300)]
+        max_plies: usize,
+    },
+
+    /// Save a bot with its options as a preset, which is picked and played in tournaments by its own name
+    SavePreset {
+        /// Name of the preset
+        name: String,
+
+        /// Bot the preset plays as
+        #[arg(long)]
+        bot: String,
+
+        /// Plies the bot searches, for bots that search
+        #[arg(long)]
+        depth: Option<u32>,
+
+        /// An option of the bot, as in the game creator's options editor, like "Epsilon %=10" or Hash=64. Can be repeated
+        #[arg(long = "option", value_name = "NAME=VALUE", value_parser = parse_option)]
+        options: Vec<(String, String)>,
+
+        /// Shown next to the preset in the player list
+        #[arg(long)]
+        description: Option<String>,
+    },
+
+    /// Add a preset file someone shared to the presets
+    ImportPreset {
+        file: PathBuf,
+    },
+
+    /// Write a preset to a file to share it
+    ExportPreset {
+        name: String,
+        file: PathBuf,
+    },
+}
+
+static ARGS: OnceLock<Args> = OnceLock::new();
+
+pub fn args() -> &'static Args {
+    ARGS.get_or_init(Args::parse)
+}
@@ -0,0 +1,305 @@
+use std::cell::Cell;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+//Time a player gets on every move before their clock is charged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Delay {
+    #[default]
+    None,
+    //US style: the clock only starts running once the delay is over
+    Simple(Duration),
+    //The clock runs straight away, and afterwards gives back the time used up to the delay
+    Bronstein(Duration),
+}
+
+impl Delay {
+    pub fn duration(&self) -> Duration {
+        match self {
+            Delay::None => Duration::ZERO,
+            Delay::Simple(delay) | Delay::Bronstein(delay) => *delay,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeControl {
+    //The clocks only count how long each side took
+    #[default]
+    Untimed,
+    Timed {
+        base: Duration,
+        increment: Duration,
+        delay: Delay,
+    },
+}
+
+//Base seconds, increment seconds and delay of the time controls the game creator cycles through. The short ones are
+//for giving bots time odds
+const PRESETS: [(u64, u64, Delay); 11] = [
+    (10, 0, Delay::None),
+    (30, 0, Delay::None),
+    (60, 0, Delay::None),
+    (180, 0, Delay::None),
+    (180, 2, Delay::None),
+    (300, 0, Delay::None),
+    (300, 0, Delay::Simple(Duration::from_secs(3))),
+    (300, 0, Delay::Bronstein(Duration::from_secs(3))),
+    (600, 5, Delay::None),
+    (900, 10, Delay::None),
+    (1800, 0, Delay::None),
+];
+
+impl TimeControl {
+    pub fn minutes(minutes: u64, increment_secs: u64) -> TimeControl {
+        TimeControl::seconds(minutes * 60, increment_secs)
+    }
+
+    pub fn seconds(base_secs: u64, increment_secs: u64) -> TimeControl {
+        TimeControl::Timed {
+            base: Duration::from_secs(base_secs),
+            increment: Duration::from_secs(increment_secs),
+            delay: Delay::None,
+        }
+    }
+
+    pub fn with_delay(self, new_delay: Delay) -> TimeControl {
+        match self {
+            TimeControl::Untimed => TimeControl::Untimed,
+            TimeControl::Timed { base, increment, .. } => TimeControl::Timed { base, increment, delay: new_delay },
+        }
+    }
+
+    //Like 5+3, followed by the delay if there is one, e.g. "5+0 delay 3" or "5+0 Bronstein 3". Bases that aren't
+    //whole minutes are written in seconds, like 10s+0
+    pub fn name(&self) -> String {
+        match self {
+            TimeControl::Untimed => String::from("Untimed"),
+            TimeControl::Timed { base, increment, delay } => {
+                let name = if base.as_secs().is_multiple_of(60) && !base.is_zero() {
+                    format!("{}+{}", base.as_secs() / 60, increment.as_secs())
+                } else {
+                    format!("{}s+{}", base.as_secs(), increment.as_secs())
+                };
+
+                match delay {
+                    Delay::None => name,
+                    Delay::Simple(delay) => format!("{} delay {}", name, delay.as_secs()),
+                    Delay::Bronstein(delay) => format!("{} Bronstein {}", name, delay.as_secs()),
+                }
+            }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TimeControl> {
+        if name == "Untimed" {
+            return Some(TimeControl::Untimed);
+        }
+
+        let mut parts = name.split_whitespace();
+
+        let (base, increment) = parts.next()?.split_once('+')?;
+
+        let time_control = match base.strip_suffix('s') {
+            Some(secs) => TimeControl::seconds(secs.parse().ok()?, increment.parse().ok()?),
+            None => TimeControl::minutes(base.parse().ok()?, increment.parse().ok()?),
+        };
+
+        let delay = match (parts.next(), parts.next()) {
+            (None, _) => Delay::None,
+            (Some(kind), Some(secs)) => {
+                let secs = Duration::from_secs(secs.parse().ok()?);
+
+                match kind.to_ascii_lowercase().as_str() {
+                    "delay" => Delay::Simple(secs),
+                    "bronstein" => Delay::Bronstein(secs),
+                    _ => return None,
+                }
+            }
+            (Some(_), None) => return None,
+        };
+
+        Some(time_control.with_delay(delay))
+    }
+
+    pub fn next(&self) -> TimeControl {
+        let preset = |(base, increment, delay): (u64, u64, Delay)| TimeControl::seconds(base, increment).with_delay(delay);
+
+        let idx = PRESETS.iter().position(|p| *self == preset(*p));
+
+        match idx.map(|i| i + 1) {
+            None => preset(PRESETS[0]),
+            Some(i) if i < PRESETS.len() => preset(PRESETS[i]),
+            Some(_) => TimeControl::Untimed,
+        }
+    }
+
+    pub fn is_timed(&self) -> bool {
+        matches!(self, TimeControl::Timed { .. })
+    }
+
+    pub fn increment(&self) -> Duration {
+        match self {
+            TimeControl::Untimed => Duration::ZERO,
+            TimeControl::Timed { increment, .. } => *increment,
+        }
+    }
+
+    pub fn delay(&self) -> Delay {
+        match self {
+            TimeControl::Untimed => Delay::None,
+            TimeControl::Timed { delay, .. } => *delay,
+        }
+    }
+
+    //How much of the time spent on a move in progress comes off the clock right now
+    pub fn charged(&self, elapsed: Duration) -> Duration {
+        match self.delay() {
+            Delay::Simple(delay) => elapsed.saturating_sub(delay),
+            _ => elapsed,
+        }
+    }
+
+    //Time left on a clock that counts down, after `used` has been spent on earlier moves, `bonus` given back for them
+    //and `pending` spent on the move being thought about
+    pub fn remaining(&self, used: Duration, bonus: Duration, pending: Duration) -> Option<Duration> {
+        match self {
+            TimeControl::Untimed => None,
+            TimeControl::Timed { base, .. } => Some((*base + bonus).saturating_sub(used + self.charged(pending))),
+        }
+    }
+
+    //The TimeControl and TimeDelay PGN tags. PGN has no standard way to write delays
+    pub fn pgn_tags(&self) -> Option<(String, Option<String>)> {
+        let TimeControl::Timed { base, increment, delay } = self else {
+            return None;
+        };
+
+        let delay = match delay {
+            Delay::None => None,
+            Delay::Simple(delay) => Some(format!("{} simple", delay.as_secs())),
+            Delay::Bronstein(delay) => Some(format!("{} Bronstein", delay.as_secs())),
+        };
+
+        Some((format!("{}+{}", base.as_secs(), increment.as_secs()), delay))
+    }
+
+    //Time given back after a move that took `elapsed`. Either delay ends up charging the same, only the clock
+    //display differs while the move is being thought about
+    pub fn refund(&self, elapsed: Duration) -> Duration {
+        self.increment() + self.delay().duration().min(elapsed)
+    }
+}
+
+//Decides how long an engine may think about its next move
+pub trait TimeManager {
+    fn budget(&self, remaining: Duration, increment: Duration, ply: usize) -> Duration;
+}
+
+//The same time for every move, as long as the clock allows it
+#[derive(Debug, Clone, Copy)]
+pub struct FixedPerMove(pub Duration);
+
+impl TimeManager for FixedPerMove {
+    fn budget(&self, _remaining: Duration, _increment: Duration, _ply: usize) -> Duration {
+        self.0
+    }
+}
+
+//A share of the remaining time, assuming the game lasts `moves_left` more moves, plus most of the increment
+#[derive(Debug, Clone, Copy)]
+pub struct Proportional {
+    pub moves_left: u32,
+}
+
+impl TimeManager for Proportional {
+    fn budget(&self, remaining: Duration, increment: Duration, _ply: usize) -> Duration {
+        remaining / self.moves_left.max(1) + increment.mul_f32(0.8)
+    }
+}
+
+//Thinks normally until the clock gets low, then only spends what it gets back from the increment
+#[derive(Debug, Clone, Copy)]
+pub struct SuddenDeath {
+    pub normal: Proportional,
+    pub panic_below: Duration,
+}
+
+impl TimeManager for SuddenDeath {
+    fn budget(&self, remaining: Duration, increment: Duration, ply: usize) -> Duration {
+        if remaining < self.panic_below {
+            increment.mul_f32(0.5) + remaining / 100
+        } else {
+            self.normal.budget(remaining, increment, ply)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeManagerKind {
+    Fixed,
+    #[default]
+    Proportional,
+    SuddenDeath,
+}
+
+pub const ALL_TIME_MANAGERS: [TimeManagerKind; 3] = [TimeManagerKind::Fixed, TimeManagerKind::Proportional, TimeManagerKind::SuddenDeath];
+
+impl TimeManagerKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeManagerKind::Fixed => "Fixed",
+            TimeManagerKind::Proportional => "Proportional",
+            TimeManagerKind::SuddenDeath => "Sudden death",
+        }
+    }
+
+    pub fn next(&self) -> TimeManagerKind {
+        let idx = ALL_TIME_MANAGERS.iter().position(|m| m == self).unwrap();
+
+        ALL_TIME_MANAGERS[(idx + 1) % ALL_TIME_MANAGERS.len()]
+    }
+
+    pub fn manager(&self) -> Box<dyn TimeManager> {
+        match self {
+            TimeManagerKind::Fixed => Box::new(FixedPerMove(Duration::from_secs(1))),
+            TimeManagerKind::Proportional => Box::new(Proportional { moves_left: 30 }),
+            TimeManagerKind::SuddenDeath => Box::new(SuddenDeath {
+                normal: Proportional { moves_left: 30 },
+                panic_below: Duration::from_secs(10),
+            }),
+        }
+    }
+
+    //Never more than half the remaining time, so that engines don't lose on time by themselves
+    pub fn allocate(&self, remaining: Duration, increment: Duration, ply: usize) -> Duration {
+        self.manager().budget(remaining, increment, ply).min(remaining / 2)
+    }
+}
+
+//What an engine knows about the clock when it is asked for a move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThinkTime {
+    pub remaining: Duration,
+    pub opponent_remaining: Duration,
+    pub increment: Duration,
+    //Free time at the start of every move from a simple or Bronstein delay
+    pub delay: Duration,
+    //Time the engine's time manager gives it for this move, including the delay
+    pub budget: Duration,
+}
+
+thread_local! {
+    //Clock of the game the engine on this thread is playing, or none in untimed games
+    static THINK_TIME: Cell<Option<ThinkTime>> = const { Cell::new(None) };
+}
+
+pub fn set_think_time(think_time: Option<ThinkTime>) {
+    THINK_TIME.with(|t| t.set(think_time));
+}
+
+pub fn think_time() -> Option<ThinkTime> {
+    THINK_TIME.with(|t| t.get())
+}
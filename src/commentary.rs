@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+
+use chess::{Board, ChessMove, Color, MoveGen, Piece, Rank, Square, ALL_PIECES, EMPTY};
+
+//An eval change this big in pawns gets a comment
+const SWING: f32 = 2.0;
+
+//Plies before players are teased for keeping their pieces at home
+const BACK_RANK_PLIES: usize = 20;
+
+//Quiet moves after which the fifty move rule gets a mention
+const QUIET_MOVES_WARNING: u32 = 40;
+
+fn piece_name(piece: Piece) -> &'static str {
+    match piece {
+        Piece::Pawn => "pawn",
+        Piece::Knight => "knight",
+        Piece::Bishop => "bishop",
+        Piece::Rook => "rook",
+        Piece::Queen => "queen",
+        Piece::King => "king",
+    }
+}
+
+fn article(piece: Piece) -> &'static str {
+    match piece {
+        Piece::Pawn => "a pawn",
+        Piece::Knight => "a knight",
+        Piece::Bishop => "a bishop",
+        Piece::Rook => "a rook",
+        Piece::Queen => "a queen",
+        Piece::King => "the king",
+    }
+}
+
+fn home_rank(color: Color) -> Rank {
+    match color {
+        Color::White => Rank::First,
+        Color::Black => Rank::Eighth,
+    }
+}
+
+//Most valuable piece of the side that just moved that the side to move can take without it being taken back.
+//A piece on `traded` already paid for itself
+fn hanging_piece(board: &Board, traded: Option<Square>) -> Option<(Piece, Square)> {
+    let mut best: Option<(Piece, Square)> = None;
+
+    for capture in MoveGen::new_legal(board) {
+        let Some(victim) = board.piece_on(capture.get_dest()).filter(|_| Some(capture.get_dest()) != traded) else {
+            continue;
+        };
+
+        if victim == Piece::Pawn || best.is_some_and(|(b, _)| b.to_index() >= victim.to_index()) {
+            continue;
+        }
+
+        let after = board.make_move_new(capture);
+        let recapture = MoveGen::new_legal(&after).any(|m| m.get_dest() == capture.get_dest());
+
+        if !recapture {
+            best = Some((victim, capture.get_dest()));
+        }
+    }
+
+    best
+}
+
+//Says what is happening in a game from the moves, the board and the observer's evals. Remarks about a player's
+//habits are only made once per game
+#[derive(Debug)]
+pub struct Commentator {
+    white: String,
+    black: String,
+    said: HashSet<String>,
+    //Where each side last left a piece hanging, so that a piece nobody takes isn't mentioned every move
+    hanging: [Option<Square>; 2],
+}
+
+impl Commentator {
+    pub fn new(white: &str, black: &str) -> Commentator {
+        Commentator {
+            white: white.to_string(),
+            black: black.to_string(),
+            said: HashSet::new(),
+            hanging: [None; 2],
+        }
+    }
+
+    //Names edited during the game are used from the next remark on
+    pub fn rename(&mut self, white: &str, black: &str) {
+        self.white = white.to_string();
+        self.black = black.to_string();
+    }
+
+    fn name(&self, color: Color) -> &str {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    fn once(&mut self, key: String, text: String, lines: &mut Vec<String>) {
+        if self.said.insert(key) {
+            lines.push(text);
+        }
+    }
+
+    //`history` has every position before `after`, the way the game stores it
+    pub fn on_move(&mut self, before: &Board, m: ChessMove, after: &Board, history: &[Board], quiet_moves: u32) -> Vec<String> {
+        let mut lines = vec![];
+
+        let mover = before.side_to_move();
+        let name = self.name(mover).to_string();
+        let piece = before.piece_on(m.get_source());
+
+        let captured = before.piece_on(m.get_dest());
+
+        if let Some(captured) = captured.filter(|p| *p != Piece::Pawn) {
+            lines.push(format!("{} takes {}", name, article(captured)));
+        }
+
+        if let Some(promotion) = m.get_promotion() {
+            lines.push(format!("{} promotes to {}!", name, article(promotion)));
+        }
+
+        if piece == Some(Piece::King) && m.get_source().get_file().to_index().abs_diff(m.get_dest().get_file().to_index()) == 2 {
+            self.once(format!("castles {:?}", mover), format!("{} castles", name), &mut lines);
+        }
+
+        if after.status() == chess::BoardStatus::Ongoing {
+            let traded = piece.zip(captured).filter(|(p, c)| c.to_index() >= p.to_index()).map(|_| m.get_dest());
+
+            let hanging = hanging_piece(after, traded);
+
+            if let Some((hung, square)) = hanging.filter(|(_, square)| Some(*square) != self.hanging[mover.to_index()]) {
+                lines.push(format!("{} hangs {} on {}", name, article(hung), square));
+            }
+
+            self.hanging[mover.to_index()] = hanging.map(|(_, square)| square);
+        }
+
+        if *after.checkers() != EMPTY && lines.is_empty() {
+            lines.push(format!("{} gives check", name));
+        }
+
+        let repetitions = history.iter().filter(|b| *b == after).count();
+
+        if repetitions == 2 {
+            lines.push(String::from("Threefold repetition available"));
+        }
+
+        if quiet_moves == QUIET_MOVES_WARNING {
+            lines.push(format!("{} quiet moves in a row, the fifty move rule is getting close", quiet_moves));
+        }
+
+        if history.len() >= BACK_RANK_PLIES {
+            let own = *after.color_combined(mover);
+            let pieces = own & !*after.pieces(Piece::Pawn);
+            let home = chess::get_rank(home_rank(mover));
+
+            if pieces & !home == EMPTY {
+                self.once(
+                    format!("back rank {:?}", mover),
+                    format!("{} refuses to leave the back rank", name),
+                    &mut lines,
+                );
+            }
+        }
+
+        //The king reaching the other half of the board
+        let king = after.king_square(mover);
+        let advanced = match mover {
+            Color::White => king.get_rank().to_index() >= 4,
+            Color::Black => king.get_rank().to_index() <= 3,
+        };
+
+        if advanced {
+            self.once(format!("king walk {:?}", mover), format!("{}'s king goes for a walk", name), &mut lines);
+        }
+
+        lines
+    }
+
+    //Comments on how much the move by `mover` changed the observer's eval, which is from white's point of view
+    pub fn on_eval(&mut self, mover: Color, before: f32, after: f32) -> Option<String> {
+        let gain = match mover {
+            Color::White => after - before,
+            Color::Black => before - after,
+        };
+
+        let name = self.name(mover);
+
+        if gain <= -SWING {
+            Some(format!("A blunder by {}! The eval goes from {:+.1} to {:+.1}", name, before, after))
+        } else if gain >= SWING {
+            Some(format!("Strong move by {}, the eval goes from {:+.1} to {:+.1}", name, before, after))
+        } else {
+            None
+        }
+    }
+
+    //Material both sides have left, for the end of the game
+    pub fn material_summary(board: &Board) -> String {
+        let count = |color: Color| {
+            ALL_PIECES[..5]
+                .iter()
+                .filter_map(|piece| {
+                    let n = (*board.pieces(*piece) & *board.color_combined(color)).popcnt();
+                    (n > 0).then(|| format!("{} {}{}", n, piece_name(*piece), if n > 1 { "s" } else { "" }))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let describe = |pieces: Vec<String>| if pieces.is_empty() { String::from("only the king") } else { pieces.join(", ") };
+
+        format!("White has {}. Black has {}", describe(count(Color::White)), describe(count(Color::Black)))
+    }
+}
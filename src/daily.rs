@@ -0,0 +1,175 @@
+use std::fs;
+use std::path::Path;
+
+use chess::Board;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::alg::{is_preset, relay, ALL_PLAYER_TYPES};
+use crate::clock::TimeControl;
+use crate::util::{current_date, pgn_date, write_atomically};
+use crate::variant;
+
+//Every daily game played or watched, kept between sessions
+const DAILY_PATH: &str = "daily.json";
+
+//Moves in UCI, played from the usual start position
+const OPENINGS: [(&str, &str); 10] = [
+    ("Italian Game", "e2e4 e7e5 g1f3 b8c6 f1c4"),
+    ("Ruy Lopez", "e2e4 e7e5 g1f3 b8c6 f1b5"),
+    ("Sicilian Defence", "e2e4 c7c5"),
+    ("French Defence", "e2e4 e7e6 d2d4 d7d5"),
+    ("Caro-Kann Defence", "e2e4 c7c6 d2d4 d7d5"),
+    ("Scandinavian Defence", "e2e4 d7d5"),
+    ("Queen's Gambit", "d2d4 d7d5 c2c4"),
+    ("King's Indian Defence", "d2d4 g8f6 c2c4 g7g6"),
+    ("London System", "d2d4 d7d5 g1f3 g8f6 c1f4"),
+    ("English Opening", "c2c4"),
+];
+
+//Minutes and increment seconds
+const TIME_CONTROLS: [(u64, u64); 4] = [(1, 0), (3, 2), (5, 0), (10, 5)];
+
+//The same two bots, opening and time control for everyone on the same day
+#[derive(Debug, Clone)]
+pub struct Matchup {
+    pub date: String,
+    pub white: String,
+    pub black: String,
+    pub opening: &'static str,
+    moves: &'static str,
+    pub time_control: TimeControl,
+}
+
+//Only built in bots, so external engines and plugins given on the command line don't change the day's pick.
+//Presets are other bots under another name
+fn bots() -> Vec<&'static str> {
+    ALL_PLAYER_TYPES
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| !["Human", "Remote", relay::NAME].contains(name) && !is_preset(name))
+        .collect()
+}
+
+impl Matchup {
+    pub fn today() -> Matchup {
+        let (year, month, day) = current_date();
+        let mut rng = StdRng::seed_from_u64(year as u64 * 10000 + month as u64 * 100 + day as u64);
+
+        let bots = bots();
+        let white = rng.gen_range(0..bots.len());
+        //Two different bots
+        let black = (white + rng.gen_range(1..bots.len())) % bots.len();
+
+        let (opening, moves) = OPENINGS[rng.gen_range(0..OPENINGS.len())];
+        let (minutes, increment) = TIME_CONTROLS[rng.gen_range(0..TIME_CONTROLS.len())];
+
+        Matchup {
+            date: pgn_date(),
+            white: bots[white].to_string(),
+            black: bots[black].to_string(),
+            opening,
+            moves,
+            time_control: TimeControl::minutes(minutes, increment),
+        }
+    }
+
+    pub fn start_board(&self) -> Board {
+        self.moves
+            .split_whitespace()
+            .fold(Board::default(), |board, uci| board.make_move_new(variant::parse_uci(uci).unwrap()))
+    }
+
+    pub fn description(&self) -> String {
+        format!("{} vs {}, {}, {}", self.white, self.black, self.opening, self.time_control.name())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyGame {
+    pub date: String,
+    //Who actually played, "Human" for a side the human took over
+    pub white: String,
+    pub black: String,
+    pub opening: String,
+    pub time_control: String,
+    pub result: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyLog {
+    pub games: Vec<DailyGame>,
+}
+
+impl DailyLog {
+    pub fn load() -> DailyLog {
+        match fs::read_to_string(DAILY_PATH) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                println!("Could not read {}: {}", DAILY_PATH, e);
+                DailyLog::default()
+            }),
+            Err(_) => DailyLog::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        write_atomically(Path::new(DAILY_PATH), &serde_json::to_string_pretty(self).unwrap())
+            .map_err(|e| format!("Could not write {}: {}", DAILY_PATH, e))
+    }
+
+    //Days in a row up to today with at least one daily game, counting yesterday's run while today's game is still to
+    //come
+    pub fn streak(&self) -> usize {
+        let (year, month, day) = current_date();
+        let days = |date: &str| {
+            let mut parts = date.split('.').map(|part| part.parse::<i64>().ok());
+            let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+            Some(days_from_civil(year, month, day))
+        };
+
+        let today = days_from_civil(year, month as i64, day as i64);
+        let played: Vec<i64> = self.games.iter().filter_map(|game| days(&game.date)).collect();
+
+        let mut day = if played.contains(&today) { today } else { today - 1 };
+        let mut streak = 0;
+
+        while played.contains(&day) {
+            streak += 1;
+            day -= 1;
+        }
+
+        streak
+    }
+}
+
+//Inverse of current_date's conversion, see http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+//Saves a finished daily game under the date it was started on
+pub fn record(matchup: &Matchup, white: &str, black: &str, result: &str) {
+    let mut log = DailyLog::load();
+
+    log.games.push(DailyGame {
+        date: matchup.date.clone(),
+        white: white.to_string(),
+        black: black.to_string(),
+        opening: matchup.opening.to_string(),
+        time_control: matchup.time_control.name(),
+        result: result.to_string(),
+    });
+
+    if let Err(e) = log.save() {
+        println!("{}", e);
+    }
+}
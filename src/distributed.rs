@@ -0,0 +1,237 @@
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cli;
+use crate::tournament::{play_pairing, GameResult, Pairing, Tournament, TournamentConfig};
+use crate::variant;
+
+pub const DEFAULT_COORDINATOR_PORT: u16 = 7879;
+
+//Sent as one JSON object per line, like network games
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerMessage {
+    //Worker asking for a game
+    Ready,
+    Play {
+        index: usize,
+        white: String,
+        black: String,
+        max_plies: usize,
+        #[serde(default)]
+        random_plies: usize,
+    },
+    //No games left
+    Done,
+    Result {
+        index: usize,
+        result: String,
+        reason: String,
+        moves: Vec<String>,
+        #[serde(default)]
+        random_plies: usize,
+    },
+    //The worker couldn't play the game, usually because it doesn't have one of the bots
+    Failed {
+        index: usize,
+        error: String,
+    },
+}
+
+fn send(stream: &mut TcpStream, message: &WorkerMessage) -> io::Result<()> {
+    stream.write_all(format!("{}\n", serde_json::to_string(message).unwrap()).as_bytes())
+}
+
+//None once the other side hung up
+fn receive(reader: &mut impl BufRead) -> io::Result<Option<WorkerMessage>> {
+    let mut line = String::new();
+
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+
+    serde_json::from_str(&line).map(Some).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+}
+
+//Hands pairings to one worker connection until there are none left. A game the worker was playing when it went away
+//is handed to someone else
+fn serve_worker(tournament: &Tournament, stream: TcpStream, addr: SocketAddr) {
+    let mut writer = stream;
+    let mut reader = BufReader::new(writer.try_clone().unwrap());
+
+    let mut playing: Option<Pairing> = None;
+
+    let res: io::Result<()> = (|| loop {
+        match receive(&mut reader)? {
+            None => return Ok(()),
+
+            Some(WorkerMessage::Ready) => match tournament.next_pairing() {
+                Some(pairing) => {
+                    send(&mut writer, &WorkerMessage::Play {
+                        index: pairing.index,
+                        white: pairing.white.clone(),
+                        black: pairing.black.clone(),
+                        max_plies: tournament.config.max_plies,
+                        random_plies: tournament.config.random_plies,
+                    })?;
+
+                    playing = Some(pairing);
+                }
+                None => {
+                    send(&mut writer, &WorkerMessage::Done)?;
+                    return Ok(());
+                }
+            },
+
+            Some(WorkerMessage::Result { index, result, reason, moves, random_plies }) => {
+                let Some(pairing) = playing.take().filter(|p| p.index == index) else {
+                    println!("Worker {} sent a result for a game it wasn't playing", addr);
+                    continue;
+                };
+
+                tournament.record(GameResult {
+                    pairing,
+                    result,
+                    reason,
+                    moves: moves.iter().map(String::as_str).filter_map(variant::parse_uci).collect(),
+                    //Workers don't say, so games played on them are never copied
+                    reproducible: false,
+                    copy_of: None,
+                    terms: vec![],
+                    random_plies,
+                });
+            }
+
+            Some(WorkerMessage::Failed { index, error }) => {
+                if let Some(pairing) = playing.take().filter(|p| p.index == index) {
+                    tournament.skip(&pairing, &format!("{} (on worker {})", error, addr));
+                }
+            }
+
+            Some(message) => println!("Unexpected message from worker {}: {:?}", addr, message),
+        }
+    })();
+
+    if let Err(e) = res {
+        println!("Lost worker {}: {}", addr, e);
+    }
+
+    if let Some(pairing) = playing {
+        println!("Game {} will be played again by another worker", pairing.index + 1);
+        tournament.requeue(pairing);
+    }
+}
+
+//Plays a tournament with games handed out to workers over TCP, as well as `config.concurrency` games on this machine
+pub fn coordinate(config: TournamentConfig, port: u16) -> Result<(), String> {
+    let tournament = Tournament::new(config)?;
+
+    let listener = TcpListener::bind(("0.0.0.0", port)).map_err(|e| format!("Could not listen on port {}: {}", port, e))?;
+    //Non-blocking so that the loop notices when the tournament is over
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+    println!(
+        "{} games between {} bots, {} at a time here. Waiting for workers on port {}",
+        tournament.pairings.len(),
+        tournament.config.bots.len(),
+        tournament.slots.len(),
+        port
+    );
+
+    tournament.start();
+
+    while !tournament.finished() {
+        match listener.accept() {
+            Ok((stream, addr)) => {
+                println!("Worker connected from {}", addr);
+
+                stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+
+                let tournament = tournament.clone();
+                thread::spawn(move || serve_worker(&tournament, stream, addr));
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(100)),
+            Err(e) => return Err(format!("Could not accept workers: {}", e)),
+        }
+    }
+
+    while tournament.running() {
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    println!();
+    println!("{}", tournament.standings_text());
+
+    Ok(())
+}
+
+//One connection to the coordinator, playing a game at a time
+fn work(address: &str) -> io::Result<usize> {
+    let mut writer = TcpStream::connect(address)?;
+    let mut reader = BufReader::new(writer.try_clone()?);
+
+    let mut played = 0;
+
+    loop {
+        send(&mut writer, &WorkerMessage::Ready)?;
+
+        let Some(WorkerMessage::Play { index, white, black, max_plies, random_plies }) = receive(&mut reader)? else {
+            return Ok(played);
+        };
+
+        let pairing = Pairing { index, white, black };
+
+        let reply = match play_pairing(&pairing, max_plies, cli::args().max_move_time, random_plies, &mut |_, _, _| {}) {
+            Ok(result) => {
+                println!("Game {}: {} vs {} {} ({})", index + 1, pairing.white, pairing.black, result.result, result.reason);
+
+                WorkerMessage::Result {
+                    index,
+                    result: result.result,
+                    reason: result.reason,
+                    moves: result.moves.into_iter().map(variant::move_to_uci).collect(),
+                    random_plies: result.random_plies,
+                }
+            }
+            Err(error) => WorkerMessage::Failed { index, error },
+        };
+
+        send(&mut writer, &reply)?;
+        played += 1;
+    }
+}
+
+//Plays games for a coordinator until it runs out, `concurrency` at a time
+pub fn run_worker(address: &str, concurrency: usize) -> Result<(), String> {
+    let address = if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_COORDINATOR_PORT)
+    };
+
+    println!("Playing games for {}, {} at a time", address, concurrency);
+
+    let handles: Vec<_> = (0..concurrency.max(1))
+        .map(|_| {
+            let address = address.clone();
+            thread::spawn(move || work(&address))
+        })
+        .collect();
+
+    let mut played = 0;
+
+    for handle in handles {
+        played += handle
+            .join()
+            .map_err(|_| String::from("A game thread crashed"))?
+            .map_err(|e| format!("Lost the coordinator at {}: {}", address, e))?;
+    }
+
+    println!("Played {} games", played);
+
+    Ok(())
+}
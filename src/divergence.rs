@@ -0,0 +1,178 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use chess::{Board, ChessMove};
+
+use crate::cli;
+use crate::pgn::PgnGame;
+use crate::tournament::{bot_name, play_pairing, Pairing};
+use crate::util::move_to_SAN;
+use crate::variant::Variant;
+
+//Only the earliest are listed, since everything after a split differs anyway
+const SHOWN_POSITIONS: usize = 10;
+const BAR_WIDTH: usize = 40;
+
+//A bot's games against itself, all from the same position
+struct MirrorGames {
+    start: Board,
+    games: Vec<Vec<ChessMove>>,
+}
+
+//Games that go the same way up to some ply and then split show where a bot isn't as deterministic as it may seem
+struct Divergence {
+    //Games that are move for move the same as an earlier one
+    repeats: usize,
+    //For the other games after the first, how many split off from every earlier game at each ply
+    split_plies: BTreeMap<usize, usize>,
+    //Positions the bot played more than one move from, with the ply they came up at and how often each move was played
+    branches: Vec<(usize, Board, BTreeMap<String, usize>)>,
+}
+
+fn common_prefix(a: &[ChessMove], b: &[ChessMove]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+fn analyse(mirror: &MirrorGames) -> Divergence {
+    let mut repeats = 0;
+    let mut split_plies = BTreeMap::new();
+
+    for (i, game) in mirror.games.iter().enumerate().skip(1) {
+        let earlier = &mirror.games[..i];
+
+        if earlier.contains(game) {
+            repeats += 1;
+            continue;
+        }
+
+        let shared = earlier.iter().map(|other| common_prefix(game, other)).max().unwrap_or(0);
+        *split_plies.entry(shared + 1).or_insert(0) += 1;
+    }
+
+    //Every position keeps the first ply it came up at
+    let mut played: Vec<(usize, Board, BTreeMap<String, usize>)> = vec![];
+    let mut indices: HashMap<Board, usize> = HashMap::new();
+
+    for game in mirror.games.iter() {
+        let mut board = mirror.start;
+
+        for (ply, m) in game.iter().enumerate() {
+            let san = move_to_SAN(&board, *m);
+
+            let index = *indices.entry(board).or_insert_with(|| {
+                played.push((ply + 1, board, BTreeMap::new()));
+                played.len() - 1
+            });
+
+            *played[index].2.entry(san).or_insert(0) += 1;
+            board = board.make_move_new(*m);
+        }
+    }
+
+    let mut branches: Vec<_> = played.into_iter().filter(|(_, _, moves)| moves.len() > 1).collect();
+    branches.sort_by_key(|(ply, _, _)| *ply);
+
+    Divergence { repeats, split_plies, branches }
+}
+
+fn print_report(bot: &str, mirror: &MirrorGames) {
+    let divergence = analyse(mirror);
+    let games = mirror.games.len();
+
+    println!();
+    println!("{}: {} games against itself, {} of them different", bot, games, games - divergence.repeats);
+
+    if divergence.branches.is_empty() {
+        if games > 1 {
+            println!("  Every game went the same way, so it always picks the same move in the same position");
+        }
+
+        return;
+    }
+
+    println!("  Games the same as an earlier one: {}", divergence.repeats);
+    println!("  Ply where games split from every earlier one:");
+
+    let most = divergence.split_plies.values().copied().max().unwrap_or(1);
+
+    for (ply, count) in divergence.split_plies.iter() {
+        println!("    {:>4}  {:<width$}  {}", ply, "#".repeat((count * BAR_WIDTH).div_ceil(most)), count, width = BAR_WIDTH);
+    }
+
+    println!("  Positions it played more than one move from ({} in all):", divergence.branches.len());
+
+    for (ply, board, moves) in divergence.branches.iter().take(SHOWN_POSITIONS) {
+        let moves: Vec<String> = moves.iter().map(|(san, count)| format!("{} ({})", san, count)).collect();
+        println!("    Ply {:>3}  {}  {}", ply, board, moves.join(", "));
+    }
+}
+
+//Mirror matches in a PGN file, by bot. Games from another start position than the bot's first are left out
+fn from_pgn(path: &Path) -> Result<BTreeMap<String, MirrorGames>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+    let mut bots: BTreeMap<String, MirrorGames> = BTreeMap::new();
+
+    for game in PgnGame::parse_all(&text)? {
+        let (Some(white), Some(black)) = (game.get_tag("White"), game.get_tag("Black")) else {
+            continue;
+        };
+
+        if white != black || game.variant() != Variant::Standard {
+            continue;
+        }
+
+        let mirror = bots.entry(white.to_string()).or_insert(MirrorGames { start: game.start, games: vec![] });
+
+        if mirror.start == game.start {
+            mirror.games.push(game.moves);
+        }
+    }
+
+    Ok(bots)
+}
+
+//Reports where a bot's games against itself go different ways, from the mirror matches in a PGN file or from `games`
+//new ones
+pub fn run(pgn: Option<&Path>, bot: Option<&str>, games: usize, max_plies: usize) -> Result<(), String> {
+    let bots = match (pgn, bot) {
+        (Some(path), _) => {
+            let mut bots = from_pgn(path)?;
+
+            if let Some(bot) = bot {
+                bots.retain(|name, _| name.eq_ignore_ascii_case(bot));
+            }
+
+            bots
+        }
+
+        (None, Some(bot)) => {
+            let bot = bot_name(bot)?;
+            let mut mirror = MirrorGames { start: Board::default(), games: vec![] };
+
+            println!("Playing {} games of {} against itself", games, bot);
+
+            for index in 0..games {
+                let pairing = Pairing { index, white: bot.to_string(), black: bot.to_string() };
+                let result = play_pairing(&pairing, max_plies, cli::args().max_move_time, 0, &mut |_, _, _| {})?;
+
+                println!("  Game {}: {} ({}), {} plies", index + 1, result.result, result.reason, result.moves.len());
+                mirror.games.push(result.moves);
+            }
+
+            BTreeMap::from([(bot.to_string(), mirror)])
+        }
+
+        (None, None) => return Err(String::from("Give a PGN file or a bot")),
+    };
+
+    if bots.is_empty() {
+        return Err(String::from("No games of a bot against itself"));
+    }
+
+    for (bot, mirror) in bots.iter() {
+        print_report(bot, mirror);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::sync::Mutex;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use serde::Serialize;
+
+use crate::net::Side;
+use crate::variant::Variant;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameEvent {
+    GameStarted {
+        white: String,
+        black: String,
+        fen: String,
+        variant: Variant,
+    },
+    Move {
+        ply: usize,
+        san: String,
+        uci: String,
+        fen: String,
+    },
+    GameOver {
+        result: String,
+        reason: String,
+    },
+    //Moves were taken back, leaving the first `ply` moves of the game
+    Takeback {
+        ply: usize,
+        fen: String,
+    },
+    Paused {
+        paused: bool,
+    },
+    //Time left on both clocks after a move in a timed game
+    Clock {
+        white_ms: u64,
+        black_ms: u64,
+    },
+    FlagFell {
+        side: Side,
+    },
+    DrawOffered {
+        side: Side,
+    },
+    //A line from the commentary feed
+    Commentary {
+        text: String,
+    },
+    //Observer analysis of the current position, in pawns from white's point of view
+    Eval {
+        eval: f32,
+        depth: u32,
+        pv: Vec<String>,
+    },
+}
+
+static SUBSCRIBERS: Mutex<Vec<Sender<GameEvent>>> = Mutex::new(Vec::new());
+
+pub fn subscribe() -> Receiver<GameEvent> {
+    let (sender, receiver) = channel();
+
+    SUBSCRIBERS.lock().unwrap().push(sender);
+
+    receiver
+}
+
+pub fn publish(event: GameEvent) {
+    //Subscribers that dropped their receiver are removed
+    SUBSCRIBERS.lock().unwrap().retain(|s| s.send(event.clone()).is_ok());
+}
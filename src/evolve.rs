@@ -0,0 +1,164 @@
+use std::path::Path;
+use std::thread;
+
+use chess::{Board, Color};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::alg::weighted::{WeightedBot, ALL_FEATURES, WEIGHTS_DIR};
+use crate::arena::play_game;
+use crate::variant::Variant;
+
+#[derive(Debug, Clone)]
+pub struct EvolveConfig {
+    pub population: usize,
+    pub generations: u32,
+    pub max_plies: usize,
+    //Standard deviation of the noise added to mutated weights
+    pub mutation: f32,
+    pub name: String,
+    //How many of the best bots of the last generation are saved
+    pub keep: usize,
+}
+
+//Normally distributed noise, using the Box-Muller transform
+fn gaussian(rng: &mut impl Rng) -> f32 {
+    let u: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let v: f32 = rng.gen();
+
+    (-2.0 * u.ln()).sqrt() * (2.0 * std::f32::consts::PI * v).cos()
+}
+
+fn random_bot(rng: &mut impl Rng) -> WeightedBot {
+    let mut bot = WeightedBot::default();
+
+    for feature in ALL_FEATURES {
+        bot.set_weight(feature, rng.gen_range(-1.0..1.0));
+    }
+
+    bot
+}
+
+//Uniform crossover followed by gaussian mutation of every weight
+fn offspring(a: &WeightedBot, b: &WeightedBot, mutation: f32, rng: &mut impl Rng) -> WeightedBot {
+    let mut child = WeightedBot::default();
+
+    for feature in ALL_FEATURES {
+        let parent = if rng.gen_bool(0.5) { a } else { b };
+        child.set_weight(feature, parent.weight(feature) + gaussian(rng) * mutation);
+    }
+
+    child
+}
+
+//Better of two random bots
+fn tournament_select<'a>(population: &'a [WeightedBot], rng: &mut impl Rng) -> &'a WeightedBot {
+    let a = population.choose(rng).unwrap();
+    let b = population.choose(rng).unwrap();
+
+    if a.fitness >= b.fitness {
+        a
+    } else {
+        b
+    }
+}
+
+//Every bot plays every other bot once with each color. Fitness is the share of points scored
+fn round_robin(population: &mut [WeightedBot], max_plies: usize) {
+    let pairings: Vec<(usize, usize)> = (0..population.len())
+        .flat_map(|white| (0..population.len()).filter(move |black| *black != white).map(move |black| (white, black)))
+        .collect();
+
+    let threads = thread::available_parallelism().map_or(4, |n| n.get());
+    let chunk_size = pairings.len().div_ceil(threads).max(1);
+
+    let bots: &[WeightedBot] = population;
+
+    let results: Vec<(usize, usize, f32)> = thread::scope(|scope| {
+        let handles: Vec<_> = pairings
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|(white, black)| {
+                            let mut white_engine = bots[*white].engine(Color::White);
+                            let mut black_engine = bots[*black].engine(Color::Black);
+
+                            let game = play_game(&mut white_engine, &mut black_engine, Variant::Standard, Board::default(), max_plies);
+
+                            (*white, *black, game.white_score())
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+    });
+
+    let games_each = (2 * (population.len() - 1)).max(1) as f32;
+
+    for bot in population.iter_mut() {
+        bot.fitness = 0.0;
+    }
+
+    for (white, black, score) in results {
+        population[white].fitness += score / games_each;
+        population[black].fitness += (1.0 - score) / games_each;
+    }
+}
+
+pub fn run(config: &EvolveConfig) -> Result<(), String> {
+    if config.population < 2 {
+        return Err(String::from("The population needs at least 2 bots"));
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut population: Vec<WeightedBot> = (0..config.population).map(|_| random_bot(&mut rng)).collect();
+
+    //A quarter of every generation survives unchanged
+    let elites = (config.population / 4).max(1);
+
+    for generation in 1..=config.generations {
+        for bot in population.iter_mut() {
+            bot.generation = generation;
+        }
+
+        round_robin(&mut population, config.max_plies);
+        population.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+
+        let best = &population[0];
+        println!(
+            "Generation {}/{}: best fitness {:.3}, mean {:.3}",
+            generation,
+            config.generations,
+            best.fitness,
+            population.iter().map(|b| b.fitness).sum::<f32>() / population.len() as f32
+        );
+        println!("  {:?}", best.weights);
+
+        if generation == config.generations {
+            break;
+        }
+
+        let mut next: Vec<WeightedBot> = population[..elites].to_vec();
+
+        while next.len() < config.population {
+            let a = tournament_select(&population, &mut rng);
+            let b = tournament_select(&population, &mut rng);
+            next.push(offspring(a, b, config.mutation, &mut rng));
+        }
+
+        population = next;
+    }
+
+    for (i, bot) in population.iter_mut().take(config.keep).enumerate() {
+        bot.name = if config.keep == 1 { config.name.clone() } else { format!("{} {}", config.name, i + 1) };
+
+        let path = bot.save(Path::new(WEIGHTS_DIR))?;
+        println!("Saved {} (fitness {:.3}) to {:?}", bot.name, bot.fitness, path);
+    }
+
+    Ok(())
+}
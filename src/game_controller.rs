@@ -0,0 +1,410 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::{Board, ChessMove, Color, Piece};
+
+use crate::alg::chess_alg::{get_move_with, nodes, set_cancel_flag, set_live_nodes, set_stop_flag, EngineSettings, GameConfig, SearchStats, STOP_GRACE};
+use crate::alg::draw_claim;
+use crate::clock::{self, ThinkTime, TimeControl};
+use crate::gui::chess_display::{GameOutcome, PlayerType};
+use crate::mobility::Mobility;
+use crate::variant::{self, Variant, VariantState};
+
+//A search running on another thread for the computer to move
+#[derive(Debug)]
+struct Search {
+    //Moves played when it started
+    ply: usize,
+    started: Instant,
+    max_move_time: Option<Duration>,
+    cancel: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    //Counted so far, for the performance HUD
+    nodes: Arc<AtomicU64>,
+    result: Arc<Mutex<Option<(ChessMove, MoveStats)>>>,
+}
+
+//How the search for a move went
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MoveStats {
+    pub time: Duration,
+    pub nodes: u64,
+    pub depth: Option<u32>,
+    pub table_hit_rate: Option<f32>,
+}
+
+impl MoveStats {
+    pub fn nodes_per_second(&self) -> u64 {
+        (self.nodes as f64 / self.time.as_secs_f64().max(0.000001)) as u64
+    }
+}
+
+//The position, its history and the players of a game, with the computer's searches started, collected and cancelled
+//as moves are played and taken back. Knows nothing about clocks or drawing
+#[derive(Debug)]
+pub struct GameController {
+    pub variant: Variant,
+    pub start_board: Board,
+    pub board: Board,
+    pub state: VariantState,
+    pub history: Vec<Board>,
+    pub state_history: Vec<VariantState>,
+    pub moves: Vec<ChessMove>,
+    pub reversable_moves: u32,
+    //Halfmove clock of the start position's FEN, which the chess crate's boards don't keep
+    pub start_reversable_moves: u32,
+    pub outcome: Option<GameOutcome>,
+    //Legal moves before every move played
+    pub mobility: Mobility,
+
+    pub white_player: PlayerType,
+    pub black_player: PlayerType,
+
+    search: Option<Search>,
+    //Engines still getting ready for the game
+    preparing: Arc<AtomicUsize>,
+    //Of each color's last move that was searched for
+    pub move_stats: [Option<MoveStats>; 2],
+}
+
+impl GameController {
+    pub fn new(white_player: PlayerType, black_player: PlayerType) -> GameController {
+        GameController {
+            variant: Variant::Standard,
+            start_board: Board::default(),
+            board: Board::default(),
+            state: VariantState::default(),
+            history: Vec::new(),
+            state_history: Vec::new(),
+            moves: Vec::new(),
+            reversable_moves: 0,
+            start_reversable_moves: 0,
+            outcome: None,
+            mobility: Mobility::default(),
+
+            white_player,
+            black_player,
+
+            search: None,
+            preparing: Arc::new(AtomicUsize::new(0)),
+            move_stats: [None; 2],
+        }
+    }
+
+    //Starts again from `start`, forgetting every move
+    pub fn reset(&mut self, variant: Variant, start: Board) {
+        self.cancel_search();
+
+        self.variant = variant;
+        self.start_board = start;
+        self.board = start;
+        self.state = VariantState::default();
+        self.history.clear();
+        self.state_history.clear();
+        self.moves.clear();
+        self.reversable_moves = 0;
+        self.start_reversable_moves = 0;
+        self.outcome = None;
+        self.mobility.clear();
+        self.move_stats = [None; 2];
+
+        //Like in `end`, an engine still winding down its search is left alone
+        for player in [&self.white_player, &self.black_player] {
+            if let PlayerType::Computer(engine) = player {
+                if let Ok(mut engine) = engine.try_lock() {
+                    engine.new_game();
+                }
+            }
+        }
+    }
+
+    pub fn player(&self, color: Color) -> &PlayerType {
+        match color {
+            Color::White => &self.white_player,
+            Color::Black => &self.black_player,
+        }
+    }
+
+    pub fn current_player(&self) -> &PlayerType {
+        self.player(self.board.side_to_move())
+    }
+
+    pub fn legal_moves(&self) -> Vec<ChessMove> {
+        self.variant.rules().legal_moves(&self.board, &self.state)
+    }
+
+    //Engines, network peers and plugins can send anything, so their moves are checked before they reach the board. An
+    //illegal one is logged and loses the game for the side that sent it
+    pub fn check_move(&self, m: ChessMove, source: &str) -> Result<ChessMove, GameOutcome> {
+        if self.legal_moves().contains(&m) {
+            return Ok(m);
+        }
+
+        let color = self.board.side_to_move();
+        println!("{} played illegal move {} as {:?} in {}, forfeiting", source, variant::move_to_uci(m), color, self.board);
+
+        Err(GameOutcome::IllegalMove(!color))
+    }
+
+    //Plays a move without checking it or whether the game is over
+    pub fn apply(&mut self, m: ChessMove) {
+        if self.board.piece_on(m.get_source()) == Some(Piece::Pawn) || self.board.piece_on(m.get_dest()).is_some() {
+            self.reversable_moves = 0;
+        } else {
+            self.reversable_moves += 1;
+        }
+
+        let rules = self.variant.rules();
+        self.mobility.record(self.board.side_to_move(), rules.count_legal_moves(&self.board, &self.state));
+
+        self.history.push(self.board);
+        self.state_history.push(self.state);
+        self.moves.push(m);
+        (self.board, self.state) = rules.apply(&self.board, &self.state, m);
+    }
+
+    //How the game ended if the rules of the variant or the draw rules every variant shares end it here
+    pub fn rules_outcome(&self) -> Option<GameOutcome> {
+        if let Some(outcome) = self.variant.rules().outcome(&self.board, &self.state) {
+            return Some(outcome);
+        }
+
+        let repetitions = self.history.iter().filter(|pos| **pos == self.board).count();
+
+        if let Some(outcome) = draw_claim::forced_draw(repetitions, self.reversable_moves) {
+            return Some(outcome);
+        }
+
+        //Either player can claim the draw. People always do, and an engine that is still busy doesn't
+        let outcome = draw_claim::claimable_draw(repetitions, self.reversable_moves)?;
+        let claims = |player: &PlayerType| match player {
+            PlayerType::Computer(engine) => engine.try_lock().is_ok_and(|mut engine| engine.claim_draw(&self.board)),
+            _ => true,
+        };
+
+        if claims(&self.white_player) || claims(&self.black_player) {
+            Some(outcome)
+        } else {
+            None
+        }
+    }
+
+    //Stops the search and lets the engines know how it went
+    pub fn end(&mut self, outcome: GameOutcome) {
+        self.cancel_search();
+
+        for player in [&self.white_player, &self.black_player] {
+            //An engine that is still winding down its search is left alone
+            if let PlayerType::Computer(engine) = player {
+                if let Ok(mut engine) = engine.try_lock() {
+                    engine.game_over(outcome.pgn_result(), outcome.get_text());
+                }
+            }
+        }
+
+        self.outcome = Some(outcome);
+    }
+
+    //Lets each engine get ready on its own thread. Nothing is searched until they all are
+    pub fn prepare(&mut self, time_controls: [TimeControl; 2]) {
+        for color in [Color::White, Color::Black] {
+            let PlayerType::Computer(engine) = self.player(color) else {
+                continue;
+            };
+
+            let engine = engine.clone();
+            let config = GameConfig {
+                color,
+                variant: self.variant,
+                start: self.start_board,
+                time_control: time_controls[color.to_index()],
+            };
+            let preparing = self.preparing.clone();
+
+            preparing.fetch_add(1, Ordering::Relaxed);
+
+            thread::spawn(move || {
+                variant::set_current(config.variant, VariantState::default());
+                engine.lock().unwrap().prepare(&config);
+
+                preparing.fetch_sub(1, Ordering::Relaxed);
+            });
+        }
+    }
+
+    pub fn preparing(&self) -> bool {
+        self.preparing.load(Ordering::Relaxed) > 0
+    }
+
+    pub fn searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    //How long the search going on has been running and the nodes it counted so far
+    pub fn live_search(&self) -> Option<(Duration, u64)> {
+        self.search.as_ref().map(|search| (search.started.elapsed(), search.nodes.load(Ordering::Relaxed)))
+    }
+
+    //Starts the computer to move thinking on another thread. Does nothing on a human's turn, while it is already
+    //thinking or while the engines are still getting ready
+    pub fn start_search(&mut self, think_time: Option<ThinkTime>, settings: EngineSettings) {
+        if self.outcome.is_some() || self.searching() || self.preparing() {
+            return;
+        }
+
+        let PlayerType::Computer(engine) = self.current_player() else {
+            return;
+        };
+
+        let engine = engine.clone();
+        let board = self.variant.rules().player_view(&self.board, self.board.side_to_move());
+        let (variant, state) = (self.variant, self.state);
+
+        let search = Search {
+            ply: self.moves.len(),
+            started: Instant::now(),
+            max_move_time: settings.max_move_time,
+            cancel: Arc::new(AtomicBool::new(false)),
+            stop: Arc::new(AtomicBool::new(false)),
+            nodes: Arc::new(AtomicU64::new(0)),
+            result: Arc::new(Mutex::new(None)),
+        };
+
+        let cancel = search.cancel.clone();
+        let stop = search.max_move_time.map(|_| search.stop.clone());
+        let live_nodes = search.nodes.clone();
+        let output = search.result.clone();
+
+        thread::spawn(move || {
+            variant::set_current(variant, state);
+            clock::set_think_time(think_time);
+            set_cancel_flag(Some(cancel.clone()));
+            set_stop_flag(stop);
+            set_live_nodes(Some(live_nodes));
+
+            let mut engine = engine.lock().unwrap();
+
+            //A search cancelled while waiting for the last one to stop never starts
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+
+            //The thread is new for every search, so its node count starts at 0
+            let started = Instant::now();
+            let m = get_move_with(&mut *engine, board, settings);
+
+            let SearchStats { depth, table_hit_rate, nodes: reported } = engine.search_stats();
+            let stats = MoveStats { time: started.elapsed(), nodes: reported.unwrap_or_else(nodes), depth, table_hit_rate };
+
+            output.lock().unwrap().replace((m, stats));
+        });
+
+        self.search = Some(search);
+    }
+
+    //The move the computer found, once its search is over. A search past its max move time is told to stop, and an
+    //engine that still hasn't answered a little later forfeits
+    pub fn poll_search(&mut self) -> Option<Result<ChessMove, GameOutcome>> {
+        let search = self.search.as_ref()?;
+        let found = search.result.lock().unwrap().take();
+
+        let Some((m, stats)) = found else {
+            let max_move_time = search.max_move_time?;
+            let elapsed = search.started.elapsed();
+
+            if elapsed > max_move_time + STOP_GRACE {
+                println!("The engine took {:.1}s and didn't stop when told to, forfeiting", elapsed.as_secs_f32());
+                self.cancel_search();
+
+                return Some(Err(GameOutcome::Forfeit(!self.board.side_to_move())));
+            }
+
+            if elapsed > max_move_time && !search.stop.swap(true, Ordering::Relaxed) {
+                println!("Out of time after {:.1}s, telling the engine to stop", elapsed.as_secs_f32());
+            }
+
+            return None;
+        };
+
+        let search = self.search.take().unwrap();
+
+        //Can't happen while every change to the position cancels the search, but a move for another position would
+        //be played on the wrong board
+        if search.ply != self.moves.len() {
+            println!("Dropping {} from a search of an earlier position", variant::move_to_uci(m));
+            return None;
+        }
+
+        self.move_stats[self.board.side_to_move().to_index()] = Some(stats);
+
+        Some(Ok(m))
+    }
+
+    //The engine stops as soon as it next checks, and whatever it finds is thrown away
+    pub fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    //Plies a takeback would undo: the computer's reply along with the move it answered, or just the last move when
+    //nobody plays against a computer
+    pub fn takeback_plies(&self) -> usize {
+        let against_computer = self.white_player.is_computer() || self.black_player.is_computer();
+
+        self.moves.len().min(if against_computer { 2 } else { 1 })
+    }
+
+    //Cancels any search and undoes the moves, returning them. Nothing is started again here, so that the computer
+    //only thinks again through start_search once it's its turn and the clocks are known
+    pub fn take_back(&mut self) -> Vec<ChessMove> {
+        self.cancel_search();
+
+        let plies = self.takeback_plies();
+        let ply = self.moves.len() - plies;
+
+        if plies > 0 {
+            self.board = self.history[ply];
+            self.state = self.state_history[ply];
+            self.history.truncate(ply);
+            self.state_history.truncate(ply);
+            self.mobility.truncate(ply);
+            self.outcome = None;
+            self.recount_reversable_moves();
+        }
+
+        self.moves.split_off(ply)
+    }
+
+    fn recount_reversable_moves(&mut self) {
+        self.reversable_moves = self.reversable_moves_at(self.moves.len());
+    }
+
+    //Right after a reset, for a start position from a FEN whose halfmove clock isn't 0
+    pub fn set_start_halfmove_clock(&mut self, halfmoves: u32) {
+        self.start_reversable_moves = halfmoves;
+        self.recount_reversable_moves();
+    }
+
+    //Moves since the last capture or pawn move before `ply`, the halfmove clock of a FEN
+    pub fn reversable_moves_at(&self, ply: usize) -> u32 {
+        let plies = ply.min(self.history.len()).min(self.moves.len());
+        let since = self
+            .history
+            .iter()
+            .zip(self.moves.iter())
+            .take(plies)
+            .rev()
+            .take_while(|(board, m)| board.piece_on(m.get_source()) != Some(Piece::Pawn) && board.piece_on(m.get_dest()).is_none())
+            .count();
+
+        //With no capture or pawn move since the start, the count goes on from the start position's
+        if since == plies {
+            self.start_reversable_moves + since as u32
+        } else {
+            since as u32
+        }
+    }
+}
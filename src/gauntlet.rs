@@ -0,0 +1,175 @@
+use std::path::PathBuf;
+
+use chess::{Board, Color};
+
+use crate::alg::external::{find_engine, EngineOptions, EngineSpec, Protocol, UciOptionKind};
+use crate::arena::play_game;
+use crate::cli;
+use crate::gui::chess_display::PlayerType;
+use crate::settings;
+use crate::tournament::{append_pgn, bot_name, computer, GameResult, Pairing};
+use crate::variant::Variant;
+
+//Levels are this far apart unless told otherwise
+const SKILL_STEP: i64 = 1;
+const ELO_STEP: i64 = 200;
+
+#[derive(Debug, Clone)]
+pub struct GauntletConfig {
+    pub bot: String,
+    //UCI engine to play against, the first one given when there isn't one
+    pub engine: Option<String>,
+    //Games at every level, alternating colors
+    pub games: usize,
+    //Weakens the engine with UCI_Elo instead of Skill Level
+    pub elo: bool,
+    pub step: Option<i64>,
+    pub max_plies: usize,
+    pub pgn: Option<PathBuf>,
+}
+
+//One strength setting of the engine
+#[derive(Debug, Clone)]
+struct Level {
+    name: String,
+    options: EngineOptions,
+}
+
+//Every level the engine's options allow, weakest first
+fn levels(spec: &EngineSpec, elo: bool, step: Option<i64>) -> Result<Vec<Level>, String> {
+    let options = spec.uci_options()?;
+    let option = if elo { "UCI_Elo" } else { "Skill Level" };
+
+    let Some(UciOptionKind::Spin { min, max, .. }) = options.iter().find(|o| o.name.eq_ignore_ascii_case(option)).map(|o| o.kind.clone()) else {
+        let other = if elo { "without --elo" } else { "with --elo" };
+        return Err(format!("{} has no {} option to set, try {}", spec.name, option, other));
+    };
+
+    let step = step.unwrap_or(if elo { ELO_STEP } else { SKILL_STEP }).max(1);
+    //Whatever else was saved for the engine, like its hash size, still applies
+    let saved = settings::get().engine_options.get(&spec.name).cloned().unwrap_or_default();
+
+    let mut values: Vec<i64> = (min..=max).step_by(step as usize).collect();
+
+    //The full strength engine is the last level even when the steps don't land on it
+    if values.last() != Some(&max) {
+        values.push(max);
+    }
+
+    Ok(values
+        .into_iter()
+        .map(|value| {
+            let mut options = saved.clone();
+            options.insert(option.to_string(), value.to_string());
+
+            if elo {
+                options.insert(String::from("UCI_LimitStrength"), String::from("true"));
+            }
+
+            Level { name: format!("{} {}", option, value), options }
+        })
+        .collect())
+}
+
+//The engine named, or the first UCI engine given on the command line
+fn engine(name: &Option<String>) -> Result<&'static EngineSpec, String> {
+    match name {
+        Some(name) => find_engine(name).ok_or(format!("No --engine called '{}'", name)),
+        None => cli::args()
+            .engines
+            .iter()
+            .find(|engine| engine.protocol == Protocol::Uci)
+            .ok_or(String::from("A gauntlet needs a UCI engine, like --engine Stockfish=uci:stockfish")),
+    }
+}
+
+//Plays the games at one level and returns the bot's points
+fn play_level(config: &GauntletConfig, bot: &str, spec: &EngineSpec, level: &Level, first_game: usize) -> Result<f32, String> {
+    let opponent = format!("{} ({})", spec.name, level.name);
+    let mut points = 0.0;
+
+    for game in 0..config.games.max(1) {
+        //The bot gets white first
+        let color = if game % 2 == 0 { Color::White } else { Color::Black };
+
+        let PlayerType::Computer(engine) = spec.player_type_with(&level.options) else {
+            return Err(format!("{} is not a bot", spec.name));
+        };
+        let bot_player = computer(bot, color)?;
+
+        let (white, black) = match color {
+            Color::White => (bot_player, engine),
+            Color::Black => (engine, bot_player),
+        };
+
+        let played = play_game(&mut *white.lock().unwrap(), &mut *black.lock().unwrap(), Variant::Standard, Board::default(), config.max_plies);
+        points += played.score(color);
+
+        let (white, black) = match color {
+            Color::White => (bot.to_string(), opponent.clone()),
+            Color::Black => (opponent.clone(), bot.to_string()),
+        };
+
+        let result = GameResult {
+            pairing: Pairing { index: first_game + game, white, black },
+            result: played.outcome.pgn_result().to_string(),
+            reason: played.outcome.get_text().to_string(),
+            moves: played.moves,
+            reproducible: played.reproducible,
+            copy_of: None,
+            terms: played.terms,
+            random_plies: 0,
+        };
+
+        println!("  {} vs {} {} ({})", result.pairing.white, result.pairing.black, result.result, result.reason);
+
+        if let Some(path) = &config.pgn {
+            let mut pgn = result.pgn();
+            pgn.set_tag("Event", "Chess Arena gauntlet");
+
+            append_pgn(path, &pgn);
+        }
+    }
+
+    Ok(points)
+}
+
+//The bot plays the engine at every level from the weakest up, and the gauntlet ends at the first level it doesn't
+//score more than half the points against
+pub fn run(config: GauntletConfig) -> Result<(), String> {
+    let bot = bot_name(&config.bot)?;
+    let spec = engine(&config.engine)?;
+    let levels = levels(spec, config.elo, config.step)?;
+    let games = config.games.max(1);
+
+    println!("{} against {} at {} levels, {} games each", bot, spec.name, levels.len(), games);
+
+    let mut lines = vec![];
+    let mut failed = None;
+
+    for (number, level) in levels.iter().enumerate() {
+        println!("{}:", level.name);
+
+        let points = play_level(&config, bot, spec, level, number * games)?;
+        let beaten = points > games as f32 / 2.0;
+
+        let line = format!("{:<24} {} of {} points, {}", level.name, points, games, if beaten { "beaten" } else { "not beaten" });
+        println!("{}", line);
+        lines.push(line);
+
+        if !beaten {
+            failed = Some(level);
+            break;
+        }
+    }
+
+    println!();
+    println!("{}", lines.join("\n"));
+
+    match failed {
+        Some(level) => println!("{} first failed to beat {} at {}", bot, spec.name, level.name),
+        None => println!("{} beat {} at every level", bot, spec.name),
+    }
+
+    Ok(())
+}
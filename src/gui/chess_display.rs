@@ -1,32 +1,137 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
 use ggez::event::{EventHandler, MouseButton};
-use ggez::graphics::{Canvas, Color, Text, Rect, Mesh, TextFragment, TextAlign, TextLayout};
+use ggez::input::keyboard::{KeyInput, KeyMods};
+use ggez::winit::event::VirtualKeyCode;
+use ggez::graphics::{Canvas, Color, Text, Rect, TextFragment, TextAlign, TextLayout, Image, ImageFormat};
 use ggez::{event, graphics, Context, GameError, GameResult};
+use ggez::glam::Vec2;
+use rand::Rng;
+
+use chess::{BitBoard, Board, ChessMove, File, Piece, Rank, Square, EMPTY};
+
+use crate::alg::chess_alg::{ChessAlgorithm, EngineSettings};
+use crate::alg::console::{self, Stream};
+use crate::alg::explain::Explanation;
+use crate::achievements::{self, HumanGame};
+use crate::autosave::SavedGame;
+use crate::campaign;
+use crate::daily::{self, Matchup};
+use crate::cli;
+use crate::clock::{Delay, ThinkTime, TimeControl, TimeManagerKind};
+use crate::events::{self, GameEvent};
+use crate::game_controller::{GameController, MoveStats};
+use crate::handicap::Handicap;
+use crate::net::{NetEvent, NetMessage, NetSession};
+use crate::commentary::Commentator;
+use crate::observer::{self, Analysis, ObserverHandle};
+use crate::pgn::{format_clock, GameTags, MoveAnnotation, PgnGame};
+use crate::settings;
+use crate::speech;
+use crate::util::{fen_with_halfmove_clock, halfmove_clock, move_to_SAN, pgn_date, timestamp};
+use crate::variant::{self, Variant, VariantState, POCKET_PIECES};
+
+use super::instant;
+use super::focus;
+use super::touch;
+use super::ticker::{Ticker, TICKER_HEIGHT};
+use super::scale::ui;
+use super::split_layout::{self, PanelLayout, PanelTab, SplitAreas};
+use super::tag_editor::TagEditor;
+use super::skin::{self, PieceBatch, PieceSkin};
+use super::clipboard;
+use super::mesh_cache;
+use super::palette::{palette, shape_markers};
+use super::theme::theme;
 
-use chess::{Board, BoardStatus, ChessMove, File, MoveGen, Piece, Rank, Square, ALL_SQUARES};
+const BACKGROUND_COLOR: Color = Color::new(0.3, 0.3, 0.3, 1.0);
 
-use crate::alg::chess_alg::ChessAlgorithm;
-use crate::util::move_to_SAN;
+const SCRUBBER_HEIGHT: f32 = 36.0;
 
-use super::skin::PieceSkin;
+//How long messages like "FEN copied" stay on the board
+const NOTICE_TIME: Duration = Duration::from_secs(3);
 
-const BACKGROUND_COLOR: Color = Color::new(0.3, 0.3, 0.3, 1.0);
+//Longest instant mode keeps playing bot moves before letting a frame be drawn
+const INSTANT_FRAME_TIME: Duration = Duration::from_millis(15);
 
-const BOARD_WHITE: Color = Color::new(227.0 / 255.0, 220.0 / 255.0, 138.0 / 255.0, 1.0);
-const BOARD_BLACK: Color = Color::new(128.0 / 255.0, 69.0 / 255.0, 33.0 / 255.0, 1.0);
+//How long the board stays covered between hotseat turns, unless clicked away
+const HANDOFF_TIME: Duration = Duration::from_millis(1500);
 
-const BOARD_SELECTED_WHITE: Color = Color::new(188.0 / 255.0, 222.0 / 255.0, 115.0 / 255.0, 1.0);
-const BOARD_SELECTED_BLACK: Color = Color::new(61.0 / 255.0, 92.0 / 255.0, 21.0 / 255.0, 1.0);
+//Move time limits the engine panel steps through, none first
+const MOVE_TIMES: [Option<Duration>; 8] = [
+    None,
+    Some(Duration::from_millis(100)),
+    Some(Duration::from_millis(250)),
+    Some(Duration::from_millis(500)),
+    Some(Duration::from_secs(1)),
+    Some(Duration::from_secs(2)),
+    Some(Duration::from_secs(5)),
+    Some(Duration::from_secs(10)),
+];
 
-#[derive(Debug)]
+const MAX_PANEL_DEPTH: u32 = 8;
+
+//External engine output shown when the console is expanded
+const CONSOLE_LINES: usize = 12;
+
+const PANEL_ROW_HEIGHT: f32 = 32.0;
+
+//Lines of the move list and the engine and console tabs of the side panel
+const SIDE_PANEL_LINE_HEIGHT: f32 = 22.0;
+
+//Lines of commentary kept for the panel
+const MAX_COMMENTARY: usize = 30;
+
+//Observer depth needed before an eval swing is commented on
+const COMMENTARY_DEPTH: u32 = 3;
+
+//Squares of the board covered by the time chart at the end of a game
+const TIME_CHART_SQUARES: f32 = 2.0;
+
+//How much of the eval bar is white's, with about 4 pawns ahead filling most of it
+fn eval_fraction(eval: f32) -> f32 {
+    0.5 + 0.5 * (eval / 4.0).tanh()
+}
+
+//Like 950, 12.3k or 4.1M
+fn short_count(n: u64) -> String {
+    match n {
+        0..1_000 => n.to_string(),
+        1_000..1_000_000 => format!("{:.1}k", n as f64 / 1e3),
+        _ => format!("{:.1}M", n as f64 / 1e6),
+    }
+}
+
+const FOG_WHITE: Color = Color::new(0.45, 0.45, 0.45, 1.0);
+const FOG_BLACK: Color = Color::new(0.35, 0.35, 0.35, 1.0);
+
+#[derive(Debug, PartialEq)]
 pub enum GameOutcome {
     Checkmate(chess::Color),
     Stalemate,
     InsufficientMaterial,
     DrawByRepetition,
     DrawBy50MoveRule,
+    Resignation(chess::Color),
+    DrawByAgreement,
+    //Royal losing chess wins
+    LostAllPieces(chess::Color),
+    NoMoves(chess::Color),
+    //Fog of war win
+    KingCaptured(chess::Color),
+    //Headless games that went on for too long
+    DrawByMoveLimit,
+    //The winner is the side whose flag didn't fall
+    Timeout(chess::Color),
+    TimeoutVsInsufficientMaterial,
+    //The winner is the side whose opponent sent a move that isn't legal
+    IllegalMove(chess::Color),
+    //The winner is the side whose opponent could no longer play, like a bot whose engine died or that stopped moving
+    Forfeit(chess::Color),
 }
 
 impl GameOutcome {
@@ -40,6 +145,62 @@ impl GameOutcome {
             GameOutcome::InsufficientMaterial => "Draw by insufficient material",
             GameOutcome::DrawByRepetition => "Draw by repetition",
             GameOutcome::DrawBy50MoveRule => "Draw by 50 move rule",
+            GameOutcome::Resignation(color) => match color {
+                chess::Color::White => "White wins by resignation",
+                chess::Color::Black => "Black wins by resignation",
+            },
+            GameOutcome::DrawByAgreement => "Draw by agreement",
+            GameOutcome::LostAllPieces(color) => match color {
+                chess::Color::White => "White wins by losing all pieces but the king",
+                chess::Color::Black => "Black wins by losing all pieces but the king",
+            },
+            GameOutcome::NoMoves(color) => match color {
+                chess::Color::White => "White wins by running out of moves",
+                chess::Color::Black => "Black wins by running out of moves",
+            },
+            GameOutcome::KingCaptured(color) => match color {
+                chess::Color::White => "White wins by capturing the king",
+                chess::Color::Black => "Black wins by capturing the king",
+            },
+            GameOutcome::DrawByMoveLimit => "Draw by move limit",
+            GameOutcome::Timeout(color) => match color {
+                chess::Color::White => "White wins on time",
+                chess::Color::Black => "Black wins on time",
+            },
+            GameOutcome::TimeoutVsInsufficientMaterial => "Draw by timeout vs insufficient material",
+            GameOutcome::IllegalMove(color) => match color {
+                chess::Color::White => "White wins, Black played an illegal move",
+                chess::Color::Black => "Black wins, White played an illegal move",
+            },
+            GameOutcome::Forfeit(color) => match color {
+                chess::Color::White => "White wins by forfeit",
+                chess::Color::Black => "Black wins by forfeit",
+            },
+        }
+    }
+
+    pub fn pgn_result(&self) -> &'static str {
+        match self {
+            GameOutcome::Checkmate(color)
+            | GameOutcome::Resignation(color)
+            | GameOutcome::LostAllPieces(color)
+            | GameOutcome::NoMoves(color)
+            | GameOutcome::KingCaptured(color)
+            | GameOutcome::Timeout(color)
+            | GameOutcome::IllegalMove(color)
+            | GameOutcome::Forfeit(color) => match color {
+                chess::Color::White => "1-0",
+                chess::Color::Black => "0-1",
+            },
+            _ => "1/2-1/2",
+        }
+    }
+
+    pub fn points(&self, color: chess::Color) -> f32 {
+        match (self.pgn_result(), color) {
+            ("1-0", chess::Color::White) | ("0-1", chess::Color::Black) => 1.0,
+            ("1-0", chess::Color::Black) | ("0-1", chess::Color::White) => 0.0,
+            _ => 0.5,
         }
     }
 }
@@ -56,6 +217,8 @@ struct BoardDimensions {
 pub enum PlayerType {
     Human,
     Computer(Arc<Mutex<dyn ChessAlgorithm>>),
+    //Moves come from the other end of a network game
+    Remote,
 }
 
 impl PlayerType {
@@ -73,70 +236,159 @@ impl PlayerType {
         }
     }
 
+    pub fn is_remote(&self) -> bool {
+        matches!(self, PlayerType::Remote)
+    }
+
     pub fn computer<T: ChessAlgorithm + 'static>(algorithm: T) -> PlayerType {
         PlayerType::Computer(Arc::new(Mutex::new(algorithm)))
     }
 }
 
-fn is_insufficient_material(board: &Board) -> bool {
-    let mut res = true;
-
-    for color in vec![chess::Color::White, chess::Color::Black] {
-        let mut num_pawns = 0;
-        let mut num_queens = 0;
-        let mut num_rooks = 0;
-        let mut num_bishops = 0;
-        let mut num_knights = 0;
-
-        for square in ALL_SQUARES {
-            if let Some(piece) = board.piece_on(square) {
-                let piece_color = board.color_on(square).unwrap();
-                if piece_color == color {
-                    match piece {
-                        chess::Piece::Pawn => num_pawns += 1,
-                        chess::Piece::Queen => num_queens += 1,
-                        chess::Piece::Rook => num_rooks += 1,
-                        chess::Piece::Bishop => num_bishops += 1,
-                        chess::Piece::Knight => num_knights += 1,
-                        _ => (),
-                    }
-                }
-            }
-        }
+//Settings picked when creating a game
+#[derive(Debug, Clone, Default)]
+pub struct GameOptions {
+    pub variant: Variant,
+    pub handicap: Handicap,
+    //White's and black's clocks, which differ in time odds games
+    pub time_controls: [TimeControl; 2],
+    //How the engine playing each color spends its time
+    pub time_managers: [TimeManagerKind; 2],
+    //Position pasted as a FEN and its halfmove clock, replacing the handicap's start position
+    pub start: Option<(Board, u32)>,
+    //Hotseat games turn the board to the side to move after every move
+    pub auto_flip: bool,
+    //Set in the game creator's tag editor, otherwise the players' names and the usual placeholders
+    pub tags: Option<GameTags>,
+    //Bot the human is playing in the campaign, which is told the result
+    pub campaign: Option<String>,
+    //Today's daily matchup, which is saved to the daily results once it ends
+    pub daily: Option<Matchup>,
+}
 
-        if num_pawns > 0
-            || num_queens > 0
-            || num_rooks > 0
-            || num_bishops > 1
-            || num_knights > 2
-            || (num_knights > 1 && num_bishops > 1)
-        {
-            res = false;
-            break;
-        }
-    }
+//What a row of the engine panel changes
+#[derive(Debug, Clone, Copy)]
+enum EngineSetting {
+    Depth,
+    MoveTime,
+    MaxMoveTime,
+    Randomness,
+}
 
-    res
+#[derive(Debug, Clone, Copy)]
+enum LiveButton {
+    ReturnToLive,
+    Replay,
+    StopReplay,
 }
 
 #[derive(Debug)]
 pub struct ChessDisplay {
-    pub board: Board,
+    pub game: GameController,
     board_dimensions: BoardDimensions,
 
-    white_player: PlayerType,
-    black_player: PlayerType,
-
-    skin: PieceSkin,
-
-    selected_square: Option<(u8, u8)>,
+    handicap: Handicap,
 
-    next_move_future: Arc<Mutex<Option<ChessMove>>>,
+    //Names in the player list, which the autosave finds the players by
+    white_name: String,
+    black_name: String,
+    //Shown on screen and written to the PGN
+    tags: GameTags,
+    //Why evaluator bots picked their moves, by ply
+    explanations: BTreeMap<usize, Explanation>,
+    tag_editor: Option<Box<TagEditor>>,
 
-    history: Vec<Board>,
-    reversable_moves: u32,
+    skin: Arc<PieceSkin>,
+    pieces: PieceBatch,
+    //Board, hidden squares and layout the piece batch was last filled for
+    batched: Option<(Board, BitBoard, [f32; 3], bool)>,
 
-    outcome: Option<GameOutcome>,
+    selected_square: Option<(u8, u8)>,
+    //Crazyhouse piece picked from the pocket
+    selected_drop: Option<Piece>,
+    //Square picked with the arrow keys, for playing without a mouse
+    cursor: Option<(u8, u8)>,
+    //Black at the bottom
+    flipped: bool,
+    //Whether the history scrubber is being dragged
+    scrubbing: bool,
+    //Toggled with T once the game is over
+    show_time_chart: bool,
+    //When the finished game being played back last stepped forward
+    autoplay: Option<Instant>,
+    autoplay_delay: Duration,
+    //Move entered while the opponent was thinking, played as soon as it is the human's turn if still legal
+    premove: Option<ChessMove>,
+    //With move confirmation on, a human move shown on the board until it is clicked again or Enter is pressed
+    staged: Option<ChessMove>,
+    confirm_moves: bool,
+
+    paused: bool,
+
+    //One for every move in the game
+    annotations: Vec<MoveAnnotation>,
+
+    //Set when viewing a finished game instead of playing one
+    replay_result: Option<String>,
+    //Cleared once the result is saved, so that taking moves back and finishing again doesn't count twice
+    campaign: Option<String>,
+    daily: Option<Matchup>,
+    //Ply being viewed if not the current position
+    view_ply: Option<usize>,
+
+    screenshot_requested: bool,
+    screenshot: Option<Image>,
+
+    net: Option<NetSession>,
+    net_status: Option<String>,
+    //Side that offered a draw
+    draw_offer: Option<chess::Color>,
+    time_used: [Duration; 2],
+    turn_start: Instant,
+    //Clocks are stopped while the game is paused
+    paused_at: Option<Instant>,
+    //Clocks also wait for the engines to get ready before the game starts
+    waiting_for_engines: bool,
+
+    time_controls: [TimeControl; 2],
+    time_managers: [TimeManagerKind; 2],
+    //Time each side got back from increments
+    time_bonus: [Duration; 2],
+
+    //Engine analysing bot games for the eval bar, graph and arrows
+    observer: Option<ObserverHandle>,
+    analysis: Option<Analysis>,
+    //Ply whose eval swing was already commented on
+    eval_commented: usize,
+
+    commentator: Commentator,
+    commentary: Vec<String>,
+    //Toggled with C
+    show_commentary: bool,
+    //Toggled with K, and only listening to the event bus while shown
+    ticker: Option<Ticker>,
+
+    //Short message over the top of the board and when it was shown
+    notice: Option<(String, Instant)>,
+
+    auto_flip: bool,
+    //When the board was covered for the next player to take over
+    handoff: Option<Instant>,
+
+    //White's and black's, changed in the panel opened with E
+    engine_settings: [EngineSettings; 2],
+    show_engine_panel: bool,
+    //Speed of every engine's search, for people working on their bots
+    perf_hud: bool,
+
+    //External engine output from before the game isn't shown in its console
+    console_start: usize,
+    console_expanded: bool,
+
+    //The tabbed panel beside the board, toggled with Tab, and where everything went last frame
+    layout: PanelLayout,
+    split: SplitAreas,
+    dragging_divider: bool,
 }
 
 impl ChessDisplay {
@@ -144,341 +396,2962 @@ impl ChessDisplay {
         ctx: &mut Context,
         white_player: PlayerType,
         black_player: PlayerType,
+        white_name: &str,
+        black_name: &str,
+        options: GameOptions,
     ) -> ChessDisplay {
-        let mut res = ChessDisplay {
-            board: Board::default(),
-            board_dimensions: BoardDimensions {
-                x_offset: 0.0,
-                y_offset: 0.0,
-                square_size: 50.0,
-            },
+        let mut res = ChessDisplay::create(ctx, white_player, black_player, white_name, black_name);
+        res.set_options(options);
+        res.begin();
 
-            white_player,
-            black_player,
-
-            skin: PieceSkin::load(ctx, "default"),
+        res
+    }
 
-            selected_square: None,
+    //Carries on with an autosaved game from where it was left
+    pub fn resume(ctx: &mut Context, white_player: PlayerType, black_player: PlayerType, saved: &SavedGame) -> Result<ChessDisplay, String> {
+        let mut res = ChessDisplay::create(ctx, white_player, black_player, &saved.white, &saved.black);
+
+        res.set_options(GameOptions {
+            variant: saved.variant,
+            handicap: saved.handicap(),
+            time_controls: saved.time_controls(),
+            time_managers: saved.time_managers,
+            start: Some((saved.start_board()?, halfmove_clock(&saved.start_fen))),
+            auto_flip: saved.auto_flip,
+            tags: saved.tags.clone(),
+            campaign: None,
+            daily: None,
+        });
+
+        for uci in saved.moves.iter() {
+            let m = variant::parse_uci(uci).ok_or(format!("Invalid move '{}' in the autosave", uci))?;
+
+            res.game.apply(m);
+            res.annotations.push(MoveAnnotation::default());
+        }
 
-            next_move_future: Arc::new(Mutex::new(None)),
+        res.time_used = saved.time_used.map(Duration::from_millis);
+        res.time_bonus = saved.time_bonus.map(Duration::from_millis);
 
-            history: Vec::new(),
-            reversable_moves: 0,
+        res.begin();
 
-            outcome: None,
-        };
+        Ok(res)
+    }
 
-        res.on_new_move();
+    fn set_options(&mut self, options: GameOptions) {
+        let (start, halfmoves) = options.start.unwrap_or_else(|| (options.handicap.start_board(), 0));
+        self.game.reset(options.variant, start);
+        self.game.set_start_halfmove_clock(halfmoves);
+        self.handicap = options.handicap;
+        self.time_controls = options.time_controls;
+        self.time_managers = options.time_managers;
+        self.auto_flip = options.auto_flip && self.game.white_player.is_human() && self.game.black_player.is_human();
+        self.campaign = options.campaign;
+        self.daily = options.daily;
+
+        if let Some(tags) = options.tags {
+            self.commentator.rename(&tags.white, &tags.black);
+            self.tags = tags;
+        }
 
-        res
+        //Only standard chess, since observers don't know the variant rules
+        if self.game.variant == Variant::Standard && self.game.white_player.is_computer() && self.game.black_player.is_computer() {
+            self.observer = observer::start_configured();
+        }
     }
 
-    fn update_dims(&mut self, x: f32, y: f32, width: f32, height: f32) {
-        let board_size = width.min(height);
+    fn begin(&mut self) {
+        events::publish(GameEvent::GameStarted {
+            white: self.tags.white.clone(),
+            black: self.tags.black.clone(),
+            fen: self.game.board.to_string(),
+            variant: self.game.variant,
+        });
 
-        self.board_dimensions.square_size = board_size / 8.0;
-        self.board_dimensions.x_offset = x + (width - board_size) / 2.0;
-        self.board_dimensions.y_offset = y + (height - board_size) / 2.0;
-    }
+        self.turn_start = Instant::now();
 
-    fn chess_to_screen(&self, rank: u8, file: u8) -> (f32, f32) {
-        let x = self.board_dimensions.x_offset + self.board_dimensions.square_size * file as f32;
-        let y =
-            self.board_dimensions.y_offset + self.board_dimensions.square_size * (7 - rank) as f32;
+        if self.auto_flip {
+            self.flipped = self.game.board.side_to_move() == chess::Color::Black;
+        }
 
-        (x, y)
+        self.game.prepare(self.time_controls);
+        self.waiting_for_engines = self.game.preparing();
+
+        self.on_new_move();
     }
 
-    fn screen_to_chess(&self, x: f32, y: f32) -> Option<(u8, u8)> {
-        let file =
-            ((x - self.board_dimensions.x_offset) / self.board_dimensions.square_size) as i32;
-        let rank =
-            ((y - self.board_dimensions.y_offset) / self.board_dimensions.square_size) as i32;
+    //Starts the clock and the engine once every engine is ready
+    fn check_engines_ready(&mut self) {
+        if !self.waiting_for_engines || self.game.preparing() {
+            return;
+        }
 
-        if file < 0 || file > 7 || rank < 0 || rank > 7 {
-            None
-        } else {
-            Some((7 - rank as u8, file as u8))
+        println!("Engines ready");
+
+        self.waiting_for_engines = false;
+        self.turn_start = Instant::now();
+
+        if self.paused_at.is_some() {
+            self.paused_at = Some(self.turn_start);
         }
-    }
 
-    fn get_square_color(&self, rank: u8, file: u8) -> Color {
-        let even = (rank + file) % 2 == 0;
+        self.try_launch_engine();
+    }
 
-        if self.selected_square == Some((rank, file)) {
-            if even {
-                BOARD_SELECTED_BLACK
-            } else {
-                BOARD_SELECTED_WHITE
-            }
-        } else if even {
-            BOARD_BLACK
-        } else {
-            BOARD_WHITE
+    //What the autosave keeps of a game being played here. Replays, network games and finished games aren't saved
+    pub fn saved_game(&self) -> Option<SavedGame> {
+        if self.is_replay() || self.net.is_some() || self.game.outcome.is_some() || self.game.white_player.is_remote() || self.game.black_player.is_remote() {
+            return None;
         }
+
+        let colors = [chess::Color::White, chess::Color::Black];
+
+        Some(SavedGame {
+            white: self.white_name.clone(),
+            black: self.black_name.clone(),
+            tags: Some(self.tags.clone()),
+            variant: self.game.variant,
+            handicap: self.handicap.name(),
+            time_controls: self.time_controls.map(|t| t.name()),
+            time_managers: self.time_managers,
+            start_fen: fen_with_halfmove_clock(&self.game.start_board, self.game.start_reversable_moves),
+            moves: self.game.moves.iter().copied().map(variant::move_to_uci).collect(),
+            time_used: colors.map(|color| self.clock(color).as_millis() as u64),
+            time_bonus: self.time_bonus.map(|bonus| bonus.as_millis() as u64),
+            auto_flip: self.auto_flip,
+            date: pgn_date(),
+        })
     }
 
-    fn draw_blank_board(&self, ctx: &mut Context, canvas: &mut Canvas) {
-        let rectangle = graphics::Mesh::new_rectangle(
+    pub fn replay(ctx: &mut Context, game: PgnGame) -> ChessDisplay {
+        let mut res = ChessDisplay::create(
             ctx,
-            graphics::DrawMode::fill(),
-            graphics::Rect::new(0.0, 0.0, 1.0, 1.0),
-            Color::WHITE,
-        )
-        .unwrap();
+            PlayerType::Human,
+            PlayerType::Human,
+            game.get_tag("White").unwrap_or("?"),
+            game.get_tag("Black").unwrap_or("?"),
+        );
+
+        res.game.reset(game.get_tag("Variant").and_then(Variant::from_name).unwrap_or_default(), game.start);
+        res.game.set_start_halfmove_clock(game.get_tag("FEN").map_or(0, halfmove_clock));
+        res.tags = GameTags::from_pgn(&game);
+
+        for m in game.moves.iter() {
+            res.game.apply(*m);
+        }
 
-        for x in 0..8 {
-            for y in 0..8 {
-                let color = self.get_square_color(y, x);
+        res.annotations = game.annotations;
+        res.annotations.resize(res.game.moves.len(), MoveAnnotation::default());
 
-                let (draw_x, draw_y) = self.chess_to_screen(y, x);
+        res.replay_result = Some(game.result);
+        res.view_ply = Some(0);
 
-                canvas.draw(
-                    &rectangle,
-                    graphics::DrawParam::default()
-                        .dest([draw_x, draw_y])
-                        .scale([
-                            self.board_dimensions.square_size,
-                            self.board_dimensions.square_size,
-                        ])
-                        .color(color),
-                );
-            }
-        }
+        res
     }
 
-    fn draw_pieces(&self, ctx: &mut Context, canvas: &mut Canvas) {
-        for x in 0..8 {
-            let file = File::from_index(x);
+    fn create(
+        ctx: &mut Context,
+        white_player: PlayerType,
+        black_player: PlayerType,
+        white_name: &str,
+        black_name: &str,
+    ) -> ChessDisplay {
+        let settings = |player: &PlayerType| EngineSettings {
+            depth: match player {
+                PlayerType::Computer(engine) => engine.lock().unwrap().depth(),
+                _ => None,
+            },
+            max_move_time: cli::args().max_move_time,
+            ..EngineSettings::default()
+        };
+        let engine_settings = [settings(&white_player), settings(&black_player)];
 
-            for y in 0..8 {
-                let rank = Rank::from_index(7 - y);
+        let skin = skin::skin(ctx, "default");
+        let pieces = PieceBatch::new(ctx, &skin);
 
-                let square = Square::make_square(rank, file);
+        ChessDisplay {
+            game: GameController::new(white_player, black_player),
+            board_dimensions: BoardDimensions {
+                x_offset: 0.0,
+                y_offset: 0.0,
+                square_size: 50.0,
+            },
 
-                if let Some(piece) = self.board.piece_on(square) {
-                    let color = self.board.color_on(square).unwrap();
+            handicap: Handicap::None,
 
-                    let piece_image = self.skin.get_piece_image(piece, color);
+            white_name: white_name.to_string(),
+            black_name: black_name.to_string(),
+            tags: GameTags::new(white_name, black_name),
+            explanations: BTreeMap::new(),
+            tag_editor: None,
 
-                    let x = self.board_dimensions.x_offset
-                        + self.board_dimensions.square_size * x as f32;
-                    let y = self.board_dimensions.y_offset
-                        + self.board_dimensions.square_size * y as f32;
+            skin,
+            pieces,
+            batched: None,
 
-                    canvas.draw(
-                        piece_image,
-                        graphics::DrawParam::default().dest([x, y]).scale([
-                            self.board_dimensions.square_size / piece_image.width() as f32,
-                            self.board_dimensions.square_size / piece_image.height() as f32,
-                        ]),
-                    );
-                }
-            }
+            selected_square: None,
+            selected_drop: None,
+            cursor: None,
+            flipped: false,
+            scrubbing: false,
+            show_time_chart: true,
+            autoplay: None,
+            autoplay_delay: Duration::from_millis(cli::args().replay_delay),
+            premove: None,
+            staged: None,
+            confirm_moves: settings::get().confirm_moves,
+
+            paused: false,
+
+            annotations: Vec::new(),
+
+            replay_result: None,
+            campaign: None,
+            daily: None,
+            view_ply: None,
+
+            screenshot_requested: false,
+            screenshot: None,
+
+            net: None,
+            net_status: None,
+            draw_offer: None,
+            time_used: [Duration::ZERO; 2],
+            turn_start: Instant::now(),
+            paused_at: None,
+            waiting_for_engines: false,
+
+            time_controls: [TimeControl::Untimed; 2],
+            time_managers: [TimeManagerKind::default(); 2],
+            time_bonus: [Duration::ZERO; 2],
+            observer: None,
+            analysis: None,
+            eval_commented: 0,
+
+            commentator: Commentator::new(white_name, black_name),
+            commentary: vec![],
+            show_commentary: true,
+            ticker: settings::get().event_ticker.then(Ticker::subscribe),
+
+            notice: None,
+
+            auto_flip: false,
+            handoff: None,
+
+            engine_settings,
+            show_engine_panel: false,
+            perf_hud: settings::get().perf_hud,
+
+            console_start: console::position(),
+            console_expanded: false,
+
+            layout: settings::get().panel_layout,
+            split: SplitAreas { board: Rect::new(0.0, 0.0, 0.0, 0.0), divider: None, panel: None },
+            dragging_divider: false,
         }
     }
 
-    fn generate_moves(&self) -> Vec<(ChessMove, (u8, u8))> {
-        //If the current player is a computer, there is nothihng that should be returned
-        if self.current_player().is_computer() {
-            return vec![];
-        }
+    pub fn join(ctx: &mut Context, mut session: NetSession, welcome: NetMessage) -> ChessDisplay {
+        let NetMessage::Welcome { white, black, fen, moves, side, variant } = welcome else {
+            panic!("Expected a welcome message");
+        };
 
-        if let Some((rank, file)) = self.selected_square {
-            let square = Square::make_square(
-                Rank::from_index(rank as usize),
-                File::from_index(file as usize),
-            );
+        let side = side.map(chess::Color::from);
 
-            MoveGen::new_legal(&self.board)
-                .filter_map(|m| {
-                    if m.get_source() == square {
-                        Some((
-                            m,
-                            (
-                                m.get_dest().get_rank().to_index() as u8,
-                                m.get_dest().get_file().to_index() as u8,
-                            ),
-                        ))
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-        } else {
-            vec![]
-        }
-    }
+        let player = |color| if side == Some(color) { PlayerType::Human } else { PlayerType::Remote };
 
-    fn draw_available_moves(&self, ctx: &mut Context, canvas: &mut Canvas) {
-        let circle = graphics::Mesh::new_circle(
-            ctx,
-            graphics::DrawMode::fill(),
-            [0.0, 0.0],
-            self.board_dimensions.square_size * 0.25,
-            0.25,
-            Color::from_rgba(255, 0, 0, 200),
-        )
-        .unwrap();
+        //Everything the local player doesn't play comes from the host
+        session.set_colors(0, [chess::Color::White, chess::Color::Black].into_iter().filter(|c| side != Some(*c)).collect());
 
-        for (_, (rank, file)) in self.generate_moves() {
-            let (x, y) = self.chess_to_screen(rank, file);
+        let mut res = ChessDisplay::create(ctx, player(chess::Color::White), player(chess::Color::Black), &white, &black);
 
-            canvas.draw(
-                &circle,
-                graphics::DrawParam::default()
-                    .dest([
-                        x + self.board_dimensions.square_size / 2.0,
-                        y + self.board_dimensions.square_size / 2.0,
-                    ])
-                    .scale([1.0, 1.0]),
-            );
-        }
-    }
+        res.game.reset(variant, fen.parse().unwrap_or_default());
+        res.game.set_start_halfmove_clock(halfmove_clock(&fen));
 
-    fn current_player<'a>(&'a self) -> &'a PlayerType {
-        if self.board.side_to_move() == chess::Color::White {
-            &self.white_player
-        } else {
-            &self.black_player
+        for m in moves.iter().map(String::as_str).filter_map(variant::parse_uci) {
+            res.game.apply(m);
+            res.annotations.push(MoveAnnotation::default());
         }
+
+        res.net = Some(session);
+        res.net_status = Some(match side {
+            Some(chess::Color::White) => String::from("Playing as White"),
+            Some(chess::Color::Black) => String::from("Playing as Black"),
+            None => String::from("Spectating"),
+        });
+
+        events::publish(GameEvent::GameStarted {
+            white,
+            black,
+            fen,
+            variant,
+        });
+
+        res.on_new_move();
+
+        res
     }
 
-    fn detect_draw(&mut self) -> bool {
-        if self.board.status() == BoardStatus::Stalemate {
-            self.outcome = Some(GameOutcome::Stalemate);
-            return true;
-        }
+    //Watches a game played somewhere else, starting from the moves already played. New moves come in through
+    //sync_spectated
+    pub fn spectator(ctx: &mut Context, white_name: &str, black_name: &str, moves: &[ChessMove]) -> ChessDisplay {
+        let mut res = ChessDisplay::create(ctx, PlayerType::Remote, PlayerType::Remote, white_name, black_name);
 
-        if is_insufficient_material(&self.board) {
-            self.outcome = Some(GameOutcome::InsufficientMaterial);
-            return true;
+        for m in moves.iter() {
+            res.game.apply(*m);
+            res.annotations.push(MoveAnnotation::default());
         }
 
-        let mut num_occurences = 0;
+        res.observer = observer::start_configured();
+
+        events::publish(GameEvent::GameStarted {
+            white: res.tags.white.clone(),
+            black: res.tags.black.clone(),
+            fen: res.game.start_board.to_string(),
+            variant: res.game.variant,
+        });
+
+        res.on_new_move();
+
+        res
+    }
 
-        for pos in self.history.iter() {
-            if *pos == self.board {
-                num_occurences += 1;
+    //Plays the moves of a spectated game that aren't on the board yet. Games stopped for going on too long are the
+    //only ones the rules here don't end by themselves
+    pub fn sync_spectated(&mut self, moves: &[ChessMove], result: Option<&str>) {
+        for m in moves.iter().skip(self.game.moves.len()) {
+            if self.game.outcome.is_some() {
+                break;
             }
-        }
 
-        if num_occurences >= 3 {
-            self.outcome = Some(GameOutcome::DrawByRepetition);
-            return true;
+            self.do_move(*m);
         }
 
-        if self.reversable_moves >= 50 {
-            self.outcome = Some(GameOutcome::DrawBy50MoveRule);
-            return true;
+        if result.is_some() && self.game.outcome.is_none() {
+            self.end_game(GameOutcome::DrawByMoveLimit);
         }
+    }
+
+    pub fn host(&mut self, port: u16) -> std::io::Result<()> {
+        self.net = Some(NetSession::host(port)?);
+        self.net_status = Some(format!("Hosting on port {}", port));
+
+        Ok(())
+    }
 
-        false
+    fn player(&self, color: chess::Color) -> &PlayerType {
+        self.game.player(color)
     }
 
-    fn on_new_move(&mut self) {
-        if self.board.status() == BoardStatus::Checkmate {
-            println!("Checkmate!");
-            match self.board.side_to_move() {
-                chess::Color::White => {
-                    println!("Black wins!");
-                    self.outcome = Some(GameOutcome::Checkmate(chess::Color::Black));
-                }
-                chess::Color::Black => {
-                    println!("White wins!");
-                    self.outcome = Some(GameOutcome::Checkmate(chess::Color::White));
-                }
-            }
+    //The side a human at this screen plays, preferring the side to move
+    fn local_color(&self) -> Option<chess::Color> {
+        let to_move = self.game.board.side_to_move();
 
-            return;
+        [to_move, !to_move].into_iter().find(|c| self.player(*c).is_human())
+    }
+
+    //Whose view of the board is shown in fog of war. Everything is shown when no human is playing or the game is over
+    fn fog_viewer(&self) -> Option<chess::Color> {
+        if self.game.outcome.is_some() || self.is_replay() {
+            return None;
         }
 
-        if self.detect_draw() {
-            println!("Draw!");
+        self.local_color()
+    }
 
-            return;
+    fn hidden_squares(&self) -> BitBoard {
+        match self.fog_viewer() {
+            Some(color) => !self.game.variant.rules().visible_squares(&self.displayed_board(), color),
+            None => EMPTY,
         }
+    }
 
-        self.try_launch_engine();
+    fn welcome(&self, side: Option<chess::Color>) -> NetMessage {
+        NetMessage::Welcome {
+            white: self.tags.white.clone(),
+            black: self.tags.black.clone(),
+            fen: self.game.start_board.to_string(),
+            moves: self.game.moves.iter().map(|m| variant::move_to_uci(*m)).collect(),
+            side: side.map(|c| c.into()),
+            variant: self.game.variant,
+        }
     }
 
-    fn try_launch_engine(&mut self) {
-        if self.outcome.is_some() {
-            return;
+    fn clock_message(&self) -> NetMessage {
+        NetMessage::Clock {
+            white_ms: self.time_used[chess::Color::White.to_index()].as_millis() as u64,
+            black_ms: self.time_used[chess::Color::Black.to_index()].as_millis() as u64,
         }
+    }
+
+    fn handle_net_event(&mut self, event: NetEvent) {
+        match event {
+            NetEvent::Connected(id) => {
+                //New connections take over a remote side nobody plays yet, or spectate
+                let side = [chess::Color::White, chess::Color::Black]
+                    .into_iter()
+                    .find(|c| self.player(*c).is_remote() && !self.net.as_ref().unwrap().is_claimed(*c));
 
-        if self.current_player().is_computer() {
-            let board = self.board.clone();
+                let welcome = self.welcome(side);
+                let clock = self.clock_message();
 
-            if let PlayerType::Computer(engine) = self.current_player() {
-                let engine = engine.clone();
+                let net = self.net.as_mut().unwrap();
 
-                {
-                    self.next_move_future.lock().unwrap().take();
+                if let Some(side) = side {
+                    net.set_colors(id, vec![side]);
+                    self.net_status = Some(format!("{:?} connected", side));
                 }
 
-                let output = self.next_move_future.clone();
+                net.send_to(id, &welcome);
+                net.send_to(id, &clock);
 
-                thread::spawn(move || {
-                    let mut engine = engine.lock().unwrap();
+                if let Some(offer) = self.draw_offer {
+                    net.send_to(id, &NetMessage::DrawOffer { side: offer.into() });
+                }
+            }
 
-                    let m = engine.get_move(board);
+            NetEvent::Disconnected(id) => {
+                println!("Network peer {} disconnected", id);
 
-                    output.lock().unwrap().replace(m);
-                });
+                if !self.net.as_ref().unwrap().is_host() {
+                    self.net_status = Some(String::from("Disconnected from host"));
+                } else if [chess::Color::White, chess::Color::Black].iter().any(|c| self.player(*c).is_remote() && !self.net.as_ref().unwrap().is_claimed(*c)) {
+                    self.net_status = Some(String::from("Opponent disconnected, waiting for them to reconnect"));
+                }
             }
+
+            NetEvent::Message(id, message) => self.handle_net_message(id, message),
         }
     }
 
-    fn do_move(&mut self, m: ChessMove) {
-        println!("Move: {}", move_to_SAN(&self.board, m));
+    fn handle_net_message(&mut self, id: usize, message: NetMessage) {
+        let net = self.net.as_ref().unwrap();
 
-        let mut reversable = true;
+        match message {
+            NetMessage::Move { uci } => {
+                let color = self.game.board.side_to_move();
 
-        if Some(Piece::Pawn) == self.board.piece_on(m.get_source()) {
-            reversable = false;
-        } else if self.board.piece_on(m.get_dest()).is_some() {
-            reversable = false;
-        }
+                if self.game.outcome.is_some() || !self.player(color).is_remote() || !net.controls(id, color) {
+                    println!("Ignoring out of turn move {} from network peer {}", uci, id);
+                    return;
+                }
 
-        if reversable {
-            self.reversable_moves += 1;
-        } else {
-            self.reversable_moves = 0;
-        }
+                let checked = match variant::parse_uci(&uci) {
+                    Some(m) => self.game.check_move(m, &format!("Network peer {}", id)),
+                    None => {
+                        println!("Network peer {} sent {}, which isn't a move, forfeiting", id, uci);
+                        Err(GameOutcome::IllegalMove(!color))
+                    }
+                };
+
+                match checked {
+                    Ok(m) => self.do_move(m),
+                    //Everyone else is told the offender resigned, since they never see the move
+                    Err(outcome) => {
+                        self.net.as_mut().unwrap().send_all(&NetMessage::Resign { side: color.into() });
+                        self.end_game(outcome);
+                    }
+                }
+            }
 
-        self.history.push(self.board.clone());
-        self.board = self.board.make_move_new(m);
+            NetMessage::Clock { white_ms, black_ms } => {
+                //The host's clock is authoritative
+                if !net.is_host() {
+                    self.time_used = [Duration::from_millis(white_ms), Duration::from_millis(black_ms)];
+                    self.turn_start = Instant::now();
+                }
+            }
 
-        self.on_new_move();
-    }
+            NetMessage::DrawOffer { side } if net.controls(id, side.into()) && self.game.outcome.is_none() => {
+                self.draw_offer = Some(side.into());
+                events::publish(GameEvent::DrawOffered { side });
+                self.net.as_mut().unwrap().send_except(side.into(), &NetMessage::DrawOffer { side });
+            }
 
-    pub fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
-        let m = { self.next_move_future.lock().unwrap().take() };
+            NetMessage::DrawAccept { side } if net.controls(id, side.into()) && self.draw_offer == Some(!chess::Color::from(side)) => {
+                self.net.as_mut().unwrap().send_except(side.into(), &NetMessage::DrawAccept { side });
+                self.end_game(GameOutcome::DrawByAgreement);
+            }
 
-        if let Some(m) = m {
-            self.do_move(m);
-        }
+            NetMessage::Resign { side } if net.controls(id, side.into()) && self.game.outcome.is_none() => {
+                self.net.as_mut().unwrap().send_except(side.into(), &NetMessage::Resign { side });
+                self.end_game(GameOutcome::Resignation(!chess::Color::from(side)));
+            }
 
-        Ok(())
+            message => println!("Ignoring network message {:?} from peer {}", message, id),
+        }
     }
 
-    pub fn draw(&mut self, ctx: &mut Context, canvas: &mut Canvas, x: f32, y: f32, w: f32, h: f32) -> Result<(), GameError> {
-        self.update_dims(x, y, w , h);
+    fn offer_or_accept_draw(&mut self) {
+        let Some(color) = self.local_color() else {
+            return;
+        };
 
-        self.draw_blank_board(ctx, canvas);
-        self.draw_pieces(ctx, canvas);
-        self.draw_available_moves(ctx, canvas);
+        if self.game.outcome.is_some() || self.draw_offer == Some(color) {
+            return;
+        }
 
-        if let Some(outcome) = &self.outcome {
-            let mut text = Text::default();
+        if self.draw_offer == Some(!color) {
+            if let Some(net) = self.net.as_mut() {
+                net.send_except(color, &NetMessage::DrawAccept { side: color.into() });
+            }
 
-            text.set_bounds([self.board_dimensions.square_size * 7.8, 10000000.0]);
-            
-            text.add(TextFragment::new(outcome.get_text()).scale(60.0).color(Color::BLACK));
-            text.add(TextFragment::new("\nPress ESC to return to main menu").scale(25.0).color(Color::new(0.4, 0.4, 0.4, 1.0)));
-            
-            text.set_layout(TextLayout::center());
+            self.end_game(GameOutcome::DrawByAgreement);
+        } else {
+            if let Some(net) = self.net.as_mut() {
+                net.send_except(color, &NetMessage::DrawOffer { side: color.into() });
+            }
+
+            self.draw_offer = Some(color);
+            events::publish(GameEvent::DrawOffered { side: color.into() });
+        }
+    }
+
+    fn resign(&mut self) {
+        let Some(color) = self.local_color() else {
+            return;
+        };
+
+        if self.game.outcome.is_some() {
+            return;
+        }
+
+        if let Some(net) = self.net.as_mut() {
+            net.send_except(color, &NetMessage::Resign { side: color.into() });
+        }
+
+        self.end_game(GameOutcome::Resignation(!color));
+    }
+
+    //Undoes the last move, and the computer's reply too in games against it. A computer that was thinking stops, and
+    //only starts again if the position taken back to is its turn
+    pub fn take_back(&mut self) {
+        if self.is_replay() || self.net.is_some() || self.player(chess::Color::White).is_remote() || self.player(chess::Color::Black).is_remote() {
+            self.show_notice(String::from("Moves can't be taken back in this game"));
+            return;
+        }
+
+        let taken_back = self.game.take_back();
+
+        if taken_back.is_empty() {
+            self.show_notice(String::from("There are no moves to take back"));
+            return;
+        }
+
+        self.explanations.split_off(&self.game.moves.len());
+
+        //Clocks go back to where they were before the moves taken back
+        let mut color = self.game.board.side_to_move();
+
+        for annotation in self.annotations.split_off(self.game.moves.len()) {
+            let time = annotation.move_time.unwrap_or_default();
+
+            self.time_used[color.to_index()] = self.time_used[color.to_index()].saturating_sub(time);
+            self.time_bonus[color.to_index()] = self.time_bonus[color.to_index()].saturating_sub(self.time_control(color).refund(time));
+
+            color = !color;
+        }
+
+        self.turn_start = Instant::now();
+
+        if self.paused_at.is_some() {
+            self.paused_at = Some(self.turn_start);
+        }
+
+        self.premove = None;
+        self.staged = None;
+        self.selected_square = None;
+        self.selected_drop = None;
+        self.draw_offer = None;
+        self.view_ply = None;
+        self.handoff = None;
+        self.eval_commented = self.eval_commented.min(self.game.moves.len());
+
+        if self.auto_flip {
+            self.flipped = self.game.board.side_to_move() == chess::Color::Black;
+        }
+
+        events::publish(GameEvent::Takeback {
+            ply: self.game.moves.len(),
+            fen: self.game.board.to_string(),
+        });
+
+        let moves = taken_back.iter().map(|m| variant::move_to_uci(*m)).collect::<Vec<_>>().join(" ");
+        self.show_notice(format!("Took back {}", moves));
+
+        self.try_launch_engine();
+    }
+
+    //When the window closes: the search is cancelled and the observer told to stop, so nothing is left thinking
+    pub fn shutdown(&mut self) {
+        self.game.cancel_search();
+        self.observer = None;
+    }
+
+    fn end_game(&mut self, outcome: GameOutcome) {
+        println!("{}", outcome.get_text());
+
+        if let Some(opponent) = self.campaign.take() {
+            let human = if self.game.white_player.is_human() { chess::Color::White } else { chess::Color::Black };
+            campaign::record(&opponent, outcome.points(human));
+        }
+
+        if let Some(matchup) = self.daily.take() {
+            daily::record(&matchup, &self.white_name, &self.black_name, outcome.pgn_result());
+        }
+
+        self.game.end(outcome);
+        self.draw_offer = None;
+        self.publish_outcome();
+        self.check_achievements();
+    }
+
+    //Time spent on the move `color` is thinking about
+    fn pending_time(&self, color: chess::Color) -> Duration {
+        if self.game.outcome.is_none() && self.game.board.side_to_move() == color && !self.waiting_for_engines {
+            self.paused_at.unwrap_or_else(Instant::now).saturating_duration_since(self.turn_start)
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    fn clock(&self, color: chess::Color) -> Duration {
+        self.time_used[color.to_index()] + self.pending_time(color)
+    }
+
+    fn time_control(&self, color: chess::Color) -> TimeControl {
+        self.time_controls[color.to_index()]
+    }
+
+    fn is_timed(&self) -> bool {
+        self.time_controls.iter().any(|t| t.is_timed())
+    }
+
+    //Time left on a clock that counts down
+    fn remaining(&self, color: chess::Color) -> Option<Duration> {
+        self.time_control(color).remaining(self.time_used[color.to_index()], self.time_bonus[color.to_index()], self.pending_time(color))
+    }
+
+    //How long `color` took over their last move
+    fn last_move_time(&self, color: chess::Color) -> Option<Duration> {
+        (0..self.game.moves.len())
+            .rev()
+            .find(|ply| self.game.history[*ply].side_to_move() == color)
+            .and_then(|ply| self.annotations[ply].move_time)
+    }
+
+    //A simple delay counts down before the clock does, and a Bronstein delay shows what will be given back
+    fn clock_text(&self, color: chess::Color) -> String {
+        let mut text = format_clock(self.remaining(color).unwrap_or_else(|| self.clock(color)));
+        let pending = self.pending_time(color);
+
+        match self.time_control(color).delay() {
+            Delay::Simple(delay) if pending < delay && self.game.board.side_to_move() == color => {
+                text = format!("{} (delay {}s)", text, (delay - pending).as_secs_f32().ceil());
+            }
+            Delay::Bronstein(delay) if !pending.is_zero() => {
+                text = format!("{} (+{}s)", text, pending.min(delay).as_secs());
+            }
+            _ => {}
+        }
+
+        if let Some(last) = self.last_move_time(color) {
+            text = format!("{}  last move {:.1}s", text, last.as_secs_f32());
+        }
+
+        text
+    }
+
+    //Bars for how long every move took, white's going up and black's going down, with the ply being viewed
+    //highlighted
+    //Over the bottom of the board when there's no side panel to put it in
+    fn time_chart_overlay(&self) -> Rect {
+        let size = self.board_dimensions.square_size;
+
+        Rect::new(
+            self.board_dimensions.x_offset,
+            self.board_dimensions.y_offset + size * (8.0 - TIME_CHART_SQUARES),
+            size * 8.0,
+            size * TIME_CHART_SQUARES,
+        )
+    }
+
+    fn draw_time_chart(&self, ctx: &mut Context, canvas: &mut Canvas, bounds: Rect) -> Result<(), GameError> {
+        let times: Vec<Duration> = self.annotations.iter().map(|a| a.move_time.unwrap_or_default()).collect();
+        let longest = times.iter().max().copied().unwrap_or_default();
+
+        if longest.is_zero() {
+            return Ok(());
+        }
+
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), bounds, [0.0, 0.0, 0.0, 0.6].into())?;
+
+        let middle = bounds.center().y;
+        let max_height = bounds.h / 2.0 - ui(14.0);
+        let bar_width = bounds.w / times.len() as f32;
+        let viewed = self.view_ply.and_then(|ply| ply.checked_sub(1));
+
+        for (ply, time) in times.iter().enumerate() {
+            let height = (max_height * time.as_secs_f32() / longest.as_secs_f32()).max(1.0);
+            let white = self.game.history[ply].side_to_move() == chess::Color::White;
+
+            let bar = if white {
+                Rect::new(bounds.x + bar_width * ply as f32, middle - height, bar_width.max(1.0), height)
+            } else {
+                Rect::new(bounds.x + bar_width * ply as f32, middle, bar_width.max(1.0), height)
+            };
+
+            let color = match (viewed == Some(ply), white) {
+                (true, _) => Color::new(1.0, 0.8, 0.2, 1.0),
+                (false, true) => Color::new(0.9, 0.9, 0.9, 1.0),
+                (false, false) => Color::new(0.45, 0.45, 0.45, 1.0),
+            };
+
+            mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), bar, color)?;
+        }
+
+        let total = |color: chess::Color| self.time_used[color.to_index()].as_secs_f32();
+
+        let mut label = Text::new(format!(
+            "Time per move (longest {:.1}s)  White {:.1}s total, Black {:.1}s total  T to hide",
+            longest.as_secs_f32(),
+            total(chess::Color::White),
+            total(chess::Color::Black)
+        ));
+        label.set_scale(ui(14.0));
+        label.set_bounds([bounds.w - ui(8.0), f32::INFINITY]);
+
+        canvas.draw(&label, graphics::DrawParam::default().dest([bounds.x + ui(4.0), bounds.y + ui(2.0)]).color(Color::WHITE));
+
+        Ok(())
+    }
+
+    fn think_time(&self, color: chess::Color) -> Option<ThinkTime> {
+        let move_time = self.engine_settings[color.to_index()].move_time;
+
+        //A move time from the engine panel in an untimed game acts like a clock that never runs out
+        let Some(remaining) = self.remaining(color) else {
+            return move_time.map(|budget| ThinkTime {
+                remaining: Duration::MAX,
+                opponent_remaining: self.remaining(!color).unwrap_or(Duration::MAX),
+                increment: Duration::ZERO,
+                delay: Duration::ZERO,
+                budget,
+            });
+        };
+
+        let increment = self.time_control(color).increment();
+        let delay = self.time_control(color).delay().duration();
+
+        //An untimed opponent in a time odds game never runs out
+        Some(ThinkTime {
+            remaining,
+            opponent_remaining: self.remaining(!color).unwrap_or(Duration::MAX),
+            increment,
+            delay,
+            budget: (self.time_managers[color.to_index()].allocate(remaining, increment, self.game.moves.len()) + delay).min(move_time.unwrap_or(Duration::MAX)),
+        })
+    }
+
+    //Ends the game when the side to move runs out of time
+    fn check_flag(&mut self) {
+        if self.game.outcome.is_some() || self.paused {
+            return;
+        }
+
+        let color = self.game.board.side_to_move();
+
+        if self.remaining(color) == Some(Duration::ZERO) {
+            println!("{:?}'s flag fell", color);
+            events::publish(GameEvent::FlagFell { side: color.into() });
+
+            let outcome = self.game.variant.rules().flag_outcome(&self.game.board, &self.game.state, color);
+            self.end_game(outcome);
+        }
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.replay_result.is_some()
+    }
+
+    fn displayed_board(&self) -> Board {
+        match self.view_ply {
+            Some(ply) if ply < self.game.history.len() => self.game.history[ply],
+            _ => self.game.board,
+        }
+    }
+
+    fn displayed_state(&self) -> VariantState {
+        match self.view_ply {
+            Some(ply) if ply < self.game.state_history.len() => self.game.state_history[ply],
+            _ => self.game.state,
+        }
+    }
+
+    //Moves through the history by `delta` plies. Only the view changes, the game carries on
+    pub fn step_history(&mut self, delta: isize) {
+        let ply = self.view_ply.unwrap_or(self.game.history.len());
+        self.set_view_ply(ply.saturating_add_signed(delta));
+    }
+
+    pub fn history_start(&mut self) {
+        self.set_view_ply(0);
+    }
+
+    pub fn history_end(&mut self) {
+        self.set_view_ply(self.game.history.len());
+    }
+
+    fn copy_fen_width(&self) -> f32 {
+        ui(100.0).min(self.board_dimensions.square_size * 2.0)
+    }
+
+    //Slider along the bottom of the board, with room on the left for the "Copy FEN" button and on the right for the
+    //"Return to live" button
+    fn scrubber_track(&self) -> Rect {
+        let width = self.board_dimensions.square_size * 8.0;
+        let button_width = ui(150.0).min(width / 2.0);
+        let copy_width = self.copy_fen_width();
+
+        Rect::new(
+            self.board_dimensions.x_offset + copy_width,
+            self.board_dimensions.y_offset + width,
+            width - button_width - copy_width,
+            ui(SCRUBBER_HEIGHT),
+        )
+    }
+
+    fn copy_fen_button(&self) -> Rect {
+        let track = self.scrubber_track();
+
+        Rect::new(self.board_dimensions.x_offset, track.y + ui(4.0), self.copy_fen_width() - ui(5.0), track.h - ui(8.0))
+    }
+
+    pub fn show_notice(&mut self, text: String) {
+        println!("{}", text);
+
+        self.notice = Some((text, Instant::now()));
+    }
+
+    //Copies the position on screen, which is an earlier one while looking back through the game
+    pub fn copy_fen(&mut self) {
+        let fen = self.displayed_board().to_string();
+
+        match clipboard::set_text(&fen) {
+            Ok(()) => self.show_notice(format!("Copied FEN {}", fen)),
+            Err(e) => self.show_notice(e),
+        }
+    }
+
+    //Covers the board while the players swap seats, so the next one sees it from their side straight away
+    fn hand_over(&mut self) {
+        self.flipped = self.game.board.side_to_move() == chess::Color::Black;
+        self.handoff = Some(Instant::now());
+        self.selected_square = None;
+        self.selected_drop = None;
+    }
+
+    fn handing_over(&self) -> bool {
+        self.handoff.is_some_and(|at| at.elapsed() < HANDOFF_TIME)
+    }
+
+    fn draw_handoff(&self, ctx: &mut Context, canvas: &mut Canvas) -> Result<(), GameError> {
+        let dims = &self.board_dimensions;
+        let bounds = Rect::new(dims.x_offset, dims.y_offset, dims.square_size * 8.0, dims.square_size * 8.0);
+
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), bounds, theme().background)?;
+
+        let side = match self.game.board.side_to_move() {
+            chess::Color::White => &self.tags.white,
+            chess::Color::Black => &self.tags.black,
+        };
+
+        let mut text = Text::default();
+        text.set_bounds([bounds.w - ui(20.0), f32::INFINITY]);
+
+        text.add(TextFragment::new(format!("{} to move", side)).scale(ui(50.0)).color(theme().text));
+        text.add(TextFragment::new("\nPass the mouse, then click to continue").scale(ui(25.0)).color(theme().muted_text));
+
+        text.set_layout(TextLayout::center());
+
+        canvas.draw(&text, graphics::DrawParam::default().dest([bounds.center().x, bounds.center().y]));
+
+        Ok(())
+    }
+
+    fn toggle_perf_hud(&mut self) {
+        self.perf_hud = !self.perf_hud;
+        settings::update(|settings| settings.perf_hud = self.perf_hud);
+
+        self.show_notice(format!("Engine performance {}", if self.perf_hud { "shown" } else { "hidden" }));
+    }
+
+    fn perf_hud_lines(&self) -> Vec<String> {
+        let mut lines = vec![];
+
+        for color in [chess::Color::White, chess::Color::Black] {
+            if !self.game.player(color).is_computer() {
+                continue;
+            }
+
+            let name = match color {
+                chess::Color::White => &self.tags.white,
+                chess::Color::Black => &self.tags.black,
+            };
+
+            lines.push(format!("{:?}: {}", color, name));
+
+            let thinking = self.game.live_search().filter(|_| self.game.board.side_to_move() == color);
+
+            if let Some((time, nodes)) = thinking {
+                let live = MoveStats { time, nodes, ..MoveStats::default() };
+                lines.push(format!("  Thinking {:.1}s, {} nodes/s", time.as_secs_f32(), short_count(live.nodes_per_second())));
+            }
+
+            match self.game.move_stats[color.to_index()] {
+                Some(stats) => {
+                    let depth = stats.depth.map_or(String::from("-"), |depth| depth.to_string());
+                    let table = stats.table_hit_rate.map_or(String::from("-"), |rate| format!("{:.0}%", rate * 100.0));
+
+                    lines.push(format!(
+                        "  Last move {:.2}s, {} nodes/s, depth {}, TT hits {}",
+                        stats.time.as_secs_f32(),
+                        short_count(stats.nodes_per_second()),
+                        depth,
+                        table
+                    ));
+                }
+                None if thinking.is_none() => lines.push(String::from("  No moves yet")),
+                None => {}
+            }
+        }
+
+        lines
+    }
+
+    //Top right of the board, under any notice
+    fn draw_perf_hud(&self, ctx: &mut Context, canvas: &mut Canvas) -> Result<(), GameError> {
+        let lines = self.perf_hud_lines();
+
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        let mut text = Text::new(lines.join("\n"));
+        text.set_scale(ui(16.0));
+
+        let dims = text.measure(ctx)?;
+        let board_right = self.board_dimensions.x_offset + self.board_dimensions.square_size * 8.0;
+        let panel = Rect::new(board_right - dims.x - ui(18.0), self.board_dimensions.y_offset + ui(8.0), dims.x + ui(10.0), dims.y + ui(8.0));
+
+        mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), panel, 4.0, [0.0, 0.0, 0.0, 0.6].into())?;
+        canvas.draw(&text, graphics::DrawParam::default().dest([panel.x + ui(5.0), panel.y + ui(4.0)]).color(Color::WHITE));
+
+        Ok(())
+    }
+
+    fn draw_notice(&self, ctx: &mut Context, canvas: &mut Canvas) -> Result<(), GameError> {
+        let notice = match &self.notice {
+            _ if self.waiting_for_engines => "Waiting for engines...",
+            Some((notice, at)) if at.elapsed() < NOTICE_TIME => notice.as_str(),
+            _ => return Ok(()),
+        };
+
+        let width = self.board_dimensions.square_size * 8.0;
+
+        let mut text = Text::new(notice);
+        text.set_scale(ui(18.0));
+        text.set_bounds([width - ui(10.0), f32::INFINITY]);
+
+        let height = text.measure(ctx)?.y + ui(8.0);
+        let strip = Rect::new(self.board_dimensions.x_offset, self.board_dimensions.y_offset, width, height);
+
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), strip, [0.0, 0.0, 0.0, 0.6].into())?;
+        canvas.draw(&text, graphics::DrawParam::default().dest([strip.x + ui(5.0), strip.y + ui(4.0)]).color(Color::WHITE));
+
+        Ok(())
+    }
+
+    //Where a control under the board can be clicked. It is grown for fingers, but never over the bottom rank
+    fn under_board_target(&self, bounds: Rect) -> Rect {
+        let board_bottom = self.board_dimensions.y_offset + self.board_dimensions.square_size * 8.0;
+        let target = touch::hit_rect(bounds);
+        let top = target.y.max(board_bottom);
+
+        Rect::new(target.x, top, target.w, target.bottom() - top)
+    }
+
+    fn live_button(&self) -> Rect {
+        let track = self.scrubber_track();
+        let width = self.board_dimensions.square_size * 8.0 - track.w;
+
+        Rect::new(track.right() + ui(5.0), track.y + ui(4.0), width - ui(5.0), track.h - ui(8.0))
+    }
+
+    fn viewing_live(&self) -> bool {
+        self.view_ply.is_none() && !self.is_replay()
+    }
+
+    //What the button right of the scrubber does, if it is a button at all
+    fn live_button_action(&self) -> Option<LiveButton> {
+        if self.autoplay.is_some() {
+            Some(LiveButton::StopReplay)
+        } else if (self.game.outcome.is_some() || self.is_replay()) && !self.game.history.is_empty() {
+            Some(LiveButton::Replay)
+        } else if !self.viewing_live() && !self.is_replay() {
+            Some(LiveButton::ReturnToLive)
+        } else {
+            None
+        }
+    }
+
+    //Returns whether it was a button to press
+    fn press_live_button(&mut self) -> bool {
+        match self.live_button_action() {
+            Some(LiveButton::ReturnToLive) => self.history_end(),
+            Some(LiveButton::Replay) => self.start_autoplay(),
+            Some(LiveButton::StopReplay) => self.autoplay = None,
+            None => return false,
+        }
+
+        true
+    }
+
+    //Once the game is over the keyboard is on the live button, which Enter presses
+    fn live_button_focused(&self) -> bool {
+        (self.game.outcome.is_some() || self.is_replay()) && self.live_button_action().is_some()
+    }
+
+    //Plays a finished game back from the start, one move every `autoplay_delay`
+    pub fn start_autoplay(&mut self) {
+        self.set_view_ply(0);
+        self.autoplay = Some(Instant::now());
+    }
+
+    pub fn autoplaying(&self) -> bool {
+        self.autoplay.is_some()
+    }
+
+    pub fn autoplay_delay(&self) -> Duration {
+        self.autoplay_delay
+    }
+
+    pub fn set_autoplay_delay(&mut self, delay: Duration) {
+        self.autoplay_delay = delay;
+    }
+
+    fn step_autoplay(&mut self) {
+        let Some(last_step) = self.autoplay else {
+            return;
+        };
+
+        if last_step.elapsed() < self.autoplay_delay && !instant::instant() {
+            return;
+        }
+
+        self.step_history(1);
+
+        let ply = self.view_ply.unwrap_or(self.game.history.len());
+        self.autoplay = if ply < self.game.history.len() { Some(Instant::now()) } else { None };
+    }
+
+    fn scrub_to(&mut self, x: f32) {
+        let track = self.scrubber_track();
+        let fraction = ((x - track.x) / track.w).clamp(0.0, 1.0);
+
+        self.set_view_ply((fraction * self.game.history.len() as f32).round() as usize);
+    }
+
+    fn draw_scrubber(&self, ctx: &mut Context, canvas: &mut Canvas) -> Result<(), GameError> {
+        let track = self.scrubber_track();
+        let ply = self.view_ply.unwrap_or(self.game.history.len());
+        let fraction = if self.game.history.is_empty() { 1.0 } else { ply as f32 / self.game.history.len() as f32 };
+
+        let line = Rect::new(track.x + ui(8.0), track.center().y - ui(2.0), track.w - ui(16.0), ui(4.0));
+
+        //Evals from the observer or an imported game as bars above and below the line
+        if self.annotations.iter().any(|a| a.eval.is_some()) {
+            let bar_width = (line.w / self.annotations.len() as f32).max(1.0);
+            let max_height = track.h / 2.0 - ui(2.0);
+
+            for (ply, eval) in self.annotations.iter().enumerate().filter_map(|(ply, a)| Some((ply, a.eval?))) {
+                let height = max_height * (eval_fraction(eval) - 0.5).abs() * 2.0;
+                let x = line.x + line.w * (ply + 1) as f32 / self.game.history.len() as f32 - bar_width;
+
+                let (bar, color) = if eval >= 0.0 {
+                    (Rect::new(x, line.center().y - height, bar_width, height), Color::new(0.95, 0.95, 0.95, 0.7))
+                } else {
+                    (Rect::new(x, line.center().y, bar_width, height), Color::new(0.1, 0.1, 0.1, 0.7))
+                };
+
+                mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), bar, color)?;
+            }
+        }
+
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), line, theme().panel_border)?;
+
+        let knob = [line.x + line.w * fraction, line.center().y];
+        mesh_cache::draw_circle(ctx, canvas, graphics::DrawMode::fill(), knob, ui(8.0), theme().text)?;
+
+        let copy_button = self.copy_fen_button();
+        mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), copy_button, 5.0, theme().button)?;
+
+        let mut copy_label = Text::new(TextFragment::new("Copy FEN").color(theme().button_text));
+        copy_label.set_scale(ui(18.0));
+
+        let dims = copy_label.measure(ctx)?;
+        canvas.draw(&copy_label, graphics::DrawParam::default().dest([copy_button.center().x - dims.x / 2.0, copy_button.center().y - dims.y / 2.0]));
+
+        let button = self.live_button();
+
+        let label = match self.live_button_action() {
+            Some(action) => {
+                mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), button, 5.0, theme().button)?;
+
+                let label = match action {
+                    LiveButton::ReturnToLive => String::from("Return to live"),
+                    LiveButton::Replay => String::from("Replay"),
+                    LiveButton::StopReplay => format!("Stop ({:.1}s)", self.autoplay_delay.as_secs_f32()),
+                };
+
+                TextFragment::new(label).color(theme().button_text)
+            }
+            None if self.viewing_live() => TextFragment::new("Live").color(theme().muted_text),
+            None => TextFragment::new(format!("Ply {}", ply)).color(theme().muted_text),
+        };
+
+        let mut label = Text::new(label);
+
+        label.set_scale(ui(18.0));
+
+        let dims = label.measure(ctx)?;
+        canvas.draw(&label, graphics::DrawParam::default().dest([button.center().x - dims.x / 2.0, button.center().y - dims.y / 2.0]));
+
+        if self.live_button_focused() {
+            focus::draw_ring(ctx, canvas, button)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, _y: f32) {
+        //There's no release event, so dragging stops as soon as the button is seen up
+        if !ctx.mouse.button_pressed(MouseButton::Left) {
+            self.scrubbing = false;
+
+            //The divider is only saved where it was let go
+            if self.dragging_divider {
+                self.dragging_divider = false;
+                self.save_layout();
+            }
+        }
+
+        if self.scrubbing {
+            self.scrub_to(x);
+        }
+
+        if self.dragging_divider {
+            let split = self.split;
+            let area = [Some(split.board), split.divider, split.panel].into_iter().flatten().fold(split.board, |area, rect| area.combine_with(rect));
+
+            self.layout.drag_to(area, x);
+        }
+    }
+
+    pub fn flip(&mut self) {
+        self.flipped = !self.flipped;
+    }
+
+    //Keeps the observer on the live position, and stores its eval of it with the move that led there
+    fn update_analysis(&mut self) {
+        let Some(observer) = &self.observer else {
+            return;
+        };
+
+        if self.game.outcome.is_some() {
+            self.observer = None;
+            return;
+        }
+
+        observer.set_position(self.game.board);
+        let analysis = observer.analysis();
+
+        if let Some(new) = analysis.as_ref().filter(|new| self.analysis.as_ref().is_none_or(|old| old.board != new.board || old.depth != new.depth)) {
+            events::publish(GameEvent::Eval {
+                eval: new.eval,
+                depth: new.depth,
+                pv: new.pv.iter().map(|m| variant::move_to_uci(*m)).collect(),
+            });
+        }
+
+        self.analysis = analysis;
+
+        if let (Some(analysis), Some(annotation)) = (&self.analysis, self.annotations.last_mut()) {
+            annotation.eval = Some(analysis.eval);
+        }
+
+        //Swings are judged once the observer has looked a few plies deep
+        let ply = self.game.moves.len();
+
+        if ply >= 2 && ply > self.eval_commented && self.analysis.as_ref().is_some_and(|a| a.depth >= COMMENTARY_DEPTH) {
+            self.eval_commented = ply;
+
+            let mover = self.game.history[ply - 1].side_to_move();
+            let before = self.annotations[ply - 2].eval;
+            let after = self.annotations[ply - 1].eval;
+
+            if let Some(line) = before.zip(after).and_then(|(before, after)| self.commentator.on_eval(mover, before, after)) {
+                self.comment(line);
+            }
+        }
+    }
+
+    fn comment(&mut self, text: String) {
+        println!("Commentary: {}", text);
+        events::publish(GameEvent::Commentary { text: text.clone() });
+
+        self.commentary.push(text);
+
+        if self.commentary.len() > MAX_COMMENTARY {
+            self.commentary.remove(0);
+        }
+    }
+
+    pub fn toggle_engine_panel(&mut self) {
+        if self.engine_panel_rows().is_empty() {
+            self.show_notice(String::from("No bots to set up in this game"));
+            return;
+        }
+
+        self.show_engine_panel = !self.show_engine_panel;
+    }
+
+    //A header for every bot playing here, followed by the settings it has
+    fn engine_panel_rows(&self) -> Vec<(chess::Color, Option<EngineSetting>)> {
+        let mut rows = vec![];
+
+        for color in [chess::Color::White, chess::Color::Black] {
+            if !self.player(color).is_computer() {
+                continue;
+            }
+
+            rows.push((color, None));
+
+            if self.engine_settings[color.to_index()].depth.is_some() {
+                rows.push((color, Some(EngineSetting::Depth)));
+            }
+
+            rows.push((color, Some(EngineSetting::MoveTime)));
+            rows.push((color, Some(EngineSetting::MaxMoveTime)));
+            rows.push((color, Some(EngineSetting::Randomness)));
+        }
+
+        rows
+    }
+
+    fn engine_panel_bounds(&self) -> Rect {
+        let dims = &self.board_dimensions;
+        let width = dims.square_size * 6.0;
+        //Rows plus the title
+        let height = (self.engine_panel_rows().len() + 1) as f32 * ui(PANEL_ROW_HEIGHT) + ui(10.0);
+
+        Rect::new(dims.x_offset + dims.square_size, dims.y_offset + (dims.square_size * 8.0 - height) / 2.0, width, height)
+    }
+
+    fn engine_panel_row(&self, row: usize) -> Rect {
+        let bounds = self.engine_panel_bounds();
+        let height = ui(PANEL_ROW_HEIGHT);
+
+        Rect::new(bounds.x + ui(10.0), bounds.y + ui(5.0) + (row + 1) as f32 * height, bounds.w - ui(20.0), height)
+    }
+
+    //The - and + buttons at the right of a row
+    fn engine_panel_buttons(row: Rect) -> [Rect; 2] {
+        let size = row.h - ui(6.0);
+
+        [
+            Rect::new(row.right() - size * 2.0 - ui(6.0), row.y + ui(3.0), size, size),
+            Rect::new(row.right() - size, row.y + ui(3.0), size, size),
+        ]
+    }
+
+    fn engine_setting_text(&self, color: chess::Color, setting: EngineSetting) -> String {
+        let settings = &self.engine_settings[color.to_index()];
+
+        match setting {
+            EngineSetting::Depth => format!("Depth: {}", settings.depth.unwrap_or(0)),
+            EngineSetting::MoveTime => match settings.move_time {
+                Some(time) => format!("Move time: {:.2}s", time.as_secs_f32()),
+                None => String::from("Move time: no limit"),
+            },
+            EngineSetting::MaxMoveTime => match settings.max_move_time {
+                Some(time) => format!("Hard limit: {:.2}s", time.as_secs_f32()),
+                None => String::from("Hard limit: none"),
+            },
+            EngineSetting::Randomness => format!("Random moves: {:.0}%", settings.randomness * 100.0),
+        }
+    }
+
+    //Takes effect on the bot's next move
+    fn adjust_engine_setting(&mut self, color: chess::Color, setting: EngineSetting, delta: i32) {
+        let settings = &mut self.engine_settings[color.to_index()];
+
+        match setting {
+            EngineSetting::Depth => {
+                settings.depth = settings.depth.map(|depth| depth.saturating_add_signed(delta).min(MAX_PANEL_DEPTH));
+            }
+            EngineSetting::MoveTime => {
+                let current = MOVE_TIMES.iter().position(|time| *time == settings.move_time).unwrap_or(0);
+                settings.move_time = MOVE_TIMES[current.saturating_add_signed(delta as isize).min(MOVE_TIMES.len() - 1)];
+            }
+            EngineSetting::MaxMoveTime => {
+                let current = MOVE_TIMES.iter().position(|time| *time == settings.max_move_time).unwrap_or(0);
+                settings.max_move_time = MOVE_TIMES[current.saturating_add_signed(delta as isize).min(MOVE_TIMES.len() - 1)];
+            }
+            EngineSetting::Randomness => {
+                settings.randomness = (settings.randomness + delta as f32 * 0.1).clamp(0.0, 1.0);
+            }
+        }
+
+        let text = self.engine_setting_text(color, setting);
+        println!("{:?}: {}", color, text);
+    }
+
+    fn draw_engine_panel(&self, ctx: &mut Context, canvas: &mut Canvas) -> Result<(), GameError> {
+        let bounds = self.engine_panel_bounds();
+
+        mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), bounds, 5.0, theme().tooltip_background)?;
+
+        let mut title = Text::new(TextFragment::new("Engine settings (E to close)").color(theme().muted_text));
+        title.set_scale(ui(20.0));
+        canvas.draw(&title, graphics::DrawParam::default().dest([bounds.x + ui(10.0), bounds.y + ui(10.0)]));
+
+        for (i, (color, setting)) in self.engine_panel_rows().into_iter().enumerate() {
+            let row = self.engine_panel_row(i);
+
+            let (label, indent) = match setting {
+                None => match color {
+                    chess::Color::White => (format!("White: {}", self.tags.white), 0.0),
+                    chess::Color::Black => (format!("Black: {}", self.tags.black), 0.0),
+                },
+                Some(setting) => (self.engine_setting_text(color, setting), ui(20.0)),
+            };
+
+            let mut text = Text::new(TextFragment::new(label).color(theme().tooltip_text));
+            text.set_scale(ui(20.0));
+            canvas.draw(&text, graphics::DrawParam::default().dest([row.x + indent, row.y + ui(6.0)]));
+
+            if setting.is_none() {
+                continue;
+            }
+
+            for (button, label) in Self::engine_panel_buttons(row).into_iter().zip(["-", "+"]) {
+                let color = if button.contains(ctx.mouse.position()) { theme().button_hover } else { theme().button };
+                mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), button, 3.0, color)?;
+
+                let mut text = Text::new(TextFragment::new(label).color(theme().button_text));
+                text.set_scale(ui(20.0));
+
+                let dims = text.measure(ctx)?;
+                canvas.draw(&text, graphics::DrawParam::default().dest([button.center().x - dims.x / 2.0, button.center().y - dims.y / 2.0]));
+            }
+        }
+
+        Ok(())
+    }
+
+    //Clicks while the engine panel is open don't reach the board. Clicking outside it closes it
+    fn engine_panel_click(&mut self, x: f32, y: f32) {
+        if !self.engine_panel_bounds().contains([x, y]) {
+            self.show_engine_panel = false;
+            return;
+        }
+
+        for (i, (color, setting)) in self.engine_panel_rows().into_iter().enumerate() {
+            let Some(setting) = setting else {
+                continue;
+            };
+
+            let [minus, plus] = Self::engine_panel_buttons(self.engine_panel_row(i));
+
+            if minus.contains([x, y]) {
+                self.adjust_engine_setting(color, setting, -1);
+            } else if plus.contains([x, y]) {
+                self.adjust_engine_setting(color, setting, 1);
+            }
+        }
+    }
+
+    //Along the bottom of the board once an external engine has said something, the last line when collapsed
+    fn draw_console(&self, ctx: &mut Context, canvas: &mut Canvas) -> Result<(), GameError> {
+        let lines = console::lines_since(self.console_start, if self.console_expanded { CONSOLE_LINES } else { 1 });
+
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        let width = self.board_dimensions.square_size * 8.0;
+        let header = if self.console_expanded { "Engine console (` to collapse)" } else { "Engine console (` to expand)" };
+
+        let mut text = Text::new(TextFragment::new(header).color(Color::new(0.7, 0.7, 0.7, 1.0)));
+        text.set_scale(ui(14.0));
+        text.set_bounds([width - ui(10.0), f32::INFINITY]);
+
+        for line in lines.iter() {
+            let color = match line.stream {
+                Stream::Sent => Color::new(0.6, 0.8, 1.0, 1.0),
+                Stream::Stdout => Color::WHITE,
+                Stream::Stderr => Color::new(1.0, 0.6, 0.5, 1.0),
+                Stream::Arena => Color::new(1.0, 0.9, 0.4, 1.0),
+            };
+
+            text.add(TextFragment::new(format!("\n{}", line.to_text())).color(color));
+        }
+
+        let height = text.measure(ctx)?.y + ui(8.0);
+        let strip = Rect::new(self.board_dimensions.x_offset, self.board_dimensions.y_offset + width - height, width, height);
+
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), strip, [0.0, 0.0, 0.0, 0.75].into())?;
+        canvas.draw(&text, graphics::DrawParam::default().dest([strip.x + ui(5.0), strip.y + ui(4.0)]));
+
+        Ok(())
+    }
+
+    //The first ply shown in the side panel's move list, and where each move is. The list follows the ply on screen
+    fn move_list(&self, content: Rect) -> Vec<(usize, Rect)> {
+        let line = ui(SIDE_PANEL_LINE_HEIGHT);
+        //A game set up with black to move has an empty space for white's first move
+        let offset = if self.game.start_board.side_to_move() == chess::Color::Black { 1 } else { 0 };
+        let rows = (self.game.moves.len() + offset).div_ceil(2);
+        let visible = ((content.h / line) as usize).max(1);
+
+        let current_row = (self.view_ply.unwrap_or(self.game.moves.len()).saturating_sub(1) + offset) / 2;
+        let first_row = (current_row + 1).saturating_sub(visible).min(rows.saturating_sub(visible));
+
+        let number_width = ui(48.0);
+        let column_width = (content.w - number_width) / 2.0;
+
+        (0..self.game.moves.len())
+            .filter_map(|ply| {
+                let row = (ply + offset) / 2;
+
+                if row < first_row || row >= first_row + visible {
+                    return None;
+                }
+
+                let column = (ply + offset) % 2;
+                let x = content.x + number_width + column as f32 * column_width;
+
+                Some((ply, Rect::new(x, content.y + (row - first_row) as f32 * line, column_width, line)))
+            })
+            .collect()
+    }
+
+    fn draw_move_list(&self, ctx: &mut Context, canvas: &mut Canvas, content: Rect) -> Result<(), GameError> {
+        if self.game.moves.is_empty() {
+            let mut text = Text::new(TextFragment::new("No moves yet").color(theme().muted_text));
+            text.set_scale(ui(16.0));
+            canvas.draw(&text, graphics::DrawParam::default().dest([content.x, content.y]));
+
+            return Ok(());
+        }
+
+        let viewed = self.view_ply.unwrap_or(self.game.moves.len());
+        let offset = if self.game.start_board.side_to_move() == chess::Color::Black { 1 } else { 0 };
+
+        for (ply, rect) in self.move_list(content) {
+            let column = (ply + offset) % 2;
+
+            if column == 0 || ply == 0 {
+                let number = (ply + offset) / 2 + 1;
+                let dots = if column == 0 { "." } else { "..." };
+
+                let mut text = Text::new(TextFragment::new(format!("{}{}", number, dots)).color(theme().muted_text));
+                text.set_scale(ui(16.0));
+                canvas.draw(&text, graphics::DrawParam::default().dest([content.x, rect.y + ui(3.0)]));
+            }
+
+            //The move that led to the position on screen
+            if ply + 1 == viewed {
+                mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), rect, 3.0, theme().button_hover)?;
+            }
+
+            let mut text = Text::new(TextFragment::new(move_to_SAN(&self.game.history[ply], self.game.moves[ply])).color(theme().text));
+            text.set_scale(ui(16.0));
+            canvas.draw(&text, graphics::DrawParam::default().dest([rect.x + ui(4.0), rect.y + ui(3.0)]));
+        }
+
+        Ok(())
+    }
+
+    //Each bot's settings and how its search is going, with the observer's view of the position
+    fn draw_engine_info(&self, canvas: &mut Canvas, content: Rect) {
+        let mut text = Text::default();
+        text.set_scale(ui(16.0));
+        text.set_bounds([content.w, content.h]);
+
+        for color in [chess::Color::White, chess::Color::Black] {
+            let PlayerType::Computer(engine) = self.player(color) else {
+                continue;
+            };
+
+            let name = if color == chess::Color::White { &self.tags.white } else { &self.tags.black };
+            text.add(TextFragment::new(format!("{} ({:?})\n", name, color)).color(theme().text));
+
+            let settings = [EngineSetting::Depth, EngineSetting::MoveTime, EngineSetting::MaxMoveTime, EngineSetting::Randomness];
+
+            for setting in settings.into_iter().filter(|s| !matches!(s, EngineSetting::Depth) || self.engine_settings[color.to_index()].depth.is_some()) {
+                text.add(TextFragment::new(format!("  {}\n", self.engine_setting_text(color, setting))).color(theme().muted_text));
+            }
+
+            //A bot that is thinking keeps its info to itself until it's done
+            if let Some(info) = engine.try_lock().ok().and_then(|engine| engine.info()) {
+                text.add(TextFragment::new(format!("  {}\n", info)).color(theme().muted_text));
+            }
+
+            text.add("\n");
+        }
+
+        if let Some(explanation) = self.shown_explanation() {
+            text.add(TextFragment::new(format!("{}\n", explanation.summary())).color(theme().text));
+
+            for line in explanation.lines() {
+                text.add(TextFragment::new(format!("  {}\n", line)).color(theme().muted_text));
+            }
+
+            text.add("\n");
+        }
+
+        if let Some(analysis) = self.shown_analysis() {
+            let board = self.displayed_board();
+            let mut line = vec![];
+            let mut position = board;
+
+            for m in analysis.pv.iter().take(8) {
+                line.push(move_to_SAN(&position, *m));
+                position = position.make_move_new(*m);
+            }
+
+            text.add(TextFragment::new(format!("Observer: {:+.2} at depth {}\n", analysis.eval, analysis.depth)).color(theme().text));
+            text.add(TextFragment::new(format!("  {}\n", line.join(" "))).color(theme().muted_text));
+        }
+
+        if text.fragments().is_empty() {
+            text.add(TextFragment::new("No engines in this game").color(theme().muted_text));
+        } else if !self.is_replay() {
+            text.add(TextFragment::new("E to change settings").color(theme().muted_text));
+        }
+
+        canvas.draw(&text, graphics::DrawParam::default().dest([content.x, content.y]));
+    }
+
+    fn draw_console_tab(&self, canvas: &mut Canvas, content: Rect) {
+        let count = ((content.h / ui(SIDE_PANEL_LINE_HEIGHT)) as usize).max(1);
+        let lines = console::lines_since(self.console_start, count);
+
+        let mut text = Text::default();
+        text.set_scale(ui(14.0));
+        text.set_bounds([content.w, content.h]);
+
+        if lines.is_empty() {
+            text.add(TextFragment::new("No engine output yet").color(theme().muted_text));
+        }
+
+        for line in lines.iter() {
+            let color = match line.stream {
+                Stream::Sent => Color::new(0.6, 0.8, 1.0, 1.0),
+                Stream::Stdout => theme().text,
+                Stream::Stderr => Color::new(1.0, 0.6, 0.5, 1.0),
+                Stream::Arena => Color::new(1.0, 0.9, 0.4, 1.0),
+            };
+
+            text.add(TextFragment::new(format!("{}\n", line.to_text())).color(color));
+        }
+
+        canvas.draw(&text, graphics::DrawParam::default().dest([content.x, content.y]));
+    }
+
+    //What the game knows about the position on screen, for finding where a bot or the rules lost track of it. The FEN
+    //has the real halfmove clock and move number, which the chess crate's own always leaves at 0 and 1
+    fn position_lines(&self) -> Vec<(&'static str, String)> {
+        let board = self.displayed_board();
+        let ply = self.view_ply.unwrap_or(self.game.moves.len()).min(self.game.moves.len());
+
+        let halfmove_clock = self.game.reversable_moves_at(ply);
+        let offset = if self.game.start_board.side_to_move() == chess::Color::Black { 1 } else { 0 };
+        let fullmove_number = (ply + offset) / 2 + 1;
+
+        let fen = board.to_string();
+        let fields: Vec<&str> = fen.split_whitespace().take(4).collect();
+
+        let castling = |color| match board.castle_rights(color) {
+            chess::CastleRights::NoRights => "none",
+            chess::CastleRights::KingSide => "kingside",
+            chess::CastleRights::QueenSide => "queenside",
+            chess::CastleRights::Both => "both sides",
+        };
+
+        //The chess crate only keeps the square when a pawn can actually take there
+        let en_passant = match board.en_passant() {
+            Some(pawn) => format!("{} (taking the pawn on {})", fields[3], pawn),
+            None => String::from("none"),
+        };
+
+        let checkers: Vec<String> = board.checkers().map(|square| square.to_string()).collect();
+        let repetitions = self.game.history.iter().take(ply).filter(|earlier| **earlier == board).count() + 1;
+
+        let mut lines = vec![
+            ("FEN", format!("{} {} {}", fields.join(" "), halfmove_clock, fullmove_number)),
+            ("To move", format!("{:?}", board.side_to_move())),
+            ("Castling", format!("White {}, Black {}", castling(chess::Color::White), castling(chess::Color::Black))),
+            ("En passant", en_passant),
+            ("Halfmove clock", halfmove_clock.to_string()),
+            ("Move number", fullmove_number.to_string()),
+            ("Seen", format!("{} time{}", repetitions, if repetitions == 1 { "" } else { "s" })),
+            ("Check from", if checkers.is_empty() { String::from("none") } else { checkers.join(", ") }),
+            ("Zobrist hash", format!("{:016x}", board.get_hash())),
+        ];
+
+        if let Some(last) = ply.checked_sub(1) {
+            let (before, m) = (self.game.history[last], self.game.moves[last]);
+            let dots = if before.side_to_move() == chess::Color::White { "." } else { "..." };
+
+            lines.push(("Last move", format!("{}{} {} ({})", (last + offset) / 2 + 1, dots, move_to_SAN(&before, m), variant::move_to_uci(m))));
+        }
+
+        if self.game.variant != Variant::Standard {
+            lines.push(("Variant", self.game.variant.name().to_string()));
+        }
+
+        lines
+    }
+
+    fn draw_position_tab(&self, canvas: &mut Canvas, content: Rect) {
+        let mut text = Text::default();
+        text.set_scale(ui(16.0));
+        text.set_bounds([content.w, content.h]);
+
+        for (label, value) in self.position_lines() {
+            text.add(TextFragment::new(format!("{}: ", label)).color(theme().muted_text));
+            text.add(TextFragment::new(format!("{}\n", value)).color(theme().text));
+        }
+
+        canvas.draw(&text, graphics::DrawParam::default().dest([content.x, content.y]));
+    }
+
+    //Evals from the observer or an imported game, above the line for white and below for black
+    fn draw_eval_graph(&self, ctx: &mut Context, canvas: &mut Canvas, bounds: Rect) -> Result<(), GameError> {
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), bounds, [0.0, 0.0, 0.0, 0.6].into())?;
+
+        let middle = Rect::new(bounds.x, bounds.center().y, bounds.w, 1.0);
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), middle, theme().panel_border)?;
+
+        let bar_width = bounds.w / self.annotations.len().max(1) as f32;
+        let max_height = bounds.h / 2.0 - ui(14.0);
+
+        for (ply, eval) in self.annotations.iter().enumerate().filter_map(|(ply, a)| Some((ply, a.eval?))) {
+            let height = max_height * (eval_fraction(eval) - 0.5).abs() * 2.0;
+            let x = bounds.x + bar_width * ply as f32;
+
+            let (bar, color) = if eval >= 0.0 {
+                (Rect::new(x, bounds.center().y - height, bar_width.max(1.0), height), Color::new(0.9, 0.9, 0.9, 1.0))
+            } else {
+                (Rect::new(x, bounds.center().y, bar_width.max(1.0), height), Color::new(0.45, 0.45, 0.45, 1.0))
+            };
+
+            mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), bar, color)?;
+        }
+
+        let mut label = Text::new("Eval");
+        label.set_scale(ui(14.0));
+        canvas.draw(&label, graphics::DrawParam::default().dest([bounds.x + ui(4.0), bounds.y + ui(2.0)]).color(Color::WHITE));
+
+        Ok(())
+    }
+
+    //Legal moves before each move, with white's turns light and black's dark
+    fn draw_mobility_graph(&self, ctx: &mut Context, canvas: &mut Canvas, bounds: Rect) -> Result<(), GameError> {
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), bounds, [0.0, 0.0, 0.0, 0.6].into())?;
+
+        let plies = self.game.mobility.plies();
+        let most = plies.iter().map(|(_, moves)| *moves).max().unwrap_or(0).max(1);
+        let bar_width = bounds.w / plies.len().max(1) as f32;
+        let max_height = bounds.h - ui(20.0);
+
+        for (ply, (color, moves)) in plies.iter().enumerate() {
+            let height = max_height * *moves as f32 / most as f32;
+            let bar = Rect::new(bounds.x + bar_width * ply as f32, bounds.bottom() - height, bar_width.max(1.0), height);
+
+            let color = match color {
+                chess::Color::White => Color::new(0.9, 0.9, 0.9, 1.0),
+                chess::Color::Black => Color::new(0.45, 0.45, 0.45, 1.0),
+            };
+
+            mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), bar, color)?;
+        }
+
+        let average = |color| self.game.mobility.average(color).map_or(String::from("-"), |average| format!("{:.1}", average));
+
+        let mut label = Text::new(format!(
+            "Legal moves (up to {}), average {} / {}",
+            most,
+            average(chess::Color::White),
+            average(chess::Color::Black)
+        ));
+        label.set_scale(ui(14.0));
+        label.set_bounds([bounds.w - ui(8.0), f32::INFINITY]);
+        canvas.draw(&label, graphics::DrawParam::default().dest([bounds.x + ui(4.0), bounds.y + ui(2.0)]).color(Color::WHITE));
+
+        Ok(())
+    }
+
+    fn draw_graphs(&self, ctx: &mut Context, canvas: &mut Canvas, content: Rect) -> Result<(), GameError> {
+        let has_evals = self.annotations.iter().any(|a| a.eval.is_some());
+        let has_times = self.annotations.iter().any(|a| a.move_time.is_some_and(|time| !time.is_zero()));
+        let has_mobility = !self.game.mobility.plies().is_empty();
+
+        let charts = [has_times, has_evals, has_mobility].into_iter().filter(|shown| *shown).count();
+
+        if charts == 0 {
+            let mut text = Text::new(TextFragment::new("Nothing to show yet").color(theme().muted_text));
+            text.set_scale(ui(16.0));
+            canvas.draw(&text, graphics::DrawParam::default().dest([content.x, content.y]));
+
+            return Ok(());
+        }
+
+        //Stacked from the top in a fixed order, sharing the height
+        let height = (content.h - ui(10.0) * (charts - 1) as f32) / charts as f32;
+        let mut slots = (0..charts).map(|i| Rect::new(content.x, content.y + i as f32 * (height + ui(10.0)), content.w, height));
+
+        if has_times {
+            self.draw_time_chart(ctx, canvas, slots.next().unwrap())?;
+        }
+
+        if has_evals {
+            self.draw_eval_graph(ctx, canvas, slots.next().unwrap())?;
+        }
+
+        if has_mobility {
+            self.draw_mobility_graph(ctx, canvas, slots.next().unwrap())?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_side_panel(&self, ctx: &mut Context, canvas: &mut Canvas, panel: Rect) -> Result<(), GameError> {
+        let mouse = ctx.mouse.position();
+
+        if let Some(divider) = self.split.divider {
+            let color = if self.dragging_divider || divider.contains(mouse) { theme().hovered_outline } else { theme().panel_border };
+            mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), divider, color)?;
+        }
+
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), panel, theme().tooltip_background)?;
+
+        let dock = split_layout::dock_button(panel);
+        let buttons = split_layout::tab_rects(panel).into_iter().map(|(tab, rect)| (Some(tab), rect, tab.name()));
+
+        for (tab, rect, label) in buttons.chain([(None, dock, "<>")]) {
+            let selected = tab == Some(self.layout.tab);
+
+            let color = if selected || rect.contains(mouse) { theme().button_hover } else { theme().button };
+
+            let inner = Rect::new(rect.x + ui(2.0), rect.y + ui(2.0), rect.w - ui(4.0), rect.h - ui(4.0));
+            mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), inner, 5.0, color)?;
+
+            if selected {
+                mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::stroke(2.0), inner, 5.0, theme().selected_outline)?;
+            }
+
+            let mut text = Text::new(TextFragment::new(label).color(theme().button_text));
+            text.set_scale(ui(16.0));
+
+            let dims = text.measure(ctx)?;
+            canvas.draw(&text, graphics::DrawParam::default().dest([rect.center().x - dims.x / 2.0, rect.center().y - dims.y / 2.0]));
+        }
+
+        let content = split_layout::content(panel);
+        canvas.set_scissor_rect(content)?;
+
+        match self.layout.tab {
+            PanelTab::Moves => self.draw_move_list(ctx, canvas, content)?,
+            PanelTab::Engine => self.draw_engine_info(canvas, content),
+            PanelTab::Console => self.draw_console_tab(canvas, content),
+            PanelTab::Graphs => self.draw_graphs(ctx, canvas, content)?,
+            PanelTab::Position => self.draw_position_tab(canvas, content),
+        }
+
+        canvas.set_default_scissor_rect();
+
+        Ok(())
+    }
+
+    //Tabs, the dock button and moves in the move list. Returns whether the click was on the panel or the divider
+    fn side_panel_click(&mut self, x: f32, y: f32) -> bool {
+        if self.split.divider.is_some_and(|divider| divider.contains([x, y])) {
+            self.dragging_divider = true;
+            return true;
+        }
+
+        let Some(panel) = self.split.panel.filter(|panel| panel.contains([x, y])) else {
+            return false;
+        };
+
+        if split_layout::dock_button(panel).contains([x, y]) {
+            self.layout.swap_side();
+            self.save_layout();
+            return true;
+        }
+
+        if let Some((tab, _)) = split_layout::tab_rects(panel).into_iter().find(|(_, rect)| rect.contains([x, y])) {
+            self.layout.tab = tab;
+            self.save_layout();
+            return true;
+        }
+
+        if self.layout.tab == PanelTab::Moves {
+            if let Some((ply, _)) = self.move_list(split_layout::content(panel)).into_iter().find(|(_, rect)| rect.contains([x, y])) {
+                self.autoplay = None;
+                self.set_view_ply(ply + 1);
+            }
+        }
+
+        true
+    }
+
+    fn save_layout(&self) {
+        let layout = self.layout;
+        settings::update(|settings| settings.panel_layout = layout);
+    }
+
+    pub fn toggle_side_panel(&mut self) {
+        self.layout.shown = !self.layout.shown;
+        self.save_layout();
+        self.show_notice(format!("Side panel {}", if self.layout.shown { "shown" } else { "hidden" }));
+    }
+
+    pub fn toggle_ticker(&mut self) {
+        self.ticker = match self.ticker {
+            Some(_) => None,
+            None => Some(Ticker::subscribe()),
+        };
+
+        let shown = self.ticker.is_some();
+        settings::update(|settings| settings.event_ticker = shown);
+
+        self.show_notice(format!("Event ticker {}", if shown { "shown" } else { "hidden" }));
+    }
+
+    pub fn toggle_commentary(&mut self) {
+        self.show_commentary = !self.show_commentary;
+    }
+
+    //In the space right of the board if there is some, otherwise the last few lines along the bottom of the board
+    fn draw_commentary(&self, ctx: &mut Context, canvas: &mut Canvas, x: f32, w: f32) -> Result<(), GameError> {
+        if !self.show_commentary || self.commentary.is_empty() {
+            return Ok(());
+        }
+
+        let size = self.board_dimensions.square_size;
+        let columns = if self.game.variant.rules().has_pockets() { 9.0 } else { 8.0 };
+        let board_right = self.board_dimensions.x_offset + size * columns;
+        let margin = x + w - board_right;
+
+        if margin >= ui(160.0) {
+            let mut text = Text::new(TextFragment::new("Commentary (C to hide)").color(theme().muted_text));
+            text.set_scale(ui(16.0));
+            text.set_bounds([margin - ui(20.0), f32::INFINITY]);
+
+            for line in self.commentary.iter().rev() {
+                text.add(TextFragment::new(format!("\n{}", line)).color(theme().text));
+            }
+
+            canvas.draw(&text, graphics::DrawParam::default().dest([board_right + ui(10.0), self.board_dimensions.y_offset]));
+        } else if self.game.outcome.is_none() {
+            let lines = &self.commentary[self.commentary.len().saturating_sub(2)..];
+
+            let mut text = Text::new(lines.join("\n"));
+            text.set_scale(ui(16.0));
+            text.set_bounds([size * 8.0 - ui(10.0), f32::INFINITY]);
+
+            let height = text.measure(ctx)?.y + ui(8.0);
+            let strip = Rect::new(self.board_dimensions.x_offset, self.board_dimensions.y_offset + size * 8.0 - height, size * 8.0, height);
+
+            mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), strip, [0.0, 0.0, 0.0, 0.6].into())?;
+            canvas.draw(&text, graphics::DrawParam::default().dest([strip.x + ui(5.0), strip.y + ui(4.0)]).color(Color::WHITE));
+        }
+
+        Ok(())
+    }
+
+    //Observer analysis of the position on screen
+    fn shown_analysis(&self) -> Option<&Analysis> {
+        self.analysis.as_ref().filter(|a| a.board == self.displayed_board())
+    }
+
+    //Filled from the bottom for white, or the top when the board is flipped, along the left edge of the board
+    fn draw_eval_bar(&self, ctx: &mut Context, canvas: &mut Canvas) -> Result<(), GameError> {
+        let Some(analysis) = self.shown_analysis() else {
+            return Ok(());
+        };
+
+        let size = self.board_dimensions.square_size;
+        let bar = Rect::new(self.board_dimensions.x_offset, self.board_dimensions.y_offset, ui(12.0), size * 8.0);
+        let white_height = bar.h * eval_fraction(analysis.eval);
+
+        let white = if self.flipped {
+            Rect::new(bar.x, bar.y, bar.w, white_height)
+        } else {
+            Rect::new(bar.x, bar.bottom() - white_height, bar.w, white_height)
+        };
+
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), bar, Color::new(0.15, 0.15, 0.15, 0.9))?;
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), white, Color::new(0.95, 0.95, 0.95, 0.9))?;
+
+        let mut label = Text::new(format!("{:+.1} d{}", analysis.eval, analysis.depth));
+        label.set_scale(ui(14.0));
+
+        let label_y = if (analysis.eval >= 0.0) != self.flipped { bar.bottom() - ui(16.0) } else { bar.y + ui(2.0) };
+        canvas.draw(&label, graphics::DrawParam::default().dest([bar.right() + ui(2.0), label_y]).color(Color::new(0.2, 0.4, 0.9, 1.0)));
+
+        Ok(())
+    }
+
+    //The first few moves of the observer's line, fading out
+    fn draw_pv_arrows(&self, ctx: &mut Context, canvas: &mut Canvas) -> Result<(), GameError> {
+        let Some(analysis) = self.shown_analysis() else {
+            return Ok(());
+        };
+
+        let size = self.board_dimensions.square_size;
+
+        let center = |square: Square| {
+            let (x, y) = self.chess_to_screen(square.get_rank().to_index() as u8, square.get_file().to_index() as u8);
+            Vec2::new(x + size / 2.0, y + size / 2.0)
+        };
+
+        for (m, alpha) in analysis.pv.iter().zip([0.8, 0.5, 0.3]) {
+            let (from, to) = (center(m.get_source()), center(m.get_dest()));
+            let direction = (to - from).normalize_or_zero();
+            let side = direction.perp() * size * 0.18;
+            let head = to - direction * size * 0.35;
+
+            let color = Color::new(0.2, 0.4, 0.9, alpha);
+
+            let line = graphics::Mesh::new_line(ctx, &[from, head], size * 0.12, color)?;
+            let tip = graphics::Mesh::new_polygon(ctx, graphics::DrawMode::fill(), &[head + side, to, head - side], color)?;
+
+            canvas.draw(&line, graphics::DrawParam::default());
+            canvas.draw(&tip, graphics::DrawParam::default());
+        }
+
+        Ok(())
+    }
+
+    pub fn toggle_time_chart(&mut self) {
+        self.show_time_chart = !self.show_time_chart;
+    }
+
+    fn set_view_ply(&mut self, ply: usize) {
+        if ply >= self.game.history.len() && !self.is_replay() {
+            self.view_ply = None;
+        } else {
+            self.view_ply = Some(ply.min(self.game.history.len()));
+        }
+    }
+
+    fn update_dims(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        //Pockets take up an extra column to the right of the board
+        let columns = if self.game.variant.rules().has_pockets() { 9.0 } else { 8.0 };
+
+        let board_size = (width * 8.0 / columns).min(height);
+
+        self.board_dimensions.square_size = board_size / 8.0;
+        self.board_dimensions.x_offset = x + (width - board_size * columns / 8.0) / 2.0;
+        self.board_dimensions.y_offset = y + (height - board_size) / 2.0;
+    }
+
+    fn chess_to_screen(&self, rank: u8, file: u8) -> (f32, f32) {
+        let (rank, file) = if self.flipped { (7 - rank, 7 - file) } else { (rank, file) };
+
+        let x = self.board_dimensions.x_offset + self.board_dimensions.square_size * file as f32;
+        let y =
+            self.board_dimensions.y_offset + self.board_dimensions.square_size * (7 - rank) as f32;
+
+        (x, y)
+    }
+
+    fn screen_to_chess(&self, x: f32, y: f32) -> Option<(u8, u8)> {
+        let file =
+            ((x - self.board_dimensions.x_offset) / self.board_dimensions.square_size) as i32;
+        let rank =
+            ((y - self.board_dimensions.y_offset) / self.board_dimensions.square_size) as i32;
+
+        if file < 0 || file > 7 || rank < 0 || rank > 7 {
+            None
+        } else {
+            if self.flipped {
+            Some((rank as u8, 7 - file as u8))
+        } else {
+            Some((7 - rank as u8, file as u8))
+        }
+        }
+    }
+
+    //Squares smaller than a fingertip are hard to hit, so a click close enough to a piece that can move or a square the
+    //selected one can move to counts as on it
+    fn screen_to_target(&self, x: f32, y: f32) -> Option<(u8, u8)> {
+        let square_size = self.board_dimensions.square_size;
+        let exact = self.screen_to_chess(x, y);
+
+        if square_size >= touch::MIN_TARGET {
+            return exact;
+        }
+
+        let Some(board) = self.input_board() else {
+            return exact;
+        };
+
+        let mut targets: Vec<(u8, u8)> = self.generate_moves().into_iter().map(|(_, square)| square).collect();
+        targets.extend(
+            self.game.variant.rules().legal_moves(&board, &self.game.state)
+                .into_iter()
+                .filter(|m| variant::as_drop(*m).is_none())
+                .map(|m| (m.get_source().get_rank().to_index() as u8, m.get_source().get_file().to_index() as u8)),
+        );
+
+        if exact.is_some_and(|square| targets.contains(&square)) {
+            return exact;
+        }
+
+        let distance = |&(rank, file): &(u8, u8)| {
+            let (square_x, square_y) = self.chess_to_screen(rank, file);
+            (square_x + square_size / 2.0 - x).hypot(square_y + square_size / 2.0 - y)
+        };
+
+        targets
+            .into_iter()
+            .filter(|square| distance(square) <= touch::MIN_TARGET / 2.0)
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .or(exact)
+    }
+
+    fn get_square_color(&self, rank: u8, file: u8, hidden: BitBoard) -> Color {
+        let even = (rank + file) % 2 == 0;
+        let square = Square::make_square(Rank::from_index(rank as usize), File::from_index(file as usize));
+
+        if hidden & BitBoard::from_square(square) != EMPTY {
+            if even {
+                FOG_BLACK
+            } else {
+                FOG_WHITE
+            }
+        } else if self.selected_square == Some((rank, file)) {
+            if even {
+                palette().selected_dark
+            } else {
+                palette().selected_light
+            }
+        } else if even {
+            palette().dark
+        } else {
+            palette().light
+        }
+    }
+
+    fn draw_blank_board(&self, ctx: &mut Context, canvas: &mut Canvas) {
+        let rectangle = mesh_cache::rectangle(ctx, graphics::DrawMode::fill(), 1.0, 1.0).unwrap();
+
+        let hidden = self.hidden_squares();
+
+        for x in 0..8 {
+            for y in 0..8 {
+                let color = self.get_square_color(y, x, hidden);
+
+                let (draw_x, draw_y) = self.chess_to_screen(y, x);
+
+                canvas.draw(
+                    &rectangle,
+                    graphics::DrawParam::default()
+                        .dest([draw_x, draw_y])
+                        .scale([
+                            self.board_dimensions.square_size,
+                            self.board_dimensions.square_size,
+                        ])
+                        .color(color),
+                );
+            }
+        }
+    }
+
+    fn reload_skin(&mut self, ctx: &mut Context) {
+        skin::reload_skins();
+
+        self.skin = skin::skin(ctx, &self.skin.name);
+        self.pieces = PieceBatch::new(ctx, &self.skin);
+        self.batched = None;
+
+        self.show_notice(format!("Reloaded the {} skin", self.skin.name));
+    }
+
+    fn draw_pieces(&mut self, canvas: &mut Canvas) {
+        let board = self.displayed_board();
+        let hidden = self.hidden_squares();
+
+        let key = (
+            board,
+            hidden,
+            [self.board_dimensions.x_offset, self.board_dimensions.y_offset, self.board_dimensions.square_size],
+            self.flipped,
+        );
+
+        //Instances only need to be uploaded again when something moved
+        if self.batched != Some(key) {
+            self.fill_piece_batch(board, hidden);
+            self.batched = Some(key);
+        }
+
+        self.pieces.draw(canvas);
+    }
+
+    fn fill_piece_batch(&mut self, board: Board, hidden: BitBoard) {
+        self.pieces.clear();
+
+        for file in 0..8 {
+            for rank in 0..8 {
+                let square = Square::make_square(Rank::from_index(rank as usize), File::from_index(file as usize));
+
+                if hidden & BitBoard::from_square(square) != EMPTY {
+                    continue;
+                }
+
+                if let Some(piece) = board.piece_on(square) {
+                    let color = board.color_on(square).unwrap();
+
+                    let piece_image = self.skin.get_piece_image(piece, color);
+
+                    let (x, y) = self.chess_to_screen(rank, file);
+
+                    self.pieces.push(
+                        piece,
+                        color,
+                        graphics::DrawParam::default().dest([x, y]).scale([
+                            self.board_dimensions.square_size / piece_image.width() as f32,
+                            self.board_dimensions.square_size / piece_image.height() as f32,
+                        ]),
+                    );
+                }
+            }
+        }
+    }
+
+    //Side that can enter a premove: a human waiting for a computer or remote opponent
+    fn premove_color(&self) -> Option<chess::Color> {
+        let to_move = self.game.board.side_to_move();
+
+        (self.game.outcome.is_none() && !self.current_player().is_human() && self.player(!to_move).is_human()).then_some(!to_move)
+    }
+
+    //Board moves are picked from, which for premoves is the current one with the turn passed to the human
+    fn input_board(&self) -> Option<Board> {
+        if self.is_replay() || self.view_ply.is_some() {
+            None
+        } else if self.current_player().is_human() {
+            Some(self.game.board)
+        } else {
+            self.premove_color().and_then(|_| self.game.board.null_move())
+        }
+    }
+
+    fn generate_moves(&self) -> Vec<(ChessMove, (u8, u8))> {
+        //If no human can move right now, there is nothing that should be returned
+        let Some(board) = self.input_board() else {
+            return vec![];
+        };
+
+        if let Some(piece) = self.selected_drop {
+            return self.game.variant.rules().legal_moves(&board, &self.game.state)
+                .into_iter()
+                .filter_map(|m| match variant::as_drop(m) {
+                    Some((p, square)) if p == piece => Some((
+                        m,
+                        (square.get_rank().to_index() as u8, square.get_file().to_index() as u8),
+                    )),
+                    _ => None,
+                })
+                .collect();
+        }
+
+        if let Some((rank, file)) = self.selected_square {
+            let square = Square::make_square(
+                Rank::from_index(rank as usize),
+                File::from_index(file as usize),
+            );
+
+            self.game.variant.rules().legal_moves(&board, &self.game.state)
+                .into_iter()
+                .filter_map(|m| {
+                    if m.get_source() == square && variant::as_drop(m).is_none() {
+                        Some((
+                            m,
+                            (
+                                m.get_dest().get_rank().to_index() as u8,
+                                m.get_dest().get_file().to_index() as u8,
+                            ),
+                        ))
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
+    //Pockets are in a column right of the board, one at the top and one at the bottom
+    fn pocket_slot(&self, color: chess::Color, idx: usize) -> Rect {
+        let size = self.board_dimensions.square_size * 0.8;
+        let x = self.board_dimensions.x_offset + self.board_dimensions.square_size * 8.0 + self.board_dimensions.square_size * 0.1;
+
+        //The pocket of the side at the bottom of the board is at the bottom
+        let top = if self.flipped { chess::Color::White } else { chess::Color::Black };
+
+        let y = if color == top {
+            self.board_dimensions.y_offset + size * idx as f32
+        } else {
+            self.board_dimensions.y_offset + self.board_dimensions.square_size * 8.0 - size * (idx + 1) as f32
+        };
+
+        Rect::new(x, y, size, size)
+    }
+
+    fn screen_to_pocket(&self, x: f32, y: f32) -> Option<(chess::Color, Piece)> {
+        if !self.game.variant.rules().has_pockets() {
+            return None;
+        }
+
+        for color in [chess::Color::White, chess::Color::Black] {
+            for (idx, piece) in POCKET_PIECES.iter().enumerate() {
+                if self.pocket_slot(color, idx).contains([x, y]) {
+                    return Some((color, *piece));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn draw_pockets(&self, ctx: &mut Context, canvas: &mut Canvas) -> Result<(), GameError> {
+        let state = self.displayed_state();
+
+        for color in [chess::Color::White, chess::Color::Black] {
+            for (idx, piece) in POCKET_PIECES.iter().enumerate() {
+                let slot = self.pocket_slot(color, idx);
+                let count = state.pocket_count(color, *piece);
+
+                let background_color = if self.selected_drop == Some(*piece) && color == self.game.board.side_to_move() {
+                    palette().selected_light
+                } else {
+                    palette().light
+                };
+
+                mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), slot, 5.0, background_color)?;
+
+                let piece_image = self.skin.get_piece_image(*piece, color);
+
+                //Empty slots are faded out
+                let alpha = if count > 0 { 1.0 } else { 0.25 };
+
+                canvas.draw(
+                    piece_image,
+                    graphics::DrawParam::default()
+                        .dest([slot.x, slot.y])
+                        .scale([slot.w / piece_image.width() as f32, slot.h / piece_image.height() as f32])
+                        .color(Color::new(1.0, 1.0, 1.0, alpha)),
+                );
+
+                if count > 1 {
+                    let mut text = Text::new(count.to_string());
+                    text.set_scale(slot.h * 0.35);
+
+                    canvas.draw(
+                        &text,
+                        graphics::DrawParam::default()
+                            .dest([slot.x + slot.w * 0.7, slot.y + slot.h * 0.6])
+                            .color(Color::BLACK),
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw_available_moves(&self, ctx: &mut Context, canvas: &mut Canvas) {
+        let size = self.board_dimensions.square_size;
+        let shapes = shape_markers();
+
+        let circle = mesh_cache::circle(ctx, graphics::DrawMode::fill(), if shapes { size * 0.15 } else { size * 0.25 }).unwrap();
+
+        //With shape markers captures get a ring instead of a dot, and the selected square an outline
+        let ring = mesh_cache::circle(ctx, graphics::DrawMode::stroke(size * 0.08), size * 0.42).unwrap();
+
+        if let Some((rank, file)) = self.selected_square.filter(|_| shapes) {
+            let (x, y) = self.chess_to_screen(rank, file);
+
+            mesh_cache::draw_rectangle(
+                ctx,
+                canvas,
+                graphics::DrawMode::stroke(size * 0.06),
+                Rect::new(x + size * 0.03, y + size * 0.03, size * 0.94, size * 0.94),
+                palette().move_marker,
+            )
+            .unwrap();
+        }
+
+        for (m, (rank, file)) in self.generate_moves() {
+            let (x, y) = self.chess_to_screen(rank, file);
+
+            let marker = if shapes && variant::is_capture(&self.game.board, m) {
+                &ring
+            } else {
+                &circle
+            };
+
+            canvas.draw(
+                marker,
+                graphics::DrawParam::default()
+                    .dest([
+                        x + self.board_dimensions.square_size / 2.0,
+                        y + self.board_dimensions.square_size / 2.0,
+                    ])
+                    .color(palette().move_marker),
+            );
+        }
+    }
+
+    fn draw_premove(&self, ctx: &mut Context, canvas: &mut Canvas) {
+        let Some(m) = self.premove else {
+            return;
+        };
+
+        let size = self.board_dimensions.square_size;
+        let mut color = palette().move_marker;
+        color.a = 0.35;
+
+        for square in [m.get_source(), m.get_dest()] {
+            let (x, y) = self.chess_to_screen(square.get_rank().to_index() as u8, square.get_file().to_index() as u8);
+
+            mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), Rect::new(x, y, size, size), color).unwrap();
+        }
+    }
+
+    //The moving piece see-through on the square it would go to, with the square it leaves marked
+    fn draw_staged(&self, ctx: &mut Context, canvas: &mut Canvas) {
+        let Some(m) = self.staged.filter(|_| self.view_ply.is_none()) else {
+            return;
+        };
+
+        let size = self.board_dimensions.square_size;
+        let mut color = palette().move_marker;
+        color.a = 0.35;
+
+        let square = |square: Square| self.chess_to_screen(square.get_rank().to_index() as u8, square.get_file().to_index() as u8);
+
+        if variant::as_drop(m).is_none() {
+            let (x, y) = square(m.get_source());
+            mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), Rect::new(x, y, size, size), color).unwrap();
+        }
+
+        //Promotions and drops show the piece that ends up on the square
+        let Some(piece) = m.get_promotion().or_else(|| self.game.board.piece_on(m.get_source())) else {
+            return;
+        };
+
+        let image = self.skin.get_piece_image(piece, self.game.board.side_to_move());
+        let (x, y) = square(m.get_dest());
+
+        canvas.draw(
+            image,
+            graphics::DrawParam::default()
+                .dest([x, y])
+                .scale([size / image.width() as f32, size / image.height() as f32])
+                .color(Color::new(1.0, 1.0, 1.0, 0.55)),
+        );
+    }
+
+    //Black and white outline so it shows up on any square color
+    fn draw_cursor(&self, ctx: &mut Context, canvas: &mut Canvas) {
+        let Some((rank, file)) = self.cursor else {
+            return;
+        };
+
+        let size = self.board_dimensions.square_size;
+        let (x, y) = self.chess_to_screen(rank, file);
+
+        for (inset, color) in [(0.04, Color::BLACK), (0.1, Color::WHITE)] {
+            mesh_cache::draw_rectangle(
+                ctx,
+                canvas,
+                graphics::DrawMode::stroke(size * 0.06),
+                Rect::new(x + size * inset, y + size * inset, size * (1.0 - 2.0 * inset), size * (1.0 - 2.0 * inset)),
+                color,
+            )
+            .unwrap();
+        }
+    }
+
+    fn current_player(&self) -> &PlayerType {
+        self.game.current_player()
+    }
+
+    fn on_new_move(&mut self) {
+        if let Some(outcome) = self.game.rules_outcome() {
+            self.end_game(outcome);
+            return;
+        }
+
+        self.check_achievements();
+        self.try_launch_engine();
+    }
+
+    //Only games between the human and a bot count
+    fn check_achievements(&mut self) {
+        let (white, black) = (&self.game.white_player, &self.game.black_player);
+
+        let (human, opponent) = if white.is_human() && black.is_computer() {
+            (chess::Color::White, &self.tags.black)
+        } else if black.is_human() && white.is_computer() {
+            (chess::Color::Black, &self.tags.white)
+        } else {
+            return;
+        };
+
+        let game = HumanGame {
+            human,
+            opponent,
+            handicap: self.handicap,
+            start: &self.game.start_board,
+            board: &self.game.board,
+            plies: self.game.moves.len(),
+            outcome: self.game.outcome.as_ref(),
+        };
+
+        for achievement in achievements::check(&game) {
+            self.show_notice(format!("Achievement unlocked: {}, {}", achievement.name, achievement.description));
+        }
+    }
+
+    fn publish_outcome(&mut self) {
+        if let Some(outcome) = &self.game.outcome {
+            let text = outcome.get_text().to_string();
+            self.comment(text);
+            self.comment(Commentator::material_summary(&self.game.board));
+
+            for (color, name) in [(chess::Color::White, self.tags.white.clone()), (chess::Color::Black, self.tags.black.clone())] {
+                if let Some(summary) = self.game.mobility.summary(color) {
+                    self.comment(format!("{}: {}", name, summary));
+                }
+            }
+        }
+
+        if let Some(outcome) = &self.game.outcome {
+            events::publish(GameEvent::GameOver {
+                result: outcome.pgn_result().to_string(),
+                reason: outcome.get_text().to_string(),
+            });
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.paused == paused {
+            return;
+        }
+
+        self.paused = paused;
+        events::publish(GameEvent::Paused { paused });
+
+        if paused {
+            self.paused_at = Some(Instant::now());
+        } else if let Some(paused_at) = self.paused_at.take() {
+            self.turn_start += paused_at.elapsed();
+        }
+
+        if !paused {
+            self.try_launch_engine();
+        }
+    }
+
+    //Lets the computer to move play a single move while the game is paused
+    pub fn step(&mut self) -> Result<(), String> {
+        if self.game.outcome.is_some() {
+            return Err(String::from("The game is over"));
+        }
+
+        if !self.current_player().is_computer() {
+            return Err(String::from("It is a human's turn"));
+        }
+
+        self.launch_engine();
+
+        Ok(())
+    }
+
+    fn try_launch_engine(&mut self) {
+        if self.paused {
+            return;
+        }
+
+        self.launch_engine();
+    }
+
+    fn launch_engine(&mut self) {
+        let color = self.game.board.side_to_move();
+
+        self.game.start_search(self.think_time(color), self.engine_settings[color.to_index()]);
+    }
+
+    fn do_move(&mut self, m: ChessMove) {
+        self.staged = None;
+
+        let san = move_to_SAN(&self.game.board, m);
+        println!("Move: {}", san);
+
+        let color = self.game.board.side_to_move();
+
+        let elapsed = self.pending_time(color);
+        self.time_used[color.to_index()] += elapsed;
+        self.time_bonus[color.to_index()] += self.time_control(color).refund(elapsed);
+        self.turn_start = Instant::now();
+
+        //Moves made while paused start the next clock paused
+        if self.paused_at.is_some() {
+            self.paused_at = Some(self.turn_start);
+        }
+
+        //Untimed sides in a time odds game show how long they took instead
+        if self.is_timed() {
+            let ms = |color| self.remaining(color).unwrap_or_else(|| self.clock(color)).as_millis() as u64;
+
+            events::publish(GameEvent::Clock {
+                white_ms: ms(chess::Color::White),
+                black_ms: ms(chess::Color::Black),
+            });
+        }
+
+        //Moving declines the opponent's draw offer
+        if self.draw_offer == Some(!color) {
+            self.draw_offer = None;
+        }
+
+        self.game.apply(m);
+        self.annotations.push(MoveAnnotation {
+            move_time: Some(elapsed),
+            //The move's time is already off the clock and the increment back on
+            clock: self.remaining(color),
+            ..MoveAnnotation::default()
+        });
+
+        //The commentator only knows the standard rules
+        if self.game.variant == Variant::Standard {
+            let before = self.game.history[self.game.history.len() - 1];
+
+            for line in self.commentator.on_move(&before, m, &self.game.board, &self.game.history, self.game.reversable_moves) {
+                self.comment(line);
+            }
+        }
+
+        events::publish(GameEvent::Move {
+            ply: self.game.moves.len(),
+            san,
+            uci: variant::move_to_uci(m),
+            fen: self.game.board.to_string(),
+        });
+
+        let clock = self.clock_message();
+
+        if let Some(net) = self.net.as_mut() {
+            net.send_except(color, &NetMessage::Move { uci: variant::move_to_uci(m) });
+
+            if net.is_host() {
+                net.send_all(&clock);
+            }
+        }
+
+        self.on_new_move();
+
+        if self.auto_flip && self.game.outcome.is_none() {
+            self.hand_over();
+        }
+    }
+
+    pub fn to_pgn_game(&self) -> PgnGame {
+        let mut game = PgnGame::new(self.game.start_board);
+
+        game.set_tag("Event", &self.tags.event);
+        game.set_tag("Site", &self.tags.site);
+        game.set_tag("Date", &pgn_date());
+        game.set_tag("Round", &self.tags.round);
+        game.set_tag("White", &self.tags.white);
+        game.set_tag("Black", &self.tags.black);
+
+        if self.game.variant != Variant::Standard {
+            game.set_tag("Variant", self.game.variant.name());
+        }
+
+        if self.handicap != Handicap::None {
+            game.set_tag("Handicap", &self.handicap.name());
+        }
+
+        //Time odds games get a tag for each side instead, with - for an untimed side
+        let [white_time, black_time] = self.time_controls;
+
+        if white_time == black_time {
+            if let Some((time_control, delay)) = white_time.pgn_tags() {
+                game.set_tag("TimeControl", &time_control);
+
+                if let Some(delay) = delay {
+                    game.set_tag("TimeDelay", &delay);
+                }
+            }
+        } else {
+            for (color, time_control) in [("White", white_time), ("Black", black_time)] {
+                let (time_control, delay) = time_control.pgn_tags().unwrap_or((String::from("-"), None));
+                game.set_tag(&format!("{}TimeControl", color), &time_control);
+
+                if let Some(delay) = delay {
+                    game.set_tag(&format!("{}TimeDelay", color), &delay);
+                }
+            }
+        }
+
+        game.moves = self.game.moves.clone();
+        game.annotations = self.annotations.clone();
+
+        if let Some(outcome) = &self.game.outcome {
+            game.result = outcome.pgn_result().to_string();
+        } else if let Some(result) = &self.replay_result {
+            game.result = result.clone();
+        }
+
+        game
+    }
+
+    //What the bot to move said about `m`, if it's the move it just found
+    fn bot_explanation(&self, m: ChessMove) -> Option<Explanation> {
+        let PlayerType::Computer(engine) = self.current_player() else {
+            return None;
+        };
+
+        engine.try_lock().ok()?.explain().filter(|explanation| explanation.chosen == m && explanation.board == self.game.board)
+    }
+
+    //For the move that led to the position on screen
+    fn shown_explanation(&self) -> Option<&Explanation> {
+        let ply = self.view_ply.unwrap_or(self.game.moves.len()).checked_sub(1)?;
+        self.explanations.get(&ply)
+    }
+
+    pub fn explain_move(&mut self) {
+        match self.shown_explanation().map(|explanation| explanation.summary()) {
+            Some(summary) => self.show_notice(summary),
+            None => self.show_notice(String::from("Only evaluator bots can explain their moves")),
+        }
+    }
+
+    //Names start out as the players' names in the bot list or the imported game
+    pub fn open_tag_editor(&mut self) {
+        let defaults = GameTags { white: self.white_name.clone(), black: self.black_name.clone(), ..self.tags.clone() };
+        self.tag_editor = Some(Box::new(TagEditor::new(self.tags.clone(), defaults)));
+    }
+
+    pub fn editing_tags(&self) -> bool {
+        self.tag_editor.is_some()
+    }
+
+    //The new names are used on screen, by the commentator and in every PGN saved from now on
+    fn check_tag_editor(&mut self) {
+        let Some(editor) = self.tag_editor.take_if(|editor| editor.finished()) else {
+            return;
+        };
+
+        if editor.tags != self.tags {
+            self.tags = editor.tags;
+            self.commentator.rename(&self.tags.white, &self.tags.black);
+            self.show_notice(String::from("PGN tags updated"));
+        }
+    }
+
+    pub fn text_input_event(&mut self, character: char) {
+        if let Some(editor) = &mut self.tag_editor {
+            editor.text_input_event(character);
+        }
+    }
+
+    pub fn save_pgn(&self, annotated: bool) -> std::io::Result<PathBuf> {
+        let mut path = PathBuf::from("games");
+        std::fs::create_dir_all(&path)?;
+
+        path.push(format!("{}.pgn", timestamp()));
+        std::fs::write(&path, self.to_pgn_game().to_pgn(annotated))?;
+
+        Ok(path)
+    }
+
+    fn render_screenshot(&mut self, ctx: &mut Context) -> Result<Image, GameError> {
+        //Rows must be a multiple of 256 bytes to be read back from the GPU
+        let size = cli::args().screenshot_size.max(64).div_ceil(64) * 64;
+
+        let image = Image::new_canvas_image(ctx, ImageFormat::Rgba8UnormSrgb, size, size, 1);
+        let mut canvas = Canvas::from_image(ctx, image.clone(), BACKGROUND_COLOR);
+
+        let selected_square = self.selected_square.take();
+        let selected_drop = self.selected_drop.take();
+
+        self.update_dims(0.0, 0.0, size as f32, size as f32);
+        self.draw_blank_board(ctx, &mut canvas);
+        self.draw_pieces(&mut canvas);
+
+        self.selected_square = selected_square;
+        self.selected_drop = selected_drop;
+
+        canvas.finish(ctx)?;
+
+        Ok(image)
+    }
+
+    fn save_screenshot(&self, ctx: &mut Context, image: &Image) -> Result<PathBuf, GameError> {
+        let pixels = image.to_pixels(ctx)?;
+
+        let mut path = PathBuf::from("screenshots");
+        std::fs::create_dir_all(&path)?;
+        path.push(format!("{}.png", timestamp()));
+
+        image::save_buffer(&path, &pixels, image.width(), image.height(), image::ColorType::Rgba8)
+            .map_err(|e| GameError::CustomError(e.to_string()))?;
+
+        Ok(path)
+    }
+
+    pub fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+        //The screenshot is rendered during the previous frame and can only be read back once that frame was submitted
+        if let Some(image) = self.screenshot.take() {
+            match self.save_screenshot(ctx, &image) {
+                Ok(path) => println!("Saved screenshot to {:?}", path),
+                Err(e) => println!("Failed to save screenshot: {}", e),
+            }
+        }
+
+        let net_events = match self.net.as_mut() {
+            Some(net) => net.poll(),
+            None => vec![],
+        };
+
+        for event in net_events {
+            self.handle_net_event(event);
+        }
+
+        self.check_engines_ready();
+        self.check_flag();
+        self.update_analysis();
+
+        if let Some(ticker) = &mut self.ticker {
+            ticker.update();
+        }
+
+        self.play_computer_move();
+
+        //Instant mode waits on the next search too, so fast bots get more than one move in per frame
+        let frame_start = Instant::now();
+
+        while instant::instant() && self.game.searching() && frame_start.elapsed() < INSTANT_FRAME_TIME {
+            if !self.play_computer_move() {
+                thread::sleep(Duration::from_micros(200));
+            }
+        }
+
+        self.step_autoplay();
+
+        if self.current_player().is_human() && self.game.outcome.is_none() {
+            if let Some(m) = self.premove.take() {
+                if self.game.variant.rules().legal_moves(&self.game.board, &self.game.state).contains(&m) {
+                    self.do_move(m);
+                } else {
+                    println!("Premove {} is not legal anymore and was cancelled", variant::move_to_uci(m));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    //Searches are cancelled when the game ends, so a move only comes back while it is still going on
+    fn play_computer_move(&mut self) -> bool {
+        let m = match self.game.poll_search() {
+            Some(Ok(m)) => m,
+            Some(Err(outcome)) => {
+                self.end_game(outcome);
+                return true;
+            }
+            None => return false,
+        };
+
+        let legal = self.game.legal_moves();
+        let color = self.game.board.side_to_move();
+
+        //A bot that can't see the whole board may try moves that are illegal on the real one, which isn't its fault
+        if !legal.contains(&m) && self.game.variant.rules().player_view(&self.game.board, color) != self.game.board {
+            println!("Computer played illegal move {}, playing a random one instead", variant::move_to_uci(m));
+            self.do_move(legal[rand::thread_rng().gen_range(0..legal.len())]);
+            return true;
+        }
+
+        let name = match color {
+            chess::Color::White => self.tags.white.clone(),
+            chess::Color::Black => self.tags.black.clone(),
+        };
+
+        match self.game.check_move(m, &name) {
+            Ok(m) => {
+                if let Some(explanation) = self.bot_explanation(m) {
+                    self.explanations.insert(self.game.moves.len(), explanation);
+                }
+
+                self.do_move(m);
+            }
+            Err(outcome) => self.end_game(outcome),
+        }
+
+        true
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, canvas: &mut Canvas, x: f32, y: f32, w: f32, h: f32) -> Result<(), GameError> {
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            self.screenshot = Some(self.render_screenshot(ctx)?);
+        }
+
+        let screen = Rect::new(x, y, w, h);
+
+        //The board gets whatever the side panel leaves
+        self.split = self.layout.split(screen);
+        let Rect { x, y, w, h } = self.split.board;
+
+        //The history scrubber goes under the board, and the ticker under that
+        let scrubber_height = ui(SCRUBBER_HEIGHT);
+        let ticker_height = if self.ticker.is_some() { ui(TICKER_HEIGHT) } else { 0.0 };
+        self.update_dims(x, y, w , h - scrubber_height - ticker_height);
+
+        self.draw_blank_board(ctx, canvas);
+        self.draw_pieces(canvas);
+        self.draw_premove(ctx, canvas);
+        self.draw_staged(ctx, canvas);
+        self.draw_available_moves(ctx, canvas);
+        self.draw_cursor(ctx, canvas);
+        self.draw_pv_arrows(ctx, canvas)?;
+        self.draw_eval_bar(ctx, canvas)?;
+        self.draw_scrubber(ctx, canvas)?;
+        self.draw_commentary(ctx, canvas, x, w)?;
+
+        if let Some(ticker) = &self.ticker {
+            let track = self.scrubber_track();
+            ticker.draw(ctx, canvas, Rect::new(self.board_dimensions.x_offset, track.bottom(), self.board_dimensions.square_size * 8.0, ticker_height))?;
+        }
+
+        //Otherwise it has a tab of its own
+        if self.split.panel.is_none() {
+            self.draw_console(ctx, canvas)?;
+        }
+
+        if self.perf_hud && !self.is_replay() {
+            self.draw_perf_hud(ctx, canvas)?;
+        }
+
+        self.draw_notice(ctx, canvas)?;
+
+        if self.handing_over() {
+            self.draw_handoff(ctx, canvas)?;
+        }
+
+        if self.game.variant.rules().has_pockets() {
+            self.draw_pockets(ctx, canvas)?;
+        }
+
+        if let Some(result) = &self.replay_result {
+            let ply = self.view_ply.unwrap_or(self.game.history.len());
+
+            let mut text = Text::new(format!(
+                "{} vs {}\nPly {}/{}  Result: {}\nLeft/Right to step through the game",
+                self.tags.white,
+                self.tags.black,
+                ply,
+                self.game.moves.len(),
+                result
+            ));
+            text.set_scale(ui(20.0));
+
+            canvas.draw(&text, graphics::DrawParam::default().dest([x + 10.0, y + 10.0]).color(theme().text));
+        }
+
+        if let Some(status) = &self.net_status {
+            let mut caption = format!(
+                "{} ({}) vs {} ({})\n{}",
+                self.tags.white,
+                self.clock_text(chess::Color::White),
+                self.tags.black,
+                self.clock_text(chess::Color::Black),
+                status
+            );
+
+            if let Some(offer) = self.draw_offer {
+                caption.push_str(&format!("\n{:?} offers a draw", offer));
+            }
+
+            if self.local_color().is_some() {
+                caption.push_str("\nD to offer/accept a draw, R to resign");
+            }
+
+            let mut text = Text::new(caption);
+            text.set_scale(ui(20.0));
+
+            canvas.draw(&text, graphics::DrawParam::default().dest([x + 10.0, y + 10.0]).color(theme().text));
+        }
+
+        if self.net_status.is_none() && self.is_timed() {
+            let mut text = Text::new(format!(
+                "{} {}\n{} {}",
+                self.tags.white,
+                self.clock_text(chess::Color::White),
+                self.tags.black,
+                self.clock_text(chess::Color::Black)
+            ));
+            text.set_scale(ui(20.0));
+
+            canvas.draw(&text, graphics::DrawParam::default().dest([x + 10.0, y + 10.0]).color(theme().text));
+        }
+
+        if self.show_time_chart && (self.game.outcome.is_some() || self.is_replay()) && self.split.panel.is_none() {
+            self.draw_time_chart(ctx, canvas, self.time_chart_overlay())?;
+        }
+
+        //Hidden while looking back through the game so it doesn't cover the board
+        if let Some(outcome) = self.game.outcome.as_ref().filter(|_| self.view_ply.is_none()) {
+            let mut text = Text::default();
+
+            text.set_bounds([self.board_dimensions.square_size * 7.8, 10000000.0]);
+            
+            text.add(TextFragment::new(outcome.get_text()).scale(ui(60.0)).color(Color::BLACK));
+            text.add(TextFragment::new("\nPress ESC to return to main menu").scale(ui(25.0)).color(Color::new(0.4, 0.4, 0.4, 1.0)));
+            text.add(TextFragment::new("\nPress P to save PGN (Shift+P with annotations)").scale(ui(25.0)).color(Color::new(0.4, 0.4, 0.4, 1.0)));
+            text.add(TextFragment::new("\nPress Enter to replay the game").scale(ui(25.0)).color(Color::new(0.4, 0.4, 0.4, 1.0)));
+            
+            text.set_layout(TextLayout::center());
 
             let dims = text.measure(ctx)?;
 
@@ -489,24 +3362,8 @@ impl ChessDisplay {
                 dims.y + 20.0,
             );
 
-            let background = Mesh::new_rounded_rectangle(
-                ctx,
-                graphics::DrawMode::fill(),
-                background_bounds,
-                5.0,
-                [1.0, 1.0, 1.0, 0.5].into(),
-            )?;
-
-            let border = Mesh::new_rounded_rectangle(
-                ctx,
-                graphics::DrawMode::stroke(5.0),
-                background_bounds,
-                5.0,
-                [0.0, 0.0, 0.0, 1.0].into(),
-            )?;
-
-            canvas.draw(&background, graphics::DrawParam::default());
-            canvas.draw(&border, graphics::DrawParam::default());
+            mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), background_bounds, 5.0, [1.0, 1.0, 1.0, 0.5].into())?;
+            mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::stroke(5.0), background_bounds, 5.0, Color::BLACK)?;
 
             let x = self.board_dimensions.x_offset + self.board_dimensions.square_size * 4.0;
             let y = self.board_dimensions.y_offset + self.board_dimensions.square_size * 4.0;
@@ -520,6 +3377,18 @@ impl ChessDisplay {
             );
         }
 
+        if let Some(panel) = self.split.panel {
+            self.draw_side_panel(ctx, canvas, panel)?;
+        }
+
+        if self.show_engine_panel {
+            self.draw_engine_panel(ctx, canvas)?;
+        }
+
+        if let Some(editor) = &mut self.tag_editor {
+            editor.draw(ctx, canvas, screen)?;
+        }
+
         Ok(())
     }
 
@@ -530,29 +3399,266 @@ impl ChessDisplay {
         x: f32,
         y: f32,
     ) -> Result<(), GameError> {
+        //The click only uncovers the board
+        if self.handing_over() {
+            self.handoff = None;
+            return Ok(());
+        }
+
+        if let Some(editor) = &mut self.tag_editor {
+            editor.mouse_button_down_event(button, x, y);
+            self.check_tag_editor();
+
+            return Ok(());
+        }
+
+        if self.show_engine_panel {
+            if button == MouseButton::Left {
+                self.engine_panel_click(x, y);
+            }
+
+            return Ok(());
+        }
+
+        if button == MouseButton::Left && self.side_panel_click(x, y) {
+            return Ok(());
+        }
+
+        //Right click cancels a premove or a move waiting to be confirmed
+        if button == MouseButton::Right && self.premove.take().is_some() {
+            println!("Premove cancelled");
+        }
+
+        if button == MouseButton::Right {
+            self.staged = None;
+        }
+
         if button == MouseButton::Left {
-            if let Some(game_pos) = self.screen_to_chess(x, y) {
-                for (m, (rank, file)) in self.generate_moves() {
-                    if (rank, file) == game_pos {
-                        self.do_move(m);
-                        self.selected_square = None;
-                        return Ok(());
-                    }
-                }
+            if self.under_board_target(self.copy_fen_button()).contains([x, y]) {
+                self.copy_fen();
+                return Ok(());
+            }
 
-                if let Some((rank, file)) = self.selected_square {
-                    if (rank, file) == game_pos {
-                        self.selected_square = None;
-                    } else {
-                        self.selected_square = Some(game_pos);
-                    }
+            if self.under_board_target(self.live_button()).contains([x, y]) && self.press_live_button() {
+                return Ok(());
+            }
+
+            if self.under_board_target(self.scrubber_track()).contains([x, y]) {
+                self.autoplay = None;
+                self.scrubbing = true;
+                self.scrub_to(x);
+                return Ok(());
+            }
+
+            if let Some((color, piece)) = self.screen_to_pocket(x, y) {
+                let can_drop = Some(color) == self.input_board().map(|b| b.side_to_move()) && self.game.state.pocket_count(color, piece) > 0;
+
+                self.selected_square = None;
+                self.selected_drop = if can_drop && self.selected_drop != Some(piece) { Some(piece) } else { None };
+
+                return Ok(());
+            }
+
+            if let Some(game_pos) = self.screen_to_target(x, y) {
+                self.cursor = None;
+                self.click_square(game_pos);
+            }
+        }
+
+        Ok(())
+    }
+
+    //Plays a move to the square if one is available, otherwise selects it
+    fn click_square(&mut self, game_pos: (u8, u8)) {
+        //Clicking the staged move's square again plays it, clicking anywhere else takes it back off the board
+        if let Some(m) = self.staged.take() {
+            if game_pos == (m.get_dest().get_rank().to_index() as u8, m.get_dest().get_file().to_index() as u8) {
+                self.confirm_staged(m);
+                return;
+            }
+
+            self.notice = None;
+        }
+
+        for (m, (rank, file)) in self.generate_moves() {
+            if (rank, file) == game_pos {
+                if self.current_player().is_human() && self.confirm_moves {
+                    self.staged = Some(m);
+                    self.show_notice(format!("Click again or press Enter to play {}", move_to_SAN(&self.game.board, m)));
+                } else if self.current_player().is_human() {
+                    self.do_move(m);
                 } else {
-                    self.selected_square = Some(game_pos);
+                    println!("Premove: {}", variant::move_to_uci(m));
+                    self.premove = Some(m);
+                }
+
+                self.selected_square = None;
+                self.selected_drop = None;
+                return;
+            }
+        }
+
+        self.selected_drop = None;
+
+        if let Some((rank, file)) = self.selected_square {
+            if (rank, file) == game_pos {
+                self.selected_square = None;
+            } else {
+                self.selected_square = Some(game_pos);
+            }
+        } else {
+            self.selected_square = Some(game_pos);
+        }
+    }
+
+    //Plays a staged move if it is still the human's turn and the move is still legal
+    fn confirm_staged(&mut self, m: ChessMove) {
+        if self.current_player().is_human() && self.game.outcome.is_none() && self.game.legal_moves().contains(&m) {
+            self.notice = None;
+            self.do_move(m);
+        }
+    }
+
+    //Directions are as seen on screen
+    fn move_cursor(&mut self, d_rank: i8, d_file: i8) {
+        let (d_rank, d_file) = if self.flipped { (-d_rank, -d_file) } else { (d_rank, d_file) };
+
+        let (rank, file) = self.cursor.or(self.selected_square).unwrap_or(match self.game.board.side_to_move() {
+            chess::Color::White => (1, 4),
+            chess::Color::Black => (6, 4),
+        });
+
+        let cursor = (
+            (rank as i8 + d_rank).clamp(0, 7) as u8,
+            (file as i8 + d_file).clamp(0, 7) as u8,
+        );
+        self.cursor = Some(cursor);
+
+        if speech::enabled() {
+            speech::say(&self.describe_square(cursor));
+        }
+    }
+
+    //e.g. "f3, white knight"
+    fn describe_square(&self, (rank, file): (u8, u8)) -> String {
+        let square = Square::make_square(Rank::from_index(rank as usize), File::from_index(file as usize));
+        let board = self.displayed_board();
+
+        let contents = if self.hidden_squares() & BitBoard::from_square(square) != EMPTY {
+            String::from("hidden")
+        } else {
+            match (board.piece_on(square), board.color_on(square)) {
+                (Some(piece), Some(color)) => format!(
+                    "{:?} {}",
+                    color,
+                    speech::piece_word(piece.to_string(chess::Color::White).chars().next().unwrap()).unwrap()
+                ).to_ascii_lowercase(),
+                _ => String::from("empty"),
+            }
+        };
+
+        format!("{}, {}", square, contents)
+    }
+
+    pub fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput) -> Result<(), GameError> {
+        //Keyboard players uncover the board with any key
+        if self.handing_over() {
+            self.handoff = None;
+            return Ok(());
+        }
+
+        if let Some(editor) = &mut self.tag_editor {
+            editor.key_down_event(input);
+            self.check_tag_editor();
+
+            return Ok(());
+        }
+
+        match input.keycode {
+            Some(VirtualKeyCode::P) => match self.save_pgn(input.mods.contains(KeyMods::SHIFT)) {
+                Ok(path) => println!("Saved PGN to {:?}", path),
+                Err(e) => println!("Failed to save PGN: {}", e),
+            },
+
+            Some(VirtualKeyCode::C) if input.mods.contains(KeyMods::CTRL) => self.copy_fen(),
+
+            Some(VirtualKeyCode::E) if !self.is_replay() => self.toggle_engine_panel(),
+
+            Some(VirtualKeyCode::M) => self.open_tag_editor(),
+
+            Some(VirtualKeyCode::X) => self.explain_move(),
+
+            Some(VirtualKeyCode::Grave) => self.console_expanded = !self.console_expanded,
+
+            Some(VirtualKeyCode::Tab) => self.toggle_side_panel(),
+
+            Some(VirtualKeyCode::I) => {
+                instant::set_instant(!instant::instant());
+                self.show_notice(format!("Instant mode {}", if instant::instant() { "on" } else { "off" }));
+            }
+
+            Some(VirtualKeyCode::Z) if input.mods.contains(KeyMods::CTRL) && !self.is_replay() => self.take_back(),
+
+            Some(VirtualKeyCode::F12) => {
+                self.screenshot_requested = true;
+            }
+
+            Some(VirtualKeyCode::H) if !self.is_replay() => self.toggle_perf_hud(),
+
+            //For working on skins: the piece images are read from disk again
+            Some(VirtualKeyCode::F5) => self.reload_skin(ctx),
+
+            Some(VirtualKeyCode::D) if self.net.is_some() => {
+                self.offer_or_accept_draw();
+            }
+
+            Some(VirtualKeyCode::R) if self.net.is_some() => {
+                self.resign();
+            }
+
+            //- and = change how fast finished games are played back
+            Some(VirtualKeyCode::Minus) => {
+                self.autoplay_delay = (self.autoplay_delay * 3 / 2).min(Duration::from_secs(5));
+            }
+
+            Some(VirtualKeyCode::Equals) => {
+                self.autoplay_delay = (self.autoplay_delay * 2 / 3).max(Duration::from_millis(100));
+            }
+
+            //Shift and the arrow keys move the keyboard cursor, plain arrows step through the history
+            Some(VirtualKeyCode::Up) if input.mods.contains(KeyMods::SHIFT) && !self.is_replay() => self.move_cursor(1, 0),
+            Some(VirtualKeyCode::Down) if input.mods.contains(KeyMods::SHIFT) && !self.is_replay() => self.move_cursor(-1, 0),
+            Some(VirtualKeyCode::Left) if input.mods.contains(KeyMods::SHIFT) && !self.is_replay() => self.move_cursor(0, -1),
+            Some(VirtualKeyCode::Right) if input.mods.contains(KeyMods::SHIFT) && !self.is_replay() => self.move_cursor(0, 1),
+
+            Some(VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter) if self.live_button_focused() => {
+                self.press_live_button();
+            }
+
+            Some(VirtualKeyCode::Return) if !self.is_replay() => {
+                if let Some(m) = self.staged.take() {
+                    self.confirm_staged(m);
+                } else if let Some(cursor) = self.cursor {
+                    self.click_square(cursor);
+                }
+            }
+
+            //Pocket pieces are picked with 1 to 5 in crazyhouse
+            Some(key @ (VirtualKeyCode::Key1 | VirtualKeyCode::Key2 | VirtualKeyCode::Key3 | VirtualKeyCode::Key4 | VirtualKeyCode::Key5))
+                if self.game.variant.rules().has_pockets() && !self.is_replay() =>
+            {
+                let piece = POCKET_PIECES[key as usize - VirtualKeyCode::Key1 as usize];
+                let color = self.input_board().map(|b| b.side_to_move());
+
+                if color.is_some_and(|color| self.game.state.pocket_count(color, piece) > 0) {
+                    self.selected_square = None;
+                    self.selected_drop = if self.selected_drop != Some(piece) { Some(piece) } else { None };
                 }
             }
+
+            _ => {}
         }
 
         Ok(())
     }
 }
-
@@ -0,0 +1,59 @@
+use std::cell::RefCell;
+use std::str::FromStr;
+
+use arboard::Clipboard;
+use chess::Board;
+
+use crate::util::halfmove_clock;
+
+thread_local! {
+    //Kept open, since on X11 copied text is gone as soon as the clipboard that copied it is dropped
+    static CLIPBOARD: RefCell<Option<Clipboard>> = const { RefCell::new(None) };
+}
+
+fn with_clipboard<T>(f: impl FnOnce(&mut Clipboard) -> Result<T, arboard::Error>) -> Result<T, String> {
+    CLIPBOARD.with(|clipboard| {
+        let mut clipboard = clipboard.borrow_mut();
+
+        if clipboard.is_none() {
+            *clipboard = Some(Clipboard::new().map_err(|e| format!("Could not open the clipboard: {}", e))?);
+        }
+
+        f(clipboard.as_mut().unwrap()).map_err(|e| format!("Clipboard error: {}", e))
+    })
+}
+
+pub fn get_text() -> Result<String, String> {
+    with_clipboard(|c| c.get_text())
+}
+
+pub fn set_text(text: &str) -> Result<(), String> {
+    with_clipboard(|c| c.set_text(text))
+}
+
+//Position and halfmove clock from the first line of the clipboard. EPD style FENs without the move counters are accepted too
+pub fn paste_fen() -> Result<(Board, u32), String> {
+    let text = get_text()?;
+
+    if text.trim().is_empty() {
+        return Err(String::from("The clipboard has no text in it"));
+    }
+
+    parse_fen(&text)
+}
+
+//The first line of the text, allowing the move counters to be left off
+pub fn parse_fen(text: &str) -> Result<(Board, u32), String> {
+    let Some(line) = text.lines().map(str::trim).find(|l| !l.is_empty()) else {
+        return Err(String::from("There is no FEN in it"));
+    };
+
+    let fen = match line.split_whitespace().count() {
+        4 => format!("{} 0 1", line),
+        _ => line.to_string(),
+    };
+
+    let board = Board::from_str(&fen).map_err(|_| format!("Not a valid FEN: '{}'", line.chars().take(80).collect::<String>()))?;
+
+    Ok((board, halfmove_clock(&fen)))
+}
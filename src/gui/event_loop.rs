@@ -0,0 +1,160 @@
+use ggez::event::winit_event::{ElementState, Event, KeyboardInput, MouseScrollDelta, WindowEvent};
+use ggez::event::{process_event, ErrorOrigin, EventHandler};
+use ggez::input::keyboard::{KeyInput, KeyMods};
+use ggez::winit::dpi::LogicalPosition;
+use ggez::winit::event_loop::{ControlFlow, EventLoop};
+use ggez::{Context, GameError};
+
+use super::main_gui::MainGUI;
+
+//Reports an error from the GUI, returning whether the window should close because of it
+fn failed<T>(ctx: &mut Context, gui: &mut MainGUI, res: Result<T, GameError>, control_flow: &mut ControlFlow, origin: ErrorOrigin) -> bool {
+    let Err(e) = res else {
+        return false;
+    };
+
+    eprintln!("Error on EventHandler {:?}: {:?}", origin, e);
+
+    if gui.on_error(ctx, origin, e) {
+        *control_flow = ControlFlow::Exit;
+        return true;
+    }
+
+    false
+}
+
+//ggez's event loop, which has no event for files dropped onto the window, with one added. Everything else is handed
+//to the GUI the same way ggez does
+pub fn run(mut ctx: Context, event_loop: EventLoop<()>, mut gui: MainGUI) -> ! {
+    event_loop.run(move |mut event, _, control_flow| {
+        let ctx = &mut ctx;
+        let gui = &mut gui;
+
+        if ctx.quit_requested {
+            let res = gui.quit_event(ctx);
+            ctx.quit_requested = false;
+
+            if let Ok(false) = res {
+                ctx.continuing = false;
+            } else if failed(ctx, gui, res, control_flow, ErrorOrigin::QuitEvent) {
+                return;
+            }
+        }
+
+        if !ctx.continuing {
+            *control_flow = ControlFlow::Exit;
+            return;
+        }
+
+        *control_flow = ControlFlow::Poll;
+
+        process_event(ctx, &mut event);
+
+        let (res, origin) = match event {
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::DroppedFile(path) => (gui.file_dropped(ctx, path), ErrorOrigin::Update),
+
+                WindowEvent::Resized(size) => (gui.resize_event(ctx, size.width as f32, size.height as f32), ErrorOrigin::ResizeEvent),
+
+                WindowEvent::CloseRequested => {
+                    let res = gui.quit_event(ctx);
+
+                    if let Ok(false) = res {
+                        ctx.continuing = false;
+                    }
+
+                    (res.map(|_| ()), ErrorOrigin::QuitEvent)
+                }
+
+                WindowEvent::Focused(gained) => (gui.focus_event(ctx, gained), ErrorOrigin::FocusEvent),
+                WindowEvent::ReceivedCharacter(character) => (gui.text_input_event(ctx, character), ErrorOrigin::TextInputEvent),
+                WindowEvent::ModifiersChanged(mods) => {
+                    ctx.keyboard.set_modifiers(KeyMods::from(mods));
+                    return;
+                }
+
+                WindowEvent::KeyboardInput { input: KeyboardInput { state, virtual_keycode: keycode, scancode, .. }, .. } => {
+                    let input = KeyInput { scancode, keycode, mods: ctx.keyboard.active_mods() };
+
+                    match state {
+                        ElementState::Pressed => {
+                            let repeat = ctx.keyboard.is_key_repeated();
+                            (gui.key_down_event(ctx, input, repeat), ErrorOrigin::KeyDownEvent)
+                        }
+                        ElementState::Released => (gui.key_up_event(ctx, input), ErrorOrigin::KeyUpEvent),
+                    }
+                }
+
+                WindowEvent::MouseWheel { delta, .. } => {
+                    let (x, y) = match delta {
+                        MouseScrollDelta::LineDelta(x, y) => (x, y),
+                        MouseScrollDelta::PixelDelta(pos) => {
+                            let LogicalPosition { x, y } = pos.to_logical::<f32>(ctx.gfx.window().scale_factor());
+                            (x, y)
+                        }
+                    };
+
+                    (gui.mouse_wheel_event(ctx, x, y), ErrorOrigin::MouseWheelEvent)
+                }
+
+                WindowEvent::MouseInput { state, button, .. } => {
+                    let position = ctx.mouse.position();
+
+                    match state {
+                        ElementState::Pressed => (gui.mouse_button_down_event(ctx, button, position.x, position.y), ErrorOrigin::MouseButtonDownEvent),
+                        ElementState::Released => (gui.mouse_button_up_event(ctx, button, position.x, position.y), ErrorOrigin::MouseButtonUpEvent),
+                    }
+                }
+
+                WindowEvent::CursorMoved { .. } => {
+                    let (position, delta) = (ctx.mouse.position(), ctx.mouse.last_delta());
+                    (gui.mouse_motion_event(ctx, position.x, position.y, delta.x, delta.y), ErrorOrigin::MouseMotionEvent)
+                }
+
+                WindowEvent::Touch(touch) => {
+                    gui.track_touch(touch.phase, touch.location.x as f32, touch.location.y as f32);
+                    (gui.touch_event(ctx, touch.phase, touch.location.x, touch.location.y), ErrorOrigin::TouchEvent)
+                }
+                WindowEvent::CursorEntered { .. } => (gui.mouse_enter_or_leave(ctx, true), ErrorOrigin::MouseEnterOrLeave),
+                WindowEvent::CursorLeft { .. } => (gui.mouse_enter_or_leave(ctx, false), ErrorOrigin::MouseEnterOrLeave),
+
+                _ => return,
+            },
+
+            Event::MainEventsCleared => {
+                ctx.time.tick();
+
+                let res = gui.update(ctx);
+                if failed(ctx, gui, res, control_flow, ErrorOrigin::Update) {
+                    return;
+                }
+
+                if let Err(e) = ctx.gfx.begin_frame() {
+                    eprintln!("Error on GraphicsContext::begin_frame(): {:?}", e);
+                    *control_flow = ControlFlow::Exit;
+                }
+
+                let res = gui.draw(ctx);
+                if failed(ctx, gui, res, control_flow, ErrorOrigin::Draw) {
+                    return;
+                }
+
+                if let Err(e) = ctx.gfx.end_frame() {
+                    eprintln!("Error on GraphicsContext::end_frame(): {:?}", e);
+                    *control_flow = ControlFlow::Exit;
+                }
+
+                //Deltas add up over a frame, and the keyboard and mouse state is what the next frame compares against
+                ctx.mouse.reset_delta();
+                ctx.keyboard.save_keyboard_state();
+                ctx.mouse.save_mouse_state();
+
+                return;
+            }
+
+            _ => return,
+        };
+
+        failed(ctx, gui, res, control_flow, origin);
+    })
+}
@@ -0,0 +1,79 @@
+use ggez::{
+    graphics::{self, Canvas, Rect},
+    input::keyboard::{KeyInput, KeyMods},
+    winit::event::VirtualKeyCode,
+    Context, GameError,
+};
+
+use super::mesh_cache;
+use super::scale::ui;
+use super::theme::theme;
+
+//What a key does to the focus of a screen's controls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusKey {
+    Next,
+    Previous,
+    Activate,
+}
+
+impl FocusKey {
+    //Tab and the arrow keys go through the controls in order, Enter and Space press the focused one
+    pub fn from_input(input: KeyInput) -> Option<FocusKey> {
+        match input.keycode? {
+            VirtualKeyCode::Tab if input.mods.contains(KeyMods::SHIFT) => Some(FocusKey::Previous),
+            VirtualKeyCode::Tab | VirtualKeyCode::Down | VirtualKeyCode::Right => Some(FocusKey::Next),
+            VirtualKeyCode::Up | VirtualKeyCode::Left => Some(FocusKey::Previous),
+            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter | VirtualKeyCode::Space => Some(FocusKey::Activate),
+            _ => None,
+        }
+    }
+}
+
+//Which of a screen's controls the keyboard is on, counting from 0 in the order the screen lists them. Nothing is
+//focused until a key is used, so the ring never shows for the mouse
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Focus {
+    index: Option<usize>,
+}
+
+impl Focus {
+    pub fn is(&self, index: usize) -> bool {
+        self.index == Some(index)
+    }
+
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn clear(&mut self) {
+        self.index = None;
+    }
+
+    //Moves through `count` controls, wrapping around at either end. Returns the control to press when one is pressed
+    pub fn key(&mut self, key: FocusKey, count: usize) -> Option<usize> {
+        if count == 0 {
+            self.index = None;
+            return None;
+        }
+
+        self.index = match (key, self.index) {
+            (FocusKey::Activate, Some(index)) => return Some(index.min(count - 1)),
+            //The first press only shows where the focus is
+            (FocusKey::Activate, None) | (FocusKey::Next, None) => Some(0),
+            (FocusKey::Next, Some(index)) => Some((index + 1) % count),
+            (FocusKey::Previous, None) => Some(count - 1),
+            (FocusKey::Previous, Some(index)) => Some((index.min(count - 1) + count - 1) % count),
+        };
+
+        None
+    }
+}
+
+//The ring around the focused control, just outside it
+pub fn draw_ring(ctx: &Context, canvas: &mut Canvas, bounds: Rect) -> Result<(), GameError> {
+    let margin = ui(4.0);
+    let ring = Rect::new(bounds.x - margin, bounds.y - margin, bounds.w + margin * 2.0, bounds.h + margin * 2.0);
+
+    mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::stroke(3.0), ring, 12.0, theme().focus_outline)
+}
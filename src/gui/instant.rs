@@ -0,0 +1,13 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+//Bot games run as fast as the engines allow, with no frame rate limit, no replay delay and nothing read out. The board
+//is still drawn every frame
+static INSTANT: AtomicBool = AtomicBool::new(false);
+
+pub fn instant() -> bool {
+    INSTANT.load(Ordering::Relaxed)
+}
+
+pub fn set_instant(instant: bool) {
+    INSTANT.store(instant, Ordering::Relaxed);
+}
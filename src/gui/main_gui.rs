@@ -1,13 +1,53 @@
 use ggez::{
-    event::{EventHandler, MouseButton},
-    graphics::{self, Canvas, Color, Drawable, Text, Transform, Rect, TextFragment, MeshBuilder, Mesh},
+    event::{winit_event::TouchPhase, EventHandler, MouseButton},
+    graphics::{self, Canvas, Color, Drawable, Text, Transform, Rect, TextFragment, TextLayout, MeshBuilder, Mesh},
     mint::{Vector2, Point2},
-    Context, GameError, input::{mouse, keyboard::KeyInput}, winit::event::VirtualKeyCode,
+    Context, GameError, conf::FullscreenType, input::{mouse, keyboard::{KeyInput, KeyMods}}, winit::event::VirtualKeyCode,
 };
 
-use crate::alg::{ALL_PLAYER_TYPES, PlayerTypeSupplier};
-
-use super::chess_display::{PlayerType, ChessDisplay};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::Board;
+
+use crate::alg::{player_types, player_description, PlayerTypeSupplier, find_player_type};
+use crate::alg::external::{self, find_engine, EngineOptions, EngineSpec, Protocol};
+use crate::alg::{bot_preset, draw_claim, epsilon, relay};
+use crate::api::{ApiCommand, ApiRequest};
+use crate::autosave::{self, SavedGame, SavedTournament, AUTOSAVE_INTERVAL};
+use crate::achievements;
+use crate::calibrate::RatingFile;
+use crate::campaign::{Campaign, Opponent};
+use crate::daily::{DailyLog, Matchup};
+use crate::cli;
+use crate::clock::{TimeControl, TimeManagerKind};
+use crate::settings;
+use crate::handicap::Handicap;
+use crate::import::fetch_game;
+use crate::net::{NetMessage, NetSession};
+use crate::pgn::{GameTags, PgnGame};
+use crate::tournament::Tournament;
+use crate::variant::Variant;
+use crate::util::fen_with_halfmove_clock;
+
+use super::chess_display::{PlayerType, ChessDisplay, GameOptions};
+use super::scale::{ui, ui_scale, set_ui_scale};
+use super::mesh_cache;
+use super::palette::{current_palette, set_palette, set_shape_markers, shape_markers};
+use super::theme::{current_theme, set_theme, theme};
+use super::spectator::Spectator;
+use super::watch_folder::FolderWatcher;
+use super::clipboard;
+use super::text_input::TextInput;
+use super::option_editor::OptionEditor;
+use super::tag_editor::TagEditor;
+use super::instant;
+use super::focus::{self, Focus, FocusKey};
+use super::touch::TouchTracker;
 
 #[derive(Debug, Clone)]
 struct Button {
@@ -27,15 +67,15 @@ struct Button {
 
 impl Button {
     pub fn new(ctx: &mut Context, text: Text, color: Color, hover_color: Color, pos: Vector2<f32>) -> Self {
-        const PADDING: f32 = 10.0;
+        let padding = ui(10.0);
 
         let dims = text.measure(ctx).unwrap();
 
         let bounds = graphics::Rect::new(
-            -dims.x / 2.0 - PADDING,
-            -dims.y / 2.0 - PADDING,
-            dims.x + (PADDING * 2.0),
-            dims.y + (PADDING * 2.0),
+            -dims.x / 2.0 - padding,
+            -dims.y / 2.0 - padding,
+            dims.x + (padding * 2.0),
+            dims.y + (padding * 2.0),
         );
 
         let rect = graphics::Mesh::new_rounded_rectangle(
@@ -83,6 +123,16 @@ impl Button {
         self.just_pressed = false;
         pressed
     }
+
+    //Pressed from the keyboard
+    pub fn press(&mut self) {
+        self.just_pressed = true;
+    }
+
+    //Where it was last drawn
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.pos.x + self.bounds.x, self.pos.y + self.bounds.y, self.bounds.w, self.bounds.h)
+    }
 }
 
 impl Drawable for Button {
@@ -105,7 +155,7 @@ impl Drawable for Button {
 
         canvas.draw(
             &self.text,
-            graphics::DrawParam::default().dest([text_x, text_y]),
+            graphics::DrawParam::default().dest([text_x, text_y]).color(theme().button_text),
         );
     }
 
@@ -119,7 +169,7 @@ impl Drawable for Button {
 
 pub struct PlayerTypePicker {
     name: Text,
-    options: Vec<(PlayerTypeSupplier, Text)>,
+    options: Vec<(PlayerTypeSupplier, Text, Option<&'static str>)>,
     selected: usize,
 
     max_option_width: f32,
@@ -127,30 +177,45 @@ pub struct PlayerTypePicker {
 
     list_region: Rect,
     just_clicked_list: bool,
+
+    //Of the last draw, for moving up and down with the keyboard
+    num_columns: usize,
+    //Scrolls the selected bot into view on the next draw, after it was picked with the keyboard
+    reveal_selected: bool,
 }
 
 impl PlayerTypePicker {
     pub fn new(ctx: &mut Context, name: &str) -> Self {
         let mut text = Text::new(
             TextFragment::new(name)
-                .scale(75.0)
-                .color(Color::new(0.7, 0.7, 0.7, 1.0))
+                .scale(ui(75.0))
+                .color(theme().muted_text)
         );
 
         let mut options = vec![];
+        //From the last calibration
+        let ratings = RatingFile::load().ratings;
 
-        for (name, func) in ALL_PLAYER_TYPES.iter() {
+        for (name, func) in player_types().iter() {
             let mut text = Text::new(
-                TextFragment::new(*name)
-                    .scale(50.0)
-                    .color(Color::new(0.5, 0.5, 0.5, 1.0))
+                TextFragment::new(name.as_str())
+                    .scale(ui(50.0))
+                    .color(theme().option_text)
             );
 
-            options.push((*func, text));
+            if let Some(rating) = ratings.get(name) {
+                text.add(
+                    TextFragment::new(format!(" {:.0}", rating))
+                        .scale(ui(30.0))
+                        .color(theme().muted_text)
+                );
+            }
+
+            options.push((func.clone(), text, player_description(name)));
         }
 
         let max_option_width = options.iter()
-            .map(|(_, text)| text.measure(ctx).unwrap().x + 20.0)
+            .map(|(_, text, _)| text.measure(ctx).unwrap().x + ui(20.0))
             .reduce(|a, b| a.max(b))
             .unwrap_or(20.0);
 
@@ -163,6 +228,9 @@ impl PlayerTypePicker {
 
             list_region: Rect::new(0.0, 0.0, 0.0, 0.0),
             just_clicked_list: false,
+
+            num_columns: 1,
+            reveal_selected: false,
         }
     }
 
@@ -189,12 +257,21 @@ impl PlayerTypePicker {
         let base_x = bounds.x + (bounds.w / 2.0) - (total_width / 2.0);
         let base_y = bounds.y + dims.y + PADDING;
 
-        const HEIGHT: f32 = 50.0;
+        let row_height = ui(50.0);
 
-        let total_height = (self.options.len() as f32 / num_columns as f32).ceil() * HEIGHT;
+        let total_height = (self.options.len() as f32 / num_columns as f32).ceil() * row_height;
         let available_height = self.list_region.h - PADDING * 2.0;
         let max_scroll_offset = (total_height - available_height).max(0.0);
 
+        self.num_columns = num_columns.max(1);
+
+        if self.reveal_selected {
+            let selected_top = (self.selected / self.num_columns) as f32 * row_height;
+
+            self.scroll_offset = self.scroll_offset.min(selected_top).max(selected_top + row_height - available_height);
+            self.reveal_selected = false;
+        }
+
         self.scroll_offset = self.scroll_offset.min(max_scroll_offset).max(0.0);
 
         let base_y = base_y - self.scroll_offset;
@@ -202,20 +279,22 @@ impl PlayerTypePicker {
         let mut xi = 0;
         let mut yi = 0;
 
-        for (idx, (_, text)) in self.options.iter().enumerate() {
+        let mut hovered_description = None;
+
+        for (idx, (_, text, description)) in self.options.iter().enumerate() {
             let text_dims = text.measure(ctx)?;
 
             let base_cell_x = base_x + (xi as f32 * self.max_option_width);
-            let base_cell_y = base_y + (yi as f32 * HEIGHT);
+            let base_cell_y = base_y + (yi as f32 * row_height);
 
             let x = base_cell_x + (self.max_option_width - text_dims.x) / 2.0;
-            let y = base_cell_y + (HEIGHT - text_dims.y) / 2.0;
+            let y = base_cell_y + (row_height - text_dims.y) / 2.0;
 
             let bounds = graphics::Rect::new(
                 base_cell_x,
                 base_cell_y,
                 self.max_option_width,
-                HEIGHT,
+                row_height,
             );
 
             let hovered = bounds.contains(ctx.mouse.position());
@@ -224,11 +303,15 @@ impl PlayerTypePicker {
             if self.just_clicked_list && hovered {
                 self.selected = idx;
             } else if hovered {
-                outline_color = Some(Color::new(0.5, 0.5, 0.5, 1.0))
+                outline_color = Some(theme().hovered_outline)
+            }
+
+            if hovered {
+                hovered_description = *description;
             }
 
             if idx == self.selected {
-                outline_color = Some(Color::new(0.0, 0.0, 0.0, 1.0));
+                outline_color = Some(theme().selected_outline);
             }
 
             canvas.draw(
@@ -244,18 +327,7 @@ impl PlayerTypePicker {
                     bounds.h - 4.0,
                 );
 
-                let rect = graphics::Mesh::new_rounded_rectangle(
-                    ctx,
-                    graphics::DrawMode::stroke(3.0),
-                    outline_bounds,
-                    5.0,
-                    col
-                )?;
-
-                canvas.draw(
-                    &rect,
-                    graphics::DrawParam::default(),
-                );
+                mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::stroke(3.0), outline_bounds, 5.0, col)?;
             }
 
             xi += 1;
@@ -269,15 +341,25 @@ impl PlayerTypePicker {
         canvas.set_default_scissor_rect();
 
         //Draw rectangle border around list region
-        let rect = graphics::Mesh::new_rounded_rectangle(
-            ctx,
-            graphics::DrawMode::stroke(3.0),
-            self.list_region,
-            5.0,
-            Color::new(0.7, 0.7, 0.7, 1.0)
-        )?;
+        mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::stroke(3.0), self.list_region, 5.0, theme().panel_border)?;
 
-        canvas.draw(&rect, graphics::DrawParam::default());
+        if let Some(description) = hovered_description {
+            let mut text = Text::new(TextFragment::new(description).color(theme().tooltip_text));
+            text.set_scale(ui(20.0));
+            text.set_bounds([self.list_region.w - PADDING * 4.0, f32::INFINITY]);
+
+            let text_dims = text.measure(ctx)?;
+
+            let background_bounds = Rect::new(
+                self.list_region.x + PADDING,
+                self.list_region.bottom() - text_dims.y - PADDING * 3.0,
+                self.list_region.w - PADDING * 2.0,
+                text_dims.y + PADDING * 2.0,
+            );
+
+            mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), background_bounds, 5.0, theme().tooltip_background)?;
+            canvas.draw(&text, graphics::DrawParam::default().dest([background_bounds.x + PADDING, background_bounds.y + PADDING]));
+        }
 
         self.just_clicked_list = false;
 
@@ -300,241 +382,1838 @@ impl PlayerTypePicker {
         }
     }
 
+    //The arrow keys move through the bots while the picker has the keyboard focus. Keys that would leave the list aren't
+    //used, so the focus moves on instead
+    pub fn key_down_event(&mut self, input: KeyInput) -> bool {
+        let columns = self.num_columns;
+
+        let selected = match input.keycode {
+            Some(VirtualKeyCode::Left) if !self.selected.is_multiple_of(columns) => self.selected - 1,
+            Some(VirtualKeyCode::Right) if self.selected % columns + 1 < columns && self.selected + 1 < self.options.len() => self.selected + 1,
+            Some(VirtualKeyCode::Up) if self.selected >= columns => self.selected - columns,
+            Some(VirtualKeyCode::Down) if self.selected + columns < self.options.len() => self.selected + columns,
+            _ => return false,
+        };
+
+        self.selected = selected;
+        self.reveal_selected = true;
+
+        true
+    }
+
+    pub fn select(&mut self, name: &str) {
+        if let Some(idx) = player_types().iter().position(|(n, _)| n == name) {
+            self.selected = idx;
+        }
+    }
+
     pub fn get(&self, color: chess::Color) -> PlayerType {
         (self.options[self.selected].0)(color)
     }
+
+    pub fn get_name(&self) -> &'static str {
+        &player_types()[self.selected].0
+    }
 }
 
+//Something on a screen the keyboard focus can be moved to
+enum Control<'a> {
+    Button(&'a mut Button),
+    //Drawn without a Button, so pressing it clicks its middle
+    Area(Rect),
+    //Takes the arrow keys while it has the focus
+    Picker(&'a mut PlayerTypePicker),
+}
+
+impl Control<'_> {
+    fn rect(&self) -> Rect {
+        match self {
+            Control::Button(button) => button.rect(),
+            Control::Area(area) => *area,
+            Control::Picker(picker) => picker.list_region,
+        }
+    }
+}
+
+//Height of a bot's row on the campaign screen
+const CAMPAIGN_ROW_HEIGHT: f32 = 50.0;
+
 enum State {
-    MainMenu { new_game_button: Button },
+    MainMenu {
+        new_game_button: Button,
+        campaign_button: Button,
+        daily_button: Button,
+        import_button: Button,
+        join_button: Button,
+
+        //Appearance settings in the bottom left corner, with whether human moves need confirming
+        theme_button: Button,
+        palette_button: Button,
+        markers_button: Button,
+        confirm_button: Button,
+
+        //In the bottom right corner
+        profile_button: Button,
+    },
+
+    //Achievements earned and how far the campaign got
+    Profile,
+
+    //Bots in the order they have to be beaten, with a button for each one that can be played
+    Campaign {
+        campaign: Campaign,
+        play_buttons: Vec<(usize, Button)>,
+        scroll_offset: f32,
+    },
+
+    //Today's two bots, which can be watched or played against, and the daily games so far
+    Daily {
+        matchup: Matchup,
+        log: DailyLog,
+
+        watch_button: Button,
+        white_button: Button,
+        black_button: Button,
+    },
+
+    Import {
+        input: TextInput,
+        import_button: Button,
+
+        error: Option<String>,
+        pending: Option<Receiver<Result<PgnGame, String>>>,
+    },
+
+    Join {
+        input: TextInput,
+        join_button: Button,
+
+        error: Option<String>,
+        pending: Option<Receiver<Result<(NetSession, NetMessage), String>>>,
+    },
 
     GameCreator {
         white_picker: PlayerTypePicker,
         black_picker: PlayerTypePicker,
 
         launch_button: Button,
+        host_button: Button,
+
+        variant: Variant,
+        variant_button: Button,
+
+        handicap: Handicap,
+        handicap_button: Button,
+
+        //Under each picker
+        time_controls: [TimeControl; 2],
+        time_control_buttons: [Button; 2],
+        time_managers: [TimeManagerKind; 2],
+        time_manager_buttons: [Button; 2],
+
+        //Pasted with Ctrl+V, with its halfmove clock
+        start_position: Option<(Board, u32)>,
+        position_error: Option<String>,
+
+        //Only offered when both players are human
+        hotseat_flip: bool,
+        hotseat_button: Button,
+
+        //Set with the buttons under the pickers of UCI engines. Each side's options are kept with the engine they are
+        //for
+        engine_options: [(String, EngineOptions); 2],
+        option_editor: Option<Box<OptionEditor>>,
+
+        //Only kept when they differ from the players' names and the usual placeholders
+        tags: Option<GameTags>,
+        tag_editor: Option<Box<TagEditor>>,
     },
 
     Game {
         chess: ChessDisplay
     },
+
+    Spectator {
+        spectator: Spectator,
+    },
+
+    Watch {
+        watcher: FolderWatcher,
+    },
+
+    //Shown at startup when the last run didn't exit cleanly
+    Recovery {
+        game: Option<SavedGame>,
+        tournament: Option<SavedTournament>,
+
+        restore_button: Button,
+        discard_button: Button,
+
+        error: Option<String>,
+    },
 }
 
 impl State {
     fn main_menu(ctx: &mut Context) -> Self {
         let mut text = Text::new("New Game");
-        text.set_scale(50.0);
+        text.set_scale(ui(50.0));
 
         let button = Button::new(
             ctx, 
             text, 
-            Color::new(0.0, 0.0, 0.0, 1.0), 
-            Color::new(0.1, 0.1, 0.1, 1.0),
+            theme().button,
+            theme().button_hover,
+            [0.0, 0.0].into()
+        );
+
+        let mut campaign_text = Text::new("Campaign");
+        campaign_text.set_scale(ui(50.0));
+
+        let campaign_button = Button::new(
+            ctx,
+            campaign_text,
+            theme().button,
+            theme().button_hover,
+            [0.0, 0.0].into()
+        );
+
+        let mut daily_text = Text::new("Daily Matchup");
+        daily_text.set_scale(ui(50.0));
+
+        let daily_button = Button::new(
+            ctx,
+            daily_text,
+            theme().button,
+            theme().button_hover,
+            [0.0, 0.0].into()
+        );
+
+        let mut import_text = Text::new("Import from URL");
+        import_text.set_scale(ui(50.0));
+
+        let import_button = Button::new(
+            ctx,
+            import_text,
+            theme().button,
+            theme().button_hover,
+            [0.0, 0.0].into()
+        );
+
+        let mut join_text = Text::new("Join Network Game");
+        join_text.set_scale(ui(50.0));
+
+        let join_button = Button::new(
+            ctx,
+            join_text,
+            theme().button,
+            theme().button_hover,
             [0.0, 0.0].into()
         );
 
+        let markers = if shape_markers() { "Shapes" } else { "Dots" };
+        let confirm = if settings::get().confirm_moves { "On" } else { "Off" };
+
         State::MainMenu {
             new_game_button: button,
+            campaign_button,
+            daily_button,
+            import_button,
+            join_button,
+
+            theme_button: State::setting_button(ctx, format!("Theme: {}", current_theme().name())),
+            palette_button: State::setting_button(ctx, format!("Board colors: {}", current_palette().name())),
+            markers_button: State::setting_button(ctx, format!("Move markers: {}", markers)),
+            confirm_button: State::setting_button(ctx, format!("Confirm moves: {}", confirm)),
+
+            profile_button: State::setting_button(ctx, String::from("Profile")),
         }
     }
 
-    fn game_creator(ctx: &mut Context) -> Self {
-        let mut launch_text = Text::new("Start!");
-        launch_text.set_scale(50.0);
+    fn campaign(ctx: &mut Context) -> Self {
+        let campaign = Campaign::load();
 
-        State::GameCreator {
-            white_picker: PlayerTypePicker::new(ctx, "White"),
-            black_picker: PlayerTypePicker::new(ctx, "Black"),
+        let play_buttons = (0..campaign.opponents.len())
+            .filter(|idx| campaign.unlocked(*idx))
+            .map(|idx| {
+                let label = if campaign.opponents[idx].beaten() { "Rematch" } else { "Play" };
+                (idx, State::setting_button(ctx, label.to_string()))
+            })
+            .collect();
 
-            launch_button: Button::new(
-                ctx,
-                launch_text,
-                Color::new(0.0, 0.0, 0.0, 1.0),
-                Color::new(0.1, 0.1, 0.1, 1.0),
-                [0.0, 0.0].into()
-            ),
-        }
+        State::Campaign { campaign, play_buttons, scroll_offset: 0.0 }
     }
 
-    fn game(ctx: &mut Context, white: PlayerType, black: PlayerType) -> Self {
-        State::Game {
-            chess: ChessDisplay::new(ctx, white, black),
-        }
+    fn campaign_game(ctx: &mut Context, opponent: &Opponent) -> Self {
+        let Some((name, supplier)) = find_player_type(&opponent.name) else {
+            return State::campaign(ctx);
+        };
+
+        let human = opponent.human_color();
+        let bot = supplier(!human);
+
+        let (white, black, white_name, black_name) = match human {
+            chess::Color::White => (PlayerType::Human, bot, "Human", name),
+            chess::Color::Black => (bot, PlayerType::Human, name, "Human"),
+        };
+
+        let mut tags = GameTags::new(white_name, black_name);
+        tags.event = String::from("Chess Arena campaign");
+
+        let options = GameOptions {
+            tags: Some(tags),
+            campaign: Some(name.to_string()),
+            ..GameOptions::default()
+        };
+
+        State::game(ctx, white, black, white_name, black_name, options, false)
     }
 
-    pub fn update(&mut self, ctx: &mut Context) -> Result<Option<State>, GameError> {
-        match self {
-            State::MainMenu {new_game_button} => {
-                if new_game_button.just_pressed() {
-                    return Ok(Some(State::game_creator(ctx)));
-                }
-            }
+    fn daily(ctx: &mut Context) -> Self {
+        let matchup = Matchup::today();
 
-            State::GameCreator {white_picker, black_picker, launch_button} => {
-                if launch_button.just_pressed() {
-                    return Ok(Some(State::game(
-                        ctx, 
-                        white_picker.get(chess::Color::White), 
-                        black_picker.get(chess::Color::Black)
-                    )));
-                }
-            }
+        State::Daily {
+            white_button: State::setting_button(ctx, format!("Play White against {}", matchup.black)),
+            black_button: State::setting_button(ctx, format!("Play Black against {}", matchup.white)),
+            watch_button: State::setting_button(ctx, String::from("Watch")),
 
-            State::Game {chess} => {
-                chess.update(ctx)?;
-            }
+            matchup,
+            log: DailyLog::load(),
         }
+    }
 
-        Ok(None)
+    //The human can take over either side, otherwise the two bots play each other
+    fn daily_game(ctx: &mut Context, matchup: &Matchup, human: Option<chess::Color>) -> Self {
+        let (Some((white_bot, white_supplier)), Some((black_bot, black_supplier))) = (find_player_type(&matchup.white), find_player_type(&matchup.black)) else {
+            return State::daily(ctx);
+        };
+
+        let (white, white_name) = match human {
+            Some(chess::Color::White) => (PlayerType::Human, "Human"),
+            _ => (white_supplier(chess::Color::White), white_bot),
+        };
+
+        let (black, black_name) = match human {
+            Some(chess::Color::Black) => (PlayerType::Human, "Human"),
+            _ => (black_supplier(chess::Color::Black), black_bot),
+        };
+
+        let mut tags = GameTags::new(white_name, black_name);
+        tags.event = format!("Chess Arena daily matchup, {}", matchup.opening);
+
+        let options = GameOptions {
+            time_controls: [matchup.time_control; 2],
+            start: Some((matchup.start_board(), 0)),
+            tags: Some(tags),
+            daily: Some(matchup.clone()),
+            ..GameOptions::default()
+        };
+
+        State::game(ctx, white, black, white_name, black_name, options, false)
     }
 
-    pub fn draw(
-        &mut self,
-        ctx: &mut Context,
-        canvas: &mut Canvas,
-    ) -> Result<Option<State>, GameError> {
-        //get draw bounds
-        let width = canvas.screen_coordinates().unwrap().w;
-        let height = canvas.screen_coordinates().unwrap().h;
+    fn setting_button(ctx: &mut Context, label: String) -> Button {
+        let mut text = Text::new(label);
+        text.set_scale(ui(30.0));
 
-        match self {
-            State::MainMenu {new_game_button} => {
-                let mut title_text = Text::new("Chess Arena");
-                title_text.set_scale(100.0);
+        Button::new(
+            ctx,
+            text,
+            theme().button,
+            theme().button_hover,
+            [0.0, 0.0].into()
+        )
+    }
 
-                let measure = title_text.measure(ctx)?;
-                let text_height = measure.y;
-                let text_width = measure.x;
+    fn import(ctx: &mut Context) -> Self {
+        let mut import_text = Text::new("Import");
+        import_text.set_scale(ui(50.0));
 
-                let text_x = (width / 2.0) - (text_width / 2.0);
-                let text_y = (height * 0.4) - text_height;
+        State::Import {
+            input: TextInput::new("Lichess or chess.com game URL / ID"),
+            import_button: Button::new(
+                ctx,
+                import_text,
+                theme().button,
+                theme().button_hover,
+                [0.0, 0.0].into()
+            ),
 
-                canvas.draw(
-                    &title_text,
-                    graphics::DrawParam::default()
-                        .dest([text_x, text_y])
-                        .color(Color::from_rgb(255, 255, 255)),
-                );
+            error: None,
+            pending: None,
+        }
+    }
 
-                new_game_button.set_pos([width / 2.0, height * 0.6].into());
+    fn start_import(input: &str) -> Receiver<Result<PgnGame, String>> {
+        let (sender, receiver) = channel();
+        let input = input.to_string();
 
-                canvas.draw(
-                    new_game_button,
-                    graphics::DrawParam::default()
-                        .color(Color::from_rgb(255, 255, 255)),
-                );
-            }
+        thread::spawn(move || {
+            let _ = sender.send(fetch_game(&input));
+        });
 
-            State::GameCreator {white_picker, black_picker, launch_button} => {
-                let mut title_text = Text::new("Game Creator");
-                title_text.set_scale(100.0);
+        receiver
+    }
 
-                let measure = title_text.measure(ctx)?;
-                let text_height = measure.y;
-                let text_width = measure.x;
+    fn join(ctx: &mut Context) -> Self {
+        let mut join_text = Text::new("Join");
+        join_text.set_scale(ui(50.0));
 
-                //Title should be 20px from the top and centered
-                let text_x = (width / 2.0) - (text_width / 2.0);
-                let text_y = 20.0;
+        State::Join {
+            input: TextInput::new(&format!("Host address (port {} if omitted)", cli::args().net_port)),
+            join_button: Button::new(
+                ctx,
+                join_text,
+                theme().button,
+                theme().button_hover,
+                [0.0, 0.0].into()
+            ),
 
-                canvas.draw(
-                    &title_text,
-                    graphics::DrawParam::default()
-                        .dest([text_x, text_y])
-                        .color(Color::from_rgb(255, 255, 255)),
-                );
+            error: None,
+            pending: None,
+        }
+    }
 
-                let top = text_height + 40.0;
+    fn start_join(input: &str) -> Receiver<Result<(NetSession, NetMessage), String>> {
+        let (sender, receiver) = channel();
+        let address = input.trim().to_string();
 
-                let halfway = width / 2.0;
+        thread::spawn(move || {
+            let _ = sender.send(NetSession::join(&address));
+        });
 
-                let white_bounds = Rect::new(10.0, top, halfway - 20.0, height - top - 100.0);
-                let black_bounds = Rect::new(halfway + 10.0, top, halfway - 20.0, height - top - 100.0);
+        receiver
+    }
 
-                white_picker.draw(ctx, canvas, white_bounds)?;
-                black_picker.draw(ctx, canvas, black_bounds)?;
+    fn variant_button(ctx: &mut Context, variant: Variant) -> Button {
+        let mut text = Text::new(format!("Variant: {}", variant.name()));
+        text.set_scale(ui(30.0));
 
-                launch_button.set_pos([width / 2.0, height - 50.0].into());
+        Button::new(
+            ctx,
+            text,
+            theme().button,
+            theme().button_hover,
+            [0.0, 0.0].into()
+        )
+    }
 
-                canvas.draw(
-                    launch_button,
-                    graphics::DrawParam::default()
-                        .color(Color::from_rgb(255, 255, 255)),
-                );
+    fn handicap_button(ctx: &mut Context, handicap: Handicap) -> Button {
+        let mut text = Text::new(format!("Handicap: {}", handicap.name()));
+        text.set_scale(ui(30.0));
 
-                /*//Make a black line to separate the pickers
-                let mut line = MeshBuilder::new();
-                line.line(
-                    &[
-                        Point2 {x: halfway, y: white_bounds.top()},
-                        Point2 {x: halfway, y: white_bounds.bottom()}
-                    ],
-                    2.0,
-                    Color::from_rgb(0, 0, 0),
-                )?;
-                let line = line.build();
-                let line = Mesh::from_data(ctx, line);
+        Button::new(
+            ctx,
+            text,
+            theme().button,
+            theme().button_hover,
+            [0.0, 0.0].into()
+        )
+    }
 
-                canvas.draw(
-                    &line,
-                    graphics::DrawParam::default()
-                );*/
-            }
+    fn time_control_button(ctx: &mut Context, time_control: TimeControl) -> Button {
+        State::setting_button(ctx, format!("Time: {}", time_control.name()))
+    }
 
-            State::Game {chess} => {
-                chess.draw(ctx, canvas, 0.0, 0.0, width, height)?;
-            }
-        }
+    fn time_manager_button(ctx: &mut Context, time_manager: TimeManagerKind) -> Button {
+        State::setting_button(ctx, format!("Time manager: {}", time_manager.name()))
+    }
 
-        Ok(None)
+    fn hotseat_button(ctx: &mut Context, hotseat_flip: bool) -> Button {
+        State::setting_button(ctx, format!("Flip board each move: {}", if hotseat_flip { "On" } else { "Off" }))
     }
 
-    pub fn mouse_button_down_event(
-        &mut self,
-        ctx: &mut Context,
-        button: MouseButton,
-        x: f32,
-        y: f32,
-    ) -> Result<Option<State>, GameError> {
-        match self {
-            State::MainMenu {new_game_button} => {
-                new_game_button.process_click(x, y, button);
-            }
+    //The UCI engine picked, if it is one
+    fn uci_engine(picker: &PlayerTypePicker) -> Option<&'static EngineSpec> {
+        find_engine(picker.get_name()).filter(|engine| engine.protocol == Protocol::Uci)
+    }
 
-            State::GameCreator {white_picker, black_picker, launch_button} => {
-                white_picker.mouse_button_down_event(ctx, button, x, y);
-                black_picker.mouse_button_down_event(ctx, button, x, y);
-                launch_button.process_click(x, y, button);
-            }
+    //Every bot can be given an epsilon and a draw claim policy, and UCI engines and relays have more to set up. Presets
+    //already come with theirs
+    fn has_options(picker: &PlayerTypePicker) -> bool {
+        !["Human", "Remote"].contains(&picker.get_name()) && bot_preset::find(picker.get_name()).is_none()
+    }
 
-            State::Game {chess} => {
-                chess.mouse_button_down_event(ctx, button, x, y)?;
-            }
+    //A side's options, starting from the ones saved for the engine when it changed since they were last looked at
+    fn side_options<'a>(slot: &'a mut (String, EngineOptions), engine: &str) -> &'a mut EngineOptions {
+        if slot.0 != engine {
+            *slot = (engine.to_string(), settings::get().engine_options.get(engine).cloned().unwrap_or_default());
         }
 
-        Ok(None)
+        &mut slot.1
     }
 
-    pub fn mouse_motion_event(
-        &mut self,
-        ctx: &mut Context,
-        x: f32,
-        y: f32,
-        dx: f32,
-        dy: f32,
-    ) -> Result<Option<State>, GameError> {
+    //Under the picker, below its time control buttons
+    fn editor_open(&self) -> bool {
         match self {
-            State::MainMenu {new_game_button} => {
-                new_game_button.process_hover(x, y);
+            State::GameCreator {option_editor, tag_editor, ..} => option_editor.is_some() || tag_editor.is_some(),
+            State::Game {chess} => chess.editing_tags(),
+            _ => false,
+        }
+    }
+
+    //The screen's controls in the order Tab goes through them. Screens that use the keys for something else, like the
+    //board or a text box, have none
+    fn controls(&mut self, width: f32) -> Vec<Control<'_>> {
+        match self {
+            State::MainMenu {new_game_button, campaign_button, daily_button, import_button, join_button, theme_button, palette_button, markers_button, confirm_button, profile_button} => {
+                [new_game_button, campaign_button, daily_button, import_button, join_button, theme_button, palette_button, markers_button, confirm_button, profile_button]
+                    .into_iter()
+                    .map(Control::Button)
+                    .collect()
             }
 
-            State::GameCreator {white_picker, black_picker, launch_button} => {
+            State::Daily {watch_button, white_button, black_button, ..} => {
+                [watch_button, white_button, black_button].into_iter().map(Control::Button).collect()
+            }
+
+            State::Campaign {play_buttons, ..} => play_buttons.iter_mut().map(|(_, button)| Control::Button(button)).collect(),
+
+            State::GameCreator {option_editor: Some(_), ..} | State::GameCreator {tag_editor: Some(_), ..} => vec![],
+
+            State::GameCreator {white_picker, black_picker, launch_button, host_button, variant_button, handicap_button, time_control_buttons, time_manager_buttons, hotseat_button, ..} => {
+                let hotseat = white_picker.get_name() == "Human" && black_picker.get_name() == "Human";
+                let mut controls = vec![];
+
+                //Each side from the top down, then the rest
+                for ((picker, time_control_button), time_manager_button) in [white_picker, black_picker].into_iter().zip(time_control_buttons.iter_mut()).zip(time_manager_buttons.iter_mut()) {
+                    let options = State::has_options(picker).then(|| State::options_button(picker));
+
+                    controls.push(Control::Picker(picker));
+                    controls.push(Control::Button(time_control_button));
+                    controls.push(Control::Button(time_manager_button));
+                    controls.extend(options.map(Control::Area));
+                }
+
+                if hotseat {
+                    controls.push(Control::Button(hotseat_button));
+                }
+
+                controls.extend([variant_button, handicap_button, launch_button, host_button].map(Control::Button));
+                controls.push(Control::Area(State::tags_button(width)));
+
+                controls
+            }
+
+            State::Recovery {restore_button, discard_button, ..} => [restore_button, discard_button].into_iter().map(Control::Button).collect(),
+
+            _ => vec![],
+        }
+    }
+
+    //In the top right corner, beside the title
+    fn tags_button(width: f32) -> Rect {
+        let (w, h) = (ui(160.0), ui(54.0));
+
+        Rect::new(width - w - ui(20.0), ui(20.0), w, h)
+    }
+
+    fn options_button(picker: &PlayerTypePicker) -> Rect {
+        let (w, h) = (ui(240.0), ui(54.0));
+
+        Rect::new(picker.list_region.center().x - w / 2.0, picker.list_region.bottom() + ui(58.0), w, h)
+    }
+
+    //Engines are started with the options set for their side
+    fn picked_player(picker: &PlayerTypePicker, options: &mut (String, EngineOptions), color: chess::Color) -> PlayerType {
+        let name = picker.get_name();
+
+        match State::uci_engine(picker) {
+            Some(engine) => {
+                let options = State::side_options(options, name);
+                let player = epsilon::wrap(engine.player_type_with(options), epsilon::epsilon(options));
+                draw_claim::wrap(player, draw_claim::policy(options), color)
+            }
+            None if name == relay::NAME => {
+                let options = State::side_options(options, name);
+                let player = epsilon::wrap(PlayerType::computer(relay::relay_bot(color, options)), epsilon::epsilon(options));
+                draw_claim::wrap(player, draw_claim::policy(options), color)
+            }
+            None => picker.get(color),
+        }
+    }
+
+    //Starts out with whatever was picked last time
+    fn game_creator(ctx: &mut Context) -> Self {
+        let settings = settings::get();
+
+        let mut white_picker = PlayerTypePicker::new(ctx, "White");
+        let mut black_picker = PlayerTypePicker::new(ctx, "Black");
+
+        if let Some(name) = &settings.white {
+            white_picker.select(name);
+        }
+
+        if let Some(name) = &settings.black {
+            black_picker.select(name);
+        }
+
+        let variant = settings.variant;
+        let handicap = settings.handicap.as_deref().and_then(Handicap::from_name).unwrap_or_default();
+        let saved = settings.time_controls.map(|t| t.as_deref().and_then(TimeControl::from_name).unwrap_or_default());
+        let time_controls = [
+            cli::args().white_time.unwrap_or(saved[0]),
+            cli::args().black_time.unwrap_or(saved[1]),
+        ];
+        let time_managers = settings.time_managers;
+        let hotseat_flip = settings.hotseat_flip;
+
+        let mut launch_text = Text::new("Start!");
+        launch_text.set_scale(ui(50.0));
+
+        let mut host_text = Text::new("Host");
+        host_text.set_scale(ui(50.0));
+
+        State::GameCreator {
+            white_picker,
+            black_picker,
+
+            launch_button: Button::new(
+                ctx,
+                launch_text,
+                theme().button,
+                theme().button_hover,
+                [0.0, 0.0].into()
+            ),
+            host_button: Button::new(
+                ctx,
+                host_text,
+                theme().button,
+                theme().button_hover,
+                [0.0, 0.0].into()
+            ),
+
+            variant,
+            variant_button: State::variant_button(ctx, variant),
+
+            handicap,
+            handicap_button: State::handicap_button(ctx, handicap),
+
+            time_controls,
+            time_control_buttons: time_controls.map(|t| State::time_control_button(ctx, t)),
+
+            time_managers,
+            time_manager_buttons: time_managers.map(|m| State::time_manager_button(ctx, m)),
+
+            start_position: None,
+            position_error: None,
+
+            hotseat_flip,
+            hotseat_button: State::hotseat_button(ctx, hotseat_flip),
+
+            engine_options: Default::default(),
+            option_editor: None,
+
+            tags: None,
+            tag_editor: None,
+        }
+    }
+
+    //The game creator with a pasted position already set up
+    fn game_creator_from(ctx: &mut Context, position: (Board, u32)) -> Self {
+        let mut state = State::game_creator(ctx);
+
+        if let State::GameCreator {start_position, ..} = &mut state {
+            *start_position = Some(position);
+        }
+
+        state
+    }
+
+    //None if nothing was left behind by the last run
+    fn recovery(ctx: &mut Context) -> Option<Self> {
+        let game = autosave::load_game();
+        let tournament = autosave::load_tournament();
+
+        if game.is_none() && tournament.is_none() {
+            return None;
+        }
+
+        Some(State::Recovery {
+            game,
+            tournament,
+
+            restore_button: State::setting_button(ctx, String::from("Restore")),
+            discard_button: State::setting_button(ctx, String::from("Discard")),
+
+            error: None,
+        })
+    }
+
+    //Picks up the tournament if there is one, since its games can't be restored separately
+    fn restore(ctx: &mut Context, game: &Option<SavedGame>, tournament: &Option<SavedTournament>) -> Result<Self, String> {
+        if let Some(saved) = tournament {
+            let (tournament, switch_every) = saved.restore()?;
+            tournament.start();
+
+            return Ok(State::Spectator { spectator: Spectator::new(tournament, switch_every) });
+        }
+
+        let saved = game.as_ref().ok_or(String::from("Nothing to restore"))?;
+
+        let (_, white) = find_player_type(&saved.white).ok_or(format!("Unknown player type '{}'", saved.white))?;
+        let (_, black) = find_player_type(&saved.black).ok_or(format!("Unknown player type '{}'", saved.black))?;
+
+        let chess = ChessDisplay::resume(ctx, white(chess::Color::White), black(chess::Color::Black), saved)?;
+
+        Ok(State::Game { chess })
+    }
+
+    //Games with a remote player are always hosted
+    fn game(ctx: &mut Context, white: PlayerType, black: PlayerType, white_name: &str, black_name: &str, options: GameOptions, host: bool) -> Self {
+        let host = host || white.is_remote() || black.is_remote();
+
+        let mut chess = ChessDisplay::new(ctx, white, black, white_name, black_name, options);
+
+        if host {
+            if let Err(e) = chess.host(cli::args().net_port) {
+                println!("Could not host game: {}", e);
+            }
+        }
+
+        State::Game { chess }
+    }
+
+    //Buttons and pickers measure their text when they are created, so they are rebuilt when the UI scale changes
+    fn rescale(&mut self, ctx: &mut Context) {
+        match self {
+            State::MainMenu {..} => *self = State::main_menu(ctx),
+
+            State::Profile => {}
+
+            State::Daily {..} => *self = State::daily(ctx),
+
+            State::Campaign {scroll_offset, ..} => {
+                let offset = *scroll_offset;
+                *self = State::campaign(ctx);
+
+                if let State::Campaign {scroll_offset, ..} = self {
+                    *scroll_offset = offset;
+                }
+            }
+
+            State::Import {input, error, pending, ..} => {
+                let mut new = State::import(ctx);
+
+                if let State::Import {input: new_input, error: new_error, pending: new_pending, ..} = &mut new {
+                    std::mem::swap(input, new_input);
+                    std::mem::swap(error, new_error);
+                    std::mem::swap(pending, new_pending);
+                }
+
+                *self = new;
+            }
+
+            State::Join {input, error, pending, ..} => {
+                let mut new = State::join(ctx);
+
+                if let State::Join {input: new_input, error: new_error, pending: new_pending, ..} = &mut new {
+                    std::mem::swap(input, new_input);
+                    std::mem::swap(error, new_error);
+                    std::mem::swap(pending, new_pending);
+                }
+
+                *self = new;
+            }
+
+            State::GameCreator {white_picker, black_picker, variant, handicap, time_controls, time_managers, start_position, position_error, hotseat_flip, engine_options, option_editor, tags, tag_editor, ..} => {
+                let (white, black, variant, handicap) = (white_picker.get_name(), black_picker.get_name(), *variant, *handicap);
+                let (time_controls, time_managers) = (*time_controls, *time_managers);
+                let (start, error, hotseat) = (*start_position, position_error.take(), *hotseat_flip);
+                let (options, editor) = (std::mem::take(engine_options), option_editor.take());
+                let (tags, tag_editor) = (tags.take(), tag_editor.take());
+
+                let mut new = State::game_creator(ctx);
+
+                if let State::GameCreator {
+                    white_picker,
+                    black_picker,
+                    variant: new_variant,
+                    variant_button,
+                    handicap: new_handicap,
+                    handicap_button,
+                    time_controls: new_time_controls,
+                    time_control_buttons,
+                    time_managers: new_time_managers,
+                    time_manager_buttons,
+                    start_position: new_start_position,
+                    position_error: new_position_error,
+                    hotseat_flip: new_hotseat_flip,
+                    hotseat_button,
+                    engine_options: new_engine_options,
+                    option_editor: new_option_editor,
+                    tags: new_tags,
+                    tag_editor: new_tag_editor,
+                    ..
+                } = &mut new {
+                    white_picker.select(white);
+                    black_picker.select(black);
+
+                    *new_variant = variant;
+                    *variant_button = State::variant_button(ctx, variant);
+
+                    *new_handicap = handicap;
+                    *handicap_button = State::handicap_button(ctx, handicap);
+
+                    *new_time_controls = time_controls;
+                    *time_control_buttons = time_controls.map(|t| State::time_control_button(ctx, t));
+
+                    *new_time_managers = time_managers;
+                    *time_manager_buttons = time_managers.map(|m| State::time_manager_button(ctx, m));
+
+                    *new_start_position = start;
+                    *new_position_error = error;
+
+                    *new_hotseat_flip = hotseat;
+                    *hotseat_button = State::hotseat_button(ctx, hotseat);
+
+                    *new_engine_options = options;
+                    *new_option_editor = editor;
+
+                    *new_tags = tags;
+                    *new_tag_editor = tag_editor;
+                }
+
+                *self = new;
+            }
+
+            State::Recovery {restore_button, discard_button, ..} => {
+                *restore_button = State::setting_button(ctx, String::from("Restore"));
+                *discard_button = State::setting_button(ctx, String::from("Discard"));
+            }
+
+            State::Game {..} | State::Spectator {..} | State::Watch {..} => {}
+        }
+    }
+
+    pub fn update(&mut self, ctx: &mut Context) -> Result<Option<State>, GameError> {
+        match self {
+            State::MainMenu {new_game_button, campaign_button, daily_button, import_button, join_button, theme_button, palette_button, markers_button, confirm_button, profile_button} => {
+                //Every menu color comes from the theme, so the menu is simply rebuilt
+                if theme_button.just_pressed() {
+                    set_theme(current_theme().next());
+                    return Ok(Some(State::main_menu(ctx)));
+                }
+
+                if palette_button.just_pressed() {
+                    set_palette(current_palette().next());
+                    return Ok(Some(State::main_menu(ctx)));
+                }
+
+                if markers_button.just_pressed() {
+                    set_shape_markers(!shape_markers());
+                    return Ok(Some(State::main_menu(ctx)));
+                }
+
+                if confirm_button.just_pressed() {
+                    settings::update(|settings| settings.confirm_moves = !settings.confirm_moves);
+                    return Ok(Some(State::main_menu(ctx)));
+                }
+
+                if new_game_button.just_pressed() {
+                    return Ok(Some(State::game_creator(ctx)));
+                }
+
+                if campaign_button.just_pressed() {
+                    return Ok(Some(State::campaign(ctx)));
+                }
+
+                if daily_button.just_pressed() {
+                    return Ok(Some(State::daily(ctx)));
+                }
+
+                if profile_button.just_pressed() {
+                    return Ok(Some(State::Profile));
+                }
+
+                if import_button.just_pressed() {
+                    return Ok(Some(State::import(ctx)));
+                }
+
+                if join_button.just_pressed() {
+                    return Ok(Some(State::join(ctx)));
+                }
+            }
+
+            State::Profile => {}
+
+            State::Daily {matchup, watch_button, white_button, black_button, ..} => {
+                if watch_button.just_pressed() {
+                    return Ok(Some(State::daily_game(ctx, matchup, None)));
+                }
+
+                if white_button.just_pressed() {
+                    return Ok(Some(State::daily_game(ctx, matchup, Some(chess::Color::White))));
+                }
+
+                if black_button.just_pressed() {
+                    return Ok(Some(State::daily_game(ctx, matchup, Some(chess::Color::Black))));
+                }
+            }
+
+            State::Campaign {campaign, play_buttons, ..} => {
+                if let Some(idx) = play_buttons.iter_mut().find_map(|(idx, button)| button.just_pressed().then_some(*idx)) {
+                    return Ok(Some(State::campaign_game(ctx, &campaign.opponents[idx])));
+                }
+            }
+
+            State::Import {input, import_button, error, pending} => {
+                if import_button.just_pressed() && pending.is_none() {
+                    *error = None;
+                    *pending = Some(State::start_import(input.text()));
+                }
+
+                let result = pending.as_ref().and_then(|p| p.try_recv().ok());
+
+                match result {
+                    Some(Ok(game)) => {
+                        return Ok(Some(State::Game {
+                            chess: ChessDisplay::replay(ctx, game),
+                        }));
+                    }
+
+                    Some(Err(e)) => {
+                        *error = Some(e);
+                        *pending = None;
+                    }
+
+                    None => {}
+                }
+            }
+
+            State::Join {input, join_button, error, pending} => {
+                if join_button.just_pressed() && pending.is_none() {
+                    *error = None;
+                    *pending = Some(State::start_join(input.text()));
+                }
+
+                let result = pending.as_ref().and_then(|p| p.try_recv().ok());
+
+                match result {
+                    Some(Ok((session, welcome))) => {
+                        return Ok(Some(State::Game {
+                            chess: ChessDisplay::join(ctx, session, welcome),
+                        }));
+                    }
+
+                    Some(Err(e)) => {
+                        *error = Some(e);
+                        *pending = None;
+                    }
+
+                    None => {}
+                }
+            }
+
+            State::GameCreator {
+                white_picker,
+                black_picker,
+                launch_button,
+                host_button,
+                variant,
+                variant_button,
+                handicap,
+                handicap_button,
+                time_controls,
+                time_control_buttons,
+                time_managers,
+                time_manager_buttons,
+                start_position,
+                hotseat_flip,
+                hotseat_button,
+                engine_options,
+                option_editor,
+                tags,
+                tag_editor,
+                ..
+            } => {
+                if let Some(editor) = tag_editor.take_if(|editor| editor.finished()) {
+                    let defaults = GameTags::new(white_picker.get_name(), black_picker.get_name());
+                    *tags = (editor.tags != defaults).then_some(editor.tags);
+                }
+
+                //Closing the editor keeps its values for the side and saves them for the engine
+                if let Some(editor) = option_editor.take_if(|editor| editor.finished()) {
+                    let idx = editor.color.to_index();
+                    engine_options[idx] = (editor.engine.clone(), editor.values.clone());
+
+                    settings::update(|settings| {
+                        settings.engine_options.insert(editor.engine, editor.values);
+                    });
+                }
+
+                if hotseat_button.just_pressed() {
+                    *hotseat_flip = !*hotseat_flip;
+
+                    let pos = hotseat_button.pos;
+                    *hotseat_button = State::hotseat_button(ctx, *hotseat_flip);
+                    hotseat_button.set_pos(pos);
+                }
+
+                if variant_button.just_pressed() {
+                    *variant = variant.next();
+
+                    let pos = variant_button.pos;
+                    *variant_button = State::variant_button(ctx, *variant);
+                    variant_button.set_pos(pos);
+                }
+
+                if handicap_button.just_pressed() {
+                    *handicap = handicap.next();
+
+                    let pos = handicap_button.pos;
+                    *handicap_button = State::handicap_button(ctx, *handicap);
+                    handicap_button.set_pos(pos);
+                }
+
+                for (time_control, button) in time_controls.iter_mut().zip(time_control_buttons.iter_mut()) {
+                    if button.just_pressed() {
+                        *time_control = time_control.next();
+
+                        let pos = button.pos;
+                        *button = State::time_control_button(ctx, *time_control);
+                        button.set_pos(pos);
+                    }
+                }
+
+                for (time_manager, button) in time_managers.iter_mut().zip(time_manager_buttons.iter_mut()) {
+                    if button.just_pressed() {
+                        *time_manager = time_manager.next();
+
+                        let pos = button.pos;
+                        *button = State::time_manager_button(ctx, *time_manager);
+                        button.set_pos(pos);
+                    }
+                }
+
+                let launch = launch_button.just_pressed();
+                let host = host_button.just_pressed();
+
+                if launch || host {
+                    settings::update(|settings| {
+                        settings.white = Some(white_picker.get_name().to_string());
+                        settings.black = Some(black_picker.get_name().to_string());
+                        settings.variant = *variant;
+                        settings.handicap = Some(handicap.name());
+                        settings.time_controls = time_controls.map(|t| Some(t.name()));
+                        settings.time_managers = *time_managers;
+                        settings.hotseat_flip = *hotseat_flip;
+                    });
+
+                    let [white_options, black_options] = engine_options;
+
+                    return Ok(Some(State::game(
+                        ctx,
+                        State::picked_player(white_picker, white_options, chess::Color::White),
+                        State::picked_player(black_picker, black_options, chess::Color::Black),
+                        white_picker.get_name(),
+                        black_picker.get_name(),
+                        GameOptions {
+                            variant: *variant,
+                            handicap: *handicap,
+                            time_controls: *time_controls,
+                            time_managers: *time_managers,
+                            start: *start_position,
+                            auto_flip: *hotseat_flip,
+                            tags: tags.clone(),
+                            campaign: None,
+                            daily: None,
+                        },
+                        host,
+                    )));
+                }
+            }
+
+            State::Game {chess} => {
+                chess.update(ctx)?;
+            }
+
+            State::Spectator {spectator} => {
+                spectator.update(ctx)?;
+            }
+
+            State::Watch {watcher} => {
+                watcher.update(ctx)?;
+            }
+
+            State::Recovery {game, tournament, restore_button, discard_button, error} => {
+                if restore_button.just_pressed() {
+                    match State::restore(ctx, game, tournament) {
+                        Ok(state) => return Ok(Some(state)),
+                        Err(e) => *error = Some(e),
+                    }
+                }
+
+                if discard_button.just_pressed() {
+                    autosave::clear_all();
+                    return Ok(Some(State::main_menu(ctx)));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn draw(
+        &mut self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+    ) -> Result<Option<State>, GameError> {
+        //get draw bounds
+        let width = canvas.screen_coordinates().unwrap().w;
+        let height = canvas.screen_coordinates().unwrap().h;
+
+        match self {
+            State::MainMenu {new_game_button, campaign_button, daily_button, import_button, join_button, theme_button, palette_button, markers_button, confirm_button, profile_button} => {
+                let mut title_text = Text::new("Chess Arena");
+                title_text.set_scale(ui(100.0));
+
+                let measure = title_text.measure(ctx)?;
+                let text_height = measure.y;
+                let text_width = measure.x;
+
+                let text_x = (width / 2.0) - (text_width / 2.0);
+                let text_y = (height * 0.4) - text_height;
+
+                canvas.draw(
+                    &title_text,
+                    graphics::DrawParam::default()
+                        .dest([text_x, text_y])
+                        .color(theme().text),
+                );
+
+                for (i, button) in [new_game_button, campaign_button, daily_button, import_button, join_button].into_iter().enumerate() {
+                    button.set_pos([width / 2.0, height * 0.5 + ui(70.0 * i as f32)].into());
+
+                    canvas.draw(
+                        button,
+                        graphics::DrawParam::default()
+                            .color(theme().text),
+                    );
+                }
+
+                for (i, button) in [confirm_button, markers_button, palette_button, theme_button].into_iter().enumerate() {
+                    button.set_pos([button.dims.x / 2.0 + 20.0, height - ui(25.0 + 52.0 * i as f32)].into());
+
+                    canvas.draw(
+                        button,
+                        graphics::DrawParam::default()
+                            .color(theme().text),
+                    );
+                }
+
+                profile_button.set_pos([width - profile_button.dims.x / 2.0 - 20.0, height - ui(25.0)].into());
+
+                canvas.draw(
+                    &*profile_button,
+                    graphics::DrawParam::default()
+                        .color(theme().text),
+                );
+            }
+
+            State::Profile => {
+                let mut title_text = Text::new("Profile");
+                title_text.set_scale(ui(80.0));
+
+                let measure = title_text.measure(ctx)?;
+
+                canvas.draw(
+                    &title_text,
+                    graphics::DrawParam::default()
+                        .dest([(width / 2.0) - (measure.x / 2.0), 20.0])
+                        .color(theme().text),
+                );
+
+                let campaign = Campaign::load();
+                let mut lines = vec![(format!("Campaign: {} of {} bots beaten", campaign.beaten(), campaign.available()), theme().text), (String::new(), theme().text)];
+
+                for (achievement, earned) in achievements::progress() {
+                    let (status, color) = match earned {
+                        Some(date) => (format!("earned {}", date), theme().text),
+                        None => (String::from("not earned yet"), theme().muted_text),
+                    };
+
+                    lines.push((format!("{}: {}, {}", achievement.name, achievement.description, status), color));
+                }
+
+                lines.push((String::new(), theme().text));
+                lines.push((String::from("Esc to return to the main menu"), theme().muted_text));
+
+                let mut y = 40.0 + measure.y;
+
+                for (line, color) in lines {
+                    let mut text = Text::new(TextFragment::new(line).color(color));
+                    text.set_scale(ui(30.0));
+
+                    canvas.draw(&text, graphics::DrawParam::default().dest([ui(40.0), y]));
+                    y += ui(40.0);
+                }
+            }
+
+            State::Daily {matchup, log, watch_button, white_button, black_button} => {
+                let mut title_text = Text::new("Daily Matchup");
+                title_text.set_scale(ui(80.0));
+
+                let measure = title_text.measure(ctx)?;
+
+                canvas.draw(
+                    &title_text,
+                    graphics::DrawParam::default()
+                        .dest([(width / 2.0) - (measure.x / 2.0), 20.0])
+                        .color(theme().text),
+                );
+
+                let mut y = 40.0 + measure.y;
+
+                for (line, scale, color) in [
+                    (matchup.date.clone(), 30.0, theme().muted_text),
+                    (matchup.description(), 40.0, theme().text),
+                    (format!("Streak: {} days", log.streak()), 30.0, theme().muted_text),
+                ] {
+                    let mut text = Text::new(TextFragment::new(line).color(color));
+                    text.set_scale(ui(scale));
+
+                    let dims = text.measure(ctx)?;
+                    canvas.draw(&text, graphics::DrawParam::default().dest([(width / 2.0) - (dims.x / 2.0), y]));
+                    y += dims.y + ui(10.0);
+                }
+
+                y += ui(30.0);
+
+                for button in [watch_button, white_button, black_button] {
+                    button.set_pos([width / 2.0, y].into());
+                    y += ui(52.0);
+
+                    canvas.draw(
+                        &*button,
+                        graphics::DrawParam::default()
+                            .color(theme().text),
+                    );
+                }
+
+                y += ui(10.0);
+
+                //Latest first, as many as fit
+                let mut lines = vec![(String::from("Recent daily games"), theme().text)];
+
+                if log.games.is_empty() {
+                    lines.push((String::from("None yet"), theme().muted_text));
+                }
+
+                for game in log.games.iter().rev() {
+                    let color = if game.date == matchup.date { theme().text } else { theme().muted_text };
+                    lines.push((format!("{}  {} vs {}  {}  {}, {}", game.date, game.white, game.black, game.result, game.opening, game.time_control), color));
+                }
+
+                for (line, color) in lines {
+                    if y > height - ui(30.0) {
+                        break;
+                    }
+
+                    let mut text = Text::new(TextFragment::new(line).color(color));
+                    text.set_scale(ui(26.0));
+
+                    canvas.draw(&text, graphics::DrawParam::default().dest([ui(40.0), y]));
+                    y += ui(34.0);
+                }
+            }
+
+            State::Campaign {campaign, play_buttons, scroll_offset} => {
+                let mut title_text = Text::new("Campaign");
+                title_text.set_scale(ui(80.0));
+
+                let measure = title_text.measure(ctx)?;
+
+                canvas.draw(
+                    &title_text,
+                    graphics::DrawParam::default()
+                        .dest([(width / 2.0) - (measure.x / 2.0), 20.0])
+                        .color(theme().text),
+                );
+
+                let progress = match campaign.next() {
+                    Some(next) => format!("{} of {} bots beaten, {} is next", campaign.beaten(), campaign.available(), campaign.opponents[next].name),
+                    None => format!("Every one of the {} bots beaten", campaign.available()),
+                };
+
+                let mut progress_text = Text::new(TextFragment::new(progress).color(theme().muted_text));
+                progress_text.set_scale(ui(30.0));
+
+                let progress_measure = progress_text.measure(ctx)?;
+                canvas.draw(&progress_text, graphics::DrawParam::default().dest([(width / 2.0) - (progress_measure.x / 2.0), 30.0 + measure.y]));
+
+                let top = 50.0 + measure.y + progress_measure.y;
+                let row_height = ui(CAMPAIGN_ROW_HEIGHT);
+
+                //Rows scrolled above the header aren't drawn
+                let max_scroll = (campaign.opponents.len() as f32 * row_height - (height - top)).max(0.0);
+                *scroll_offset = scroll_offset.clamp(0.0, max_scroll);
+
+                for (idx, opponent) in campaign.opponents.iter().enumerate() {
+                    let y = top + idx as f32 * row_height - *scroll_offset;
+
+                    if y < top - 1.0 || y > height {
+                        continue;
+                    }
+
+                    let (status, color) = if !opponent.available {
+                        ("Not available", theme().muted_text)
+                    } else if opponent.beaten() {
+                        ("Beaten", theme().text)
+                    } else if campaign.unlocked(idx) {
+                        ("Next", theme().text)
+                    } else {
+                        ("Locked", theme().muted_text)
+                    };
+
+                    let rating = opponent.rating.map_or(String::from("unrated"), |rating| format!("{:.0}", rating));
+                    let line = format!("{}. {} ({})  {}  {}", idx + 1, opponent.name, rating, status, opponent.record());
+
+                    let mut text = Text::new(TextFragment::new(line).color(color));
+                    text.set_scale(ui(28.0));
+
+                    let dims = text.measure(ctx)?;
+                    canvas.draw(&text, graphics::DrawParam::default().dest([ui(20.0), y + (row_height - dims.y) / 2.0]));
+                }
+
+                for (idx, button) in play_buttons.iter_mut() {
+                    let y = top + *idx as f32 * row_height - *scroll_offset;
+
+                    //Off screen buttons are moved out of the way of clicks
+                    if y < top - 1.0 || y > height {
+                        button.set_pos([-width, -height].into());
+                        continue;
+                    }
+
+                    button.set_pos([width - button.dims.x / 2.0 - ui(30.0), y + row_height / 2.0].into());
+
+                    canvas.draw(
+                        &*button,
+                        graphics::DrawParam::default()
+                            .color(theme().text),
+                    );
+                }
+            }
+
+            State::Import {input, import_button, error, pending} => {
+                let mut title_text = Text::new("Import Game");
+                title_text.set_scale(ui(100.0));
+
+                let measure = title_text.measure(ctx)?;
+
+                canvas.draw(
+                    &title_text,
+                    graphics::DrawParam::default()
+                        .dest([(width / 2.0) - (measure.x / 2.0), 20.0])
+                        .color(theme().text),
+                );
+
+                let input_width = (width - 40.0).min(800.0);
+                input.draw(ctx, canvas, Rect::new((width - input_width) / 2.0, height * 0.4, input_width, ui(50.0)))?;
+
+                import_button.set_pos([width / 2.0, height * 0.4 + ui(120.0)].into());
+
+                canvas.draw(
+                    import_button,
+                    graphics::DrawParam::default()
+                        .color(theme().text),
+                );
+
+                let status = if pending.is_some() {
+                    Some(TextFragment::new("Loading...").color(theme().muted_text))
+                } else {
+                    error.as_ref().map(|e| TextFragment::new(e.as_str()).color(theme().error_text))
+                };
+
+                if let Some(status) = status {
+                    let mut status_text = Text::new(status);
+                    status_text.set_scale(ui(30.0));
+
+                    let measure = status_text.measure(ctx)?;
+
+                    canvas.draw(
+                        &status_text,
+                        graphics::DrawParam::default().dest([(width / 2.0) - (measure.x / 2.0), height * 0.4 + ui(180.0)]),
+                    );
+                }
+            }
+
+            State::Join {input, join_button, error, pending} => {
+                let mut title_text = Text::new("Join Game");
+                title_text.set_scale(ui(100.0));
+
+                let measure = title_text.measure(ctx)?;
+
+                canvas.draw(
+                    &title_text,
+                    graphics::DrawParam::default()
+                        .dest([(width / 2.0) - (measure.x / 2.0), 20.0])
+                        .color(theme().text),
+                );
+
+                let input_width = (width - 40.0).min(800.0);
+                input.draw(ctx, canvas, Rect::new((width - input_width) / 2.0, height * 0.4, input_width, ui(50.0)))?;
+
+                join_button.set_pos([width / 2.0, height * 0.4 + ui(120.0)].into());
+
+                canvas.draw(
+                    join_button,
+                    graphics::DrawParam::default()
+                        .color(theme().text),
+                );
+
+                let status = if pending.is_some() {
+                    Some(TextFragment::new("Connecting...").color(theme().muted_text))
+                } else {
+                    error.as_ref().map(|e| TextFragment::new(e.as_str()).color(theme().error_text))
+                };
+
+                if let Some(status) = status {
+                    let mut status_text = Text::new(status);
+                    status_text.set_scale(ui(30.0));
+
+                    let measure = status_text.measure(ctx)?;
+
+                    canvas.draw(
+                        &status_text,
+                        graphics::DrawParam::default().dest([(width / 2.0) - (measure.x / 2.0), height * 0.4 + ui(180.0)]),
+                    );
+                }
+            }
+
+            State::GameCreator {white_picker, black_picker, launch_button, host_button, variant_button, handicap_button, time_control_buttons, time_manager_buttons, start_position, position_error, hotseat_button, option_editor, tags, tag_editor, ..} => {
+                let mut title_text = Text::new("Game Creator");
+                title_text.set_scale(ui(100.0));
+
+                let measure = title_text.measure(ctx)?;
+                let text_height = measure.y;
+                let text_width = measure.x;
+
+                //Title should be 20px from the top and centered
+                let text_x = (width / 2.0) - (text_width / 2.0);
+                let text_y = 20.0;
+
+                canvas.draw(
+                    &title_text,
+                    graphics::DrawParam::default()
+                        .dest([text_x, text_y])
+                        .color(theme().text),
+                );
+
+                let top = text_height + 40.0;
+                let bottom = ui(100.0);
+                let hotseat = white_picker.get_name() == "Human" && black_picker.get_name() == "Human";
+                let options = State::has_options(white_picker) || State::has_options(black_picker);
+                //Room for the time control and time manager buttons and the start position under the pickers, the
+                //hotseat button in human vs human games and the options buttons of UCI engines and relays
+                let picker_bottom = bottom + ui(if hotseat { 132.0 } else { 80.0 }) + if options { ui(60.0) } else { 0.0 };
+
+                let halfway = width / 2.0;
+
+                let white_bounds = Rect::new(10.0, top, halfway - 20.0, height - top - picker_bottom);
+                let black_bounds = Rect::new(halfway + 10.0, top, halfway - 20.0, height - top - picker_bottom);
+
+                white_picker.draw(ctx, canvas, white_bounds)?;
+                black_picker.draw(ctx, canvas, black_bounds)?;
+
+                for ((time_control_button, time_manager_button), bounds) in time_control_buttons.iter_mut().zip(time_manager_buttons.iter_mut()).zip([white_bounds, black_bounds]) {
+                    time_control_button.set_pos([bounds.x + bounds.w * 0.25, bounds.bottom() + ui(26.0)].into());
+                    time_manager_button.set_pos([bounds.x + bounds.w * 0.75, bounds.bottom() + ui(26.0)].into());
+
+                    for button in [&*time_control_button, &*time_manager_button] {
+                        canvas.draw(
+                            button,
+                            graphics::DrawParam::default()
+                                .color(theme().text),
+                        );
+                    }
+                }
+
+                for picker in [&*white_picker, &*black_picker].into_iter().filter(|picker| State::has_options(picker)) {
+                    let button = State::options_button(picker);
+
+                    let color = if button.contains(ctx.mouse.position()) { theme().button_hover } else { theme().button };
+                    mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), button, 10.0, color)?;
+
+                    let label = if picker.get_name() == relay::NAME {
+                        "Relay bots"
+                    } else if State::uci_engine(picker).is_some() {
+                        "Engine options"
+                    } else {
+                        "Bot options"
+                    };
+                    let mut text = Text::new(TextFragment::new(label).color(theme().button_text));
+                    text.set_scale(ui(30.0));
+
+                    let dims = text.measure(ctx)?;
+                    canvas.draw(&text, graphics::DrawParam::default().dest([button.center().x - dims.x / 2.0, button.center().y - dims.y / 2.0]));
+                }
+
+                let (position_text, position_color) = match (&*position_error, &*start_position) {
+                    (Some(error), _) => (error.clone(), theme().error_text),
+                    (None, Some((board, halfmoves))) => (format!("Starting from {} (Delete to clear)", fen_with_halfmove_clock(board, *halfmoves)), theme().text),
+                    (None, None) => (String::from("Ctrl+V to start from a FEN"), theme().muted_text),
+                };
+
+                let mut position_text = Text::new(TextFragment::new(position_text).color(position_color));
+                position_text.set_scale(ui(18.0));
+                position_text.set_bounds([width - 40.0, f32::INFINITY]);
+
+                let dims = position_text.measure(ctx)?;
+                canvas.draw(&position_text, graphics::DrawParam::default().dest([(width - dims.x) / 2.0, height - bottom - ui(24.0)]));
+
+                if hotseat {
+                    hotseat_button.set_pos([width / 2.0, height - bottom - ui(58.0)].into());
+
+                    canvas.draw(
+                        hotseat_button,
+                        graphics::DrawParam::default()
+                            .color(theme().text),
+                    );
+                }
+
+                //Variant and handicap are stacked in the bottom left corner
+
+                variant_button.set_pos([variant_button.dims.x / 2.0 + 20.0, height - ui(77.0)].into());
+
+                canvas.draw(
+                    variant_button,
+                    graphics::DrawParam::default()
+                        .color(theme().text),
+                );
+
+                handicap_button.set_pos([handicap_button.dims.x / 2.0 + 20.0, height - ui(25.0)].into());
+
+                canvas.draw(
+                    handicap_button,
+                    graphics::DrawParam::default()
+                        .color(theme().text),
+                );
+
+                launch_button.set_pos([width * 0.55, height - ui(50.0)].into());
+
+                canvas.draw(
+                    launch_button,
+                    graphics::DrawParam::default()
+                        .color(theme().text),
+                );
+
+                host_button.set_pos([width * 0.75, height - ui(50.0)].into());
+
+                canvas.draw(
+                    host_button,
+                    graphics::DrawParam::default()
+                        .color(theme().text),
+                );
+
+                let tags_button = State::tags_button(width);
+
+                let color = if tags_button.contains(ctx.mouse.position()) { theme().button_hover } else { theme().button };
+                mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), tags_button, 10.0, color)?;
+
+                //Marked when the tags have been changed
+                let mut text = Text::new(TextFragment::new(if tags.is_some() { "PGN tags *" } else { "PGN tags" }).color(theme().button_text));
+                text.set_scale(ui(30.0));
+
+                let dims = text.measure(ctx)?;
+                canvas.draw(&text, graphics::DrawParam::default().dest([tags_button.center().x - dims.x / 2.0, tags_button.center().y - dims.y / 2.0]));
+
+                if let Some(editor) = option_editor {
+                    editor.draw(ctx, canvas, Rect::new(0.0, 0.0, width, height))?;
+                }
+
+                if let Some(editor) = tag_editor {
+                    editor.draw(ctx, canvas, Rect::new(0.0, 0.0, width, height))?;
+                }
+
+                /*//Make a black line to separate the pickers
+                let mut line = MeshBuilder::new();
+                line.line(
+                    &[
+                        Point2 {x: halfway, y: white_bounds.top()},
+                        Point2 {x: halfway, y: white_bounds.bottom()}
+                    ],
+                    2.0,
+                    theme().selected_outline,
+                )?;
+                let line = line.build();
+                let line = Mesh::from_data(ctx, line);
+
+                canvas.draw(
+                    &line,
+                    graphics::DrawParam::default()
+                );*/
+            }
+
+            State::Game {chess} => {
+                chess.draw(ctx, canvas, 0.0, 0.0, width, height)?;
+            }
+
+            State::Spectator {spectator} => {
+                spectator.draw(ctx, canvas, width, height)?;
+            }
+
+            State::Watch {watcher} => {
+                watcher.draw(ctx, canvas, width, height)?;
+            }
+
+            State::Recovery {game, tournament, restore_button, discard_button, error} => {
+                let mut title_text = Text::new("Restore Session?");
+                title_text.set_scale(ui(80.0));
+
+                let measure = title_text.measure(ctx)?;
+
+                canvas.draw(
+                    &title_text,
+                    graphics::DrawParam::default()
+                        .dest([(width / 2.0) - (measure.x / 2.0), 20.0])
+                        .color(theme().text),
+                );
+
+                let saved = tournament.as_ref().map(SavedTournament::describe).or(game.as_ref().map(SavedGame::describe)).unwrap_or_default();
+
+                let mut text = Text::default();
+
+                text.set_bounds([width - 40.0, f32::INFINITY]);
+
+                text.add(TextFragment::new("Chess Arena didn't exit cleanly last time. Pick up where it left off?").color(theme().muted_text));
+                text.add(TextFragment::new(format!("\n{}", saved)).color(theme().text));
+
+                if let Some(e) = error {
+                    text.add(TextFragment::new(format!("\n{}", e)).color(theme().error_text));
+                }
+
+                text.set_scale(ui(30.0));
+                text.set_layout(TextLayout::center());
+
+                canvas.draw(&text, graphics::DrawParam::default().dest([width / 2.0, height * 0.4]));
+
+                restore_button.set_pos([width / 2.0 - ui(100.0), height * 0.7].into());
+                discard_button.set_pos([width / 2.0 + ui(100.0), height * 0.7].into());
+
+                for button in [restore_button, discard_button] {
+                    canvas.draw(
+                        button,
+                        graphics::DrawParam::default()
+                            .color(theme().text),
+                    );
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> Result<Option<State>, GameError> {
+        match self {
+            State::MainMenu {new_game_button, campaign_button, daily_button, import_button, join_button, theme_button, palette_button, markers_button, confirm_button, profile_button} => {
+                new_game_button.process_click(x, y, button);
+                campaign_button.process_click(x, y, button);
+                daily_button.process_click(x, y, button);
+                import_button.process_click(x, y, button);
+                join_button.process_click(x, y, button);
+                theme_button.process_click(x, y, button);
+                palette_button.process_click(x, y, button);
+                markers_button.process_click(x, y, button);
+                confirm_button.process_click(x, y, button);
+                profile_button.process_click(x, y, button);
+            }
+
+            State::Profile => {}
+
+            State::Daily {watch_button, white_button, black_button, ..} => {
+                watch_button.process_click(x, y, button);
+                white_button.process_click(x, y, button);
+                black_button.process_click(x, y, button);
+            }
+
+            State::Campaign {play_buttons, ..} => {
+                for (_, play_button) in play_buttons.iter_mut() {
+                    play_button.process_click(x, y, button);
+                }
+            }
+
+            State::Import {input, import_button, ..} => {
+                input.mouse_button_down_event(button, x, y);
+                import_button.process_click(x, y, button);
+            }
+
+            State::Join {input, join_button, ..} => {
+                input.mouse_button_down_event(button, x, y);
+                join_button.process_click(x, y, button);
+            }
+
+            //The options editor takes every click while it is open
+            State::GameCreator {option_editor: Some(editor), ..} => {
+                editor.mouse_button_down_event(button, x, y);
+            }
+
+            State::GameCreator {tag_editor: Some(editor), ..} => {
+                editor.mouse_button_down_event(button, x, y);
+            }
+
+            State::GameCreator {white_picker, black_picker, launch_button, host_button, variant_button, handicap_button, time_control_buttons, time_manager_buttons, hotseat_button, engine_options, option_editor, position_error, tags, tag_editor, ..} => {
+                //The names start out as the picked players'
+                if button == MouseButton::Left && State::tags_button(ctx.gfx.drawable_size().0).contains([x, y]) {
+                    let defaults = GameTags::new(white_picker.get_name(), black_picker.get_name());
+                    *tag_editor = Some(Box::new(TagEditor::new(tags.clone().unwrap_or_else(|| defaults.clone()), defaults)));
+
+                    return Ok(None);
+                }
+
+                white_picker.mouse_button_down_event(ctx, button, x, y);
+                black_picker.mouse_button_down_event(ctx, button, x, y);
+                launch_button.process_click(x, y, button);
+                host_button.process_click(x, y, button);
+                variant_button.process_click(x, y, button);
+                handicap_button.process_click(x, y, button);
+                for time_button in time_control_buttons.iter_mut().chain(time_manager_buttons.iter_mut()) {
+                    time_button.process_click(x, y, button);
+                }
+
+                //Hidden unless both players are human
+                if white_picker.get_name() == "Human" && black_picker.get_name() == "Human" {
+                    hotseat_button.process_click(x, y, button);
+                }
+
+                //Asking the engine for its options starts it, so it can take a moment
+                for (idx, picker) in [&*white_picker, &*black_picker].into_iter().enumerate() {
+                    if button != MouseButton::Left || !State::has_options(picker) || !State::options_button(picker).contains([x, y]) {
+                        continue;
+                    }
+
+                    let color = if idx == 0 { chess::Color::White } else { chess::Color::Black };
+
+                    let name = picker.get_name();
+
+                    let Some(engine) = State::uci_engine(picker) else {
+                        let mut options = if name == relay::NAME { relay::options() } else { vec![] };
+                        options.extend([epsilon::option(), draw_claim::option()]);
+
+                        let values = State::side_options(&mut engine_options[idx], name).clone();
+                        *option_editor = Some(Box::new(OptionEditor::new(name, color, options, values)));
+                        continue;
+                    };
+
+                    match engine.uci_options() {
+                        Ok(options) => {
+                            let options = [vec![epsilon::option(), draw_claim::option()], options].concat();
+                            let values = State::side_options(&mut engine_options[idx], name).clone();
+                            *option_editor = Some(Box::new(OptionEditor::new(name, color, options, values)));
+                        }
+                        Err(e) => *position_error = Some(format!("Could not get the options of {}: {}", engine.name, e)),
+                    }
+                }
+            }
+
+            State::Game {chess} => {
+                chess.mouse_button_down_event(ctx, button, x, y)?;
+            }
+
+            State::Spectator {spectator} => {
+                if let Some(chess) = spectator.chess_mut() {
+                    chess.mouse_button_down_event(ctx, button, x, y)?;
+                }
+            }
+
+            State::Watch {watcher} => {
+                if let Some(chess) = watcher.chess_mut() {
+                    chess.mouse_button_down_event(ctx, button, x, y)?;
+                }
+            }
+
+            State::Recovery {restore_button, discard_button, ..} => {
+                restore_button.process_click(x, y, button);
+                discard_button.process_click(x, y, button);
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn mouse_motion_event(
+        &mut self,
+        ctx: &mut Context,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    ) -> Result<Option<State>, GameError> {
+        match self {
+            State::MainMenu {new_game_button, campaign_button, daily_button, import_button, join_button, theme_button, palette_button, markers_button, confirm_button, profile_button} => {
+                new_game_button.process_hover(x, y);
+                campaign_button.process_hover(x, y);
+                daily_button.process_hover(x, y);
+                import_button.process_hover(x, y);
+                join_button.process_hover(x, y);
+                theme_button.process_hover(x, y);
+                palette_button.process_hover(x, y);
+                markers_button.process_hover(x, y);
+                confirm_button.process_hover(x, y);
+                profile_button.process_hover(x, y);
+            }
+
+            State::Profile => {}
+
+            State::Daily {watch_button, white_button, black_button, ..} => {
+                watch_button.process_hover(x, y);
+                white_button.process_hover(x, y);
+                black_button.process_hover(x, y);
+            }
+
+            State::Campaign {play_buttons, ..} => {
+                for (_, button) in play_buttons.iter_mut() {
+                    button.process_hover(x, y);
+                }
+            }
+
+            State::Import {import_button, ..} => {
+                import_button.process_hover(x, y);
+            }
+
+            State::Join {join_button, ..} => {
+                join_button.process_hover(x, y);
+            }
+
+            State::GameCreator {option_editor: Some(_), ..} | State::GameCreator {tag_editor: Some(_), ..} => {}
+
+            State::GameCreator {white_picker, black_picker, launch_button, host_button, variant_button, handicap_button, time_control_buttons, time_manager_buttons, hotseat_button, ..} => {
                 white_picker.mouse_motion_event(ctx, x, y, dx, dy);
                 black_picker.mouse_motion_event(ctx, x, y, dx, dy);
                 launch_button.process_hover(x, y);
+                host_button.process_hover(x, y);
+                variant_button.process_hover(x, y);
+                handicap_button.process_hover(x, y);
+                for button in time_control_buttons.iter_mut().chain(time_manager_buttons.iter_mut()).chain([hotseat_button]) {
+                    button.process_hover(x, y);
+                }
+            }
+
+            State::Game {chess} => {
+                chess.mouse_motion_event(ctx, x, y);
+            }
+
+            State::Spectator {spectator} => {
+                if let Some(chess) = spectator.chess_mut() {
+                    chess.mouse_motion_event(ctx, x, y);
+                }
+            }
+
+            State::Watch {watcher} => {
+                if let Some(chess) = watcher.chess_mut() {
+                    chess.mouse_motion_event(ctx, x, y);
+                }
             }
 
-            State::Game {..} => {}
+            State::Recovery {restore_button, discard_button, ..} => {
+                restore_button.process_hover(x, y);
+                discard_button.process_hover(x, y);
+            }
         }
 
         Ok(None)
@@ -547,54 +2226,441 @@ impl State {
         y: f32,
     ) -> Result<Option<State>, GameError> {
         match self {
-            State::MainMenu {..} => {}
+            State::Campaign {scroll_offset, ..} => *scroll_offset -= y * ui(CAMPAIGN_ROW_HEIGHT),
+
+            State::MainMenu {..} | State::Profile | State::Daily {..} | State::Import {..} | State::Join {..} => {}
+
+            State::GameCreator {option_editor: Some(editor), ..} => editor.mouse_wheel_event(y),
+            State::GameCreator {tag_editor: Some(_), ..} => {}
 
             State::GameCreator {white_picker, black_picker, ..} => {
                 white_picker.mouse_wheel_event(ctx, x, y);
                 black_picker.mouse_wheel_event(ctx, x, y);
             }
 
-            State::Game {..} => {}
+            State::Game {..} | State::Spectator {..} | State::Watch {..} | State::Recovery {..} => {}
+        }
+
+        Ok(None)
+    }
+
+    pub fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        input: KeyInput,
+    ) -> Result<Option<State>, GameError> {
+        match self {
+            //Pasting a position sets up a new game from it
+            State::Game {chess} if input.keycode == Some(VirtualKeyCode::V) && input.mods.contains(KeyMods::CTRL) && !chess.editing_tags() => {
+                match clipboard::paste_fen() {
+                    Ok(position) => return Ok(Some(State::game_creator_from(ctx, position))),
+                    Err(e) => chess.show_notice(e),
+                }
+            }
+
+            State::Game {chess} => {
+                chess.key_down_event(ctx, input)?;
+            }
+
+            State::GameCreator {option_editor: Some(editor), ..} => editor.key_down_event(input),
+            State::GameCreator {tag_editor: Some(editor), ..} => editor.key_down_event(input),
+
+            State::GameCreator {start_position, position_error, ..} => match input.keycode {
+                Some(VirtualKeyCode::V) if input.mods.contains(KeyMods::CTRL) => match clipboard::paste_fen() {
+                    Ok(position) => {
+                        *start_position = Some(position);
+                        *position_error = None;
+                    }
+                    Err(e) => *position_error = Some(e),
+                },
+
+                Some(VirtualKeyCode::Delete | VirtualKeyCode::Back) => {
+                    *start_position = None;
+                    *position_error = None;
+                }
+
+                _ => {}
+            },
+
+            State::Spectator {spectator} => {
+                if input.keycode == Some(VirtualKeyCode::N) {
+                    spectator.next_board();
+                } else if input.keycode == Some(VirtualKeyCode::M) {
+                    //Tournament games are written by the tournament, so there are no tags to edit here
+                } else if let Some(chess) = spectator.chess_mut() {
+                    chess.key_down_event(ctx, input)?;
+                }
+            }
+
+            //Played back games have no tags worth editing either
+            State::Watch {watcher} if input.keycode != Some(VirtualKeyCode::M) => {
+                if let Some(chess) = watcher.chess_mut() {
+                    chess.key_down_event(ctx, input)?;
+                }
+            }
+
+            State::Import {input: text_input, error, pending, ..} => {
+                text_input.key_down_event(input);
+
+                if input.keycode == Some(VirtualKeyCode::Return) && pending.is_none() {
+                    *error = None;
+                    *pending = Some(State::start_import(text_input.text()));
+                }
+            }
+
+            State::Join {input: text_input, error, pending, ..} => {
+                text_input.key_down_event(input);
+
+                if input.keycode == Some(VirtualKeyCode::Return) && pending.is_none() {
+                    *error = None;
+                    *pending = Some(State::start_join(text_input.text()));
+                }
+            }
+
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    //PGNs open in the replay viewer and FENs in the game creator. Tournaments being watched and the recovery prompt
+    //aren't left for a dropped file
+    pub fn file_dropped(&mut self, ctx: &mut Context, path: &Path) -> Result<Option<State>, GameError> {
+        if matches!(self, State::Spectator {..} | State::Recovery {..}) || self.editor_open() {
+            return Ok(None);
+        }
+
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+
+        let opened = match extension.as_str() {
+            "pgn" => fs::read_to_string(path)
+                .map_err(|e| format!("Could not read {}: {}", path.display(), e))
+                .and_then(|text| PgnGame::parse(&text))
+                .map(|game| State::Game { chess: ChessDisplay::replay(ctx, game) }),
+
+            "fen" => fs::read_to_string(path)
+                .map_err(|e| format!("Could not read {}: {}", path.display(), e))
+                .and_then(|text| clipboard::parse_fen(&text))
+                .map(|position| State::game_creator_from(ctx, position)),
+
+            _ => Err(format!("Only .pgn and .fen files can be opened, not {}", path.display())),
+        };
+
+        match (opened, self) {
+            (Ok(state), _) => return Ok(Some(state)),
+
+            (Err(e), State::Game {chess}) => chess.show_notice(e),
+            (Err(e), State::GameCreator {position_error, ..}) => *position_error = Some(e),
+            (Err(e), State::Import {error, ..} | State::Join {error, ..}) => *error = Some(e),
+            (Err(e), _) => println!("{}", e),
+        }
+
+        Ok(None)
+    }
+
+    pub fn text_input_event(&mut self, _ctx: &mut Context, character: char) -> Result<Option<State>, GameError> {
+        match self {
+            State::Import {input, ..} | State::Join {input, ..} => input.text_input_event(character),
+            State::GameCreator {option_editor: Some(editor), ..} => editor.text_input_event(character),
+            State::GameCreator {tag_editor: Some(editor), ..} => editor.text_input_event(character),
+            State::Game {chess} => chess.text_input_event(character),
+            _ => {}
         }
 
         Ok(None)
     }
 }
 
+//Screens the window can open on from the command line
+pub enum StartScreen {
+    //Tournament to watch, and how long to stay on each board
+    Spectate(Arc<Tournament>, Duration),
+    Replay(PgnGame),
+    Watch(Box<FolderWatcher>),
+}
+
 pub struct MainGUI {
     state: State,
+
+    api_requests: Option<Receiver<ApiRequest>>,
+
+    //Used to drop to the idle frame rate when nothing is happening
+    last_activity: Instant,
+    last_board: Option<Board>,
+    frame_start: Instant,
+
+    autosaved_at: Instant,
+
+    //On the screen's controls, see State::controls
+    focus: Focus,
+
+    touch: TouchTracker,
 }
 
+//How long the window has to go without input or moves before the idle frame rate kicks in
+const IDLE_AFTER: Duration = Duration::from_secs(3);
+
 impl MainGUI {
-    pub fn new(ctx: &mut Context) -> Self {
+    pub fn new(ctx: &mut Context, api_requests: Option<Receiver<ApiRequest>>) -> Self {
         MainGUI {
-            state: State::main_menu(ctx),
+            state: State::recovery(ctx).unwrap_or_else(|| State::main_menu(ctx)),
+
+            api_requests,
+
+            last_activity: Instant::now(),
+            last_board: None,
+            frame_start: Instant::now(),
+
+            autosaved_at: Instant::now(),
+
+            focus: Focus::default(),
+
+            touch: TouchTracker::default(),
+        }
+    }
+
+    //Called for every touch before ggez turns it into the left mouse button
+    pub fn track_touch(&mut self, phase: TouchPhase, x: f32, y: f32) {
+        self.touch.track(phase, x, y);
+    }
+
+    //Opens on something other than the main menu
+    pub fn starting_with(ctx: &mut Context, api_requests: Option<Receiver<ApiRequest>>, start: StartScreen) -> Self {
+        let mut gui = MainGUI::new(ctx, api_requests);
+
+        gui.state = match start {
+            StartScreen::Spectate(tournament, switch_every) => State::Spectator { spectator: Spectator::new(tournament, switch_every) },
+            StartScreen::Replay(game) => State::Game { chess: ChessDisplay::replay(ctx, game) },
+            StartScreen::Watch(watcher) => State::Watch { watcher: *watcher },
+        };
+
+        gui
+    }
+
+    fn active(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    //Sleeps out the rest of the frame so a static screen doesn't keep the CPU and GPU busy
+    fn limit_frame_rate(&mut self) {
+        let board = match &self.state {
+            State::Game {chess} => Some(chess.game.board),
+            State::Spectator {spectator} => spectator.board(),
+            State::Watch {watcher} => watcher.board(),
+            _ => None,
+        };
+
+        if board != self.last_board {
+            self.last_board = board;
+            self.active();
+        }
+
+        let args = cli::args();
+
+        let fps = if self.last_activity.elapsed() < IDLE_AFTER {
+            args.max_fps
+        } else {
+            args.idle_fps
+        };
+
+        if fps > 0 && !instant::instant() {
+            let frame_time = Duration::from_secs_f64(1.0 / fps as f64);
+            thread::sleep(frame_time.saturating_sub(self.frame_start.elapsed()));
+        }
+
+        self.frame_start = Instant::now();
+    }
+
+    fn handle_api_command(&mut self, ctx: &mut Context, command: ApiCommand) -> Result<(), String> {
+        match command {
+            ApiCommand::StartGame { white, black, variant } => {
+                let (white_name, white) = find_player_type(&white).ok_or(format!("Unknown player type '{}'", white))?;
+                let (black_name, black) = find_player_type(&black).ok_or(format!("Unknown player type '{}'", black))?;
+
+                self.state = State::game(
+                    ctx,
+                    white(chess::Color::White),
+                    black(chess::Color::Black),
+                    white_name,
+                    black_name,
+                    GameOptions {
+                        variant,
+                        ..GameOptions::default()
+                    },
+                    false,
+                );
+
+                Ok(())
+            }
+
+            ApiCommand::SetPaused(paused) => match &mut self.state {
+                State::Game {chess} => {
+                    chess.set_paused(paused);
+                    Ok(())
+                }
+                _ => Err(String::from("No game in progress")),
+            },
+
+            ApiCommand::Step => match &mut self.state {
+                State::Game {chess} => chess.step(),
+                _ => Err(String::from("No game in progress")),
+            },
+        }
+    }
+
+    //Keeps the files for the screen being shown and removes the rest
+    fn autosave(&mut self) {
+        self.autosaved_at = Instant::now();
+
+        match &self.state {
+            //Left alone until the player decides what to do with them
+            State::Recovery {..} => {}
+
+            State::Game {chess} => {
+                match chess.saved_game() {
+                    Some(game) => autosave::save_game(&game),
+                    None => autosave::clear_game(),
+                }
+
+                autosave::clear_tournament();
+            }
+
+            State::Spectator {spectator} => {
+                match spectator.saved() {
+                    Some(tournament) => autosave::save_tournament(&tournament),
+                    None => autosave::clear_tournament(),
+                }
+
+                autosave::clear_game();
+            }
+
+            _ => autosave::clear_all(),
         }
     }
 
+    pub fn file_dropped(&mut self, ctx: &mut Context, path: PathBuf) -> Result<(), GameError> {
+        self.active();
+
+        let res = self.state.file_dropped(ctx, &path)?;
+        self.state_change(ctx, res);
+
+        Ok(())
+    }
+
     fn state_change(&mut self, ctx: &mut Context, new_state: Option<State>) {
         if let Some(new_state) = new_state {
+            //Screens that rebuild themselves, like the main menu after a setting changes, keep the focus where it was
+            if std::mem::discriminant(&new_state) != std::mem::discriminant(&self.state) {
+                self.focus.clear();
+            }
+
             self.state = new_state;
         }
     }
+
+    //Moves the focus between the screen's controls and presses them. Returns whether the key was used
+    fn focus_key(&mut self, ctx: &mut Context, input: KeyInput) -> Result<bool, GameError> {
+        let width = ctx.gfx.drawable_size().0;
+        let mut controls = self.state.controls(width);
+
+        if controls.is_empty() {
+            return Ok(false);
+        }
+
+        if let Some(Control::Picker(picker)) = self.focus.index().and_then(|index| controls.get_mut(index)) {
+            if picker.key_down_event(input) {
+                return Ok(true);
+            }
+        }
+
+        let Some(key) = FocusKey::from_input(input) else {
+            return Ok(false);
+        };
+
+        let count = controls.len();
+        let clicked = match self.focus.key(key, count).map(|index| &mut controls[index]) {
+            Some(Control::Button(button)) => {
+                button.press();
+                None
+            }
+            Some(Control::Area(area)) => Some(area.center()),
+            _ => None,
+        };
+
+        if let Some(center) = clicked {
+            let res = self.state.mouse_button_down_event(ctx, MouseButton::Left, center.x, center.y)?;
+            self.state_change(ctx, res);
+        }
+
+        Ok(true)
+    }
+}
+
+//Window size and position are saved on exit. The windowed size is kept while in fullscreen
+fn save_window_geometry(ctx: &Context) {
+    let fullscreen = ctx.gfx.window().fullscreen().is_some();
+    let (width, height) = ctx.gfx.drawable_size();
+    let position = ctx.gfx.window_position().ok().map(|p| (p.x, p.y));
+
+    settings::update(|settings| {
+        settings.window.fullscreen = fullscreen;
+
+        if !fullscreen {
+            settings.window.width = width;
+            settings.window.height = height;
+            settings.window.position = position;
+        }
+    });
 }
 
 impl EventHandler for MainGUI {
     fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+        let requests: Vec<ApiRequest> = match &self.api_requests {
+            Some(receiver) => receiver.try_iter().collect(),
+            None => vec![],
+        };
+
+        for request in requests {
+            self.active();
+
+            let res = self.handle_api_command(ctx, request.command);
+            let _ = request.reply.send(res);
+        }
+
+        //A long press is the right click of a touchscreen
+        if let Some((x, y)) = self.touch.long_press() {
+            self.active();
+
+            let res = self.state.mouse_button_down_event(ctx, MouseButton::Right, x, y)?;
+            self.state_change(ctx, res);
+        }
+
         let res = self.state.update(ctx)?;
         self.state_change(ctx, res);
 
+        if self.autosaved_at.elapsed() >= AUTOSAVE_INTERVAL {
+            self.autosave();
+        }
+
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> Result<(), GameError> {
-        let mut canvas = graphics::Canvas::from_frame(ctx, Color::new(0.2, 0.2, 0.2, 1.0));
+        let mut canvas = graphics::Canvas::from_frame(ctx, theme().background);
 
         let res = self.state.draw(ctx, &mut canvas)?;
         self.state_change(ctx, res);
 
+        let width = canvas.screen_coordinates().unwrap().w;
+
+        if let Some(control) = self.focus.index().and_then(|index| self.state.controls(width).into_iter().nth(index)) {
+            focus::draw_ring(ctx, &mut canvas, control.rect())?;
+        }
+
         canvas.finish(ctx)?;
 
+        self.limit_frame_rate();
+
         Ok(())
     }
 
@@ -605,6 +2671,9 @@ impl EventHandler for MainGUI {
         x: f32,
         y: f32,
     ) -> Result<(), GameError> {
+        self.active();
+        self.focus.clear();
+
         let res = self.state.mouse_button_down_event(ctx, button, x, y)?;
         self.state_change(ctx, res);
 
@@ -619,6 +2688,8 @@ impl EventHandler for MainGUI {
             dx: f32,
             dy: f32,
         ) -> Result<(), GameError> {
+        self.active();
+
         let res = self.state.mouse_motion_event(ctx, x, y, dx, dy)?;
         self.state_change(ctx, res);
 
@@ -626,26 +2697,144 @@ impl EventHandler for MainGUI {
     }
 
     fn mouse_wheel_event(&mut self, ctx: &mut Context, x: f32, y: f32) -> Result<(), GameError> {
+        self.active();
+
         let res = self.state.mouse_wheel_event(ctx, x, y)?;
         self.state_change(ctx, res);
 
         Ok(())
     }
 
+    fn text_input_event(&mut self, ctx: &mut Context, character: char) -> Result<(), GameError> {
+        self.active();
+
+        let res = self.state.text_input_event(ctx, character)?;
+        self.state_change(ctx, res);
+
+        Ok(())
+    }
+
+    fn resize_event(&mut self, _ctx: &mut Context, _width: f32, _height: f32) -> Result<(), GameError> {
+        self.active();
+
+        Ok(())
+    }
+
+    fn quit_event(&mut self, ctx: &mut Context) -> Result<bool, GameError> {
+        save_window_geometry(ctx);
+
+        //A clean exit leaves nothing to recover, unless the recovery prompt itself was closed
+        if !matches!(self.state, State::Recovery {..}) {
+            autosave::clear_all();
+        }
+
+        //Threads still searching are cancelled and engine processes told to quit rather than left behind
+        match &mut self.state {
+            State::Game {chess} => chess.shutdown(),
+            State::Spectator {spectator} => spectator.shutdown(),
+            State::Watch {watcher} => watcher.shutdown(),
+            _ => {}
+        }
+
+        external::shutdown_engines();
+
+        Ok(false)
+    }
+
     fn key_down_event(
             &mut self,
             ctx: &mut Context,
             input: ggez::input::keyboard::KeyInput,
             _repeated: bool,
         ) -> Result<(), GameError> {
+        self.active();
+
         match input.keycode {
-            Some(VirtualKeyCode::Escape) => {
+            //Escape closes the options and tag editors first
+            Some(VirtualKeyCode::Escape) if !self.state.editor_open() => {
                 self.state = State::main_menu(ctx);
+                self.focus.clear();
             },
 
-            _ => {}
+            Some(VirtualKeyCode::F11) => {
+                let fullscreen = if ctx.gfx.window().fullscreen().is_some() {
+                    FullscreenType::Windowed
+                } else {
+                    FullscreenType::Desktop
+                };
+
+                ctx.gfx.set_fullscreen(fullscreen)?;
+            }
+
+            //Ctrl with +, - and 0 changes the UI scale
+            Some(key @ (VirtualKeyCode::Equals | VirtualKeyCode::Plus | VirtualKeyCode::NumpadAdd | VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract | VirtualKeyCode::Key0))
+                if input.mods.contains(KeyMods::CTRL) =>
+            {
+                let scale = match key {
+                    VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => ui_scale() - 0.1,
+                    VirtualKeyCode::Key0 => 1.0,
+                    _ => ui_scale() + 0.1,
+                };
+
+                set_ui_scale(scale);
+                println!("UI scale: {:.1}", ui_scale());
+
+                self.state.rescale(ctx);
+            }
+
+            //Keys typed into the tag editor aren't shortcuts
+            Some(key) if !input.mods.intersects(KeyMods::SHIFT | KeyMods::CTRL) && !self.state.editor_open() && game_shortcut(&mut self.state, key) => {}
+
+            _ => {
+                //Editors open over the game creator take every key
+                if !self.state.editor_open() && self.focus_key(ctx, input)? {
+                    return Ok(());
+                }
+
+                let res = self.state.key_down_event(ctx, input)?;
+                self.state_change(ctx, res);
+            }
         }
 
         Ok(())
     }
 }
+
+//Shortcuts for the game screen. Returns whether the key was used
+fn game_shortcut(state: &mut State, key: VirtualKeyCode) -> bool {
+    let chess = match state {
+        State::Game {chess} => chess,
+        State::Spectator {spectator} => match spectator.chess_mut() {
+            Some(chess) => chess,
+            None => return false,
+        },
+        State::Watch {watcher} => match watcher.chess_mut() {
+            Some(chess) => chess,
+            None => return false,
+        },
+        _ => return false,
+    };
+
+    match key {
+        VirtualKeyCode::Space => chess.set_paused(!chess.is_paused()),
+
+        VirtualKeyCode::Left => chess.step_history(-1),
+        VirtualKeyCode::Right => chess.step_history(1),
+        VirtualKeyCode::Home => chess.history_start(),
+        VirtualKeyCode::End => chess.history_end(),
+
+        VirtualKeyCode::F => chess.flip(),
+        VirtualKeyCode::T => chess.toggle_time_chart(),
+        VirtualKeyCode::C => chess.toggle_commentary(),
+        VirtualKeyCode::K => chess.toggle_ticker(),
+
+        VirtualKeyCode::S => match chess.save_pgn(false) {
+            Ok(path) => println!("Saved PGN to {:?}", path),
+            Err(e) => println!("Failed to save PGN: {}", e),
+        },
+
+        _ => return false,
+    }
+
+    true
+}
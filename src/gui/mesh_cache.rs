@@ -0,0 +1,110 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use ggez::graphics::{self, Canvas, Color, DrawMode, Mesh, Rect};
+use ggez::{Context, GameResult};
+
+//Meshes are built white at the origin and then moved and tinted with the DrawParam, so only the shape and size
+//need to be part of the key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Shape {
+    Rectangle,
+    RoundedRectangle,
+    Circle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct MeshKey {
+    shape: Shape,
+    //Line width for outlines, None for filled shapes
+    stroke: Option<u32>,
+    dims: [u32; 3],
+}
+
+//Window resizes keep producing new sizes, so the cache is emptied once it gets this big
+const MAX_MESHES: usize = 512;
+
+thread_local! {
+    static MESHES: RefCell<HashMap<MeshKey, Mesh>> = RefCell::new(HashMap::new());
+}
+
+fn get_or_build(
+    shape: Shape,
+    mode: DrawMode,
+    dims: [f32; 3],
+    build: impl FnOnce() -> GameResult<Mesh>,
+) -> GameResult<Mesh> {
+    let key = MeshKey {
+        shape,
+        stroke: match mode {
+            DrawMode::Fill(_) => None,
+            DrawMode::Stroke(options) => Some(options.line_width.to_bits()),
+        },
+        dims: dims.map(f32::to_bits),
+    };
+
+    if let Some(mesh) = MESHES.with(|m| m.borrow().get(&key).cloned()) {
+        return Ok(mesh);
+    }
+
+    let mesh = build()?;
+
+    MESHES.with(|m| {
+        let mut meshes = m.borrow_mut();
+
+        if meshes.len() >= MAX_MESHES {
+            meshes.clear();
+        }
+
+        meshes.insert(key, mesh.clone());
+    });
+
+    Ok(mesh)
+}
+
+pub fn rectangle(ctx: &Context, mode: DrawMode, w: f32, h: f32) -> GameResult<Mesh> {
+    get_or_build(Shape::Rectangle, mode, [w, h, 0.0], || {
+        Mesh::new_rectangle(ctx, mode, Rect::new(0.0, 0.0, w, h), Color::WHITE)
+    })
+}
+
+pub fn rounded_rectangle(ctx: &Context, mode: DrawMode, w: f32, h: f32, radius: f32) -> GameResult<Mesh> {
+    get_or_build(Shape::RoundedRectangle, mode, [w, h, radius], || {
+        Mesh::new_rounded_rectangle(ctx, mode, Rect::new(0.0, 0.0, w, h), radius, Color::WHITE)
+    })
+}
+
+//Centered on the origin
+pub fn circle(ctx: &Context, mode: DrawMode, radius: f32) -> GameResult<Mesh> {
+    get_or_build(Shape::Circle, mode, [radius, 0.0, 0.0], || {
+        Mesh::new_circle(ctx, mode, [0.0, 0.0], radius, 0.25, Color::WHITE)
+    })
+}
+
+pub fn draw_rectangle(ctx: &Context, canvas: &mut Canvas, mode: DrawMode, bounds: Rect, color: Color) -> GameResult {
+    let mesh = rectangle(ctx, mode, bounds.w, bounds.h)?;
+    canvas.draw(&mesh, graphics::DrawParam::default().dest(bounds.point()).color(color));
+
+    Ok(())
+}
+
+pub fn draw_rounded_rectangle(
+    ctx: &Context,
+    canvas: &mut Canvas,
+    mode: DrawMode,
+    bounds: Rect,
+    radius: f32,
+    color: Color,
+) -> GameResult {
+    let mesh = rounded_rectangle(ctx, mode, bounds.w, bounds.h, radius)?;
+    canvas.draw(&mesh, graphics::DrawParam::default().dest(bounds.point()).color(color));
+
+    Ok(())
+}
+
+pub fn draw_circle(ctx: &Context, canvas: &mut Canvas, mode: DrawMode, center: [f32; 2], radius: f32, color: Color) -> GameResult {
+    let mesh = circle(ctx, mode, radius)?;
+    canvas.draw(&mesh, graphics::DrawParam::default().dest(center).color(color));
+
+    Ok(())
+}
@@ -1,3 +1,18 @@
 pub mod chess_display;
 pub mod skin;
-pub mod main_gui;
\ No newline at end of file
+pub mod main_gui;
+pub mod text_input;
+pub mod scale;
+pub mod theme;
+pub mod palette;
+pub mod mesh_cache;
+pub mod spectator;
+pub mod watch_folder;
+pub mod clipboard;
+pub mod option_editor;
+pub mod tag_editor;
+pub mod instant;
+pub mod focus;
+pub mod touch;
+pub mod ticker;
+pub mod split_layout;pub mod event_loop;
@@ -0,0 +1,361 @@
+use ggez::{
+    event::MouseButton,
+    graphics::{self, Canvas, Rect, Text, TextFragment},
+    input::keyboard::KeyInput,
+    winit::event::VirtualKeyCode,
+    Context, GameError,
+};
+
+use crate::alg::bot_preset::BotPreset;
+use crate::alg::external::{EngineOptions, UciOption, UciOptionKind};
+
+use super::mesh_cache;
+use super::scale::ui;
+use super::text_input::TextInput;
+use super::theme::theme;
+
+const ROW_HEIGHT: f32 = 44.0;
+
+//Title at the top, Reset, Save preset and Done at the bottom
+const HEADER_HEIGHT: f32 = 50.0;
+const FOOTER_HEIGHT: f32 = 56.0;
+
+//Edits the options of one side's UCI engine in the game creator. Values left at the engine's default aren't kept
+pub struct OptionEditor {
+    pub engine: String,
+    pub color: chess::Color,
+    pub values: EngineOptions,
+
+    //Buttons only do something while the engine is running, so they aren't listed
+    options: Vec<UciOption>,
+
+    //Spin and string options are typed into
+    editing: Option<(usize, TextInput)>,
+    scroll_offset: f32,
+
+    //Name typed for the preset being saved, and how saving the last one went
+    naming: Option<TextInput>,
+    saved: Option<String>,
+
+    bounds: Rect,
+    done: bool,
+}
+
+impl OptionEditor {
+    pub fn new(engine: &str, color: chess::Color, options: Vec<UciOption>, values: EngineOptions) -> OptionEditor {
+        OptionEditor {
+            engine: engine.to_string(),
+            color,
+            values,
+
+            options: options.into_iter().filter(|option| option.kind != UciOptionKind::Button).collect(),
+
+            editing: None,
+            scroll_offset: 0.0,
+
+            naming: None,
+            saved: None,
+
+            bounds: Rect::new(0.0, 0.0, 0.0, 0.0),
+            done: false,
+        }
+    }
+
+    //Closed with Done or Escape
+    pub fn finished(&self) -> bool {
+        self.done
+    }
+
+    fn value(&self, option: &UciOption) -> String {
+        self.values.get(&option.name).cloned().unwrap_or_else(|| option.default_value())
+    }
+
+    fn set(&mut self, idx: usize, value: String) {
+        let option = &self.options[idx];
+
+        if value == option.default_value() {
+            self.values.remove(&option.name);
+        } else {
+            self.values.insert(option.name.clone(), value);
+        }
+    }
+
+    //Invalid input is dropped, leaving the option as it was
+    fn commit(&mut self) {
+        if let Some((idx, input)) = self.editing.take() {
+            if let Some(value) = self.options[idx].validate(input.text()) {
+                self.set(idx, value);
+            }
+        }
+    }
+
+    fn list_region(&self) -> Rect {
+        Rect::new(self.bounds.x, self.bounds.y + ui(HEADER_HEIGHT), self.bounds.w, self.bounds.h - ui(HEADER_HEIGHT + FOOTER_HEIGHT))
+    }
+
+    fn row(&self, idx: usize) -> Rect {
+        let list = self.list_region();
+
+        Rect::new(list.x + ui(10.0), list.y + idx as f32 * ui(ROW_HEIGHT) - self.scroll_offset, list.w - ui(20.0), ui(ROW_HEIGHT))
+    }
+
+    //Right half of a row
+    fn value_rect(&self, idx: usize) -> Rect {
+        let row = self.row(idx);
+
+        Rect::new(row.x + row.w * 0.5, row.y + ui(2.0), row.w * 0.5, row.h - ui(4.0))
+    }
+
+    fn footer_buttons(&self) -> [Rect; 3] {
+        let y = self.bounds.bottom() - ui(FOOTER_HEIGHT) + ui(8.0);
+        let (w, h, gap) = (ui(140.0), ui(40.0), ui(20.0));
+
+        [
+            Rect::new(self.bounds.center().x - w * 1.5 - gap, y, w, h),
+            Rect::new(self.bounds.center().x - w * 0.5, y, w, h),
+            Rect::new(self.bounds.center().x + w * 0.5 + gap, y, w, h),
+        ]
+    }
+
+    //Where the preset's name is typed, over the first two buttons
+    fn name_rect(&self) -> Rect {
+        let [reset, save, _] = self.footer_buttons();
+
+        Rect::new(reset.x, reset.y, save.right() - reset.x, reset.h)
+    }
+
+    //The bot with the values set here, under the typed name
+    fn save_preset(&mut self) {
+        let Some(input) = self.naming.take() else {
+            return;
+        };
+
+        let preset = BotPreset::new(input.text(), &self.engine, self.values.clone());
+
+        self.saved = Some(match preset.save() {
+            Ok(_) => format!("Saved {}, in the player list from the next start", preset.name),
+            Err(e) => format!("Could not save the preset: {}", e),
+        });
+    }
+
+    fn hint(option: &UciOption) -> String {
+        match &option.kind {
+            UciOptionKind::Spin { min, max, .. } => format!("{} to {}", min, max),
+            UciOptionKind::Check { .. } | UciOptionKind::Combo { .. } => String::from("Click to change"),
+            UciOptionKind::String { .. } | UciOptionKind::Button => String::from("Click to type"),
+        }
+    }
+
+    //In the middle of `screen`, as tall as the options need
+    pub fn draw(&mut self, ctx: &mut Context, canvas: &mut Canvas, screen: Rect) -> Result<(), GameError> {
+        let w = (screen.w * 0.8).min(ui(700.0));
+        let wanted = ui(HEADER_HEIGHT + FOOTER_HEIGHT) + self.options.len().max(1) as f32 * ui(ROW_HEIGHT);
+        let h = wanted.min(screen.h * 0.9);
+
+        self.bounds = Rect::new(screen.center().x - w / 2.0, screen.center().y - h / 2.0, w, h);
+
+        let list = self.list_region();
+        let max_scroll = (self.options.len() as f32 * ui(ROW_HEIGHT) - list.h).max(0.0);
+        self.scroll_offset = self.scroll_offset.clamp(0.0, max_scroll);
+
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), screen, [0.0, 0.0, 0.0, 0.5].into())?;
+        mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), self.bounds, 5.0, theme().tooltip_background)?;
+        mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::stroke(3.0), self.bounds, 5.0, theme().panel_border)?;
+
+        let mut title = Text::new(TextFragment::new(format!("{} options ({:?})", self.engine, self.color)).color(theme().tooltip_text));
+        title.set_scale(ui(28.0));
+
+        if let Some(saved) = &self.saved {
+            title.add(TextFragment::new(format!("  {}", saved)).color(theme().muted_text).scale(ui(16.0)));
+        }
+        canvas.draw(&title, graphics::DrawParam::default().dest([self.bounds.x + ui(10.0), self.bounds.y + ui(10.0)]));
+
+        canvas.set_scissor_rect(list)?;
+
+        if self.options.is_empty() {
+            let mut text = Text::new(TextFragment::new("This engine has no options").color(theme().muted_text));
+            text.set_scale(ui(20.0));
+            canvas.draw(&text, graphics::DrawParam::default().dest([list.x + ui(10.0), list.y + ui(10.0)]));
+        }
+
+        let mouse = ctx.mouse.position();
+
+        for (idx, option) in self.options.iter().enumerate() {
+            let row = self.row(idx);
+
+            if row.bottom() < list.y || row.y > list.bottom() {
+                continue;
+            }
+
+            let changed = self.values.contains_key(&option.name);
+
+            let mut label = Text::new(TextFragment::new(option.name.as_str()).color(theme().tooltip_text));
+            label.add(TextFragment::new(format!("  {}", OptionEditor::hint(option))).color(theme().muted_text).scale(ui(14.0)));
+            label.set_scale(ui(20.0));
+            label.set_bounds([row.w * 0.5 - ui(10.0), row.h]);
+
+            let dims = label.measure(ctx)?;
+            canvas.draw(&label, graphics::DrawParam::default().dest([row.x, row.y + (row.h - dims.y) / 2.0]));
+
+            let value_rect = self.value_rect(idx);
+
+            if let Some((_, input)) = self.editing.as_mut().filter(|(editing, _)| *editing == idx) {
+                input.draw(ctx, canvas, value_rect)?;
+                continue;
+            }
+
+            let background = if value_rect.contains(mouse) { theme().button_hover } else { theme().button };
+            mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), value_rect, 5.0, background)?;
+
+            //Changed values stand out from the defaults
+            let value = self.values.get(&option.name).cloned().unwrap_or_else(|| option.default_value());
+            let mut text = Text::new(TextFragment::new(if changed { format!("{} *", value) } else { value }).color(theme().button_text));
+            text.set_scale(ui(20.0));
+            text.set_bounds([value_rect.w - ui(10.0), value_rect.h]);
+
+            let dims = text.measure(ctx)?;
+            canvas.draw(&text, graphics::DrawParam::default().dest([value_rect.x + ui(8.0), value_rect.y + (value_rect.h - dims.y) / 2.0]));
+        }
+
+        canvas.set_default_scissor_rect();
+
+        let [reset, save, done] = self.footer_buttons();
+
+        let buttons = match &mut self.naming {
+            Some(input) => {
+                input.draw(ctx, canvas, Rect::new(reset.x, reset.y, save.right() - reset.x, reset.h))?;
+                vec![(done, "Save")]
+            }
+            None => vec![(reset, "Reset all"), (save, "Save preset"), (done, "Done")],
+        };
+
+        for (rect, label) in buttons {
+            let color = if rect.contains(mouse) { theme().button_hover } else { theme().button };
+            mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), rect, 5.0, color)?;
+
+            let mut text = Text::new(TextFragment::new(label).color(theme().button_text));
+            text.set_scale(ui(22.0));
+
+            let dims = text.measure(ctx)?;
+            canvas.draw(&text, graphics::DrawParam::default().dest([rect.center().x - dims.x / 2.0, rect.center().y - dims.y / 2.0]));
+        }
+
+        Ok(())
+    }
+
+    pub fn mouse_button_down_event(&mut self, button: MouseButton, x: f32, y: f32) {
+        if button != MouseButton::Left {
+            return;
+        }
+
+        //Clicking anywhere else finishes typing
+        if let Some((idx, _)) = &self.editing {
+            if self.value_rect(*idx).contains([x, y]) {
+                return;
+            }
+
+            self.commit();
+        }
+
+        let [reset, save, done] = self.footer_buttons();
+
+        //Clicking anywhere but the name or Save leaves the preset unsaved
+        if self.naming.is_some() {
+            if done.contains([x, y]) {
+                self.save_preset();
+            } else if !self.name_rect().contains([x, y]) {
+                self.naming = None;
+            }
+
+            return;
+        }
+
+        if save.contains([x, y]) {
+            self.naming = Some(TextInput::new("Preset name"));
+            return;
+        }
+
+        if reset.contains([x, y]) {
+            self.values.clear();
+            return;
+        }
+
+        if done.contains([x, y]) {
+            self.done = true;
+            return;
+        }
+
+        if !self.list_region().contains([x, y]) {
+            return;
+        }
+
+        let Some(idx) = (0..self.options.len()).find(|idx| self.value_rect(*idx).contains([x, y])) else {
+            return;
+        };
+
+        let value = self.value(&self.options[idx]);
+
+        match &self.options[idx].kind {
+            UciOptionKind::Check { .. } => self.set(idx, (value != "true").to_string()),
+
+            UciOptionKind::Combo { choices, .. } => {
+                let current = choices.iter().position(|choice| *choice == value);
+                let next = current.map_or(0, |current| (current + 1) % choices.len().max(1));
+
+                if let Some(choice) = choices.get(next).cloned() {
+                    self.set(idx, choice);
+                }
+            }
+
+            UciOptionKind::Spin { .. } | UciOptionKind::String { .. } => {
+                let mut input = TextInput::new(&value);
+
+                //Starts from the current value rather than an empty box
+                for c in value.chars() {
+                    input.text_input_event(c);
+                }
+
+                self.editing = Some((idx, input));
+            }
+
+            UciOptionKind::Button => {}
+        }
+    }
+
+    pub fn mouse_wheel_event(&mut self, y: f32) {
+        self.scroll_offset -= y * ui(20.0);
+    }
+
+    pub fn key_down_event(&mut self, input: KeyInput) {
+        if let Some(naming) = &mut self.naming {
+            match input.keycode {
+                Some(VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter) => self.save_preset(),
+                Some(VirtualKeyCode::Escape) => self.naming = None,
+                _ => naming.key_down_event(input),
+            }
+
+            return;
+        }
+
+        match input.keycode {
+            Some(VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter) => self.commit(),
+
+            Some(VirtualKeyCode::Escape) if self.editing.is_some() => self.editing = None,
+            Some(VirtualKeyCode::Escape) => self.done = true,
+
+            _ => {
+                if let Some((_, text_input)) = &mut self.editing {
+                    text_input.key_down_event(input);
+                }
+            }
+        }
+    }
+
+    pub fn text_input_event(&mut self, character: char) {
+        if let Some(naming) = &mut self.naming {
+            naming.text_input_event(character);
+        } else if let Some((_, input)) = &mut self.editing {
+            input.text_input_event(character);
+        }
+    }
+}
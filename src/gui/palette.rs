@@ -0,0 +1,120 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use ggez::graphics::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::settings;
+
+//Colors of the board and move highlights
+#[derive(Debug, Clone, Copy)]
+pub struct BoardPalette {
+    pub light: Color,
+    pub dark: Color,
+    pub selected_light: Color,
+    pub selected_dark: Color,
+    pub move_marker: Color,
+}
+
+const CLASSIC: BoardPalette = BoardPalette {
+    light: Color::new(227.0 / 255.0, 220.0 / 255.0, 138.0 / 255.0, 1.0),
+    dark: Color::new(128.0 / 255.0, 69.0 / 255.0, 33.0 / 255.0, 1.0),
+    selected_light: Color::new(188.0 / 255.0, 222.0 / 255.0, 115.0 / 255.0, 1.0),
+    selected_dark: Color::new(61.0 / 255.0, 92.0 / 255.0, 21.0 / 255.0, 1.0),
+    move_marker: Color::new(1.0, 0.0, 0.0, 200.0 / 255.0),
+};
+
+//No red/green pairs, for deuteranopia and protanopia
+const BLUE_ORANGE: BoardPalette = BoardPalette {
+    light: Color::new(222.0 / 255.0, 227.0 / 255.0, 230.0 / 255.0, 1.0),
+    dark: Color::new(120.0 / 255.0, 145.0 / 255.0, 160.0 / 255.0, 1.0),
+    selected_light: Color::new(240.0 / 255.0, 190.0 / 255.0, 100.0 / 255.0, 1.0),
+    selected_dark: Color::new(213.0 / 255.0, 130.0 / 255.0, 20.0 / 255.0, 1.0),
+    move_marker: Color::new(0.0, 70.0 / 255.0, 140.0 / 255.0, 220.0 / 255.0),
+};
+
+//No blue/yellow pairs, for tritanopia
+const RED_TEAL: BoardPalette = BoardPalette {
+    light: Color::new(240.0 / 255.0, 225.0 / 255.0, 225.0 / 255.0, 1.0),
+    dark: Color::new(170.0 / 255.0, 110.0 / 255.0, 110.0 / 255.0, 1.0),
+    selected_light: Color::new(130.0 / 255.0, 210.0 / 255.0, 210.0 / 255.0, 1.0),
+    selected_dark: Color::new(30.0 / 255.0, 130.0 / 255.0, 130.0 / 255.0, 1.0),
+    move_marker: Color::new(0.1, 0.1, 0.1, 200.0 / 255.0),
+};
+
+const HIGH_CONTRAST: BoardPalette = BoardPalette {
+    light: Color::new(0.95, 0.95, 0.95, 1.0),
+    dark: Color::new(0.35, 0.35, 0.35, 1.0),
+    selected_light: Color::new(240.0 / 255.0, 228.0 / 255.0, 66.0 / 255.0, 1.0),
+    selected_dark: Color::new(170.0 / 255.0, 160.0 / 255.0, 0.0, 1.0),
+    move_marker: Color::new(213.0 / 255.0, 94.0 / 255.0, 0.0, 230.0 / 255.0),
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteName {
+    #[default]
+    Classic,
+    BlueOrange,
+    RedTeal,
+    HighContrast,
+}
+
+pub const ALL_PALETTES: [PaletteName; 4] = [PaletteName::Classic, PaletteName::BlueOrange, PaletteName::RedTeal, PaletteName::HighContrast];
+
+impl PaletteName {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PaletteName::Classic => "Classic",
+            PaletteName::BlueOrange => "Blue/Orange",
+            PaletteName::RedTeal => "Red/Teal",
+            PaletteName::HighContrast => "High Contrast",
+        }
+    }
+
+    pub fn next(&self) -> PaletteName {
+        let idx = ALL_PALETTES.iter().position(|p| p == self).unwrap();
+
+        ALL_PALETTES[(idx + 1) % ALL_PALETTES.len()]
+    }
+
+    pub fn palette(&self) -> &'static BoardPalette {
+        match self {
+            PaletteName::Classic => &CLASSIC,
+            PaletteName::BlueOrange => &BLUE_ORANGE,
+            PaletteName::RedTeal => &RED_TEAL,
+            PaletteName::HighContrast => &HIGH_CONTRAST,
+        }
+    }
+}
+
+//Index into ALL_PALETTES
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+//Whether highlights also differ in shape, so they can be told apart without relying on color
+static SHAPE_MARKERS: AtomicBool = AtomicBool::new(false);
+
+pub fn current_palette() -> PaletteName {
+    ALL_PALETTES[CURRENT.load(Ordering::Relaxed)]
+}
+
+pub fn palette() -> &'static BoardPalette {
+    current_palette().palette()
+}
+
+pub fn shape_markers() -> bool {
+    SHAPE_MARKERS.load(Ordering::Relaxed)
+}
+
+pub fn init_palette(name: PaletteName, shapes: bool) {
+    CURRENT.store(ALL_PALETTES.iter().position(|p| *p == name).unwrap(), Ordering::Relaxed);
+    SHAPE_MARKERS.store(shapes, Ordering::Relaxed);
+}
+
+pub fn set_palette(name: PaletteName) {
+    CURRENT.store(ALL_PALETTES.iter().position(|p| *p == name).unwrap(), Ordering::Relaxed);
+    settings::update(|settings| settings.palette = name);
+}
+
+pub fn set_shape_markers(shapes: bool) {
+    SHAPE_MARKERS.store(shapes, Ordering::Relaxed);
+    settings::update(|settings| settings.shape_markers = shapes);
+}
@@ -0,0 +1,30 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::settings;
+
+pub const MIN_UI_SCALE: f32 = 0.5;
+pub const MAX_UI_SCALE: f32 = 3.0;
+
+//Stored as the bits of an f32 so it can be read from anywhere without locking, starting out at 1.0
+static UI_SCALE: AtomicU32 = AtomicU32::new(0x3f80_0000);
+
+pub fn ui_scale() -> f32 {
+    f32::from_bits(UI_SCALE.load(Ordering::Relaxed))
+}
+
+//Size of a piece of UI after applying the scale factor
+pub fn ui(size: f32) -> f32 {
+    size * ui_scale()
+}
+
+pub fn init_ui_scale(scale: f32) {
+    UI_SCALE.store(scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE).to_bits(), Ordering::Relaxed);
+}
+
+//Changes the scale and remembers it for next time
+pub fn set_ui_scale(scale: f32) {
+    init_ui_scale(scale);
+
+    let scale = ui_scale();
+    settings::update(|settings| settings.ui_scale = Some(scale));
+}
@@ -1,6 +1,33 @@
-use chess::{Piece, Color};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chess::{Piece, Color, ALL_COLORS, ALL_PIECES};
+use ggez::graphics::{Canvas, DrawParam, InstanceArray};
 use ggez::{graphics, Context};
 
+//Skins by name, each loaded the first time a board asks for it and then shared by every board after it
+thread_local! {
+    static SKINS: RefCell<HashMap<String, Arc<PieceSkin>>> = RefCell::new(HashMap::new());
+}
+
+pub fn skin(ctx: &mut Context, name: &str) -> Arc<PieceSkin> {
+    if let Some(skin) = SKINS.with(|skins| skins.borrow().get(name).cloned()) {
+        return skin;
+    }
+
+    let skin = Arc::new(PieceSkin::load(ctx, name));
+    SKINS.with(|skins| skins.borrow_mut().insert(name.to_string(), skin.clone()));
+
+    skin
+}
+
+//Forgets every loaded skin, so images changed on disk are read again. Boards keep the skin they have until they ask
+//for it again
+pub fn reload_skins() {
+    SKINS.with(|skins| skins.borrow_mut().clear());
+}
+
 #[derive(Debug)]
 pub struct PieceSkin {
     pub name: String,
@@ -72,4 +99,44 @@ impl PieceSkin {
             (Piece::Pawn, Color::Black) => &self.black_pawn,
         }
     }
-}
\ No newline at end of file
+}
+//One instance array per piece image, so a full board takes at most 12 draw calls instead of one per piece
+#[derive(Debug)]
+pub struct PieceBatch {
+    arrays: Vec<InstanceArray>,
+}
+
+impl PieceBatch {
+    pub fn new(ctx: &Context, skin: &PieceSkin) -> Self {
+        let mut arrays = vec![];
+
+        for color in ALL_COLORS {
+            for piece in ALL_PIECES {
+                arrays.push(InstanceArray::new(ctx, skin.get_piece_image(piece, color).clone()));
+            }
+        }
+
+        PieceBatch { arrays }
+    }
+
+    fn index(piece: Piece, color: Color) -> usize {
+        color.to_index() * ALL_PIECES.len() + piece.to_index()
+    }
+
+    pub fn clear(&mut self) {
+        for array in self.arrays.iter_mut() {
+            array.clear();
+        }
+    }
+
+    //`param` is the same as for drawing the piece image on its own
+    pub fn push(&mut self, piece: Piece, color: Color, param: DrawParam) {
+        self.arrays[PieceBatch::index(piece, color)].push(param);
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas) {
+        for array in self.arrays.iter() {
+            canvas.draw(array, DrawParam::default());
+        }
+    }
+}
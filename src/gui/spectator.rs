@@ -0,0 +1,237 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chess::Board;
+use ggez::{
+    graphics::{self, Canvas, Rect, Text, TextFragment},
+    Context, GameError,
+};
+
+use crate::autosave::SavedTournament;
+use crate::tournament::Tournament;
+
+use super::chess_display::ChessDisplay;
+use super::mesh_cache;
+use super::scale::ui;
+use super::theme::theme;
+
+//Least time spent on a board before jumping to another one, so that busy tournaments don't flicker between boards
+const MIN_DWELL: Duration = Duration::from_secs(3);
+
+//How long a finished game stays up before the next game on its board replaces it
+const RESULT_TIME: Duration = Duration::from_secs(3);
+
+const HEADER_HEIGHT: f32 = 50.0;
+
+//Watches the games of a tournament one board at a time, rotating between boards and jumping to ones where
+//something interesting just happened
+pub struct Spectator {
+    tournament: Arc<Tournament>,
+    switch_every: Duration,
+
+    slot: usize,
+    switched_at: Instant,
+    //Why the view is on this board
+    reason: String,
+
+    chess: Option<ChessDisplay>,
+    //Schedule position of the game on screen
+    shown: Option<usize>,
+    ended_at: Option<Instant>,
+}
+
+impl Spectator {
+    pub fn new(tournament: Arc<Tournament>, switch_every: Duration) -> Spectator {
+        Spectator {
+            tournament,
+            switch_every,
+
+            slot: 0,
+            switched_at: Instant::now(),
+            reason: String::from("First board"),
+
+            chess: None,
+            shown: None,
+            ended_at: None,
+        }
+    }
+
+    pub fn chess_mut(&mut self) -> Option<&mut ChessDisplay> {
+        self.chess.as_mut()
+    }
+
+    pub fn board(&self) -> Option<Board> {
+        self.chess.as_ref().map(|chess| chess.game.board)
+    }
+
+    //What the autosave keeps of the tournament, nothing once it is over
+    pub fn saved(&self) -> Option<SavedTournament> {
+        if self.tournament.finished() {
+            return None;
+        }
+
+        Some(SavedTournament::new(&self.tournament, self.switch_every))
+    }
+
+    fn switch_to(&mut self, slot: usize, reason: String) {
+        println!("Spectating board {}: {}", slot + 1, reason);
+
+        self.slot = slot;
+        self.switched_at = Instant::now();
+        self.reason = reason;
+
+        self.chess = None;
+        self.shown = None;
+        self.ended_at = None;
+    }
+
+    //The next board after the current one with a game going on
+    fn next_busy_slot(&self) -> Option<usize> {
+        let slots = &self.tournament.slots;
+
+        (1..slots.len()).map(|d| (self.slot + d) % slots.len()).find(|i| {
+            let live = slots[*i].lock().unwrap();
+            live.pairing.is_some() && live.result.is_none()
+        })
+    }
+
+    //A board where something happened since the last switch, latest first, or the next board once time is up
+    fn pick_slot(&self) -> Option<(usize, String)> {
+        if self.switched_at.elapsed() < MIN_DWELL {
+            return None;
+        }
+
+        let mut best: Option<(usize, Instant, String)> = None;
+
+        for (i, slot) in self.tournament.slots.iter().enumerate() {
+            let live = slot.lock().unwrap();
+
+            if i == self.slot || live.result.is_some() {
+                continue;
+            }
+
+            if let Some((at, what)) = live.highlight.as_ref().filter(|(at, _)| *at > self.switched_at) {
+                if best.as_ref().is_none_or(|(_, best_at, _)| at > best_at) {
+                    best = Some((i, *at, what.clone()));
+                }
+            }
+        }
+
+        if let Some((slot, _, what)) = best {
+            return Some((slot, what));
+        }
+
+        if self.switched_at.elapsed() >= self.switch_every {
+            return self.next_busy_slot().map(|slot| (slot, String::from("Rotating")));
+        }
+
+        None
+    }
+
+    //When the window closes
+    pub fn shutdown(&mut self) {
+        self.tournament.stop();
+
+        if let Some(chess) = &mut self.chess {
+            chess.shutdown();
+        }
+    }
+
+    pub fn next_board(&mut self) {
+        if let Some(slot) = self.next_busy_slot() {
+            self.switch_to(slot, String::from("Picked by hand"));
+        }
+    }
+
+    pub fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+        if let Some((slot, reason)) = self.pick_slot() {
+            self.switch_to(slot, reason);
+        }
+
+        let live = self.tournament.slots[self.slot].lock().unwrap().clone();
+
+        if let Some(pairing) = &live.pairing {
+            let showing_result = self.ended_at.is_some_and(|at| at.elapsed() < RESULT_TIME);
+
+            if self.shown != Some(pairing.index) && !showing_result {
+                self.chess = Some(ChessDisplay::spectator(ctx, &pairing.white, &pairing.black, &live.moves));
+                self.shown = Some(pairing.index);
+                self.ended_at = None;
+            }
+
+            if let Some(chess) = self.chess.as_mut().filter(|_| self.shown == Some(pairing.index)) {
+                chess.sync_spectated(&live.moves, live.result.as_deref());
+
+                if live.result.is_some() && self.ended_at.is_none() {
+                    self.ended_at = Some(Instant::now());
+                }
+            }
+        }
+
+        if let Some(chess) = self.chess.as_mut() {
+            chess.update(ctx)?;
+        }
+
+        Ok(())
+    }
+
+    fn caption(&self) -> String {
+        let tournament = &self.tournament;
+
+        let game = match self.shown.and_then(|i| tournament.pairings.get(i)) {
+            Some(pairing) => format!(
+                "Game {}/{}: {} vs {}  |  Board {} of {}  |  {}",
+                pairing.index + 1,
+                tournament.pairings.len(),
+                pairing.white,
+                pairing.black,
+                self.slot + 1,
+                tournament.slots.len(),
+                self.reason
+            ),
+            None => String::from("Waiting for the first game"),
+        };
+
+        let status = if tournament.finished() {
+            String::from("Tournament over, Esc to return to the main menu")
+        } else {
+            let left = self.switch_every.saturating_sub(self.switched_at.elapsed());
+            format!("Next board in {}s, N to switch now", left.as_secs() + 1)
+        };
+
+        format!("{}\n{}", game, status)
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, canvas: &mut Canvas, width: f32, height: f32) -> Result<(), GameError> {
+        let header = ui(HEADER_HEIGHT);
+
+        if let Some(chess) = self.chess.as_mut() {
+            chess.draw(ctx, canvas, 0.0, header, width, height - header)?;
+        }
+
+        let mut text = Text::new(self.caption());
+        text.set_scale(ui(20.0));
+
+        canvas.draw(&text, graphics::DrawParam::default().dest([ui(10.0), ui(5.0)]).color(theme().text));
+
+        if self.tournament.finished() {
+            let mut text = Text::new(TextFragment::new(format!("Standings\n{}", self.tournament.standings_text())).color(theme().tooltip_text));
+            text.set_scale(ui(24.0));
+
+            let dims = text.measure(ctx)?;
+            let padding = ui(10.0);
+
+            let bounds = Rect::new(
+                (width - dims.x) / 2.0 - padding,
+                (height - dims.y) / 2.0 - padding,
+                dims.x + padding * 2.0,
+                dims.y + padding * 2.0,
+            );
+
+            mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), bounds, 5.0, theme().tooltip_background)?;
+            canvas.draw(&text, graphics::DrawParam::default().dest([bounds.x + padding, bounds.y + padding]));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use ggez::graphics::Rect;
+use serde::{Deserialize, Serialize};
+
+use super::scale::ui;
+
+//Narrower than this and the panel is hidden, leaving the whole window to the board
+const MIN_PANEL_WIDTH: f32 = 220.0;
+const MIN_BOARD_WIDTH: f32 = 300.0;
+
+const DIVIDER_WIDTH: f32 = 8.0;
+const TAB_HEIGHT: f32 = 32.0;
+const DOCK_BUTTON_WIDTH: f32 = 44.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PanelTab {
+    #[default]
+    Moves,
+    Engine,
+    Console,
+    Graphs,
+    Position,
+}
+
+pub const PANEL_TABS: [PanelTab; 5] = [PanelTab::Moves, PanelTab::Engine, PanelTab::Console, PanelTab::Graphs, PanelTab::Position];
+
+impl PanelTab {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PanelTab::Moves => "Moves",
+            PanelTab::Engine => "Engine",
+            PanelTab::Console => "Console",
+            PanelTab::Graphs => "Graphs",
+            PanelTab::Position => "Position",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DockSide {
+    Left,
+    #[default]
+    Right,
+}
+
+//Where the side panel next to the board in a game goes, and how much of the window it gets
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelLayout {
+    pub shown: bool,
+    pub side: DockSide,
+    pub tab: PanelTab,
+    //Of the window's width
+    pub fraction: f32,
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        PanelLayout {
+            shown: true,
+            side: DockSide::Right,
+            tab: PanelTab::Moves,
+            fraction: 0.3,
+        }
+    }
+}
+
+//The parts of the window a game is drawn in. The divider and panel are missing when the panel is hidden or there's
+//no room for it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplitAreas {
+    pub board: Rect,
+    pub divider: Option<Rect>,
+    pub panel: Option<Rect>,
+}
+
+impl PanelLayout {
+    pub fn split(&self, area: Rect) -> SplitAreas {
+        if !self.shown || area.w < ui(MIN_PANEL_WIDTH + MIN_BOARD_WIDTH) + ui(DIVIDER_WIDTH) {
+            return SplitAreas { board: area, divider: None, panel: None };
+        }
+
+        let panel_width = self.panel_width(area);
+        let board_width = area.w - panel_width - ui(DIVIDER_WIDTH);
+
+        let (board_x, divider_x, panel_x) = match self.side {
+            DockSide::Right => (area.x, area.x + board_width, area.x + board_width + ui(DIVIDER_WIDTH)),
+            DockSide::Left => (area.x + panel_width + ui(DIVIDER_WIDTH), area.x + panel_width, area.x),
+        };
+
+        SplitAreas {
+            board: Rect::new(board_x, area.y, board_width, area.h),
+            divider: Some(Rect::new(divider_x, area.y, ui(DIVIDER_WIDTH), area.h)),
+            panel: Some(Rect::new(panel_x, area.y, panel_width, area.h)),
+        }
+    }
+
+    fn panel_width(&self, area: Rect) -> f32 {
+        let max = area.w - ui(MIN_BOARD_WIDTH) - ui(DIVIDER_WIDTH);
+
+        (area.w * self.fraction).clamp(ui(MIN_PANEL_WIDTH), max.max(ui(MIN_PANEL_WIDTH)))
+    }
+
+    //Moves the divider to `x`, keeping both sides at least as wide as they need to be
+    pub fn drag_to(&mut self, area: Rect, x: f32) {
+        let panel_width = match self.side {
+            DockSide::Right => area.right() - x - ui(DIVIDER_WIDTH) / 2.0,
+            DockSide::Left => x - area.x - ui(DIVIDER_WIDTH) / 2.0,
+        };
+
+        let max = area.w - ui(MIN_BOARD_WIDTH) - ui(DIVIDER_WIDTH);
+
+        self.fraction = panel_width.clamp(ui(MIN_PANEL_WIDTH), max.max(ui(MIN_PANEL_WIDTH))) / area.w.max(1.0);
+    }
+
+    pub fn swap_side(&mut self) {
+        self.side = match self.side {
+            DockSide::Left => DockSide::Right,
+            DockSide::Right => DockSide::Left,
+        };
+    }
+}
+
+//Tabs along the top of the panel, leaving room for the dock button
+pub fn tab_rects(panel: Rect) -> Vec<(PanelTab, Rect)> {
+    let width = (panel.w - ui(DOCK_BUTTON_WIDTH)) / PANEL_TABS.len() as f32;
+
+    PANEL_TABS
+        .iter()
+        .enumerate()
+        .map(|(i, tab)| (*tab, Rect::new(panel.x + i as f32 * width, panel.y, width, ui(TAB_HEIGHT))))
+        .collect()
+}
+
+//Moves the panel to the other side of the board
+pub fn dock_button(panel: Rect) -> Rect {
+    Rect::new(panel.right() - ui(DOCK_BUTTON_WIDTH), panel.y, ui(DOCK_BUTTON_WIDTH), ui(TAB_HEIGHT))
+}
+
+pub fn content(panel: Rect) -> Rect {
+    Rect::new(panel.x + ui(8.0), panel.y + ui(TAB_HEIGHT + 8.0), panel.w - ui(16.0), panel.h - ui(TAB_HEIGHT + 16.0))
+}
@@ -0,0 +1,229 @@
+use ggez::{
+    event::MouseButton,
+    graphics::{self, Canvas, Rect, Text, TextFragment},
+    input::keyboard::KeyInput,
+    winit::event::VirtualKeyCode,
+    Context, GameError,
+};
+
+use crate::pgn::GameTags;
+
+use super::mesh_cache;
+use super::scale::ui;
+use super::text_input::TextInput;
+use super::theme::theme;
+
+const ROW_HEIGHT: f32 = 44.0;
+
+const HEADER_HEIGHT: f32 = 50.0;
+const FOOTER_HEIGHT: f32 = 56.0;
+
+const FIELDS: [&str; 5] = ["Event", "Site", "Round", "White", "Black"];
+
+//Edits the tags written to a game's PGN, before it in the game creator or during and after it
+#[derive(Debug)]
+pub struct TagEditor {
+    pub tags: GameTags,
+    //What Reset goes back to
+    defaults: GameTags,
+
+    editing: Option<(usize, TextInput)>,
+
+    bounds: Rect,
+    done: bool,
+}
+
+impl TagEditor {
+    pub fn new(tags: GameTags, defaults: GameTags) -> TagEditor {
+        TagEditor {
+            tags,
+            defaults,
+
+            editing: None,
+
+            bounds: Rect::new(0.0, 0.0, 0.0, 0.0),
+            done: false,
+        }
+    }
+
+    //Closed with Done or Escape
+    pub fn finished(&self) -> bool {
+        self.done
+    }
+
+    fn field(tags: &mut GameTags, idx: usize) -> &mut String {
+        match idx {
+            0 => &mut tags.event,
+            1 => &mut tags.site,
+            2 => &mut tags.round,
+            3 => &mut tags.white,
+            _ => &mut tags.black,
+        }
+    }
+
+    fn start_editing(&mut self, idx: usize) {
+        let value = TagEditor::field(&mut self.tags, idx).clone();
+        let mut input = TextInput::new(FIELDS[idx]);
+
+        //Starts from the current value rather than an empty box
+        for c in value.chars() {
+            input.text_input_event(c);
+        }
+
+        self.editing = Some((idx, input));
+    }
+
+    //Blank values would leave the PGN with empty tags, so they put back the default
+    fn commit(&mut self) {
+        if let Some((idx, input)) = self.editing.take() {
+            let value = input.text().trim().to_string();
+
+            *TagEditor::field(&mut self.tags, idx) = if value.is_empty() {
+                TagEditor::field(&mut self.defaults, idx).clone()
+            } else {
+                value
+            };
+        }
+    }
+
+    fn row(&self, idx: usize) -> Rect {
+        Rect::new(self.bounds.x + ui(10.0), self.bounds.y + ui(HEADER_HEIGHT) + idx as f32 * ui(ROW_HEIGHT), self.bounds.w - ui(20.0), ui(ROW_HEIGHT))
+    }
+
+    //Everything right of the tag name
+    fn value_rect(&self, idx: usize) -> Rect {
+        let row = self.row(idx);
+
+        Rect::new(row.x + row.w * 0.3, row.y + ui(2.0), row.w * 0.7, row.h - ui(4.0))
+    }
+
+    fn footer_buttons(&self) -> [Rect; 2] {
+        let y = self.bounds.bottom() - ui(FOOTER_HEIGHT) + ui(8.0);
+        let (w, h) = (ui(140.0), ui(40.0));
+
+        [
+            Rect::new(self.bounds.center().x - w - ui(10.0), y, w, h),
+            Rect::new(self.bounds.center().x + ui(10.0), y, w, h),
+        ]
+    }
+
+    //In the middle of `screen`
+    pub fn draw(&mut self, ctx: &mut Context, canvas: &mut Canvas, screen: Rect) -> Result<(), GameError> {
+        let w = (screen.w * 0.8).min(ui(600.0));
+        let h = ui(HEADER_HEIGHT + FOOTER_HEIGHT) + FIELDS.len() as f32 * ui(ROW_HEIGHT);
+
+        self.bounds = Rect::new(screen.center().x - w / 2.0, screen.center().y - h / 2.0, w, h);
+
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), screen, [0.0, 0.0, 0.0, 0.5].into())?;
+        mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), self.bounds, 5.0, theme().tooltip_background)?;
+        mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::stroke(3.0), self.bounds, 5.0, theme().panel_border)?;
+
+        let mut title = Text::new(TextFragment::new("PGN tags").color(theme().tooltip_text));
+        title.set_scale(ui(28.0));
+        canvas.draw(&title, graphics::DrawParam::default().dest([self.bounds.x + ui(10.0), self.bounds.y + ui(10.0)]));
+
+        let mouse = ctx.mouse.position();
+
+        for (idx, name) in FIELDS.iter().enumerate() {
+            let row = self.row(idx);
+
+            let mut label = Text::new(TextFragment::new(*name).color(theme().tooltip_text));
+            label.set_scale(ui(20.0));
+
+            let dims = label.measure(ctx)?;
+            canvas.draw(&label, graphics::DrawParam::default().dest([row.x, row.y + (row.h - dims.y) / 2.0]));
+
+            let value_rect = self.value_rect(idx);
+
+            if let Some((_, input)) = self.editing.as_mut().filter(|(editing, _)| *editing == idx) {
+                input.draw(ctx, canvas, value_rect)?;
+                continue;
+            }
+
+            let background = if value_rect.contains(mouse) { theme().button_hover } else { theme().button };
+            mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), value_rect, 5.0, background)?;
+
+            let value = TagEditor::field(&mut self.tags, idx).clone();
+            let mut text = Text::new(TextFragment::new(value).color(theme().button_text));
+            text.set_scale(ui(20.0));
+            text.set_bounds([value_rect.w - ui(10.0), value_rect.h]);
+
+            let dims = text.measure(ctx)?;
+            canvas.draw(&text, graphics::DrawParam::default().dest([value_rect.x + ui(8.0), value_rect.y + (value_rect.h - dims.y) / 2.0]));
+        }
+
+        for (rect, label) in self.footer_buttons().into_iter().zip(["Reset all", "Done"]) {
+            let color = if rect.contains(mouse) { theme().button_hover } else { theme().button };
+            mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), rect, 5.0, color)?;
+
+            let mut text = Text::new(TextFragment::new(label).color(theme().button_text));
+            text.set_scale(ui(22.0));
+
+            let dims = text.measure(ctx)?;
+            canvas.draw(&text, graphics::DrawParam::default().dest([rect.center().x - dims.x / 2.0, rect.center().y - dims.y / 2.0]));
+        }
+
+        Ok(())
+    }
+
+    pub fn mouse_button_down_event(&mut self, button: MouseButton, x: f32, y: f32) {
+        if button != MouseButton::Left {
+            return;
+        }
+
+        //Clicking anywhere else finishes typing
+        if let Some((idx, _)) = &self.editing {
+            if self.value_rect(*idx).contains([x, y]) {
+                return;
+            }
+
+            self.commit();
+        }
+
+        let [reset, done] = self.footer_buttons();
+
+        if reset.contains([x, y]) {
+            self.tags = self.defaults.clone();
+            return;
+        }
+
+        if done.contains([x, y]) {
+            self.done = true;
+            return;
+        }
+
+        let Some(idx) = (0..FIELDS.len()).find(|idx| self.value_rect(*idx).contains([x, y])) else {
+            return;
+        };
+
+        self.start_editing(idx);
+    }
+
+    pub fn key_down_event(&mut self, input: KeyInput) {
+        match input.keycode {
+            Some(VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter) => self.commit(),
+
+            //Tab moves on to the next tag, which is quicker when filling them all in
+            Some(VirtualKeyCode::Tab) => {
+                let next = self.editing.as_ref().map_or(0, |(idx, _)| (idx + 1) % FIELDS.len());
+                self.commit();
+                self.start_editing(next);
+            }
+
+            Some(VirtualKeyCode::Escape) if self.editing.is_some() => self.editing = None,
+            Some(VirtualKeyCode::Escape) => self.done = true,
+
+            _ => {
+                if let Some((_, text_input)) = &mut self.editing {
+                    text_input.key_down_event(input);
+                }
+            }
+        }
+    }
+
+    pub fn text_input_event(&mut self, character: char) {
+        if let Some((_, input)) = &mut self.editing {
+            input.text_input_event(character);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use ggez::{
+    event::MouseButton,
+    graphics::{self, Canvas, Rect, Text, TextFragment},
+    input::keyboard::{KeyInput, KeyMods},
+    winit::event::VirtualKeyCode,
+    Context, GameError,
+};
+
+use super::clipboard;
+use super::mesh_cache;
+use super::theme::theme;
+
+#[derive(Debug, Clone)]
+pub struct TextInput {
+    text: String,
+    placeholder: String,
+    focused: bool,
+
+    bounds: Rect,
+}
+
+impl TextInput {
+    pub fn new(placeholder: &str) -> Self {
+        TextInput {
+            text: String::new(),
+            placeholder: placeholder.to_string(),
+            focused: true,
+
+            bounds: Rect::new(0.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn text_input_event(&mut self, character: char) {
+        if self.focused && !character.is_control() {
+            self.text.push(character);
+        }
+    }
+
+    pub fn key_down_event(&mut self, input: KeyInput) {
+        if !self.focused {
+            return;
+        }
+
+        match input.keycode {
+            Some(VirtualKeyCode::Back) => {
+                self.text.pop();
+            }
+
+            Some(VirtualKeyCode::V) if input.mods.contains(KeyMods::CTRL) => {
+                match clipboard::get_text() {
+                    Ok(text) => self.text.push_str(text.trim()),
+                    Err(e) => println!("{}", e),
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    pub fn mouse_button_down_event(&mut self, button: MouseButton, x: f32, y: f32) {
+        if button == MouseButton::Left {
+            self.focused = self.bounds.contains([x, y]);
+        }
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, canvas: &mut Canvas, bounds: Rect) -> Result<(), GameError> {
+        const PADDING: f32 = 10.0;
+
+        self.bounds = bounds;
+
+        mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::fill(), bounds, 5.0, theme().input_background)?;
+
+        let mut text = if self.text.is_empty() {
+            Text::new(TextFragment::new(self.placeholder.as_str()).color(theme().input_placeholder))
+        } else {
+            Text::new(TextFragment::new(self.text.as_str()).color(theme().input_text))
+        };
+
+        if self.focused && (ctx.time.time_since_start().as_millis() / 500).is_multiple_of(2) {
+            text.add(TextFragment::new("|").color(theme().input_text));
+        }
+
+        text.set_scale(bounds.h - PADDING * 2.0);
+
+        let dims = text.measure(ctx)?;
+
+        //Keep the end of long inputs visible
+        let x = bounds.x + PADDING - (dims.x - (bounds.w - PADDING * 2.0)).max(0.0);
+
+        canvas.set_scissor_rect(Rect::new(bounds.x + PADDING, bounds.y, bounds.w - PADDING * 2.0, bounds.h))?;
+        canvas.draw(&text, graphics::DrawParam::default().dest([x, bounds.y + (bounds.h - dims.y) / 2.0]));
+        canvas.set_default_scissor_rect();
+
+        if self.focused {
+            mesh_cache::draw_rounded_rectangle(ctx, canvas, graphics::DrawMode::stroke(3.0), bounds, 5.0, theme().input_text)?;
+        }
+
+        Ok(())
+    }
+}
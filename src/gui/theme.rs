@@ -0,0 +1,135 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ggez::graphics::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::settings;
+
+//Colors used by the menus
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub background: Color,
+    pub text: Color,
+    pub muted_text: Color,
+    pub error_text: Color,
+
+    pub button: Color,
+    pub button_hover: Color,
+    pub button_text: Color,
+
+    //Player picker entries and outlines
+    pub option_text: Color,
+    pub selected_outline: Color,
+    pub hovered_outline: Color,
+    pub panel_border: Color,
+    //Around whatever the keyboard is on
+    pub focus_outline: Color,
+
+    pub tooltip_background: Color,
+    pub tooltip_text: Color,
+
+    pub input_background: Color,
+    pub input_text: Color,
+    pub input_placeholder: Color,
+}
+
+const DARK: Theme = Theme {
+    background: Color::new(0.2, 0.2, 0.2, 1.0),
+    text: Color::WHITE,
+    muted_text: Color::new(0.7, 0.7, 0.7, 1.0),
+    error_text: Color::new(1.0, 0.4, 0.4, 1.0),
+
+    button: Color::new(0.0, 0.0, 0.0, 1.0),
+    button_hover: Color::new(0.1, 0.1, 0.1, 1.0),
+    button_text: Color::WHITE,
+
+    option_text: Color::new(0.5, 0.5, 0.5, 1.0),
+    selected_outline: Color::new(0.0, 0.0, 0.0, 1.0),
+    hovered_outline: Color::new(0.5, 0.5, 0.5, 1.0),
+    panel_border: Color::new(0.7, 0.7, 0.7, 1.0),
+    focus_outline: Color::new(1.0, 0.8, 0.2, 1.0),
+
+    tooltip_background: Color::new(0.1, 0.1, 0.1, 0.9),
+    tooltip_text: Color::WHITE,
+
+    input_background: Color::new(0.9, 0.9, 0.9, 1.0),
+    input_text: Color::BLACK,
+    input_placeholder: Color::new(0.5, 0.5, 0.5, 1.0),
+};
+
+const LIGHT: Theme = Theme {
+    background: Color::new(0.93, 0.93, 0.9, 1.0),
+    text: Color::new(0.1, 0.1, 0.1, 1.0),
+    muted_text: Color::new(0.35, 0.35, 0.35, 1.0),
+    error_text: Color::new(0.8, 0.1, 0.1, 1.0),
+
+    button: Color::new(0.2, 0.3, 0.5, 1.0),
+    button_hover: Color::new(0.3, 0.4, 0.6, 1.0),
+    button_text: Color::WHITE,
+
+    option_text: Color::new(0.4, 0.4, 0.4, 1.0),
+    selected_outline: Color::new(0.1, 0.1, 0.1, 1.0),
+    hovered_outline: Color::new(0.6, 0.6, 0.6, 1.0),
+    panel_border: Color::new(0.6, 0.6, 0.6, 1.0),
+    focus_outline: Color::new(0.1, 0.4, 0.9, 1.0),
+
+    tooltip_background: Color::new(0.2, 0.2, 0.2, 0.9),
+    tooltip_text: Color::WHITE,
+
+    input_background: Color::WHITE,
+    input_text: Color::BLACK,
+    input_placeholder: Color::new(0.5, 0.5, 0.5, 1.0),
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+}
+
+pub const ALL_THEMES: [ThemeName; 2] = [ThemeName::Dark, ThemeName::Light];
+
+impl ThemeName {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ThemeName::Dark => "Dark",
+            ThemeName::Light => "Light",
+        }
+    }
+
+    pub fn next(&self) -> ThemeName {
+        let idx = ALL_THEMES.iter().position(|t| t == self).unwrap();
+
+        ALL_THEMES[(idx + 1) % ALL_THEMES.len()]
+    }
+
+    pub fn theme(&self) -> &'static Theme {
+        match self {
+            ThemeName::Dark => &DARK,
+            ThemeName::Light => &LIGHT,
+        }
+    }
+}
+
+//Index into ALL_THEMES
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+pub fn current_theme() -> ThemeName {
+    ALL_THEMES[CURRENT.load(Ordering::Relaxed)]
+}
+
+pub fn theme() -> &'static Theme {
+    current_theme().theme()
+}
+
+pub fn init_theme(name: ThemeName) {
+    CURRENT.store(ALL_THEMES.iter().position(|t| *t == name).unwrap(), Ordering::Relaxed);
+}
+
+//Switches theme and remembers it for next time
+pub fn set_theme(name: ThemeName) {
+    init_theme(name);
+    settings::update(|settings| settings.theme = name);
+}
@@ -0,0 +1,169 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+use ggez::{
+    graphics::{self, Canvas, Color, Rect, Text, TextFragment},
+    Context, GameError,
+};
+
+use crate::events::{self, GameEvent};
+use crate::net::Side;
+
+use super::mesh_cache;
+use super::scale::ui;
+use super::theme::theme;
+
+pub const TICKER_HEIGHT: f32 = 28.0;
+
+//Only the newest fit on the strip anyway
+const MAX_ITEMS: usize = 12;
+//How long a new item takes to slide in
+const SLIDE_IN: Duration = Duration::from_millis(400);
+//Clocks under this get a warning, once per side and game
+const FLAG_WARNING_MS: u64 = 10_000;
+//Analysis depths worth a mention, the first time a game's analysis gets there
+const DEPTH_MILESTONE: u32 = 5;
+
+const SEPARATOR: &str = "   |   ";
+
+//Strip under the board with the latest captures, checks, draw offers, low clocks and analysis depths from the event
+//bus, newest on the left
+#[derive(Debug)]
+pub struct Ticker {
+    events: Receiver<GameEvent>,
+    items: VecDeque<String>,
+    last_item: Instant,
+
+    flag_warned: [bool; 2],
+    deepest: u32,
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::White => "White",
+        Side::Black => "Black",
+    }
+}
+
+impl Ticker {
+    pub fn subscribe() -> Self {
+        Ticker {
+            events: events::subscribe(),
+            items: VecDeque::new(),
+            last_item: Instant::now(),
+
+            flag_warned: [false; 2],
+            deepest: 0,
+        }
+    }
+
+    fn push(&mut self, item: String) {
+        self.items.push_front(item);
+        self.items.truncate(MAX_ITEMS);
+        self.last_item = Instant::now();
+    }
+
+    //What is worth a mention from an event, if anything
+    fn describe(&mut self, event: GameEvent) -> Option<String> {
+        match event {
+            GameEvent::GameStarted { white, black, .. } => {
+                self.items.clear();
+                self.flag_warned = [false; 2];
+                self.deepest = 0;
+
+                Some(format!("{} vs {}", white, black))
+            }
+
+            GameEvent::Move { ply, san, .. } => {
+                let number = if ply % 2 == 1 { format!("{}.", ply.div_ceil(2)) } else { format!("{}...", ply / 2) };
+
+                let what = if san.ends_with('#') {
+                    "checkmate"
+                } else if san.contains('x') && san.ends_with('+') {
+                    "capture with check"
+                } else if san.ends_with('+') {
+                    "check"
+                } else if san.contains('x') {
+                    "capture"
+                } else {
+                    return None;
+                };
+
+                Some(format!("{}{} {}", number, san, what))
+            }
+
+            GameEvent::Clock { white_ms, black_ms } => {
+                let low = [white_ms, black_ms].map(|ms| ms < FLAG_WARNING_MS);
+                let side = [Side::White, Side::Black].into_iter().zip(0..).find(|(_, i)| low[*i] && !self.flag_warned[*i]);
+                let (side, i) = side?;
+
+                self.flag_warned[i] = true;
+                Some(format!("{} has under {}s left", side_name(side), FLAG_WARNING_MS / 1000))
+            }
+
+            GameEvent::FlagFell { side } => Some(format!("{}'s flag fell", side_name(side))),
+
+            GameEvent::DrawOffered { side } => Some(format!("{} offers a draw", side_name(side))),
+
+            GameEvent::Eval { eval, depth, .. } => {
+                let milestone = depth - depth % DEPTH_MILESTONE;
+
+                if milestone <= self.deepest {
+                    return None;
+                }
+
+                self.deepest = milestone;
+                Some(format!("Analysis reached depth {} ({:+.2})", milestone, eval))
+            }
+
+            GameEvent::Takeback { ply, .. } => Some(format!("Taken back to ply {}", ply)),
+
+            GameEvent::GameOver { result, reason } => Some(format!("{} {}", result, reason)),
+
+            GameEvent::Paused { .. } | GameEvent::Commentary { .. } => None,
+        }
+    }
+
+    pub fn update(&mut self) {
+        let events: Vec<GameEvent> = self.events.try_iter().collect();
+
+        for event in events {
+            if let Some(item) = self.describe(event) {
+                self.push(item);
+            }
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, bounds: Rect) -> Result<(), GameError> {
+        mesh_cache::draw_rectangle(ctx, canvas, graphics::DrawMode::fill(), bounds, theme().input_background)?;
+
+        let scale = ui(16.0);
+
+        let mut newest = Text::new(TextFragment::new(self.items.front().map_or("Nothing has happened yet", |item| item.as_str())));
+        newest.set_scale(scale);
+
+        let mut older = Text::new(TextFragment::new(
+            self.items.iter().skip(1).map(|item| format!("{}{}", SEPARATOR, item)).collect::<String>(),
+        ));
+        older.set_scale(scale);
+
+        //Older items are pushed along to the right as the newest slides in
+        let width = newest.measure(ctx)?.x;
+        let shown = (self.last_item.elapsed().as_secs_f32() / SLIDE_IN.as_secs_f32()).min(1.0);
+        let x = bounds.x + ui(6.0) - width * (1.0 - shown);
+        let y = bounds.y + (bounds.h - newest.measure(ctx)?.y) / 2.0;
+
+        canvas.set_scissor_rect(bounds)?;
+
+        let alpha = if self.items.is_empty() { 0.5 } else { shown };
+        let text_color = theme().input_text;
+
+        canvas.draw(&newest, graphics::DrawParam::default().dest([x, y]).color(Color { a: alpha, ..text_color }));
+        canvas.draw(&older, graphics::DrawParam::default().dest([x + width, y]).color(theme().input_placeholder));
+
+        canvas.set_default_scissor_rect();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use std::time::{Duration, Instant};
+
+use ggez::event::winit_event::TouchPhase;
+use ggez::graphics::Rect;
+
+//Smallest a control can be and still be easy to hit with a finger, in pixels whatever the UI scale
+pub const MIN_TARGET: f32 = 44.0;
+
+//Holding a finger still this long counts as a right click
+const LONG_PRESS: Duration = Duration::from_millis(500);
+//How far a finger can drift during a long press
+const LONG_PRESS_SLOP: f32 = 12.0;
+
+//Where a control can be clicked, grown around its middle when it is smaller than a fingertip
+pub fn hit_rect(bounds: Rect) -> Rect {
+    let w = bounds.w.max(MIN_TARGET);
+    let h = bounds.h.max(MIN_TARGET);
+
+    Rect::new(bounds.x - (w - bounds.w) / 2.0, bounds.y - (h - bounds.h) / 2.0, w, h)
+}
+
+//Watches the finger on a touchscreen for long presses. Taps and drags are already turned into the left mouse button by ggez
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TouchTracker {
+    //Where and when the finger went down, until it is lifted, moves away or has long pressed
+    press: Option<(Instant, f32, f32)>,
+}
+
+impl TouchTracker {
+    pub fn track(&mut self, phase: TouchPhase, x: f32, y: f32) {
+        match phase {
+            TouchPhase::Started => self.press = Some((Instant::now(), x, y)),
+            TouchPhase::Moved => {
+                if self.press.is_some_and(|(_, start_x, start_y)| (x - start_x).hypot(y - start_y) > LONG_PRESS_SLOP) {
+                    self.press = None;
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => self.press = None,
+        }
+    }
+
+    //Where the finger has been held long enough, once per press
+    pub fn long_press(&mut self) -> Option<(f32, f32)> {
+        let (start, x, y) = self.press?;
+
+        if start.elapsed() < LONG_PRESS {
+            return None;
+        }
+
+        self.press = None;
+        Some((x, y))
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use chess::Board;
+use ggez::{
+    graphics::{self, Canvas, Text},
+    Context, GameError,
+};
+
+use crate::pgn::PgnGame;
+
+use super::chess_display::ChessDisplay;
+use super::scale::ui;
+use super::theme::theme;
+
+//How often the folder is looked at again
+const POLL_EVERY: Duration = Duration::from_secs(2);
+
+//Files changed more recently than this might still be being written to
+const SETTLE_TIME: Duration = Duration::from_secs(1);
+
+const HEADER_HEIGHT: f32 = 50.0;
+
+struct WatchedFile {
+    modified: SystemTime,
+    //Games in the file that were already queued or skipped
+    games: usize,
+}
+
+//Plays back every game that shows up in the PGN files of a folder, for showing games written by tournaments on
+//another machine
+pub struct FolderWatcher {
+    dir: PathBuf,
+    //How long the end of a game stays up before the next one starts
+    hold: Duration,
+
+    files: HashMap<PathBuf, WatchedFile>,
+    queue: VecDeque<(String, PgnGame)>,
+    polled_at: Instant,
+    error: Option<String>,
+
+    chess: Option<ChessDisplay>,
+    //Which file and game is on screen
+    shown: String,
+    finished_at: Option<Instant>,
+    //Carried over from game to game, so a speed changed with - and = sticks
+    delay: Duration,
+}
+
+impl FolderWatcher {
+    //Games already in the folder are only played when `existing` is set, otherwise just the ones added later are
+    pub fn new(dir: PathBuf, hold: Duration, delay: Duration, existing: bool) -> FolderWatcher {
+        let mut watcher = FolderWatcher {
+            dir,
+            hold,
+
+            files: HashMap::new(),
+            queue: VecDeque::new(),
+            polled_at: Instant::now(),
+            error: None,
+
+            chess: None,
+            shown: String::new(),
+            finished_at: None,
+            delay,
+        };
+
+        watcher.scan(existing);
+        watcher
+    }
+
+    pub fn chess_mut(&mut self) -> Option<&mut ChessDisplay> {
+        self.chess.as_mut()
+    }
+
+    pub fn board(&self) -> Option<Board> {
+        self.chess.as_ref().map(|chess| chess.game.board)
+    }
+
+    pub fn shutdown(&mut self) {
+        if let Some(chess) = &mut self.chess {
+            chess.shutdown();
+        }
+    }
+
+    //Queues the games added to the folder's PGN files since the last scan, oldest file first
+    fn scan(&mut self, queue: bool) {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.error = Some(format!("Could not read {}: {}", self.dir.display(), e));
+                return;
+            }
+        };
+
+        self.error = None;
+
+        let mut paths: Vec<(PathBuf, SystemTime)> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("pgn")))
+            .filter_map(|path| {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+                Some((path, modified))
+            })
+            .collect();
+
+        paths.sort_by_key(|(_, modified)| *modified);
+
+        for (path, modified) in paths {
+            if self.files.get(&path).is_some_and(|file| file.modified == modified) {
+                continue;
+            }
+
+            if queue && modified.elapsed().unwrap_or_default() < SETTLE_TIME {
+                continue;
+            }
+
+            let seen = self.files.get(&path).map_or(0, |file| file.games);
+
+            let games = match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|text| PgnGame::parse_all(&text)) {
+                Ok(games) => games,
+                Err(e) => {
+                    println!("Could not read {}: {}", path.display(), e);
+                    self.files.insert(path, WatchedFile { modified, games: seen });
+                    continue;
+                }
+            };
+
+            //A file that was written over rather than added to is played from its first game again
+            let seen = if games.len() < seen { 0 } else { seen };
+            let count = games.len();
+
+            if queue {
+                let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+
+                for (i, game) in games.into_iter().enumerate().skip(seen) {
+                    println!("Queued {} game {}", name, i + 1);
+                    self.queue.push_back((format!("{} game {}", name, i + 1), game));
+                }
+            }
+
+            self.files.insert(path, WatchedFile { modified, games: count });
+        }
+    }
+
+    fn show(&mut self, ctx: &mut Context, name: String, game: PgnGame) {
+        if let Some(chess) = &self.chess {
+            self.delay = chess.autoplay_delay();
+        }
+
+        let mut chess = ChessDisplay::replay(ctx, game);
+        chess.set_autoplay_delay(self.delay);
+        chess.start_autoplay();
+
+        self.chess = Some(chess);
+        self.shown = name;
+        self.finished_at = None;
+    }
+
+    pub fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
+        if self.polled_at.elapsed() >= POLL_EVERY {
+            self.scan(true);
+            self.polled_at = Instant::now();
+        }
+
+        if let Some(chess) = self.chess.as_mut() {
+            chess.update(ctx)?;
+
+            //Stepping through the game by hand stops the playback too, which then counts as the end of it
+            if !chess.autoplaying() && self.finished_at.is_none() {
+                self.finished_at = Some(Instant::now());
+            }
+        }
+
+        let done = self.chess.is_none() || self.finished_at.is_some_and(|at| at.elapsed() >= self.hold);
+
+        if done {
+            if let Some((name, game)) = self.queue.pop_front() {
+                self.show(ctx, name, game);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn caption(&self) -> String {
+        let game = match (&self.error, &self.chess) {
+            (Some(error), _) => error.clone(),
+            (None, Some(_)) => format!("{}  |  {} more waiting", self.shown, self.queue.len()),
+            (None, None) => String::from("Waiting for the first game"),
+        };
+
+        format!("{}\nWatching {} for new games, Esc to return to the main menu", game, self.dir.display())
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, canvas: &mut Canvas, width: f32, height: f32) -> Result<(), GameError> {
+        let header = ui(HEADER_HEIGHT);
+
+        if let Some(chess) = self.chess.as_mut() {
+            chess.draw(ctx, canvas, 0.0, header, width, height - header)?;
+        }
+
+        let mut text = Text::new(self.caption());
+        text.set_scale(ui(20.0));
+
+        canvas.draw(&text, graphics::DrawParam::default().dest([ui(10.0), ui(5.0)]).color(theme().text));
+
+        Ok(())
+    }
+}
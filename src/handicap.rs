@@ -0,0 +1,101 @@
+use chess::{Board, BoardBuilder, CastleRights, Color, File, Piece, Rank, Square};
+
+//Material odds, where one side starts without one of its pieces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Handicap {
+    #[default]
+    None,
+    Odds(Color, Piece),
+}
+
+const ODDS_PIECES: [Piece; 5] = [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+
+impl Handicap {
+    pub fn name(&self) -> String {
+        match self {
+            Handicap::None => String::from("None"),
+            Handicap::Odds(color, piece) => format!("{:?} gives {} odds", color, piece_name(*piece)),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Handicap> {
+        let mut handicap = Handicap::None;
+
+        loop {
+            if handicap.name() == name {
+                return Some(handicap);
+            }
+
+            handicap = handicap.next();
+
+            if handicap == Handicap::None {
+                return None;
+            }
+        }
+    }
+
+    //Cycles through no handicap, then every odds for white, then every odds for black
+    pub fn next(&self) -> Handicap {
+        match self {
+            Handicap::None => Handicap::Odds(Color::White, ODDS_PIECES[0]),
+
+            Handicap::Odds(color, piece) => {
+                let idx = ODDS_PIECES.iter().position(|p| p == piece).unwrap();
+
+                match (ODDS_PIECES.get(idx + 1), color) {
+                    (Some(next), _) => Handicap::Odds(*color, *next),
+                    (None, Color::White) => Handicap::Odds(Color::Black, ODDS_PIECES[0]),
+                    (None, Color::Black) => Handicap::None,
+                }
+            }
+        }
+    }
+
+    //Traditional squares the piece is taken from: the f-pawn and the queenside knight, bishop and rook
+    fn removed_square(color: Color, piece: Piece) -> Square {
+        let file = match piece {
+            Piece::Pawn => File::F,
+            Piece::Knight => File::B,
+            Piece::Bishop => File::C,
+            Piece::Rook => File::A,
+            Piece::Queen => File::D,
+            Piece::King => File::E,
+        };
+
+        let rank = match (color, piece) {
+            (Color::White, Piece::Pawn) => Rank::Second,
+            (Color::White, _) => Rank::First,
+            (Color::Black, Piece::Pawn) => Rank::Seventh,
+            (Color::Black, _) => Rank::Eighth,
+        };
+
+        Square::make_square(rank, file)
+    }
+
+    pub fn start_board(&self) -> Board {
+        let Handicap::Odds(color, piece) = *self else {
+            return Board::default();
+        };
+
+        let mut builder = BoardBuilder::from(Board::default());
+        builder.clear_square(Handicap::removed_square(color, piece));
+
+        //Without the a-rook there is nothing to castle with on the queenside
+        if piece == Piece::Rook {
+            builder.castle_rights(color, Board::default().castle_rights(color).remove(CastleRights::QueenSide));
+        }
+
+        Board::try_from(builder).unwrap()
+    }
+}
+
+fn piece_name(piece: Piece) -> &'static str {
+    match piece {
+        Piece::Pawn => "pawn",
+        Piece::Knight => "knight",
+        Piece::Bishop => "bishop",
+        Piece::Rook => "rook",
+        Piece::Queen => "queen",
+        Piece::King => "king",
+    }
+}
@@ -0,0 +1,543 @@
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::{Board, ChessMove, Color};
+
+use crate::alg::chess_alg::{ChessAlgorithm, EngineSettings};
+use crate::game_controller::GameController;
+use crate::gui::chess_display::{GameOutcome, PlayerType};
+use crate::util;
+use crate::variant::{self, Variant};
+
+//Longest a scripted engine gets to answer before the test fails rather than hanging
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+//Plays the moves it was given in order and remembers what it was told, so that tests can check the controller
+//drives engines the way the GUI would
+#[derive(Debug, Default)]
+pub struct ScriptedEngine {
+    moves: Vec<ChessMove>,
+    next: usize,
+
+    pub positions: Vec<Board>,
+    pub new_games: usize,
+    pub results: Vec<String>,
+}
+
+impl ScriptedEngine {
+    pub fn new(moves: &[&str]) -> ScriptedEngine {
+        ScriptedEngine {
+            moves: moves.iter().map(|m| parse(m)).collect(),
+            ..ScriptedEngine::default()
+        }
+    }
+}
+
+impl ChessAlgorithm for ScriptedEngine {
+    fn get_move(&mut self, board: Board) -> ChessMove {
+        self.positions.push(board);
+
+        let m = *self.moves.get(self.next).unwrap_or_else(|| panic!("Script ran out of moves at {}", board));
+        self.next += 1;
+
+        m
+    }
+
+    fn new_game(&mut self) {
+        self.new_games += 1;
+        self.next = 0;
+    }
+
+    fn game_over(&mut self, result: &str, _reason: &str) {
+        self.results.push(result.to_string());
+    }
+}
+
+fn parse(m: &str) -> ChessMove {
+    variant::parse_uci(m).unwrap_or_else(|| panic!("{} isn't a UCI move", m))
+}
+
+//A GameController without a window: moves are fed in ply by ply, the computer's searches are waited on and the
+//game is ended with the same rules the GUI checks after every move
+pub struct Harness {
+    pub game: GameController,
+    engines: [Option<Arc<Mutex<ScriptedEngine>>>; 2],
+}
+
+impl Harness {
+    pub fn new(white: Option<&[&str]>, black: Option<&[&str]>) -> Harness {
+        let engines = [white, black].map(|script| script.map(|moves| Arc::new(Mutex::new(ScriptedEngine::new(moves)))));
+
+        let player = |engine: &Option<Arc<Mutex<ScriptedEngine>>>| match engine {
+            Some(engine) => PlayerType::Computer(engine.clone()),
+            None => PlayerType::Human,
+        };
+
+        Harness {
+            game: GameController::new(player(&engines[0]), player(&engines[1])),
+            engines,
+        }
+    }
+
+    pub fn humans() -> Harness {
+        Harness::new(None, None)
+    }
+
+    pub fn with_fen(mut self, fen: &str) -> Harness {
+        self.game.reset(self.game.variant, Board::from_str(fen).unwrap());
+        self.game.set_start_halfmove_clock(util::halfmove_clock(fen));
+        self
+    }
+
+    pub fn with_variant(mut self, variant: Variant) -> Harness {
+        self.game.reset(variant, self.game.start_board);
+        self
+    }
+
+    pub fn engine(&self, color: Color) -> Arc<Mutex<ScriptedEngine>> {
+        self.engines[color.to_index()].clone().expect("That side isn't scripted")
+    }
+
+    //Plays a human's move, or returns an error without touching the game if it's illegal or the game is over
+    pub fn try_play(&mut self, m: &str) -> Result<(), String> {
+        if let Some(outcome) = &self.game.outcome {
+            return Err(format!("The game is already over: {}", outcome.get_text()));
+        }
+
+        let m = parse(m);
+
+        if !self.game.legal_moves().contains(&m) {
+            return Err(format!("{} isn't legal in {}", variant::move_to_uci(m), self.game.board));
+        }
+
+        self.after_move(m);
+        Ok(())
+    }
+
+    pub fn play(&mut self, m: &str) -> &mut Harness {
+        self.try_play(m).unwrap();
+        self
+    }
+
+    pub fn play_all(&mut self, moves: &[&str]) -> &mut Harness {
+        for m in moves {
+            self.play(m);
+        }
+
+        self
+    }
+
+    //Lets the computer to move search and plays what it finds, forfeiting if it isn't legal like the GUI does. Returns
+    //the move, or None once the game is over
+    pub fn step(&mut self) -> Option<ChessMove> {
+        if self.game.outcome.is_some() {
+            return None;
+        }
+
+        assert!(self.game.current_player().is_computer(), "It's a human's turn, use play");
+
+        self.game.start_search(None, EngineSettings::default());
+        let started = Instant::now();
+
+        let m = loop {
+            match self.game.poll_search() {
+                Some(Ok(m)) => break m,
+                Some(Err(outcome)) => {
+                    self.game.end(outcome);
+                    return None;
+                }
+                None => {}
+            }
+
+            assert!(started.elapsed() < SEARCH_TIMEOUT, "The engine never answered");
+            thread::sleep(Duration::from_millis(1));
+        };
+
+        match self.game.check_move(m, "Scripted engine") {
+            Ok(m) => self.after_move(m),
+            Err(outcome) => self.game.end(outcome),
+        }
+
+        Some(m)
+    }
+
+    //Steps until the game ends or it's a human's turn, giving up after `max_plies`
+    pub fn run(&mut self, max_plies: usize) -> Option<&GameOutcome> {
+        for _ in 0..max_plies {
+            if self.game.outcome.is_some() || !self.game.current_player().is_computer() {
+                break;
+            }
+
+            self.step();
+        }
+
+        self.game.outcome.as_ref()
+    }
+
+    pub fn outcome(&self) -> Option<&GameOutcome> {
+        self.game.outcome.as_ref()
+    }
+
+    fn after_move(&mut self, m: ChessMove) {
+        self.game.apply(m);
+
+        if let Some(outcome) = self.game.rules_outcome() {
+            self.game.end(outcome);
+        }
+    }
+}
+
+mod tests {
+    use chess::{Color, MoveGen, Piece, Square};
+
+    use crate::alg::chess_alg::STOP_GRACE;
+    use crate::alg::evaluators;
+    use crate::arena;
+    use crate::clock::TimeControl;
+    use crate::pgn::{MoveAnnotation, PgnGame};
+
+    use super::*;
+
+    const SCHOLARS_MATE: [&str; 7] = ["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6", "h5f7"];
+
+    //Knights out and back, returning to the starting position every four plies
+    const SHUFFLE: [&str; 4] = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+    fn white_moves(moves: &[&'static str]) -> Vec<&'static str> {
+        moves.iter().step_by(2).copied().collect()
+    }
+
+    fn black_moves(moves: &[&'static str]) -> Vec<&'static str> {
+        moves.iter().skip(1).step_by(2).copied().collect()
+    }
+
+    #[test]
+    fn fools_mate() {
+        let mut harness = Harness::humans();
+        harness.play_all(&["f2f3", "e7e5", "g2g4", "d8h4"]);
+
+        assert_eq!(harness.outcome(), Some(&GameOutcome::Checkmate(Color::Black)));
+        assert!(harness.try_play("e2e4").is_err());
+    }
+
+    #[test]
+    fn scripted_engines_play_scholars_mate() {
+        let mut harness = Harness::new(Some(&white_moves(&SCHOLARS_MATE)), Some(&black_moves(&SCHOLARS_MATE)));
+
+        assert_eq!(harness.run(20), Some(&GameOutcome::Checkmate(Color::White)));
+        assert_eq!(harness.game.moves.len(), SCHOLARS_MATE.len());
+
+        let white = harness.engine(Color::White);
+        let black = harness.engine(Color::Black);
+
+        assert_eq!(white.lock().unwrap().positions.len(), 4);
+        assert_eq!(black.lock().unwrap().positions.len(), 3);
+        assert_eq!(white.lock().unwrap().results, vec!["1-0"]);
+        assert_eq!(black.lock().unwrap().results, vec!["1-0"]);
+    }
+
+    #[test]
+    fn human_against_engine() {
+        let mut harness = Harness::new(None, Some(&black_moves(&SCHOLARS_MATE)));
+
+        for m in white_moves(&SCHOLARS_MATE) {
+            harness.play(m);
+            harness.run(1);
+        }
+
+        assert_eq!(harness.outcome(), Some(&GameOutcome::Checkmate(Color::White)));
+    }
+
+    #[test]
+    fn illegal_engine_moves_forfeit() {
+        let mut harness = Harness::new(Some(&["e2e4", "e4e6"]), Some(&["e7e5"]));
+
+        assert_eq!(harness.run(10), Some(&GameOutcome::IllegalMove(Color::Black)));
+        assert_eq!(harness.game.moves.len(), 2);
+        assert_eq!(harness.engine(Color::White).lock().unwrap().results, vec!["0-1"]);
+    }
+
+    #[test]
+    fn illegal_moves_are_refused() {
+        let mut harness = Harness::humans();
+
+        assert!(harness.try_play("e2e5").is_err());
+        assert!(harness.try_play("e7e5").is_err());
+        assert!(harness.game.moves.is_empty());
+    }
+
+    #[test]
+    fn stalemate() {
+        let mut harness = Harness::humans().with_fen("7k/8/6K1/8/8/8/5Q2/8 w - - 0 1");
+        harness.play("f2f7");
+
+        assert_eq!(harness.outcome(), Some(&GameOutcome::Stalemate));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let mut harness = Harness::humans().with_fen("8/8/8/4k3/8/8/3q4/3K4 w - - 0 1");
+        harness.play("d1d2");
+
+        assert_eq!(harness.outcome(), Some(&GameOutcome::InsufficientMaterial));
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let mut harness = Harness::humans();
+
+        //The starting position comes back a second time, which isn't enough
+        harness.play_all(&SHUFFLE);
+        assert_eq!(harness.outcome(), None);
+
+        harness.play_all(&SHUFFLE);
+        assert_eq!(harness.outcome(), Some(&GameOutcome::DrawByRepetition));
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        let mut harness = Harness::humans().with_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+
+        //Walks the rook around the a and b files while the black king goes around the other corner. The walks are 12
+        //and 7 moves long, so no position repeats before the 100th ply
+        let rook = ["a1a2", "a2a3", "a3a4", "a4a5", "a5a6", "a6b6", "b6b5", "b5b4", "b4b3", "b3b2", "b2b1", "b1a1"];
+        let king = ["e8f8", "f8g8", "g8h8", "h8h7", "h7g7", "g7f7", "f7e8"];
+
+        for ply in 0..99 {
+            harness.play(if ply % 2 == 0 { rook[(ply / 2) % rook.len()] } else { king[(ply / 2) % king.len()] });
+        }
+
+        assert_eq!(harness.outcome(), None);
+
+        harness.play("e8f8");
+        assert_eq!(harness.outcome(), Some(&GameOutcome::DrawBy50MoveRule));
+    }
+
+    #[test]
+    fn pawn_moves_reset_the_fifty_move_count() {
+        let mut harness = Harness::humans();
+        harness.play_all(&["g1f3", "g8f6", "e2e4"]);
+
+        assert_eq!(harness.game.reversable_moves, 0);
+
+        harness.play("b8c6");
+        assert_eq!(harness.game.reversable_moves, 1);
+    }
+
+    #[test]
+    fn promotion() {
+        let mut harness = Harness::humans().with_fen("8/P7/8/8/8/8/k7/4K3 w - - 0 1");
+        harness.play("a7a8q");
+
+        assert_eq!(harness.game.board.piece_on(Square::A8), Some(Piece::Queen));
+        assert_eq!(harness.game.reversable_moves, 0);
+    }
+
+    #[test]
+    fn underpromotion() {
+        let mut harness = Harness::humans().with_fen("8/P7/8/8/8/8/k7/4K3 w - - 0 1");
+        harness.play("a7a8n");
+
+        assert_eq!(harness.game.board.piece_on(Square::A8), Some(Piece::Knight));
+    }
+
+    #[test]
+    fn promotion_without_a_piece_is_refused() {
+        let mut harness = Harness::humans().with_fen("8/P7/8/8/8/8/k7/4K3 w - - 0 1");
+
+        assert!(harness.try_play("a7a8").is_err());
+    }
+
+    #[test]
+    fn promoting_to_mate() {
+        for m in ["c7c8q", "c7c8r"] {
+            let mut harness = Harness::humans().with_fen("k7/2P5/1K6/8/8/8/8/8 w - - 0 1");
+            harness.play(m);
+
+            assert_eq!(harness.outcome(), Some(&GameOutcome::Checkmate(Color::White)), "{}", m);
+        }
+    }
+
+    #[test]
+    fn castling() {
+        let mut harness = Harness::humans();
+        harness.play_all(&["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1"]);
+
+        assert_eq!(harness.game.board.piece_on(Square::G1), Some(Piece::King));
+        assert_eq!(harness.game.board.piece_on(Square::F1), Some(Piece::Rook));
+        assert_eq!(harness.game.board.piece_on(Square::H1), None);
+    }
+
+    #[test]
+    fn castling_queenside() {
+        let mut harness = Harness::humans().with_fen("r3k3/8/8/8/8/8/8/R3K3 b q - 0 1");
+        harness.play("e8c8");
+
+        assert_eq!(harness.game.board.piece_on(Square::C8), Some(Piece::King));
+        assert_eq!(harness.game.board.piece_on(Square::D8), Some(Piece::Rook));
+    }
+
+    #[test]
+    fn no_castling_through_check() {
+        let mut harness = Harness::humans().with_fen("4kr2/8/8/8/8/8/8/4K2R w K - 0 1");
+
+        assert!(harness.try_play("e1g1").is_err());
+    }
+
+    #[test]
+    fn no_castling_after_the_king_moves() {
+        let mut harness = Harness::humans().with_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+        harness.play_all(&["e1f1", "e8d8", "f1e1", "d8e8"]);
+
+        assert!(harness.try_play("e1g1").is_err());
+    }
+
+    #[test]
+    fn royal_losing_chess_captures_are_compulsory() {
+        let mut harness = Harness::humans().with_variant(Variant::RoyalLosingChess);
+        harness.play_all(&["e2e4", "d7d5"]);
+
+        assert!(harness.try_play("e4e5").is_err());
+        harness.play("e4d5");
+    }
+
+    #[test]
+    fn fog_of_war_is_won_by_taking_the_king() {
+        let mut harness = Harness::humans().with_variant(Variant::FogOfWar);
+        harness.play_all(&["e2e4", "f7f6", "d1h5", "a7a6"]);
+        harness.play("h5e8");
+
+        assert_eq!(harness.outcome(), Some(&GameOutcome::KingCaptured(Color::White)));
+    }
+
+    #[test]
+    fn take_back_against_an_engine() {
+        let mut harness = Harness::new(None, Some(&["e7e5", "d7d5"]));
+        harness.play("e2e4");
+        harness.run(1);
+
+        assert_eq!(harness.game.take_back().len(), 2);
+        assert_eq!(harness.game.board, Board::default());
+        assert_eq!(harness.game.mobility.plies().len(), 0);
+    }
+
+    #[test]
+    fn take_back_undoes_the_result() {
+        let mut harness = Harness::humans();
+        harness.play_all(&["f2f3", "e7e5", "g2g4", "d8h4"]);
+        harness.game.take_back();
+
+        assert_eq!(harness.outcome(), None);
+        assert_eq!(harness.game.moves.len(), 3);
+
+        harness.play("d8g5");
+        assert_eq!(harness.outcome(), None);
+    }
+
+    #[test]
+    fn take_back_recounts_the_fifty_move_count() {
+        let mut harness = Harness::humans();
+        harness.play_all(&["e2e4", "g8f6", "g1f3", "f6g8"]);
+        assert_eq!(harness.game.reversable_moves, 3);
+
+        harness.game.take_back();
+        assert_eq!(harness.game.reversable_moves, 2);
+    }
+
+    #[test]
+    fn take_back_counts_on_from_the_start_fen() {
+        let mut harness = Harness::humans().with_fen("4k3/8/8/8/8/8/8/4K2R w K - 40 60");
+        harness.play_all(&["h1h2", "e8d8", "h2h1"]);
+        assert_eq!(harness.game.reversable_moves, 43);
+
+        harness.game.take_back();
+        assert_eq!(harness.game.reversable_moves, 42);
+    }
+
+    #[test]
+    fn pasted_and_autosaved_fens_keep_the_halfmove_clock() {
+        let (board, halfmoves) = crate::gui::clipboard::parse_fen("4k3/8/8/8/8/8/8/4K2R w K - 40 60\n").unwrap();
+        assert_eq!(halfmoves, 40);
+        assert_eq!(util::fen_with_halfmove_clock(&board, halfmoves), "4k3/8/8/8/8/8/8/4K2R w K - 40 1");
+
+        assert_eq!(crate::gui::clipboard::parse_fen("4k3/8/8/8/8/8/8/4K2R w K -").unwrap().1, 0);
+    }
+
+    #[test]
+    fn reset_starts_engines_on_a_new_game() {
+        let mut harness = Harness::new(Some(&["e2e4"]), None);
+        harness.game.reset(Variant::Standard, Board::default());
+
+        assert_eq!(harness.engine(Color::White).lock().unwrap().new_games, 1);
+        assert_eq!(harness.step().map(variant::move_to_uci), Some("e2e4".to_string()));
+    }
+
+    #[test]
+    fn timed_games_export_clock_comments() {
+        let mut harness = Harness::humans();
+        harness.play_all(&["e2e4", "e7e5", "g1f3"]);
+
+        //Each move takes a second, with the clocks kept the way the GUI does
+        let time_control = TimeControl::minutes(5, 2);
+        let mut used = [Duration::ZERO; 2];
+        let mut bonus = [Duration::ZERO; 2];
+        let mut annotations = vec![];
+
+        for board in harness.game.history.iter() {
+            let side = board.side_to_move().to_index();
+            let elapsed = Duration::from_secs(1);
+
+            used[side] += elapsed;
+            bonus[side] += time_control.refund(elapsed);
+
+            annotations.push(MoveAnnotation {
+                clock: time_control.remaining(used[side], bonus[side], Duration::ZERO),
+                move_time: Some(elapsed),
+                ..MoveAnnotation::default()
+            });
+        }
+
+        let mut game = PgnGame::new(harness.game.start_board);
+        game.moves = harness.game.moves.clone();
+        game.annotations = annotations;
+
+        let pgn = game.to_pgn(true);
+        assert!(pgn.contains("[%clk 0:05:01]"), "{}", pgn);
+        assert_eq!(pgn.matches("[%clk").count(), 3);
+    }
+
+    //Keeps thinking long after it is told to stop
+    #[derive(Debug)]
+    struct StubbornEngine;
+
+    impl ChessAlgorithm for StubbornEngine {
+        fn get_move(&mut self, board: Board) -> ChessMove {
+            thread::sleep(STOP_GRACE + Duration::from_millis(200));
+            MoveGen::new_legal(&board).next().unwrap()
+        }
+    }
+
+    #[test]
+    fn engines_that_dont_stop_forfeit_headless_games() {
+        let mut white = ScriptedEngine::new(&["e2e4"]);
+        let max_move_time = Some(Duration::from_millis(10));
+        let game = arena::play_game_watched(&mut white, &mut StubbornEngine, Variant::Standard, Board::default(), 10, max_move_time, &mut |_, _, _| {});
+
+        assert_eq!(game.outcome, GameOutcome::Forfeit(Color::White));
+        assert_eq!(game.moves.len(), 1);
+    }
+
+    #[test]
+    fn losing_a_piece_never_helps_invade() {
+        let eval = |fen| evaluators::eval_invade(&Board::from_str(fen).unwrap(), Color::White);
+
+        let home = eval("4k3/8/8/8/8/8/P7/4K3 w - - 0 1");
+        let invading = eval("4k3/8/8/P7/8/8/8/4K3 w - - 0 1");
+        let lost = eval("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+
+        assert!(lost < home, "{} < {}", lost, home);
+        assert!(home < invading, "{} < {}", home, invading);
+    }
+}
@@ -0,0 +1,106 @@
+use serde_json::Value;
+
+use crate::pgn::PgnGame;
+
+enum GameSource {
+    Lichess(String),
+    ChessCom { id: String, daily: bool },
+}
+
+fn parse_source(input: &str) -> Result<GameSource, String> {
+    let input = input.trim();
+
+    let path = input
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    if segments.is_empty() {
+        return Err(String::from("Enter a game URL or ID"));
+    }
+
+    if segments[0].ends_with("chess.com") || (segments.len() == 1 && segments[0].chars().all(|c| c.is_ascii_digit())) {
+        let id = segments.last().unwrap();
+
+        if !id.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("'{}' is not a chess.com game URL", input));
+        }
+
+        return Ok(GameSource::ChessCom {
+            id: id.to_string(),
+            daily: segments.contains(&"daily"),
+        });
+    }
+
+    let id = if segments[0].ends_with("lichess.org") {
+        segments.get(1).ok_or(format!("'{}' is not a lichess game URL", input))?
+    } else {
+        segments[0]
+    };
+
+    //Player-specific URLs have 12 character IDs where the first 8 are the game ID
+    if (id.len() == 8 || id.len() == 12) && id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Ok(GameSource::Lichess(id[..8].to_string()))
+    } else {
+        Err(format!("'{}' is not a lichess or chess.com game", input))
+    }
+}
+
+fn get(url: &str) -> Result<ureq::Response, String> {
+    ureq::get(url).call().map_err(|e| match e {
+        ureq::Error::Status(404, _) => String::from("Game not found"),
+        e => e.to_string(),
+    })
+}
+
+fn fetch_lichess(id: &str) -> Result<String, String> {
+    get(&format!("https://lichess.org/game/export/{}?clocks=true&evals=true", id))?
+        .into_string()
+        .map_err(|e| e.to_string())
+}
+
+//chess.com has no single game PGN endpoint, so the game is looked up in the white player's monthly archive
+fn fetch_chess_com(id: &str, daily: bool) -> Result<String, String> {
+    let kind = if daily { "daily" } else { "live" };
+
+    let info: Value = get(&format!("https://www.chess.com/callback/{}/game/{}", kind, id))?
+        .into_json()
+        .map_err(|e| e.to_string())?;
+
+    let headers = &info["game"]["pgnHeaders"];
+    let white = headers["White"].as_str().ok_or("Could not read game players")?;
+    let date = headers["Date"].as_str().ok_or("Could not read game date")?;
+
+    let mut date_parts = date.split('.');
+    let year = date_parts.next().unwrap_or_default();
+    let month = date_parts.next().unwrap_or_default();
+
+    let archive: Value = get(&format!(
+        "https://api.chess.com/pub/player/{}/games/{}/{}",
+        white.to_lowercase(),
+        year,
+        month
+    ))?
+    .into_json()
+    .map_err(|e| e.to_string())?;
+
+    archive["games"]
+        .as_array()
+        .and_then(|games| games.iter().find(|g| g["url"].as_str().is_some_and(|url| url.ends_with(&format!("/{}", id)))))
+        .and_then(|game| game["pgn"].as_str())
+        .map(|pgn| pgn.to_string())
+        .ok_or(String::from("Game not found in the player's archive"))
+}
+
+pub fn fetch_game(input: &str) -> Result<PgnGame, String> {
+    let pgn = match parse_source(input)? {
+        GameSource::Lichess(id) => fetch_lichess(&id)?,
+        GameSource::ChessCom { id, daily } => fetch_chess_com(&id, daily)?,
+    };
+
+    PgnGame::parse(&pgn)
+}
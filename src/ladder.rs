@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::calibrate::RatingFile;
+use crate::cli;
+use crate::tournament::{append_pgn, bot_name, play_pairing, Pairing};
+
+#[derive(Debug, Clone)]
+pub struct LadderConfig {
+    pub bots: Vec<String>,
+    //Games in every match, alternating colors
+    pub games: usize,
+    //Times every challenger gets a shot, roughly
+    pub rounds: usize,
+    pub max_plies: usize,
+    pub pgn: Option<PathBuf>,
+    //File the history and reigns are written to at the end
+    pub log: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+struct Reign {
+    champion: String,
+    //Match the title was won in, 0 for the first champion
+    won: usize,
+    defenses: usize,
+    //Match the title was lost in
+    lost: Option<usize>,
+}
+
+//Plays a match and returns the champion's and the challenger's points
+fn play_match(config: &LadderConfig, champion: &str, challenger: &str, first_game: usize) -> Result<(f32, f32), String> {
+    let mut points = (0.0, 0.0);
+
+    for game in 0..config.games.max(1) {
+        //The challenger gets white first
+        let (white, black) = if game % 2 == 0 { (challenger, champion) } else { (champion, challenger) };
+
+        let pairing = Pairing {
+            index: first_game + game,
+            white: white.to_string(),
+            black: black.to_string(),
+        };
+
+        let result = play_pairing(&pairing, config.max_plies, cli::args().max_move_time, 0, &mut |_, _, _| {})?;
+
+        println!("  {} vs {} {} ({})", white, black, result.result, result.reason);
+
+        let (white_points, black_points) = result.points();
+
+        if white == champion {
+            points.0 += white_points;
+            points.1 += black_points;
+        } else {
+            points.0 += black_points;
+            points.1 += white_points;
+        }
+
+        if let Some(path) = &config.pgn {
+            let mut pgn = result.pgn();
+            pgn.set_tag("Event", "Chess Arena ladder");
+
+            append_pgn(path, &pgn);
+        }
+    }
+
+    Ok(points)
+}
+
+//Bots with how many times they held the title, how many matches they defended it in, their longest reign and their
+//rating from the last calibration
+fn summary(bots: &[String], reigns: &[Reign]) -> String {
+    let ratings = RatingFile::load().ratings;
+
+    let mut rows: Vec<(&String, usize, usize, usize)> = bots
+        .iter()
+        .map(|bot| {
+            let held: Vec<&Reign> = reigns.iter().filter(|reign| &reign.champion == bot).collect();
+
+            (
+                bot,
+                held.len(),
+                held.iter().map(|reign| reign.defenses).sum(),
+                held.iter().map(|reign| reign.defenses).max().unwrap_or(0),
+            )
+        })
+        .collect();
+
+    rows.sort_by(|a, b| b.2.cmp(&a.2).then(b.1.cmp(&a.1)));
+
+    let mut lines = vec![format!("{:<24} {:>6} {:>9} {:>8} {:>7}", "Bot", "Reigns", "Defenses", "Longest", "Rating")];
+
+    for (bot, held, defenses, longest) in rows {
+        let rating = ratings.get(bot).map_or(String::from("-"), |rating| format!("{:.0}", rating));
+        lines.push(format!("{:<24} {:>6} {:>9} {:>8} {:>7}", bot, held, defenses, longest, rating));
+    }
+
+    lines.join("\n")
+}
+
+//The first bot starts as champion and defends the title against the others in turn. A challenger has to score more
+//than half the points of a match to take over, and the old champion joins the back of the queue
+pub fn run(config: LadderConfig) -> Result<(), String> {
+    if config.bots.len() < 2 {
+        return Err(String::from("A ladder needs at least 2 bots"));
+    }
+
+    let bots = config.bots.iter().map(|bot| bot_name(bot).map(str::to_string)).collect::<Result<Vec<_>, String>>()?;
+
+    let mut champion = bots[0].clone();
+    let mut challengers: VecDeque<String> = bots[1..].iter().cloned().collect();
+
+    let mut reigns = vec![Reign {
+        champion: champion.clone(),
+        won: 0,
+        defenses: 0,
+        lost: None,
+    }];
+
+    let mut history = vec![format!("{} starts as champion", champion)];
+    println!("{}", history[0]);
+
+    let matches = config.rounds.max(1) * (bots.len() - 1);
+
+    for number in 1..=matches {
+        let challenger = challengers.pop_front().unwrap();
+
+        println!("Match {}/{}: {} defends against {}", number, matches, champion, challenger);
+
+        let (champion_points, challenger_points) = play_match(&config, &champion, &challenger, (number - 1) * config.games.max(1))?;
+        let reign = reigns.last_mut().unwrap();
+
+        let line = if challenger_points > champion_points {
+            reign.lost = Some(number);
+
+            let line = format!(
+                "Match {}: {} {} - {} {}, {} takes the title after a reign of {} defenses",
+                number, champion, champion_points, challenger_points, challenger, challenger, reign.defenses
+            );
+
+            reigns.push(Reign {
+                champion: challenger.clone(),
+                won: number,
+                defenses: 0,
+                lost: None,
+            });
+
+            challengers.push_back(std::mem::replace(&mut champion, challenger));
+
+            line
+        } else {
+            reign.defenses += 1;
+
+            let line = format!(
+                "Match {}: {} {} - {} {}, {} defends ({} in a row)",
+                number, champion, champion_points, challenger_points, challenger, champion, reign.defenses
+            );
+
+            challengers.push_back(challenger);
+
+            line
+        };
+
+        println!("{}", line);
+        history.push(line);
+    }
+
+    let mut report = vec![String::from("History"), history.join("\n"), String::new(), String::from("Reigns")];
+
+    for reign in reigns.iter() {
+        let end = match reign.lost {
+            Some(lost) => format!("lost in match {}", lost),
+            None => String::from("still champion"),
+        };
+
+        let start = match reign.won {
+            0 => String::from("from the start"),
+            won => format!("won in match {}", won),
+        };
+
+        report.push(format!("{:<24} {}, {} defenses, {}", reign.champion, start, reign.defenses, end));
+    }
+
+    report.push(String::new());
+    report.push(summary(&bots, &reigns));
+    report.push(String::new());
+    report.push(format!("Champion after {} matches: {}", matches, champion));
+
+    let report = report.join("\n");
+
+    println!();
+    println!("{}", report);
+
+    if let Some(path) = &config.log {
+        fs::write(path, format!("{}\n", report)).map_err(|e| format!("Could not write {:?}: {}", path, e))?;
+        println!("History saved to {:?}", path);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,222 @@
+use std::io::{BufRead, BufReader};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chess::{Board, ChessMove};
+use serde_json::Value;
+
+use crate::alg::chess_alg::ChessAlgorithm;
+use crate::alg::{find_player_type, PlayerTypeSupplier};
+use crate::clock::{self, ThinkTime, TimeManagerKind};
+use crate::gui::chess_display::PlayerType;
+
+const API_URL: &str = "https://lichess.org/api";
+
+#[derive(Clone)]
+struct LichessClient {
+    agent: ureq::Agent,
+    token: String,
+}
+
+impl LichessClient {
+    fn get(&self, path: &str) -> Result<ureq::Response, String> {
+        self.agent
+            .get(&format!("{}{}", API_URL, path))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .call()
+            .map_err(|e| e.to_string())
+    }
+
+    fn post(&self, path: &str) -> Result<ureq::Response, String> {
+        self.agent
+            .post(&format!("{}{}", API_URL, path))
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .call()
+            .map_err(|e| e.to_string())
+    }
+
+    //Calls `handler` for every event of an ndjson stream until it returns false or the stream ends
+    fn stream(&self, path: &str, mut handler: impl FnMut(Value) -> bool) -> Result<(), String> {
+        let reader = BufReader::new(self.get(path)?.into_reader());
+
+        for line in reader.lines() {
+            let line = line.map_err(|e| e.to_string())?;
+
+            //Empty lines are keep-alives
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str(&line) {
+                Ok(event) => {
+                    if !handler(event) {
+                        break;
+                    }
+                }
+                Err(e) => println!("Could not parse lichess event '{}': {}", line, e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn run(bot_name: &str, token: &str) -> Result<(), String> {
+    let (name, supplier) = find_player_type(bot_name).ok_or(format!("Unknown bot '{}'", bot_name))?;
+
+    if !supplier(chess::Color::White).is_computer() {
+        return Err(format!("'{}' is not a bot", name));
+    }
+
+    let client = LichessClient {
+        agent: ureq::AgentBuilder::new().build(),
+        token: token.to_string(),
+    };
+
+    let account: Value = client.get("/account")?.into_json().map_err(|e| e.to_string())?;
+    let account_id = account["id"].as_str().ok_or("Could not read account id")?.to_string();
+
+    println!("Playing on lichess as {} with {}", account_id, name);
+
+    client.stream("/stream/event", |event| {
+        match event["type"].as_str() {
+            Some("challenge") => {
+                let challenge = &event["challenge"];
+                let id = challenge["id"].as_str().unwrap_or_default();
+
+                //Variants and custom positions aren't supported
+                if challenge["variant"]["key"].as_str() == Some("standard") {
+                    println!("Accepting challenge {}", id);
+
+                    if let Err(e) = client.post(&format!("/challenge/{}/accept", id)) {
+                        println!("Could not accept challenge {}: {}", id, e);
+                    }
+                } else {
+                    println!("Declining challenge {}", id);
+
+                    let _ = client.post(&format!("/challenge/{}/decline", id));
+                }
+            }
+
+            Some("gameStart") => {
+                if let Some(game_id) = event["game"]["gameId"].as_str().or(event["game"]["id"].as_str()) {
+                    let client = client.clone();
+                    let account_id = account_id.clone();
+                    let game_id = game_id.to_string();
+                    let supplier = supplier.clone();
+
+                    thread::spawn(move || {
+                        if let Err(e) = play_game(&client, &account_id, &game_id, &supplier) {
+                            println!("Game {} failed: {}", game_id, e);
+                        }
+                    });
+                }
+            }
+
+            _ => {}
+        }
+
+        true
+    })
+}
+
+fn play_game(client: &LichessClient, account_id: &str, game_id: &str, supplier: &PlayerTypeSupplier) -> Result<(), String> {
+    let mut start = Board::default();
+    let mut engine: Option<(chess::Color, Arc<Mutex<dyn ChessAlgorithm>>)> = None;
+
+    println!("Starting game {}", game_id);
+
+    client.stream(&format!("/bot/game/stream/{}", game_id), |event| {
+        let state = match event["type"].as_str() {
+            Some("gameFull") => {
+                let color = if event["white"]["id"].as_str() == Some(account_id) {
+                    chess::Color::White
+                } else {
+                    chess::Color::Black
+                };
+
+                if let PlayerType::Computer(algorithm) = supplier(color) {
+                    engine = Some((color, algorithm));
+                }
+
+                if let Some(fen) = event["initialFen"].as_str() {
+                    if fen != "startpos" {
+                        start = Board::from_str(fen).unwrap_or_default();
+                    }
+                }
+
+                &event["state"]
+            }
+
+            Some("gameState") => &event,
+
+            _ => return true,
+        };
+
+        if state["status"].as_str() != Some("started") {
+            let status = state["status"].as_str().unwrap_or("unknown");
+            println!("Game {} ended: {}", game_id, status);
+
+            if let Some((_, algorithm)) = &engine {
+                let result = match state["winner"].as_str() {
+                    Some("white") => "1-0",
+                    Some("black") => "0-1",
+                    _ => "1/2-1/2",
+                };
+
+                algorithm.lock().unwrap().game_over(result, status);
+            }
+
+            return false;
+        }
+
+        let mut board = start;
+
+        for m in state["moves"].as_str().unwrap_or_default().split_whitespace() {
+            match ChessMove::from_str(m) {
+                Ok(m) => board = board.make_move_new(m),
+                Err(_) => {
+                    println!("Invalid move {} in game {}", m, game_id);
+                    return false;
+                }
+            }
+        }
+
+        if let Some((color, algorithm)) = &engine {
+            if board.side_to_move() == *color {
+                let (own, opponent) = match color {
+                    chess::Color::White => ("w", "b"),
+                    chess::Color::Black => ("b", "w"),
+                };
+
+                let ms = |key: String| Duration::from_millis(state[key].as_u64().unwrap_or(0));
+
+                //Correspondence games have no clock
+                let think_time = state[format!("{}time", own)].as_u64().map(|_| {
+                    let remaining = ms(format!("{}time", own));
+                    let increment = ms(format!("{}inc", own));
+
+                    ThinkTime {
+                        remaining,
+                        opponent_remaining: ms(format!("{}time", opponent)),
+                        increment,
+                        delay: Duration::ZERO,
+                        budget: TimeManagerKind::default().allocate(remaining, increment, 0),
+                    }
+                });
+
+                clock::set_think_time(think_time);
+
+                let m = algorithm.lock().unwrap().get_move(board);
+
+                if let Err(e) = client.post(&format!("/bot/game/{}/move/{}", game_id, m)) {
+                    println!("Could not play {} in game {}: {}", m, game_id, e);
+                }
+            }
+        }
+
+        true
+    })
+}
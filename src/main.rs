@@ -1,21 +1,456 @@
 pub mod gui;
 pub mod alg;
 pub mod util;
+pub mod pgn;
+pub mod cli;
+pub mod events;
+pub mod broadcast;
+pub mod api;
+pub mod lichess;
+pub mod import;
+pub mod net;
+pub mod variant;
+pub mod handicap;
+pub mod ruleset;
+pub mod settings;
+pub mod clock;
+pub mod speech;
+pub mod arena;
+pub mod evolve;
+pub mod train;
+pub mod tune;
+pub mod bench;
+pub mod observer;
+pub mod commentary;
+pub mod tournament;
+pub mod distributed;
+pub mod overlay;
+pub mod record;
+pub mod autosave;
+pub mod ladder;
+pub mod gauntlet;
+pub mod calibrate;
+pub mod game_controller;
+pub mod stats;
+pub mod campaign;
+pub mod achievements;
+pub mod daily;
+pub mod accuracy;
+pub mod report;
+pub mod progress;
+pub mod budget;
+pub mod divergence;
+pub mod mobility;
+pub mod watchdog;
+#[cfg(test)]
+mod harness;
 
 use std::sync::{Arc, Mutex};
 
 use alg::chess_alg::RandomChessAlgorithm;
 use ggez::{Context, ContextBuilder, GameResult};
 use ggez::graphics::{self, Color};
-use ggez::event::{self, EventHandler};
+use ggez::event::EventHandler;
 use gui::chess_display::{ChessDisplay, PlayerType};
-use ggez::conf::{WindowSetup, WindowMode};
-use gui::main_gui::MainGUI;
+use ggez::conf::{FullscreenType, WindowSetup, WindowMode};
+use ggez::winit::dpi::PhysicalPosition;
+use gui::main_gui::{MainGUI, StartScreen};
+use gui::watch_folder::FolderWatcher;
+
+fn default_concurrency() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
 
 fn main() {
+    //Parse arguments before opening the window so that --help and bad arguments exit early
+    let args = cli::args();
+
+    //Screen to open instead of the main menu
+    let mut start_screen = None;
+
+    //The broadcast and the API follow the game on screen, so commands that run without a window have nothing for them
+    let opens_window = matches!(
+        &args.command,
+        None | Some(cli::Command::Tournament { spectate: true, .. }) | Some(cli::Command::Replay { verify: false, .. }) | Some(cli::Command::Watch { .. })
+    );
+
+    if !opens_window && (args.broadcast.is_some() || args.api.is_some()) {
+        eprintln!("--broadcast and --api follow the game on screen, so they can't be used without a window. Tournaments can be watched with --spectate");
+        std::process::exit(1);
+    }
+
+    match &args.command {
+        Some(cli::Command::LichessBot { bot, token }) => {
+            if let Err(e) = lichess::run(bot, token) {
+                eprintln!("Lichess bot stopped: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Evolve { population, generations, max_plies, mutation, name, keep }) => {
+            let config = evolve::EvolveConfig {
+                population: *population,
+                generations: *generations,
+                max_plies: *max_plies,
+                mutation: *mutation,
+                name: name.clone(),
+                keep: *keep,
+            };
+
+            if let Err(e) = evolve::run(&config) {
+                eprintln!("Evolution failed: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Train { games, max_plies, learning_rate, epsilon, checkpoint_every, checkpoint_dir, register, name, resume }) => {
+            let config = train::TrainConfig {
+                games: *games,
+                max_plies: *max_plies,
+                learning_rate: *learning_rate,
+                epsilon: *epsilon,
+                checkpoint_every: *checkpoint_every,
+                checkpoint_dir: checkpoint_dir.clone(),
+                register: *register,
+                name: name.clone(),
+                resume: resume.clone(),
+            };
+
+            if let Err(e) = train::run(&config) {
+                eprintln!("Training failed: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::TrainPolicy { pgn, name, book_plies }) => {
+            match alg::policy::train(pgn, name, *book_plies) {
+                Ok(path) => println!("Saved {} to {:?}", name, path),
+                Err(e) => {
+                    eprintln!("Training failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Tune { pgn, epochs, learning_rate, skip_plies, output }) => {
+            let config = tune::TuneConfig {
+                pgn: pgn.clone(),
+                epochs: *epochs,
+                learning_rate: *learning_rate,
+                skip_plies: *skip_plies,
+                output: output.clone(),
+            };
+
+            if let Err(e) = tune::run(&config) {
+                eprintln!("Tuning failed: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Bench { bot, positions, repeat }) => {
+            if let Err(e) = bench::run(bot, positions.as_deref(), *repeat) {
+                eprintln!("Benchmark failed: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Tournament { bots, games, concurrency, max_plies, random_plies, stall_limit, pgn, spectate, switch_every, accuracy, accuracy_depth, report, eval_terms, eval_terms_json, budget, quiet, json_lines, constraints }) => {
+            let config = tournament::TournamentConfig {
+                bots: bots.clone(),
+                games: *games,
+                concurrency: concurrency.unwrap_or_else(default_concurrency).max(1),
+                max_plies: *max_plies,
+                pgn: pgn.clone(),
+                constraints: constraints.clone(),
+                eval_terms: *eval_terms,
+                eval_terms_json: eval_terms_json.clone(),
+                budget: *budget,
+                move_time: None,
+                random_plies: *random_plies,
+                stall_limit: *stall_limit,
+            };
+
+            if !spectate {
+                let output = if *quiet {
+                    progress::OutputMode::Quiet
+                } else if *json_lines {
+                    progress::OutputMode::JsonLines
+                } else {
+                    progress::OutputMode::Progress
+                };
+
+                let res = tournament::run(config, output).and_then(|tournament| {
+                    if let Some(dir) = report {
+                        report::write(&tournament, dir)?;
+                    }
+
+                    match accuracy {
+                        Some(reference) => accuracy::score_games(reference, *accuracy_depth, &tournament.scored_games()),
+                        None => Ok(()),
+                    }
+                });
+
+                if let Err(e) = res {
+                    eprintln!("Tournament failed: {}", e);
+                    std::process::exit(1);
+                }
+
+                return;
+            }
+
+            match tournament::Tournament::new(config) {
+                Ok(tournament) => {
+                    tournament.start();
+                    start_screen = Some(StartScreen::Spectate(tournament, std::time::Duration::from_secs(*switch_every)));
+                }
+                Err(e) => {
+                    eprintln!("Tournament failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Some(cli::Command::Coordinate { bots, games, local, max_plies, random_plies, stall_limit, pgn, port, constraints }) => {
+            let config = tournament::TournamentConfig {
+                bots: bots.clone(),
+                games: *games,
+                concurrency: *local,
+                max_plies: *max_plies,
+                pgn: pgn.clone(),
+                constraints: constraints.clone(),
+                eval_terms: false,
+                eval_terms_json: None,
+                budget: None,
+                move_time: None,
+                random_plies: *random_plies,
+                stall_limit: *stall_limit,
+            };
+
+            if let Err(e) = distributed::coordinate(config, *port) {
+                eprintln!("Tournament failed: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Worker { address, concurrency }) => {
+            if let Err(e) = distributed::run_worker(address, concurrency.unwrap_or_else(default_concurrency)) {
+                eprintln!("Worker stopped: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Ladder { bots, games, rounds, max_plies, pgn, log }) => {
+            let config = ladder::LadderConfig {
+                bots: bots.clone(),
+                games: *games,
+                rounds: *rounds,
+                max_plies: *max_plies,
+                pgn: pgn.clone(),
+                log: log.clone(),
+            };
+
+            if let Err(e) = ladder::run(config) {
+                eprintln!("Ladder failed: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Gauntlet { bot, engine, games, elo, step, max_plies, pgn }) => {
+            let config = gauntlet::GauntletConfig {
+                bot: bot.clone(),
+                engine: engine.clone(),
+                games: *games,
+                elo: *elo,
+                step: *step,
+                max_plies: *max_plies,
+                pgn: pgn.clone(),
+            };
+
+            if let Err(e) = gauntlet::run(config) {
+                eprintln!("Gauntlet failed: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Calibrate { bots, anchor, games, max_plies, pgn }) => {
+            let config = calibrate::CalibrateConfig {
+                bots: bots.clone(),
+                anchors: anchor.clone(),
+                games: *games,
+                max_plies: *max_plies,
+                pgn: pgn.clone(),
+            };
+
+            if let Err(e) = calibrate::run(config) {
+                eprintln!("Calibration failed: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Accuracy { pgn, reference, depth }) => {
+            if let Err(e) = accuracy::run(pgn, reference, *depth) {
+                eprintln!("Scoring failed: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Divergence { pgn, bot, games, max_plies }) => {
+            if let Err(e) = divergence::run(pgn.as_deref(), bot.as_deref(), *games, *max_plies) {
+                eprintln!("Divergence analysis failed: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::SavePreset { name, bot, depth, options, description }) => {
+            match alg::bot_preset::save_new(name, bot, *depth, options, description.as_deref()) {
+                Ok(path) => println!("Saved preset {} to {:?}", name, path),
+                Err(e) => {
+                    eprintln!("Could not save preset: {}", e);
+                    std::process::exit(1);
+                }
+            }
+
+            return;
+        }
+
+        Some(cli::Command::ImportPreset { file }) => {
+            match alg::bot_preset::import(file) {
+                Ok(path) => println!("Imported preset to {:?}", path),
+                Err(e) => {
+                    eprintln!("Could not import preset: {}", e);
+                    std::process::exit(1);
+                }
+            }
+
+            return;
+        }
+
+        Some(cli::Command::ExportPreset { name, file }) => {
+            if let Err(e) = alg::bot_preset::export(name, file) {
+                eprintln!("Could not export preset: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Stats) => {
+            if let Err(e) = stats::run() {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Record { white, black, output, seed, white_depth, black_depth, white_options, black_options, variant, max_plies }) => {
+            let config = |name: &String, depth: &Option<u32>, options: &Vec<(String, String)>| record::PlayerConfig {
+                name: name.clone(),
+                depth: *depth,
+                time_manager: Default::default(),
+                options: options.iter().cloned().collect(),
+            };
+
+            let (white, black) = (config(white, white_depth, white_options), config(black, black_depth, black_options));
+
+            if let Err(e) = record::record(&white, &black, *variant, *max_plies, *seed, output) {
+                eprintln!("Recording failed: {}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        }
+
+        Some(cli::Command::Replay { file, verify }) => {
+            if *verify {
+                if let Err(e) = record::verify(file) {
+                    eprintln!("Verification failed: {}", e);
+                    std::process::exit(1);
+                }
+
+                return;
+            }
+
+            match record::GameRecord::load(file).and_then(|record| record.pgn()) {
+                Ok(game) => start_screen = Some(StartScreen::Replay(game)),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Some(cli::Command::Watch { dir, hold, existing }) => {
+            if !dir.is_dir() {
+                eprintln!("{} is not a folder", dir.display());
+                std::process::exit(1);
+            }
+
+            let (hold, delay) = (std::time::Duration::from_secs(*hold), std::time::Duration::from_millis(args.replay_delay));
+            start_screen = Some(StartScreen::Watch(Box::new(FolderWatcher::new(dir.clone(), hold, delay, *existing))));
+        }
+
+        None => {}
+    }
+
+    if let Some(port) = args.broadcast {
+        broadcast::start(port).expect("Could not start broadcast server");
+    }
+
+    if args.overlay.is_some() || args.overlay_port.is_some() {
+        overlay::start(args.overlay.clone(), args.overlay_port, std::time::Duration::from_millis(args.overlay_interval)).expect("Could not start overlay");
+    }
+
+    if args.speak {
+        speech::start(args.speech_command.clone());
+    }
+
+    let api_requests = args.api.map(|port| api::start(port).expect("Could not start API server"));
+
+    let settings = settings::get();
+    let window = settings.window;
+
+    gui::scale::init_ui_scale(args.ui_scale.or(settings.ui_scale).unwrap_or(1.0));
+    gui::instant::set_instant(args.instant);
+    gui::theme::init_theme(settings.theme);
+    gui::palette::init_palette(settings.palette, settings.shape_markers);
+
+    let fullscreen_type = if window.fullscreen { FullscreenType::Desktop } else { FullscreenType::Windowed };
+
     let mut cb = ContextBuilder::new("chess_arena", "Salamander")
-        .window_setup(WindowSetup::default().title("Chess Arena"))
-        .window_mode(WindowMode::default().dimensions(800.0, 600.0).resizable(true));
+        .window_setup(WindowSetup::default().title("Chess Arena").vsync(!args.no_vsync && !args.instant))
+        .window_mode(
+            WindowMode::default()
+                .dimensions(window.width, window.height)
+                .resizable(true)
+                .fullscreen_type(fullscreen_type),
+        );
 
     if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
         let mut path = std::path::PathBuf::from(manifest_dir);
@@ -31,7 +466,14 @@ fn main() {
     let (mut ctx, event_loop) = cb.build()
         .expect("aieee, could not create ggez context!");
 
-    let gui = MainGUI::new(&mut ctx);
+    if let Some((x, y)) = window.position {
+        ctx.gfx.set_window_position(PhysicalPosition::new(x, y)).unwrap();
+    }
+
+    let gui = match start_screen {
+        Some(start) => MainGUI::starting_with(&mut ctx, api_requests, start),
+        None => MainGUI::new(&mut ctx, api_requests),
+    };
 
-    event::run(ctx, event_loop, gui);
+    gui::event_loop::run(ctx, event_loop, gui);
 }
\ No newline at end of file
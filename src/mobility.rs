@@ -0,0 +1,75 @@
+use chess::{Board, ChessMove, Color};
+
+use crate::variant::{Variant, VariantState};
+
+//How many legal moves each side had to choose from over a game, which tells bots that keep their options open from
+//ones that box themselves or their opponent in
+#[derive(Debug, Clone, Default)]
+pub struct Mobility {
+    //The side to move and its legal moves in the position before each move
+    plies: Vec<(Color, usize)>,
+}
+
+impl Mobility {
+    //Replays a finished game to count the moves at every ply
+    pub fn from_game(variant: Variant, start: Board, moves: &[ChessMove]) -> Mobility {
+        let rules = variant.rules();
+        let mut mobility = Mobility::default();
+
+        let mut board = start;
+        let mut state = VariantState::default();
+
+        for m in moves.iter() {
+            mobility.record(board.side_to_move(), rules.count_legal_moves(&board, &state));
+            (board, state) = rules.apply(&board, &state, *m);
+        }
+
+        mobility
+    }
+
+    pub fn record(&mut self, color: Color, moves: usize) {
+        self.plies.push((color, moves));
+    }
+
+    //Forgets plies that were taken back
+    pub fn truncate(&mut self, plies: usize) {
+        self.plies.truncate(plies);
+    }
+
+    pub fn clear(&mut self) {
+        self.plies.clear();
+    }
+
+    pub fn plies(&self) -> &[(Color, usize)] {
+        &self.plies
+    }
+
+    //The legal moves `color` had on each of its turns
+    pub fn curve(&self, color: Color) -> impl Iterator<Item = usize> + '_ {
+        self.plies.iter().filter(move |(side, _)| *side == color).map(|(_, moves)| *moves)
+    }
+
+    //The average branching factor of the positions `color` had to move in
+    pub fn average(&self, color: Color) -> Option<f32> {
+        let (total, turns) = self.curve(color).fold((0, 0), |(total, turns), moves| (total + moves, turns + 1));
+
+        (turns > 0).then(|| total as f32 / turns as f32)
+    }
+
+    pub fn fewest(&self, color: Color) -> Option<usize> {
+        self.curve(color).min()
+    }
+
+    pub fn most(&self, color: Color) -> Option<usize> {
+        self.curve(color).max()
+    }
+
+    pub fn summary(&self, color: Color) -> Option<String> {
+        Some(format!(
+            "{:.1} legal moves a turn on average, between {} and {}",
+            self.average(color)?,
+            self.fewest(color)?,
+            self.most(color)?
+        ))
+    }
+}
@@ -0,0 +1,290 @@
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::variant::Variant;
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    White,
+    Black,
+}
+
+impl From<chess::Color> for Side {
+    fn from(color: chess::Color) -> Self {
+        match color {
+            chess::Color::White => Side::White,
+            chess::Color::Black => Side::Black,
+        }
+    }
+}
+
+impl From<Side> for chess::Color {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::White => chess::Color::White,
+            Side::Black => chess::Color::Black,
+        }
+    }
+}
+
+//Messages are sent as one JSON object per line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NetMessage {
+    //Sent by the host to every new connection. `side` is the color the connection plays, or none for spectators
+    Welcome {
+        white: String,
+        black: String,
+        fen: String,
+        moves: Vec<String>,
+        side: Option<Side>,
+        #[serde(default)]
+        variant: Variant,
+    },
+    Move {
+        uci: String,
+    },
+    //Time used by each side so far, sent by the host after every move
+    Clock {
+        white_ms: u64,
+        black_ms: u64,
+    },
+    DrawOffer {
+        side: Side,
+    },
+    DrawAccept {
+        side: Side,
+    },
+    Resign {
+        side: Side,
+    },
+}
+
+#[derive(Debug)]
+pub enum NetEvent {
+    Connected(usize),
+    Message(usize, NetMessage),
+    Disconnected(usize),
+}
+
+#[derive(Debug)]
+enum Incoming {
+    Stream(TcpStream),
+    Message(usize, NetMessage),
+    Closed(usize),
+}
+
+#[derive(Debug)]
+struct Peer {
+    id: usize,
+    stream: TcpStream,
+    //Colors whose moves come from this peer
+    colors: Vec<chess::Color>,
+}
+
+#[derive(Debug)]
+pub struct NetSession {
+    host: bool,
+
+    peers: Vec<Peer>,
+    next_id: usize,
+
+    sender: Sender<Incoming>,
+    receiver: Receiver<Incoming>,
+
+    closed: Arc<AtomicBool>,
+}
+
+impl NetSession {
+    fn create(host: bool) -> Self {
+        let (sender, receiver) = channel();
+
+        NetSession {
+            host,
+
+            peers: vec![],
+            next_id: 0,
+
+            sender,
+            receiver,
+
+            closed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn host(port: u16) -> io::Result<NetSession> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        //Non-blocking so that the thread notices when the session is closed
+        listener.set_nonblocking(true)?;
+
+        let session = NetSession::create(true);
+
+        let sender = session.sender.clone();
+        let closed = session.closed.clone();
+
+        thread::spawn(move || {
+            while !closed.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, addr)) => {
+                        println!("Network connection from {}", addr);
+
+                        if stream.set_nonblocking(false).is_ok() && sender.send(Incoming::Stream(stream)).is_err() {
+                            break;
+                        }
+                    }
+
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(100)),
+
+                    Err(e) => println!("Could not accept network connection: {}", e),
+                }
+            }
+        });
+
+        println!("Hosting network game on port {}", port);
+
+        Ok(session)
+    }
+
+    //Connects to a host and waits for its welcome message
+    pub fn join(address: &str) -> Result<(NetSession, NetMessage), String> {
+        let address = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{}:{}", address, DEFAULT_PORT)
+        };
+
+        let stream = TcpStream::connect(&address).map_err(|e| format!("Could not connect to {}: {}", address, e))?;
+
+        let mut session = NetSession::create(false);
+        session.add_peer(stream);
+
+        match session.receiver.recv_timeout(Duration::from_secs(10)) {
+            Ok(Incoming::Message(_, welcome @ NetMessage::Welcome { .. })) => Ok((session, welcome)),
+            Ok(_) => Err(String::from("Host did not send a game")),
+            Err(_) => Err(String::from("Timed out waiting for the host")),
+        }
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.host
+    }
+
+    fn add_peer(&mut self, stream: TcpStream) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let reader = stream.try_clone().unwrap();
+        let sender = self.sender.clone();
+
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                match serde_json::from_str(&line) {
+                    Ok(message) => {
+                        if sender.send(Incoming::Message(id, message)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => println!("Invalid network message '{}': {}", line, e),
+                }
+            }
+
+            let _ = sender.send(Incoming::Closed(id));
+        });
+
+        self.peers.push(Peer {
+            id,
+            stream,
+            colors: vec![],
+        });
+
+        id
+    }
+
+    pub fn poll(&mut self) -> Vec<NetEvent> {
+        let mut events = vec![];
+
+        while let Ok(incoming) = self.receiver.try_recv() {
+            match incoming {
+                Incoming::Stream(stream) => events.push(NetEvent::Connected(self.add_peer(stream))),
+
+                Incoming::Message(id, message) => events.push(NetEvent::Message(id, message)),
+
+                Incoming::Closed(id) => {
+                    self.peers.retain(|p| p.id != id);
+                    events.push(NetEvent::Disconnected(id));
+                }
+            }
+        }
+
+        events
+    }
+
+    pub fn set_colors(&mut self, id: usize, colors: Vec<chess::Color>) {
+        if let Some(peer) = self.peers.iter_mut().find(|p| p.id == id) {
+            peer.colors = colors;
+        }
+    }
+
+    pub fn controls(&self, id: usize, color: chess::Color) -> bool {
+        self.peers.iter().any(|p| p.id == id && p.colors.contains(&color))
+    }
+
+    pub fn is_claimed(&self, color: chess::Color) -> bool {
+        self.peers.iter().any(|p| p.colors.contains(&color))
+    }
+
+    fn write(peer: &mut Peer, line: &str) {
+        if let Err(e) = peer.stream.write_all(line.as_bytes()) {
+            println!("Could not send to network peer {}: {}", peer.id, e);
+        }
+    }
+
+    pub fn send_to(&mut self, id: usize, message: &NetMessage) {
+        let line = format!("{}\n", serde_json::to_string(message).unwrap());
+
+        if let Some(peer) = self.peers.iter_mut().find(|p| p.id == id) {
+            NetSession::write(peer, &line);
+        }
+    }
+
+    pub fn send_all(&mut self, message: &NetMessage) {
+        let line = format!("{}\n", serde_json::to_string(message).unwrap());
+
+        for peer in self.peers.iter_mut() {
+            NetSession::write(peer, &line);
+        }
+    }
+
+    //Sends to every peer that isn't the source of `color`'s moves
+    pub fn send_except(&mut self, color: chess::Color, message: &NetMessage) {
+        let line = format!("{}\n", serde_json::to_string(message).unwrap());
+
+        for peer in self.peers.iter_mut().filter(|p| !p.colors.contains(&color)) {
+            NetSession::write(peer, &line);
+        }
+    }
+}
+
+impl Drop for NetSession {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+
+        for peer in self.peers.iter() {
+            let _ = peer.stream.shutdown(Shutdown::Both);
+        }
+    }
+}
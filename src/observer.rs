@@ -0,0 +1,215 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chess::{Board, ChessMove, MoveGen};
+
+use crate::alg::classic::ClassicParams;
+use crate::cli;
+
+//Deepest the built in observer looks. Every extra ply takes several times longer than the last
+const CLASSIC_MAX_DEPTH: u32 = 4;
+
+//What the observer thinks of a position
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub board: Board,
+    //Pawns from white's point of view
+    pub eval: f32,
+    //Best line for both sides, starting with the side to move
+    pub pv: Vec<ChessMove>,
+    pub depth: u32,
+}
+
+//An engine that watches games without playing in them
+pub trait Observer: Send {
+    fn analyse(&mut self, board: &Board, depth: u32) -> Option<Analysis>;
+
+    fn max_depth(&self) -> u32;
+}
+
+//Negamax over the Classic bot's evaluation, keeping track of the best line
+pub struct ClassicObserver {
+    params: ClassicParams,
+}
+
+impl ClassicObserver {
+    fn new() -> ClassicObserver {
+        ClassicObserver { params: ClassicParams::load() }
+    }
+
+    fn negamax(&self, board: &Board, depth: u32, mut alpha: f32, beta: f32, pv: &mut Vec<ChessMove>) -> f32 {
+        let mut moves: Vec<ChessMove> = MoveGen::new_legal(board).collect();
+
+        if depth == 0 || moves.is_empty() {
+            return self.params.evaluate(board, board.side_to_move());
+        }
+
+        //Captures first so that alpha-beta cuts more
+        moves.sort_by_key(|m| board.piece_on(m.get_dest()).is_none());
+
+        for m in moves {
+            let mut line = vec![];
+            let score = -self.negamax(&board.make_move_new(m), depth - 1, -beta, -alpha, &mut line);
+
+            if score >= beta {
+                return beta;
+            }
+
+            if score > alpha {
+                alpha = score;
+
+                pv.clear();
+                pv.push(m);
+                pv.extend(line);
+            }
+        }
+
+        alpha
+    }
+}
+
+impl Observer for ClassicObserver {
+    fn analyse(&mut self, board: &Board, depth: u32) -> Option<Analysis> {
+        let mut pv = vec![];
+        let score = self.negamax(board, depth, f32::NEG_INFINITY, f32::INFINITY, &mut pv);
+
+        Some(Analysis {
+            board: *board,
+            eval: if board.side_to_move() == chess::Color::White { score } else { -score },
+            pv,
+            depth,
+        })
+    }
+
+    fn max_depth(&self) -> u32 {
+        CLASSIC_MAX_DEPTH
+    }
+}
+
+//Longest prefix of `moves` that can be played from `board`, since engines sometimes send nonsense
+pub fn legal_line(board: &Board, moves: impl IntoIterator<Item = ChessMove>) -> Vec<ChessMove> {
+    let mut board = *board;
+    let mut line = vec![];
+
+    for m in moves {
+        if !board.legal(m) {
+            break;
+        }
+
+        line.push(m);
+        board = board.make_move_new(m);
+    }
+
+    line
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    //Position the game is at
+    position: Option<Board>,
+    analysis: Option<Analysis>,
+    stop: bool,
+}
+
+//Runs an observer on its own thread, deepening its analysis of the latest position until it changes. Stops when
+//dropped
+#[derive(Debug)]
+pub struct ObserverHandle {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl ObserverHandle {
+    pub fn start(mut observer: Box<dyn Observer>) -> ObserverHandle {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let thread_shared = shared.clone();
+
+        thread::spawn(move || {
+            let mut current: Option<Board> = None;
+            let mut depth = 1;
+
+            loop {
+                let position = {
+                    let shared = thread_shared.lock().unwrap();
+
+                    if shared.stop {
+                        break;
+                    }
+
+                    shared.position
+                };
+
+                let Some(board) = position else {
+                    thread::sleep(Duration::from_millis(50));
+                    continue;
+                };
+
+                if current != Some(board) {
+                    current = Some(board);
+                    depth = 1;
+                }
+
+                if depth > observer.max_depth() {
+                    thread::sleep(Duration::from_millis(50));
+                    continue;
+                }
+
+                if let Some(analysis) = observer.analyse(&board, depth) {
+                    let mut shared = thread_shared.lock().unwrap();
+
+                    //Results for a position that has moved on are thrown away
+                    if shared.position == Some(board) {
+                        shared.analysis = Some(analysis);
+                    }
+                }
+
+                depth += 1;
+            }
+        });
+
+        ObserverHandle { shared }
+    }
+
+    pub fn set_position(&self, board: Board) {
+        self.shared.lock().unwrap().position = Some(board);
+    }
+
+    //Latest analysis of the current position
+    pub fn analysis(&self) -> Option<Analysis> {
+        let shared = self.shared.lock().unwrap();
+
+        shared.analysis.clone().filter(|a| Some(a.board) == shared.position)
+    }
+}
+
+impl Drop for ObserverHandle {
+    fn drop(&mut self) {
+        self.shared.lock().unwrap().stop = true;
+    }
+}
+
+//Classic, or a UCI --engine
+pub fn by_name(name: &str) -> Result<Box<dyn Observer>, String> {
+    if name.eq_ignore_ascii_case("classic") {
+        return Ok(Box::new(ClassicObserver::new()));
+    }
+
+    let Some(spec) = cli::args().engines.iter().find(|e| e.name == name) else {
+        return Err(format!("Unknown engine '{}', expected Classic or the name of a UCI --engine", name));
+    };
+
+    Ok(Box::new(spec.observer()?))
+}
+
+//The observer picked with --observer, if any
+pub fn start_configured() -> Option<ObserverHandle> {
+    let name = cli::args().observer.as_deref()?;
+
+    match by_name(name) {
+        Ok(observer) => Some(ObserverHandle::start(observer)),
+        Err(e) => {
+            println!("Could not start observer {}: {}", name, e);
+            None
+        }
+    }
+}
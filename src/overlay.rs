@@ -0,0 +1,240 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::events::{self, GameEvent};
+use crate::pgn::format_clock;
+use crate::util::write_atomically;
+use crate::variant::Variant;
+
+//Commentary lines kept for the overlay
+const COMMENTARY_LINES: usize = 3;
+
+//What a streaming overlay shows about the game on screen
+#[derive(Debug, Clone, Default, Serialize)]
+struct Overlay {
+    white: String,
+    black: String,
+    variant: Variant,
+    fen: String,
+    ply: usize,
+    last_move: Option<String>,
+    last_move_uci: Option<String>,
+    //Time left in timed games, counting down for the side to move
+    white_ms: Option<u64>,
+    black_ms: Option<u64>,
+    white_clock: Option<String>,
+    black_clock: Option<String>,
+    //Observer eval in pawns from white's point of view
+    eval: Option<f32>,
+    depth: Option<u32>,
+    pv: Vec<String>,
+    result: Option<String>,
+    reason: Option<String>,
+    paused: bool,
+    commentary: Vec<String>,
+
+    //When the clocks were last sent
+    #[serde(skip)]
+    clock_at: Option<Instant>,
+}
+
+impl Overlay {
+    fn apply(&mut self, event: GameEvent) {
+        match event {
+            GameEvent::GameStarted { white, black, fen, variant } => {
+                *self = Overlay {
+                    white,
+                    black,
+                    variant,
+                    fen,
+                    ..Overlay::default()
+                };
+            }
+
+            GameEvent::Move { ply, san, uci, fen } => {
+                self.ply = ply;
+                self.last_move = Some(san);
+                self.last_move_uci = Some(uci);
+                self.fen = fen;
+
+                self.eval = None;
+                self.depth = None;
+                self.pv.clear();
+            }
+
+            GameEvent::GameOver { result, reason } => {
+                self.result = Some(result);
+                self.reason = Some(reason);
+            }
+
+            GameEvent::Takeback { ply, fen } => {
+                self.ply = ply;
+                self.last_move = None;
+                self.last_move_uci = None;
+                self.fen = fen;
+
+                self.result = None;
+                self.reason = None;
+                self.eval = None;
+                self.depth = None;
+                self.pv.clear();
+            }
+
+            GameEvent::Paused { paused } => self.paused = paused,
+
+            GameEvent::Clock { white_ms, black_ms } => {
+                self.white_ms = Some(white_ms);
+                self.black_ms = Some(black_ms);
+                self.clock_at = Some(Instant::now());
+            }
+
+            GameEvent::FlagFell { .. } | GameEvent::DrawOffered { .. } => {}
+
+            GameEvent::Commentary { text } => {
+                self.commentary.push(text);
+
+                if self.commentary.len() > COMMENTARY_LINES {
+                    self.commentary.remove(0);
+                }
+            }
+
+            GameEvent::Eval { eval, depth, pv } => {
+                self.eval = Some(eval);
+                self.depth = Some(depth);
+                self.pv = pv;
+            }
+        }
+    }
+
+    //Copy with the clock of the side to move run down to now
+    fn now(&self) -> Overlay {
+        let mut overlay = self.clone();
+
+        let white_to_move = self.fen.split_whitespace().nth(1) != Some("b");
+        let running = self.result.is_none() && !self.paused;
+
+        if let (Some(at), true) = (self.clock_at, running) {
+            let elapsed = at.elapsed().as_millis() as u64;
+            let ms = if white_to_move { &mut overlay.white_ms } else { &mut overlay.black_ms };
+
+            *ms = ms.map(|ms| ms.saturating_sub(elapsed));
+        }
+
+        overlay.white_clock = overlay.white_ms.map(|ms| format_clock(Duration::from_millis(ms)));
+        overlay.black_clock = overlay.black_ms.map(|ms| format_clock(Duration::from_millis(ms)));
+
+        overlay
+    }
+
+    fn text(&self) -> String {
+        let clock = |clock: &Option<String>| clock.as_ref().map(|c| format!(" ({})", c)).unwrap_or_default();
+
+        let mut lines = vec![format!("{}{} vs {}{}", self.white, clock(&self.white_clock), self.black, clock(&self.black_clock))];
+
+        if let Some(last_move) = &self.last_move {
+            let number = self.ply.div_ceil(2);
+            let dots = if !self.ply.is_multiple_of(2) { "." } else { "..." };
+
+            lines.push(format!("Last move: {}{} {}", number, dots, last_move));
+        }
+
+        if let Some(eval) = self.eval {
+            lines.push(format!("Eval: {:+.1} (depth {})", eval, self.depth.unwrap_or(0)));
+        }
+
+        if let Some(reason) = &self.reason {
+            lines.push(reason.clone());
+        } else if self.paused {
+            lines.push(String::from("Paused"));
+        }
+
+        lines.extend(self.commentary.iter().cloned());
+
+        lines.join("\n")
+    }
+}
+
+//JSON for .json files, text for anything else
+fn render(overlay: &Overlay, path: &Path) -> String {
+    if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("json")) {
+        serde_json::to_string_pretty(overlay).unwrap()
+    } else {
+        overlay.text()
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, overlay: &Mutex<Overlay>) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(stream.try_clone()?).read_line(&mut request_line)?;
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let overlay = overlay.lock().unwrap().now();
+
+    let (content_type, body) = if path.ends_with(".txt") {
+        ("text/plain; charset=utf-8", overlay.text())
+    } else {
+        ("application/json", serde_json::to_string(&overlay).unwrap())
+    };
+
+    //Browser sources load the overlay from another origin
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+//Keeps the state of the game on screen up to date from game events, writing it to `file` every `interval` and serving
+//it on `port` as JSON, or as text for paths ending in .txt
+pub fn start(file: Option<PathBuf>, port: Option<u16>, interval: Duration) -> std::io::Result<()> {
+    let overlay = Arc::new(Mutex::new(Overlay::default()));
+
+    let receiver = events::subscribe();
+
+    {
+        let overlay = overlay.clone();
+
+        thread::spawn(move || {
+            for event in receiver {
+                overlay.lock().unwrap().apply(event);
+            }
+        });
+    }
+
+    if let Some(port) = port {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        let overlay = overlay.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if let Err(e) = handle_connection(stream, &overlay) {
+                    println!("Overlay connection error: {}", e);
+                }
+            }
+        });
+
+        println!("Overlay served on http://127.0.0.1:{}/overlay.json and /overlay.txt", port);
+    }
+
+    if let Some(path) = file {
+        thread::spawn(move || loop {
+            let contents = render(&overlay.lock().unwrap().now(), &path);
+
+            if let Err(e) = write_atomically(&path, &contents) {
+                println!("Could not write overlay to {:?}: {}", path, e);
+            }
+
+            thread::sleep(interval);
+        });
+    }
+
+    Ok(())
+}
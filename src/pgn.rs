@@ -0,0 +1,473 @@
+use std::time::Duration;
+
+use chess::{Board, ChessMove, Color};
+use serde::{Deserialize, Serialize};
+
+use crate::util::move_to_SAN;
+use crate::variant::{self, Variant, VariantState};
+
+//Tags every PGN has, in the order they are written. The result comes after them
+const SEVEN_TAG_ROSTER: [&str; 6] = ["Event", "Site", "Date", "Round", "White", "Black"];
+
+//Mate in n moves is stored as an eval of this less n, so that quicker mates score higher. Bots that don't know how
+//far away the mate is use this as it is
+pub const MATE_EVAL: f32 = 1000.0;
+//Evals closer than this to MATE_EVAL are mates
+const MAX_MATE_MOVES: f32 = 500.0;
+
+//Eval of a mate score like the 3 or -2 of a UCI "mate 3" or a PGN "#-2", negative for the side getting mated
+pub fn mate_eval(mate: &str) -> Option<f32> {
+    let moves: i32 = mate.parse().ok()?;
+    let eval = MATE_EVAL - (moves.unsigned_abs() as f32).min(MAX_MATE_MOVES - 1.0);
+
+    Some(if mate.starts_with('-') { -eval } else { eval })
+}
+
+//Moves to the mate an eval stands for, if it is one
+fn mate_in(eval: f32) -> Option<i32> {
+    let moves = MATE_EVAL - eval.abs();
+
+    (moves < MAX_MATE_MOVES).then(|| (moves.round() as i32).max(1) * if eval < 0.0 { -1 } else { 1 })
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MoveAnnotation {
+    //Evaluation in pawns from white's point of view
+    pub eval: Option<f32>,
+    //Time left on the mover's clock after the move
+    pub clock: Option<Duration>,
+    //How long the mover thought about the move
+    pub move_time: Option<Duration>,
+}
+
+impl MoveAnnotation {
+    fn comment(&self) -> Option<String> {
+        let mut parts = vec![];
+
+        match (self.eval, self.eval.and_then(mate_in)) {
+            (_, Some(mate)) => parts.push(format!("[%eval #{}]", mate)),
+            (Some(eval), None) => parts.push(format!("[%eval {:.2}]", eval)),
+            _ => {}
+        }
+
+        if let Some(clock) = self.clock {
+            parts.push(format!("[%clk {}]", format_clock(clock)));
+        }
+
+        if let Some(move_time) = self.move_time {
+            parts.push(format!("[%emt {}]", format_move_time(move_time)));
+        }
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(format!("{{ {} }}", parts.join(" ")))
+        }
+    }
+}
+
+pub fn format_clock(time: Duration) -> String {
+    let secs = time.as_secs();
+
+    format!("{}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60)
+}
+
+//Like format_clock but with hundredths, since bots often move in well under a second
+pub fn format_move_time(time: Duration) -> String {
+    let secs = time.as_secs();
+
+    format!("{}:{:02}:{:05.2}", secs / 3600, (secs / 60) % 60, time.as_secs_f64() % 60.0)
+}
+
+//The tags of a game that can be edited before or after it. The names start out as the players' names in the bot list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameTags {
+    pub event: String,
+    pub site: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+}
+
+impl GameTags {
+    pub fn new(white: &str, black: &str) -> GameTags {
+        GameTags {
+            event: String::from("Chess Arena game"),
+            site: String::from("Chess Arena"),
+            round: String::from("-"),
+            white: white.to_string(),
+            black: black.to_string(),
+        }
+    }
+
+    //Missing tags are filled in with the usual placeholders
+    pub fn from_pgn(game: &PgnGame) -> GameTags {
+        let tag = |name: &str| game.get_tag(name).unwrap_or("?").to_string();
+
+        GameTags {
+            event: tag("Event"),
+            site: tag("Site"),
+            round: game.get_tag("Round").unwrap_or("-").to_string(),
+            white: tag("White"),
+            black: tag("Black"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub start: Board,
+    pub moves: Vec<ChessMove>,
+    pub annotations: Vec<MoveAnnotation>,
+    //Text written after a move whether or not the game is annotated, like the terms behind a bot's move
+    pub comments: Vec<Option<String>>,
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn new(start: Board) -> Self {
+        PgnGame {
+            tags: vec![],
+            start,
+            moves: vec![],
+            annotations: vec![],
+            comments: vec![],
+            result: String::from("*"),
+        }
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        if let Some(tag) = self.tags.iter_mut().find(|(n, _)| n == name) {
+            tag.1 = value.to_string();
+        } else {
+            self.tags.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.get_tag("Variant").and_then(Variant::from_name).unwrap_or_default()
+    }
+
+    pub fn to_pgn(&self, annotated: bool) -> String {
+        let mut res = String::new();
+        let mut push_tag = |name: &str, value: &str| {
+            res.push_str(&format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\"")));
+        };
+
+        //The Seven Tag Roster comes first and in its own order, with the result last
+        for name in SEVEN_TAG_ROSTER {
+            if let Some(value) = self.get_tag(name) {
+                push_tag(name, value);
+            }
+        }
+
+        push_tag("Result", &self.result);
+
+        for (name, value) in self.tags.iter().filter(|(name, _)| !SEVEN_TAG_ROSTER.contains(&name.as_str())) {
+            push_tag(name, value);
+        }
+
+        if self.start != Board::default() && self.get_tag("FEN").is_none() {
+            push_tag("SetUp", "1");
+            push_tag("FEN", &self.start.to_string());
+        }
+
+        res.push('\n');
+
+        let variant = self.variant();
+        let mut state = VariantState::default();
+
+        let mut tokens = vec![];
+        let mut board = self.start;
+        let mut move_number = 1;
+        //Black needs a move number if it is the first move or follows a comment
+        let mut needs_number = true;
+
+        for (i, m) in self.moves.iter().enumerate() {
+            if board.side_to_move() == Color::White {
+                tokens.push(format!("{}.", move_number));
+            } else if needs_number {
+                tokens.push(format!("{}...", move_number));
+            }
+
+            tokens.push(move_to_SAN(&board, *m));
+            needs_number = false;
+
+            if annotated {
+                if let Some(comment) = self.annotations.get(i).and_then(|a| a.comment()) {
+                    tokens.push(comment);
+                    needs_number = true;
+                }
+            }
+
+            if let Some(Some(comment)) = self.comments.get(i) {
+                tokens.push(format!("{{ {} }}", comment));
+                needs_number = true;
+            }
+
+            if board.side_to_move() == Color::Black {
+                move_number += 1;
+            }
+
+            (board, state) = variant.rules().apply(&board, &state, *m);
+        }
+
+        tokens.push(self.result.clone());
+
+        let mut line_len = 0;
+
+        for token in tokens {
+            if line_len > 0 && line_len + token.len() + 1 > 80 {
+                res.push('\n');
+                line_len = 0;
+            } else if line_len > 0 {
+                res.push(' ');
+                line_len += 1;
+            }
+
+            line_len += token.len();
+            res.push_str(&token);
+        }
+
+        res.push('\n');
+
+        res
+    }
+}
+
+fn parse_clock(text: &str) -> Option<Duration> {
+    let mut secs = 0.0;
+
+    for part in text.split(':') {
+        secs = secs * 60.0 + part.parse::<f64>().ok()?;
+    }
+
+    Some(Duration::from_secs_f64(secs))
+}
+
+fn parse_annotation(comment: &str, annotation: &mut MoveAnnotation) {
+    for command in comment.split('[').skip(1) {
+        let command = command.split(']').next().unwrap_or_default().trim();
+
+        if let Some(eval) = command.strip_prefix("%eval") {
+            let eval = eval.trim();
+
+            annotation.eval = if let Some(mate) = eval.strip_prefix('#') {
+                mate_eval(mate)
+            } else {
+                eval.parse().ok()
+            };
+        } else if let Some(clock) = command.strip_prefix("%clk") {
+            annotation.clock = parse_clock(clock.trim());
+        } else if let Some(move_time) = command.strip_prefix("%emt") {
+            annotation.move_time = parse_clock(move_time.trim());
+        }
+    }
+}
+
+pub fn parse_san(board: &Board, text: &str) -> Option<ChessMove> {
+    let text = text
+        .trim_end_matches(['+', '#', '!', '?'])
+        .replace('=', "")
+        .replace('0', "O");
+
+    if let Some((piece, square)) = text.split_once('@') {
+        return Some(variant::drop_move(variant::parse_piece(piece)?, square.parse().ok()?));
+    }
+
+    ChessMove::from_san(board, &text).ok()
+}
+
+impl PgnGame {
+    pub fn parse(text: &str) -> Result<PgnGame, String> {
+        PgnGame::parse_all(text)?.into_iter().next().ok_or(String::from("No game found in PGN"))
+    }
+
+    pub fn parse_all(text: &str) -> Result<Vec<PgnGame>, String> {
+        let mut games = vec![];
+
+        let mut tags: Vec<(String, String)> = vec![];
+        let mut game: Option<PgnGame> = None;
+        let mut board = Board::default();
+        let mut variant = Variant::Standard;
+        let mut state = VariantState::default();
+
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '[' if game.is_none() => {
+                    let tag: String = chars.by_ref().take_while(|c| *c != ']').collect();
+
+                    if let Some((name, value)) = tag.split_once(' ') {
+                        let value = value.trim().trim_matches('"').replace("\\\"", "\"").replace("\\\\", "\\");
+                        tags.push((name.to_string(), value));
+                    }
+                }
+
+                '{' => {
+                    let comment: String = chars.by_ref().take_while(|c| *c != '}').collect();
+
+                    if let Some(game) = game.as_mut() {
+                        if let Some(annotation) = game.annotations.last_mut() {
+                            parse_annotation(&comment, annotation);
+                        }
+                    }
+                }
+
+                ';' => {
+                    chars.by_ref().take_while(|c| *c != '\n').for_each(drop);
+                }
+
+                '(' => {
+                    //Variations are skipped
+                    let mut depth = 1;
+
+                    while let Some(c) = chars.next() {
+                        match c {
+                            '(' => depth += 1,
+                            ')' => depth -= 1,
+                            '{' => chars.by_ref().take_while(|c| *c != '}').for_each(drop),
+                            _ => {}
+                        }
+
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                }
+
+                c if c.is_whitespace() => {}
+
+                c => {
+                    let mut token = String::from(c);
+
+                    while let Some(c) = chars.peek() {
+                        if c.is_whitespace() || "{}();[".contains(*c) {
+                            break;
+                        }
+
+                        token.push(*c);
+                        chars.next();
+                    }
+
+                    let current = match game.as_mut() {
+                        Some(game) => game,
+                        None => {
+                            let mut start = Board::default();
+
+                            if let Some((_, fen)) = tags.iter().find(|(name, _)| name == "FEN") {
+                                start = fen.parse().map_err(|_| format!("Invalid FEN '{}'", fen))?;
+                            }
+
+                            board = start;
+                            state = VariantState::default();
+
+                            let mut new_game = PgnGame::new(start);
+
+                            if let Some((_, result)) = tags.iter().find(|(name, _)| name == "Result") {
+                                new_game.result = result.clone();
+                            }
+
+                            new_game.tags = std::mem::take(&mut tags)
+                                .into_iter()
+                                .filter(|(name, _)| name != "Result" && name != "SetUp" && name != "FEN")
+                                .collect();
+
+                            variant = new_game.variant();
+
+                            game.insert(new_game)
+                        }
+                    };
+
+                    if ["1-0", "0-1", "1/2-1/2", "*"].contains(&token.as_str()) {
+                        current.result = token;
+                        games.push(game.take().unwrap());
+                        continue;
+                    }
+
+                    //Move numbers and NAGs
+                    let token = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+                    if token.is_empty() || token.starts_with('$') {
+                        continue;
+                    }
+
+                    let m = parse_san(&board, token)
+                        .filter(|m| variant.rules().legal_moves(&board, &state).contains(m))
+                        .ok_or(format!("Illegal move '{}' in position {}", token, board))?;
+
+                    current.moves.push(m);
+                    current.annotations.push(MoveAnnotation::default());
+                    (board, state) = variant.rules().apply(&board, &state, m);
+                }
+            }
+        }
+
+        //A game without a result token
+        if let Some(game) = game {
+            games.push(game);
+        }
+
+        Ok(games)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn san(fen: &str, uci: &str) -> String {
+        move_to_SAN(&Board::from_str(fen).unwrap(), variant::parse_uci(uci).unwrap())
+    }
+
+    #[test]
+    fn en_passant_is_written_as_a_capture() {
+        assert_eq!(san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+    }
+
+    #[test]
+    fn promotions_are_not_disambiguated() {
+        assert_eq!(san("8/1P6/7k/8/8/8/8/4K3 w - - 0 1", "b7b8q"), "b8=Q");
+        assert_eq!(san("8/1P6/7k/8/8/8/8/4K3 w - - 0 1", "b7b8n"), "b8=N");
+    }
+
+    #[test]
+    fn capture_promotions_name_the_pawn_file() {
+        assert_eq!(san("r1r5/1P6/7k/8/8/8/8/4K3 w - - 0 1", "b7a8q"), "bxa8=Q");
+        assert_eq!(san("r1r5/1P6/7k/8/8/8/8/4K3 w - - 0 1", "b7c8r"), "bxc8=R");
+    }
+
+    #[test]
+    fn result_is_in_the_seven_tag_roster() {
+        let mut game = PgnGame::new(Board::from_str("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap());
+        game.set_tag("Annotator", "Chess Arena");
+        game.set_tag("White", "A");
+        game.set_tag("Event", "Test");
+
+        let pgn = game.to_pgn(false);
+        let tags: Vec<&str> = pgn.lines().take_while(|line| !line.is_empty()).map(|line| line.split(' ').next().unwrap()).collect();
+
+        assert_eq!(tags, ["[Event", "[White", "[Result", "[Annotator", "[SetUp", "[FEN"]);
+    }
+
+    #[test]
+    fn mates_are_written_as_mate_evals() {
+        let annotation = |eval| MoveAnnotation { eval: Some(eval), ..MoveAnnotation::default() };
+
+        assert_eq!(annotation(mate_eval("3").unwrap()).comment().unwrap(), "{ [%eval #3] }");
+        assert_eq!(annotation(-MATE_EVAL).comment().unwrap(), "{ [%eval #-1] }");
+        assert_eq!(annotation(1.5).comment().unwrap(), "{ [%eval 1.50] }");
+
+        let mut parsed = MoveAnnotation::default();
+        parse_annotation("[%eval #-4]", &mut parsed);
+        assert_eq!(parsed.eval.and_then(mate_in), Some(-4));
+    }
+}
@@ -0,0 +1,186 @@
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::pgn::format_clock;
+use crate::tournament::{GameResult, Tournament};
+
+//Width of the bar in characters
+const BAR_WIDTH: usize = 24;
+
+//Bots shown in the score summary next to the bar
+const SUMMARY_BOTS: usize = 3;
+
+//How a headless tournament reports what is going on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    //A line for every game, with a progress bar under them when writing to a terminal
+    #[default]
+    Progress,
+    //Only the final standings
+    Quiet,
+    //A JSON object per line for every game and the final standings, for scripts
+    JsonLines,
+}
+
+//Follows a tournament from the main thread while its games are played on others
+pub struct Progress {
+    mode: OutputMode,
+    started: Instant,
+    //Results already reported
+    reported: usize,
+    //Whether the bar is on the last line of the terminal
+    bar_shown: bool,
+}
+
+impl Progress {
+    pub fn new(mode: OutputMode) -> Progress {
+        Progress {
+            mode,
+            started: Instant::now(),
+            reported: 0,
+            bar_shown: false,
+        }
+    }
+
+    //From how long the games so far took on average
+    fn eta(&self, done: usize, total: usize) -> Option<Duration> {
+        (done > 0).then(|| self.started.elapsed().mul_f64((total - done.min(total)) as f64 / done as f64))
+    }
+
+    fn clear_bar(&mut self) {
+        if self.bar_shown {
+            eprint!("\r\x1b[K");
+            self.bar_shown = false;
+        }
+    }
+
+    //`done` counts the games over once this one is
+    fn report(&mut self, result: &GameResult, done: usize, tournament: &Tournament) {
+        let total = tournament.pairings.len();
+
+        match self.mode {
+            OutputMode::Quiet => {}
+
+            OutputMode::Progress => {
+                self.clear_bar();
+
+                println!(
+                    "Game {}/{}: {} vs {} {} ({}){}",
+                    result.pairing.index + 1,
+                    total,
+                    result.pairing.white,
+                    result.pairing.black,
+                    result.result,
+                    result.reason,
+                    result.copy_text()
+                );
+            }
+
+            OutputMode::JsonLines => {
+                let line = json!({
+                    "type": "game",
+                    "round": result.pairing.index + 1,
+                    "white": result.pairing.white,
+                    "black": result.pairing.black,
+                    "result": result.result,
+                    "reason": result.reason,
+                    "plies": result.moves.len(),
+                    "copy_of": result.copy_of.map(|index| index + 1),
+                    "completed": done,
+                    "total": total,
+                    "elapsed_secs": self.started.elapsed().as_secs_f64(),
+                    "eta_secs": self.eta(done, total).map(|eta| eta.as_secs_f64()),
+                });
+
+                println!("{}", line);
+            }
+        }
+    }
+
+    fn draw_bar(&mut self, tournament: &Tournament) {
+        let total = tournament.pairings.len();
+        let done = tournament.done().min(total);
+
+        let filled = BAR_WIDTH * done / total.max(1);
+        let summary = tournament
+            .standings()
+            .iter()
+            .take(SUMMARY_BOTS)
+            .map(|(bot, points, _)| format!("{} {}", bot, points))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let eta = self.eta(done, total).map_or(String::from("--:--:--"), format_clock);
+
+        eprint!("\r\x1b[K[{}{}] {}/{}  {}  ETA {}", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled), done, total, summary, eta);
+        let _ = std::io::stderr().flush();
+
+        self.bar_shown = true;
+    }
+
+    //Reports games that finished since the last call and redraws the bar
+    pub fn update(&mut self, tournament: &Tournament) {
+        let new: Vec<GameResult> = tournament.results.lock().unwrap()[self.reported..].to_vec();
+
+        for result in new.iter() {
+            self.reported += 1;
+            self.report(result, self.reported, tournament);
+        }
+
+        //Logs don't want a bar redrawn over and over
+        if self.mode == OutputMode::Progress && std::io::stderr().is_terminal() {
+            self.draw_bar(tournament);
+        }
+    }
+
+    pub fn finish(&mut self, tournament: &Tournament) {
+        self.update(tournament);
+        self.clear_bar();
+
+        match self.mode {
+            OutputMode::JsonLines => {
+                let standings: Vec<_> = tournament
+                    .standings()
+                    .into_iter()
+                    .map(|(bot, points, games)| json!({ "bot": bot, "points": points, "games": games }))
+                    .collect();
+
+                println!("{}", json!({ "type": "standings", "standings": standings, "elapsed_secs": self.started.elapsed().as_secs_f64() }));
+
+                if tournament.plan.is_some() {
+                    let margins: Vec<_> = tournament.rating_margins().into_iter().map(|(bot, margin)| json!({ "bot": bot, "elo_margin": margin })).collect();
+
+                    println!("{}", json!({ "type": "precision", "margins": margins, "unplayed": tournament.unplayed() }));
+                }
+            }
+
+            OutputMode::Progress => {
+                println!();
+                println!("Finished in {}", format_clock(self.started.elapsed()));
+                println!();
+                println!("{}", tournament.standings_text());
+
+                if tournament.plan.is_some() {
+                    print_precision(tournament);
+                }
+            }
+
+            OutputMode::Quiet => println!("{}", tournament.standings_text()),
+        }
+    }
+}
+
+//How much the ratings from a budgeted tournament can be trusted
+fn print_precision(tournament: &Tournament) {
+    println!();
+    println!("Rating precision (95%):");
+
+    for (bot, margin) in tournament.rating_margins() {
+        println!("  {}: ±{:.0} Elo", bot, margin);
+    }
+
+    if tournament.unplayed() > 0 {
+        println!("{} games were left out when the budget ran out", tournament.unplayed());
+    }
+}
@@ -0,0 +1,187 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use chess::{Board, Color};
+use serde::{Deserialize, Serialize};
+
+use crate::alg::bot_preset;
+use crate::alg::chess_alg::{seed_rng, ChessAlgorithm};
+use crate::alg::external::EngineOptions;
+use crate::alg::find_player_type;
+use crate::arena::play_game;
+use crate::clock::TimeManagerKind;
+use crate::gui::chess_display::PlayerType;
+use crate::pgn::PgnGame;
+use crate::util::{move_to_SAN, pgn_date};
+use crate::variant::{self, Variant};
+
+//Bumped when records stop being readable by older versions
+const RECORD_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerConfig {
+    //Name in the player list
+    pub name: String,
+    //Plies searched, for bots that search
+    #[serde(default)]
+    pub depth: Option<u32>,
+    //Recorded games are untimed, so this only says how the bot was set up
+    #[serde(default)]
+    pub time_manager: TimeManagerKind,
+    //The same options as the game creator's options editor, the epsilon and draw claims included
+    #[serde(default)]
+    pub options: EngineOptions,
+}
+
+impl PlayerConfig {
+    //The bot started with every option it was recorded with
+    pub fn bot(&self, color: Color) -> Result<Arc<Mutex<dyn ChessAlgorithm>>, String> {
+        let (name, supplier) = find_player_type(&self.name).ok_or(format!("Unknown bot '{}'", self.name))?;
+
+        match bot_preset::configure(name, self.depth, &self.options, &supplier, color) {
+            PlayerType::Computer(algorithm) => Ok(algorithm),
+            _ => Err(format!("{} is not a bot", name)),
+        }
+    }
+}
+
+//A bot game with everything needed to play it again the same way: the players, the options and the seed the bots'
+//random choices come from. Saved as JSON, usually with a .bcr extension
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub version: u32,
+    pub white: PlayerConfig,
+    pub black: PlayerConfig,
+    #[serde(default)]
+    pub variant: Variant,
+    pub start_fen: String,
+    pub max_plies: usize,
+    pub seed: u64,
+    pub moves: Vec<String>,
+    pub result: String,
+    pub reason: String,
+    pub date: String,
+}
+
+impl GameRecord {
+    pub fn load(path: &Path) -> Result<GameRecord, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+        let record: GameRecord = serde_json::from_str(&text).map_err(|e| format!("Invalid replay file {:?}: {}", path, e))?;
+
+        if record.version > RECORD_VERSION {
+            return Err(format!("{:?} was written by a newer version (format {})", path, record.version));
+        }
+
+        Ok(record)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap()).map_err(|e| format!("Could not write {:?}: {}", path, e))
+    }
+
+    pub fn start_board(&self) -> Result<Board, String> {
+        Board::from_str(&self.start_fen).map_err(|_| format!("Invalid start position '{}'", self.start_fen))
+    }
+
+    pub fn pgn(&self) -> Result<PgnGame, String> {
+        let mut game = PgnGame::new(self.start_board()?);
+
+        game.set_tag("Event", "Chess Arena replay");
+        game.set_tag("Date", &self.date);
+        game.set_tag("White", &self.white.name);
+        game.set_tag("Black", &self.black.name);
+        game.set_tag("Termination", &self.reason);
+
+        if self.variant != Variant::Standard {
+            game.set_tag("Variant", self.variant.name());
+        }
+
+        game.moves = self.moves.iter().map(String::as_str).filter_map(variant::parse_uci).collect();
+        game.result = self.result.clone();
+
+        Ok(game)
+    }
+}
+
+//Plays a game on this thread with the bots' randomness seeded with `seed`
+pub fn play(white: &PlayerConfig, black: &PlayerConfig, variant: Variant, start: Board, max_plies: usize, seed: u64) -> Result<GameRecord, String> {
+    let white_bot = white.bot(Color::White)?;
+    let black_bot = black.bot(Color::Black)?;
+
+    seed_rng(seed);
+
+    let game = play_game(
+        &mut *white_bot.lock().unwrap(),
+        &mut *black_bot.lock().unwrap(),
+        variant,
+        start,
+        max_plies,
+    );
+
+    Ok(GameRecord {
+        version: RECORD_VERSION,
+        white: white.clone(),
+        black: black.clone(),
+        variant,
+        start_fen: start.to_string(),
+        max_plies,
+        seed,
+        moves: game.moves.into_iter().map(variant::move_to_uci).collect(),
+        result: game.outcome.pgn_result().to_string(),
+        reason: game.outcome.get_text().to_string(),
+        date: pgn_date(),
+    })
+}
+
+pub fn record(white: &PlayerConfig, black: &PlayerConfig, variant: Variant, max_plies: usize, seed: Option<u64>, output: &Path) -> Result<(), String> {
+    let seed = seed.unwrap_or_else(rand::random);
+
+    let record = play(white, black, variant, Board::default(), max_plies, seed)?;
+    record.save(output)?;
+
+    println!("{} vs {}: {} ({}) in {} plies, seed {}", white.name, black.name, record.result, record.reason, record.moves.len(), seed);
+    println!("Saved to {:?}", output);
+
+    Ok(())
+}
+
+//Plays the recorded game again and checks every move matches
+pub fn verify(path: &Path) -> Result<(), String> {
+    let record = GameRecord::load(path)?;
+    let start = record.start_board()?;
+
+    println!("Replaying {} vs {} with seed {}", record.white.name, record.black.name, record.seed);
+
+    let replayed = play(&record.white, &record.black, record.variant, start, record.max_plies, record.seed)?;
+
+    let mismatch = (0..record.moves.len().max(replayed.moves.len())).find(|i| record.moves.get(*i) != replayed.moves.get(*i));
+
+    let Some(ply) = mismatch else {
+        println!("All {} moves and the result ({}) match", record.moves.len(), record.result);
+        return Ok(());
+    };
+
+    //Positions before the first difference are the same in both games
+    let rules = record.variant.rules();
+    let mut board = start;
+    let mut state = Default::default();
+
+    for m in record.moves[..ply].iter().filter_map(|uci| variant::parse_uci(uci)) {
+        (board, state) = rules.apply(&board, &state, m);
+    }
+
+    let describe = |uci: Option<&String>| match uci.and_then(|uci| variant::parse_uci(uci)) {
+        Some(m) => move_to_SAN(&board, m),
+        None => String::from("nothing (the game was over)"),
+    };
+
+    Err(format!(
+        "Moves differ at ply {} ({}): the record has {} but the bots played {}",
+        ply + 1,
+        board,
+        describe(record.moves.get(ply)),
+        describe(replayed.moves.get(ply))
+    ))
+}
@@ -0,0 +1,345 @@
+use std::fs;
+use std::path::Path;
+
+use chess::{Board, Color};
+
+use crate::mobility::Mobility;
+use crate::tournament::{GameResult, Tournament};
+use crate::variant::Variant;
+use crate::util::pgn_date;
+
+//Ratings everyone starts the tournament on, and how far one game moves them
+const START_RATING: f32 = 1500.0;
+const K_FACTOR: f32 = 32.0;
+
+//A table written out as Markdown or HTML
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+//A cell that links to a file in the report directory
+fn link(text: &str, path: &str) -> String {
+    format!("[{}]({})", text, path)
+}
+
+impl Table {
+    fn new(headers: &[&str]) -> Table {
+        Table {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: vec![],
+        }
+    }
+
+    fn markdown(&self) -> String {
+        let mut lines = vec![
+            format!("| {} |", self.headers.join(" | ")),
+            format!("|{}", "---|".repeat(self.headers.len())),
+        ];
+
+        for row in self.rows.iter() {
+            lines.push(format!("| {} |", row.join(" | ")));
+        }
+
+        lines.join("\n")
+    }
+
+    fn html(&self) -> String {
+        let cell = |tag: &str, text: &str| format!("<{}>{}</{}>", tag, html_cell(text), tag);
+
+        let mut lines = vec![String::from("<table>")];
+        lines.push(format!("<tr>{}</tr>", self.headers.iter().map(|h| cell("th", h)).collect::<String>()));
+
+        for row in self.rows.iter() {
+            lines.push(format!("<tr>{}</tr>", row.iter().map(|c| cell("td", c)).collect::<String>()));
+        }
+
+        lines.push(String::from("</table>"));
+        lines.join("\n")
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+//Escapes a cell, turning a Markdown link into an HTML one
+fn html_cell(text: &str) -> String {
+    let parsed = text
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(')'))
+        .and_then(|rest| rest.split_once("]("));
+
+    match parsed {
+        Some((text, path)) => format!("<a href=\"{}\">{}</a>", escape_html(path), escape_html(text)),
+        None => escape_html(text),
+    }
+}
+
+fn game_file(result: &GameResult) -> String {
+    format!("games/game-{:03}.pgn", result.pairing.index + 1)
+}
+
+fn game_title(result: &GameResult) -> String {
+    format!("{} vs {} {}", result.pairing.white, result.pairing.black, result.result)
+}
+
+//Elo ratings after every game in schedule order, with everyone starting on the same rating
+fn ratings(bots: &[String], results: &[GameResult]) -> Vec<f32> {
+    let mut ratings = vec![START_RATING; bots.len()];
+    let index = |name: &str| bots.iter().position(|bot| bot == name);
+
+    for result in results.iter() {
+        let (Some(white), Some(black)) = (index(&result.pairing.white), index(&result.pairing.black)) else {
+            continue;
+        };
+
+        let expected = 1.0 / (1.0 + 10f32.powf((ratings[black] - ratings[white]) / 400.0));
+        let change = K_FACTOR * (result.points().0 - expected);
+
+        ratings[white] += change;
+        ratings[black] -= change;
+    }
+
+    ratings
+}
+
+//Points `bot` scored against `opponent`, and games between them
+fn head_to_head(results: &[GameResult], bot: &str, opponent: &str) -> (f32, usize) {
+    results.iter().fold((0.0, 0), |(points, games), result| {
+        let (white_points, black_points) = result.points();
+
+        if result.pairing.white == bot && result.pairing.black == opponent {
+            (points + white_points, games + 1)
+        } else if result.pairing.black == bot && result.pairing.white == opponent {
+            (points + black_points, games + 1)
+        } else {
+            (points, games)
+        }
+    })
+}
+
+//Finished games worth a look, with why
+fn notable_games(results: &[GameResult]) -> Vec<(&'static str, &GameResult)> {
+    let mut notable = vec![];
+
+    if let Some(mate) = results.iter().filter(|r| r.reason.contains("checkmate")).min_by_key(|r| r.moves.len()) {
+        notable.push(("Shortest mate", mate));
+    }
+
+    if let Some(longest) = results.iter().max_by_key(|r| r.moves.len()) {
+        notable.push(("Longest game", longest));
+    }
+
+    //Usually the shortest mate too, in which case it is only listed once
+    if let Some(shortest) = results.iter().filter(|r| r.result != "1/2-1/2").min_by_key(|r| r.moves.len()) {
+        if notable.iter().all(|(_, game)| game.pairing.index != shortest.pairing.index) {
+            notable.push(("Shortest decisive game", shortest));
+        }
+    }
+
+    notable
+}
+
+//Where a bot's turns fall when its mobility over a game is split into the opening, middlegame and endgame
+const MOBILITY_PHASES: [(usize, usize); 3] = [(1, 10), (11, 30), (31, usize::MAX)];
+
+fn average(counts: &[usize]) -> String {
+    if counts.is_empty() {
+        return String::from("-");
+    }
+
+    format!("{:.1}", counts.iter().sum::<usize>() as f32 / counts.len() as f32)
+}
+
+//Legal moves `bot` had on its turns, by move number, and the legal moves it left its opponents
+fn mobility(results: &[GameResult], bot: &str) -> (Vec<(usize, usize)>, Vec<usize>) {
+    let mut own = vec![];
+    let mut opponents = vec![];
+
+    for result in results.iter() {
+        let color = if result.pairing.white == bot {
+            Color::White
+        } else if result.pairing.black == bot {
+            Color::Black
+        } else {
+            continue;
+        };
+
+        let mobility = Mobility::from_game(Variant::Standard, Board::default(), &result.moves);
+
+        for (ply, (side, moves)) in mobility.plies().iter().enumerate() {
+            if *side == color {
+                own.push((ply / 2 + 1, *moves));
+            } else {
+                opponents.push(*moves);
+            }
+        }
+    }
+
+    (own, opponents)
+}
+
+//Writes report.md and report.html to `dir` with the standings, a crosstable, rating changes and notable games,
+//and every game as PGN under games/ for the reports to link to
+pub fn write(tournament: &Tournament, dir: &Path) -> Result<(), String> {
+    let error = |e: std::io::Error| format!("Could not write the report to {:?}: {}", dir, e);
+
+    fs::create_dir_all(dir.join("games")).map_err(error)?;
+
+    let mut results = tournament.results.lock().unwrap().clone();
+    results.sort_by_key(|result| result.pairing.index);
+
+    let mut all_games = String::new();
+
+    for result in results.iter() {
+        let pgn = result.pgn().to_pgn(false);
+
+        fs::write(dir.join(game_file(result)), &pgn).map_err(error)?;
+        all_games.push_str(&pgn);
+        all_games.push('\n');
+    }
+
+    fs::write(dir.join("games/all.pgn"), all_games).map_err(error)?;
+
+    let bots = &tournament.config.bots;
+    let standings = tournament.standings();
+    let final_ratings = ratings(bots, &results);
+    let rating = |name: &str| bots.iter().position(|bot| bot == name).map_or(START_RATING, |i| final_ratings[i]);
+
+    let mut standings_table = Table::new(&["#", "Bot", "Points", "Games", "Score"]);
+
+    for (i, (bot, points, games)) in standings.iter().enumerate() {
+        standings_table.rows.push(vec![
+            (i + 1).to_string(),
+            bot.clone(),
+            format!("{:.1}", points),
+            games.to_string(),
+            format!("{:.0}%", 100.0 * points / (*games).max(1) as f32),
+        ]);
+    }
+
+    //Opponents are numbered by their place in the standings
+    let mut crosstable_headers = vec!["#", "Bot"];
+    let numbers: Vec<String> = (1..=standings.len()).map(|n| n.to_string()).collect();
+    crosstable_headers.extend(numbers.iter().map(String::as_str));
+
+    let mut crosstable = Table::new(&crosstable_headers);
+
+    for (i, (bot, _, _)) in standings.iter().enumerate() {
+        let mut row = vec![(i + 1).to_string(), bot.clone()];
+
+        for (opponent, _, _) in standings.iter() {
+            row.push(match head_to_head(&results, bot, opponent) {
+                _ if opponent == bot => String::from("-"),
+                (_, 0) => String::new(),
+                (points, games) => format!("{:.1}/{}", points, games),
+            });
+        }
+
+        crosstable.rows.push(row);
+    }
+
+    let mut ratings_table = Table::new(&["Bot", "Start", "End", "Change"]);
+
+    for (bot, _, _) in standings.iter() {
+        let end = rating(bot);
+
+        ratings_table.rows.push(vec![
+            bot.clone(),
+            format!("{:.0}", START_RATING),
+            format!("{:.0}", end),
+            format!("{:+.0}", end - START_RATING),
+        ]);
+    }
+
+    let mut mobility_table = Table::new(&["Bot", "Own moves", "Opponents' moves", "Moves 1-10", "Moves 11-30", "Moves 31+", "Fewest"]);
+
+    for (bot, _, _) in standings.iter() {
+        let (own, opponents) = mobility(&results, bot);
+        let counts: Vec<usize> = own.iter().map(|(_, moves)| *moves).collect();
+
+        let mut row = vec![bot.clone(), average(&counts), average(&opponents)];
+
+        for (first, last) in MOBILITY_PHASES {
+            let phase: Vec<usize> = own.iter().filter(|(number, _)| (first..=last).contains(number)).map(|(_, moves)| *moves).collect();
+            row.push(average(&phase));
+        }
+
+        row.push(counts.iter().min().map_or(String::from("-"), |fewest| fewest.to_string()));
+
+        mobility_table.rows.push(row);
+    }
+
+    let mut notable_table = Table::new(&["", "Game", "Moves", "Result"]);
+
+    for (why, result) in notable_games(&results) {
+        notable_table.rows.push(vec![
+            why.to_string(),
+            link(&game_title(result), &game_file(result)),
+            result.moves.len().div_ceil(2).to_string(),
+            result.reason.clone(),
+        ]);
+    }
+
+    let mut games_table = Table::new(&["Round", "White", "Black", "Result", "Moves", "Termination"]);
+
+    for result in results.iter() {
+        games_table.rows.push(vec![
+            link(&(result.pairing.index + 1).to_string(), &game_file(result)),
+            result.pairing.white.clone(),
+            result.pairing.black.clone(),
+            result.result.clone(),
+            result.moves.len().div_ceil(2).to_string(),
+            result.reason.clone(),
+        ]);
+    }
+
+    let title = format!("Chess Arena tournament, {}", pgn_date());
+    let summary = format!(
+        "{} bots, {} of {} games played. Ratings start at {} and change by up to {} a game.",
+        bots.len(),
+        results.len(),
+        tournament.pairings.len(),
+        START_RATING,
+        K_FACTOR
+    );
+    let download = link("Download every game", "games/all.pgn");
+
+    let sections = [
+        ("Standings", standings_table),
+        ("Crosstable", crosstable),
+        ("Rating changes", ratings_table),
+        ("Mobility", mobility_table),
+        ("Notable games", notable_table),
+        ("Games", games_table),
+    ];
+
+    let mut markdown = vec![format!("# {}", title), summary.clone(), download.clone()];
+    let mut html = vec![
+        String::from("<!DOCTYPE html>"),
+        format!("<html><head><meta charset=\"utf-8\"><title>{}</title>", escape_html(&title)),
+        String::from("<style>body { font-family: sans-serif; } table { border-collapse: collapse; } th, td { border: 1px solid #999; padding: 2px 8px; }</style>"),
+        String::from("</head><body>"),
+        format!("<h1>{}</h1>", escape_html(&title)),
+        format!("<p>{}</p>", escape_html(&summary)),
+        format!("<p>{}</p>", html_cell(&download)),
+    ];
+
+    for (heading, table) in sections.iter() {
+        markdown.push(format!("## {}", heading));
+        markdown.push(table.markdown());
+
+        html.push(format!("<h2>{}</h2>", heading));
+        html.push(table.html());
+    }
+
+    html.push(String::from("</body></html>"));
+
+    fs::write(dir.join("report.md"), markdown.join("\n\n") + "\n").map_err(error)?;
+    fs::write(dir.join("report.html"), html.join("\n") + "\n").map_err(error)?;
+
+    println!("Report written to {:?}", dir.join("report.html"));
+
+    Ok(())
+}
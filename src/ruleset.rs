@@ -0,0 +1,277 @@
+use chess::{BitBoard, Board, ChessMove, Color, MoveGen, Piece, ALL_SQUARES, EMPTY};
+
+use crate::gui::chess_display::GameOutcome;
+use crate::variant::{self, VariantState, POCKET_PIECES};
+
+//Everything that differs between the variants when playing a game
+pub trait Ruleset: Sync {
+    //Every legal move without collecting them, for bots that look at a lot of positions
+    fn for_each_legal_move(&self, board: &Board, _state: &VariantState, f: &mut dyn FnMut(ChessMove)) {
+        for m in MoveGen::new_legal(board) {
+            f(m);
+        }
+    }
+
+    fn legal_moves(&self, board: &Board, state: &VariantState) -> Vec<ChessMove> {
+        let mut moves = Vec::new();
+        self.for_each_legal_move(board, state, &mut |m| moves.push(m));
+        moves
+    }
+
+    fn count_legal_moves(&self, board: &Board, state: &VariantState) -> usize {
+        let mut count = 0;
+        self.for_each_legal_move(board, state, &mut |_| count += 1);
+        count
+    }
+
+    fn apply(&self, board: &Board, state: &VariantState, m: ChessMove) -> (Board, VariantState) {
+        (board.make_move_new(m), *state)
+    }
+
+    //Endings that don't depend on the side to move running out of moves
+    fn terminal(&self, _board: &Board, _state: &VariantState) -> Option<GameOutcome> {
+        None
+    }
+
+    //Result when the side to move has no legal moves
+    fn no_moves(&self, board: &Board) -> GameOutcome {
+        if *board.checkers() != EMPTY {
+            GameOutcome::Checkmate(!board.side_to_move())
+        } else {
+            GameOutcome::Stalemate
+        }
+    }
+
+    fn outcome(&self, board: &Board, state: &VariantState) -> Option<GameOutcome> {
+        if self.count_legal_moves(board, state) == 0 {
+            Some(self.no_moves(board))
+        } else {
+            self.terminal(board, state)
+        }
+    }
+
+    //Squares `color` is allowed to see
+    fn visible_squares(&self, _board: &Board, _color: Color) -> BitBoard {
+        !EMPTY
+    }
+
+    //Board given to bots playing `color`
+    fn player_view(&self, board: &Board, _color: Color) -> Board {
+        *board
+    }
+
+    fn has_pockets(&self) -> bool {
+        false
+    }
+
+    //Result when `flagged` runs out of time. The opponent only wins if they could still checkmate somehow
+    fn flag_outcome(&self, board: &Board, _state: &VariantState, flagged: Color) -> GameOutcome {
+        if can_mate(board, !flagged) {
+            GameOutcome::Timeout(!flagged)
+        } else {
+            GameOutcome::TimeoutVsInsufficientMaterial
+        }
+    }
+}
+
+pub struct StandardRules;
+
+impl Ruleset for StandardRules {
+    fn legal_moves(&self, board: &Board, _state: &VariantState) -> Vec<ChessMove> {
+        MoveGen::new_legal(board).collect()
+    }
+
+    //Counted from the move generator's bitboards without making any moves
+    fn count_legal_moves(&self, board: &Board, _state: &VariantState) -> usize {
+        MoveGen::new_legal(board).len()
+    }
+
+    fn terminal(&self, board: &Board, _state: &VariantState) -> Option<GameOutcome> {
+        is_insufficient_material(board).then_some(GameOutcome::InsufficientMaterial)
+    }
+}
+
+pub struct RoyalLosingChessRules;
+
+impl Ruleset for RoyalLosingChessRules {
+    //Captures are compulsory, so the moves are gone through twice rather than collected
+    fn for_each_legal_move(&self, board: &Board, _state: &VariantState, f: &mut dyn FnMut(ChessMove)) {
+        let moves = || MoveGen::new_legal(board).filter(|m| !variant::is_castle(board, *m));
+        let must_capture = moves().any(|m| variant::is_capture(board, m));
+
+        for m in moves().filter(|m| !must_capture || variant::is_capture(board, *m)) {
+            f(m);
+        }
+    }
+
+    fn terminal(&self, board: &Board, _state: &VariantState) -> Option<GameOutcome> {
+        let color = board.side_to_move();
+
+        (*board.color_combined(color) == *board.pieces(Piece::King) & *board.color_combined(color))
+            .then_some(GameOutcome::LostAllPieces(color))
+    }
+
+    fn no_moves(&self, board: &Board) -> GameOutcome {
+        GameOutcome::NoMoves(board.side_to_move())
+    }
+
+    //Nobody needs mating material to win by losing pieces
+    fn flag_outcome(&self, _board: &Board, _state: &VariantState, flagged: Color) -> GameOutcome {
+        GameOutcome::Timeout(!flagged)
+    }
+}
+
+pub struct CrazyhouseRules;
+
+impl Ruleset for CrazyhouseRules {
+    fn for_each_legal_move(&self, board: &Board, state: &VariantState, f: &mut dyn FnMut(ChessMove)) {
+        for m in MoveGen::new_legal(board) {
+            f(m);
+        }
+
+        let color = board.side_to_move();
+
+        for piece in POCKET_PIECES {
+            if state.pocket_count(color, piece) == 0 {
+                continue;
+            }
+
+            for square in ALL_SQUARES {
+                if variant::drop_board(board, piece, square).is_some() {
+                    f(variant::drop_move(piece, square));
+                }
+            }
+        }
+    }
+
+    fn apply(&self, board: &Board, state: &VariantState, m: ChessMove) -> (Board, VariantState) {
+        let color = board.side_to_move();
+        let mut state = *state;
+
+        if let Some((piece, square)) = variant::as_drop(m) {
+            state.pockets[color.to_index()][piece.to_index()] -= 1;
+
+            return (variant::drop_board(board, piece, square).unwrap(), state);
+        }
+
+        if let Some(square) = variant::captured_square(board, m) {
+            let piece = if state.promoted & BitBoard::from_square(square) != EMPTY {
+                Piece::Pawn
+            } else {
+                board.piece_on(square).unwrap()
+            };
+
+            state.pockets[color.to_index()][piece.to_index()] += 1;
+            state.promoted &= !BitBoard::from_square(square);
+        }
+
+        if state.promoted & BitBoard::from_square(m.get_source()) != EMPTY || m.get_promotion().is_some() {
+            state.promoted &= !BitBoard::from_square(m.get_source());
+            state.promoted |= BitBoard::from_square(m.get_dest());
+        }
+
+        (board.make_move_new(m), state)
+    }
+
+    fn has_pockets(&self) -> bool {
+        true
+    }
+
+    //Captured pieces come back as drops, so there is always material to mate with
+    fn flag_outcome(&self, _board: &Board, _state: &VariantState, flagged: Color) -> GameOutcome {
+        GameOutcome::Timeout(!flagged)
+    }
+}
+
+//Nobody knows whether they are in check, so moves can leave the king attacked and the game is won by taking it
+pub struct FogOfWarRules;
+
+impl Ruleset for FogOfWarRules {
+    fn for_each_legal_move(&self, board: &Board, _state: &VariantState, f: &mut dyn FnMut(ChessMove)) {
+        variant::for_each_pseudo_legal_move(board, f);
+    }
+
+    fn apply(&self, board: &Board, state: &VariantState, m: ChessMove) -> (Board, VariantState) {
+        if board.piece_on(m.get_dest()) == Some(Piece::King) {
+            return (*board, VariantState { king_taken_by: Some(board.side_to_move()), ..*state });
+        }
+
+        (board.make_move_new(m), *state)
+    }
+
+    //The king being taken is checked first, as the board is left with the side that took it to move again
+    fn outcome(&self, board: &Board, state: &VariantState) -> Option<GameOutcome> {
+        if let Some(color) = state.king_taken_by {
+            Some(GameOutcome::KingCaptured(color))
+        } else if self.count_legal_moves(board, state) == 0 {
+            Some(GameOutcome::Stalemate)
+        } else {
+            None
+        }
+    }
+
+    fn visible_squares(&self, board: &Board, color: Color) -> BitBoard {
+        variant::visible_squares(board, color)
+    }
+
+    //Bots only get to see what their pieces can see
+    fn player_view(&self, board: &Board, color: Color) -> Board {
+        variant::fog_board(board, color)
+    }
+}
+
+//Whether `color` could still checkmate with some series of legal moves. A lone minor piece can only do it if the
+//other side has something to block its own king in with
+pub fn can_mate(board: &Board, color: Color) -> bool {
+    let own = *board.color_combined(color);
+
+    if (*board.pieces(Piece::Pawn) | *board.pieces(Piece::Rook) | *board.pieces(Piece::Queen)) & own != EMPTY {
+        return true;
+    }
+
+    let minors = ((*board.pieces(Piece::Knight) | *board.pieces(Piece::Bishop)) & own).popcnt();
+    let their_pieces = (*board.color_combined(!color) & !*board.pieces(Piece::King)).popcnt();
+
+    minors >= 2 || (minors == 1 && their_pieces > 0)
+}
+
+fn is_insufficient_material(board: &Board) -> bool {
+    let mut res = true;
+
+    for color in vec![chess::Color::White, chess::Color::Black] {
+        let mut num_pawns = 0;
+        let mut num_queens = 0;
+        let mut num_rooks = 0;
+        let mut num_bishops = 0;
+        let mut num_knights = 0;
+
+        for square in ALL_SQUARES {
+            if let Some(piece) = board.piece_on(square) {
+                let piece_color = board.color_on(square).unwrap();
+                if piece_color == color {
+                    match piece {
+                        chess::Piece::Pawn => num_pawns += 1,
+                        chess::Piece::Queen => num_queens += 1,
+                        chess::Piece::Rook => num_rooks += 1,
+                        chess::Piece::Bishop => num_bishops += 1,
+                        chess::Piece::Knight => num_knights += 1,
+                        _ => (),
+                    }
+                }
+            }
+        }
+
+        if num_pawns > 0
+            || num_queens > 0
+            || num_rooks > 0
+            || num_bishops > 1
+            || num_knights > 2
+            || (num_knights > 1 && num_bishops > 1)
+        {
+            res = false;
+            break;
+        }
+    }
+
+    res
+}
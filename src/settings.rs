@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::alg::external::EngineOptions;
+use crate::clock::TimeManagerKind;
+use crate::gui::palette::PaletteName;
+use crate::gui::split_layout::PanelLayout;
+use crate::gui::theme::ThemeName;
+use crate::variant::Variant;
+
+const SETTINGS_PATH: &str = "settings.json";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowGeometry {
+    //Inner size in physical pixels
+    pub width: f32,
+    pub height: f32,
+    pub position: Option<(i32, i32)>,
+    pub fullscreen: bool,
+}
+
+impl Default for WindowGeometry {
+    fn default() -> Self {
+        WindowGeometry {
+            width: 800.0,
+            height: 600.0,
+            position: None,
+            fullscreen: false,
+        }
+    }
+}
+
+//Choices that are remembered between runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    //Last players picked in the game creator
+    pub white: Option<String>,
+    pub black: Option<String>,
+
+    pub variant: Variant,
+    pub handicap: Option<String>,
+    //White's and black's
+    pub time_controls: [Option<String>; 2],
+    pub time_managers: [TimeManagerKind; 2],
+    //Flip the board between moves when two humans share the screen
+    pub hotseat_flip: bool,
+
+    pub window: WindowGeometry,
+    pub ui_scale: Option<f32>,
+    pub theme: ThemeName,
+
+    //Board colors and whether move highlights also differ in shape
+    pub palette: PaletteName,
+    pub shape_markers: bool,
+
+    //Human moves wait on the board for a second click or Enter before they are played
+    pub confirm_moves: bool,
+
+    //Options set for UCI engines in the game creator, by engine name
+    pub engine_options: BTreeMap<String, EngineOptions>,
+
+    //Side panel next to the board in games
+    pub panel_layout: PanelLayout,
+
+    //Nodes per second and search depth of the engines, shown over the board in games
+    pub perf_hud: bool,
+
+    //Strip of recent game events under the board
+    pub event_ticker: bool,
+
+    //Ids of the achievements earned so far, with the date each was first earned
+    pub achievements: BTreeMap<String, String>,
+}
+
+static SETTINGS: OnceLock<Mutex<Settings>> = OnceLock::new();
+
+fn settings() -> &'static Mutex<Settings> {
+    SETTINGS.get_or_init(|| {
+        let settings = match fs::read_to_string(SETTINGS_PATH) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                println!("Could not read {}: {}", SETTINGS_PATH, e);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        };
+
+        Mutex::new(settings)
+    })
+}
+
+pub fn get() -> Settings {
+    settings().lock().unwrap().clone()
+}
+
+//Changes the settings and saves them straight away
+pub fn update(change: impl FnOnce(&mut Settings)) {
+    let mut settings = settings().lock().unwrap();
+    change(&mut settings);
+
+    let res = serde_json::to_string_pretty(&*settings)
+        .map_err(|e| e.to_string())
+        .and_then(|text| fs::write(SETTINGS_PATH, text).map_err(|e| e.to_string()));
+
+    if let Err(e) = res {
+        println!("Could not save {}: {}", SETTINGS_PATH, e);
+    }
+}
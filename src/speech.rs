@@ -0,0 +1,152 @@
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::OnceLock;
+use std::thread;
+
+use crate::events::{self, GameEvent};
+use crate::gui::instant;
+
+static SPEAKER: OnceLock<Sender<String>> = OnceLock::new();
+
+//Command that reads out its last argument
+fn speech_command(custom: Option<&str>, text: &str) -> Vec<String> {
+    if let Some(custom) = custom {
+        let mut command: Vec<String> = custom.split_whitespace().map(|s| s.to_string()).collect();
+        command.push(text.to_string());
+        return command;
+    }
+
+    if cfg!(target_os = "windows") {
+        vec![
+            String::from("powershell"),
+            String::from("-NoProfile"),
+            String::from("-Command"),
+            format!(
+                "Add-Type -AssemblyName System.Speech; (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak('{}')",
+                text.replace('\'', "''")
+            ),
+        ]
+    } else if cfg!(target_os = "macos") {
+        vec![String::from("say"), text.to_string()]
+    } else {
+        vec![String::from("espeak"), text.to_string()]
+    }
+}
+
+//Reads out moves and results as they happen, using the system's text to speech or `command` if given
+pub fn start(command: Option<String>) {
+    let (sender, receiver) = channel::<String>();
+
+    thread::spawn(move || {
+        for text in receiver {
+            let command = speech_command(command.as_deref(), &text);
+
+            let res = Command::new(&command[0])
+                .args(&command[1..])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+
+            if let Err(e) = res {
+                println!("Could not run text to speech command {:?}: {}", command[0], e);
+            }
+        }
+    });
+
+    SPEAKER.set(sender).unwrap();
+
+    let events = events::subscribe();
+
+    thread::spawn(move || {
+        while let Ok(event) = events.recv() {
+            //Fast bots move quicker than moves can be read out, so only the latest queued move is announced
+            let mut batch = vec![event];
+            batch.extend(events.try_iter());
+
+            let last_move = batch.iter().rposition(|e| matches!(e, GameEvent::Move { .. }));
+
+            for (i, event) in batch.into_iter().enumerate() {
+                let text = match event {
+                    GameEvent::GameStarted { white, black, .. } => Some(format!("{} versus {}", white, black)),
+                    GameEvent::Move { san, .. } if Some(i) == last_move => Some(spoken_san(&san)),
+                    GameEvent::GameOver { reason, .. } => Some(reason),
+                    _ => None,
+                };
+
+                if let Some(text) = text {
+                    say(&text);
+                }
+            }
+        }
+    });
+}
+
+pub fn enabled() -> bool {
+    SPEAKER.get().is_some()
+}
+
+pub fn say(text: &str) {
+    if instant::instant() {
+        return;
+    }
+
+    if let Some(sender) = SPEAKER.get() {
+        let _ = sender.send(text.to_string());
+    }
+}
+
+pub fn piece_word(c: char) -> Option<&'static str> {
+    match c.to_ascii_uppercase() {
+        'P' => Some("Pawn"),
+        'N' => Some("Knight"),
+        'B' => Some("Bishop"),
+        'R' => Some("Rook"),
+        'Q' => Some("Queen"),
+        'K' => Some("King"),
+        _ => None,
+    }
+}
+
+//Turns SAN like Nxf3+ into words, e.g. "Knight takes f3, check"
+pub fn spoken_san(san: &str) -> String {
+    let body = san.trim_end_matches(['+', '#']);
+
+    let mut words = vec![];
+
+    if body.starts_with("O-O-O") {
+        words.push(String::from("Castles queenside"));
+    } else if body.starts_with("O-O") {
+        words.push(String::from("Castles kingside"));
+    } else if let Some((piece, square)) = body.split_once('@') {
+        words.push(format!("{} drop {}", piece.chars().next().and_then(piece_word).unwrap_or("Pawn"), square));
+    } else {
+        let (body, promotion) = match body.split_once('=') {
+            Some((body, piece)) => (body, piece.chars().next().and_then(piece_word)),
+            None => (body, None),
+        };
+
+        let mut chars = body.chars().peekable();
+
+        if let Some(piece) = chars.peek().filter(|c| c.is_ascii_uppercase()).and_then(|c| piece_word(*c)) {
+            words.push(piece.to_string());
+            chars.next();
+        }
+
+        let rest: String = chars.collect();
+        words.push(rest.replace('x', " takes "));
+
+        if let Some(promotion) = promotion {
+            words.push(format!("promotes to {}", promotion.to_ascii_lowercase()));
+        }
+    }
+
+    let text = words.join(" ");
+
+    if san.ends_with('#') {
+        format!("{}. Checkmate", text)
+    } else if san.ends_with('+') {
+        format!("{}, check", text)
+    } else {
+        text
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::write_atomically;
+
+//Every analysed game's result and accuracy, added up per bot
+const RESULTS_PATH: &str = "results.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BotStats {
+    pub games: usize,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+
+    //Moves compared with the reference engine's, with the centipawns and accuracy percentage summed over them
+    pub analysed_moves: usize,
+    pub total_cp_loss: f64,
+    pub total_accuracy: f64,
+}
+
+impl BotStats {
+    pub fn points(&self) -> f32 {
+        self.wins as f32 + self.draws as f32 * 0.5
+    }
+
+    //Performance rating from the share of points it scored, with 1500 for an even score. None before its first game
+    pub fn rating(&self) -> Option<f32> {
+        (self.games > 0).then(|| {
+            let score = (self.points() / self.games as f32).clamp(0.01, 0.99);
+            1500.0 + 400.0 * (score / (1.0 - score)).log10()
+        })
+    }
+
+    //Average centipawn loss
+    pub fn acpl(&self) -> Option<f64> {
+        (self.analysed_moves > 0).then(|| self.total_cp_loss / self.analysed_moves as f64)
+    }
+
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.analysed_moves > 0).then(|| self.total_accuracy / self.analysed_moves as f64)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResultsDatabase {
+    pub bots: BTreeMap<String, BotStats>,
+}
+
+impl ResultsDatabase {
+    pub fn load() -> ResultsDatabase {
+        let Ok(text) = fs::read_to_string(RESULTS_PATH) else {
+            return ResultsDatabase::default();
+        };
+
+        serde_json::from_str(&text).unwrap_or_else(|e| {
+            println!("Could not read {}: {}", RESULTS_PATH, e);
+            ResultsDatabase::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        write_atomically(Path::new(RESULTS_PATH), &serde_json::to_string_pretty(self).unwrap())
+            .map_err(|e| format!("Could not write {}: {}", RESULTS_PATH, e))
+    }
+
+    //Adds a game from one bot's side. `points` is 1, 0.5 or 0
+    pub fn add_game(&mut self, bot: &str, points: f32, moves: usize, cp_loss: f64, accuracy: f64) {
+        let stats = self.bots.entry(bot.to_string()).or_default();
+
+        stats.games += 1;
+
+        if points >= 1.0 {
+            stats.wins += 1;
+        } else if points > 0.0 {
+            stats.draws += 1;
+        } else {
+            stats.losses += 1;
+        }
+
+        stats.analysed_moves += moves;
+        stats.total_cp_loss += cp_loss;
+        stats.total_accuracy += accuracy;
+    }
+
+    //Bots from most to least accurate, with ones that were never analysed at the end
+    pub fn dashboard(&self) -> String {
+        let mut rows: Vec<(&String, &BotStats)> = self.bots.iter().collect();
+        rows.sort_by(|a, b| b.1.accuracy().unwrap_or(-1.0).total_cmp(&a.1.accuracy().unwrap_or(-1.0)));
+
+        let mut lines = vec![format!(
+            "{:<24} {:>5} {:>5} {:>5} {:>5} {:>7} {:>7} {:>9}",
+            "Bot", "Games", "Won", "Drawn", "Lost", "Score", "ACPL", "Accuracy"
+        )];
+
+        for (bot, stats) in rows {
+            let score = 100.0 * stats.points() / stats.games.max(1) as f32;
+
+            lines.push(format!(
+                "{:<24} {:>5} {:>5} {:>5} {:>5} {:>6.1}% {:>7} {:>9}",
+                bot,
+                stats.games,
+                stats.wins,
+                stats.draws,
+                stats.losses,
+                score,
+                stats.acpl().map_or(String::from("-"), |acpl| format!("{:.0}", acpl)),
+                stats.accuracy().map_or(String::from("-"), |accuracy| format!("{:.1}%", accuracy)),
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+//Prints the dashboard
+pub fn run() -> Result<(), String> {
+    let database = ResultsDatabase::load();
+
+    if database.bots.is_empty() {
+        return Err(format!("{} has no games yet. Analyse some with the accuracy command or tournament --accuracy", RESULTS_PATH));
+    }
+
+    println!("{}", database.dashboard());
+
+    Ok(())
+}
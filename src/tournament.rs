@@ -0,0 +1,882 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use chess::{Board, BoardStatus, ChessMove, Color, MoveGen};
+use serde::{Deserialize, Serialize};
+
+use crate::accuracy::ScoredGame;
+use crate::alg::chess_alg::{set_cancel_flag, ChessAlgorithm};
+use crate::alg::classic::ClassicParams;
+use crate::alg::explain::MoveTerms;
+use crate::alg::find_player_type;
+use crate::arena::play_game_watched;
+use crate::budget::{self, BudgetPlan};
+use crate::cli;
+use crate::gui::chess_display::{GameOutcome, PlayerType};
+use crate::pgn::{format_clock, PgnGame};
+use crate::progress::{OutputMode, Progress};
+use crate::util::pgn_date;
+use crate::variant::{self, Variant};
+use crate::watchdog::{self, Resume};
+
+//A change in the Classic eval this big in pawns makes a game worth watching
+const INTERESTING_SWING: f32 = 3.0;
+
+//How often a headless tournament reports progress
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone)]
+pub struct TournamentConfig {
+    pub bots: Vec<String>,
+    //Games between every two bots, alternating colors
+    pub games: usize,
+    //Games played at the same time
+    pub concurrency: usize,
+    pub max_plies: usize,
+    //File every finished game is appended to
+    pub pgn: Option<PathBuf>,
+    pub constraints: PairingConstraints,
+    //Whether the terms behind evaluator bots' moves are written into the PGN file as comments
+    pub eval_terms: bool,
+    //File the terms behind every game's moves are appended to, a line of JSON per game
+    pub eval_terms_json: Option<PathBuf>,
+    //Wall clock time the tournament has to finish in. Games and the move limit are picked to fit, and games that
+    //would start after it are left out
+    pub budget: Option<Duration>,
+    //Longest a bot may think about a move, instead of --max-move-time
+    pub move_time: Option<Duration>,
+    //Plies at the start of every game that are random moves rather than the bots'
+    pub random_plies: usize,
+    //Longest a game can go without a move before the watchdog gives up on it, instead of one from the move limit
+    pub stall_limit: Option<Duration>,
+}
+
+fn parse_bot_pair(s: &str) -> Result<(String, String), String> {
+    s.split_once(':')
+        .map(|(a, b)| (a.trim().to_string(), b.trim().to_string()))
+        .filter(|(a, b)| !a.is_empty() && !b.is_empty())
+        .ok_or(format!("Expected two bots as A:B, got '{}'", s))
+}
+
+//Games left out of a tournament's schedule
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, clap::Args)]
+pub struct PairingConstraints {
+    /// Two bots that never play each other, as A:B. Can be repeated
+    #[arg(long = "exclude", value_name = "A:B", value_parser = parse_bot_pair)]
+    pub exclude: Vec<(String, String)>,
+
+    /// Bot the others are measured against. When there are any, the other bots only play anchors and anchors don't play each other. Can be repeated
+    #[arg(long = "anchor", value_name = "BOT")]
+    pub anchors: Vec<String>,
+
+    /// Leave out games between two entries of the same bot, which are no use for bots that always play the same moves
+    #[arg(long)]
+    pub skip_mirror: bool,
+}
+
+impl PairingConstraints {
+    pub fn allows(&self, a: &str, b: &str) -> bool {
+        if self.skip_mirror && a == b {
+            return false;
+        }
+
+        if self.exclude.iter().any(|(x, y)| (x == a && y == b) || (x == b && y == a)) {
+            return false;
+        }
+
+        if !self.anchors.is_empty() {
+            let anchor = |bot: &str| self.anchors.iter().any(|anchor| anchor == bot);
+
+            return anchor(a) != anchor(b);
+        }
+
+        true
+    }
+
+    //Uses the names in the player list, like the bots themselves
+    fn resolve(&mut self, bots: &[String]) -> Result<(), String> {
+        for (a, b) in self.exclude.iter_mut() {
+            *a = bot_name(a)?.to_string();
+            *b = bot_name(b)?.to_string();
+        }
+
+        for anchor in self.anchors.iter_mut() {
+            *anchor = bot_name(anchor)?.to_string();
+
+            if !bots.contains(anchor) {
+                return Err(format!("Anchor {} is not taking part", anchor));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pairing {
+    //Position in the schedule, from 0
+    pub index: usize,
+    pub white: String,
+    pub black: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameResult {
+    pub pairing: Pairing,
+    pub result: String,
+    pub reason: String,
+    pub moves: Vec<ChessMove>,
+    //Nothing random happened in the game, so playing the pairing again would give the same game
+    pub reproducible: bool,
+    //The earlier game this one is a copy of, rather than being played again
+    pub copy_of: Option<usize>,
+    //For every move, when the bot that played it explains its moves. Empty for games played elsewhere
+    pub terms: Vec<Option<MoveTerms>>,
+    //How many of the first moves were random instead of played by the bots
+    pub random_plies: usize,
+}
+
+impl GameResult {
+    //Told apart from games that were played
+    pub fn copy_text(&self) -> String {
+        self.copy_of.map(|index| format!(", same as game {}", index + 1)).unwrap_or_default()
+    }
+
+    //Points for white and black
+    pub fn points(&self) -> (f32, f32) {
+        match self.result.as_str() {
+            "1-0" => (1.0, 0.0),
+            "0-1" => (0.0, 1.0),
+            _ => (0.5, 0.5),
+        }
+    }
+
+    pub fn pgn(&self) -> PgnGame {
+        let mut game = PgnGame::new(Board::default());
+
+        game.set_tag("Event", "Chess Arena tournament");
+        game.set_tag("Date", &pgn_date());
+        game.set_tag("Round", &(self.pairing.index + 1).to_string());
+        game.set_tag("White", &self.pairing.white);
+        game.set_tag("Black", &self.pairing.black);
+        game.set_tag("Termination", &self.reason);
+
+        game.moves = self.moves.clone();
+        game.result = self.result.clone();
+        game.comments = vec![Some(String::from(RANDOM_MOVE_COMMENT)); self.random_plies.min(self.moves.len())];
+
+        game
+    }
+
+    //Like pgn, with the terms behind every move as comments
+    pub fn pgn_with_terms(&self) -> PgnGame {
+        let mut game = self.pgn();
+        game.comments.resize(self.moves.len(), None);
+
+        for (comment, terms) in game.comments.iter_mut().zip(self.terms.iter()) {
+            if let Some(terms) = terms {
+                *comment = Some(terms.comment());
+            }
+        }
+
+        game
+    }
+}
+
+//One line of an --eval-terms-json file
+#[derive(Debug, Serialize)]
+struct TermRecord<'a> {
+    round: usize,
+    white: &'a str,
+    black: &'a str,
+    result: &'a str,
+    moves: Vec<MoveTermRecord<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct MoveTermRecord<'a> {
+    ply: usize,
+    uci: String,
+    bot: &'a str,
+    //Missing for bots that don't explain their moves and for their random moves
+    #[serde(flatten)]
+    terms: Option<&'a MoveTerms>,
+}
+
+const RANDOM_MOVE_COMMENT: &str = "Random opening move";
+
+//What a spectator needs to know about a game being played right now
+#[derive(Debug, Clone, Default)]
+pub struct LiveGame {
+    pub pairing: Option<Pairing>,
+    pub moves: Vec<ChessMove>,
+    //Set once the game is over, until the next one starts
+    pub result: Option<String>,
+    //When something worth watching last happened, and what it was
+    pub highlight: Option<(Instant, String)>,
+    //When the game started or the last move was played, for the watchdog
+    pub moved_at: Option<Instant>,
+    pub random_plies: usize,
+    //Times the game was played again after something went wrong
+    pub replays: usize,
+}
+
+//Every bot plays every other bot `games` times, swapping colors every game, apart from the pairs `constraints` leave out
+pub fn round_robin(bots: &[String], games: usize, constraints: &PairingConstraints) -> Vec<Pairing> {
+    let mut pairings = vec![];
+
+    for game in 0..games {
+        for (i, a) in bots.iter().enumerate() {
+            for b in bots[i + 1..].iter().filter(|b| constraints.allows(a, b)) {
+                let (white, black) = if game % 2 == 0 { (a, b) } else { (b, a) };
+
+                pairings.push(Pairing {
+                    index: pairings.len(),
+                    white: white.clone(),
+                    black: black.clone(),
+                });
+            }
+        }
+    }
+
+    pairings
+}
+
+//The name in the player list for a bot, checking it isn't a person
+pub fn bot_name(name: &str) -> Result<&'static str, String> {
+    let (found, _) = find_player_type(name).ok_or(format!("Unknown bot '{}'", name))?;
+
+    if found == "Human" || found == "Remote" {
+        return Err(format!("{} is not a bot", found));
+    }
+
+    Ok(found)
+}
+
+//Adds a finished game to a PGN file, reporting but otherwise ignoring failures
+pub fn append_pgn(path: &Path, game: &PgnGame) {
+    let res = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", game.to_pgn(false)));
+
+    if let Err(e) = res {
+        println!("Could not save game to {:?}: {}", path, e);
+    }
+}
+
+//Adds the terms behind a finished game's moves to a file with a line of JSON for every game
+pub fn append_terms(path: &Path, result: &GameResult) {
+    let moves = result
+        .moves
+        .iter()
+        .enumerate()
+        .map(|(ply, m)| MoveTermRecord {
+            ply: ply + 1,
+            uci: variant::move_to_uci(*m),
+            bot: if ply % 2 == 0 { &result.pairing.white } else { &result.pairing.black },
+            terms: result.terms.get(ply).and_then(Option::as_ref),
+        })
+        .collect();
+
+    let record = TermRecord {
+        round: result.pairing.index + 1,
+        white: &result.pairing.white,
+        black: &result.pairing.black,
+        result: &result.result,
+        moves,
+    };
+
+    let res = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", serde_json::to_string(&record).unwrap()));
+
+    if let Err(e) = res {
+        println!("Could not save the terms of game {} to {:?}: {}", record.round, path, e);
+    }
+}
+
+pub fn computer(name: &str, color: Color) -> Result<Arc<Mutex<dyn ChessAlgorithm>>, String> {
+    let (name, supplier) = find_player_type(name).ok_or(format!("Unknown bot '{}'", name))?;
+
+    match supplier(color) {
+        PlayerType::Computer(algorithm) => Ok(algorithm),
+        _ => Err(format!("{} is not a bot", name)),
+    }
+}
+
+//Whether the side to move can checkmate straight away
+fn mate_in_one(board: &Board) -> bool {
+    MoveGen::new_legal(board).any(|m| board.make_move_new(m).status() == BoardStatus::Checkmate)
+}
+
+//Plays one game of the schedule, calling `on_move` with the board after every move and what was interesting about
+//it, if anything. The first `random_plies` moves are random ones, and the bots take over from there
+pub fn play_pairing(
+    pairing: &Pairing,
+    max_plies: usize,
+    max_move_time: Option<Duration>,
+    random_plies: usize,
+    on_move: &mut dyn FnMut(ChessMove, &Board, Option<String>),
+) -> Result<GameResult, String> {
+    play_resumed(&Resume::new(pairing.clone(), random_plies), max_plies, max_move_time, on_move).map(|(result, _)| result)
+}
+
+//Like play_pairing, with the bots taking over after the moves `game` starts from, and the outcome the game came to
+fn play_resumed(
+    game: &Resume,
+    max_plies: usize,
+    max_move_time: Option<Duration>,
+    on_move: &mut dyn FnMut(ChessMove, &Board, Option<String>),
+) -> Result<(GameResult, GameOutcome), String> {
+    let pairing = &game.pairing;
+    let white = computer(&pairing.white, Color::White)?;
+    let black = computer(&pairing.black, Color::Black)?;
+
+    let params = ClassicParams::load();
+    let mut last_eval = 0.0;
+
+    let mut watch = |_before: &Board, m: ChessMove, after: &Board| {
+        let eval = params.evaluate(after, Color::White);
+
+        let highlight = if after.status() == BoardStatus::Checkmate {
+            Some(String::from("Checkmate"))
+        } else if mate_in_one(after) {
+            Some(String::from("Mate threat"))
+        } else if (eval - last_eval).abs() >= INTERESTING_SWING {
+            Some(format!("Eval swing to {:+.1}", eval))
+        } else {
+            None
+        };
+
+        last_eval = eval;
+        on_move(m, after, highlight);
+    };
+
+    let opening = &game.moves;
+    let mut start = Board::default();
+
+    for m in opening.iter().copied() {
+        let before = start;
+        start = before.make_move_new(m);
+        watch(&before, m, &start);
+    }
+
+    let played = play_game_watched(
+        &mut *white.lock().unwrap(),
+        &mut *black.lock().unwrap(),
+        Variant::Standard,
+        start,
+        max_plies.saturating_sub(opening.len()),
+        max_move_time,
+        &mut watch,
+    );
+
+    let mut terms = vec![None; opening.len()];
+    terms.extend(played.terms);
+
+    let result = GameResult {
+        pairing: pairing.clone(),
+        result: played.outcome.pgn_result().to_string(),
+        reason: played.outcome.get_text().to_string(),
+        random_plies: game.random_plies,
+        moves: opening.iter().copied().chain(played.moves).collect(),
+        //Playing the pairing again would start with other random moves, or wouldn't go wrong in the same place
+        reproducible: played.reproducible && opening.is_empty(),
+        copy_of: None,
+        terms,
+    };
+
+    Ok((result, played.outcome))
+}
+
+//A thread playing the games of a slot
+#[derive(Debug)]
+struct Worker {
+    cancel: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Debug)]
+pub struct Tournament {
+    pub config: TournamentConfig,
+    pub pairings: Vec<Pairing>,
+    //Next pairing to hand out
+    next: Mutex<usize>,
+    //Pairings handed out to workers that went away before finishing them
+    retry: Mutex<Vec<Pairing>>,
+    //One for each game played at the same time
+    pub slots: Vec<Mutex<LiveGame>>,
+    pub results: Mutex<Vec<GameResult>>,
+    //Games that are over, including ones that couldn't be played
+    done: Mutex<usize>,
+    //Set when something else reports finished games, like the progress of a headless run
+    quiet: AtomicBool,
+    //Set when the arena is closing. Searches are cancelled and nothing more is played or recorded
+    stopped: AtomicBool,
+    //The worker playing on every slot. Ones the watchdog gave up on are replaced
+    workers: Mutex<Vec<Worker>>,
+    stall_limit: Duration,
+    //What the budget came to, and when it runs out
+    pub plan: Option<BudgetPlan>,
+    deadline: Option<Instant>,
+    //Games left out because the budget ran out
+    unplayed: Mutex<usize>,
+}
+
+impl Tournament {
+    //Checks every bot exists and can play before anything starts
+    pub fn new(mut config: TournamentConfig) -> Result<Arc<Tournament>, String> {
+        if config.bots.len() < 2 {
+            return Err(String::from("A tournament needs at least 2 bots"));
+        }
+
+        for bot in config.bots.iter_mut() {
+            *bot = bot_name(bot)?.to_string();
+        }
+
+        config.constraints.resolve(&config.bots)?;
+
+        let plan = config.budget.map(|budget| {
+            let pairs = round_robin(&config.bots, 1, &config.constraints).len();
+            budget::plan(budget, pairs, config.concurrency, config.max_plies)
+        });
+
+        if let Some(plan) = plan {
+            config.games = plan.games;
+            config.move_time = Some(plan.move_time);
+        }
+
+        let deadline = config.budget.map(|budget| Instant::now() + budget);
+        let stall_limit = watchdog::stall_limit(&config);
+
+        let pairings = round_robin(&config.bots, config.games.max(1), &config.constraints);
+
+        if pairings.is_empty() {
+            return Err(String::from("The pairing constraints leave no games to play"));
+        }
+
+        //No local games when every game is played by remote workers
+        let concurrency = config.concurrency.min(pairings.len());
+
+        Ok(Arc::new(Tournament {
+            config,
+            pairings,
+            next: Mutex::new(0),
+            retry: Mutex::new(vec![]),
+            slots: (0..concurrency).map(|_| Mutex::new(LiveGame::default())).collect(),
+            results: Mutex::new(vec![]),
+            done: Mutex::new(0),
+            quiet: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            workers: Mutex::new(vec![]),
+            stall_limit,
+            plan,
+            deadline,
+            unplayed: Mutex::new(0),
+        }))
+    }
+
+    pub fn next_pairing(&self) -> Option<Pairing> {
+        if self.stopped() {
+            return None;
+        }
+
+        //Nothing new is started once the budget has run out
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.leave_out_rest();
+            return None;
+        }
+
+        if let Some(pairing) = self.retry.lock().unwrap().pop() {
+            return Some(pairing);
+        }
+
+        let mut next = self.next.lock().unwrap();
+
+        loop {
+            let pairing = self.pairings.get(*next).cloned();
+            *next += 1;
+
+            //Skipping games restored from an autosave
+            match pairing {
+                Some(pairing) if self.played(pairing.index) => continue,
+
+                Some(pairing) => match self.reproducible_game(&pairing) {
+                    Some(original) => self.record(GameResult {
+                        pairing,
+                        copy_of: Some(original.pairing.index),
+                        ..original
+                    }),
+                    None => return Some(pairing),
+                },
+
+                None => return None,
+            }
+        }
+    }
+
+    //Counts the games that were never handed out as over
+    fn leave_out_rest(&self) {
+        let mut next = self.next.lock().unwrap();
+
+        let retried = self.retry.lock().unwrap().drain(..).count();
+        let left = self.pairings[(*next).min(self.pairings.len())..].iter().filter(|pairing| !self.played(pairing.index)).count() + retried;
+        *next = self.pairings.len();
+
+        *self.done.lock().unwrap() += left;
+        *self.unplayed.lock().unwrap() += left;
+    }
+
+    pub fn unplayed(&self) -> usize {
+        *self.unplayed.lock().unwrap()
+    }
+
+    //An earlier game between the same bots with the same colors that nothing random happened in. Every game starts
+    //from the same position, so playing it again would only give the same game
+    fn reproducible_game(&self, pairing: &Pairing) -> Option<GameResult> {
+        self.results
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|result| result.reproducible && result.copy_of.is_none() && result.pairing.white == pairing.white && result.pairing.black == pairing.black)
+            .cloned()
+    }
+
+    fn played(&self, index: usize) -> bool {
+        self.results.lock().unwrap().iter().any(|result| result.pairing.index == index)
+    }
+
+    //Counts games from an earlier run as over, so that they aren't handed out again. Called before the tournament starts
+    pub fn restore_results(&self, results: Vec<GameResult>) {
+        *self.done.lock().unwrap() += results.len();
+        self.results.lock().unwrap().extend(results);
+    }
+
+    //The game on a slot. A bot that panicked while it was held leaves it poisoned, which is cleared since the game is
+    //dealt with by the worker anyway
+    fn slot(&self, slot: usize) -> MutexGuard<'_, LiveGame> {
+        self.slots[slot].lock().unwrap_or_else(|poisoned| {
+            self.slots[slot].clear_poison();
+            poisoned.into_inner()
+        })
+    }
+
+    //Plays pairings on one slot until there are none left, starting with `resume` when the watchdog handed it a game
+    //another worker got stuck on. Set, `cancel` stops the worker's searches and has it leave without recording anything
+    fn worker(&self, slot: usize, cancel: Arc<AtomicBool>, mut resume: Option<Resume>) {
+        set_cancel_flag(Some(cancel.clone()));
+
+        let move_time = self.config.move_time.or(cli::args().max_move_time);
+
+        while !cancel.load(Ordering::Relaxed) {
+            let Some(game) = resume.take().or_else(|| self.next_pairing().map(|pairing| Resume::new(pairing, self.config.random_plies))) else {
+                break;
+            };
+
+            *self.slot(slot) = LiveGame {
+                pairing: Some(game.pairing.clone()),
+                moved_at: Some(Instant::now()),
+                random_plies: game.random_plies,
+                replays: game.replays,
+                ..LiveGame::default()
+            };
+
+            let res = panic::catch_unwind(AssertUnwindSafe(|| {
+                play_resumed(&game, self.config.max_plies, move_time, &mut |m, _, highlight| {
+                    let mut live = self.slot(slot);
+
+                    //The slot has another worker now
+                    if cancel.load(Ordering::Relaxed) {
+                        return;
+                    }
+
+                    live.moves.push(m);
+                    live.moved_at = Some(Instant::now());
+
+                    if let Some(highlight) = highlight {
+                        live.highlight = Some((Instant::now(), highlight));
+                    }
+                })
+            }));
+
+            //Held until the game is dealt with, so that the watchdog can't give up on it at the same time
+            let mut live = self.slot(slot);
+
+            //A game cut short by cancelled searches doesn't count, and one the watchdog gave up on is already dealt with
+            if cancel.load(Ordering::Relaxed) {
+                break;
+            }
+
+            match res {
+                //A bot whose engine died gets a new one, carrying on from the last move
+                Ok(Ok((result, GameOutcome::Forfeit(_)))) if game.can_replay() => {
+                    live.moved_at = Some(Instant::now());
+                    resume = Some(game.replay(result.moves, "an engine stopped running"));
+                }
+
+                Ok(Ok((result, _))) => {
+                    live.result = Some(result.result.clone());
+                    drop(live);
+                    self.record(result);
+                }
+
+                Ok(Err(e)) => {
+                    drop(live);
+                    self.skip(&game.pairing, &e);
+                }
+
+                //A bot panicked, and the moves up to then are all that is left of the game
+                Err(_) => {
+                    let moves = live.moves.clone();
+
+                    if game.can_replay() {
+                        live.moved_at = Some(Instant::now());
+                        resume = Some(game.replay(moves, "a bot crashed"));
+                    } else {
+                        let result = game.forfeit(moves, "a bot crashed again");
+                        live.result = Some(result.result.clone());
+                        drop(live);
+                        self.record(result);
+                    }
+                }
+            }
+        }
+    }
+
+    fn spawn_worker(self: &Arc<Self>, slot: usize, resume: Option<Resume>) -> Worker {
+        let cancel = Arc::new(AtomicBool::new(self.stopped()));
+
+        let tournament = self.clone();
+        let flag = cancel.clone();
+        let handle = thread::spawn(move || tournament.worker(slot, flag, resume));
+
+        Worker { cancel, handle }
+    }
+
+    //Gives up on games that have gone too long without a move, handing their slot to a new worker that plays the game
+    //again from its last move or records the stalled bot's forfeit. The stuck worker is left to finish by itself, with
+    //its searches cancelled and its game ignored
+    fn watch(self: &Arc<Self>) {
+        while !self.stopped() && self.running() {
+            thread::sleep(watchdog::WATCH_INTERVAL);
+
+            for slot in 0..self.slots.len() {
+                let mut live = self.slot(slot);
+
+                let (Some(pairing), Some(moved_at), None) = (live.pairing.clone(), live.moved_at, &live.result) else {
+                    continue;
+                };
+
+                if moved_at.elapsed() < self.stall_limit || self.stopped() {
+                    continue;
+                }
+
+                let board = live.moves.iter().fold(Board::default(), |board, m| board.make_move_new(*m));
+
+                //The game is over and only the bots' game_over is left before the worker records it
+                if board.status() != BoardStatus::Ongoing || live.moves.len() >= self.config.max_plies {
+                    continue;
+                }
+
+                let stalled = match board.side_to_move() {
+                    Color::White => &pairing.white,
+                    Color::Black => &pairing.black,
+                };
+                let why = format!("{} made no move in {}s", stalled, self.stall_limit.as_secs_f32());
+
+                let game = Resume {
+                    pairing: pairing.clone(),
+                    moves: vec![],
+                    random_plies: live.random_plies,
+                    replays: live.replays,
+                };
+
+                let mut workers = self.workers.lock().unwrap();
+                workers[slot].cancel.store(true, Ordering::Relaxed);
+
+                let resume = if game.can_replay() {
+                    live.moved_at = Some(Instant::now());
+                    Some(game.replay(live.moves.clone(), &why))
+                } else {
+                    let result = game.forfeit(live.moves.clone(), &why);
+                    live.result = Some(result.result.clone());
+                    drop(live);
+                    self.record(result);
+                    None
+                };
+
+                workers[slot] = self.spawn_worker(slot, resume);
+            }
+        }
+    }
+
+    //Whether any game is still being played here. Workers the watchdog gave up on don't count
+    pub fn running(&self) -> bool {
+        self.workers.lock().unwrap().iter().any(|worker| !worker.handle.is_finished())
+    }
+
+    //Stops handing out games and cancels the searches of the ones being played
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+
+        for worker in self.workers.lock().unwrap().iter() {
+            worker.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    //Hands a pairing out again
+    pub fn requeue(&self, pairing: Pairing) {
+        self.retry.lock().unwrap().push(pairing);
+    }
+
+    //Counts a pairing that couldn't be played as over
+    pub fn skip(&self, pairing: &Pairing, error: &str) {
+        println!("Could not play {} vs {}: {}", pairing.white, pairing.black, error);
+
+        *self.done.lock().unwrap() += 1;
+    }
+
+    pub fn record(&self, result: GameResult) {
+        if !self.quiet.load(Ordering::Relaxed) {
+            println!(
+                "Game {}/{}: {} vs {} {} ({}){}",
+                result.pairing.index + 1,
+                self.pairings.len(),
+                result.pairing.white,
+                result.pairing.black,
+                result.result,
+                result.reason,
+                result.copy_text()
+            );
+        }
+
+        if let Some(path) = &self.config.pgn {
+            let pgn = if self.config.eval_terms { result.pgn_with_terms() } else { result.pgn() };
+            append_pgn(path, &pgn);
+        }
+
+        if let Some(path) = &self.config.eval_terms_json {
+            append_terms(path, &result);
+        }
+
+        self.results.lock().unwrap().push(result);
+        *self.done.lock().unwrap() += 1;
+    }
+
+    //Starts a worker on every slot, and the watchdog looking after them
+    pub fn start(self: &Arc<Self>) {
+        *self.workers.lock().unwrap() = (0..self.slots.len()).map(|slot| self.spawn_worker(slot, None)).collect();
+
+        let tournament = self.clone();
+        thread::spawn(move || tournament.watch());
+    }
+
+    pub fn finished(&self) -> bool {
+        self.done() >= self.pairings.len()
+    }
+
+    pub fn done(&self) -> usize {
+        *self.done.lock().unwrap()
+    }
+
+    //Bots with their points and games played, best first
+    pub fn standings(&self) -> Vec<(String, f32, usize)> {
+        let mut standings: Vec<(String, f32, usize)> = self.config.bots.iter().map(|bot| (bot.clone(), 0.0, 0)).collect();
+
+        for result in self.results.lock().unwrap().iter() {
+            let (white_points, black_points) = result.points();
+
+            for (name, points) in [(&result.pairing.white, white_points), (&result.pairing.black, black_points)] {
+                if let Some(entry) = standings.iter_mut().find(|(bot, _, _)| bot == name) {
+                    entry.1 += points;
+                    entry.2 += 1;
+                }
+            }
+        }
+
+        standings.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        standings
+    }
+
+    //Finished games in schedule order, for scoring against a reference engine
+    pub fn scored_games(&self) -> Vec<ScoredGame> {
+        let mut results = self.results.lock().unwrap().clone();
+        results.sort_by_key(|result| result.pairing.index);
+
+        results
+            .into_iter()
+            .map(|result| ScoredGame {
+                white: result.pairing.white,
+                black: result.pairing.black,
+                result: result.result,
+                start: Board::default(),
+                moves: result.moves,
+            })
+            .collect()
+    }
+
+    //How far every bot's rating could be from the one its score gives, in Elo either way
+    pub fn rating_margins(&self) -> Vec<(String, f32)> {
+        self.standings().into_iter().map(|(bot, points, games)| (bot, budget::rating_margin(points, games))).collect()
+    }
+
+    pub fn standings_text(&self) -> String {
+        self.standings()
+            .iter()
+            .enumerate()
+            .map(|(i, (bot, points, games))| format!("{:>2}. {:<24} {:>5.1}/{}", i + 1, bot, points, games))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+//Plays the whole tournament without the GUI, reporting games as they finish, and prints the standings
+pub fn run(config: TournamentConfig, output: OutputMode) -> Result<Arc<Tournament>, String> {
+    let tournament = Tournament::new(config)?;
+    tournament.quiet.store(true, Ordering::Relaxed);
+
+    if output == OutputMode::Progress {
+        println!(
+            "{} games between {} bots, {} at a time",
+            tournament.pairings.len(),
+            tournament.config.bots.len(),
+            tournament.slots.len()
+        );
+
+        if let Some(plan) = tournament.plan {
+            println!(
+                "Fitting the budget: {} games between every two bots with moves limited to {:.2}s, at most {} if every move takes that long",
+                plan.games,
+                plan.move_time.as_secs_f32(),
+                format_clock(plan.estimate)
+            );
+        }
+    }
+
+    tournament.start();
+    let mut progress = Progress::new(output);
+
+    while tournament.running() {
+        progress.update(&tournament);
+        thread::sleep(PROGRESS_INTERVAL);
+    }
+
+    progress.finish(&tournament);
+
+    Ok(tournament)
+}
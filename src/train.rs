@@ -0,0 +1,121 @@
+use std::path::{Path, PathBuf};
+
+use chess::{Board, ChessMove, Color};
+
+use crate::alg::epsilon::EpsilonGreedy;
+use crate::alg::weighted::{Feature, WeightedBot, ALL_FEATURES, WEIGHTS_DIR};
+use crate::arena::play_game;
+use crate::variant::{Variant, VariantState};
+
+#[derive(Debug, Clone)]
+pub struct TrainConfig {
+    pub games: usize,
+    pub max_plies: usize,
+    pub learning_rate: f32,
+    //Chance of playing a random move instead of the best one, so that games don't all go the same way
+    pub epsilon: f64,
+    pub checkpoint_every: usize,
+    pub checkpoint_dir: PathBuf,
+    //Also save checkpoints to the weights folder so that they can be played against
+    pub register: bool,
+    pub name: String,
+    //Weights file to continue training from
+    pub resume: Option<PathBuf>,
+}
+
+fn starting_bot(config: &TrainConfig) -> Result<WeightedBot, String> {
+    let mut bot = match &config.resume {
+        Some(path) => WeightedBot::load(path).map_err(|e| format!("Could not load {:?}: {}", path, e))?,
+        None => {
+            //Only knowing that pieces are worth something gives the games a direction to start with
+            let mut bot = WeightedBot::default();
+            bot.set_weight(Feature::Material, 0.1);
+            bot
+        }
+    };
+
+    bot.name = config.name.clone();
+
+    Ok(bot)
+}
+
+//Moves every weight so that the evaluation of each position after a move is closer to how the game ended for the
+//side that made it. Evaluations are squashed with tanh, and every feature is learned relative to its usual scale
+fn learn_from_game(bot: &mut WeightedBot, moves: &[ChessMove], score: f32, learning_rate: f32) {
+    let rules = Variant::Standard.rules();
+
+    let mut board = Board::default();
+    let mut state = VariantState::default();
+
+    let mut gradient = vec![0.0; ALL_FEATURES.len()];
+
+    for m in moves {
+        let color = board.side_to_move();
+        (board, state) = rules.apply(&board, &state, *m);
+
+        let target = match color {
+            Color::White => 2.0 * score - 1.0,
+            Color::Black => 1.0 - 2.0 * score,
+        };
+
+        let prediction = bot.evaluate(&board, color).tanh();
+        let error = (target - prediction) * (1.0 - prediction * prediction);
+
+        for (i, feature) in ALL_FEATURES.iter().enumerate() {
+            gradient[i] += error * feature.value(&board, color) / (feature.scale() * feature.scale());
+        }
+    }
+
+    let n = moves.len().max(1) as f32;
+
+    for (i, feature) in ALL_FEATURES.iter().enumerate() {
+        bot.set_weight(*feature, bot.weight(*feature) + learning_rate * gradient[i] / n);
+    }
+}
+
+fn save_checkpoint(bot: &mut WeightedBot, config: &TrainConfig, games: usize) -> Result<(), String> {
+    bot.name = format!("{} {}", config.name, games);
+    let path = bot.save(&config.checkpoint_dir)?;
+    println!("Saved checkpoint {:?}", path);
+
+    if config.register {
+        bot.save(Path::new(WEIGHTS_DIR))?;
+    }
+
+    bot.name = config.name.clone();
+
+    Ok(())
+}
+
+pub fn run(config: &TrainConfig) -> Result<(), String> {
+    let mut bot = starting_bot(config)?;
+
+    let mut results = [0; 3];
+
+    for game in 1..=config.games {
+        let mut white = EpsilonGreedy::new(bot.engine(Color::White), config.epsilon as f32);
+        let mut black = EpsilonGreedy::new(bot.engine(Color::Black), config.epsilon as f32);
+
+        let res = play_game(&mut white, &mut black, Variant::Standard, Board::default(), config.max_plies);
+        let score = res.white_score();
+
+        results[(2.0 * score) as usize] += 1;
+
+        learn_from_game(&mut bot, &res.moves, score, config.learning_rate);
+
+        if game % config.checkpoint_every.max(1) == 0 || game == config.games {
+            bot.generation += 1;
+
+            println!(
+                "Game {}/{}: {} black wins, {} draws, {} white wins since the last checkpoint",
+                game, config.games, results[0], results[1], results[2]
+            );
+            println!("  {:?}", bot.weights);
+
+            results = [0; 3];
+            save_checkpoint(&mut bot, config, game)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::PathBuf;
+
+use chess::{Board, EMPTY};
+
+use crate::alg::classic::ClassicParams;
+use crate::pgn::PgnGame;
+use crate::variant::Variant;
+
+#[derive(Debug, Clone)]
+pub struct TuneConfig {
+    pub pgn: PathBuf,
+    pub epochs: usize,
+    pub learning_rate: f32,
+    //Opening moves are mostly book moves and say little about who is better
+    pub skip_plies: usize,
+    pub output: PathBuf,
+}
+
+//Turns evaluations in pawns into an expected score, with a 4 pawn lead meaning a 10 to 1 chance to win
+const SCALE: f32 = std::f32::consts::LN_10 / 4.0;
+
+struct Sample {
+    features: Vec<(usize, f32)>,
+    //Result of the game for white
+    result: f32,
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-SCALE * x).exp())
+}
+
+fn white_result(game: &PgnGame) -> Option<f32> {
+    match game.result.as_str() {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" => Some(0.5),
+        _ => None,
+    }
+}
+
+//Every position of finished standard games, labelled with the result. Positions in check are left out since the
+//evaluation can't see what happens next
+fn load_samples(config: &TuneConfig) -> Result<Vec<Sample>, String> {
+    let text = fs::read_to_string(&config.pgn).map_err(|e| format!("Could not read {:?}: {}", config.pgn, e))?;
+    let games = PgnGame::parse_all(&text)?;
+
+    let mut samples = vec![];
+
+    for game in games.iter().filter(|g| g.variant() == Variant::Standard) {
+        let Some(result) = white_result(game) else {
+            continue;
+        };
+
+        let mut board: Board = game.start;
+
+        for (ply, m) in game.moves.iter().enumerate() {
+            if ply >= config.skip_plies && *board.checkers() == EMPTY {
+                samples.push(Sample {
+                    features: ClassicParams::features(&board),
+                    result,
+                });
+            }
+
+            board = board.make_move_new(*m);
+        }
+    }
+
+    Ok(samples)
+}
+
+fn predict(weights: &[f32], sample: &Sample) -> f32 {
+    sigmoid(sample.features.iter().map(|(i, count)| weights[*i] * count).sum())
+}
+
+fn mean_error(weights: &[f32], samples: &[Sample]) -> f32 {
+    samples.iter().map(|s| (s.result - predict(weights, s)).powi(2)).sum::<f32>() / samples.len() as f32
+}
+
+//Texel tuning: gradient descent on the squared error between the predicted and actual results
+pub fn run(config: &TuneConfig) -> Result<(), String> {
+    let samples = load_samples(config)?;
+
+    if samples.is_empty() {
+        return Err(format!("No positions from finished standard games in {:?}", config.pgn));
+    }
+
+    let mut weights = ClassicParams::load().to_vec();
+
+    println!("Tuning on {} positions, starting error {:.5}", samples.len(), mean_error(&weights, &samples));
+
+    for epoch in 1..=config.epochs {
+        let mut gradient = vec![0.0; ClassicParams::LEN];
+
+        for sample in samples.iter() {
+            let prediction = predict(&weights, sample);
+            let error = (sample.result - prediction) * prediction * (1.0 - prediction) * SCALE;
+
+            for (i, count) in sample.features.iter() {
+                gradient[*i] += error * count;
+            }
+        }
+
+        for (weight, gradient) in weights.iter_mut().zip(gradient.iter()) {
+            *weight += config.learning_rate * gradient / samples.len() as f32;
+        }
+
+        //The king is always on the board so its value means nothing
+        weights[5] = 0.0;
+
+        if epoch.is_multiple_of(10) || epoch == config.epochs {
+            println!("Epoch {}/{}: error {:.5}", epoch, config.epochs, mean_error(&weights, &samples));
+        }
+    }
+
+    ClassicParams::from_vec(&weights).save(&config.output)?;
+    println!("Saved tuned weights to {:?}", config.output);
+
+    Ok(())
+}
@@ -1,5 +1,11 @@
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use chess::{ChessMove, Board, Piece, MoveGen, Rank, File, BoardStatus};
 
+use crate::variant;
+
 pub fn rank_to_char(rank: Rank) -> char {
     match rank {
         Rank::First => '1',
@@ -27,6 +33,17 @@ pub fn file_to_char(file: File) -> char {
 }
 
 pub fn move_to_SAN(board: &Board, m: ChessMove) -> String {
+    //Crazyhouse drops
+    if let Some((piece, square)) = variant::as_drop(m) {
+        let mut san = format!("{}@{}", piece.to_string(chess::Color::White), square);
+
+        if variant::drop_board(board, piece, square).is_some_and(|b| b.checkers().0 != 0) {
+            san.push('+');
+        }
+
+        return san;
+    }
+
     //First check for castling
 
     if Some(Piece::King) == board.piece_on(m.get_source()) {
@@ -62,7 +79,8 @@ pub fn move_to_SAN(board: &Board, m: ChessMove) -> String {
     let same_rank: Vec<_> = with_same_piece.iter().filter(|x| x.get_source().get_rank() == m.get_source().get_rank()).collect();
     let same_file: Vec<_> = with_same_piece.iter().filter(|x| x.get_source().get_file() == m.get_source().get_file()).collect();
 
-    if with_same_piece.len() > 1 {
+    //A pawn's promotions all go from the same square, and its captures already say which file it came from
+    if piece != Piece::Pawn && with_same_piece.len() > 1 {
         if same_rank.len() > 1 {
             san.push(file_to_char(m.get_source().get_file()));
         } else if same_file.len() > 1 {
@@ -73,8 +91,8 @@ pub fn move_to_SAN(board: &Board, m: ChessMove) -> String {
         }
     }
 
-    if board.piece_on(m.get_dest()) != None {
-        if piece == Piece::Pawn && with_same_piece.len() == 1 {
+    if variant::is_capture(board, m) {
+        if piece == Piece::Pawn {
             san.push(file_to_char(m.get_source().get_file()));
         }
 
@@ -104,4 +122,54 @@ pub fn move_to_SAN(board: &Board, m: ChessMove) -> String {
     }
 
     san
-}
\ No newline at end of file
+}
+
+//Halfmove clock field of a FEN, 0 if it is left off
+pub fn halfmove_clock(fen: &str) -> u32 {
+    fen.split_whitespace().nth(4).and_then(|field| field.parse().ok()).unwrap_or(0)
+}
+
+//The board's FEN with the given halfmove clock instead of the 0 the chess crate always writes
+pub fn fen_with_halfmove_clock(board: &Board, halfmoves: u32) -> String {
+    let fen = board.to_string();
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+
+    format!("{} {} {}", fields[..4].join(" "), halfmoves, fields.get(5).unwrap_or(&"1"))
+}
+
+//Returns (year, month, day) for the current UTC date
+pub fn current_date() -> (i64, u32, u32) {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+
+    //Days since epoch to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = secs.div_euclid(86400) + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+pub fn pgn_date() -> String {
+    let (year, month, day) = current_date();
+
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+pub fn timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+//Written next to the file and renamed over it, so that readers and crashes never leave half a file
+pub fn write_atomically(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path)
+}
@@ -0,0 +1,254 @@
+use std::cell::Cell;
+use std::str::FromStr;
+
+use chess::{
+    get_bishop_moves, get_king_moves, get_knight_moves, get_pawn_attacks, get_pawn_quiets, get_rook_moves, BitBoard, Board,
+    BoardBuilder, ChessMove, Color, MoveGen, Piece, Rank, Square, EMPTY,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::ruleset::{CrazyhouseRules, FogOfWarRules, RoyalLosingChessRules, Ruleset, StandardRules};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Variant {
+    #[default]
+    Standard,
+    //Antichess with royal kings, as the chess crate needs both kings on the board. Kings can't be captured or left
+    //in check, and the first side down to a bare king wins
+    #[serde(alias = "antichess")]
+    RoyalLosingChess,
+    Crazyhouse,
+    //Players only see the squares their pieces can move to
+    FogOfWar,
+}
+
+pub const ALL_VARIANTS: [Variant; 4] = [Variant::Standard, Variant::RoyalLosingChess, Variant::Crazyhouse, Variant::FogOfWar];
+
+//Pieces that can be held in a crazyhouse pocket, in display order
+pub const POCKET_PIECES: [Piece; 5] = [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+
+//Extra state some variants need besides the board
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VariantState {
+    //Crazyhouse pieces in hand, indexed by color and then piece
+    pub pockets: [[u8; 5]; 2],
+    //Crazyhouse pieces that were promoted and turn back into pawns when captured
+    pub promoted: BitBoard,
+    //Fog of war side that took the other's king. The chess crate can't take a king off the board, so the game ends
+    //with the board as it was before
+    pub king_taken_by: Option<Color>,
+}
+
+impl VariantState {
+    pub fn pocket_count(&self, color: Color, piece: Piece) -> u8 {
+        self.pockets[color.to_index()][piece.to_index()]
+    }
+}
+
+thread_local! {
+    //Variant and state of the game the engine on this thread is playing, used by `available_moves`
+    static CURRENT: Cell<(Variant, VariantState)> = Cell::new((Variant::Standard, VariantState::default()));
+}
+
+pub fn set_current(variant: Variant, state: VariantState) {
+    CURRENT.with(|c| c.set((variant, state)));
+}
+
+pub fn current() -> (Variant, VariantState) {
+    CURRENT.with(|c| c.get())
+}
+
+//Drops are stored as a move from and to the same square, with the dropped piece as the promotion
+pub fn drop_move(piece: Piece, square: Square) -> ChessMove {
+    ChessMove::new(square, square, Some(piece))
+}
+
+pub fn as_drop(m: ChessMove) -> Option<(Piece, Square)> {
+    if m.get_source() == m.get_dest() {
+        m.get_promotion().map(|piece| (piece, m.get_dest()))
+    } else {
+        None
+    }
+}
+
+//UCI notation, with drops written like N@f3
+pub fn move_to_uci(m: ChessMove) -> String {
+    match as_drop(m) {
+        Some((piece, square)) => format!("{}@{}", piece.to_string(Color::White), square),
+        None => m.to_string(),
+    }
+}
+
+pub fn parse_uci(text: &str) -> Option<ChessMove> {
+    match text.split_once('@') {
+        Some((piece, square)) => Some(drop_move(parse_piece(piece)?, Square::from_str(square).ok()?)),
+        None => ChessMove::from_str(text).ok(),
+    }
+}
+
+//Accepts both P@e4 and @e4 for pawns
+pub fn parse_piece(text: &str) -> Option<Piece> {
+    match text {
+        "" | "P" | "p" => Some(Piece::Pawn),
+        "N" | "n" => Some(Piece::Knight),
+        "B" | "b" => Some(Piece::Bishop),
+        "R" | "r" => Some(Piece::Rook),
+        "Q" | "q" => Some(Piece::Queen),
+        _ => None,
+    }
+}
+
+//Board after dropping a piece for the side to move, if the drop doesn't leave their king in check
+pub fn drop_board(board: &Board, piece: Piece, square: Square) -> Option<Board> {
+    let color = board.side_to_move();
+
+    if board.piece_on(square).is_some() {
+        return None;
+    }
+
+    if piece == Piece::Pawn && (square.get_rank() == Rank::First || square.get_rank() == Rank::Eighth) {
+        return None;
+    }
+
+    let mut builder = BoardBuilder::from(board);
+    builder.piece(square, piece, color).side_to_move(!color).en_passant(None);
+
+    Board::try_from(builder).ok()
+}
+
+pub fn is_castle(board: &Board, m: ChessMove) -> bool {
+    board.piece_on(m.get_source()) == Some(Piece::King)
+        && (m.get_source().get_file().to_index() as i32 - m.get_dest().get_file().to_index() as i32).abs() == 2
+}
+
+//Square of the piece a move captures, if any
+pub fn captured_square(board: &Board, m: ChessMove) -> Option<Square> {
+    if board.piece_on(m.get_dest()).is_some() {
+        Some(m.get_dest())
+    } else if board.piece_on(m.get_source()) == Some(Piece::Pawn)
+        && board.en_passant() == Some(m.get_dest().ubackward(board.side_to_move()))
+    {
+        board.en_passant()
+    } else {
+        None
+    }
+}
+
+pub fn is_capture(board: &Board, m: ChessMove) -> bool {
+    captured_square(board, m).is_some()
+}
+
+//Every move of the side to move that follows how its pieces move, including ones that leave its king attacked. Castling
+//still can't be done out of or through check
+pub fn for_each_pseudo_legal_move(board: &Board, f: &mut dyn FnMut(ChessMove)) {
+    let color = board.side_to_move();
+    let own = *board.color_combined(color);
+    let their = *board.color_combined(!color);
+    let blockers = *board.combined();
+    let en_passant = board.en_passant().map_or(EMPTY, |square| BitBoard::from_square(square.uforward(color)));
+
+    for source in own {
+        let piece = board.piece_on(source).unwrap();
+        let moves = match piece {
+            Piece::Pawn => get_pawn_quiets(source, color, blockers) | get_pawn_attacks(source, color, their | en_passant),
+            Piece::Knight => get_knight_moves(source),
+            Piece::Bishop => get_bishop_moves(source, blockers),
+            Piece::Rook => get_rook_moves(source, blockers),
+            Piece::Queen => get_bishop_moves(source, blockers) | get_rook_moves(source, blockers),
+            Piece::King => get_king_moves(source),
+        };
+
+        for dest in moves & !own {
+            if piece == Piece::Pawn && dest.get_rank() == color.to_their_backrank() {
+                for promotion in [Piece::Queen, Piece::Knight, Piece::Rook, Piece::Bishop] {
+                    f(ChessMove::new(source, dest, Some(promotion)));
+                }
+            } else {
+                f(ChessMove::new(source, dest, None));
+            }
+        }
+    }
+
+    for m in MoveGen::new_legal(board).filter(|m| is_castle(board, *m)) {
+        f(m);
+    }
+}
+
+//Squares `color` can see in fog of war: its own pieces and every square they could move to
+pub fn visible_squares(board: &Board, color: Color) -> BitBoard {
+    let own = *board.color_combined(color);
+    let their = *board.color_combined(!color);
+    let blockers = *board.combined();
+
+    let mut visible = own;
+
+    for square in own {
+        let moves = match board.piece_on(square).unwrap() {
+            Piece::Pawn => get_pawn_quiets(square, color, blockers) | get_pawn_attacks(square, color, their),
+            Piece::Knight => get_knight_moves(square),
+            Piece::Bishop => get_bishop_moves(square, blockers),
+            Piece::Rook => get_rook_moves(square, blockers),
+            Piece::Queen => get_bishop_moves(square, blockers) | get_rook_moves(square, blockers),
+            Piece::King => get_king_moves(square),
+        };
+
+        visible |= moves & !own;
+    }
+
+    //A pawn that can be taken en passant is seen along with the square behind it
+    if let Some(square) = board.en_passant().filter(|_| board.side_to_move() == color) {
+        let dest = square.uforward(color);
+
+        if get_pawn_attacks(dest, !color, own & *board.pieces(Piece::Pawn)) != EMPTY {
+            visible |= BitBoard::from_square(dest) | BitBoard::from_square(square);
+        }
+    }
+
+    visible
+}
+
+//What `color` knows about the board in fog of war. Hidden pieces are removed, except for the king which the chess
+//crate can't do without. The crate won't make a board where the side to move could take the king either, so there
+//nothing is hidden. Taking the king wins straight away anyway
+pub fn fog_board(board: &Board, color: Color) -> Board {
+    let hidden = !visible_squares(board, color) & *board.color_combined(!color) & !*board.pieces(Piece::King);
+
+    let mut builder = BoardBuilder::from(board);
+
+    for square in hidden {
+        builder.clear_square(square);
+    }
+
+    Board::try_from(builder).unwrap_or(*board)
+}
+
+impl Variant {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Standard => "Standard",
+            Variant::RoyalLosingChess => "Royal Losing Chess",
+            Variant::Crazyhouse => "Crazyhouse",
+            Variant::FogOfWar => "Fog of War",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Variant> {
+        ALL_VARIANTS.iter().find(|v| v.name().eq_ignore_ascii_case(name)).copied()
+    }
+
+    pub fn next(&self) -> Variant {
+        let idx = ALL_VARIANTS.iter().position(|v| v == self).unwrap();
+
+        ALL_VARIANTS[(idx + 1) % ALL_VARIANTS.len()]
+    }
+
+    pub fn rules(&self) -> &'static dyn Ruleset {
+        match self {
+            Variant::Standard => &StandardRules,
+            Variant::RoyalLosingChess => &RoyalLosingChessRules,
+            Variant::Crazyhouse => &CrazyhouseRules,
+            Variant::FogOfWar => &FogOfWarRules,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use chess::{Board, ChessMove};
+
+use crate::arena::random_opening;
+use crate::cli;
+use crate::gui::chess_display::GameOutcome;
+use crate::tournament::{GameResult, Pairing, TournamentConfig};
+
+//How often the watchdog looks at the games being played
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+//Without a move limit bots can think for as long as they like, so only a very long wait counts as a stall
+const DEFAULT_STALL_LIMIT: Duration = Duration::from_secs(10 * 60);
+//With one, a bot gets this many times as long. UCI engines that don't answer are given up on well before that, and
+//forfeit
+const STALL_LIMIT_MOVES: u32 = 20;
+const MIN_STALL_LIMIT: Duration = Duration::from_secs(30);
+
+//Times a game is played again from its last move after something went wrong, before the bot to move forfeits
+const MAX_REPLAYS: usize = 1;
+
+//Longest a game can go without a move before the watchdog steps in
+pub fn stall_limit(config: &TournamentConfig) -> Duration {
+    config.stall_limit.unwrap_or_else(|| {
+        config
+            .move_time
+            .or(cli::args().max_move_time)
+            .map_or(DEFAULT_STALL_LIMIT, |move_time| (move_time * STALL_LIMIT_MOVES).max(MIN_STALL_LIMIT))
+    })
+}
+
+//A game of the schedule and the moves it starts from. These are random opening moves for a new game, and the moves
+//played before something went wrong for a game played again
+#[derive(Debug, Clone)]
+pub struct Resume {
+    pub pairing: Pairing,
+    pub moves: Vec<ChessMove>,
+    pub random_plies: usize,
+    pub replays: usize,
+}
+
+impl Resume {
+    pub fn new(pairing: Pairing, random_plies: usize) -> Resume {
+        let moves = random_opening(random_plies);
+
+        Resume { pairing, random_plies: moves.len(), moves, replays: 0 }
+    }
+
+    pub fn can_replay(&self) -> bool {
+        self.replays < MAX_REPLAYS
+    }
+
+    //The same game from `moves` on with new bots. Bots that lost their engine get a new one
+    pub fn replay(&self, moves: Vec<ChessMove>, why: &str) -> Resume {
+        println!(
+            "Game {}: {} vs {}: {}. Playing it again from ply {}",
+            self.pairing.index + 1,
+            self.pairing.white,
+            self.pairing.black,
+            why,
+            moves.len()
+        );
+
+        Resume {
+            pairing: self.pairing.clone(),
+            moves,
+            random_plies: self.random_plies,
+            replays: self.replays + 1,
+        }
+    }
+
+    //Gives the game up after `moves`, with the bot to move losing for `why`
+    pub fn forfeit(&self, moves: Vec<ChessMove>, why: &str) -> GameResult {
+        let board = moves.iter().fold(Board::default(), |board, m| board.make_move_new(*m));
+        let outcome = GameOutcome::Forfeit(!board.side_to_move());
+
+        println!(
+            "Game {}: {} vs {}: {}. {} after {} plies",
+            self.pairing.index + 1,
+            self.pairing.white,
+            self.pairing.black,
+            why,
+            outcome.get_text(),
+            moves.len()
+        );
+
+        GameResult {
+            pairing: self.pairing.clone(),
+            result: outcome.pgn_result().to_string(),
+            reason: outcome.get_text().to_string(),
+            terms: vec![None; moves.len()],
+            moves,
+            reproducible: false,
+            copy_of: None,
+            random_plies: self.random_plies,
+        }
+    }
+}